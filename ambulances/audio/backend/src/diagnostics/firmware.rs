@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Sound cards the kernel found, and codec firmware it failed to load
+//!
+//! Modern laptops (Intel SOF, AMD ACP, Cirrus/TI smart amplifiers) need DSP
+//! firmware and topology files; without them the card never appears or
+//! only the null output is left. Failures are read from this boot's
+//! kernel messages in the journal.
+
+use crate::report::{mark, print_notes};
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::journal::{self, Journal};
+
+/// Drivers whose firmware messages concern audio
+const AUDIO_DRIVERS: &[&str] = &[
+    "sof", "snd_", "snd-", "hda", "avs", "cs35l", "cs42l", "tas2", "audio",
+];
+
+/// Ways a failed firmware load is reported
+const FAILURES: &[&str] = &[
+    "failed",
+    "not found",
+    "missing",
+    "error -2",
+    "Direct firmware load",
+];
+
+const MAX_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirmwareDiagnostics {
+    /// Lines of `/proc/asound/cards` naming each card
+    pub cards: Vec<String>,
+    pub journal_available: bool,
+    pub firmware_errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Card titles from `/proc/asound/cards`, e.g. `0 [PCH]: HDA-Intel - HDA Intel PCH`
+pub fn cards() -> Vec<String> {
+    std::fs::read_to_string(host::path("/proc/asound/cards"))
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| {
+            l.trim_start()
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_digit())
+        })
+        .map(|l| l.trim().to_string())
+        .collect()
+}
+
+pub fn is_firmware_failure(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    (lower.contains("firmware") || lower.contains("topology"))
+        && AUDIO_DRIVERS.iter().any(|d| lower.contains(d))
+        && FAILURES.iter().any(|f| lower.contains(&f.to_lowercase()))
+}
+
+/// This boot's kernel messages, oldest first
+fn kernel_messages() -> std::io::Result<Vec<String>> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.match_this_boot()?;
+    journal.seek_head()?;
+    let mut messages = Vec::new();
+    while journal.next_entry()? {
+        if let Some(message) = journal.field("MESSAGE") {
+            messages.push(message);
+        }
+    }
+    Ok(messages)
+}
+
+pub fn diagnose() -> FirmwareDiagnostics {
+    let mut diag = FirmwareDiagnostics {
+        cards: cards(),
+        ..FirmwareDiagnostics::default()
+    };
+
+    // A journal the user may not read opens fine and is empty
+    match kernel_messages() {
+        Ok(messages) if !messages.is_empty() => {
+            diag.journal_available = true;
+            diag.firmware_errors = messages
+                .into_iter()
+                .filter(|m| is_firmware_failure(m))
+                .take(MAX_SAMPLES)
+                .collect();
+        }
+        _ => diag.recommendations.push(
+            "Join the systemd-journal group to scan the kernel log for firmware errors".to_string(),
+        ),
+    }
+
+    if diag.cards.is_empty() {
+        diag.warnings
+            .push("The kernel found no sound card".to_string());
+    }
+    for line in &diag.firmware_errors {
+        diag.warnings.push(format!("Firmware: {}", line));
+    }
+    if !diag.firmware_errors.is_empty() || diag.cards.is_empty() {
+        diag.recommendations.push(
+            "Install the audio firmware packages (sof-firmware or firmware-sof-signed, linux-firmware, alsa-firmware) and reboot".to_string(),
+        );
+    }
+    diag
+}
+
+impl FirmwareDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Firmware Diagnostics ===");
+        println!(
+            "{} Sound cards: {}",
+            mark(!self.cards.is_empty()),
+            self.cards.len()
+        );
+        if verbose {
+            for card in &self.cards {
+                println!("    {}", card);
+            }
+        }
+        if self.journal_available {
+            println!(
+                "{} Firmware load errors: {}",
+                mark(self.firmware_errors.is_empty()),
+                self.firmware_errors.len()
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
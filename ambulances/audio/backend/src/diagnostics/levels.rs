@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Muted and zero-volume channels
+//!
+//! Only the default sink and source matter for "I hear nothing"; a single
+//! channel at 0% (balance dragged to one side) is as silent on that speaker
+//! as a mute.
+
+use crate::pulse::{self, Device};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Level {
+    pub device: String,
+    /// `sink` or `source`
+    pub kind: &'static str,
+    pub muted: bool,
+    pub volume_percent: Vec<u32>,
+}
+
+impl Level {
+    pub fn silent(&self) -> bool {
+        self.muted || self.volume_percent.contains(&0)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LevelDiagnostics {
+    pub levels: Vec<Level>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn level(kind: &'static str, default: Option<&str>, devices: &[Device]) -> Option<Level> {
+    let device = devices.iter().find(|d| Some(d.name.as_str()) == default)?;
+    Some(Level {
+        device: device.name.clone(),
+        kind,
+        muted: device.muted,
+        volume_percent: device.volume_percent.clone(),
+    })
+}
+
+/// Levels of the current default sink and source
+pub fn defaults() -> Vec<Level> {
+    let Some(info) = pulse::info() else {
+        return Vec::new();
+    };
+    let sinks = pulse::devices("sinks").unwrap_or_default();
+    let sources = pulse::devices("sources").unwrap_or_default();
+    [
+        level("sink", info.default_sink.as_deref(), &sinks),
+        level("source", info.default_source.as_deref(), &sources),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+pub fn diagnose() -> LevelDiagnostics {
+    let mut diag = LevelDiagnostics {
+        levels: defaults(),
+        ..LevelDiagnostics::default()
+    };
+    for l in &diag.levels {
+        let what = if l.kind == "sink" { "output" } else { "input" };
+        if l.muted {
+            diag.warnings
+                .push(format!("Default {} {} is muted", what, l.device));
+        } else if l.silent() {
+            diag.warnings.push(format!(
+                "Default {} {} has a channel at 0% ({})",
+                what,
+                l.device,
+                percentages(&l.volume_percent)
+            ));
+        }
+    }
+    if diag.levels.iter().any(Level::silent) {
+        diag.recommendations
+            .push("Unmute and restore volume: audio-ambulance repair defaults".to_string());
+    }
+    diag
+}
+
+fn percentages(volumes: &[u32]) -> String {
+    volumes
+        .iter()
+        .map(|v| format!("{}%", v))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+impl LevelDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Level Diagnostics ===");
+        for l in &self.levels {
+            println!(
+                "{} {} {}: {}{}",
+                mark(!l.silent()),
+                l.kind,
+                l.device,
+                percentages(&l.volume_percent),
+                if l.muted { " (muted)" } else { "" }
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Audio diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget, so a sound server slow to answer holds up only the
+//! sections that ask it.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod firmware;
+pub mod levels;
+pub mod routing;
+pub mod sample_rates;
+pub mod stack;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; a sound server that stopped answering can
+/// hang pactl
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Stack(stack::StackDiagnostics),
+    Routing(routing::RoutingDiagnostics),
+    Levels(levels::LevelDiagnostics),
+    SampleRates(sample_rates::SampleRateDiagnostics),
+    Firmware(firmware::FirmwareDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("stack", |_| {
+            Ok(Section::Stack(stack::diagnose()))
+        }))
+        .job(Job::blocking("routing", |_| {
+            Ok(Section::Routing(routing::diagnose()))
+        }))
+        .job(Job::blocking("levels", |_| {
+            Ok(Section::Levels(levels::diagnose()))
+        }))
+        .job(Job::blocking("sample_rates", |_| {
+            Ok(Section::SampleRates(sample_rates::diagnose()))
+        }))
+        .job(Job::blocking("firmware", |_| {
+            Ok(Section::Firmware(firmware::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        stack: Default::default(),
+        routing: Default::default(),
+        levels: Default::default(),
+        sample_rates: Default::default(),
+        firmware: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Stack(d)) => result.stack = d,
+            Some(Section::Routing(d)) => result.routing = d,
+            Some(Section::Levels(d)) => result.levels = d,
+            Some(Section::SampleRates(d)) => result.sample_rates = d,
+            Some(Section::Firmware(d)) => result.firmware = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "stack" => &mut result.stack.warnings,
+                    "routing" => &mut result.routing.warnings,
+                    "levels" => &mut result.levels.warnings,
+                    "sample_rates" => &mut result.sample_rates.warnings,
+                    _ => &mut result.firmware.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Where sound goes by default
+//!
+//! Flags a missing or stale default sink/source, defaults that point at the
+//! null output, a monitor used as the microphone, and defaults whose active
+//! port is unplugged while another device is available.
+
+use crate::pulse::{self, Device};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceSummary {
+    pub name: String,
+    pub description: String,
+    pub state: String,
+    pub active_port: Option<String>,
+    pub port_unavailable: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RoutingDiagnostics {
+    pub default_sink: Option<String>,
+    pub default_source: Option<String>,
+    /// Why the default output is wrong, if it is
+    pub sink_problem: Option<String>,
+    pub source_problem: Option<String>,
+    pub sinks: Vec<DeviceSummary>,
+    pub sources: Vec<DeviceSummary>,
+    /// Why the device lists could not be read
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl From<&Device> for DeviceSummary {
+    fn from(d: &Device) -> DeviceSummary {
+        DeviceSummary {
+            name: d.name.clone(),
+            description: d.description.clone(),
+            state: d.state.clone(),
+            active_port: d.active_port.clone(),
+            port_unavailable: d.port_unavailable,
+        }
+    }
+}
+
+/// A device that can actually play or record right now
+pub fn usable(device: &Device) -> bool {
+    !device.is_placeholder() && !device.is_monitor() && !device.port_unavailable
+}
+
+/// Why `default` is a bad default, given the devices of its kind
+pub fn problem(default: Option<&str>, devices: &[Device]) -> Option<String> {
+    let Some(name) = default.filter(|n| !n.is_empty()) else {
+        return Some("none is set".to_string());
+    };
+    let Some(device) = devices.iter().find(|d| d.name == name) else {
+        return Some(format!("{} no longer exists", name));
+    };
+    let alternative = devices.iter().any(usable);
+    if device.is_placeholder() {
+        Some(format!("{} is a placeholder that discards audio", name))
+    } else if device.is_monitor() {
+        Some(format!("{} is a monitor of an output, not an input", name))
+    } else if device.port_unavailable && alternative {
+        Some(format!(
+            "{} is on port {} which is not plugged in",
+            device.description,
+            device.active_port.as_deref().unwrap_or("?")
+        ))
+    } else {
+        None
+    }
+}
+
+pub fn diagnose() -> RoutingDiagnostics {
+    let mut diag = RoutingDiagnostics::default();
+    let Some(info) = pulse::info() else {
+        diag.error = Some("no sound server answers pactl".to_string());
+        return diag;
+    };
+    diag.default_sink = info.default_sink.clone();
+    diag.default_source = info.default_source.clone();
+
+    let (sinks, sources) = match (pulse::devices("sinks"), pulse::devices("sources")) {
+        (Ok(sinks), Ok(sources)) => (sinks, sources),
+        (Err(e), _) | (_, Err(e)) => {
+            diag.error = Some(e);
+            return diag;
+        }
+    };
+    diag.sinks = sinks.iter().map(DeviceSummary::from).collect();
+    diag.sources = sources.iter().map(DeviceSummary::from).collect();
+
+    if !sinks.iter().any(usable) {
+        diag.warnings
+            .push("No usable output device; only placeholders or unplugged ports".to_string());
+        diag.recommendations.push(
+            "Check the firmware section and that the card's profile is not `off` (pavucontrol → Configuration)".to_string(),
+        );
+    } else {
+        diag.sink_problem = problem(info.default_sink.as_deref(), &sinks);
+    }
+    // A monitor may be the only source on a machine without a microphone
+    if sources.iter().any(usable) {
+        diag.source_problem = problem(info.default_source.as_deref(), &sources);
+    }
+    if let Some(p) = &diag.sink_problem {
+        diag.warnings.push(format!("Default output: {}", p));
+    }
+    if let Some(p) = &diag.source_problem {
+        diag.warnings.push(format!("Default input: {}", p));
+    }
+    if diag.sink_problem.is_some() || diag.source_problem.is_some() {
+        diag.recommendations
+            .push("Reset the defaults: audio-ambulance repair defaults".to_string());
+    }
+
+    diag
+}
+
+impl RoutingDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Routing Diagnostics ===");
+        if let Some(e) = &self.error {
+            println!("✗ {}", e);
+            print_notes(&self.warnings, &self.recommendations);
+            return;
+        }
+        for (label, default, problem, devices) in [
+            (
+                "output",
+                &self.default_sink,
+                &self.sink_problem,
+                &self.sinks,
+            ),
+            (
+                "input",
+                &self.default_source,
+                &self.source_problem,
+                &self.sources,
+            ),
+        ] {
+            let name = default.as_deref().unwrap_or("none");
+            let description = devices
+                .iter()
+                .find(|d| d.name == name)
+                .map_or(name, |d| d.description.as_str());
+            println!(
+                "{} Default {}: {}",
+                mark(problem.is_none()),
+                label,
+                description
+            );
+            if verbose {
+                for d in devices.iter() {
+                    println!(
+                        "    {} [{}]{}",
+                        d.name,
+                        d.state,
+                        if d.port_unavailable {
+                            " (unplugged)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Sample-rate mismatches between the server, its devices and the hardware
+//!
+//! A mismatch is not an outage: the server resamples. It does cost CPU,
+//! and an input and output at different rates is a common source of
+//! crackle and drift in calls. Hardware rates come from the `hw_params`
+//! of every open ALSA substream under `/proc/asound`.
+
+use crate::pulse;
+use crate::report::{mark, print_notes};
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HardwareStream {
+    /// e.g. `card0/pcm0p/sub0`
+    pub substream: String,
+    pub rate: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SampleRateDiagnostics {
+    pub server_rate: Option<u32>,
+    pub sink_rate: Option<u32>,
+    pub source_rate: Option<u32>,
+    pub hardware: Vec<HardwareStream>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn read_dir_names(dir: &Path, prefix: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|n| n.starts_with(prefix))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Open ALSA substreams and the rate each runs at
+pub fn hardware_streams() -> Vec<HardwareStream> {
+    let root = host::path("/proc/asound");
+    let mut streams = Vec::new();
+    for card in read_dir_names(&root, "card") {
+        for pcm in read_dir_names(&root.join(&card), "pcm") {
+            for sub in read_dir_names(&root.join(&card).join(&pcm), "sub") {
+                let path = root.join(&card).join(&pcm).join(&sub).join("hw_params");
+                // Closed substreams read as the single word `closed`
+                let Ok(params) = std::fs::read_to_string(path) else {
+                    continue;
+                };
+                let rate = params.lines().find_map(|l| {
+                    l.strip_prefix("rate:")?
+                        .split_whitespace()
+                        .next()?
+                        .parse()
+                        .ok()
+                });
+                if let Some(rate) = rate {
+                    streams.push(HardwareStream {
+                        substream: format!("{}/{}/{}", card, pcm, sub),
+                        rate,
+                    });
+                }
+            }
+        }
+    }
+    streams
+}
+
+pub fn diagnose() -> SampleRateDiagnostics {
+    let mut diag = SampleRateDiagnostics {
+        hardware: hardware_streams(),
+        ..SampleRateDiagnostics::default()
+    };
+    let Some(info) = pulse::info() else {
+        return diag;
+    };
+    diag.server_rate = info
+        .default_sample_spec
+        .as_deref()
+        .and_then(pulse::spec_rate);
+    let rate_of = |kind: &str, name: Option<&str>| {
+        pulse::devices(kind)
+            .ok()?
+            .into_iter()
+            .find(|d| Some(d.name.as_str()) == name)?
+            .rate()
+    };
+    diag.sink_rate = rate_of("sinks", info.default_sink.as_deref());
+    diag.source_rate = rate_of("sources", info.default_source.as_deref());
+
+    if let (Some(sink), Some(source)) = (diag.sink_rate, diag.source_rate) {
+        if sink != source {
+            diag.warnings.push(format!(
+                "Default output runs at {} Hz but default input at {} Hz; calls may crackle or drift",
+                sink, source
+            ));
+        }
+    }
+    if let Some(server) = diag.server_rate {
+        for stream in diag.hardware.iter().filter(|s| s.rate != server) {
+            diag.warnings.push(format!(
+                "{} runs at {} Hz while the server mixes at {} Hz; audio is resampled",
+                stream.substream, stream.rate, server
+            ));
+        }
+    }
+    if !diag.warnings.is_empty() {
+        diag.recommendations.push(if info.server_name.contains("PipeWire") {
+            "Allow the device's native rates: set default.clock.allowed-rates = [ 44100 48000 ] in ~/.config/pipewire/pipewire.conf.d/".to_string()
+        } else {
+            "Set default-sample-rate and alternate-sample-rate in ~/.config/pulse/daemon.conf".to_string()
+        });
+    }
+    diag
+}
+
+fn hz(rate: Option<u32>) -> String {
+    rate.map_or_else(|| "unknown".to_string(), |r| format!("{} Hz", r))
+}
+
+impl SampleRateDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Sample Rate Diagnostics ===");
+        println!("Server: {}", hz(self.server_rate));
+        println!(
+            "{} Output {} / input {}",
+            mark(self.sink_rate == self.source_rate || self.source_rate.is_none()),
+            hz(self.sink_rate),
+            hz(self.source_rate)
+        );
+        if verbose {
+            for stream in &self.hardware {
+                println!("    {}: {} Hz", stream.substream, stream.rate);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Is the user's audio stack running?
+//!
+//! PipeWire needs three daemons: `pipewire` itself, `pipewire-pulse` for
+//! PulseAudio clients and a session manager (`wireplumber`) to route
+//! streams. A PulseAudio system needs only `pulseaudio`. Processes are
+//! looked up for the current user, and the matching systemd user units are
+//! asked for their state on the session bus.
+
+use crate::pulse;
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::os::unix::fs::MetadataExt;
+use systemd_shim::bus::{self, Bus};
+
+const PIPEWIRE_DAEMONS: &[&str] = &["pipewire", "pipewire-pulse", "wireplumber"];
+const PULSEAUDIO_DAEMONS: &[&str] = &["pulseaudio"];
+
+/// Session managers PipeWire can run under instead of WirePlumber
+const SESSION_MANAGERS: &[&str] = &["wireplumber", "pipewire-media-session"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Daemon {
+    pub name: String,
+    pub running: bool,
+    /// `ActiveState` of the user unit of the same name, when it is loaded
+    pub unit_state: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StackDiagnostics {
+    /// `pipewire`, `pulseaudio` or `none`
+    pub stack: String,
+    /// As `pactl info` reports it; `None` when no server answers
+    pub server_name: Option<String>,
+    pub daemons: Vec<Daemon>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Names (`comm`) of the current user's processes
+fn user_processes() -> Vec<String> {
+    let uid = system::uid();
+    let Ok(entries) = std::fs::read_dir(host::path("/proc")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .filter(|e| e.metadata().is_ok_and(|m| m.uid() == uid))
+        .filter_map(|e| std::fs::read_to_string(e.path().join("comm")).ok())
+        .map(|comm| comm.trim().to_string())
+        .collect()
+}
+
+/// `LoadState` and `ActiveState` of the user unit `name`
+pub fn unit_states(bus: &Bus, name: &str) -> Option<(String, String)> {
+    let path = bus::unit_path(name).ok()?;
+    let state = |property| {
+        bus.get_property_string(bus::SYSTEMD, &path, bus::UNIT, property)
+            .ok()
+    };
+    Some((state("LoadState")?, state("ActiveState")?))
+}
+
+fn unit_state(bus: Option<&Bus>, name: &str) -> Option<String> {
+    let (load, active) = unit_states(bus?, &format!("{}.service", name))?;
+    (load == "loaded").then_some(active)
+}
+
+pub fn diagnose() -> StackDiagnostics {
+    let processes = user_processes();
+    let running = |name: &str| processes.iter().any(|p| p == name);
+    let server = pulse::info();
+    let user_bus = Bus::user().ok();
+
+    let stack = if system::has("pipewire") || running("pipewire") {
+        "pipewire"
+    } else if system::has("pulseaudio") || running("pulseaudio") {
+        "pulseaudio"
+    } else {
+        "none"
+    };
+    let expected = match stack {
+        "pipewire" => PIPEWIRE_DAEMONS,
+        "pulseaudio" => PULSEAUDIO_DAEMONS,
+        _ => &[],
+    };
+    let session_manager = SESSION_MANAGERS.iter().copied().find(|m| running(m));
+
+    let mut diag = StackDiagnostics {
+        stack: stack.to_string(),
+        server_name: server.map(|s| s.server_name),
+        daemons: expected
+            .iter()
+            .map(|name| Daemon {
+                name: name.to_string(),
+                running: running(name) || (*name == "wireplumber" && session_manager.is_some()),
+                unit_state: unit_state(user_bus.as_ref(), name),
+            })
+            .collect(),
+        ..StackDiagnostics::default()
+    };
+
+    if ambulance_privilege::is_elevated() {
+        diag.warnings
+            .push("Running as root; the audio stack belongs to the desktop user".to_string());
+        diag.recommendations
+            .push("Run audio-ambulance as the logged-in user, without sudo".to_string());
+        return diag;
+    }
+
+    if stack == "none" {
+        diag.warnings
+            .push("Neither PipeWire nor PulseAudio is installed".to_string());
+        diag.recommendations.push(
+            "Install PipeWire (pipewire, pipewire-pulse, wireplumber) for desktop audio"
+                .to_string(),
+        );
+        return diag;
+    }
+
+    for daemon in &diag.daemons {
+        if daemon.unit_state.as_deref() == Some("failed") {
+            diag.warnings
+                .push(format!("{} user unit has failed", daemon.name));
+        } else if !daemon.running {
+            diag.warnings
+                .push(format!("{} is not running", daemon.name));
+        }
+    }
+    if stack == "pipewire" && running("pulseaudio") {
+        diag.warnings.push(
+            "PulseAudio is running alongside PipeWire and competes for the devices".to_string(),
+        );
+        diag.recommendations.push(
+            "Mask PulseAudio: systemctl --user mask pulseaudio.service pulseaudio.socket"
+                .to_string(),
+        );
+    }
+    if diag.server_name.is_none() {
+        diag.warnings
+            .push("No sound server answers pactl".to_string());
+    }
+    if diag.daemons.iter().any(|d| !d.running) || diag.server_name.is_none() {
+        diag.recommendations
+            .push("Restart the audio stack: audio-ambulance repair restart".to_string());
+    }
+
+    diag
+}
+
+impl StackDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Audio Stack Diagnostics ===");
+        println!("Stack: {}", self.stack);
+        match &self.server_name {
+            Some(name) => println!("✓ Server: {}", name),
+            None => println!("✗ Server: not reachable"),
+        }
+        for daemon in &self.daemons {
+            match &daemon.unit_state {
+                Some(state) if verbose => {
+                    println!("{} {} (unit {})", mark(daemon.running), daemon.name, state)
+                }
+                _ => println!("{} {}", mark(daemon.running), daemon.name),
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Audio Ambulance backend
+//!
+//! Diagnoses the PipeWire/PulseAudio/ALSA stack: dead daemons, wrong
+//! default devices, muted or silent channels, sample-rate mismatches and
+//! missing codec firmware. Repairs restart the user audio stack and reset
+//! the defaults. `--json` output follows the network ambulance's report
+//! model.
+
+mod diagnostics;
+mod pulse;
+mod repairs;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Category, Error};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Audio Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: audio-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all audio diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Audio Ambulance");
+    println!("===============\n");
+    result.stack.print(verbose);
+    result.routing.print(verbose);
+    result.levels.print(verbose);
+    result.sample_rates.print(verbose);
+    result.firmware.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Stack", &result.stack.warnings),
+        ("Routing", &result.routing.warnings),
+        ("Levels", &result.levels.warnings),
+        ("Sample rates", &result.sample_rates.warnings),
+        ("Firmware", &result.firmware.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    exit::findings(result.has_warnings())
+}
+
+fn run_repair(target: &str, json: bool) -> ExitCode {
+    // The audio stack runs per user; as root we would restart the wrong one
+    if ambulance_privilege::is_elevated() {
+        let e = Error::new(
+            "privilege.unwanted",
+            Category::Privilege,
+            "Audio repairs act on your own session",
+        )
+        .with_hint(format!(
+            "Please run without sudo: audio-ambulance repair {}",
+            target
+        ));
+        return fail(e, json);
+    }
+
+    let result = match repairs::run(target) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [
+        ("restart", "Audio Stack Restart", &result.restart_repair),
+        ("defaults", "Default Device Reset", &result.defaults_repair),
+    ];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Audio Ambulance - Repair Mode");
+        println!("=============================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, json),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Audio Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
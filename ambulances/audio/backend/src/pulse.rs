@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Sound server state through `pactl`
+//!
+//! `pactl` talks to PulseAudio and to PipeWire's pulse server alike, so one
+//! reader covers both stacks. Device lists use `pactl -f json` (pactl 16 and
+//! later); server info is parsed from the C-locale text output.
+
+use serde_json::Value;
+use std::process::Command;
+
+/// Fields of `pactl info` the diagnostics use
+#[derive(Debug, Clone, Default)]
+pub struct ServerInfo {
+    pub server_name: String,
+    pub default_sink: Option<String>,
+    pub default_source: Option<String>,
+    /// e.g. `float32le 2ch 48000Hz`
+    pub default_sample_spec: Option<String>,
+}
+
+/// One sink or source
+#[derive(Debug, Clone)]
+pub struct Device {
+    pub name: String,
+    pub description: String,
+    pub state: String,
+    pub muted: bool,
+    /// Per-channel volume in percent
+    pub volume_percent: Vec<u32>,
+    pub sample_spec: String,
+    pub active_port: Option<String>,
+    /// The active port reports `not available` (e.g. headphones unplugged)
+    pub port_unavailable: bool,
+}
+
+impl Device {
+    /// Null and dummy outputs the server falls back to when it has no hardware
+    pub fn is_placeholder(&self) -> bool {
+        self.name == "auto_null" || self.name.contains("null") || self.name.contains("dummy")
+    }
+
+    /// The loopback source every sink exposes
+    pub fn is_monitor(&self) -> bool {
+        self.name.ends_with(".monitor")
+    }
+
+    pub fn rate(&self) -> Option<u32> {
+        spec_rate(&self.sample_spec)
+    }
+}
+
+/// Run pactl in the C locale, returning stdout on success
+pub fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("failed to run pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pactl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `None` when no sound server answers
+pub fn info() -> Option<ServerInfo> {
+    let text = pactl(&["info"]).ok()?;
+    let mut info = ServerInfo::default();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "Server Name" => info.server_name = value,
+            "Default Sink" => info.default_sink = Some(value),
+            "Default Source" => info.default_source = Some(value),
+            "Default Sample Specification" => info.default_sample_spec = Some(value),
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+/// Sample rate out of a spec such as `s16le 2ch 44100Hz`
+pub fn spec_rate(spec: &str) -> Option<u32> {
+    spec.split_whitespace()
+        .find_map(|part| part.strip_suffix("Hz")?.parse().ok())
+}
+
+fn parse_device(value: &Value) -> Option<Device> {
+    let active_port = value["active_port"].as_str().map(str::to_string);
+    let port_unavailable = active_port.as_deref().is_some_and(|active| {
+        value["ports"].as_array().is_some_and(|ports| {
+            ports
+                .iter()
+                .any(|p| p["name"] == active && p["availability"] == "not available")
+        })
+    });
+    let volume_percent = value["volume"]
+        .as_object()
+        .map(|channels| {
+            channels
+                .values()
+                .filter_map(|c| {
+                    c["value_percent"]
+                        .as_str()?
+                        .trim_end_matches('%')
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Device {
+        name: value["name"].as_str()?.to_string(),
+        description: value["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        state: value["state"].as_str().unwrap_or_default().to_string(),
+        muted: value["mute"].as_bool().unwrap_or(false),
+        volume_percent,
+        sample_spec: value["sample_specification"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        active_port,
+        port_unavailable,
+    })
+}
+
+/// `kind` is `sinks` or `sources`
+pub fn devices(kind: &str) -> Result<Vec<Device>, String> {
+    let text = pactl(&["-f", "json", "list", kind])?;
+    let list: Vec<Value> = serde_json::from_str(&text)
+        .map_err(|e| format!("unreadable pactl {} list (needs pactl 16+): {}", kind, e))?;
+    Ok(list.iter().filter_map(parse_device).collect())
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Point the defaults at real devices, unmuted and audible
+//!
+//! A bad default sink or source is replaced by the first usable device,
+//! preferring one already running. The defaults are then unmuted, and a
+//! channel at 0% is raised to `RESTORE_VOLUME`; other levels are left as
+//! the user set them.
+
+use crate::diagnostics::{levels, routing};
+use crate::pulse::{self, Device};
+use crate::report::RepairOutcome;
+
+const RESTORE_VOLUME: &str = "50%";
+
+fn best(devices: &[Device]) -> Option<&Device> {
+    devices
+        .iter()
+        .filter(|d| routing::usable(d))
+        .max_by_key(|d| d.state == "RUNNING")
+}
+
+fn pactl(result: &mut RepairOutcome, args: &[&str], action: String) {
+    match pulse::pactl(args) {
+        Ok(_) => result.actions.push(action),
+        Err(e) => result.errors.push(e),
+    }
+}
+
+pub fn repair() -> RepairOutcome {
+    let Some(info) = pulse::info() else {
+        return RepairOutcome {
+            errors: vec!["No sound server answers; run `repair restart` first".to_string()],
+            ..RepairOutcome::default()
+        };
+    };
+    let mut result = RepairOutcome::default();
+
+    for (kind, label, default, command) in [
+        (
+            "sinks",
+            "output",
+            info.default_sink.as_deref(),
+            "set-default-sink",
+        ),
+        (
+            "sources",
+            "input",
+            info.default_source.as_deref(),
+            "set-default-source",
+        ),
+    ] {
+        let devices = match pulse::devices(kind) {
+            Ok(devices) => devices,
+            Err(e) => {
+                result.errors.push(e);
+                continue;
+            }
+        };
+        let Some(problem) = routing::problem(default, &devices) else {
+            continue;
+        };
+        match best(&devices) {
+            Some(device) => pactl(
+                &mut result,
+                &[command, &device.name],
+                format!(
+                    "Default {} was wrong ({}); now {}",
+                    label, problem, device.description
+                ),
+            ),
+            // Sources may legitimately have nothing but monitors
+            None if kind == "sinks" => result.errors.push(format!(
+                "No usable output device to switch to ({})",
+                problem
+            )),
+            None => {}
+        }
+    }
+
+    for level in levels::defaults() {
+        let target = if level.kind == "sink" {
+            "@DEFAULT_SINK@"
+        } else {
+            "@DEFAULT_SOURCE@"
+        };
+        if level.muted {
+            pactl(
+                &mut result,
+                &[&format!("set-{}-mute", level.kind), target, "0"],
+                format!("Unmuted {}", level.device),
+            );
+        }
+        if level.volume_percent.contains(&0) {
+            pactl(
+                &mut result,
+                &[
+                    &format!("set-{}-volume", level.kind),
+                    target,
+                    RESTORE_VOLUME,
+                ],
+                format!("Set {} to {}", level.device, RESTORE_VOLUME),
+            );
+        }
+    }
+
+    if result.actions.is_empty() && result.errors.is_empty() {
+        return RepairOutcome::not_needed(
+            "Defaults are set, unmuted and audible, no repair needed",
+        );
+    }
+    result.success = result.errors.is_empty();
+    result
+}
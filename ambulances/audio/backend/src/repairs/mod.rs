@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Audio repairs, one module per target
+
+pub mod defaults;
+pub mod restart;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["restart", "defaults", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+pub fn run(target: &str) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    // Restart first so `all` resets the defaults on the fresh server
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        restart_repair: if selected("restart") {
+            restart::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        defaults_repair: if selected("defaults") {
+            defaults::repair()
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Restart the user's audio stack
+//!
+//! Runs whenever asked, healthy or not: a restart is the cure for glitches
+//! the diagnostics cannot see. The systemd user units are restarted over
+//! the session bus when they exist; PulseAudio without units is killed
+//! and left to autospawn.
+
+use crate::diagnostics::stack;
+use crate::pulse;
+use crate::report::RepairOutcome;
+use crate::system;
+use std::time::Duration;
+use systemd_shim::bus::Bus;
+
+/// Restart order: the server before its clients
+const PIPEWIRE_UNITS: &[&str] = &[
+    "pipewire.service",
+    "pipewire-pulse.service",
+    "wireplumber.service",
+    "pipewire-media-session.service",
+];
+
+/// How long the server gets to come back before we call it failed
+const SETTLE: Duration = Duration::from_secs(2);
+
+/// The user units among `candidates` that are installed
+fn installed_units(bus: &Bus, candidates: &[&str]) -> Vec<String> {
+    candidates
+        .iter()
+        .filter(|unit| stack::unit_states(bus, unit).is_some_and(|(load, _)| load == "loaded"))
+        .map(|unit| unit.to_string())
+        .collect()
+}
+
+pub fn repair() -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let stack = stack::diagnose().stack;
+    let user_bus = Bus::user();
+    let installed = |candidates: &[&str]| match &user_bus {
+        Ok(bus) => installed_units(bus, candidates),
+        Err(_) => Vec::new(),
+    };
+
+    let units = match stack.as_str() {
+        "pipewire" => installed(PIPEWIRE_UNITS),
+        "pulseaudio" => installed(&["pulseaudio.service"]),
+        _ => {
+            result
+                .errors
+                .push("No PipeWire or PulseAudio installation to restart".to_string());
+            return result;
+        }
+    };
+
+    if let (Ok(bus), false) = (&user_bus, units.is_empty()) {
+        let failed: Vec<String> = units
+            .iter()
+            .filter_map(|unit| {
+                let e = bus.restart_unit(unit, "replace").err()?;
+                Some(format!("{}: {}", unit, e))
+            })
+            .collect();
+        if failed.is_empty() {
+            result
+                .actions
+                .push(format!("Restarted {}", units.join(", ")));
+        }
+        result.errors.extend(failed);
+    } else if stack == "pulseaudio" {
+        match system::run("pulseaudio", &["-k"]) {
+            Ok(_) => result
+                .actions
+                .push("Stopped PulseAudio; it respawns on first use".to_string()),
+            Err(e) => result.errors.push(e),
+        }
+    } else if let Err(e) = &user_bus {
+        result
+            .errors
+            .push(format!("Cannot reach the session bus: {}", e));
+    } else {
+        result
+            .errors
+            .push("No PipeWire user units found to restart".to_string());
+    }
+
+    std::thread::sleep(SETTLE);
+    match pulse::info() {
+        Some(info) => result
+            .actions
+            .push(format!("Sound server is answering: {}", info.server_name)),
+        None if result.errors.is_empty() => result
+            .errors
+            .push("Sound server did not come back after the restart".to_string()),
+        None => {}
+    }
+
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    firmware::FirmwareDiagnostics, levels::LevelDiagnostics, routing::RoutingDiagnostics,
+    sample_rates::SampleRateDiagnostics, stack::StackDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "audio-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub stack: StackDiagnostics,
+    pub routing: RoutingDiagnostics,
+    pub levels: LevelDiagnostics,
+    pub sample_rates: SampleRateDiagnostics,
+    pub firmware: FirmwareDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.stack.warnings,
+            &self.routing.warnings,
+            &self.levels.warnings,
+            &self.sample_rates.warnings,
+            &self.firmware.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub restart_repair: RepairOutcome,
+    pub defaults_repair: RepairOutcome,
+}
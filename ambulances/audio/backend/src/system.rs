@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thin helpers over the external tools and kernel interfaces we read
+
+use std::process::Command;
+
+/// Run a program that must succeed, returning its stdout
+pub fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether `program` is found on PATH
+pub fn has(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+pub fn uid() -> u32 {
+    unsafe { libc::geteuid() }
+}
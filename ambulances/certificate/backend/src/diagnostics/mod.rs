@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Certificate diagnostics, one module per report section
+//!
+//! Roots and service certificates are read as ambulance-core jobs at the
+//! same time, within the scan's budget.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod roots;
+pub mod services;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; the trust store can hold hundreds of
+/// certificates, each read by openssl
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+enum Section {
+    Roots(roots::RootDiagnostics),
+    Services(services::ServiceDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("roots", |_| {
+            Ok(Section::Roots(roots::diagnose()))
+        }))
+        .job(Job::blocking("services", |_| {
+            Ok(Section::Services(services::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        roots: Default::default(),
+        services: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Roots(d)) => result.roots = d,
+            Some(Section::Services(d)) => result.services = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "roots" => &mut result.roots.warnings,
+                    _ => &mut result.services.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,278 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Trusted root certificates
+//!
+//! Reads the bundle every TLS client on the system trusts, as generated by
+//! `update-ca-certificates` or `update-ca-trust`, and the roots an admin
+//! (or an installer) added locally. Known-bad roots are matched by name:
+//! the vendor roots shipped with their private keys and the CAs browsers
+//! stopped trusting. TLS interception roots are reported but left alone,
+//! since corporate proxies and debugging tools install them on purpose.
+
+use crate::report::{mark, print_notes};
+use crate::x509::{self, Certificate};
+use serde::Serialize;
+use std::path::Path;
+
+/// Generated bundles: Debian, Ubuntu and Arch; Fedora and RHEL; openSUSE
+pub const BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+];
+
+/// Where locally added roots go, in the same distribution order
+pub const LOCAL_ANCHORS: &[&str] = &[
+    "/usr/local/share/ca-certificates",
+    "/etc/pki/ca-trust/source/anchors",
+    "/etc/ca-certificates/trust-source/anchors",
+    "/etc/pki/trust/anchors",
+];
+
+/// Subject names of roots nobody should trust, and why
+const KNOWN_BAD: &[(&str, &str)] = &[
+    (
+        "Superfish",
+        "Lenovo adware root, private key public since 2015",
+    ),
+    (
+        "eDellRoot",
+        "Dell support root, private key public since 2015",
+    ),
+    (
+        "DSDTestProvider",
+        "Dell support root, private key public since 2015",
+    ),
+    ("DigiNotar", "CA compromised in 2011"),
+    ("CNNIC ROOT", "distrusted by browsers in 2015"),
+    ("WoSign", "distrusted by browsers in 2016"),
+    (
+        "StartCom Certification Authority",
+        "distrusted by browsers in 2016",
+    ),
+    ("TrustCor", "distrusted by browsers in 2022"),
+];
+
+/// Products that install a root to read HTTPS traffic
+const INTERCEPTION: &[&str] = &[
+    "mitmproxy",
+    "Fiddler",
+    "Charles Proxy",
+    "PortSwigger",
+    "Kaspersky",
+    "Avast",
+    "AVG",
+    "ESET SSL Filter",
+    "Bitdefender",
+    "Zscaler",
+    "Netskope",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Root {
+    #[serde(flatten)]
+    pub certificate: Certificate,
+    /// The anchor file of a locally added root; `None` for bundle entries
+    pub path: Option<String>,
+    /// Why the root is known bad
+    pub known_bad: Option<&'static str>,
+    /// Interception product the root belongs to
+    pub interception: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RootDiagnostics {
+    /// Generated bundle read
+    pub bundle: Option<String>,
+    pub trusted: usize,
+    /// Roots in the bundle past their expiry date
+    pub expired: usize,
+    /// Roots added locally
+    pub local: Vec<Root>,
+    /// Known-bad roots, local or bundled
+    pub known_bad: Vec<Root>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn classify(certificate: Certificate, path: Option<String>) -> Root {
+    let subject = &certificate.subject;
+    Root {
+        known_bad: KNOWN_BAD
+            .iter()
+            .find(|(name, _)| subject.contains(name))
+            .map(|(_, why)| *why),
+        interception: INTERCEPTION
+            .iter()
+            .find(|name| subject.contains(*name))
+            .copied(),
+        certificate,
+        path,
+    }
+}
+
+/// Locally added roots, one per anchor file; unreadable files go to `errors`
+pub fn local_roots(errors: &mut Vec<String>) -> Vec<Root> {
+    let mut roots = Vec::new();
+    for dir in LOCAL_ANCHORS {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<_> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect();
+        paths.sort();
+        for path in paths {
+            match x509::read_file(&path) {
+                Ok(certificates) => roots.extend(
+                    certificates
+                        .into_iter()
+                        .map(|c| classify(c, Some(path.display().to_string()))),
+                ),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+    roots
+}
+
+pub fn diagnose() -> RootDiagnostics {
+    let mut diag = RootDiagnostics::default();
+    let Some(bundle) = BUNDLES.iter().find(|b| Path::new(b).exists()) else {
+        diag.warnings
+            .push("No CA bundle found; TLS clients cannot verify anything".to_string());
+        diag.recommendations.push(
+            "Reinstall the ca-certificates package (ca-certificates on most distributions)"
+                .to_string(),
+        );
+        return diag;
+    };
+    diag.bundle = Some(bundle.to_string());
+    let bundled = match x509::read_file(bundle) {
+        Ok(certificates) => certificates,
+        Err(e) => {
+            diag.error = Some(e);
+            return diag;
+        }
+    };
+    let mut errors = Vec::new();
+    diag.local = local_roots(&mut errors);
+
+    diag.trusted = bundled.len();
+    diag.expired = bundled.iter().filter(|c| c.days_left < 0).count();
+    diag.known_bad = diag
+        .local
+        .iter()
+        .filter(|r| r.known_bad.is_some())
+        .cloned()
+        .collect();
+    for certificate in bundled.iter() {
+        let root = classify(certificate.clone(), None);
+        let listed = diag
+            .known_bad
+            .iter()
+            .any(|r| r.certificate.fingerprint == certificate.fingerprint);
+        if root.known_bad.is_some() && !listed {
+            diag.known_bad.push(root);
+        }
+    }
+
+    for root in &diag.known_bad {
+        diag.warnings.push(format!(
+            "Known-bad root trusted: {} ({})",
+            root.certificate.name(),
+            root.known_bad.unwrap_or_default()
+        ));
+    }
+    if !diag.known_bad.is_empty() {
+        diag.recommendations
+            .push("Distrust them: certificate-ambulance repair known-bad".to_string());
+    }
+    let mut stale = false;
+    for root in diag.local.iter().filter(|r| r.known_bad.is_none()) {
+        let path = root.path.as_deref().unwrap_or_default();
+        if let Some(product) = root.interception {
+            diag.warnings.push(format!(
+                "Local root {} ({}) belongs to {}, which can read all HTTPS traffic",
+                root.certificate.name(),
+                path,
+                product
+            ));
+            diag.recommendations.push(format!(
+                "Remove {} and refresh the store unless the interception is intended",
+                path
+            ));
+        }
+        if root.certificate.days_left < 0 {
+            diag.warnings.push(format!(
+                "Local root {} ({}) expired on {}",
+                root.certificate.name(),
+                path,
+                root.certificate.not_after
+            ));
+        }
+        if !bundled
+            .iter()
+            .any(|c| c.fingerprint == root.certificate.fingerprint)
+        {
+            diag.warnings.push(format!(
+                "Local root {} ({}) is not in {} yet",
+                root.certificate.name(),
+                path,
+                bundle
+            ));
+            stale = true;
+        }
+    }
+    if stale {
+        diag.recommendations
+            .push("Refresh the store: update-ca-certificates (or update-ca-trust)".to_string());
+    }
+    if diag.expired > 0 {
+        diag.warnings.push(format!(
+            "{} of {} trusted roots have expired",
+            diag.expired, diag.trusted
+        ));
+        diag.recommendations
+            .push("Update the ca-certificates package, which looks outdated".to_string());
+    }
+    for error in errors {
+        diag.warnings
+            .push(format!("Unreadable local root: {}", error));
+    }
+
+    diag
+}
+
+impl RootDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Trusted Roots ===");
+        match (&self.bundle, &self.error) {
+            (_, Some(error)) => println!("- Unknown: {}", error),
+            (Some(bundle), None) => {
+                println!("- Store: {} ({} roots)", bundle, self.trusted);
+                println!(
+                    "{} Known-bad roots: {}",
+                    mark(self.known_bad.is_empty()),
+                    self.known_bad.len()
+                );
+                println!("- Locally added: {}", self.local.len());
+                if verbose {
+                    for root in &self.local {
+                        println!(
+                            "  {}  {} (until {})",
+                            root.path.as_deref().unwrap_or_default(),
+                            root.certificate.name(),
+                            root.certificate.not_after
+                        );
+                    }
+                    println!("- Expired in store: {}", self.expired);
+                }
+            }
+            (None, None) => println!("✗ No CA bundle"),
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
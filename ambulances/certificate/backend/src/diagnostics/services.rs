@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Certificates services present to clients
+//!
+//! Finds the certificate files named in web and mail server
+//! configurations and checks when they expire. A renewal job that
+//! silently stopped working shows up here weeks before clients start
+//! refusing the connection. Paths built from variables are skipped.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use crate::x509::{self, Certificate};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Days before expiry from which a certificate is reported
+pub const EXPIRING_DAYS: i64 = 30;
+
+/// Days before expiry from which `remind` logs a certificate
+pub const REMIND_DAYS: i64 = 60;
+
+/// Configuration directories of each service and the directives that
+/// name its certificate; directives match case-insensitively
+const SERVICES: &[(&str, &[&str], &[&str])] = &[
+    ("nginx", &["/etc/nginx"], &["ssl_certificate"]),
+    (
+        "apache",
+        &["/etc/apache2", "/etc/httpd"],
+        &["SSLCertificateFile"],
+    ),
+    (
+        "postfix",
+        &["/etc/postfix"],
+        &["smtpd_tls_cert_file", "smtp_tls_cert_file"],
+    ),
+    ("dovecot", &["/etc/dovecot"], &["ssl_cert"]),
+    ("haproxy", &["/etc/haproxy"], &["crt"]),
+];
+
+/// How deep configuration trees are searched
+const DEPTH: usize = 4;
+
+/// Configuration files above this size are not read
+const MAX_CONFIG_BYTES: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceCertificate {
+    pub service: &'static str,
+    /// Configuration file naming the certificate
+    pub config: String,
+    pub path: String,
+    /// The first certificate in the file, the one presented
+    pub certificate: Option<Certificate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServiceDiagnostics {
+    /// Soonest expiry first
+    pub certificates: Vec<ServiceCertificate>,
+    /// Certificates readable by root only, not checked
+    pub unreadable: usize,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn config_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        // Follows links, as sites-enabled/ is made of them
+        let Ok(meta) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() && depth > 0 {
+            config_files(&path, depth - 1, files);
+        } else if meta.is_file() && meta.len() <= MAX_CONFIG_BYTES {
+            files.push(path);
+        }
+    }
+}
+
+/// Certificate paths named by `directives` in one configuration text
+///
+/// Handles `directive path;` (nginx), `Directive path` (Apache, HAProxy
+/// `bind ... crt path`), `key = path` (Postfix) and `key = <path` (Dovecot).
+fn referenced(text: &str, directives: &[&str]) -> Vec<String> {
+    let mut paths = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        let tokens: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == '=')
+            .filter(|t| !t.is_empty())
+            .collect();
+        for (i, token) in tokens.iter().enumerate() {
+            if !directives.iter().any(|d| token.eq_ignore_ascii_case(d)) {
+                continue;
+            }
+            let Some(value) = tokens.get(i + 1) else {
+                continue;
+            };
+            let value = value
+                .trim_end_matches(';')
+                .trim_matches(['"', '\''])
+                .trim_start_matches('<');
+            if value.starts_with('/') && !value.contains('$') && !value.contains('%') {
+                paths.push(value.to_string());
+            }
+        }
+    }
+    paths
+}
+
+/// Every certificate file the service configurations name
+fn references() -> Vec<(&'static str, String, String)> {
+    let mut found: Vec<(&'static str, String, String)> = Vec::new();
+    for (service, dirs, directives) in SERVICES {
+        let mut files = Vec::new();
+        for dir in dirs.iter() {
+            config_files(Path::new(dir), DEPTH, &mut files);
+        }
+        for file in files {
+            let Some(text) = system::read(&file) else {
+                continue;
+            };
+            for path in referenced(&text, directives) {
+                // HAProxy takes a directory of PEM files too
+                let paths = if Path::new(&path).is_dir() {
+                    let mut inner: Vec<String> = std::fs::read_dir(&path)
+                        .into_iter()
+                        .flat_map(|entries| entries.flatten())
+                        .map(|e| e.path())
+                        .filter(|p| p.is_file())
+                        .map(|p| p.display().to_string())
+                        .collect();
+                    inner.sort();
+                    inner
+                } else {
+                    vec![path]
+                };
+                for path in paths {
+                    if !found.iter().any(|(s, _, p)| s == service && *p == path) {
+                        found.push((service, file.display().to_string(), path));
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+pub fn diagnose() -> ServiceDiagnostics {
+    let mut diag = ServiceDiagnostics::default();
+    for (service, config, path) in references() {
+        let mut entry = ServiceCertificate {
+            service,
+            config,
+            path,
+            certificate: None,
+            error: None,
+        };
+        if !Path::new(&entry.path).exists() {
+            diag.warnings.push(format!(
+                "{} names {}, which does not exist; the service will not start",
+                entry.config, entry.path
+            ));
+            entry.error = Some("missing".to_string());
+        } else if std::fs::File::open(&entry.path).is_err() && !ambulance_privilege::is_elevated() {
+            diag.unreadable += 1;
+            entry.error = Some("readable by root only".to_string());
+        } else {
+            match x509::read_file(&entry.path) {
+                Ok(mut certificates) => entry.certificate = Some(certificates.remove(0)),
+                Err(e) => {
+                    diag.warnings
+                        .push(format!("{} certificate unreadable: {}", service, e));
+                    entry.error = Some(e);
+                }
+            }
+        }
+        diag.certificates.push(entry);
+    }
+    diag.certificates.sort_by_key(|c| {
+        c.certificate
+            .as_ref()
+            .map_or(i64::MAX, |certificate| certificate.days_left)
+    });
+
+    let mut renew = false;
+    for entry in &diag.certificates {
+        let Some(certificate) = &entry.certificate else {
+            continue;
+        };
+        if certificate.days_left < 0 {
+            diag.warnings.push(format!(
+                "{} certificate {} ({}) expired {} days ago",
+                entry.service,
+                entry.path,
+                certificate.name(),
+                -certificate.days_left
+            ));
+        } else if certificate.days_left <= EXPIRING_DAYS {
+            diag.warnings.push(format!(
+                "{} certificate {} ({}) expires in {} days, on {}",
+                entry.service,
+                entry.path,
+                certificate.name(),
+                certificate.days_left,
+                certificate.not_after
+            ));
+        } else {
+            continue;
+        }
+        renew = true;
+        if entry.path.starts_with("/etc/letsencrypt/") {
+            diag.recommendations.push(
+                "Renew with `certbot renew` and check that certbot.timer is active".to_string(),
+            );
+        }
+    }
+    if renew {
+        diag.recommendations
+            .push("Renew the certificates and reload the services that present them".to_string());
+        diag.recommendations.push(
+            "Run `certificate-ambulance remind` from a timer to have expiries logged".to_string(),
+        );
+    }
+    if diag.unreadable > 0 {
+        diag.recommendations.push(format!(
+            "Run as root to check {} certificates readable by root only",
+            diag.unreadable
+        ));
+    }
+    diag.recommendations.dedup();
+
+    diag
+}
+
+impl ServiceDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Service Certificates ===");
+        let expiring = self
+            .certificates
+            .iter()
+            .filter_map(|c| c.certificate.as_ref())
+            .filter(|c| c.days_left <= EXPIRING_DAYS)
+            .count();
+        println!(
+            "{} {} referenced, {} expired or expiring within {} days",
+            mark(expiring == 0),
+            self.certificates.len(),
+            expiring,
+            EXPIRING_DAYS
+        );
+        if verbose {
+            for entry in &self.certificates {
+                match (&entry.certificate, &entry.error) {
+                    (Some(certificate), _) => println!(
+                        "- {:>5}d  {} {} ({})",
+                        certificate.days_left,
+                        entry.service,
+                        entry.path,
+                        certificate.name()
+                    ),
+                    (None, error) => println!(
+                        "-     ?   {} {} ({})",
+                        entry.service,
+                        entry.path,
+                        error.as_deref().unwrap_or_default()
+                    ),
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Certificate Ambulance backend
+//!
+//! Audits the system CA store for known-bad and interception roots,
+//! checks when the certificates web and mail servers present expire,
+//! logs reminders for those due soon and distrusts known-bad roots.
+//! `--json` output follows the network ambulance's report model.
+
+mod diagnostics;
+mod remind;
+mod repairs;
+mod report;
+mod system;
+mod x509;
+
+use ambulance_core::error::{exit, Error};
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Certificate Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: certificate-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all certificate diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!(
+        "  remind               Log certificates due within {} days to the journal",
+        diagnostics::services::REMIND_DAYS
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+    println!("  -y, --yes            Approve changes that ask for confirmation");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Certificate Ambulance");
+    println!("=====================\n");
+    result.roots.print(verbose);
+    result.services.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Trusted roots", &result.roots.warnings),
+        ("Service certificates", &result.services.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    if let Some(next) = result
+        .services
+        .certificates
+        .iter()
+        .find_map(|c| c.certificate.as_ref())
+    {
+        println!("Next expiry: {} in {} days", next.name(), next.days_left);
+    }
+    exit::findings(result.has_warnings())
+}
+
+fn run_remind(json: bool) -> ExitCode {
+    let result = remind::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else if result.reminders.is_empty() {
+        println!(
+            "No certificate due within {} days",
+            diagnostics::services::REMIND_DAYS
+        );
+    } else {
+        for reminder in &result.reminders {
+            println!(
+                "{:>5}d  {} {}",
+                reminder.days_left, reminder.source, reminder.path
+            );
+        }
+    }
+    if !json {
+        for error in &result.errors {
+            eprintln!("✗ {}", error);
+        }
+    }
+
+    if result.errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(exit::FAILED)
+    }
+}
+
+/// Ask on the terminal; without one (or in JSON mode) nothing is approved
+fn prompt(question: &str, json: bool) -> bool {
+    if json || !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn run_repair(target: &str, json: bool, yes: bool) -> ExitCode {
+    if let Err(e) =
+        ambulance_privilege::require(&format!("certificate-ambulance repair {}", target))
+    {
+        return fail(e, json);
+    }
+
+    let mut confirm = |question: &str| yes || prompt(question, json);
+    let result = match repairs::run(target, &mut confirm) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [("known-bad", "Known-Bad Roots", &result.known_bad_repair)];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Certificate Ambulance - Repair Mode");
+        println!("===================================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let yes = args.iter().any(|a| a == "-y" || a == "--yes");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, json, yes),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("remind") => run_remind(json),
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Certificate Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
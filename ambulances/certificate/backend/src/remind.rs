@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Expiry reminders in the journal
+//!
+//! `remind` is meant to run daily from a timer. Each certificate due
+//! within `REMIND_DAYS` becomes one warning entry with the certificate
+//! in structured fields, so whatever watches the journal can alert on
+//! `SYSLOG_IDENTIFIER=certificate-ambulance` without parsing messages.
+
+use crate::diagnostics::{roots, services};
+use crate::report::{Reminder, ReminderResult, TOOL, VERSION};
+use systemd_shim::journal;
+
+/// Journal priority of a reminder: warning, or error once expired
+fn priority(days_left: i64) -> &'static str {
+    if days_left < 0 {
+        "3"
+    } else {
+        "4"
+    }
+}
+
+fn log(reminder: &Reminder) -> Result<(), String> {
+    let message = if reminder.days_left < 0 {
+        format!(
+            "{} certificate {} expired {} days ago",
+            reminder.source, reminder.path, -reminder.days_left
+        )
+    } else {
+        format!(
+            "{} certificate {} expires in {} days, on {}",
+            reminder.source, reminder.path, reminder.days_left, reminder.not_after
+        )
+    };
+    journal::send(&[
+        ("MESSAGE", message.as_str()),
+        ("PRIORITY", priority(reminder.days_left)),
+        ("SYSLOG_IDENTIFIER", TOOL),
+        ("CERTIFICATE_SOURCE", reminder.source),
+        ("CERTIFICATE_PATH", reminder.path.as_str()),
+        ("CERTIFICATE_SUBJECT", reminder.subject.as_str()),
+        ("CERTIFICATE_NOT_AFTER", reminder.not_after.as_str()),
+        (
+            "CERTIFICATE_DAYS_LEFT",
+            reminder.days_left.to_string().as_str(),
+        ),
+    ])
+    .map_err(|e| format!("journal: {}", e))
+}
+
+/// Log every service certificate and local root due within `REMIND_DAYS`
+pub fn run() -> ReminderResult {
+    let mut reminders = Vec::new();
+    let mut errors = Vec::new();
+    for entry in services::diagnose().certificates {
+        if let Some(certificate) = entry.certificate {
+            reminders.push(Reminder {
+                source: entry.service,
+                path: entry.path,
+                subject: certificate.subject,
+                not_after: certificate.not_after,
+                days_left: certificate.days_left,
+            });
+        }
+    }
+    for root in roots::local_roots(&mut errors) {
+        reminders.push(Reminder {
+            source: "root",
+            path: root.path.unwrap_or_default(),
+            subject: root.certificate.subject,
+            not_after: root.certificate.not_after,
+            days_left: root.certificate.days_left,
+        });
+    }
+    reminders.retain(|r| r.days_left <= services::REMIND_DAYS);
+    reminders.sort_by_key(|r| r.days_left);
+
+    errors.extend(reminders.iter().filter_map(|r| log(r).err()));
+    ReminderResult {
+        version: VERSION,
+        tool: TOOL,
+        reminders,
+        errors,
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Distrust known-bad roots
+//!
+//! A locally added anchor is deleted. A root the distribution ships is
+//! distrusted the distribution's way, so a package update does not bring
+//! it back: deselected with `!` in `/etc/ca-certificates.conf` on Debian,
+//! or copied into the p11-kit blocklist elsewhere. The bundle is then
+//! regenerated and read again to confirm.
+
+use crate::diagnostics::roots::{self, Root};
+use crate::report::RepairOutcome;
+use crate::system;
+use crate::x509;
+use std::path::{Path, PathBuf};
+
+/// Debian's list of packaged roots, relative to `PACKAGED`
+const DEBIAN_CONF: &str = "/etc/ca-certificates.conf";
+const PACKAGED: &str = "/usr/share/ca-certificates";
+
+/// p11-kit blocklists: Fedora and RHEL (new and old name), Arch, openSUSE
+const BLOCKLISTS: &[&str] = &[
+    "/etc/pki/ca-trust/source/blocklist",
+    "/etc/pki/ca-trust/source/blacklist",
+    "/etc/ca-certificates/trust-source/blocklist",
+    "/etc/pki/trust/blacklist",
+];
+
+/// Bundle generators, with the arguments that rebuild from scratch
+const REFRESH: &[(&str, &[&str])] = &[
+    ("update-ca-trust", &["extract"]),
+    ("update-ca-certificates", &["--fresh"]),
+];
+
+fn base64_body(pem: &str) -> String {
+    pem.lines()
+        .filter(|l| !l.starts_with("-----"))
+        .flat_map(|l| l.trim().chars())
+        .collect()
+}
+
+fn files_under(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            files_under(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// The packaged file holding `root`, relative to `PACKAGED`
+fn packaged(root: &Root) -> Option<String> {
+    let body = base64_body(&root.certificate.pem);
+    let mut files = Vec::new();
+    files_under(Path::new(PACKAGED), &mut files);
+    files.into_iter().find_map(|path| {
+        let text = system::read(&path)?;
+        x509::split_pem(&text)
+            .iter()
+            .any(|pem| base64_body(pem) == body)
+            .then(|| {
+                path.strip_prefix(PACKAGED)
+                    .unwrap_or(&path)
+                    .display()
+                    .to_string()
+            })
+    })
+}
+
+/// Distrust a root shipped in the bundle
+fn distrust(root: &Root) -> Result<String, String> {
+    let name = root.certificate.name();
+    if let Some(conf) = system::read(DEBIAN_CONF) {
+        let relative =
+            packaged(root).ok_or_else(|| format!("{}: not found under {}", name, PACKAGED))?;
+        let mut found = false;
+        let mut lines: Vec<String> = conf
+            .lines()
+            .map(|l| {
+                if l.trim() == relative {
+                    found = true;
+                    format!("!{}", relative)
+                } else {
+                    l.to_string()
+                }
+            })
+            .collect();
+        if !found {
+            lines.push(format!("!{}", relative));
+        }
+        std::fs::write(DEBIAN_CONF, lines.join("\n") + "\n")
+            .map_err(|e| format!("{}: {}", DEBIAN_CONF, e))?;
+        return Ok(format!(
+            "Deselected {} ({}) in {}",
+            relative, name, DEBIAN_CONF
+        ));
+    }
+
+    let dir = BLOCKLISTS
+        .iter()
+        .find(|d| Path::new(d).is_dir())
+        .ok_or_else(|| format!("{}: no CA blocklist directory on this system", name))?;
+    let id: String = root
+        .certificate
+        .fingerprint
+        .chars()
+        .filter(|c| *c != ':')
+        .take(16)
+        .collect();
+    let file = format!("{}/{}.pem", dir, id.to_ascii_lowercase());
+    std::fs::write(&file, &root.certificate.pem).map_err(|e| format!("{}: {}", file, e))?;
+    Ok(format!("Blocklisted {} in {}", name, file))
+}
+
+fn refresh() -> Result<String, String> {
+    let (program, args) = REFRESH
+        .iter()
+        .find(|(program, _)| system::has(program))
+        .ok_or("Neither update-ca-trust nor update-ca-certificates is installed")?;
+    system::run(program, args)?;
+    Ok(format!("Regenerated the bundle with {}", program))
+}
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let diag = roots::diagnose();
+    let mut result = RepairOutcome::default();
+    if let Some(error) = diag.error {
+        result.errors.push(error);
+        return result;
+    }
+    if diag.known_bad.is_empty() {
+        return RepairOutcome::not_needed("No known-bad roots trusted, no repair needed");
+    }
+
+    let names: Vec<&str> = diag
+        .known_bad
+        .iter()
+        .map(|r| r.certificate.name())
+        .collect();
+    let question = format!(
+        "Distrust {} known-bad roots ({})?",
+        names.len(),
+        names.join(", ")
+    );
+    if !confirm(&question) {
+        result.errors.push(format!(
+            "Known-bad roots not confirmed, still trusted: {} (rerun with --yes to confirm)",
+            names.join(", ")
+        ));
+        return result;
+    }
+
+    for root in &diag.known_bad {
+        let outcome = match &root.path {
+            Some(path) => std::fs::remove_file(path)
+                .map(|()| format!("Removed local root {} ({})", root.certificate.name(), path))
+                .map_err(|e| format!("{}: {}", path, e)),
+            None => distrust(root),
+        };
+        match outcome {
+            Ok(action) => result.actions.push(action),
+            Err(e) => result.errors.push(e),
+        }
+    }
+    match refresh() {
+        Ok(action) => result.actions.push(action),
+        Err(e) => result.errors.push(e),
+    }
+
+    let remaining: Vec<String> = roots::diagnose()
+        .known_bad
+        .iter()
+        .map(|r| r.certificate.name().to_string())
+        .collect();
+    if remaining.is_empty() {
+        result
+            .actions
+            .push("No known-bad root left in the store".to_string());
+    } else {
+        result
+            .errors
+            .push(format!("Still trusted: {}", remaining.join(", ")));
+    }
+    result.success = result.errors.is_empty();
+    result
+}
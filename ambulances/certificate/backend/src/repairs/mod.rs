@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Certificate store repairs, one module per target
+
+pub mod known_bad;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["known-bad", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before the store is changed.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        known_bad_repair: if selected("known-bad") {
+            known_bad::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{roots::RootDiagnostics, services::ServiceDiagnostics};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "certificate-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub roots: RootDiagnostics,
+    pub services: ServiceDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [&self.roots.warnings, &self.services.warnings]
+            .iter()
+            .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub known_bad_repair: RepairOutcome,
+}
+
+/// One certificate logged by `remind`
+#[derive(Debug, Serialize)]
+pub struct Reminder {
+    /// Service presenting the certificate, or `root` for a local root
+    pub source: &'static str,
+    pub path: String,
+    pub subject: String,
+    pub not_after: String,
+    pub days_left: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReminderResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub reminders: Vec<Reminder>,
+    pub errors: Vec<String>,
+}
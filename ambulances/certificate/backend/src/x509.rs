@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Certificate fields, read with `openssl x509`
+//!
+//! Bundles and chain files hold several PEM blocks; each is handed to
+//! openssl on stdin on its own, so one bad block does not hide the rest.
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const END: &str = "-----END CERTIFICATE-----";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Certificate {
+    /// RFC 2253 form: comma-separated `CN=...` style parts
+    pub subject: String,
+    pub issuer: String,
+    /// As openssl prints it, e.g. `Dec 31 09:37:37 2030 GMT`
+    pub not_after: String,
+    /// Whole days until `not_after`; negative once expired
+    pub days_left: i64,
+    /// SHA-256 of the DER encoding, colon-separated upper-case hex
+    pub fingerprint: String,
+    #[serde(skip)]
+    pub pem: String,
+}
+
+impl Certificate {
+    /// Common name from the subject, or the whole subject without one
+    pub fn name(&self) -> &str {
+        self.subject
+            .split(',')
+            .find_map(|part| part.strip_prefix("CN="))
+            .unwrap_or(&self.subject)
+    }
+}
+
+/// The PEM certificate blocks of `text`, markers included
+pub fn split_pem(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if line == BEGIN {
+            current = Some(String::new());
+        }
+        if let Some(block) = current.as_mut() {
+            block.push_str(line);
+            block.push('\n');
+        }
+        if line == END {
+            blocks.extend(current.take());
+        }
+    }
+    blocks
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Unix time of an openssl date such as `Dec 31 09:37:37 2030 GMT`
+fn parse_date(date: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let mut parts = date.split_whitespace();
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let day: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':').map(|n| n.parse::<i64>());
+    let (h, m, s) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+    let year: i64 = parts.next()?.parse().ok()?;
+    Some(days_from_civil(year, month, day) * 86_400 + h * 3600 + m * 60 + s)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Fields of one PEM certificate
+pub fn parse(pem: &str) -> Result<Certificate, String> {
+    let mut child = Command::new("openssl")
+        .args([
+            "x509",
+            "-noout",
+            "-subject",
+            "-issuer",
+            "-enddate",
+            "-fingerprint",
+            "-sha256",
+            "-nameopt",
+            "RFC2253",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run openssl: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(pem.as_bytes());
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("openssl x509 failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "openssl x509 failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let field = |prefix: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(prefix))
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default()
+    };
+    let not_after = field("notAfter=");
+    let expires = parse_date(&not_after).ok_or_else(|| format!("bad date: {}", not_after))?;
+    Ok(Certificate {
+        subject: field("subject="),
+        issuer: field("issuer="),
+        days_left: (expires - now()).div_euclid(86_400),
+        not_after,
+        // `sha256 Fingerprint=` in OpenSSL 3, `SHA256 Fingerprint=` before
+        fingerprint: text
+            .lines()
+            .find_map(|l| l.split_once("Fingerprint="))
+            .map(|(_, v)| v.trim().to_string())
+            .unwrap_or_default(),
+        pem: pem.to_string(),
+    })
+}
+
+/// Every certificate in a PEM file, in file order
+pub fn read_file(path: impl AsRef<Path>) -> Result<Vec<Certificate>, String> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let blocks = split_pem(&text);
+    if blocks.is_empty() {
+        return Err(format!("{}: no PEM certificate", path.display()));
+    }
+    blocks.iter().map(|pem| parse(pem)).collect()
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Daemon and socket reachability
+//!
+//! For each installed engine: whether its API socket answers a ping,
+//! what systemd says about its service and socket units, and whether
+//! `info` works, which for Docker needs the daemon and for Podman needs
+//! readable storage.
+
+use crate::engines::{self, Engine, Info};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::bus::{self, Bus};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineStatus {
+    pub name: &'static str,
+    #[serde(skip)]
+    pub engine: &'static Engine,
+    pub socket: String,
+    /// Why the socket did not answer; `None` when it did
+    pub socket_error: Option<String>,
+    /// `ActiveState` of the service and socket units, `None` if not installed
+    pub service_state: Option<String>,
+    pub socket_unit_state: Option<String>,
+    pub info: Option<Info>,
+    pub info_error: Option<String>,
+}
+
+impl EngineStatus {
+    /// Whether images and containers can be listed
+    pub fn reachable(&self) -> bool {
+        self.info.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DaemonDiagnostics {
+    pub engines: Vec<EngineStatus>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// `ActiveState` of a unit that is installed
+pub fn unit_state(bus: Option<&Bus>, unit: &str) -> Option<String> {
+    let bus = bus?;
+    let path = bus::unit_path(unit).ok()?;
+    let load = bus
+        .get_property_string(bus::SYSTEMD, &path, bus::UNIT, "LoadState")
+        .ok()?;
+    if load != "loaded" {
+        return None;
+    }
+    bus.get_property_string(bus::SYSTEMD, &path, bus::UNIT, "ActiveState")
+        .ok()
+}
+
+pub fn status(bus: Option<&Bus>, engine: &'static Engine) -> EngineStatus {
+    let (info, info_error) = match engine.info() {
+        Ok(info) => (Some(info), None),
+        Err(e) => (None, Some(e)),
+    };
+    EngineStatus {
+        name: engine.name,
+        engine,
+        socket: engine.socket(),
+        socket_error: engine.ping().err(),
+        service_state: unit_state(bus, engine.service),
+        socket_unit_state: unit_state(bus, engine.socket_unit),
+        info,
+        info_error,
+    }
+}
+
+pub fn diagnose() -> DaemonDiagnostics {
+    let bus = Bus::system().ok();
+    let mut diag = DaemonDiagnostics {
+        engines: engines::installed()
+            .map(|e| status(bus.as_ref(), e))
+            .collect(),
+        ..DaemonDiagnostics::default()
+    };
+
+    for s in &diag.engines {
+        let engine = s.engine;
+        let failed_units: Vec<&str> = [
+            (engine.service, &s.service_state),
+            (engine.socket_unit, &s.socket_unit_state),
+        ]
+        .iter()
+        .filter(|(_, state)| state.as_deref() == Some("failed"))
+        .map(|(unit, _)| *unit)
+        .collect();
+        let failed = !failed_units.is_empty();
+        if failed {
+            diag.warnings
+                .push(format!("{} failed", failed_units.join(" and ")));
+        }
+        match (&s.socket_error, engine.daemonless) {
+            (Some(e), false) => {
+                diag.warnings
+                    .push(format!("{} cannot reach its daemon: {}", s.name, e));
+                diag.recommendations.push(if e.starts_with("permission denied") {
+                    format!(
+                        "Run as root, or add the user to the {} group and log in again",
+                        s.name
+                    )
+                } else if s.service_state.is_none() {
+                    format!(
+                        "No {} unit is installed; start the daemon the way it was set up",
+                        engine.service
+                    )
+                } else {
+                    format!(
+                        "Restart the daemon: container-ambulance repair daemon (then journalctl -u {} if it fails again)",
+                        engine.service
+                    )
+                });
+            }
+            // Podman's socket is optional; only a failed unit matters
+            (Some(_), true) if failed => diag.recommendations.push(format!(
+                "Restart {}: container-ambulance repair daemon",
+                engine.socket_unit
+            )),
+            _ => {}
+        }
+        if let (Some(e), true) = (&s.info_error, s.socket_error.is_none() || engine.daemonless) {
+            diag.warnings.push(format!("{} info fails: {}", s.name, e));
+            diag.recommendations.push(format!(
+                "Run `{} info` to see the full error; a storage.conf or daemon.json change is the usual cause",
+                s.name
+            ));
+        }
+    }
+    diag
+}
+
+impl DaemonDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Daemons ===");
+        if self.engines.is_empty() {
+            println!("- No container engine installed");
+        }
+        for s in &self.engines {
+            let engine = s.engine;
+            let socket_ok = s.socket_error.is_none() || engine.daemonless;
+            println!(
+                "{} {}: {}",
+                mark(s.reachable() && socket_ok),
+                s.name,
+                match (&s.info, &s.socket_error) {
+                    (Some(info), _) => format!("{} ({})", info.version, info.storage_driver),
+                    (None, Some(e)) => e.clone(),
+                    (None, None) => "unreachable".to_string(),
+                }
+            );
+            if verbose {
+                println!(
+                    "    {}: {}",
+                    s.socket,
+                    s.socket_error.as_deref().unwrap_or("answers")
+                );
+                for (unit, state) in [
+                    (engine.service, &s.service_state),
+                    (engine.socket_unit, &s.socket_unit_state),
+                ] {
+                    println!(
+                        "    {}: {}",
+                        unit,
+                        state.as_deref().unwrap_or("not installed")
+                    );
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Dangling images and volumes
+//!
+//! A dangling image has lost its tag to a newer build or pull; nothing
+//! can refer to it by name again. A dangling volume is one no container
+//! mounts. Anonymous volumes, named by a 64-digit hash, were created for
+//! a container that is gone; named ones may hold data someone means to
+//! keep, so they are listed but never removed.
+
+use crate::diagnostics::daemon::DaemonDiagnostics;
+use crate::engines::{human_bytes, parse_size};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineDangling {
+    pub name: &'static str,
+    pub images: Vec<String>,
+    pub images_bytes: u64,
+    /// Unused volumes named by a hash
+    pub anonymous_volumes: Vec<String>,
+    /// Unused volumes someone named
+    pub named_volumes: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DanglingDiagnostics {
+    pub engines: Vec<EngineDangling>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn anonymous(volume: &str) -> bool {
+    volume.len() == 64 && volume.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+pub fn scan(daemon: &DaemonDiagnostics) -> Vec<EngineDangling> {
+    daemon
+        .engines
+        .iter()
+        .filter(|s| s.reachable())
+        .map(|s| {
+            let mut found = EngineDangling {
+                name: s.name,
+                images: Vec::new(),
+                images_bytes: 0,
+                anonymous_volumes: Vec::new(),
+                named_volumes: Vec::new(),
+                error: None,
+            };
+            let images = s.engine.cli(&[
+                "images",
+                "--filter",
+                "dangling=true",
+                "--format",
+                "{{.ID}}\t{{.Size}}",
+            ]);
+            let volumes = s
+                .engine
+                .ids(&["volume", "ls", "--quiet", "--filter", "dangling=true"]);
+            match (images, volumes) {
+                (Ok(images), Ok(volumes)) => {
+                    for line in images.lines() {
+                        let (id, size) = line.split_once('\t').unwrap_or((line, ""));
+                        if id.is_empty() {
+                            continue;
+                        }
+                        found.images.push(id.to_string());
+                        found.images_bytes += parse_size(size).unwrap_or(0);
+                    }
+                    (found.anonymous_volumes, found.named_volumes) =
+                        volumes.into_iter().partition(|v| anonymous(v));
+                }
+                (Err(e), _) | (_, Err(e)) => found.error = Some(e),
+            }
+            found
+        })
+        .collect()
+}
+
+pub fn diagnose(daemon: &DaemonDiagnostics) -> DanglingDiagnostics {
+    let mut diag = DanglingDiagnostics {
+        engines: scan(daemon),
+        ..DanglingDiagnostics::default()
+    };
+
+    for engine in &diag.engines {
+        if !engine.images.is_empty() {
+            diag.warnings.push(format!(
+                "{} has {} dangling image(s) holding {}",
+                engine.name,
+                engine.images.len(),
+                human_bytes(engine.images_bytes)
+            ));
+        }
+        if !engine.anonymous_volumes.is_empty() {
+            diag.warnings.push(format!(
+                "{} has {} anonymous volume(s) no container uses",
+                engine.name,
+                engine.anonymous_volumes.len()
+            ));
+        }
+        if !engine.named_volumes.is_empty() {
+            diag.recommendations.push(format!(
+                "Review unused named {} volumes before removing them by hand: {}",
+                engine.name,
+                engine.named_volumes.join(", ")
+            ));
+        }
+        if let Some(e) = &engine.error {
+            diag.warnings.push(format!(
+                "Cannot list {} images or volumes: {}",
+                engine.name, e
+            ));
+        }
+    }
+    if diag
+        .engines
+        .iter()
+        .any(|e| !e.images.is_empty() || !e.anonymous_volumes.is_empty())
+    {
+        diag.recommendations
+            .push("Remove them: container-ambulance repair cleanup".to_string());
+    }
+    diag
+}
+
+impl DanglingDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Dangling ===");
+        if self.engines.is_empty() {
+            println!("- No reachable engine");
+        }
+        for engine in &self.engines {
+            println!(
+                "{} {}: {} image(s) ({}), {} anonymous and {} named unused volume(s)",
+                mark(engine.images.is_empty() && engine.anonymous_volumes.is_empty()),
+                engine.name,
+                engine.images.len(),
+                human_bytes(engine.images_bytes),
+                engine.anonymous_volumes.len(),
+                engine.named_volumes.len()
+            );
+            if verbose {
+                for volume in &engine.named_volumes {
+                    println!("    volume {}", volume);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
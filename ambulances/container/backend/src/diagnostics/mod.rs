@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Container diagnostics, one module per report section
+//!
+//! The daemon section finds the engines; storage, dangling resources and
+//! restarts run as ambulance-core jobs after it, at the same time, within
+//! the scan's budget. When the daemon section does not finish, the others
+//! are skipped and say so in their warnings; a section the budget cut
+//! short is left empty and its timing says why.
+
+pub mod daemon;
+pub mod dangling;
+pub mod restarts;
+pub mod storage;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Inputs, Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; a daemon that stopped answering hangs
+/// the engine's CLI
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+enum Section {
+    Daemon(daemon::DaemonDiagnostics),
+    Storage(storage::StorageDiagnostics),
+    Dangling(dangling::DanglingDiagnostics),
+    Restarts(restarts::RestartDiagnostics),
+}
+
+/// The daemon section, for the sections that run after it
+fn daemon(inputs: &Inputs<Section>) -> Result<&daemon::DaemonDiagnostics, String> {
+    match inputs.get("daemon") {
+        Some(Section::Daemon(d)) => Ok(d),
+        _ => Err("no daemon section".to_string()),
+    }
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("daemon", |_| {
+            Ok(Section::Daemon(daemon::diagnose()))
+        }))
+        .job(
+            Job::blocking("storage", |inputs| {
+                Ok(Section::Storage(storage::diagnose(daemon(&inputs)?)))
+            })
+            .after(&["daemon"]),
+        )
+        .job(
+            Job::blocking("dangling", |inputs| {
+                Ok(Section::Dangling(dangling::diagnose(daemon(&inputs)?)))
+            })
+            .after(&["daemon"]),
+        )
+        .job(
+            Job::blocking("restarts", |inputs| {
+                Ok(Section::Restarts(restarts::diagnose(daemon(&inputs)?)))
+            })
+            .after(&["daemon"]),
+        )
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        daemon: Default::default(),
+        storage: Default::default(),
+        dangling: Default::default(),
+        restarts: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Daemon(d)) => result.daemon = d,
+            Some(Section::Storage(d)) => result.storage = d,
+            Some(Section::Dangling(d)) => result.dangling = d,
+            Some(Section::Restarts(d)) => result.restarts = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "daemon" => &mut result.daemon.warnings,
+                    "storage" => &mut result.storage.warnings,
+                    "dangling" => &mut result.dangling.warnings,
+                    _ => &mut result.restarts.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
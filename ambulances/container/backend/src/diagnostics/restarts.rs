@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Containers caught in a restart loop
+//!
+//! A restart policy brings a crashed container back, and back again, with
+//! no one noticing. A container that is `restarting` right now, or one
+//! with a policy that has already restarted it several times, is reported
+//! with the last lines it wrote and whether the kernel killed it for
+//! memory.
+
+use crate::diagnostics::daemon::DaemonDiagnostics;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+/// Restarts under a policy from which a container counts as looping
+const LOOP_RESTARTS: u64 = 5;
+
+/// Lines of a looping container's log kept for the report
+const LOG_LINES: usize = 5;
+
+const INSPECT_FORMAT: &str = "{{.Name}}\t{{.RestartCount}}\t{{.State.Status}}\t{{.State.ExitCode}}\t{{.HostConfig.RestartPolicy.Name}}\t{{.State.OOMKilled}}";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Container {
+    pub engine: &'static str,
+    pub name: String,
+    pub restart_count: u64,
+    /// `running`, `restarting`, `exited`...
+    pub status: String,
+    pub exit_code: i64,
+    /// `no`, `always`, `on-failure` or `unless-stopped`
+    pub restart_policy: String,
+    pub oom_killed: bool,
+    pub last_logs: Vec<String>,
+}
+
+impl Container {
+    pub fn looping(&self) -> bool {
+        self.status == "restarting"
+            || (self.restart_count >= LOOP_RESTARTS
+                && !matches!(self.restart_policy.as_str(), "" | "no"))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestartDiagnostics {
+    /// Every container, looping or not
+    pub containers: usize,
+    pub looping: Vec<Container>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn parse(engine: &'static str, line: &str) -> Option<Container> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < 6 {
+        return None;
+    }
+    Some(Container {
+        engine,
+        name: fields[0].trim_start_matches('/').to_string(),
+        restart_count: fields[1].parse().unwrap_or(0),
+        status: fields[2].to_string(),
+        exit_code: fields[3].parse().unwrap_or(0),
+        restart_policy: fields[4].to_string(),
+        oom_killed: fields[5] == "true",
+        last_logs: Vec::new(),
+    })
+}
+
+pub fn diagnose(daemon: &DaemonDiagnostics) -> RestartDiagnostics {
+    let mut diag = RestartDiagnostics::default();
+
+    for s in daemon.engines.iter().filter(|s| s.reachable()) {
+        let ids = match s.engine.ids(&["ps", "--all", "--quiet"]) {
+            Ok(ids) => ids,
+            Err(e) => {
+                diag.warnings
+                    .push(format!("Cannot list {} containers: {}", s.name, e));
+                continue;
+            }
+        };
+        diag.containers += ids.len();
+        if ids.is_empty() {
+            continue;
+        }
+        let mut args = vec!["inspect", "--format", INSPECT_FORMAT];
+        args.extend(ids.iter().map(String::as_str));
+        let output = match s.engine.cli(&args) {
+            Ok(output) => output,
+            Err(e) => {
+                diag.warnings
+                    .push(format!("Cannot inspect {} containers: {}", s.name, e));
+                continue;
+            }
+        };
+        for mut container in output.lines().filter_map(|l| parse(s.name, l)) {
+            if container.looping() {
+                container.last_logs = s.engine.logs(&container.name, LOG_LINES);
+                diag.looping.push(container);
+            }
+        }
+    }
+
+    for c in &diag.looping {
+        diag.warnings.push(format!(
+            "{} container {} has restarted {} time(s){}",
+            c.engine,
+            c.name,
+            c.restart_count,
+            if c.oom_killed {
+                ", last killed for running out of memory".to_string()
+            } else {
+                format!(", last exit code {}", c.exit_code)
+            }
+        ));
+        diag.recommendations.push(format!(
+            "Read why it stops: {} logs {}; to stop the loop while you look: {} update --restart=no {}",
+            c.engine, c.name, c.engine, c.name
+        ));
+    }
+    if diag.looping.iter().any(|c| c.oom_killed) {
+        diag.recommendations.push(
+            "Raise the memory limit (--memory) of containers killed for memory, or find what grows"
+                .to_string(),
+        );
+    }
+    diag
+}
+
+impl RestartDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Restart Loops ===");
+        println!(
+            "{} {} container(s), {} looping",
+            mark(self.looping.is_empty()),
+            self.containers,
+            self.looping.len()
+        );
+        for c in &self.looping {
+            println!(
+                "  {} {}: {}, {} restart(s), policy {}",
+                c.engine, c.name, c.status, c.restart_count, c.restart_policy
+            );
+            if verbose {
+                for line in &c.last_logs {
+                    println!("    | {}", line);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Storage driver and disk usage
+//!
+//! `system df` splits an engine's usage into images, containers, local
+//! volumes and build cache, each with what could be reclaimed. The
+//! filesystem under the engine's root directory is what fills when they
+//! grow, and the storage driver decides how fast: `vfs` copies every
+//! layer in full.
+
+use crate::diagnostics::daemon::DaemonDiagnostics;
+use crate::engines::{human_bytes, parse_size};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+
+/// Filesystem use, in percent, from which the root filesystem is reported
+const FULL_PERCENT: u64 = 90;
+
+/// Reclaimable space from which cleanup is suggested
+const RECLAIMABLE_BYTES: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Usage {
+    /// `Images`, `Containers`, `Local Volumes` or `Build Cache`
+    pub kind: String,
+    pub bytes: u64,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineStorage {
+    pub name: &'static str,
+    pub driver: String,
+    pub root_dir: String,
+    pub filesystem_bytes: Option<u64>,
+    pub filesystem_used_percent: Option<u64>,
+    pub usage: Vec<Usage>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageDiagnostics {
+    pub engines: Vec<EngineStorage>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Size and percentage used of the filesystem holding `path`
+// statvfs field widths differ between targets
+#[allow(clippy::unnecessary_cast)]
+fn filesystem(path: &str) -> Option<(u64, u64)> {
+    let path = CString::new(path).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    let used = (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64);
+    let usable = used + stat.f_bavail as u64;
+    if usable == 0 {
+        return None;
+    }
+    Some((
+        stat.f_blocks as u64 * stat.f_frsize as u64,
+        used * 100 / usable,
+    ))
+}
+
+fn usage(daemon: &DaemonDiagnostics) -> Vec<EngineStorage> {
+    daemon
+        .engines
+        .iter()
+        .filter_map(|s| {
+            let info = s.info.as_ref()?;
+            let fs = filesystem(&info.root_dir);
+            let mut storage = EngineStorage {
+                name: s.name,
+                driver: info.storage_driver.clone(),
+                root_dir: info.root_dir.clone(),
+                filesystem_bytes: fs.map(|(size, _)| size),
+                filesystem_used_percent: fs.map(|(_, percent)| percent),
+                usage: Vec::new(),
+                error: None,
+            };
+            match s.engine.cli(&[
+                "system",
+                "df",
+                "--format",
+                "{{.Type}}\t{{.Size}}\t{{.Reclaimable}}",
+            ]) {
+                Ok(df) => {
+                    storage.usage = df
+                        .lines()
+                        .filter_map(|line| {
+                            let fields: Vec<&str> = line.split('\t').collect();
+                            let size = |i: usize| {
+                                fields
+                                    .get(i)
+                                    // Reclaimable reads "1.2GB (40%)"
+                                    .and_then(|f| parse_size(f.split(" (").next().unwrap_or(f)))
+                                    .unwrap_or(0)
+                            };
+                            Some(Usage {
+                                kind: fields.first()?.to_string(),
+                                bytes: size(1),
+                                reclaimable_bytes: size(2),
+                            })
+                        })
+                        .collect()
+                }
+                Err(e) => storage.error = Some(e),
+            }
+            Some(storage)
+        })
+        .collect()
+}
+
+pub fn diagnose(daemon: &DaemonDiagnostics) -> StorageDiagnostics {
+    let mut diag = StorageDiagnostics {
+        engines: usage(daemon),
+        ..StorageDiagnostics::default()
+    };
+
+    for engine in &diag.engines {
+        if let Some(percent) = engine
+            .filesystem_used_percent
+            .filter(|p| *p >= FULL_PERCENT)
+        {
+            diag.warnings.push(format!(
+                "The filesystem holding {}'s {} is {}% full",
+                engine.name, engine.root_dir, percent
+            ));
+        }
+        match engine.driver.as_str() {
+            "vfs" => {
+                diag.warnings.push(format!(
+                    "{} uses the vfs storage driver, which copies every layer in full",
+                    engine.name
+                ));
+                diag.recommendations.push(if engine.name == "docker" {
+                    "Switch to overlay2 (\"storage-driver\" in /etc/docker/daemon.json); existing images must be pulled again".to_string()
+                } else {
+                    "Switch to overlay (driver in /etc/containers/storage.conf); existing images must be pulled again".to_string()
+                });
+            }
+            "devicemapper" => {
+                diag.warnings.push(format!(
+                    "{} uses devicemapper, which is deprecated and gone from Docker 25",
+                    engine.name
+                ));
+                diag.recommendations.push(
+                    "Move to overlay2 before upgrading: save what you need with docker save, then change storage-driver".to_string(),
+                );
+            }
+            _ => {}
+        }
+        let reclaimable: u64 = engine.usage.iter().map(|u| u.reclaimable_bytes).sum();
+        if reclaimable >= RECLAIMABLE_BYTES {
+            diag.warnings.push(format!(
+                "{} could reclaim {} of unused data",
+                engine.name,
+                human_bytes(reclaimable)
+            ));
+            diag.recommendations.push(format!(
+                "Remove dangling images and volumes: container-ambulance repair cleanup; `{} system prune` goes further",
+                engine.name
+            ));
+        }
+        if let Some(e) = &engine.error {
+            diag.warnings
+                .push(format!("{} system df fails: {}", engine.name, e));
+        }
+    }
+    diag
+}
+
+impl StorageDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Storage ===");
+        if self.engines.is_empty() {
+            println!("- No reachable engine");
+        }
+        for engine in &self.engines {
+            let total: u64 = engine.usage.iter().map(|u| u.bytes).sum();
+            println!(
+                "{} {}: {} in {} ({}{})",
+                mark(
+                    engine
+                        .filesystem_used_percent
+                        .map_or(true, |p| p < FULL_PERCENT)
+                ),
+                engine.name,
+                human_bytes(total),
+                engine.root_dir,
+                engine.driver,
+                engine
+                    .filesystem_used_percent
+                    .map(|p| format!(", filesystem {}% full", p))
+                    .unwrap_or_default()
+            );
+            if verbose {
+                for u in &engine.usage {
+                    println!(
+                        "    {}: {} ({} reclaimable)",
+                        u.kind,
+                        human_bytes(u.bytes),
+                        human_bytes(u.reclaimable_bytes)
+                    );
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Container engines and how to reach them
+//!
+//! Docker's CLI talks to dockerd over a Unix socket, so the socket is
+//! pinged directly first: that tells a daemon that is not running from
+//! one that refuses us or has hung. Podman needs no daemon; its socket,
+//! when `podman.socket` is enabled, only serves the Docker-compatible
+//! API. Rootless Podman keeps everything per user; run as that user to
+//! see it.
+
+use crate::system;
+use serde::Serialize;
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::process::Command;
+use std::time::Duration;
+
+/// How long a daemon may take to answer a ping
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub struct Engine {
+    pub name: &'static str,
+    /// Default API socket of a rootful engine
+    socket: &'static str,
+    pub service: &'static str,
+    pub socket_unit: &'static str,
+    /// Whether the CLI works without the service running
+    pub daemonless: bool,
+    /// `info --format` template giving version, storage driver and root
+    info_format: &'static str,
+}
+
+pub const ENGINES: &[Engine] = &[
+    Engine {
+        name: "docker",
+        socket: "/var/run/docker.sock",
+        service: "docker.service",
+        socket_unit: "docker.socket",
+        daemonless: false,
+        info_format: "{{.ServerVersion}}\t{{.Driver}}\t{{.DockerRootDir}}",
+    },
+    Engine {
+        name: "podman",
+        socket: "/run/podman/podman.sock",
+        service: "podman.service",
+        socket_unit: "podman.socket",
+        daemonless: true,
+        info_format: "{{.Version.Version}}\t{{.Store.GraphDriverName}}\t{{.Store.GraphRoot}}",
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Info {
+    pub version: String,
+    /// e.g. `overlay2`, `overlay` or `vfs`
+    pub storage_driver: String,
+    /// Where images, containers and volumes live
+    pub root_dir: String,
+}
+
+/// Size as the engines print it: decimal units, e.g. `1.234GB` or `512kB`
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = text.split_at(split);
+    let scale = match unit.to_ascii_uppercase().as_str() {
+        "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((number.trim().parse::<f64>().ok()? * scale) as u64)
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+impl Engine {
+    pub fn installed(&self) -> bool {
+        system::has(self.name)
+    }
+
+    /// The API socket, honouring a `unix://` `DOCKER_HOST` for Docker
+    pub fn socket(&self) -> String {
+        if self.name == "docker" {
+            if let Some(path) = std::env::var("DOCKER_HOST")
+                .ok()
+                .and_then(|h| h.strip_prefix("unix://").map(str::to_string))
+            {
+                return path;
+            }
+        }
+        self.socket.to_string()
+    }
+
+    /// Ask the API socket for `/_ping`, saying why it does not answer
+    pub fn ping(&self) -> Result<(), String> {
+        let socket = self.socket();
+        let mut stream = UnixStream::connect(&socket).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => format!("no socket at {}", socket),
+            ErrorKind::ConnectionRefused => format!("nothing is listening on {}", socket),
+            ErrorKind::PermissionDenied => format!("permission denied on {}", socket),
+            _ => format!("{}: {}", socket, e),
+        })?;
+        stream
+            .set_read_timeout(Some(PING_TIMEOUT))
+            .map_err(|e| e.to_string())?;
+        stream
+            .write_all(b"GET /_ping HTTP/1.0\r\nHost: localhost\r\n\r\n")
+            .map_err(|e| format!("{}: {}", socket, e))?;
+        let mut answer = String::new();
+        match stream.read_to_string(&mut answer) {
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err(format!(
+                    "no answer on {} within {}s",
+                    socket,
+                    PING_TIMEOUT.as_secs()
+                ))
+            }
+            Err(e) => return Err(format!("{}: {}", socket, e)),
+            Ok(_) => {}
+        }
+        let status = answer.lines().next().unwrap_or_default();
+        if status.split_whitespace().nth(1) == Some("200") {
+            Ok(())
+        } else {
+            Err(format!("{} answered '{}'", socket, status))
+        }
+    }
+
+    /// Run the engine's CLI, returning its stdout
+    pub fn cli(&self, args: &[&str]) -> Result<String, String> {
+        system::run(self.name, args)
+    }
+
+    pub fn info(&self) -> Result<Info, String> {
+        let output = self.cli(&["info", "--format", self.info_format])?;
+        let mut fields = output.trim().split('\t').map(str::to_string);
+        Ok(Info {
+            version: fields.next().unwrap_or_default(),
+            storage_driver: fields.next().unwrap_or_default(),
+            root_dir: fields.next().unwrap_or_default(),
+        })
+    }
+
+    /// IDs printed one per line by a `-q` listing
+    pub fn ids(&self, args: &[&str]) -> Result<Vec<String>, String> {
+        Ok(self
+            .cli(args)?
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Up to the last `lines` lines a container wrote, its stdout lines
+    /// ahead of its stderr ones
+    pub fn logs(&self, container: &str, lines: usize) -> Vec<String> {
+        let tail = lines.to_string();
+        let Ok(output) = Command::new(self.name)
+            .args(["logs", "--tail", &tail, container])
+            .output()
+        else {
+            return Vec::new();
+        };
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        let all: Vec<String> = text.lines().map(str::to_string).collect();
+        all[all.len().saturating_sub(lines)..].to_vec()
+    }
+}
+
+/// The engines whose CLI is installed
+pub fn installed() -> impl Iterator<Item = &'static Engine> {
+    ENGINES.iter().filter(|e| e.installed())
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Remove dangling images and anonymous volumes
+//!
+//! Images go through `image prune` without `--all`, which only touches
+//! untagged ones. Volumes are removed by ID rather than with `volume
+//! prune`, whose reach changed between Docker releases: only anonymous
+//! volumes found unused by the diagnosis are removed, and a volume a
+//! container took in the meantime is refused by the engine.
+
+use crate::diagnostics::{daemon, dangling};
+use crate::engines::human_bytes;
+use crate::report::RepairOutcome;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let daemon = daemon::diagnose();
+    let found = dangling::scan(&daemon);
+    let mut needed = false;
+
+    for (status, engine) in daemon.engines.iter().filter(|s| s.reachable()).zip(&found) {
+        if let Some(e) = &engine.error {
+            result.errors.push(format!(
+                "Cannot list {} images or volumes: {}",
+                engine.name, e
+            ));
+            continue;
+        }
+        if !engine.images.is_empty() {
+            needed = true;
+            if confirm(&format!(
+                "Remove {} dangling {} image(s) ({})?",
+                engine.images.len(),
+                engine.name,
+                human_bytes(engine.images_bytes)
+            )) {
+                match status.engine.cli(&["image", "prune", "--force"]) {
+                    Ok(_) => result.actions.push(format!(
+                        "Removed {} dangling {} image(s)",
+                        engine.images.len(),
+                        engine.name
+                    )),
+                    Err(e) => result
+                        .errors
+                        .push(format!("{} image prune: {}", engine.name, e)),
+                }
+            } else {
+                result.errors.push(format!(
+                    "{} image removal not confirmed, left as is (rerun with --yes to confirm)",
+                    engine.name
+                ));
+            }
+        }
+        if !engine.anonymous_volumes.is_empty() {
+            needed = true;
+            if confirm(&format!(
+                "Remove {} anonymous {} volume(s) no container uses?",
+                engine.anonymous_volumes.len(),
+                engine.name
+            )) {
+                let mut args = vec!["volume", "rm"];
+                args.extend(engine.anonymous_volumes.iter().map(String::as_str));
+                match status.engine.cli(&args) {
+                    Ok(_) => result.actions.push(format!(
+                        "Removed {} anonymous {} volume(s)",
+                        engine.anonymous_volumes.len(),
+                        engine.name
+                    )),
+                    Err(e) => result
+                        .errors
+                        .push(format!("{} volume rm: {}", engine.name, e)),
+                }
+            } else {
+                result.errors.push(format!(
+                    "{} volume removal not confirmed, left as is (rerun with --yes to confirm)",
+                    engine.name
+                ));
+            }
+        }
+        if !engine.named_volumes.is_empty() {
+            result.actions.push(format!(
+                "Left {} named {} volume(s) for review: {}",
+                engine.named_volumes.len(),
+                engine.name,
+                engine.named_volumes.join(", ")
+            ));
+        }
+    }
+
+    if !needed && result.errors.is_empty() {
+        let mut outcome =
+            RepairOutcome::not_needed("No dangling image or anonymous volume, no repair needed");
+        outcome.actions.extend(result.actions);
+        return outcome;
+    }
+    result.success = result.errors.is_empty();
+    result
+}
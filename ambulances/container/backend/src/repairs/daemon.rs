@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Restart unreachable or failed daemons
+//!
+//! Failed units have their failed state cleared first, since a unit
+//! that hit its start limit refuses to start again until it is. Docker
+//! is then waited on until its socket answers; dockerd can take a few
+//! seconds to replay its containers.
+
+use crate::diagnostics::daemon::{self, unit_state};
+use crate::report::RepairOutcome;
+use std::thread::sleep;
+use std::time::Duration;
+use systemd_shim::bus::Bus;
+
+/// How many times, a second apart, a restarted daemon's socket is pinged
+const PING_ATTEMPTS: u32 = 10;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let diag = daemon::diagnose();
+    let stuck: Vec<_> = diag
+        .engines
+        .iter()
+        .filter(|s| {
+            (s.socket_error.is_some() && !s.engine.daemonless)
+                || s.service_state.as_deref() == Some("failed")
+                || s.socket_unit_state.as_deref() == Some("failed")
+        })
+        .collect();
+    if stuck.is_empty() {
+        return RepairOutcome::not_needed("Every daemon answers, no repair needed");
+    }
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            result.errors.push(format!("system bus: {}", e));
+            return result;
+        }
+    };
+
+    for s in stuck {
+        let engine = s.engine;
+        let service_failed = s.service_state.as_deref() == Some("failed");
+        let socket_failed = s.socket_unit_state.as_deref() == Some("failed");
+        let unreachable = s.socket_error.is_some() && !engine.daemonless;
+        // Podman has no daemon to restart; its service only runs while
+        // the socket hands it a connection
+        let unit = if service_failed || (unreachable && s.service_state.is_some()) {
+            engine.service
+        } else if s.socket_unit_state.is_some() {
+            engine.socket_unit
+        } else {
+            result.errors.push(format!(
+                "{} is unreachable and no {} unit is installed; start it the way it was set up",
+                s.name, engine.service
+            ));
+            continue;
+        };
+        if !confirm(&format!("Restart {}?", unit)) {
+            result.errors.push(format!(
+                "{} restart not confirmed, left as is (rerun with --yes to confirm)",
+                unit
+            ));
+            continue;
+        }
+        for (failed_unit, failed) in [
+            (engine.service, service_failed),
+            (engine.socket_unit, socket_failed),
+        ] {
+            if failed {
+                match bus.reset_failed_unit(failed_unit) {
+                    Ok(()) => result
+                        .actions
+                        .push(format!("Cleared the failed state of {}", failed_unit)),
+                    Err(e) => result.errors.push(format!("{}: {}", failed_unit, e)),
+                }
+            }
+        }
+        if let Err(e) = bus.restart_unit(unit, "replace") {
+            result.errors.push(format!("{}: {}", unit, e));
+            continue;
+        }
+
+        if engine.daemonless {
+            match unit_state(Some(&bus), unit).as_deref() {
+                Some("active") => result.actions.push(format!("Restarted {}", unit)),
+                state => result.errors.push(format!(
+                    "{} is {} after the restart; see journalctl -u {}",
+                    unit,
+                    state.unwrap_or("gone"),
+                    unit
+                )),
+            }
+            continue;
+        }
+        let mut answer = engine.ping();
+        for _ in 1..PING_ATTEMPTS {
+            if answer.is_ok() {
+                break;
+            }
+            sleep(Duration::from_secs(1));
+            answer = engine.ping();
+        }
+        match answer {
+            Ok(()) => result
+                .actions
+                .push(format!("Restarted {}; {} answers", unit, s.socket)),
+            Err(e) => result.errors.push(format!(
+                "{} restarted but {}; see journalctl -u {}",
+                unit, e, engine.service
+            )),
+        }
+    }
+
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Container repairs, one module per target
+//!
+//! `all` restarts daemons before cleaning up, since nothing can be
+//! removed through an engine that does not answer.
+
+pub mod cleanup;
+pub mod daemon;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["daemon", "cleanup", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change, naming the unit, engine and what goes.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        daemon_repair: if selected("daemon") {
+            daemon::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        cleanup_repair: if selected("cleanup") {
+            cleanup::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    daemon::DaemonDiagnostics, dangling::DanglingDiagnostics, restarts::RestartDiagnostics,
+    storage::StorageDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "container-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub daemon: DaemonDiagnostics,
+    pub storage: StorageDiagnostics,
+    pub dangling: DanglingDiagnostics,
+    pub restarts: RestartDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.daemon.warnings,
+            &self.storage.warnings,
+            &self.dangling.warnings,
+            &self.restarts.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub daemon_repair: RepairOutcome,
+    pub cleanup_repair: RepairOutcome,
+}
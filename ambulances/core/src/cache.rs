@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Results of expensive probes, kept for a while
+//!
+//! Asking every drive for its SMART log or a server for the public
+//! address takes seconds, and the answer rarely changes between two runs
+//! a minute apart. A probe's result is kept as JSON under
+//! `$XDG_CACHE_HOME/system-tools/<tool>/<key>.json` (or [`DIR_VAR`]'s
+//! directory) with the time it was stored, and used again until its TTL
+//! runs out. Only successes are kept: a probe that failed is tried again
+//! next time.
+//!
+//! The cache is an optimisation and never an error: an entry that cannot
+//! be read is a miss, and one that cannot be written is not kept. A
+//! [`Cache::refresh`] cache stores without reading, which is what a deep
+//! scan uses.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable naming the cache directory, for tests and
+/// services without a home
+pub const DIR_VAR: &str = "SYSTEM_TOOLS_CACHE_DIR";
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    /// Seconds since the epoch
+    stored: u64,
+    value: T,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `key` as a file name: anything but letters, digits, `.`, `_` and `-`
+/// becomes `_`
+fn file_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.json", name.trim_start_matches('.'))
+}
+
+/// The cache directory of all tools
+pub fn base_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DIR_VAR).filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".cache")))?;
+    Some(base.join("system-tools"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Cache {
+    /// `None` when there is nowhere to keep anything
+    dir: Option<PathBuf>,
+    refresh: bool,
+}
+
+impl Cache {
+    /// `tool`'s cache in the user's cache directory
+    pub fn open(tool: &str) -> Cache {
+        Cache {
+            dir: base_dir().map(|d| d.join(tool)),
+            refresh: false,
+        }
+    }
+
+    /// A cache kept in `dir`
+    pub fn at(dir: impl Into<PathBuf>) -> Cache {
+        Cache {
+            dir: Some(dir.into()),
+            refresh: false,
+        }
+    }
+
+    /// When `refresh`, every lookup misses, so probes run again and
+    /// their new results replace the old
+    pub fn refresh(mut self, refresh: bool) -> Cache {
+        self.refresh = refresh;
+        self
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|d| d.join(file_name(key)))
+    }
+
+    /// The value stored under `key` less than `ttl` ago
+    pub fn get<T: DeserializeOwned>(&self, key: &str, ttl: Duration) -> Option<T> {
+        if self.refresh {
+            return None;
+        }
+        let text = std::fs::read_to_string(self.path(key)?).ok()?;
+        let entry: Entry<T> = serde_json::from_str(&text).ok()?;
+        // An entry from the future (the clock went back) is not trusted
+        let age = now().checked_sub(entry.stored)?;
+        (age < ttl.as_secs()).then_some(entry.value)
+    }
+
+    /// Store `value` under `key`, replacing what was there
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+        let entry = Entry {
+            stored: now(),
+            value,
+        };
+        let Ok(text) = serde_json::to_string(&entry) else {
+            return;
+        };
+        // Written aside and renamed, so a concurrent run never reads half
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&tmp, text))
+            .and_then(|()| std::fs::rename(&tmp, &path));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+
+    /// The value under `key` if fresh, else `probe`'s, kept if it succeeded
+    pub fn get_or<T, E>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        probe: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        if let Some(value) = self.get(key, ttl) {
+            return Ok(value);
+        }
+        let value = probe()?;
+        self.put(key, &value);
+        Ok(value)
+    }
+
+    /// Forget everything this cache holds
+    pub fn clear(&self) -> std::io::Result<()> {
+        match &self.dir {
+            Some(dir) if dir.exists() => std::fs::remove_dir_all(dir),
+            _ => Ok(()),
+        }
+    }
+}
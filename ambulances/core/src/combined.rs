@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Several ambulances' reports from one machine, as one
+//!
+//! `doctor all` fills one in from every backend it finds; a backend that
+//! would not run, timed out or printed something unreadable is listed
+//! under `failures` rather than dropped. [`CombinedReport::correlate`]
+//! adds the root-cause chains that tie findings of different reports
+//! together, and [`CombinedReport::advise`] the known issues that match.
+
+use crate::correlate::{self, Chain};
+use crate::error::Error;
+use crate::knowledge::{Advice, Facts, KnowledgeBase};
+use crate::schema::{DiagnosticReport, Finding, Severity, Timing, SCHEMA_VERSION};
+use serde::{Deserialize, Serialize};
+
+/// A backend that produced no report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Failure {
+    pub tool: String,
+    pub error: String,
+    /// The error typed, from the backend's own envelope when it printed one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Error>,
+}
+
+impl Failure {
+    pub fn new(tool: &str, error: Error) -> Failure {
+        Failure {
+            tool: tool.to_string(),
+            error: error.to_string(),
+            details: Some(error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CombinedReport {
+    pub schema: u32,
+    pub host: String,
+    /// Seconds since the epoch
+    pub generated: u64,
+    pub reports: Vec<DiagnosticReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<Failure>,
+    /// Root causes and what they explain, once [`CombinedReport::correlate`] ran
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chains: Vec<Chain>,
+    /// Known issues that match, once [`CombinedReport::advise`] ran
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub advice: Vec<Advice>,
+    /// How long each backend took, by tool; each report has its own
+    /// checks' timings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl CombinedReport {
+    pub fn new(host: &str, generated: u64) -> CombinedReport {
+        CombinedReport {
+            schema: SCHEMA_VERSION,
+            host: host.to_string(),
+            generated,
+            reports: Vec::new(),
+            failures: Vec::new(),
+            chains: Vec::new(),
+            advice: Vec::new(),
+            timings: Vec::new(),
+        }
+    }
+
+    /// Link the reports' findings into root-cause chains
+    pub fn correlate(&mut self) {
+        self.chains = correlate::chains(&self.reports);
+    }
+
+    /// Match the reports against the known issues of `kb`
+    pub fn advise(&mut self, kb: &KnowledgeBase, facts: &Facts) {
+        self.advice = kb.advise(&self.reports, facts);
+    }
+
+    /// The finding with `id`, and the tool that reported it
+    pub fn finding(&self, id: &str) -> Option<(&str, &Finding)> {
+        self.reports.iter().find_map(|r| {
+            r.findings()
+                .find(|f| f.id == id)
+                .map(|f| (r.tool.as_str(), f))
+        })
+    }
+
+    /// The most severe finding across every report
+    pub fn worst(&self) -> Option<Severity> {
+        self.reports
+            .iter()
+            .filter_map(DiagnosticReport::worst)
+            .max()
+    }
+
+    /// Findings of `severity` across every report
+    pub fn count(&self, severity: Severity) -> usize {
+        self.reports
+            .iter()
+            .flat_map(DiagnosticReport::findings)
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+}
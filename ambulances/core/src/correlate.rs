@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Root-cause chains across ambulances
+//!
+//! Each ambulance reports on its own subsystem, so one fault shows up as
+//! symptoms in several reports: a full disk makes journald drop logs,
+//! and every ambulance that reads the journal then finds it unreadable.
+//! [`RULES`] records which findings explain which, by subsystem, check
+//! and wording. [`chains`] applies them to a set of reports and returns
+//! one [`Chain`] per root cause, each link saying why the cause explains
+//! the effect. The links are inferences: they say a finding is the
+//! likely cause, not that it was proven to be.
+
+use crate::schema::{short_name, DiagnosticReport, Finding};
+use serde::{Deserialize, Serialize};
+
+/// Where a finding was reported, and what it says
+#[derive(Debug, Clone, Copy)]
+pub struct Site {
+    /// Short tool name, such as `disk`; `*` for any
+    pub tool: &'static str,
+    /// Check ids; any check when empty
+    pub checks: &'static [&'static str],
+    /// Lowercase fragments the summary must contain one of; any summary when empty
+    pub text: &'static [&'static str],
+}
+
+impl Site {
+    fn matches(&self, tool: &str, check: &str, finding: &Finding) -> bool {
+        (self.tool == "*" || self.tool == tool)
+            && (self.checks.is_empty() || self.checks.contains(&check))
+            && (self.text.is_empty() || {
+                let summary = finding.summary.to_lowercase();
+                self.text.iter().any(|t| summary.contains(t))
+            })
+    }
+}
+
+/// Findings at any of `causes` explain findings at any of `effects`
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    pub causes: &'static [Site],
+    pub effects: &'static [Site],
+    pub because: &'static str,
+    /// Only where the two name the same disk, or one names none
+    pub same_device: bool,
+}
+
+const fn at(tool: &'static str, checks: &'static [&'static str]) -> Site {
+    Site {
+        tool,
+        checks,
+        text: &[],
+    }
+}
+
+const fn saying(
+    tool: &'static str,
+    checks: &'static [&'static str],
+    text: &'static [&'static str],
+) -> Site {
+    Site { tool, checks, text }
+}
+
+const DISK_FULL: Site = saying("disk", &["usage"], &["% full", "of its inodes"]);
+const READ_ONLY: Site = saying("disk", &["mounts"], &["read-only"]);
+const DISK_ERRORS: &[Site] = &[at("disk", &["io_errors", "smart"]), at("kernel", &["io"])];
+const WRONG_CLOCK: Site = saying(
+    "time-sync",
+    &["sync", "rtc"],
+    &[
+        "not synchronised",
+        "drifts freely",
+        "off the ntp server",
+        "s ahead of",
+        "s behind",
+        "local time",
+    ],
+);
+const NO_NETWORK: Site = at("network", &["dns", "routing", "connectivity"]);
+
+pub const RULES: &[Rule] = &[
+    // Space
+    Rule {
+        causes: &[at("storage-space", &[])],
+        effects: &[DISK_FULL],
+        because: "this is what fills the disk",
+        same_device: false,
+    },
+    Rule {
+        causes: &[at("service", &["flapping"])],
+        effects: &[at("storage-space", &["coredumps"])],
+        because: "every crash of a restarting service leaves a core dump",
+        same_device: false,
+    },
+    Rule {
+        causes: &[DISK_FULL],
+        effects: &[at("journal", &["usage"])],
+        because: "journald stops writing, and drops old history, once its filesystem is full",
+        same_device: false,
+    },
+    Rule {
+        causes: &[DISK_FULL],
+        effects: &[at("package", &["transactions", "packages"])],
+        because: "package transactions fail when they cannot unpack",
+        same_device: false,
+    },
+    Rule {
+        causes: &[DISK_FULL],
+        effects: &[at("container", &["daemon", "storage", "restarts"])],
+        because: "container engines cannot pull, create or start on a full filesystem",
+        same_device: false,
+    },
+    Rule {
+        causes: &[DISK_FULL],
+        effects: &[at("service", &["failed"])],
+        because: "services fail when they cannot write their state or logs",
+        same_device: false,
+    },
+    Rule {
+        causes: &[DISK_FULL, at("user-env", &["quota"])],
+        effects: &[at("user-env", &["session", "apps"])],
+        because: "sessions and apps break when they cannot write their config and cache",
+        same_device: false,
+    },
+    // Failing storage
+    Rule {
+        causes: DISK_ERRORS,
+        effects: &[READ_ONLY],
+        because: "the kernel remounts a filesystem read-only after I/O errors",
+        same_device: true,
+    },
+    Rule {
+        causes: DISK_ERRORS,
+        effects: &[saying("kernel", &["hangs"], &["hung task"])],
+        because: "tasks hang waiting on a failing disk",
+        same_device: true,
+    },
+    Rule {
+        causes: &[at("disk", &["smart"])],
+        effects: &[at("disk", &["filesystems"])],
+        because: "bad sectors corrupt the filesystem on them",
+        same_device: true,
+    },
+    Rule {
+        causes: &[READ_ONLY],
+        effects: &[
+            at("journal", &["usage"]),
+            at("package", &["transactions"]),
+            at("service", &["failed"]),
+        ],
+        because: "nothing can be written to a read-only filesystem",
+        same_device: false,
+    },
+    // Logs
+    Rule {
+        causes: &[at("journal", &["storage", "usage"])],
+        effects: &[saying("*", &[], &["read the journal", "open the journal"])],
+        because: "journald is not keeping the logs it reads",
+        same_device: false,
+    },
+    // Memory
+    Rule {
+        causes: &[at("memory", &["swap"])],
+        effects: &[at("memory", &["pressure", "oom"])],
+        because: "without room to swap, memory pressure goes straight to the OOM killer",
+        same_device: false,
+    },
+    Rule {
+        causes: &[at("memory", &["oom"])],
+        effects: &[
+            at("service", &["failed", "flapping"]),
+            at("container", &["restarts"]),
+        ],
+        because: "the OOM killer ends processes, which then fail or restart",
+        same_device: false,
+    },
+    // Drivers and firmware
+    Rule {
+        causes: &[saying(
+            "kernel",
+            &["firmware"],
+            &[
+                "(i915)",
+                "(xe)",
+                "(amdgpu)",
+                "(radeon)",
+                "(nouveau)",
+                "(nvidia)",
+            ],
+        )],
+        effects: &[at("gpu", &["firmware", "drivers"])],
+        because: "the GPU driver could not load its firmware",
+        same_device: false,
+    },
+    Rule {
+        causes: &[saying(
+            "kernel",
+            &["firmware"],
+            &["(snd", "(sof", "(cs35l", "(tas2", "(avs"],
+        )],
+        effects: &[at("audio", &["firmware"])],
+        because: "the sound driver could not load its firmware",
+        same_device: false,
+    },
+    Rule {
+        causes: &[at("gpu", &["drivers"])],
+        effects: &[at("display", &[]), at("gpu", &["apis", "sessions"])],
+        because: "without a working GPU driver the display falls back or fails",
+        same_device: false,
+    },
+    // Heat
+    Rule {
+        causes: &[at("thermal", &["fans"])],
+        effects: &[at("thermal", &["sensors", "throttling"])],
+        because: "a failed fan lets the machine overheat",
+        same_device: false,
+    },
+    Rule {
+        causes: &[at("thermal", &["sensors"])],
+        effects: &[at("thermal", &["throttling", "performance"])],
+        because: "the CPU slows itself down when hot",
+        same_device: false,
+    },
+    // Clock
+    Rule {
+        causes: &[WRONG_CLOCK],
+        effects: &[saying(
+            "certificate",
+            &["services"],
+            &["expired", "expires in"],
+        )],
+        because: "certificates look expired or not yet valid on a wrong clock",
+        same_device: false,
+    },
+    Rule {
+        causes: &[WRONG_CLOCK],
+        effects: &[at("scheduled-task", &["timers"])],
+        because: "timers run at the wrong time, or are missed, on a wrong clock",
+        same_device: false,
+    },
+    // Network
+    Rule {
+        causes: &[at("network", &["interfaces"])],
+        effects: &[NO_NETWORK],
+        because: "no interface carries the traffic",
+        same_device: false,
+    },
+    Rule {
+        causes: &[at("network", &["dns"])],
+        effects: &[at("time-sync", &["ports"])],
+        because: "NTP servers are found by name",
+        same_device: false,
+    },
+    Rule {
+        causes: &[NO_NETWORK],
+        effects: &[
+            at("package", &["metadata"]),
+            at("firmware-update", &["updates"]),
+            at("time-sync", &["sync"]),
+        ],
+        because: "the servers it needs cannot be reached",
+        same_device: false,
+    },
+];
+
+/// One finding explaining another, by finding id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    pub cause: String,
+    pub effect: String,
+    pub because: String,
+}
+
+/// A root cause and everything it explains, breadth first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chain {
+    pub root: String,
+    pub links: Vec<Link>,
+}
+
+impl Chain {
+    /// Links from `cause` to the findings it explains directly
+    pub fn explained_by<'a>(&'a self, cause: &'a str) -> impl Iterator<Item = &'a Link> {
+        self.links.iter().filter(move |l| l.cause == cause)
+    }
+}
+
+/// Every finding of `reports`, with the short tool name and check it came from
+fn findings(reports: &[DiagnosticReport]) -> Vec<(&str, &str, &Finding)> {
+    reports
+        .iter()
+        .flat_map(|r| {
+            let tool = short_name(&r.tool);
+            r.checks
+                .iter()
+                .flat_map(move |c| c.findings.iter().map(move |f| (tool, c.id.as_str(), f)))
+        })
+        .collect()
+}
+
+/// Disks a summary names: `sda` for `/dev/sda2` or `jbd2/sda1-8`, `nvme0n1` for `nvme0n1p3`
+fn devices(summary: &str) -> Vec<&str> {
+    summary
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter_map(|word| {
+            if word.starts_with("nvme") || word.starts_with("mmcblk") {
+                // Partitions are `p<N>` after the disk
+                let disk = word
+                    .rfind('p')
+                    .filter(|&i| i > 4)
+                    .map_or(word, |i| &word[..i]);
+                return Some(disk);
+            }
+            ["xvd", "sd", "vd"].iter().find_map(|prefix| {
+                let letters = word
+                    .strip_prefix(prefix)?
+                    .trim_end_matches(|c: char| c.is_ascii_digit());
+                (!letters.is_empty()
+                    && letters.len() <= 2
+                    && letters.chars().all(|c| c.is_ascii_lowercase()))
+                .then(|| &word[..prefix.len() + letters.len()])
+            })
+        })
+        .collect()
+}
+
+/// Every link between findings of `reports` that [`RULES`] makes
+pub fn links(reports: &[DiagnosticReport]) -> Vec<Link> {
+    let findings = findings(reports);
+    let at = |sites: &[Site]| -> Vec<&Finding> {
+        findings
+            .iter()
+            .filter(|(tool, check, f)| sites.iter().any(|s| s.matches(tool, check, f)))
+            .map(|(_, _, f)| *f)
+            .collect()
+    };
+    let mut links: Vec<Link> = Vec::new();
+    for rule in RULES {
+        let effects = at(rule.effects);
+        for cause in at(rule.causes) {
+            for effect in &effects {
+                let known = links
+                    .iter()
+                    .any(|l| l.cause == cause.id && l.effect == effect.id);
+                let unrelated = rule.same_device && {
+                    let (a, b) = (devices(&cause.summary), devices(&effect.summary));
+                    !a.is_empty() && !b.is_empty() && !a.iter().any(|d| b.contains(d))
+                };
+                if cause.id != effect.id && !known && !unrelated {
+                    links.push(Link {
+                        cause: cause.id.clone(),
+                        effect: effect.id.clone(),
+                        because: rule.because.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    links
+}
+
+/// The links reachable from `root`, breadth first, each finding explained once
+fn chain(root: &str, links: &[Link]) -> Chain {
+    let mut reached: Vec<&str> = vec![root];
+    let mut chain = Chain {
+        root: root.to_string(),
+        links: Vec::new(),
+    };
+    let mut next = 0;
+    while next < reached.len() {
+        let cause = reached[next];
+        next += 1;
+        for link in links.iter().filter(|l| l.cause == cause) {
+            if !reached.contains(&link.effect.as_str()) {
+                reached.push(&link.effect);
+                chain.links.push(link.clone());
+            }
+        }
+    }
+    chain
+}
+
+/// One chain per root cause among the findings of `reports`, most severe root first
+///
+/// A root is a finding that explains others and is explained by none.
+/// Rules may explain each other in a circle, as a flapping service
+/// filling the disk with core dumps fails again for the full disk; the
+/// most severe finding of a circle no root reaches is taken as its root.
+pub fn chains(reports: &[DiagnosticReport]) -> Vec<Chain> {
+    let findings = findings(reports);
+    let severity = |id: &str| {
+        findings
+            .iter()
+            .find(|(_, _, f)| f.id == id)
+            .map(|(_, _, f)| f.severity)
+    };
+    let links = links(reports);
+    let mut causes: Vec<&str> = Vec::new();
+    for link in &links {
+        if !causes.contains(&link.cause.as_str()) {
+            causes.push(&link.cause);
+        }
+    }
+
+    let mut chains: Vec<Chain> = causes
+        .iter()
+        .filter(|c| !links.iter().any(|l| l.effect == **c))
+        .map(|root| chain(root, &links))
+        .collect();
+    loop {
+        let reached = |id: &str| {
+            chains
+                .iter()
+                .any(|c| c.root == id || c.links.iter().any(|l| l.effect == id))
+        };
+        let mut left = causes.iter().filter(|c| !reached(c));
+        let Some(first) = left.next() else {
+            break;
+        };
+        let root = left.fold(*first, |best, c| {
+            if severity(c) > severity(best) {
+                c
+            } else {
+                best
+            }
+        });
+        chains.push(chain(root, &links));
+    }
+    chains.sort_by_key(|c| std::cmp::Reverse(severity(&c.root)));
+    chains
+}
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Errors every binary reports the same way
+//!
+//! An error has a stable `code` such as `usage.unknown-target`, a
+//! [`Category`] that decides the exit code, a message and, where there
+//! is one, a hint at what to do about it. With `--json` it is printed on
+//! stdout as `{version, tool, error}`, the report model's envelope, so a
+//! caller reads one stream whether the command worked or not; without,
+//! it goes to stderr as `Error: <message>` and the hint.
+//!
+//! | Exit | Meaning |
+//! |------|---------|
+//! | 0 | Worked, nothing to report |
+//! | 1 | Worked, and found problems |
+//! | 2 | Usage: unknown command, target or option |
+//! | 3 | Needs privileges it does not have, or must not have |
+//! | 4 | Something it needs is missing: a service, a bus, a tool |
+//! | 5 | Ran and failed: a repair, a backend |
+//! | 6 | Timed out |
+//! | 7 | Internal: a bug |
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::process::ExitCode;
+
+/// The exit codes of the table above
+pub mod exit {
+    pub const OK: u8 = 0;
+    pub const FINDINGS: u8 = 1;
+    pub const USAGE: u8 = 2;
+    pub const PRIVILEGE: u8 = 3;
+    pub const UNAVAILABLE: u8 = 4;
+    pub const FAILED: u8 = 5;
+    pub const TIMEOUT: u8 = 6;
+    pub const INTERNAL: u8 = 7;
+
+    /// How a check that worked ends: `FINDINGS` when it found problems
+    pub fn findings(found: bool) -> super::ExitCode {
+        super::ExitCode::from(if found { FINDINGS } else { OK })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Usage,
+    Privilege,
+    Unavailable,
+    Failed,
+    Timeout,
+    Internal,
+}
+
+impl Category {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Category::Usage => exit::USAGE,
+            Category::Privilege => exit::PRIVILEGE,
+            Category::Unavailable => exit::UNAVAILABLE,
+            Category::Failed => exit::FAILED,
+            Category::Timeout => exit::TIMEOUT,
+            Category::Internal => exit::INTERNAL,
+        }
+    }
+
+    /// The category an exit code stands for; `None` for 0, 1 and codes
+    /// outside the scheme
+    pub fn from_exit_code(code: i32) -> Option<Category> {
+        [
+            Category::Usage,
+            Category::Privilege,
+            Category::Unavailable,
+            Category::Failed,
+            Category::Timeout,
+            Category::Internal,
+        ]
+        .into_iter()
+        .find(|c| i32::from(c.exit_code()) == code)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Error {
+    pub code: String,
+    pub category: Category,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Error {
+    pub fn new(code: &str, category: Category, message: impl Into<String>) -> Error {
+        Error {
+            code: code.to_string(),
+            category,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Error {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    pub fn unknown_command(command: &str, tool: &str) -> Error {
+        Error::new(
+            "usage.unknown-command",
+            Category::Usage,
+            format!("Unknown command: {}", command),
+        )
+        .with_hint(format!("Run '{} help' for usage", tool))
+    }
+
+    pub fn missing_target(tool: &str, targets: &[&str]) -> Error {
+        Error::new(
+            "usage.missing-target",
+            Category::Usage,
+            "repair command requires a target",
+        )
+        .with_hint(format!("Usage: {} repair <{}>", tool, targets.join("|")))
+    }
+
+    pub fn unknown_target(target: &str, targets: &[&str]) -> Error {
+        Error::new(
+            "usage.unknown-target",
+            Category::Usage,
+            format!("Unknown repair target: {}", target),
+        )
+        .with_hint(format!("Valid targets: {}", targets.join(", ")))
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.category.exit_code())
+    }
+
+    /// Print the error for `tool`, as the JSON envelope with `json`, and
+    /// return the exit code to end with
+    pub fn report(&self, tool: &str, version: &str, json: bool) -> ExitCode {
+        if json {
+            let envelope = ErrorEnvelope {
+                version: version.to_string(),
+                tool: tool.to_string(),
+                error: self.clone(),
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&envelope).unwrap_or_default()
+            );
+        } else {
+            eprintln!("Error: {}", self.message);
+            if let Some(hint) = &self.hint {
+                eprintln!("{}", hint);
+            }
+        }
+        self.exit_code()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "; {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorEnvelope {
+    pub version: String,
+    pub tool: String,
+    pub error: Error,
+}
+
+impl ErrorEnvelope {
+    /// The envelope in a binary's stdout, if that is what it printed
+    pub fn parse(stdout: &str) -> Option<ErrorEnvelope> {
+        serde_json::from_str(stdout.trim()).ok()
+    }
+}
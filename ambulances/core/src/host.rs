@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Where procfs and sysfs are read from
+//!
+//! Backends' diagnostics read `/proc`, `/sys` and the odd device node
+//! through [`path`], which is the path itself on a real machine. With
+//! `SYSTEM_TOOLS_ROOT` set, it is the same path under that directory
+//! instead, so a test can hand a backend a fake tree holding exactly the
+//! broken state it wants to check.
+
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the directory standing in for `/`
+pub const ROOT_VAR: &str = "SYSTEM_TOOLS_ROOT";
+
+/// `path`, under `SYSTEM_TOOLS_ROOT` when that is set
+pub fn path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match std::env::var_os(ROOT_VAR).filter(|r| !r.is_empty()) {
+        Some(root) => Path::new(&root).join(path.strip_prefix("/").unwrap_or(path)),
+        None => path.to_path_buf(),
+    }
+}
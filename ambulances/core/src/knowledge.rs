@@ -0,0 +1,335 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Known issues shipped as data
+//!
+//! A known issue is a rule: when a finding, a journal message or a
+//! value a check reported matches, it explains the cause and suggests
+//! the fix. Rules live in TOML or JSON files, so a new known issue is a
+//! file update rather than a change to every ambulance's check code.
+//!
+//! ```toml
+//! [[issue]]
+//! id = "iwlwifi-firmware"
+//! title = "Intel Wi-Fi firmware missing"
+//! explanation = "The iwlwifi driver needs a firmware file per card generation"
+//! suggestion = "Install the firmware: kernel-ambulance repair firmware"
+//!
+//! [issue.when]
+//! finding = "kernel.firmware.*"
+//! summary = "iwlwifi"
+//! ```
+//!
+//! Every condition given under `when` must hold. `finding` is a finding
+//! id where `*` matches anything, `summary` a case-insensitive part of
+//! that finding's summary, `message_id` a journal `MESSAGE_ID` that was
+//! logged, and `property` a value in a check's details, addressed by
+//! JSON pointer. Files are read in name order from each directory of
+//! [`DIRECTORIES`]; a later issue with the same id replaces an earlier
+//! one, so `/etc` overrides what a package ships.
+
+use crate::schema::{short_name, DiagnosticReport, Recommendation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Where known issues are read from, packaged first
+pub const DIRECTORIES: &[&str] = &[
+    "/usr/share/system-tools/known-issues",
+    "/etc/system-tools/known-issues",
+];
+
+/// Newest rule file format this reader understands
+pub const FORMAT: u32 = 1;
+
+/// A value in a check's details
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Property {
+    /// Short tool name, such as `journal`
+    pub tool: String,
+    pub check: String,
+    /// JSON pointer into the check's details, such as `/mode`
+    pub pointer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+    /// Case-insensitive part of a string value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub above: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub below: Option<f64>,
+}
+
+impl Property {
+    fn holds(&self, reports: &[DiagnosticReport]) -> bool {
+        let Some(value) = reports
+            .iter()
+            .filter(|r| short_name(&r.tool) == self.tool)
+            .flat_map(|r| r.checks.iter())
+            .find(|c| c.id == self.check)
+            .and_then(|c| {
+                Value::Object(c.details.clone())
+                    .pointer(&self.pointer)
+                    .cloned()
+            })
+        else {
+            return false;
+        };
+        self.equals.as_ref().map_or(true, |e| *e == value)
+            && self.contains.as_ref().map_or(true, |part| {
+                value
+                    .as_str()
+                    .is_some_and(|s| s.to_lowercase().contains(&part.to_lowercase()))
+            })
+            && self
+                .above
+                .map_or(true, |a| value.as_f64().is_some_and(|v| v > a))
+            && self
+                .below
+                .map_or(true, |b| value.as_f64().is_some_and(|v| v < b))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<Property>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownIssue {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub explanation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    /// Repair id, `<tool>.<target>`, when the suggestion does not name it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repair: Option<String>,
+    pub when: Conditions,
+}
+
+impl KnownIssue {
+    fn validate(&self) -> Result<(), String> {
+        let when = &self.when;
+        if self.id.is_empty() {
+            return Err("an issue has no id".to_string());
+        }
+        if when.finding.is_none()
+            && when.summary.is_none()
+            && when.message_id.is_none()
+            && when.property.is_none()
+        {
+            return Err(format!("{}: no condition under `when`", self.id));
+        }
+        if when.summary.is_some() && when.finding.is_none() {
+            return Err(format!("{}: `summary` needs `finding`", self.id));
+        }
+        if let Some(id) = &when.message_id {
+            if id.len() != 32 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "{}: message_id {} is not 32 hex digits",
+                    self.id, id
+                ));
+            }
+        }
+        if let Some(p) = &when.property {
+            if !p.pointer.starts_with('/') {
+                return Err(format!(
+                    "{}: pointer {} must start with /",
+                    self.id, p.pointer
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rule file: `format`, then `[[issue]]` tables
+#[derive(Debug, Deserialize)]
+struct File {
+    #[serde(default = "first_format")]
+    format: u32,
+    #[serde(default)]
+    issue: Vec<KnownIssue>,
+}
+
+fn first_format() -> u32 {
+    1
+}
+
+/// What was seen outside the reports
+#[derive(Debug, Clone, Default)]
+pub struct Facts {
+    /// Journal `MESSAGE_ID`s logged, and how often
+    pub message_ids: BTreeMap<String, usize>,
+}
+
+/// A known issue that matched, and the findings it matched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advice {
+    pub issue: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<String>,
+    pub explanation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<Recommendation>,
+}
+
+/// `*` in `pattern` matches any run of characters
+fn glob(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KnowledgeBase {
+    pub issues: Vec<KnownIssue>,
+}
+
+impl KnowledgeBase {
+    fn merge(&mut self, file: File, source: &str) -> Result<(), String> {
+        if file.format > FORMAT {
+            return Err(format!(
+                "{} uses rule format {}, this reader knows up to {}",
+                source, file.format, FORMAT
+            ));
+        }
+        for mut issue in file.issue {
+            issue.validate().map_err(|e| format!("{}: {}", source, e))?;
+            // journald stores ids in lowercase, and matches them exactly
+            if let Some(id) = &mut issue.when.message_id {
+                id.make_ascii_lowercase();
+            }
+            match self.issues.iter_mut().find(|i| i.id == issue.id) {
+                Some(known) => *known = issue,
+                None => self.issues.push(issue),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_toml(&mut self, text: &str, source: &str) -> Result<(), String> {
+        let file: File = toml::from_str(text).map_err(|e| format!("{}: {}", source, e))?;
+        self.merge(file, source)
+    }
+
+    pub fn add_json(&mut self, text: &str, source: &str) -> Result<(), String> {
+        let file: File = serde_json::from_str(text).map_err(|e| format!("{}: {}", source, e))?;
+        self.merge(file, source)
+    }
+
+    /// Add a `.toml` or `.json` file, or every such file in a directory
+    pub fn add_path(&mut self, path: &Path) -> Result<(), String> {
+        if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)
+                .map_err(|e| format!("{}: {}", path.display(), e))?
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    matches!(
+                        p.extension().and_then(|e| e.to_str()),
+                        Some("toml" | "json")
+                    )
+                })
+                .collect();
+            files.sort();
+            return files.iter().try_for_each(|f| self.add_path(f));
+        }
+        let source = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", source, e))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => self.add_json(&text, &source),
+            _ => self.add_toml(&text, &source),
+        }
+    }
+
+    /// The journal `MESSAGE_ID`s some issue looks for, for filling in [`Facts`]
+    pub fn message_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self
+            .issues
+            .iter()
+            .filter_map(|i| i.when.message_id.as_deref())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// The known issues that match `reports` and `facts`
+    pub fn advise(&self, reports: &[DiagnosticReport], facts: &Facts) -> Vec<Advice> {
+        let mut advice = Vec::new();
+        for issue in &self.issues {
+            let when = &issue.when;
+            if when
+                .message_id
+                .as_ref()
+                .is_some_and(|id| !facts.message_ids.contains_key(&id.to_lowercase()))
+                || when.property.as_ref().is_some_and(|p| !p.holds(reports))
+            {
+                continue;
+            }
+            let findings: Vec<String> = match &when.finding {
+                None => Vec::new(),
+                Some(pattern) => {
+                    let summary = when.summary.as_deref().map(str::to_lowercase);
+                    let matched: Vec<String> = reports
+                        .iter()
+                        .flat_map(DiagnosticReport::findings)
+                        .filter(|f| glob(pattern, &f.id))
+                        .filter(|f| {
+                            summary
+                                .as_ref()
+                                .map_or(true, |s| f.summary.to_lowercase().contains(s))
+                        })
+                        .map(|f| f.id.clone())
+                        .collect();
+                    if matched.is_empty() {
+                        continue;
+                    }
+                    matched
+                }
+            };
+            let recommendation = issue.suggestion.as_deref().map(|text| {
+                let mut rec = Recommendation::from_text(text);
+                if let Some(repair) = &issue.repair {
+                    if !rec.repairs.contains(repair) {
+                        rec.repairs.push(repair.clone());
+                    }
+                }
+                rec
+            });
+            advice.push(Advice {
+                issue: issue.id.clone(),
+                title: issue.title.clone(),
+                findings,
+                explanation: issue.explanation.clone(),
+                recommendation,
+            });
+        }
+        advice
+    }
+}
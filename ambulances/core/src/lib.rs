@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report types shared by every ambulance
+//!
+//! The backends all print the model the network ambulance introduced:
+//! `diagnose --json` emits `{version, tool, <section>...}` with
+//! `warnings` and `recommendations` in every section, and `repair --json`
+//! emits `{version, tool, <target>_repair...}` with `success`, `actions`
+//! and `errors`. [`wire`] holds the parts of that model that are the same
+//! in every backend. [`schema`] is the typed, versioned form consumers
+//! work with, converted from any backend's output: checks holding
+//! findings with an id, a severity, a confidence, evidence and a
+//! recommendation linked to the repairs that carry it out, and repairs.
+//! [`combined`] gathers several backends' reports from one machine, and
+//! [`correlate`] links their findings into root-cause chains.
+//! [`knowledge`] matches findings against known issues shipped as data.
+//! [`runner`] runs an ambulance's checks as async jobs, and [`error`]
+//! is how every binary reports a failure and picks its exit code.
+//! [`host`] is where backends read procfs and sysfs, which tests point
+//! at a fake tree. [`scan`] says whether a run is quick or deep and how
+//! long it may take, and [`cache`] keeps expensive probes' results
+//! between quick runs.
+
+pub mod cache;
+pub mod combined;
+pub mod correlate;
+pub mod error;
+pub mod host;
+pub mod knowledge;
+pub mod runner;
+pub mod scan;
+pub mod schema;
+pub mod wire;
+
+pub use combined::{CombinedReport, Failure};
+pub use correlate::{Chain, Link};
+pub use error::{Category, Error, ErrorEnvelope};
+pub use knowledge::{Advice, Facts, KnowledgeBase};
+pub use schema::{
+    Check, CheckStatus, Confidence, DiagnosticReport, Evidence, Finding, Recommendation, Repair,
+    RepairReport, Severity, Timing, SCHEMA_VERSION,
+};
+pub use wire::{RepairOutcome, Section};
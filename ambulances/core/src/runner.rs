@@ -0,0 +1,430 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Checks as small async jobs
+//!
+//! An ambulance describes each check as a [`Job`]: an id, the ids of the
+//! jobs whose output it needs, and a future, or a blocking function for
+//! checks that read files and call D-Bus. The [`Runner`] starts every job
+//! whose inputs are ready, at most [`Runner::limit`] at a time, gives up
+//! on a job at its timeout, skips the jobs that needed a job that did not
+//! succeed, and reports each start and finish to a progress callback.
+//! With a [`Runner::budget`], the whole run also has a deadline: jobs
+//! running then are stopped and the rest never start, all ending
+//! [`Status::OverBudget`].
+//!
+//! ```
+//! use ambulance_core::runner::{Job, Runner};
+//!
+//! let outcomes = Runner::<usize>::new()
+//!     .job(Job::blocking("servers", |_| Ok(2)))
+//!     .job(Job::blocking("answered", |inputs| Ok(inputs.get("servers").map_or(0, |n| n - 1)))
+//!         .after(&["servers"]))
+//!     .block_on();
+//! assert_eq!(outcomes[1].output, Some(1));
+//! ```
+//!
+//! A timed-out or cancelled async job is dropped at its next await. A
+//! blocking job cannot be interrupted; its thread runs to the end, and
+//! its result is discarded. Until then it holds its inputs, so the
+//! outputs it was given are cloned for their own outcomes.
+
+use crate::schema::Timing;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::{AbortHandle, JoinSet};
+
+type JobFuture<T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send>>;
+type Start<T> = Box<dyn FnOnce(Inputs<T>) -> JobFuture<T> + Send>;
+type ProgressFn = Box<dyn Fn(Progress<'_>) + Send + Sync>;
+
+/// Outputs of the jobs a job runs after
+pub struct Inputs<T> {
+    outputs: HashMap<&'static str, Arc<T>>,
+}
+
+impl<T> Inputs<T> {
+    pub fn get(&self, id: &str) -> Option<&T> {
+        self.outputs.get(id).map(|o| o.as_ref())
+    }
+}
+
+pub struct Job<T> {
+    pub id: &'static str,
+    after: Vec<&'static str>,
+    timeout: Option<Duration>,
+    start: Start<T>,
+}
+
+impl<T: Send + Sync + 'static> Job<T> {
+    pub fn new<F, Fut>(id: &'static str, f: F) -> Job<T>
+    where
+        F: FnOnce(Inputs<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, String>> + Send + 'static,
+    {
+        Job {
+            id,
+            after: Vec::new(),
+            timeout: None,
+            start: Box::new(move |inputs| Box::pin(f(inputs))),
+        }
+    }
+
+    /// A job that blocks, run on tokio's blocking threads
+    pub fn blocking<F>(id: &'static str, f: F) -> Job<T>
+    where
+        F: FnOnce(Inputs<T>) -> Result<T, String> + Send + 'static,
+    {
+        Job::new(id, move |inputs| async move {
+            tokio::task::spawn_blocking(move || f(inputs))
+                .await
+                .map_err(|_| "panicked".to_string())?
+        })
+    }
+
+    /// Run once these jobs succeeded; their outputs are the job's inputs
+    pub fn after(mut self, ids: &[&'static str]) -> Job<T> {
+        self.after.extend_from_slice(ids);
+        self
+    }
+
+    /// Overrides the runner's timeout for this job
+    pub fn timeout(mut self, timeout: Duration) -> Job<T> {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Succeeded,
+    Failed(String),
+    TimedOut(Duration),
+    /// Not run; says which input was missing
+    Skipped(String),
+    /// Stopped, or never started, at the runner's budget
+    OverBudget,
+    Cancelled,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Succeeded => write!(f, "succeeded"),
+            Status::Failed(e) => write!(f, "failed: {}", e),
+            Status::TimedOut(after) => write!(f, "timed out after {}s", after.as_secs_f64()),
+            Status::Skipped(why) => write!(f, "skipped: {}", why),
+            Status::OverBudget => write!(f, "stopped at the time budget"),
+            Status::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Outcome<T> {
+    pub id: &'static str,
+    pub status: Status,
+    /// Zero for jobs that never started
+    pub elapsed: Duration,
+    /// `Some` exactly when the job succeeded; a clone when a timed-out
+    /// blocking job still holds it as an input
+    pub output: Option<T>,
+}
+
+impl<T> Outcome<T> {
+    /// How long the job took, for the report's timings
+    pub fn timing(&self) -> Timing {
+        Timing {
+            id: self.id.to_string(),
+            millis: self.elapsed.as_millis() as u64,
+            status: (self.status != Status::Succeeded).then(|| self.status.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Progress<'a> {
+    Started {
+        id: &'a str,
+    },
+    Finished {
+        id: &'a str,
+        status: &'a Status,
+        /// Jobs finished so far, this one included
+        done: usize,
+        total: usize,
+    },
+}
+
+/// Stops a run: running jobs are dropped and the rest not started
+#[derive(Clone)]
+pub struct Cancel(Arc<watch::Sender<bool>>);
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+/// Resolves once `cancel` was called
+async fn cancelled(mut rx: watch::Receiver<bool>) {
+    while !*rx.borrow_and_update() {
+        if rx.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Aborts the job's own task when its wrapper is dropped or aborted
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+enum State<T> {
+    Waiting(Job<T>),
+    Running,
+    Done(Status, Duration, Option<Arc<T>>),
+}
+
+pub struct Runner<T> {
+    jobs: Vec<Job<T>>,
+    limit: usize,
+    timeout: Option<Duration>,
+    budget: Option<Duration>,
+    progress: Option<ProgressFn>,
+    cancel: Cancel,
+}
+
+impl<T: Send + Sync + 'static> Default for Runner<T> {
+    fn default() -> Self {
+        Runner::new()
+    }
+}
+
+impl<T: Send + Sync + 'static> Runner<T> {
+    /// As many jobs at once as there are CPUs, and no timeout
+    pub fn new() -> Runner<T> {
+        Runner {
+            jobs: Vec::new(),
+            limit: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            timeout: None,
+            budget: None,
+            progress: None,
+            cancel: Cancel(Arc::new(watch::channel(false).0)),
+        }
+    }
+
+    pub fn job(mut self, job: Job<T>) -> Runner<T> {
+        self.jobs.push(job);
+        self
+    }
+
+    /// At most this many jobs at once
+    pub fn limit(mut self, limit: usize) -> Runner<T> {
+        self.limit = limit.max(1);
+        self
+    }
+
+    /// Timeout of each job that does not set its own
+    pub fn timeout(mut self, timeout: Duration) -> Runner<T> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Time the whole run may take, from when it starts
+    pub fn budget(mut self, budget: Duration) -> Runner<T> {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub fn on_progress(mut self, f: impl Fn(Progress<'_>) + Send + Sync + 'static) -> Runner<T> {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    pub fn canceller(&self) -> Cancel {
+        self.cancel.clone()
+    }
+
+    /// Runs every job; outcomes come in the order the jobs were added
+    pub async fn run(self) -> Vec<Outcome<T>>
+    where
+        T: Clone,
+    {
+        let Runner {
+            jobs,
+            limit,
+            timeout,
+            budget,
+            progress,
+            cancel,
+        } = self;
+        let budget_end = budget.map(|b| Instant::now() + b);
+        let report = |p: Progress<'_>| {
+            if let Some(f) = &progress {
+                f(p)
+            }
+        };
+        let total = jobs.len();
+        let ids: Vec<&'static str> = jobs.iter().map(|j| j.id).collect();
+        let index: HashMap<&'static str, usize> =
+            ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut states: Vec<State<T>> = jobs.into_iter().map(State::Waiting).collect();
+        let mut done = 0;
+        let mut running = JoinSet::new();
+        let stop = cancelled(cancel.0.subscribe());
+        tokio::pin!(stop);
+
+        loop {
+            // Settle what can be settled without running anything, then
+            // start what is ready
+            let mut i = 0;
+            while i < states.len() {
+                let State::Waiting(job) = &states[i] else {
+                    i += 1;
+                    continue;
+                };
+                let mut missing = None;
+                let mut ready = true;
+                for dep in &job.after {
+                    match index.get(dep).map(|&d| &states[d]) {
+                        None => missing = Some(format!("no check {}", dep)),
+                        Some(State::Done(Status::Succeeded, ..)) => {}
+                        Some(State::Done(..)) => missing = Some(format!("{} did not succeed", dep)),
+                        Some(_) => ready = false,
+                    }
+                    if missing.is_some() {
+                        break;
+                    }
+                }
+                // Over budget, what could start now never will
+                let left = budget_end.map(|d| d.saturating_duration_since(Instant::now()));
+                let over = missing.is_none() && ready && left == Some(Duration::ZERO);
+                if missing.is_some() || over {
+                    let status = missing.map_or(Status::OverBudget, Status::Skipped);
+                    done += 1;
+                    report(Progress::Finished {
+                        id: job.id,
+                        status: &status,
+                        done,
+                        total,
+                    });
+                    states[i] = State::Done(status, Duration::ZERO, None);
+                    // A skip can settle jobs already passed over
+                    i = 0;
+                    continue;
+                }
+                if ready && running.len() < limit {
+                    let State::Waiting(job) = std::mem::replace(&mut states[i], State::Running)
+                    else {
+                        unreachable!()
+                    };
+                    let outputs = job
+                        .after
+                        .iter()
+                        .filter_map(|dep| match &states[index[dep]] {
+                            State::Done(_, _, Some(output)) => Some((*dep, output.clone())),
+                            _ => None,
+                        })
+                        .collect();
+                    report(Progress::Started { id: job.id });
+                    // The job's own timeout, unless the budget ends first
+                    let own = job.timeout.or(timeout);
+                    let by_budget = left.is_some_and(|l| own.map_or(true, |t| l < t));
+                    let deadline = if by_budget { left } else { own };
+                    let task = tokio::spawn((job.start)(Inputs { outputs }));
+                    running.spawn(async move {
+                        let guard = AbortOnDrop(task.abort_handle());
+                        let started = Instant::now();
+                        let result = match deadline {
+                            Some(after) => match tokio::time::timeout(after, task).await {
+                                Ok(joined) => joined,
+                                Err(_) if by_budget => {
+                                    return (i, Status::OverBudget, started, None)
+                                }
+                                Err(_) => return (i, Status::TimedOut(after), started, None),
+                            },
+                            None => task.await,
+                        };
+                        drop(guard);
+                        match result {
+                            Ok(Ok(output)) => (i, Status::Succeeded, started, Some(output)),
+                            Ok(Err(e)) => (i, Status::Failed(e), started, None),
+                            Err(_) => (i, Status::Failed("panicked".to_string()), started, None),
+                        }
+                    });
+                }
+                i += 1;
+            }
+            if running.is_empty() {
+                break;
+            }
+            tokio::select! {
+                biased;
+                _ = &mut stop => {
+                    running.abort_all();
+                    break;
+                }
+                Some(joined) = running.join_next() => {
+                    let Ok((i, status, started, output)) = joined else {
+                        continue;
+                    };
+                    done += 1;
+                    report(Progress::Finished {
+                        id: ids[i],
+                        status: &status,
+                        done,
+                        total,
+                    });
+                    states[i] = State::Done(status, started.elapsed(), output.map(Arc::new));
+                }
+            }
+        }
+
+        // What is left was cancelled, or waits on a cycle
+        let cycle = !*cancel.0.borrow();
+        states
+            .into_iter()
+            .zip(ids)
+            .map(|(state, id)| match state {
+                State::Done(status, elapsed, output) => Outcome {
+                    id,
+                    status,
+                    elapsed,
+                    output: output.map(|o| Arc::try_unwrap(o).unwrap_or_else(|o| (*o).clone())),
+                },
+                State::Waiting(_) if cycle => Outcome {
+                    id,
+                    status: Status::Skipped("waits on a dependency cycle".to_string()),
+                    elapsed: Duration::ZERO,
+                    output: None,
+                },
+                _ => Outcome {
+                    id,
+                    status: Status::Cancelled,
+                    elapsed: Duration::ZERO,
+                    output: None,
+                },
+            })
+            .collect()
+    }
+
+    /// [`Runner::run`] on a runtime of its own, for synchronous callers
+    pub fn block_on(self) -> Vec<Outcome<T>>
+    where
+        T: Clone,
+    {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("tokio runtime")
+            .block_on(self.run())
+    }
+}
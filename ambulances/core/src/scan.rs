@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Quick and deep scans
+//!
+//! A quick scan, the default, has a budget of a few seconds: checks run
+//! as [`crate::runner`] jobs stop when it runs out, and expensive probes
+//! answer from the [`crate::cache`] while their results are fresh. A deep
+//! scan has minutes and probes everything again. The doctor passes the
+//! scan it was asked for to every backend in [`MODE_VAR`] and
+//! [`BUDGET_VAR`]; a backend run on its own reads them the same way.
+
+use crate::cache::Cache;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// `quick` or `deep`
+pub const MODE_VAR: &str = "SYSTEM_TOOLS_SCAN";
+
+/// The budget in seconds, overriding the mode's
+pub const BUDGET_VAR: &str = "SYSTEM_TOOLS_BUDGET";
+
+pub const QUICK_BUDGET: Duration = Duration::from_secs(3);
+pub const DEEP_BUDGET: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Quick,
+    Deep,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Quick => "quick",
+            Mode::Deep => "deep",
+        })
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "quick" => Ok(Mode::Quick),
+            "deep" => Ok(Mode::Deep),
+            _ => Err(format!("unknown scan {}, expected quick or deep", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scan {
+    pub mode: Mode,
+    /// Wall-clock time the whole run may take
+    pub budget: Duration,
+}
+
+impl Default for Scan {
+    fn default() -> Scan {
+        Scan::new(Mode::Quick)
+    }
+}
+
+impl Scan {
+    /// `mode` with its default budget
+    pub fn new(mode: Mode) -> Scan {
+        let budget = match mode {
+            Mode::Quick => QUICK_BUDGET,
+            Mode::Deep => DEEP_BUDGET,
+        };
+        Scan { mode, budget }
+    }
+
+    pub fn budget(mut self, budget: Duration) -> Scan {
+        self.budget = budget;
+        self
+    }
+
+    /// The scan the environment asks for; values that do not parse are
+    /// ignored, leaving a quick scan
+    pub fn from_env() -> Scan {
+        let mode = std::env::var(MODE_VAR)
+            .ok()
+            .and_then(|m| m.parse().ok())
+            .unwrap_or_default();
+        let scan = Scan::new(mode);
+        let secs = std::env::var(BUDGET_VAR)
+            .ok()
+            .and_then(|b| b.parse::<f64>().ok())
+            .filter(|s| s.is_finite() && *s > 0.0);
+        match secs {
+            Some(secs) => scan.budget(Duration::from_secs_f64(secs)),
+            None => scan,
+        }
+    }
+
+    pub fn deep(&self) -> bool {
+        self.mode == Mode::Deep
+    }
+
+    /// The environment that passes this scan on to a backend
+    pub fn env(&self) -> [(&'static str, String); 2] {
+        [
+            (MODE_VAR, self.mode.to_string()),
+            (BUDGET_VAR, self.budget.as_secs_f64().to_string()),
+        ]
+    }
+
+    /// `tool`'s cache, read in a quick scan and only refreshed in a deep one
+    pub fn cache(&self, tool: &str) -> Cache {
+        Cache::open(tool).refresh(self.deep())
+    }
+}
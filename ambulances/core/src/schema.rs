@@ -0,0 +1,443 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Typed, versioned reports
+//!
+//! A [`DiagnosticReport`] holds one [`Check`] per section a backend ran,
+//! each with its [`Finding`]s; a [`RepairReport`] holds one [`Repair`]
+//! per target that ran. Both carry [`SCHEMA_VERSION`], and
+//! [`DiagnosticReport::parse`] refuses reports from a newer schema
+//! rather than misread them.
+//!
+//! Backend output is converted with `from_wire`. Every warning becomes a
+//! finding, numbered within its check, so its id is stable only as long
+//! as the warnings before it are; the security ambulance's ranked
+//! findings keep their severity and remedy. Recommendations that name a
+//! repair (`thermal-ambulance repair fans`) are linked to it by id, and
+//! every finding of the check is linked to the repairs its check
+//! recommends, since the wire format does not pair them any closer.
+
+use crate::wire::RepairOutcome;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Bumped on any change that older readers would misread
+///
+/// 2: four severities, confidence, and recommendations linked to repairs.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Wire keys that are not sections
+const RESERVED: &[&str] = &["version", "tool", "findings", "plugins", "timings"];
+
+/// How bad a finding is, from what it does to the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth knowing; nothing is wrong
+    Info,
+    /// Works now, but will cause trouble or is set up against advice
+    #[serde(alias = "warning")]
+    Warn,
+    /// Works, but slower, less reliably or with less than it should
+    Degraded,
+    /// Does not work
+    #[serde(alias = "critical")]
+    Broken,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Broken,
+        Severity::Degraded,
+        Severity::Warn,
+        Severity::Info,
+    ];
+
+    /// Map the security ambulance's five levels onto these four
+    pub fn from_wire(level: &str) -> Severity {
+        match level {
+            "critical" => Severity::Broken,
+            "high" => Severity::Degraded,
+            "low" | "medium" | "warning" => Severity::Warn,
+            _ => Severity::Info,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Degraded => "degraded",
+            Severity::Broken => "broken",
+        }
+    }
+
+    /// The symbol the CLIs print before a note of this severity
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ",
+            Severity::Warn => "⚠",
+            Severity::Degraded => "▼",
+            Severity::Broken => "✗",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// How sure the check is that a finding is right
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    /// A guess from indirect signs, such as heat while idle meaning dust
+    Low,
+    /// Inferred from what was observed, or not stated by the backend
+    Medium,
+    /// Observed directly: a failed unit, a fault flag, a missing file
+    #[default]
+    High,
+}
+
+/// Advice, and the repairs that carry it out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recommendation {
+    pub text: String,
+    /// Repair ids, `<tool>.<target>` like [`Repair::id`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<String>,
+}
+
+impl Recommendation {
+    /// Parse `text`, linking the `<tool>-ambulance repair <target>` it names
+    pub fn from_text(text: &str) -> Recommendation {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let repairs = words
+            .windows(3)
+            .filter(|w| w[1] == "repair")
+            .filter_map(|w| {
+                let tool = w[0].strip_suffix("-ambulance")?;
+                let target = w[2].trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+                (!target.is_empty()).then(|| format!("{}.{}", tool, target))
+            })
+            .collect();
+        Recommendation {
+            text: text.to_string(),
+            repairs,
+        }
+    }
+}
+
+/// Something observed that backs a finding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evidence {
+    /// Where it was read: a file, command, unit or journal field
+    pub source: String,
+    pub observed: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    /// `<tool>.<check>.<n>`, e.g. `thermal.fans.0`
+    pub id: String,
+    pub severity: Severity,
+    #[serde(default)]
+    pub confidence: Confidence,
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<Evidence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recommendation: Option<Recommendation>,
+    /// Repairs that address this finding, by [`Repair::id`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Check {
+    /// The section name, e.g. `fans`
+    pub id: String,
+    pub status: CheckStatus,
+    #[serde(default)]
+    pub findings: Vec<Finding>,
+    /// Advice for the check as a whole, not tied to one finding
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recommendations: Vec<Recommendation>,
+    /// What the check inspected, as the backend reported it
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub details: Map<String, Value>,
+}
+
+impl Check {
+    fn new(id: &str) -> Check {
+        Check {
+            id: id.to_string(),
+            status: CheckStatus::Passed,
+            findings: Vec::new(),
+            recommendations: Vec::new(),
+            details: Map::new(),
+        }
+    }
+
+    fn push(
+        &mut self,
+        tool: &str,
+        severity: Severity,
+        confidence: Confidence,
+        summary: String,
+        remedy: Option<&str>,
+    ) {
+        let recommendation = remedy.map(Recommendation::from_text);
+        self.findings.push(Finding {
+            id: format!("{}.{}.{}", tool, self.id, self.findings.len()),
+            severity,
+            confidence,
+            summary,
+            evidence: Vec::new(),
+            repairs: recommendation
+                .as_ref()
+                .map(|r| r.repairs.clone())
+                .unwrap_or_default(),
+            recommendation,
+        });
+        if severity > Severity::Info {
+            self.status = CheckStatus::Failed;
+        }
+    }
+}
+
+/// How long one check, or one backend, took
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    /// The check's id, or the tool's for a whole backend
+    pub id: String,
+    pub millis: u64,
+    /// Why it did not finish, e.g. `timed out after 3s`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+    pub schema: u32,
+    pub tool: String,
+    pub version: String,
+    pub checks: Vec<Check>,
+    /// Per check, from backends that time them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+/// A repair target that ran
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Repair {
+    /// `<tool>.<target>`, e.g. `thermal.fans`
+    pub id: String,
+    #[serde(flatten)]
+    pub outcome: RepairOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepairReport {
+    pub schema: u32,
+    pub tool: String,
+    pub version: String,
+    pub repairs: Vec<Repair>,
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|s| s.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `(tool, version, fields)` of a wire report
+fn header(value: &Value) -> Result<(&str, &str, &Map<String, Value>), String> {
+    let fields = value.as_object().ok_or("report is not a JSON object")?;
+    let text = |key: &str| {
+        fields
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("report has no {}", key))
+    };
+    Ok((text("tool")?, text("version")?, fields))
+}
+
+/// `thermal` for `thermal-ambulance`, `network` for the network ambulance's `network-ambulance-d`
+pub fn short_name(tool: &str) -> &str {
+    tool.strip_suffix("-ambulance-d")
+        .or_else(|| tool.strip_suffix("-ambulance"))
+        .unwrap_or(tool)
+}
+
+fn check_schema(json: &Value) -> Result<(), String> {
+    match json.get("schema").and_then(Value::as_u64) {
+        Some(v) if v > SCHEMA_VERSION as u64 => Err(format!(
+            "report uses schema {}, this reader knows up to {}",
+            v, SCHEMA_VERSION
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl DiagnosticReport {
+    /// Convert a backend's `diagnose --json` output
+    pub fn from_wire(value: &Value) -> Result<DiagnosticReport, String> {
+        let (tool, version, fields) = header(value)?;
+        let short = short_name(tool);
+        let mut checks: Vec<Check> = Vec::new();
+        for (key, section) in fields {
+            let Some(data) = section.as_object() else {
+                continue;
+            };
+            if RESERVED.contains(&key.as_str()) {
+                continue;
+            }
+            let mut check = Check::new(key);
+            check.recommendations = strings(&section["recommendations"])
+                .iter()
+                .map(|r| Recommendation::from_text(r))
+                .collect();
+            let repairs: Vec<String> = check
+                .recommendations
+                .iter()
+                .flat_map(|r| r.repairs.iter().cloned())
+                .collect();
+            // The wire format only says something is wrong, not how badly
+            for warning in strings(&section["warnings"]) {
+                check.push(short, Severity::Warn, Confidence::Medium, warning, None);
+                if let Some(f) = check.findings.last_mut() {
+                    f.repairs = repairs.clone();
+                }
+            }
+            check.details = data
+                .iter()
+                .filter(|(k, _)| *k != "warnings" && *k != "recommendations")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            checks.push(check);
+        }
+
+        for finding in fields
+            .get("findings")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let section = finding["section"].as_str().unwrap_or("findings");
+            if !checks.iter().any(|c| c.id == section) {
+                checks.push(Check::new(section));
+            }
+            let Some(check) = checks.iter_mut().find(|c| c.id == section) else {
+                continue;
+            };
+            check.push(
+                short,
+                Severity::from_wire(finding["severity"].as_str().unwrap_or_default()),
+                Confidence::High,
+                finding["title"].as_str().unwrap_or_default().to_string(),
+                finding["remedy"].as_str(),
+            );
+        }
+
+        let timings = fields
+            .get("timings")
+            .and_then(|t| serde_json::from_value(t.clone()).ok())
+            .unwrap_or_default();
+
+        Ok(DiagnosticReport {
+            schema: SCHEMA_VERSION,
+            tool: tool.to_string(),
+            version: version.to_string(),
+            checks,
+            timings,
+        })
+    }
+
+    /// Read either this schema or a backend's wire output
+    pub fn parse(json: &str) -> Result<DiagnosticReport, String> {
+        let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if value.get("schema").is_none() {
+            return DiagnosticReport::from_wire(&value);
+        }
+        check_schema(&value)?;
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.checks.iter().flat_map(|c| c.findings.iter())
+    }
+
+    /// The most severe finding's severity, `None` when nothing was found
+    pub fn worst(&self) -> Option<Severity> {
+        self.findings().map(|f| f.severity).max()
+    }
+
+    /// Every repair some finding links to, each once, in finding order
+    pub fn repairs(&self) -> Vec<&str> {
+        let mut repairs: Vec<&str> = Vec::new();
+        for id in self.findings().flat_map(|f| f.repairs.iter()) {
+            if !repairs.contains(&id.as_str()) {
+                repairs.push(id);
+            }
+        }
+        repairs
+    }
+}
+
+impl RepairReport {
+    /// Convert a backend's `repair --json` output; targets that did not run are left out
+    pub fn from_wire(value: &Value) -> Result<RepairReport, String> {
+        let (tool, version, fields) = header(value)?;
+        let short = short_name(tool);
+        let mut repairs = Vec::new();
+        for (key, outcome) in fields {
+            let Some(id) = key.strip_suffix("_repair") else {
+                continue;
+            };
+            let outcome: RepairOutcome =
+                serde_json::from_value(outcome.clone()).map_err(|e| format!("{}: {}", key, e))?;
+            if outcome.ran() {
+                repairs.push(Repair {
+                    id: format!("{}.{}", short, id),
+                    outcome,
+                });
+            }
+        }
+        Ok(RepairReport {
+            schema: SCHEMA_VERSION,
+            tool: tool.to_string(),
+            version: version.to_string(),
+            repairs,
+        })
+    }
+
+    /// Read either this schema or a backend's wire output
+    pub fn parse(json: &str) -> Result<RepairReport, String> {
+        let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if value.get("schema").is_none() {
+            return RepairReport::from_wire(&value);
+        }
+        check_schema(&value)?;
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.repairs.iter().all(|r| r.outcome.success)
+    }
+}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The JSON every backend prints, and its CLI rendering
+//!
+//! Sections differ from one ambulance to the next beyond their
+//! `warnings` and `recommendations`, so [`Section`] keeps the rest as
+//! JSON and indexes into it the way `serde_json::Value` does. Repair
+//! outcomes are the same everywhere.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// One section of a backend's `diagnose --json` output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub recommendations: Vec<String>,
+    /// Everything else the section reports
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+impl Section {
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+}
+
+static NULL: Value = Value::Null;
+
+/// `section["field"]`, `Value::Null` when the section does not report it
+impl std::ops::Index<&str> for Section {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        self.data.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// Outcome of one repair target
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RepairOutcome {
+    pub success: bool,
+    pub actions: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl RepairOutcome {
+    /// A target that was checked and found healthy
+    pub fn not_needed(reason: &str) -> RepairOutcome {
+        RepairOutcome {
+            success: true,
+            actions: vec![reason.to_string()],
+            errors: Vec::new(),
+        }
+    }
+
+    /// Whether the target ran at all; unselected targets stay at the default
+    pub fn ran(&self) -> bool {
+        self.success || !self.actions.is_empty() || !self.errors.is_empty()
+    }
+
+    pub fn print(&self, title: &str) {
+        println!("=== {} ===", title);
+        for action in &self.actions {
+            println!("  {}", action);
+        }
+        for error in &self.errors {
+            println!("  ✗ {}", error);
+        }
+        if self.success {
+            println!("✓ {} completed successfully\n", title);
+        } else {
+            println!("✗ {} failed\n", title);
+        }
+    }
+}
+
+/// Print a section's warnings and recommendations the way the network CLI does
+pub fn print_notes(warnings: &[String], recommendations: &[String]) {
+    if !warnings.is_empty() {
+        println!("\nWarnings:");
+        for warning in warnings {
+            println!("  ⚠ {}", warning);
+        }
+    }
+    if !recommendations.is_empty() {
+        println!("\nRecommendations:");
+        for rec in recommendations {
+            println!("  → {}", rec);
+        }
+    }
+    println!();
+}
+
+pub fn mark(ok: bool) -> &'static str {
+    if ok {
+        "✓"
+    } else {
+        "✗"
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Probe results kept for their TTL, and the scans that use them
+
+use ambulance_core::cache::Cache;
+use ambulance_core::scan::{Mode, Scan, BUDGET_VAR, MODE_VAR, QUICK_BUDGET};
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ambulance-cache-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn probes_run_once_within_their_ttl() {
+    let dir = dir("ttl");
+    let cache = Cache::at(&dir);
+    let runs = Cell::new(0);
+    let probe = || {
+        runs.set(runs.get() + 1);
+        Ok::<_, String>(vec!["sda".to_string(), "nvme0n1".to_string()])
+    };
+    let hour = Duration::from_secs(3600);
+
+    assert_eq!(cache.get_or("devices", hour, probe).unwrap().len(), 2);
+    assert_eq!(cache.get_or("devices", hour, probe).unwrap().len(), 2);
+    assert_eq!(runs.get(), 1);
+
+    // Expired, and refreshing, both probe again
+    cache.get_or("devices", Duration::ZERO, probe).unwrap();
+    cache
+        .clone()
+        .refresh(true)
+        .get_or("devices", hour, probe)
+        .unwrap();
+    assert_eq!(runs.get(), 3);
+
+    cache.clear().unwrap();
+    assert!(!dir.exists());
+}
+
+#[test]
+fn failures_and_odd_keys_are_not_a_problem() {
+    let dir = dir("odd");
+    let cache = Cache::at(&dir);
+    let hour = Duration::from_secs(3600);
+
+    let failed: Result<u32, String> = cache.get_or("ip", hour, || Err("offline".to_string()));
+    assert!(failed.is_err());
+    assert_eq!(cache.get::<u32>("ip", hour), None);
+
+    cache.put("smart /dev/../sda", &42u32);
+    assert_eq!(cache.get::<u32>("smart /dev/../sda", hour), Some(42));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    // A value of another shape is a miss, not an error
+    assert_eq!(cache.get::<String>("smart /dev/../sda", hour), None);
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+    assert_eq!(cache.get::<u32>("broken", hour), None);
+    cache.clear().unwrap();
+}
+
+#[test]
+fn scans_come_from_the_environment() {
+    // The only test here that sets these
+    std::env::set_var(MODE_VAR, "deep");
+    std::env::set_var(BUDGET_VAR, "7.5");
+    let scan = Scan::from_env();
+    assert!(scan.deep());
+    assert_eq!(scan.budget, Duration::from_millis(7500));
+    assert_eq!(
+        scan.env(),
+        [
+            (MODE_VAR, "deep".to_string()),
+            (BUDGET_VAR, "7.5".to_string())
+        ]
+    );
+
+    std::env::set_var(MODE_VAR, "thorough");
+    std::env::set_var(BUDGET_VAR, "-1");
+    assert_eq!(Scan::from_env(), Scan::new(Mode::Quick));
+    assert_eq!(Scan::from_env().budget, QUICK_BUDGET);
+    std::env::remove_var(MODE_VAR);
+    std::env::remove_var(BUDGET_VAR);
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Root-cause chains across reports converted from backend wire output
+
+use ambulance_core::{correlate, CombinedReport, DiagnosticReport};
+use serde_json::{json, Value};
+
+fn report(tool: &str, sections: Value) -> DiagnosticReport {
+    let mut wire = sections;
+    wire["tool"] = json!(tool);
+    wire["version"] = json!("0.1.0");
+    DiagnosticReport::from_wire(&wire).unwrap()
+}
+
+fn warnings(list: &[&str]) -> Value {
+    json!({"warnings": list, "recommendations": []})
+}
+
+#[test]
+fn full_disk_explains_journal_and_its_readers() {
+    let mut combined = CombinedReport::new("workstation", 1_760_000_000);
+    combined.reports = vec![
+        report(
+            "disk-ambulance",
+            json!({"usage": warnings(&["/var is 98% full (310.0M free)"])}),
+        ),
+        report(
+            "journal-ambulance",
+            json!({"usage": warnings(&["The journal keeps only 2 days of history"])}),
+        ),
+        report(
+            "memory-ambulance",
+            json!({"oom": warnings(&["Could not read the journal: Bad message"])}),
+        ),
+        report(
+            "thermal-ambulance",
+            json!({"sensors": warnings(&["Running hot: coretemp Package id 0 at 99°C"])}),
+        ),
+    ];
+    combined.correlate();
+
+    assert_eq!(combined.chains.len(), 1);
+    let chain = &combined.chains[0];
+    assert_eq!(chain.root, "disk.usage.0");
+    let links: Vec<(&str, &str)> = chain
+        .links
+        .iter()
+        .map(|l| (l.cause.as_str(), l.effect.as_str()))
+        .collect();
+    assert_eq!(
+        links,
+        [
+            ("disk.usage.0", "journal.usage.0"),
+            ("journal.usage.0", "memory.oom.0")
+        ]
+    );
+    let (tool, finding) = combined.finding("memory.oom.0").unwrap();
+    assert_eq!(tool, "memory-ambulance");
+    assert!(finding.summary.starts_with("Could not read"));
+
+    let back: CombinedReport =
+        serde_json::from_str(&serde_json::to_string(&combined).unwrap()).unwrap();
+    assert_eq!(back, combined);
+}
+
+#[test]
+fn io_errors_only_explain_the_same_disk() {
+    let reports = |mount: &str| {
+        vec![
+            report(
+                "kernel-ambulance",
+                json!({"io": warnings(&["3 block error(s) on sdb"])}),
+            ),
+            report("disk-ambulance", json!({"mounts": warnings(&[mount])})),
+        ]
+    };
+    let other = reports("/data (ext4 on /dev/sda1) is mounted read-only");
+    assert!(correlate::links(&other).is_empty());
+    let same = reports("/data (ext4 on /dev/sdb1) is mounted read-only");
+    assert_eq!(correlate::links(&same).len(), 1);
+}
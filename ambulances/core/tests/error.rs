@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The error envelope and the exit codes it maps to
+
+use ambulance_core::error::{exit, Category, Error, ErrorEnvelope};
+use ambulance_core::{CombinedReport, Failure};
+use serde_json::json;
+
+#[test]
+fn envelope_matches_the_wire_format() {
+    let error = Error::unknown_target("fans", &["sensors", "all"]);
+    let envelope = ErrorEnvelope {
+        version: "0.1.0".to_string(),
+        tool: "thermal-ambulance".to_string(),
+        error: error.clone(),
+    };
+    let value = serde_json::to_value(&envelope).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "version": "0.1.0",
+            "tool": "thermal-ambulance",
+            "error": {
+                "code": "usage.unknown-target",
+                "category": "usage",
+                "message": "Unknown repair target: fans",
+                "hint": "Valid targets: sensors, all"
+            }
+        })
+    );
+    let back = ErrorEnvelope::parse(&format!("{}\n", value)).unwrap();
+    assert_eq!(back.error, error);
+    assert_eq!(
+        error.to_string(),
+        "Unknown repair target: fans; Valid targets: sensors, all"
+    );
+}
+
+#[test]
+fn reports_are_not_envelopes() {
+    let report = json!({"version": "0.1.0", "tool": "disk-ambulance", "usage": {}});
+    assert!(ErrorEnvelope::parse(&report.to_string()).is_none());
+    assert!(ErrorEnvelope::parse("Error: something").is_none());
+}
+
+#[test]
+fn categories_have_their_own_exit_codes() {
+    let categories = [
+        Category::Usage,
+        Category::Privilege,
+        Category::Unavailable,
+        Category::Failed,
+        Category::Timeout,
+        Category::Internal,
+    ];
+    let codes: Vec<u8> = categories.iter().map(|c| c.exit_code()).collect();
+    assert_eq!(
+        codes,
+        [
+            exit::USAGE,
+            exit::PRIVILEGE,
+            exit::UNAVAILABLE,
+            exit::FAILED,
+            exit::TIMEOUT,
+            exit::INTERNAL
+        ]
+    );
+    for category in categories {
+        assert_eq!(
+            Category::from_exit_code(category.exit_code().into()),
+            Some(category)
+        );
+    }
+    assert_eq!(Category::from_exit_code(exit::OK.into()), None);
+    assert_eq!(Category::from_exit_code(exit::FINDINGS.into()), None);
+    assert_eq!(Category::from_exit_code(127), None);
+}
+
+#[test]
+fn failures_carry_the_typed_error() {
+    let mut combined = CombinedReport::new("workstation", 1_760_000_000);
+    combined.failures.push(Failure::new(
+        "service-ambulance",
+        Error::new(
+            "unavailable.system-bus",
+            Category::Unavailable,
+            "cannot connect to the system bus",
+        ),
+    ));
+    let value = serde_json::to_value(&combined).unwrap();
+    assert_eq!(
+        value["failures"][0]["error"],
+        "cannot connect to the system bus"
+    );
+    assert_eq!(value["failures"][0]["details"]["category"], "unavailable");
+    let back: CombinedReport = serde_json::from_value(value).unwrap();
+    assert_eq!(back, combined);
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Known issues loaded from rule files and matched against reports
+
+use ambulance_core::{CombinedReport, DiagnosticReport, Facts, KnowledgeBase};
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn report(tool: &str, sections: Value) -> DiagnosticReport {
+    let mut wire = sections;
+    wire["tool"] = json!(tool);
+    wire["version"] = json!("0.1.0");
+    DiagnosticReport::from_wire(&wire).unwrap()
+}
+
+fn reports() -> Vec<DiagnosticReport> {
+    vec![
+        report(
+            "kernel-ambulance",
+            json!({"firmware": {
+                "warnings": [
+                    "Missing firmware iwlwifi-so-a0-gf-a0-86.ucode (iwlwifi)",
+                    "Missing firmware amdgpu/gc_11_0_1_me.bin (amdgpu)"
+                ],
+                "recommendations": []
+            }}),
+        ),
+        report(
+            "journal-ambulance",
+            json!({"storage": {
+                "setting": "auto",
+                "mode": "volatile",
+                "persistent_dir": false,
+                "warnings": [],
+                "recommendations": []
+            }}),
+        ),
+    ]
+}
+
+const RULES: &str = r#"
+[[issue]]
+id = "iwlwifi-firmware"
+explanation = "iwlwifi needs firmware per card generation"
+suggestion = "Install it: kernel-ambulance repair firmware"
+
+[issue.when]
+finding = "kernel.firmware.*"
+summary = "(IWLWIFI)"
+
+[[issue]]
+id = "volatile"
+explanation = "Logs live in /run"
+
+[issue.when.property]
+tool = "journal"
+check = "storage"
+pointer = "/mode"
+equals = "volatile"
+
+[[issue]]
+id = "dropped"
+explanation = "journald dropped messages"
+
+[issue.when]
+message_id = "A596D6FE7BFA4994828E72309E95D61E"
+"#;
+
+#[test]
+fn rules_match_findings_properties_and_messages() {
+    let mut kb = KnowledgeBase::default();
+    kb.add_toml(RULES, "rules.toml").unwrap();
+    // In lowercase, as journald stores it, though the rule has capitals
+    assert_eq!(kb.message_ids(), ["a596d6fe7bfa4994828e72309e95d61e"]);
+
+    let mut combined = CombinedReport::new("laptop", 1_760_000_000);
+    combined.reports = reports();
+    combined.advise(&kb, &Facts::default());
+    let issues: Vec<&str> = combined.advice.iter().map(|a| a.issue.as_str()).collect();
+    assert_eq!(issues, ["iwlwifi-firmware", "volatile"]);
+    let firmware = &combined.advice[0];
+    assert_eq!(firmware.findings, ["kernel.firmware.0"]);
+    let rec = firmware.recommendation.as_ref().unwrap();
+    assert_eq!(rec.repairs, ["kernel.firmware"]);
+
+    let mut facts = Facts::default();
+    facts
+        .message_ids
+        .insert("a596d6fe7bfa4994828e72309e95d61e".to_string(), 3);
+    assert_eq!(kb.advise(&combined.reports, &facts).len(), 3);
+
+    let back: CombinedReport =
+        serde_json::from_str(&serde_json::to_string(&combined).unwrap()).unwrap();
+    assert_eq!(back, combined);
+}
+
+#[test]
+fn later_files_replace_issues_by_id() {
+    let mut kb = KnowledgeBase::default();
+    kb.add_toml(RULES, "rules.toml").unwrap();
+    let local = json!({"issue": [{
+        "id": "volatile",
+        "explanation": "Volatile on purpose here",
+        "when": {"property": {
+            "tool": "journal", "check": "storage", "pointer": "/setting", "equals": "volatile"
+        }}
+    }]});
+    kb.add_json(&local.to_string(), "local.json").unwrap();
+    assert_eq!(kb.issues.len(), 3);
+    let advice = kb.advise(&reports(), &Facts::default());
+    assert!(advice.iter().all(|a| a.issue != "volatile"));
+
+    assert!(kb.add_toml("format = 2", "future.toml").is_err());
+    let bad = "[[issue]]\nid = \"x\"\nexplanation = \"y\"\n[issue.when]\nsummary = \"z\"";
+    assert!(kb.add_toml(bad, "bad.toml").is_err());
+}
+
+#[test]
+fn shipped_known_issues_load() {
+    let mut kb = KnowledgeBase::default();
+    kb.add_path(&Path::new(env!("CARGO_MANIFEST_DIR")).join("known-issues"))
+        .unwrap();
+    assert!(kb.issues.len() >= 6);
+    let advice = kb.advise(&reports(), &Facts::default());
+    let issues: Vec<&str> = advice.iter().map(|a| a.issue.as_str()).collect();
+    assert_eq!(issues, ["journal-volatile-auto", "iwlwifi-firmware"]);
+}
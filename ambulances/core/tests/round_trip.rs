@@ -0,0 +1,276 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Serde round trips of the typed schema and of backend wire output
+
+use ambulance_core::{
+    Check, CheckStatus, CombinedReport, Confidence, DiagnosticReport, Evidence, Failure, Finding,
+    Recommendation, Repair, RepairOutcome, RepairReport, Section, Severity, Timing, SCHEMA_VERSION,
+};
+use serde_json::json;
+
+fn report() -> DiagnosticReport {
+    DiagnosticReport {
+        schema: SCHEMA_VERSION,
+        tool: "thermal-ambulance".to_string(),
+        version: "0.1.0".to_string(),
+        checks: vec![
+            Check {
+                id: "fans".to_string(),
+                status: CheckStatus::Failed,
+                findings: vec![Finding {
+                    id: "thermal.fans.0".to_string(),
+                    severity: Severity::Broken,
+                    confidence: Confidence::High,
+                    summary: "nct6775 fan2: stands still though driven at 78%".to_string(),
+                    evidence: vec![Evidence {
+                        source: "/sys/class/hwmon/hwmon1/fan2_input".to_string(),
+                        observed: "0".to_string(),
+                    }],
+                    recommendation: Some(Recommendation {
+                        text: "Replace the fan".to_string(),
+                        repairs: Vec::new(),
+                    }),
+                    repairs: vec!["thermal.fans".to_string()],
+                }],
+                recommendations: Vec::new(),
+                details: serde_json::Map::new(),
+            },
+            Check {
+                id: "sensors".to_string(),
+                status: CheckStatus::Passed,
+                findings: Vec::new(),
+                recommendations: vec![Recommendation::from_text("Nothing to do")],
+                details: json!({"zones": [{"name": "thermal_zone0", "celsius": 41.5}]})
+                    .as_object()
+                    .cloned()
+                    .unwrap(),
+            },
+        ],
+        timings: vec![
+            Timing {
+                id: "fans".to_string(),
+                millis: 12,
+                status: None,
+            },
+            Timing {
+                id: "sensors".to_string(),
+                millis: 3000,
+                status: Some("timed out after 3s".to_string()),
+            },
+        ],
+    }
+}
+
+#[test]
+fn diagnostic_report_round_trips() {
+    let report = report();
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(DiagnosticReport::parse(&json).unwrap(), report);
+}
+
+#[test]
+fn repair_report_round_trips() {
+    let report = RepairReport {
+        schema: SCHEMA_VERSION,
+        tool: "virtualization-ambulance".to_string(),
+        version: "0.1.0".to_string(),
+        repairs: vec![Repair {
+            id: "virtualization.networks".to_string(),
+            outcome: RepairOutcome {
+                success: false,
+                actions: vec!["Started network default".to_string()],
+                errors: vec!["Network lab not confirmed".to_string()],
+            },
+        }],
+    };
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains(r#""success":false"#), "outcome is flattened");
+    assert_eq!(RepairReport::parse(&json).unwrap(), report);
+}
+
+#[test]
+fn severity_serializes_lowercase_and_orders() {
+    assert_eq!(
+        serde_json::to_string(&Severity::Degraded).unwrap(),
+        r#""degraded""#
+    );
+    assert!(Severity::ALL.windows(2).all(|w| w[0] > w[1]));
+    assert_eq!(Severity::Warn.to_string(), "warn");
+}
+
+#[test]
+fn schema_1_reports_still_parse() {
+    let v1 = json!({
+        "schema": 1,
+        "tool": "thermal-ambulance",
+        "version": "0.1.0",
+        "checks": [{
+            "id": "fans",
+            "status": "failed",
+            "findings": [
+                {"id": "thermal.fans.0", "severity": "critical", "summary": "fan2 stopped"},
+                {"id": "thermal.fans.1", "severity": "warning", "summary": "fan1 held back"}
+            ]
+        }]
+    });
+    let report = DiagnosticReport::parse(&v1.to_string()).unwrap();
+    let findings: Vec<&Finding> = report.findings().collect();
+    assert_eq!(findings[0].severity, Severity::Broken);
+    assert_eq!(findings[1].severity, Severity::Warn);
+    assert_eq!(findings[0].confidence, Confidence::High);
+}
+
+#[test]
+fn recommendations_link_the_repairs_they_name() {
+    let r = Recommendation::from_text(
+        "Restart the daemon: container-ambulance repair daemon (then journalctl -u docker.service if it fails again)",
+    );
+    assert_eq!(r.repairs, vec!["container.daemon"]);
+    let r = Recommendation::from_text("Run sudo disk-ambulance repair fsck.");
+    assert_eq!(r.repairs, vec!["disk.fsck"]);
+    assert!(Recommendation::from_text("Clean the dust out of the vents")
+        .repairs
+        .is_empty());
+}
+
+#[test]
+fn newer_schema_is_refused() {
+    let mut value = serde_json::to_value(report()).unwrap();
+    value["schema"] = json!(SCHEMA_VERSION + 1);
+    let err = DiagnosticReport::parse(&value.to_string()).unwrap_err();
+    assert!(err.contains("schema"), "{}", err);
+}
+
+#[test]
+fn wire_sections_become_checks() {
+    let wire = json!({
+        "version": "0.1.0",
+        "tool": "thermal-ambulance",
+        "fans": {
+            "fans": [],
+            "warnings": ["nct6775 fan1: held at 15%", "nct6775 fan2: stands still"],
+            "recommendations": ["Hand the fans back to automatic control: thermal-ambulance repair fans"]
+        },
+        "sensors": {"chips": [], "warnings": [], "recommendations": []}
+    });
+    let report = DiagnosticReport::parse(&wire.to_string()).unwrap();
+    assert_eq!(report.schema, SCHEMA_VERSION);
+    assert_eq!(report.checks.len(), 2);
+    let fans = &report.checks[0];
+    assert_eq!(fans.status, CheckStatus::Failed);
+    assert_eq!(fans.findings[1].id, "thermal.fans.1");
+    assert_eq!(fans.findings[1].confidence, Confidence::Medium);
+    assert_eq!(fans.findings[1].repairs, vec!["thermal.fans"]);
+    assert_eq!(fans.recommendations[0].repairs, vec!["thermal.fans"]);
+    assert!(fans.details.contains_key("fans"));
+    assert_eq!(report.checks[1].status, CheckStatus::Passed);
+    assert_eq!(report.worst(), Some(Severity::Warn));
+    assert_eq!(report.repairs(), vec!["thermal.fans"]);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert_eq!(DiagnosticReport::parse(&json).unwrap(), report);
+}
+
+#[test]
+fn ranked_findings_keep_severity_and_remedy() {
+    let wire = json!({
+        "version": "0.1.0",
+        "tool": "security-ambulance",
+        "findings": [
+            {"severity": "critical", "section": "ssh", "title": "Root login with a password", "remedy": "Set PermitRootLogin no"},
+            {"severity": "low", "section": "path", "title": "Writable PATH entry", "remedy": "chmod o-w"}
+        ],
+        "ssh": {"config": "/etc/ssh/sshd_config"}
+    });
+    let report = DiagnosticReport::from_wire(&wire).unwrap();
+    let ssh = report.checks.iter().find(|c| c.id == "ssh").unwrap();
+    assert_eq!(ssh.findings[0].severity, Severity::Broken);
+    assert_eq!(
+        ssh.findings[0]
+            .recommendation
+            .as_ref()
+            .map(|r| r.text.as_str()),
+        Some("Set PermitRootLogin no")
+    );
+    assert_eq!(report.worst(), Some(Severity::Broken));
+    assert_eq!(report.findings().count(), 2);
+}
+
+#[test]
+fn wire_timings_are_not_a_section() {
+    let wire = json!({
+        "version": "0.1.0",
+        "tool": "disk-ambulance",
+        "smart": {"devices": [], "warnings": [], "recommendations": []},
+        "timings": [
+            {"id": "smart", "millis": 2400},
+            {"id": "filesystems", "millis": 3000, "status": "timed out after 3s"}
+        ]
+    });
+    let report = DiagnosticReport::from_wire(&wire).unwrap();
+    assert_eq!(report.checks.len(), 1);
+    assert_eq!(report.timings.len(), 2);
+    assert_eq!(report.timings[0].millis, 2400);
+    assert_eq!(
+        report.timings[1].status.as_deref(),
+        Some("timed out after 3s")
+    );
+}
+
+#[test]
+fn wire_repairs_skip_targets_that_did_not_run() {
+    let wire = json!({
+        "version": "0.1.0",
+        "tool": "thermal-ambulance",
+        "profile_repair": {"success": false, "actions": [], "errors": []},
+        "fans_repair": {"success": true, "actions": ["Handed fan1 back"], "errors": []}
+    });
+    let report = RepairReport::parse(&wire.to_string()).unwrap();
+    assert_eq!(report.repairs.len(), 1);
+    assert_eq!(report.repairs[0].id, "thermal.fans");
+    assert!(report.succeeded());
+}
+
+#[test]
+fn section_keeps_fields_it_does_not_know() {
+    let wire = json!({
+        "gateway_ip": "192.168.1.1",
+        "routes": [{"dest": "default"}],
+        "warnings": ["No IPv6 route"],
+        "recommendations": []
+    });
+    let section: Section = serde_json::from_value(wire.clone()).unwrap();
+    assert!(section.has_warnings());
+    assert_eq!(section["gateway_ip"], "192.168.1.1");
+    assert!(section["missing"].is_null());
+    assert_eq!(serde_json::to_value(&section).unwrap(), wire);
+}
+
+#[test]
+fn repair_outcome_matches_the_wire_format() {
+    let outcome = RepairOutcome::not_needed("Nothing to repair");
+    let value = serde_json::to_value(&outcome).unwrap();
+    assert_eq!(
+        value,
+        json!({"success": true, "actions": ["Nothing to repair"], "errors": []})
+    );
+    assert_eq!(
+        serde_json::from_value::<RepairOutcome>(value).unwrap(),
+        outcome
+    );
+}
+
+#[test]
+fn combined_report_round_trips() {
+    let mut combined = CombinedReport::new("workstation", 1_760_000_000);
+    combined.reports.push(report());
+    combined.failures.push(Failure {
+        tool: "virtualization-ambulance".to_string(),
+        error: "timed out after 120s".to_string(),
+        details: None,
+    });
+    let text = serde_json::to_string(&combined).unwrap();
+    let back: CombinedReport = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, combined);
+    assert_eq!(back.worst(), Some(Severity::Broken));
+    assert_eq!(back.count(Severity::Broken), 1);
+}
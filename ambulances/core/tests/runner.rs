@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Jobs run in dependency order, within the limit and their timeouts
+
+use ambulance_core::runner::{Job, Progress, Runner, Status};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn statuses<T>(outcomes: &[ambulance_core::runner::Outcome<T>]) -> Vec<(&str, Status)> {
+    outcomes.iter().map(|o| (o.id, o.status.clone())).collect()
+}
+
+#[test]
+fn outputs_flow_along_dependencies() {
+    let outcomes = Runner::new()
+        .job(
+            Job::blocking("total", |inputs| {
+                Ok(inputs.get("a").unwrap() + inputs.get("b").unwrap())
+            })
+            .after(&["a", "b"]),
+        )
+        .job(Job::blocking("a", |_| Ok(2)))
+        .job(Job::new("b", |_| async { Ok(3) }))
+        .block_on();
+    let outputs: Vec<(&str, Option<i32>)> = outcomes.iter().map(|o| (o.id, o.output)).collect();
+    assert_eq!(
+        outputs,
+        [("total", Some(5)), ("a", Some(2)), ("b", Some(3))]
+    );
+}
+
+#[test]
+fn failures_skip_what_needs_them() {
+    let outcomes = Runner::<()>::new()
+        .job(Job::blocking("bus", |_| Err("no system bus".to_string())))
+        .job(Job::blocking("units", |_| Ok(())).after(&["bus"]))
+        .job(Job::blocking("failed", |_| Ok(())).after(&["units"]))
+        .job(Job::blocking("typo", |_| Ok(())).after(&["buss"]))
+        .job(Job::blocking("x", |_| Ok(())).after(&["y"]))
+        .job(Job::blocking("y", |_| Ok(())).after(&["x"]))
+        .job(Job::blocking("panics", |_| panic!("bug")))
+        .block_on();
+    assert_eq!(
+        statuses(&outcomes),
+        [
+            ("bus", Status::Failed("no system bus".to_string())),
+            ("units", Status::Skipped("bus did not succeed".to_string())),
+            (
+                "failed",
+                Status::Skipped("units did not succeed".to_string())
+            ),
+            ("typo", Status::Skipped("no check buss".to_string())),
+            (
+                "x",
+                Status::Skipped("waits on a dependency cycle".to_string())
+            ),
+            (
+                "y",
+                Status::Skipped("waits on a dependency cycle".to_string())
+            ),
+            ("panics", Status::Failed("panicked".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn limit_bounds_jobs_at_once() {
+    let now = Arc::new(AtomicUsize::new(0));
+    let most = Arc::new(AtomicUsize::new(0));
+    let mut runner = Runner::new().limit(2);
+    for id in ["a", "b", "c", "d", "e"] {
+        let (now, most) = (now.clone(), most.clone());
+        runner = runner.job(Job::new(id, move |_| async move {
+            most.fetch_max(now.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            now.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }));
+    }
+    let outcomes = runner.block_on();
+    assert!(outcomes.iter().all(|o| o.status == Status::Succeeded));
+    assert_eq!(most.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn slow_jobs_time_out() {
+    let outcomes = Runner::new()
+        .timeout(Duration::from_millis(50))
+        .job(Job::new("hangs", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        }))
+        .job(
+            Job::new("patient", |_| async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(())
+            })
+            .timeout(Duration::from_secs(5)),
+        )
+        .block_on();
+    assert_eq!(
+        statuses(&outcomes),
+        [
+            ("hangs", Status::TimedOut(Duration::from_millis(50))),
+            ("patient", Status::Succeeded),
+        ]
+    );
+}
+
+#[test]
+fn an_output_held_by_a_timed_out_dependent_is_still_reported() {
+    let outcomes = Runner::new()
+        .timeout(Duration::from_millis(50))
+        .job(Job::blocking("sensors", |_| Ok(vec![42])))
+        .job(
+            Job::blocking("fans", |inputs| {
+                // Holds its inputs past the timeout
+                std::thread::sleep(Duration::from_millis(300));
+                Ok(inputs.get("sensors").cloned().unwrap_or_default())
+            })
+            .after(&["sensors"]),
+        )
+        .block_on();
+    assert_eq!(outcomes[0].status, Status::Succeeded);
+    assert_eq!(outcomes[0].output, Some(vec![42]));
+    assert_eq!(
+        outcomes[1].status,
+        Status::TimedOut(Duration::from_millis(50))
+    );
+    assert_eq!(outcomes[1].output, None);
+}
+
+#[test]
+fn the_budget_bounds_the_whole_run() {
+    let started = std::time::Instant::now();
+    let outcomes = Runner::new()
+        .limit(1)
+        .timeout(Duration::from_secs(60))
+        .budget(Duration::from_millis(100))
+        .job(Job::new("quick", |_| async { Ok(()) }))
+        .job(Job::new("hangs", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        }))
+        .job(Job::new("later", |_| async { Ok(()) }))
+        .block_on();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(outcomes[0].status, Status::Succeeded);
+    assert_eq!(outcomes[1].status, Status::OverBudget);
+    assert_eq!(outcomes[2].status, Status::OverBudget);
+
+    let timings: Vec<_> = outcomes.iter().map(|o| o.timing()).collect();
+    assert_eq!(timings[0].status, None);
+    assert!(timings[1].millis >= 50);
+    assert_eq!(
+        timings[2].status.as_deref(),
+        Some("stopped at the time budget")
+    );
+}
+
+#[tokio::test]
+async fn cancel_stops_the_run() {
+    let runner = Runner::new()
+        .job(Job::new("first", |_| async { Ok(()) }))
+        .job(
+            Job::new("slow", |_| async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            })
+            .after(&["first"]),
+        )
+        .job(Job::new("last", |_| async { Ok(()) }).after(&["slow"]));
+    let cancel = runner.canceller();
+    let run = tokio::spawn(runner.run());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    cancel.cancel();
+    let outcomes = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        statuses(&outcomes),
+        [
+            ("first", Status::Succeeded),
+            ("slow", Status::Cancelled),
+            ("last", Status::Cancelled),
+        ]
+    );
+}
+
+#[test]
+fn progress_reports_every_job() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    Runner::<()>::new()
+        .limit(1)
+        .job(Job::blocking("a", |_| Ok(())))
+        .job(Job::blocking("b", |_| Err("broken".to_string())).after(&["a"]))
+        .job(Job::blocking("c", |_| Ok(())).after(&["b"]))
+        .on_progress(move |p| {
+            log.lock().unwrap().push(match p {
+                Progress::Started { id } => format!("start {}", id),
+                Progress::Finished {
+                    id, done, total, ..
+                } => format!("{} {}/{}", id, done, total),
+            })
+        })
+        .block_on();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        ["start a", "a 1/3", "start b", "b 2/3", "c 3/3"]
+    );
+}
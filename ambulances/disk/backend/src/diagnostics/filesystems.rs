@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Filesystem repair advisor
+//!
+//! Finds filesystems that need checking (unclean state or recorded
+//! errors), fstab entries whose device is missing or whose mount point
+//! does not exist, and mount options that trade integrity for speed.
+//! `repair fsck` then schedules a check at next boot for the unclean ones.
+
+use crate::mtab::{self, MountEntry};
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use std::path::Path;
+
+/// Filesystems with no block device behind them
+const VIRTUAL_TYPES: &[&str] = &[
+    "tmpfs", "proc", "sysfs", "devpts", "devtmpfs", "cgroup", "cgroup2", "overlay", "nfs", "nfs4",
+    "cifs", "smbfs", "sshfs", "9p", "autofs",
+];
+
+/// Options that risk corruption or data exposure, with why
+const DANGEROUS_OPTIONS: &[(&str, &str)] = &[
+    (
+        "nobarrier",
+        "write barriers disabled; a power loss can corrupt the filesystem",
+    ),
+    (
+        "barrier=0",
+        "write barriers disabled; a power loss can corrupt the filesystem",
+    ),
+    (
+        "data=writeback",
+        "metadata may point at stale data after a crash",
+    ),
+    (
+        "errors=continue",
+        "the kernel keeps writing to a filesystem it knows is corrupt",
+    ),
+    (
+        "nodatasum",
+        "btrfs checksums disabled; silent corruption goes undetected",
+    ),
+    ("nodatacow", "btrfs copy-on-write and checksums disabled"),
+    (
+        "journal_async_commit",
+        "journal commit without waiting on the descriptor block",
+    ),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesystemState {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    /// As the filesystem reports it, e.g. `clean`, `not clean`
+    pub state: String,
+    pub error_count: u64,
+    /// Needs a check: unclean, or errors recorded
+    pub needs_check: bool,
+    /// Why the state could not be read
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FstabIssue {
+    pub spec: String,
+    pub mount_point: String,
+    pub problem: String,
+    /// `nofail` entries only lose that mount; others drop boot into emergency mode
+    pub blocks_boot: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OptionIssue {
+    pub mount_point: String,
+    pub option: String,
+    pub risk: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FilesystemDiagnostics {
+    pub states: Vec<FilesystemState>,
+    pub fstab_issues: Vec<FstabIssue>,
+    pub dangerous_options: Vec<OptionIssue>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn is_ext(fs_type: &str) -> bool {
+    matches!(fs_type, "ext2" | "ext3" | "ext4")
+}
+
+/// Read the ext2/3/4 superblock state with `dumpe2fs -h`
+fn ext_state(entry: &MountEntry) -> FilesystemState {
+    let mut state = FilesystemState {
+        device: entry.device.clone(),
+        mount_point: entry.mount_point.clone(),
+        fs_type: entry.fs_type.clone(),
+        state: "unknown".to_string(),
+        error_count: 0,
+        needs_check: false,
+        error: None,
+    };
+    match system::run("dumpe2fs", &["-h", &entry.device]) {
+        Ok(header) => {
+            for line in header.lines() {
+                let Some((key, value)) = line.split_once(':') else {
+                    continue;
+                };
+                match key.trim() {
+                    "Filesystem state" => state.state = value.trim().to_string(),
+                    "FS Error count" => state.error_count = value.trim().parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+            state.needs_check = state.state != "clean" || state.error_count > 0;
+        }
+        // dumpe2fs prefixes its error with a version banner
+        Err(e) => state.error = e.lines().last().map(str::to_string),
+    }
+    state
+}
+
+/// Sum btrfs per-device error counters with `btrfs device stats`
+fn btrfs_state(entry: &MountEntry) -> FilesystemState {
+    let mut state = FilesystemState {
+        device: entry.device.clone(),
+        mount_point: entry.mount_point.clone(),
+        fs_type: entry.fs_type.clone(),
+        state: "unknown".to_string(),
+        error_count: 0,
+        needs_check: false,
+        error: None,
+    };
+    // Exits non-zero when any counter is set, so read stdout regardless
+    match system::output("btrfs", &["device", "stats", &entry.mount_point]) {
+        Some(output) if !output.stdout.is_empty() => {
+            state.error_count = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|l| l.split_whitespace().nth(1)?.parse::<u64>().ok())
+                .sum();
+            state.state = if state.error_count > 0 {
+                "errors"
+            } else {
+                "clean"
+            }
+            .to_string();
+            state.needs_check = state.error_count > 0;
+        }
+        Some(output) => {
+            state.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+        None => state.error = Some("btrfs-progs not installed".to_string()),
+    }
+    state
+}
+
+/// State of every mounted ext and btrfs filesystem
+pub fn states() -> Vec<FilesystemState> {
+    let mut seen = Vec::new();
+    mtab::mounts()
+        .into_iter()
+        .filter(|m| m.is_block_device())
+        .filter(|m| {
+            let first = !seen.contains(&m.device);
+            seen.push(m.device.clone());
+            first
+        })
+        .filter_map(|m| match m.fs_type.as_str() {
+            t if is_ext(t) => Some(ext_state(&m)),
+            "btrfs" => Some(btrfs_state(&m)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The device node an fstab spec names, if it is a device at all
+fn resolve_spec(spec: &str) -> Option<String> {
+    const TAGS: &[(&str, &str)] = &[
+        ("UUID=", "/dev/disk/by-uuid/"),
+        ("LABEL=", "/dev/disk/by-label/"),
+        ("PARTUUID=", "/dev/disk/by-partuuid/"),
+        ("PARTLABEL=", "/dev/disk/by-partlabel/"),
+    ];
+    for (tag, dir) in TAGS {
+        if let Some(value) = spec.strip_prefix(tag) {
+            return Some(format!("{}{}", dir, value.trim_matches('"')));
+        }
+    }
+    spec.starts_with("/dev/").then(|| spec.to_string())
+}
+
+fn validate_fstab(fstab: &[MountEntry]) -> Vec<FstabIssue> {
+    let mut issues = Vec::new();
+    for entry in fstab {
+        if VIRTUAL_TYPES.contains(&entry.fs_type.as_str())
+            || entry.fs_type.starts_with("fuse")
+            || entry.has_option("bind")
+        {
+            continue;
+        }
+        let mut issue = |problem: String| {
+            issues.push(FstabIssue {
+                spec: entry.device.clone(),
+                mount_point: entry.mount_point.clone(),
+                problem,
+                blocks_boot: !entry.has_option("nofail") && !entry.has_option("noauto"),
+            })
+        };
+
+        if let Some(node) = resolve_spec(&entry.device) {
+            if !Path::new(&node).exists() {
+                issue(format!("device {} is not present", entry.device));
+            }
+        }
+        let is_swap = entry.fs_type == "swap" || entry.mount_point == "none";
+        if !is_swap && !Path::new(&entry.mount_point).is_dir() {
+            issue(format!("mount point {} does not exist", entry.mount_point));
+        }
+        if entry.mount_point == "/" && is_ext(&entry.fs_type) && entry.pass == 0 {
+            issue("root filesystem has fsck pass 0 and is never checked at boot".to_string());
+        }
+    }
+    issues
+}
+
+fn dangerous_options(entries: &[&MountEntry]) -> Vec<OptionIssue> {
+    let mut issues: Vec<OptionIssue> = Vec::new();
+    for entry in entries {
+        for (option, risk) in DANGEROUS_OPTIONS {
+            let present = match option.split_once('=') {
+                Some((key, value)) => entry.option_value(key) == Some(value),
+                None => entry.has_option(option),
+            };
+            let duplicate = issues
+                .iter()
+                .any(|i| i.mount_point == entry.mount_point && i.option == *option);
+            if present && !duplicate {
+                issues.push(OptionIssue {
+                    mount_point: entry.mount_point.clone(),
+                    option: option.to_string(),
+                    risk: risk.to_string(),
+                });
+            }
+        }
+    }
+    issues
+}
+
+pub fn diagnose() -> FilesystemDiagnostics {
+    let fstab = mtab::fstab();
+    let mounts = mtab::mounts();
+    let block_entries: Vec<&MountEntry> = mounts
+        .iter()
+        .filter(|m| m.is_block_device())
+        .chain(fstab.iter().filter(|f| resolve_spec(&f.device).is_some()))
+        .collect();
+
+    let mut diag = FilesystemDiagnostics {
+        states: states(),
+        fstab_issues: validate_fstab(&fstab),
+        dangerous_options: dangerous_options(&block_entries),
+        ..FilesystemDiagnostics::default()
+    };
+
+    for s in diag.states.iter().filter(|s| s.needs_check) {
+        diag.warnings.push(format!(
+            "{} ({}) needs a check: state '{}', {} recorded errors",
+            s.mount_point, s.device, s.state, s.error_count
+        ));
+    }
+    if diag
+        .states
+        .iter()
+        .any(|s| s.needs_check && is_ext(&s.fs_type))
+    {
+        diag.recommendations.push(
+            "Schedule a filesystem check at next boot: disk-ambulance repair fsck".to_string(),
+        );
+    }
+    if diag
+        .states
+        .iter()
+        .any(|s| s.needs_check && s.fs_type == "btrfs")
+    {
+        diag.recommendations.push(
+            "Run `btrfs scrub start` on btrfs filesystems with errors and check SMART".to_string(),
+        );
+    }
+    if !ambulance_privilege::is_elevated() && diag.states.iter().any(|s| s.error.is_some()) {
+        diag.recommendations
+            .push("Run as root to read filesystem superblocks".to_string());
+    }
+
+    for issue in &diag.fstab_issues {
+        diag.warnings.push(format!(
+            "fstab {}: {}{}",
+            issue.mount_point,
+            issue.problem,
+            if issue.blocks_boot {
+                " (boot will stop in emergency mode)"
+            } else {
+                ""
+            }
+        ));
+    }
+    if diag.fstab_issues.iter().any(|i| i.blocks_boot) {
+        diag.recommendations.push(
+            "Fix or remove stale fstab entries, or add `nofail` to removable ones".to_string(),
+        );
+    }
+
+    for issue in &diag.dangerous_options {
+        diag.warnings.push(format!(
+            "{} uses {}: {}",
+            issue.mount_point, issue.option, issue.risk
+        ));
+    }
+    if !diag.dangerous_options.is_empty() {
+        diag.recommendations.push(
+            "Remove the flagged options from /etc/fstab unless you accept the risk".to_string(),
+        );
+    }
+
+    diag
+}
+
+impl FilesystemDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Filesystem Diagnostics ===");
+        for s in &self.states {
+            match &s.error {
+                Some(e) if verbose => println!("- {}: state unknown ({})", s.mount_point, e),
+                Some(_) => println!("- {}: state unknown", s.mount_point),
+                None => println!(
+                    "{} {}: {} {}",
+                    mark(!s.needs_check),
+                    s.mount_point,
+                    s.fs_type,
+                    s.state
+                ),
+            }
+        }
+        println!(
+            "{} fstab: {} problem(s)",
+            mark(self.fstab_issues.is_empty()),
+            self.fstab_issues.len()
+        );
+        println!(
+            "{} Mount options: {} risky option(s)",
+            mark(self.dangerous_options.is_empty()),
+            self.dangerous_options.len()
+        );
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
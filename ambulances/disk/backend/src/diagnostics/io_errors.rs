@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Disk I/O error patterns in this boot's kernel log
+
+use crate::report::print_notes;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use systemd_shim::journal::{self, Journal};
+
+/// Kernel messages that indicate a failing disk, controller or filesystem
+const PATTERNS: &[&str] = &[
+    "I/O error",
+    "critical medium error",
+    "EXT4-fs error",
+    "metadata I/O error",
+    "Corruption detected",
+    "BTRFS error",
+    "failed command:",
+    "hard resetting link",
+    "timeout, aborting",
+];
+
+/// Sample lines kept for the report
+const MAX_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IoErrorDiagnostics {
+    pub journal_available: bool,
+    pub total: u64,
+    /// Matches per device, where the message names one
+    pub by_device: BTreeMap<String, u64>,
+    pub samples: Vec<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Device named in a kernel message, e.g. `sda1` from `dev sda1, sector`
+/// or `(device sda1)`
+pub fn device_of(line: &str) -> Option<String> {
+    const MARKERS: &[&str] = &["(device ", "dev ", "XFS ("];
+    MARKERS.iter().find_map(|marker| {
+        let start = line.find(marker)? + marker.len();
+        let name: String = line[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        let known = ["sd", "nvme", "vd", "hd", "mmcblk", "xvd", "dm-", "md"];
+        known
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+            .then_some(name)
+    })
+}
+
+pub fn matches(line: &str) -> bool {
+    PATTERNS.iter().any(|p| line.contains(p))
+}
+
+/// Kernel messages from the current boot, oldest first; `None` when the
+/// journal cannot be opened or holds none the user may read
+pub fn kernel_log() -> Option<Vec<String>> {
+    let read = || -> std::io::Result<Vec<String>> {
+        let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+        journal.add_match("_TRANSPORT=kernel")?;
+        journal.match_this_boot()?;
+        journal.seek_head()?;
+        let mut messages = Vec::new();
+        while journal.next_entry()? {
+            messages.extend(journal.field("MESSAGE"));
+        }
+        Ok(messages)
+    };
+    read().ok().filter(|messages| !messages.is_empty())
+}
+
+pub fn diagnose() -> IoErrorDiagnostics {
+    let mut diag = IoErrorDiagnostics::default();
+    let Some(log) = kernel_log() else {
+        diag.recommendations
+            .push("Run as root or in the systemd-journal group to scan the kernel log".to_string());
+        return diag;
+    };
+    diag.journal_available = true;
+
+    for line in log.iter().filter(|l| matches(l)) {
+        diag.total += 1;
+        if let Some(device) = device_of(line) {
+            *diag.by_device.entry(device).or_default() += 1;
+        }
+        if diag.samples.len() < MAX_SAMPLES {
+            diag.samples.push(line.to_string());
+        }
+    }
+
+    if diag.total > 0 {
+        diag.warnings.push(format!(
+            "{} disk I/O error messages in the kernel log this boot",
+            diag.total
+        ));
+        for (device, count) in &diag.by_device {
+            diag.warnings
+                .push(format!("{}: {} error messages", device, count));
+        }
+        diag.recommendations
+            .push("Back up important data from the affected devices".to_string());
+        diag.recommendations
+            .push("Check cables and SMART status; errors without SMART findings often mean a bad cable or port".to_string());
+    }
+
+    diag
+}
+
+impl IoErrorDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== I/O Error Diagnostics ===");
+        if !self.journal_available {
+            println!("- Kernel log not readable");
+        } else if self.total == 0 {
+            println!("✓ No disk I/O errors logged this boot");
+        } else {
+            println!("✗ {} disk I/O error messages this boot", self.total);
+            if verbose {
+                for sample in &self.samples {
+                    println!("  {}", sample);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Disk diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget: SMART waits on every drive in turn and should not hold
+//! up a report on free space. A section the budget cut short is left
+//! empty and its timing says why.
+
+pub mod filesystems;
+pub mod io_errors;
+pub mod mounts;
+pub mod smart;
+pub mod usage;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; a drive that stopped answering can
+/// hang smartctl, and a dead NFS server statfs
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+enum Section {
+    Smart(smart::SmartDiagnostics),
+    Usage(usage::UsageDiagnostics),
+    Mounts(mounts::MountDiagnostics),
+    IoErrors(io_errors::IoErrorDiagnostics),
+    Filesystems(filesystems::FilesystemDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let scan = Scan::from_env();
+    let cache = scan.cache(TOOL);
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(scan.budget)
+        .job(Job::blocking("smart", move |_| {
+            Ok(Section::Smart(smart::diagnose(&cache)))
+        }))
+        .job(Job::blocking("usage", |_| {
+            Ok(Section::Usage(usage::diagnose()))
+        }))
+        .job(Job::blocking("mounts", |_| {
+            Ok(Section::Mounts(mounts::diagnose()))
+        }))
+        .job(Job::blocking("io_errors", |_| {
+            Ok(Section::IoErrors(io_errors::diagnose()))
+        }))
+        .job(Job::blocking("filesystems", |_| {
+            Ok(Section::Filesystems(filesystems::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        smart: Default::default(),
+        usage: Default::default(),
+        mounts: Default::default(),
+        io_errors: Default::default(),
+        filesystems: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Smart(d)) => result.smart = d,
+            Some(Section::Usage(d)) => result.usage = d,
+            Some(Section::Mounts(d)) => result.mounts = d,
+            Some(Section::IoErrors(d)) => result.io_errors = d,
+            Some(Section::Filesystems(d)) => result.filesystems = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "smart" => &mut result.smart.warnings,
+                    "usage" => &mut result.usage.warnings,
+                    "mounts" => &mut result.mounts.warnings,
+                    "io_errors" => &mut result.io_errors.warnings,
+                    _ => &mut result.filesystems.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Filesystems the kernel remounted read-only
+//!
+//! ext4 with `errors=remount-ro`, XFS and btrfs all drop to read-only
+//! after a metadata error. We flag block-device filesystems mounted `ro`
+//! that fstab does not ask to be read-only.
+
+use crate::mtab::{self, MountEntry};
+use crate::report::print_notes;
+use serde::Serialize;
+
+/// Formats that are read-only by nature
+const READ_ONLY_TYPES: &[&str] = &["squashfs", "iso9660", "erofs", "udf"];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MountDiagnostics {
+    pub read_only: Vec<MountEntry>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Mounted read-only although not configured that way
+pub fn unexpected_read_only() -> Vec<MountEntry> {
+    let fstab = mtab::fstab();
+    mtab::mounts()
+        .into_iter()
+        .filter(|m| {
+            m.is_block_device()
+                && m.has_option("ro")
+                && !READ_ONLY_TYPES.contains(&m.fs_type.as_str())
+        })
+        .filter(|m| {
+            !fstab
+                .iter()
+                .any(|f| f.mount_point == m.mount_point && f.has_option("ro"))
+        })
+        .collect()
+}
+
+pub fn diagnose() -> MountDiagnostics {
+    let mut diag = MountDiagnostics {
+        read_only: unexpected_read_only(),
+        ..MountDiagnostics::default()
+    };
+
+    for m in &diag.read_only {
+        diag.warnings.push(format!(
+            "{} ({} on {}) is mounted read-only",
+            m.mount_point, m.fs_type, m.device
+        ));
+    }
+    if !diag.read_only.is_empty() {
+        diag.recommendations
+            .push("Check the I/O error section before remounting; a read-only flip usually follows disk errors".to_string());
+        diag.recommendations.push(
+            "Remount read-write once the cause is fixed: disk-ambulance repair mounts".to_string(),
+        );
+    }
+
+    diag
+}
+
+impl MountDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Mount Diagnostics ===");
+        if self.read_only.is_empty() {
+            println!("✓ No unexpected read-only filesystems");
+        }
+        for m in &self.read_only {
+            println!("✗ {}: {} mounted ro (READ-ONLY)", m.mount_point, m.fs_type);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,233 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! SMART health via `smartctl --json`
+//!
+//! Each drive's answer can take seconds, longer for one that has to spin
+//! up, so it is cached: a quick scan reuses an answer up to an hour old.
+//! Only answers with a health verdict are kept; one that failed for lack
+//! of permission is asked again next time, perhaps as root.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::cache::Cache;
+use ambulance_core::host;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::time::Duration;
+
+/// Drive temperature above which we warn, in °C
+const HOT_CELSIUS: i64 = 60;
+/// NVMe wear (percentage of rated endurance used) at which we warn
+const WORN_PERCENT: u64 = 90;
+
+/// How long a drive's SMART answer is reused
+const TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Kernel block devices that never carry SMART data
+const VIRTUAL_PREFIXES: &[&str] = &["loop", "ram", "zram", "dm-", "md", "sr", "fd", "nbd"];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SmartDevice {
+    pub name: String,
+    pub model: Option<String>,
+    pub protocol: Option<String>,
+    /// Overall health self-assessment
+    pub passed: Option<bool>,
+    pub temperature_c: Option<i64>,
+    pub power_on_hours: Option<u64>,
+    pub reallocated_sectors: Option<u64>,
+    pub pending_sectors: Option<u64>,
+    pub uncorrectable_sectors: Option<u64>,
+    pub media_errors: Option<u64>,
+    pub percentage_used: Option<u64>,
+    /// Why no SMART data could be read
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SmartDiagnostics {
+    pub smartctl_available: bool,
+    pub devices: Vec<SmartDevice>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Physical block devices, e.g. `sda`, `nvme0n1`
+pub fn block_devices() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(host::path("/sys/block"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok()?.file_name().into_string().ok())
+                .filter(|name| !VIRTUAL_PREFIXES.iter().any(|p| name.starts_with(p)))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Raw value of an ATA attribute by id
+fn ata_attribute(report: &Value, id: u64) -> Option<u64> {
+    report["ata_smart_attributes"]["table"]
+        .as_array()?
+        .iter()
+        .find(|a| a["id"].as_u64() == Some(id))?["raw"]["value"]
+        .as_u64()
+}
+
+/// `smartctl`'s JSON report on `name`, from `cache` while fresh
+fn smartctl(name: &str, cache: &Cache) -> Result<Value, String> {
+    let key = format!("smart-{}", name);
+    if let Some(report) = cache.get(&key, TTL) {
+        return Ok(report);
+    }
+    // smartctl's exit status is a bitmask that is non-zero for healthy
+    // drives with logged errors, so judge by the JSON instead
+    let path = format!("/dev/{}", name);
+    let output = system::output("smartctl", &["--json=c", "-H", "-A", "-i", &path])
+        .ok_or("smartctl could not be run")?;
+    let report: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("unreadable smartctl output: {}", e))?;
+    if report["smart_status"]["passed"].is_boolean() {
+        cache.put(&key, &report);
+    }
+    Ok(report)
+}
+
+fn read_device(name: &str, cache: &Cache) -> SmartDevice {
+    let mut device = SmartDevice {
+        name: name.to_string(),
+        ..SmartDevice::default()
+    };
+    let report = match smartctl(name, cache) {
+        Ok(report) => report,
+        Err(e) => {
+            device.error = Some(e);
+            return device;
+        }
+    };
+
+    device.model = report["model_name"].as_str().map(str::to_string);
+    device.protocol = report["device"]["protocol"].as_str().map(str::to_string);
+    device.passed = report["smart_status"]["passed"].as_bool();
+    device.temperature_c = report["temperature"]["current"].as_i64();
+    device.power_on_hours = report["power_on_time"]["hours"].as_u64();
+    device.reallocated_sectors = ata_attribute(&report, 5);
+    device.pending_sectors = ata_attribute(&report, 197);
+    device.uncorrectable_sectors = ata_attribute(&report, 198);
+
+    let nvme = &report["nvme_smart_health_information_log"];
+    device.media_errors = nvme["media_errors"].as_u64();
+    device.percentage_used = nvme["percentage_used"].as_u64();
+
+    if device.passed.is_none() {
+        device.error = report["smartctl"]["messages"]
+            .as_array()
+            .and_then(|messages| messages.iter().find(|m| m["severity"] == "error"))
+            .and_then(|m| m["string"].as_str())
+            .map(str::to_string)
+            .or_else(|| Some("SMART not supported".to_string()));
+    }
+    device
+}
+
+pub fn diagnose(cache: &Cache) -> SmartDiagnostics {
+    let mut diag = SmartDiagnostics {
+        smartctl_available: system::has("smartctl"),
+        ..SmartDiagnostics::default()
+    };
+
+    if !diag.smartctl_available {
+        diag.recommendations
+            .push("Install smartmontools to check drive health".to_string());
+        return diag;
+    }
+
+    diag.devices = block_devices()
+        .iter()
+        .map(|name| read_device(name, cache))
+        .collect();
+
+    for d in &diag.devices {
+        if d.passed == Some(false) {
+            diag.warnings
+                .push(format!("{}: SMART overall health check FAILED", d.name));
+            diag.recommendations
+                .push(format!("Back up {} now and plan its replacement", d.name));
+        }
+        for (count, what) in [
+            (d.reallocated_sectors, "reallocated sectors"),
+            (d.pending_sectors, "sectors pending reallocation"),
+            (d.uncorrectable_sectors, "uncorrectable sectors"),
+            (d.media_errors, "media errors"),
+        ] {
+            if let Some(n) = count.filter(|&n| n > 0) {
+                diag.warnings.push(format!("{}: {} {}", d.name, n, what));
+            }
+        }
+        if let Some(t) = d.temperature_c.filter(|&t| t > HOT_CELSIUS) {
+            diag.warnings
+                .push(format!("{}: temperature {}°C is high", d.name, t));
+            diag.recommendations
+                .push(format!("Check cooling and airflow around {}", d.name));
+        }
+        if let Some(used) = d.percentage_used.filter(|&u| u >= WORN_PERCENT) {
+            diag.warnings.push(format!(
+                "{}: {}% of rated write endurance used",
+                d.name, used
+            ));
+        }
+    }
+
+    if diag.devices.iter().any(|d| {
+        d.pending_sectors.is_some_and(|n| n > 0) || d.uncorrectable_sectors.is_some_and(|n| n > 0)
+    }) {
+        diag.recommendations
+            .push("Run a long SMART self-test: disk-ambulance repair smart".to_string());
+    }
+    if !ambulance_privilege::is_elevated() && diag.devices.iter().any(|d| d.error.is_some()) {
+        diag.recommendations
+            .push("Run as root to read SMART data from every drive".to_string());
+    }
+
+    diag
+}
+
+impl SmartDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== SMART Diagnostics ===");
+        if !self.smartctl_available {
+            println!("✗ smartctl not installed");
+        }
+        for d in &self.devices {
+            match (&d.error, d.passed) {
+                (Some(e), _) => println!("- /dev/{}: {}", d.name, e),
+                (None, passed) => {
+                    let ok = passed == Some(true);
+                    print!(
+                        "{} /dev/{}: SMART {}",
+                        mark(ok),
+                        d.name,
+                        if ok { "OK" } else { "FAILED" }
+                    );
+                    if let Some(hours) = d.power_on_hours {
+                        print!(", {} days power-on", hours / 24);
+                    }
+                    if let Some(t) = d.temperature_c {
+                        print!(", {}°C", t);
+                    }
+                    println!();
+                }
+            }
+            if verbose {
+                if let Some(model) = &d.model {
+                    println!("  Model: {}", model);
+                }
+                if let Some(protocol) = &d.protocol {
+                    println!("  Protocol: {}", protocol);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
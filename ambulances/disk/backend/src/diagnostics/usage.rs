@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Space and inode usage of mounted block-device filesystems
+
+use crate::mtab;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+
+/// Usage at which a filesystem is reported as nearly full
+pub const FULL_PERCENT: f64 = 90.0;
+
+/// Read-only image formats that are always 100% full by design
+const IMAGE_TYPES: &[&str] = &["squashfs", "iso9660", "erofs", "udf"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FilesystemUsage {
+    pub mount_point: String,
+    pub device: String,
+    pub fs_type: String,
+    pub size_bytes: u64,
+    pub available_bytes: u64,
+    pub used_percent: f64,
+    /// Zero for filesystems without a fixed inode table (btrfs, ...)
+    pub inodes_total: u64,
+    pub inodes_free: u64,
+    pub inodes_used_percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageDiagnostics {
+    pub filesystems: Vec<FilesystemUsage>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 * 100.0 / total as f64
+    }
+}
+
+fn statvfs(path: &str) -> Option<libc::statvfs> {
+    let path = CString::new(path).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    Some(unsafe { stat.assume_init() })
+}
+
+// statvfs field widths differ between targets
+#[allow(clippy::unnecessary_cast)]
+fn measure(entry: &mtab::MountEntry) -> Option<FilesystemUsage> {
+    let stat = statvfs(&entry.mount_point)?;
+    let fragment = stat.f_frsize as u64;
+    let size = stat.f_blocks as u64 * fragment;
+    // Unprivileged users cannot use the root-reserved blocks, so measure
+    // against what is actually available to them, as df does
+    let used = (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64);
+    let usable = used + stat.f_bavail as u64;
+    let inodes_total = stat.f_files as u64;
+    let inodes_free = stat.f_ffree as u64;
+
+    Some(FilesystemUsage {
+        mount_point: entry.mount_point.clone(),
+        device: entry.device.clone(),
+        fs_type: entry.fs_type.clone(),
+        size_bytes: size,
+        available_bytes: stat.f_bavail as u64 * fragment,
+        used_percent: percent(used, usable),
+        inodes_total,
+        inodes_free,
+        inodes_used_percent: percent(inodes_total.saturating_sub(inodes_free), inodes_total),
+    })
+}
+
+/// Mounted block-device filesystems, one entry per device
+pub fn measure_all() -> Vec<FilesystemUsage> {
+    let mut seen = Vec::new();
+    mtab::mounts()
+        .iter()
+        .filter(|m| m.is_block_device() && !IMAGE_TYPES.contains(&m.fs_type.as_str()))
+        // Bind mounts and btrfs subvolumes repeat the device
+        .filter(|m| {
+            let first = !seen.contains(&m.device);
+            seen.push(m.device.clone());
+            first
+        })
+        .filter_map(measure)
+        .collect()
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+pub fn diagnose() -> UsageDiagnostics {
+    let mut diag = UsageDiagnostics {
+        filesystems: measure_all(),
+        ..UsageDiagnostics::default()
+    };
+
+    for fs in &diag.filesystems {
+        if fs.used_percent >= FULL_PERCENT {
+            diag.warnings.push(format!(
+                "{} is {:.0}% full ({} free)",
+                fs.mount_point,
+                fs.used_percent,
+                human_bytes(fs.available_bytes)
+            ));
+        }
+        if fs.inodes_total > 0 && fs.inodes_used_percent >= FULL_PERCENT {
+            diag.warnings.push(format!(
+                "{} has used {:.0}% of its inodes",
+                fs.mount_point, fs.inodes_used_percent
+            ));
+            diag.recommendations.push(format!(
+                "Find directories with many small files under {} (caches, mail spools, session files)",
+                fs.mount_point
+            ));
+        }
+    }
+    if diag
+        .filesystems
+        .iter()
+        .any(|fs| fs.used_percent >= FULL_PERCENT)
+    {
+        diag.recommendations.push(
+            "Free space by vacuuming logs and caches: disk-ambulance repair space".to_string(),
+        );
+    }
+
+    diag
+}
+
+impl UsageDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Space Diagnostics ===");
+        for fs in &self.filesystems {
+            let ok = fs.used_percent < FULL_PERCENT
+                && (fs.inodes_total == 0 || fs.inodes_used_percent < FULL_PERCENT);
+            println!(
+                "{} {}: {} {:.0}% used ({} free)",
+                mark(ok),
+                fs.mount_point,
+                fs.fs_type,
+                fs.used_percent,
+                human_bytes(fs.available_bytes)
+            );
+            if verbose {
+                println!("  Device: {}", fs.device);
+                if fs.inodes_total > 0 {
+                    println!(
+                        "  Inodes: {:.0}% used ({} free)",
+                        fs.inodes_used_percent, fs.inodes_free
+                    );
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
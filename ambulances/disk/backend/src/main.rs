@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Disk Ambulance backend
+//!
+//! Diagnoses SMART health, space and inode exhaustion, read-only remounts
+//! and kernel I/O errors, and performs the matching repairs. `--json`
+//! output follows the network ambulance's report model.
+
+mod diagnostics;
+mod mtab;
+mod repairs;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Error};
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Disk Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: disk-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all disk diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+    println!("  -y, --yes            Approve changes that ask for confirmation");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Disk Ambulance");
+    println!("==============\n");
+    result.smart.print(verbose);
+    result.usage.print(verbose);
+    result.mounts.print(verbose);
+    result.io_errors.print(verbose);
+    result.filesystems.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("SMART", &result.smart.warnings),
+        ("Space", &result.usage.warnings),
+        ("Mounts", &result.mounts.warnings),
+        ("I/O errors", &result.io_errors.warnings),
+        ("Filesystems", &result.filesystems.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    exit::findings(result.has_warnings())
+}
+
+/// Ask on the terminal; without one (or in JSON mode) nothing is approved
+fn prompt(question: &str, json: bool) -> bool {
+    if json || !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn run_repair(target: &str, json: bool, yes: bool) -> ExitCode {
+    if let Err(e) = ambulance_privilege::require(&format!("disk-ambulance repair {}", target)) {
+        return fail(e, json);
+    }
+
+    let mut confirm = |question: &str| yes || prompt(question, json);
+    let result = match repairs::run(target, &mut confirm) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [
+        ("smart", "SMART Self-Test", &result.smart_repair),
+        ("space", "Space Repair", &result.space_repair),
+        ("mounts", "Mount Repair", &result.mounts_repair),
+        ("fsck", "Filesystem Check Scheduling", &result.fsck_repair),
+    ];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Disk Ambulance - Repair Mode");
+        println!("============================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let yes = args.iter().any(|a| a == "-y" || a == "--yes");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, json, yes),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Disk Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Mount tables: `/proc/self/mounts` and `/etc/fstab` share one format
+
+use ambulance_core::host;
+use serde::Serialize;
+use std::fs;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+    pub options: Vec<String>,
+    /// fsck order at boot (sixth field); 0 means never checked
+    pub pass: u32,
+}
+
+impl MountEntry {
+    pub fn has_option(&self, option: &str) -> bool {
+        self.options.iter().any(|o| o == option)
+    }
+
+    /// Value of a `key=value` option
+    pub fn option_value(&self, key: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find_map(|o| o.strip_prefix(key)?.strip_prefix('='))
+    }
+
+    /// Backed by a block device rather than a pseudo or network filesystem
+    pub fn is_block_device(&self) -> bool {
+        self.device.starts_with("/dev/")
+    }
+}
+
+/// Undo the octal escapes (`\040` for space, ...) used in mount tables
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|b| (b'0'..=b'7').contains(b)));
+        if let Some(digits) = octal {
+            out.push(
+                digits
+                    .iter()
+                    .fold(0u8, |acc, d| acc.wrapping_mul(8).wrapping_add(d - b'0')),
+            );
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub fn parse(text: &str) -> Vec<MountEntry> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(MountEntry {
+                device: unescape(fields.next()?),
+                mount_point: unescape(fields.next()?),
+                fs_type: fields.next()?.to_string(),
+                options: fields
+                    .next()
+                    .unwrap_or("defaults")
+                    .split(',')
+                    .map(str::to_string)
+                    .collect(),
+                pass: fields.nth(1).and_then(|p| p.parse().ok()).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// Currently mounted filesystems
+pub fn mounts() -> Vec<MountEntry> {
+    parse(&fs::read_to_string(host::path("/proc/self/mounts")).unwrap_or_default())
+}
+
+/// Configured filesystems
+pub fn fstab() -> Vec<MountEntry> {
+    parse(&fs::read_to_string("/etc/fstab").unwrap_or_default())
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Schedule fsck at next boot for filesystems that need it
+//!
+//! A mounted filesystem cannot be repaired safely, so nothing is checked
+//! now. ext2/3/4 get the superblock's force-fsck flag (`tune2fs -E
+//! force_fsck`), which makes the boot-time fsck do a full check. Each
+//! filesystem is only flagged after the caller confirms it.
+
+use crate::diagnostics::filesystems;
+use crate::report::RepairOutcome;
+use crate::system;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let pending: Vec<_> = filesystems::states()
+        .into_iter()
+        .filter(|s| s.needs_check)
+        .collect();
+    if pending.is_empty() {
+        return RepairOutcome::not_needed("All readable filesystems are clean, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for fs in &pending {
+        if !matches!(fs.fs_type.as_str(), "ext2" | "ext3" | "ext4") {
+            result.errors.push(format!(
+                "{}: cannot schedule a boot-time check for {}; boot once with fsck.mode=force",
+                fs.mount_point, fs.fs_type
+            ));
+            continue;
+        }
+
+        let question = format!(
+            "Schedule a full fsck of {} ({}) at next boot?",
+            fs.device, fs.mount_point
+        );
+        if !confirm(&question) {
+            result.errors.push(format!(
+                "{}: not confirmed, nothing scheduled (rerun with --yes to confirm)",
+                fs.mount_point
+            ));
+            continue;
+        }
+
+        match system::run("tune2fs", &["-E", "force_fsck", &fs.device]) {
+            Ok(_) => result.actions.push(format!(
+                "Scheduled fsck of {} ({}) for next boot",
+                fs.device, fs.mount_point
+            )),
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    if pending.iter().any(|fs| fs.mount_point == "/") && !result.actions.is_empty() {
+        result
+            .actions
+            .push("Reboot to run the check; / is checked before it is mounted".to_string());
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Disk repairs, one module per target
+
+pub mod fsck;
+pub mod mounts;
+pub mod smart;
+pub mod space;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["smart", "space", "mounts", "fsck", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change that needs explicit approval.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        smart_repair: if selected("smart") {
+            smart::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        space_repair: if selected("space") {
+            space::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        mounts_repair: if selected("mounts") {
+            mounts::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        fsck_repair: if selected("fsck") {
+            fsck::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Remount filesystems the kernel flipped to read-only
+//!
+//! Remounting over a disk that is still throwing errors risks further
+//! corruption, so devices with I/O errors logged this boot are refused
+//! and left for fsck.
+
+use crate::diagnostics::{io_errors, mounts};
+use crate::report::RepairOutcome;
+use crate::system;
+
+pub fn repair() -> RepairOutcome {
+    let read_only = mounts::unexpected_read_only();
+    if read_only.is_empty() {
+        return RepairOutcome::not_needed("No unexpected read-only filesystems, no repair needed");
+    }
+
+    let errors = io_errors::diagnose();
+    let mut result = RepairOutcome::default();
+
+    for m in &read_only {
+        let device = m.device.trim_start_matches("/dev/");
+        let failing = errors
+            .by_device
+            .keys()
+            .any(|d| device.starts_with(d.as_str()) || d.starts_with(device));
+        if failing {
+            result.errors.push(format!(
+                "{}: I/O errors were logged for {} this boot; check the filesystem with fsck instead of remounting",
+                m.mount_point, m.device
+            ));
+            continue;
+        }
+
+        match system::run("mount", &["-o", "remount,rw", &m.mount_point]) {
+            Ok(_) => result
+                .actions
+                .push(format!("Remounted {} read-write", m.mount_point)),
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Start SMART self-tests
+//!
+//! Drives run self-tests in the background and keep working normally, so
+//! this only starts them; results appear in a later `diagnose`.
+
+use crate::diagnostics::smart;
+use crate::report::{RepairOutcome, TOOL};
+use crate::system;
+use ambulance_core::cache::Cache;
+
+pub fn repair() -> RepairOutcome {
+    // Acts on the drives as they are now, and refreshes what is cached
+    let diag = smart::diagnose(&Cache::open(TOOL).refresh(true));
+    if !diag.smartctl_available {
+        return RepairOutcome {
+            success: false,
+            actions: Vec::new(),
+            errors: vec!["smartctl is not installed".to_string()],
+        };
+    }
+
+    let mut result = RepairOutcome::default();
+    for device in diag.devices.iter().filter(|d| d.passed.is_some()) {
+        // A long test reads every sector, which is what pending or
+        // uncorrectable sectors call for
+        let suspect = device.pending_sectors.is_some_and(|n| n > 0)
+            || device.uncorrectable_sectors.is_some_and(|n| n > 0)
+            || device.passed == Some(false);
+        let kind = if suspect { "long" } else { "short" };
+        let path = format!("/dev/{}", device.name);
+
+        match system::run("smartctl", &["-t", kind, &path]) {
+            Ok(_) => result
+                .actions
+                .push(format!("Started {} SMART self-test on {}", kind, path)),
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    if result.actions.is_empty() && result.errors.is_empty() {
+        return RepairOutcome::not_needed("No SMART-capable drives found");
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Reclaim space from logs and package caches
+//!
+//! Only regenerable data is removed: the journal's archived files are
+//! trimmed to a size cap and downloaded package archives are cleaned.
+//! User files are never touched.
+
+use crate::diagnostics::usage::{self, human_bytes, FULL_PERCENT};
+use crate::report::RepairOutcome;
+use crate::system;
+use systemd_shim::journal;
+
+/// Size the journal is vacuumed down to
+const JOURNAL_CAP: u64 = 500 << 20;
+
+/// Package manager cache cleanups, first installed one wins
+const PACKAGE_CLEANERS: &[(&str, &[&str])] = &[
+    ("apt-get", &["clean"]),
+    ("dnf", &["clean", "packages"]),
+    ("zypper", &["clean", "--all"]),
+    ("paccache", &["-r"]),
+];
+
+pub fn repair() -> RepairOutcome {
+    let before = usage::measure_all();
+    if !before.iter().any(|fs| fs.used_percent >= FULL_PERCENT) {
+        return RepairOutcome::not_needed("No filesystem is nearly full, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+
+    match journal::vacuum(JOURNAL_CAP) {
+        Ok(vacuum) => {
+            result.actions.push(format!(
+                "Vacuumed the journal to at most {}, removing {} archived file(s)",
+                human_bytes(JOURNAL_CAP),
+                vacuum.removed.len()
+            ));
+            for (file, e) in &vacuum.failed {
+                result
+                    .errors
+                    .push(format!("Could not remove {}: {}", file.path.display(), e));
+            }
+        }
+        Err(e) => result.errors.push(format!("Journal vacuum failed: {}", e)),
+    }
+
+    if let Some((tool, args)) = PACKAGE_CLEANERS.iter().find(|(tool, _)| system::has(tool)) {
+        match system::run(tool, args) {
+            Ok(_) => result.actions.push(format!(
+                "Cleaned package cache ({} {})",
+                tool,
+                args.join(" ")
+            )),
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    for fs in usage::measure_all() {
+        let Some(old) = before.iter().find(|b| b.device == fs.device) else {
+            continue;
+        };
+        let freed = fs.available_bytes.saturating_sub(old.available_bytes);
+        if freed > 0 {
+            result.actions.push(format!(
+                "{}: freed {} ({:.0}% used)",
+                fs.mount_point,
+                usage::human_bytes(freed),
+                fs.used_percent
+            ));
+        }
+    }
+
+    result.success = result.errors.is_empty();
+    result
+}
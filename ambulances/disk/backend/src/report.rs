@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    filesystems::FilesystemDiagnostics, io_errors::IoErrorDiagnostics, mounts::MountDiagnostics,
+    smart::SmartDiagnostics, usage::UsageDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "disk-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub smart: SmartDiagnostics,
+    pub usage: UsageDiagnostics,
+    pub mounts: MountDiagnostics,
+    pub io_errors: IoErrorDiagnostics,
+    pub filesystems: FilesystemDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.smart.warnings,
+            &self.usage.warnings,
+            &self.mounts.warnings,
+            &self.io_errors.warnings,
+            &self.filesystems.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub smart_repair: RepairOutcome,
+    pub space_repair: RepairOutcome,
+    pub mounts_repair: RepairOutcome,
+    pub fsck_repair: RepairOutcome,
+}
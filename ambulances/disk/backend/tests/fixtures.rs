@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The disk checks against broken fake systems
+
+use ambulance_testkit::{CheckExt, ReportExt, Script, System};
+use serde_json::json;
+
+const BINARY: &str = env!("CARGO_BIN_EXE_disk-ambulance");
+
+/// `smartctl --json=c -H -A -i /dev/<name>` answering with `report`
+fn smartctl(name: &str, report: serde_json::Value) -> Script {
+    let path = format!("/dev/{}", name);
+    Script::prints(&report.to_string()).when(&["--json=c", "-H", "-A", "-i", &path])
+}
+
+#[test]
+fn a_failing_drive_is_told_apart_from_a_worn_one() {
+    let mut system = System::new();
+    system
+        .block_device("sda", 953_869, true)
+        .block_device("nvme0n1", 476_940, false)
+        .block_device("loop0", 100, false)
+        .command(
+            "smartctl",
+            smartctl(
+                "sda",
+                json!({
+                    "model_name": "WDC WD10EZEX",
+                    "device": {"protocol": "ATA"},
+                    "smart_status": {"passed": false},
+                    "temperature": {"current": 41},
+                    "ata_smart_attributes": {"table": [
+                        {"id": 5, "raw": {"value": 12}},
+                        {"id": 197, "raw": {"value": 0}}
+                    ]}
+                }),
+            ),
+        )
+        .command(
+            "smartctl",
+            smartctl(
+                "nvme0n1",
+                json!({
+                    "model_name": "Samsung SSD 970",
+                    "device": {"protocol": "NVMe"},
+                    "smart_status": {"passed": true},
+                    "nvme_smart_health_information_log": {
+                        "media_errors": 0,
+                        "percentage_used": 97
+                    }
+                }),
+            ),
+        );
+    let report = system.diagnose(BINARY);
+
+    assert_eq!(
+        report.check("smart").summaries(),
+        [
+            "nvme0n1: 97% of rated write endurance used",
+            "sda: SMART overall health check FAILED",
+            "sda: 12 reallocated sectors"
+        ]
+    );
+    // Loop devices are not drives
+    assert!(!system
+        .calls()
+        .iter()
+        .any(|c| c.iter().any(|a| a == "/dev/loop0")));
+}
+
+#[test]
+fn a_filesystem_flipped_read_only_with_errors_is_found() {
+    let mut system = System::new();
+    system
+        .file(
+            "/proc/self/mounts",
+            "proc /proc proc rw,nosuid 0 0\n\
+             /dev/sdz1 /srv/fixture ext4 ro,relatime 0 0\n\
+             /dev/sr0 /media/cdrom iso9660 ro 0 0\n",
+        )
+        .command(
+            "dumpe2fs",
+            Script::prints(
+                "Filesystem volume name:   <none>\n\
+                 Filesystem state:         not clean with errors\n\
+                 FS Error count:           3\n",
+            )
+            .when(&["-h", "/dev/sdz1"]),
+        );
+    let report = system.diagnose(BINARY);
+
+    assert_eq!(
+        report.check("mounts").summaries(),
+        ["/srv/fixture (ext4 on /dev/sdz1) is mounted read-only"]
+    );
+    assert!(report.check("filesystems").summaries().contains(
+        &"/srv/fixture (/dev/sdz1) needs a check: state 'not clean with errors', 3 recorded errors"
+    ));
+}
+
+#[test]
+fn without_smartctl_drives_are_not_judged() {
+    let mut system = System::new();
+    system.block_device("sda", 953_869, true);
+    let report = system.diagnose(BINARY);
+
+    let smart = report.check("smart");
+    assert!(smart.summaries().is_empty(), "{:?}", report);
+    assert!(smart
+        .recommendations
+        .iter()
+        .any(|r| r.text == "Install smartmontools to check drive health"));
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Compositor and display server crashes
+//!
+//! systemd-coredump logs every crash with the process name and time.
+//! Crashes that start right after a monitor layout changed point at the
+//! layout, which mutter and KWin re-read at every login and hotplug.
+
+use crate::layout::LayoutFile;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// `MESSAGE_ID` of systemd-coredump's crash reports
+const COREDUMP_MESSAGE: &str = "fc2e22bc6ee647b6b90729ab34a250b1";
+
+/// Process names (`comm`, at most 15 characters) of compositors and
+/// display servers
+const COMPOSITORS: &[&str] = &[
+    "gnome-shell",
+    "mutter",
+    "kwin_wayland",
+    "kwin_x11",
+    "plasmashell",
+    "Xorg",
+    "Xwayland",
+    "sway",
+    "Hyprland",
+    "weston",
+    "gamescope",
+    "cosmic-comp",
+    "labwc",
+    "wayfire",
+];
+
+const WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// A layout change this long before the first crash counts as its cause
+const LAYOUT_LEAD_SECS: u64 = 24 * 60 * 60;
+
+/// Coredump entries read at most
+const MAX_ENTRIES: usize = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Crashes {
+    pub process: String,
+    pub count: u32,
+    /// Seconds since the epoch
+    pub first: u64,
+    pub last: u64,
+    /// Signal of the most recent crash, e.g. `SIGSEGV`
+    pub last_signal: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CompositorDiagnostics {
+    pub crashes: Vec<Crashes>,
+    /// Layout files changed shortly before the crashes began
+    pub suspect_layouts: Vec<String>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn crashes(since: u64) -> std::io::Result<Vec<Crashes>> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match(&format!("MESSAGE_ID={}", COREDUMP_MESSAGE))?;
+    journal.seek_tail()?;
+    let mut by_process: BTreeMap<String, Crashes> = BTreeMap::new();
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? {
+            break;
+        }
+        let Some(time) = journal
+            .field("COREDUMP_TIMESTAMP")
+            .and_then(|t| t.parse::<u64>().ok())
+            .map(|usec| usec / 1_000_000)
+        else {
+            continue;
+        };
+        // Newest first, so the first old entry ends the window
+        if time < since {
+            break;
+        }
+        let Some(comm) = journal.field("COREDUMP_COMM") else {
+            continue;
+        };
+        if !COMPOSITORS.contains(&comm.as_str()) {
+            continue;
+        }
+        let signal = journal.field("COREDUMP_SIGNAL_NAME");
+        let entry = by_process.entry(comm.clone()).or_insert(Crashes {
+            process: comm,
+            count: 0,
+            first: time,
+            last: time,
+            last_signal: signal,
+        });
+        entry.count += 1;
+        entry.first = time;
+    }
+    Ok(by_process.into_values().collect())
+}
+
+pub fn diagnose(layouts: &[LayoutFile]) -> CompositorDiagnostics {
+    let now = now_secs();
+    let mut diag = CompositorDiagnostics::default();
+    match crashes(now.saturating_sub(WINDOW_SECS)) {
+        Ok(crashes) => diag.crashes = crashes,
+        Err(e) => {
+            diag.error = Some(e.to_string());
+            diag.warnings
+                .push(format!("Cannot read the journal: {}", e));
+            return diag;
+        }
+    }
+    let Some(first) = diag.crashes.iter().map(|c| c.first).min() else {
+        return diag;
+    };
+
+    for c in &diag.crashes {
+        diag.warnings.push(format!(
+            "{} crashed {} time(s) in the last 7 days{}",
+            c.process,
+            c.count,
+            c.last_signal
+                .as_deref()
+                .map_or(String::new(), |s| format!(" (last: {})", s))
+        ));
+    }
+    diag.suspect_layouts = layouts
+        .iter()
+        .filter(|l| {
+            l.modified
+                .is_some_and(|m| m <= first && first - m <= LAYOUT_LEAD_SECS)
+        })
+        .map(|l| l.path.clone())
+        .collect();
+    if !diag.suspect_layouts.is_empty() {
+        diag.warnings.push(format!(
+            "The crashes began within a day of a change to {}",
+            diag.suspect_layouts.join(", ")
+        ));
+        diag.recommendations
+            .push("Start over from a default layout: display-ambulance repair layout".to_string());
+    }
+    diag.recommendations.push(format!(
+        "Inspect the backtrace: coredumpctl info {}",
+        diag.crashes
+            .iter()
+            .max_by_key(|c| c.last)
+            .map_or("", |c| c.process.as_str())
+    ));
+    diag
+}
+
+impl CompositorDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Compositor ===");
+        if let Some(error) = &self.error {
+            println!("- Crashes: unknown ({})", error);
+            println!();
+            return;
+        }
+        let total: u32 = self.crashes.iter().map(|c| c.count).sum();
+        println!("{} Crashes in 7 days: {}", mark(total == 0), total);
+        if verbose {
+            for c in &self.crashes {
+                println!("    {}: {} crash(es)", c.process, c.count);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
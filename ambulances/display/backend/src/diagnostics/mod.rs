@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Display diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs within the scan's budget:
+//! scaling compares the layout files with the outputs found, and the
+//! compositor's checks read those layout files, so each waits on the one
+//! before it. When one is not done in time the sections after it are
+//! skipped, saying so in their warnings; a section the budget cut short
+//! is left empty and its timing says why.
+
+pub mod compositor;
+pub mod outputs;
+pub mod scaling;
+
+use crate::layout;
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Inputs, Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; a large journal is slow to search for
+/// the compositor's crashes
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Outputs(outputs::OutputDiagnostics),
+    Scaling(scaling::ScalingDiagnostics),
+    Compositor(compositor::CompositorDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("outputs", |_| {
+            Ok(Section::Outputs(outputs::diagnose()))
+        }))
+        .job(
+            Job::blocking("scaling", |inputs: Inputs<Section>| {
+                let Some(Section::Outputs(outputs)) = inputs.get("outputs") else {
+                    return Err("no outputs section".to_string());
+                };
+                let homes = layout::homes();
+                let layouts = layout::find(&homes);
+                Ok(Section::Scaling(scaling::diagnose(
+                    outputs, &homes, layouts,
+                )))
+            })
+            .after(&["outputs"]),
+        )
+        .job(
+            Job::blocking("compositor", |inputs: Inputs<Section>| {
+                let Some(Section::Scaling(scaling)) = inputs.get("scaling") else {
+                    return Err("no scaling section".to_string());
+                };
+                Ok(Section::Compositor(compositor::diagnose(&scaling.layouts)))
+            })
+            .after(&["scaling"]),
+        )
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        outputs: Default::default(),
+        compositor: Default::default(),
+        scaling: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Outputs(d)) => result.outputs = d,
+            Some(Section::Scaling(d)) => result.scaling = d,
+            Some(Section::Compositor(d)) => result.compositor = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "outputs" => &mut result.outputs.warnings,
+                    "scaling" => &mut result.scaling.warnings,
+                    _ => &mut result.compositor.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
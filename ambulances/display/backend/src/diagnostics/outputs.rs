@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Connected outputs and their EDID
+//!
+//! DRM connectors are the `cardN-<connector>` devices of the `drm`
+//! subsystem. sd-device gives their status, the modes the kernel offers
+//! and the raw EDID, which names the monitor and gives its native mode
+//! and physical size, and so its pixel density.
+
+use crate::edid::{self, Edid};
+use crate::layout::Scale;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::device;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Output {
+    /// e.g. `card1`
+    pub card: String,
+    /// e.g. `DP-2` or `HDMI-A-1`
+    pub connector: String,
+    /// `connected`, `disconnected` or `unknown`
+    pub status: String,
+    pub enabled: bool,
+    /// Modes the kernel offers, preferred first
+    pub modes: Vec<String>,
+    pub edid: Option<Edid>,
+    /// Why the EDID could not be read, for a connected output
+    pub edid_error: Option<String>,
+    pub dpi: Option<f64>,
+}
+
+impl Output {
+    pub fn connected(&self) -> bool {
+        self.status == "connected"
+    }
+
+    /// Whether a layout entry is for this output: GNOME names `HDMI-A-n`
+    /// `HDMI-n`, and records the monitor's vendor and name when it can
+    pub fn matches(&self, scale: &Scale) -> bool {
+        let connector = self.connector == scale.connector
+            || self.connector.replacen("HDMI-A-", "HDMI-", 1) == scale.connector;
+        let monitor = match &self.edid {
+            Some(edid) => {
+                scale
+                    .vendor
+                    .as_ref()
+                    .map_or(true, |v| *v == edid.manufacturer)
+                    && scale
+                        .product
+                        .as_ref()
+                        .map_or(true, |p| Some(p) == edid.name.as_ref())
+            }
+            None => true,
+        };
+        connector && monitor
+    }
+
+    pub fn monitor(&self) -> String {
+        match &self.edid {
+            Some(edid) => edid
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{} {:04x}", edid.manufacturer, edid.product_code)),
+            None => "unknown monitor".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutputDiagnostics {
+    pub outputs: Vec<Output>,
+    /// Why devices could not be enumerated
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn outputs() -> std::io::Result<Vec<Output>> {
+    Ok(device::enumerate("drm")?
+        .iter()
+        .filter_map(|d| {
+            let name = d.sysname()?;
+            // Cards are `card0`; connectors `card0-DP-1`
+            let (card, connector) = name.split_once('-')?;
+            let status = d.sysattr("status")?;
+            let (edid, edid_error) = if status != "connected" {
+                (None, None)
+            } else {
+                match d.sysattr_bytes("edid").filter(|b| !b.is_empty()) {
+                    Some(bytes) => match edid::parse(&bytes) {
+                        Ok(edid) => (Some(edid), None),
+                        Err(e) => (None, Some(e)),
+                    },
+                    None => (None, Some("no EDID".to_string())),
+                }
+            };
+            Some(Output {
+                card: card.to_string(),
+                connector: connector.to_string(),
+                enabled: d.sysattr("enabled").as_deref() == Some("enabled"),
+                modes: d
+                    .sysattr("modes")
+                    .unwrap_or_default()
+                    .lines()
+                    .map(str::to_string)
+                    .collect(),
+                dpi: edid.as_ref().and_then(Edid::dpi),
+                status,
+                edid,
+                edid_error,
+            })
+        })
+        .collect())
+}
+
+pub fn diagnose() -> OutputDiagnostics {
+    let mut diag = OutputDiagnostics::default();
+    match outputs() {
+        Ok(mut outputs) => {
+            outputs.sort_by(|a, b| (&a.card, &a.connector).cmp(&(&b.card, &b.connector)));
+            diag.outputs = outputs;
+        }
+        Err(e) => {
+            diag.error = Some(e.to_string());
+            diag.warnings
+                .push(format!("Cannot enumerate DRM devices: {}", e));
+            return diag;
+        }
+    }
+
+    if !diag.outputs.is_empty() && !diag.outputs.iter().any(Output::connected) {
+        diag.warnings
+            .push("No output reports a connected monitor".to_string());
+        diag.recommendations.push(
+            "Check the cable and the monitor's input source; docks and adapters often need to be powered"
+                .to_string(),
+        );
+    }
+    for output in diag.outputs.iter().filter(|o| o.connected()) {
+        if let Some(e) = &output.edid_error {
+            diag.warnings.push(format!(
+                "{} is connected but its EDID cannot be used ({}), so only fallback modes are offered",
+                output.connector, e
+            ));
+            diag.recommendations.push(format!(
+                "Reseat or replace the cable on {}; switches and adapters often drop EDID. drm.edid_firmware= can load a saved copy",
+                output.connector
+            ));
+        } else if output.modes.is_empty() {
+            diag.warnings.push(format!(
+                "{} is connected but the kernel offers no modes for it",
+                output.connector
+            ));
+        }
+        let native = output
+            .edid
+            .as_ref()
+            .and_then(|e| e.preferred.as_ref())
+            .map(|m| format!("{}x{}", m.width, m.height));
+        if let Some(native) = native {
+            if !output.modes.is_empty() && !output.modes.contains(&native) {
+                diag.warnings.push(format!(
+                    "{}'s native {} is not among the modes offered for {}",
+                    output.monitor(),
+                    native,
+                    output.connector
+                ));
+                diag.recommendations.push(format!(
+                    "The link may not carry {}: try a cable or adapter rated for it, or connect the monitor directly",
+                    native
+                ));
+            }
+        }
+    }
+    diag
+}
+
+impl OutputDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Outputs ===");
+        if let Some(error) = &self.error {
+            println!("- Outputs: unknown ({})", error);
+            println!();
+            return;
+        }
+        let connected: Vec<&Output> = self.outputs.iter().filter(|o| o.connected()).collect();
+        println!(
+            "{} Connected: {} of {} output(s)",
+            mark(!connected.is_empty() || self.outputs.is_empty()),
+            connected.len(),
+            self.outputs.len()
+        );
+        for output in &self.outputs {
+            if !verbose && !output.connected() {
+                continue;
+            }
+            let mode = output
+                .edid
+                .as_ref()
+                .and_then(|e| e.preferred.as_ref())
+                .map(|m| format!(", {}x{}@{}Hz", m.width, m.height, m.refresh_hz))
+                .unwrap_or_default();
+            let dpi = output
+                .dpi
+                .map(|d| format!(", {:.0} dpi", d))
+                .unwrap_or_default();
+            if output.connected() {
+                println!(
+                    "  {} {}: {}{}{}{}",
+                    mark(output.edid_error.is_none()),
+                    output.connector,
+                    output.monitor(),
+                    mode,
+                    dpi,
+                    if output.enabled { "" } else { " (disabled)" }
+                );
+            } else {
+                println!("  - {}: {}", output.connector, output.status);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Scaling: layout files, environment variables and font DPI
+//!
+//! Each monitor's scale is set in the desktop's layout file. It goes
+//! wrong in a few common ways: a fractional scale that mutter is not
+//! allowed to use, so it throws the whole layout away; a scale far from
+//! what the monitor's pixel density calls for; and toolkit variables or a
+//! forced font DPI that scale a second time on top of the desktop.
+
+use crate::diagnostics::outputs::OutputDiagnostics;
+use crate::layout::{self, Home, LayoutFile};
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Pixel density at scale 1
+const BASE_DPI: f64 = 96.0;
+
+/// How far a scale may stray from what the density suggests, as a factor
+const SCALE_TOLERANCE: f64 = 1.5;
+
+/// Toolkit variables that scale on their own
+const VARIABLES: &[&str] = &[
+    "GDK_SCALE",
+    "GDK_DPI_SCALE",
+    "QT_SCALE_FACTOR",
+    "QT_SCREEN_SCALE_FACTORS",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Variable {
+    pub file: String,
+    /// User the file belongs to; `None` for system-wide files
+    pub user: Option<String>,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScalingDiagnostics {
+    pub layouts: Vec<LayoutFile>,
+    pub variables: Vec<Variable>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// `KEY=value` files read into the session environment
+fn environment_files(homes: &[Home]) -> Vec<(PathBuf, Option<String>)> {
+    let conf_files = |dir: &Path| -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().is_some_and(|x| x == "conf"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    };
+    let mut files = vec![(PathBuf::from("/etc/environment"), None)];
+    files.extend(
+        conf_files(Path::new("/etc/environment.d"))
+            .into_iter()
+            .map(|f| (f, None)),
+    );
+    for home in homes {
+        files.extend(
+            conf_files(&home.path.join(".config/environment.d"))
+                .into_iter()
+                .map(|f| (f, Some(home.user.clone()))),
+        );
+    }
+    files
+}
+
+fn variables(homes: &[Home]) -> Vec<Variable> {
+    let mut found = Vec::new();
+    for (file, user) in environment_files(homes) {
+        let Some(text) = system::read(&file) else {
+            continue;
+        };
+        for line in text.lines().map(str::trim) {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            if VARIABLES.contains(&name.trim()) {
+                found.push(Variable {
+                    file: file.display().to_string(),
+                    user: user.clone(),
+                    name: name.trim().to_string(),
+                    value: value
+                        .trim()
+                        .trim_matches(|c| c == '"' || c == '\'')
+                        .to_string(),
+                });
+            }
+        }
+    }
+    found
+}
+
+/// `forceFontDPI` from a Plasma user's font settings, when it is forced
+fn forced_font_dpi(home: &Home) -> Option<u32> {
+    system::read(home.path.join(".config/kcmfonts"))?
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("forceFontDPI="))
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|dpi| *dpi > 0)
+}
+
+/// Why a layout file is a problem, if it is
+fn layout_problem(layout: &LayoutFile, home: &Home, outputs: &OutputDiagnostics) -> Option<String> {
+    if let Some(error) = &layout.error {
+        return Some(format!("cannot be read ({})", error));
+    }
+    if layout.desktop == "GNOME" {
+        if let Some(s) = layout.scales.iter().find(|s| s.scale.fract() != 0.0) {
+            if !layout::fractional_enabled(&home.path) {
+                return Some(format!(
+                    "sets scale {} on {} but fractional scaling is not enabled, so mutter rejects the layout",
+                    s.scale, s.connector
+                ));
+            }
+        }
+    }
+    for output in outputs.outputs.iter().filter(|o| o.connected()) {
+        let Some(dpi) = output.dpi else {
+            continue;
+        };
+        let suggested = (dpi / BASE_DPI).max(1.0);
+        for s in layout.scales.iter().filter(|s| output.matches(s)) {
+            if s.scale >= suggested * SCALE_TOLERANCE {
+                return Some(format!(
+                    "scales {} ({:.0} dpi) by {}, leaving little room on screen",
+                    output.connector, dpi, s.scale
+                ));
+            }
+            if s.scale * SCALE_TOLERANCE <= suggested {
+                return Some(format!(
+                    "scales {} ({:.0} dpi) by only {}, leaving text tiny",
+                    output.connector, dpi, s.scale
+                ));
+            }
+        }
+    }
+    None
+}
+
+pub fn diagnose(
+    outputs: &OutputDiagnostics,
+    homes: &[Home],
+    layouts: Vec<LayoutFile>,
+) -> ScalingDiagnostics {
+    let mut diag = ScalingDiagnostics {
+        layouts,
+        variables: variables(homes),
+        ..ScalingDiagnostics::default()
+    };
+
+    for layout in &mut diag.layouts {
+        let Some(home) = homes.iter().find(|h| h.user == layout.user) else {
+            continue;
+        };
+        layout.problem = layout_problem(layout, home, outputs);
+        if let Some(problem) = &layout.problem {
+            diag.warnings.push(format!(
+                "{} layout of {} {}",
+                layout.desktop, layout.user, problem
+            ));
+        }
+    }
+    if diag.layouts.iter().any(|l| l.problem.is_some()) {
+        diag.recommendations.push(
+            "Move the layout aside so the desktop starts from defaults: display-ambulance repair layout"
+                .to_string(),
+        );
+    }
+
+    let scaled_users: Vec<&str> = diag
+        .layouts
+        .iter()
+        .filter(|l| l.scales.iter().any(|s| s.scale > 1.0))
+        .map(|l| l.user.as_str())
+        .collect();
+    for v in &diag.variables {
+        if v.name == "GDK_SCALE" && v.value.parse::<u32>().is_err() {
+            diag.warnings.push(format!(
+                "GDK_SCALE={} in {} is ignored: GTK only takes whole numbers",
+                v.value, v.file
+            ));
+            diag.recommendations.push(
+                "Use the desktop's fractional scaling instead, or GDK_DPI_SCALE for text only"
+                    .to_string(),
+            );
+            continue;
+        }
+        let scales = v
+            .value
+            .split([';', ',', '='])
+            .filter_map(|f| f.parse::<f64>().ok())
+            .any(|f| f > 1.0);
+        let doubled = match &v.user {
+            Some(user) => scaled_users.contains(&user.as_str()),
+            None => !scaled_users.is_empty(),
+        };
+        if scales && doubled {
+            diag.warnings.push(format!(
+                "{}={} in {} scales apps on top of the desktop's own scale",
+                v.name, v.value, v.file
+            ));
+            diag.recommendations.push(format!(
+                "Remove {} from {}; the desktop scale already applies to these apps",
+                v.name, v.file
+            ));
+        }
+    }
+
+    for home in homes {
+        let Some(dpi) = forced_font_dpi(home) else {
+            continue;
+        };
+        let kde_scaled = diag.layouts.iter().any(|l| {
+            l.user == home.user && l.desktop == "KDE" && l.scales.iter().any(|s| s.scale > 1.0)
+        });
+        if kde_scaled {
+            diag.warnings.push(format!(
+                "{} forces font DPI {} and also scales the screen, so text is scaled twice",
+                home.user, dpi
+            ));
+            diag.recommendations.push(format!(
+                "Turn off Force font DPI in System Settings > Text & Fonts for {} (forceFontDPI=0 in ~/.config/kcmfonts)",
+                home.user
+            ));
+        }
+    }
+    diag
+}
+
+impl ScalingDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Scaling ===");
+        let troubled = self.layouts.iter().filter(|l| l.problem.is_some()).count();
+        println!(
+            "{} Layouts: {} ({} with problems)",
+            mark(troubled == 0),
+            self.layouts.len(),
+            troubled
+        );
+        if verbose {
+            for layout in &self.layouts {
+                let scales: Vec<String> = layout
+                    .scales
+                    .iter()
+                    .map(|s| format!("{} x{}", s.connector, s.scale))
+                    .collect();
+                println!(
+                    "  {} {} ({}, {}): {}",
+                    mark(layout.problem.is_none()),
+                    layout.path,
+                    layout.desktop,
+                    layout.user,
+                    if scales.is_empty() {
+                        "no scales".to_string()
+                    } else {
+                        scales.join(", ")
+                    }
+                );
+            }
+            for v in &self.variables {
+                println!("  - {}={} ({})", v.name, v.value, v.file);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
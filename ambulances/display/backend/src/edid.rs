@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! EDID base blocks
+//!
+//! Only the 128-byte base block is read: who made the monitor, its name,
+//! its physical size and the preferred mode from the first detailed
+//! timing. Extension blocks (CTA, DisplayID) are skipped.
+
+use serde::Serialize;
+
+const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Physical sizes below this are aspect ratios or placeholders, not sizes
+const MIN_WIDTH_MM: u32 = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Mode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_hz: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Edid {
+    /// Three-letter PNP vendor ID, e.g. `DEL`
+    pub manufacturer: String,
+    pub product_code: u16,
+    pub name: Option<String>,
+    pub serial: Option<String>,
+    pub width_mm: u32,
+    pub height_mm: u32,
+    pub preferred: Option<Mode>,
+}
+
+impl Edid {
+    /// Horizontal pixels per inch of the preferred mode, when the size is real
+    pub fn dpi(&self) -> Option<f64> {
+        let mode = self.preferred.as_ref()?;
+        (self.width_mm >= MIN_WIDTH_MM).then(|| mode.width as f64 * 25.4 / self.width_mm as f64)
+    }
+}
+
+/// The text of a display descriptor, up to its newline terminator
+fn descriptor_text(data: &[u8]) -> Option<String> {
+    let text: String = data
+        .iter()
+        .take_while(|b| **b != 0x0a)
+        .map(|b| *b as char)
+        .collect();
+    let text = text.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Edid, String> {
+    let Some(base) = bytes.get(..128) else {
+        return Err(format!("{} bytes, shorter than a base block", bytes.len()));
+    };
+    if base[..8] != HEADER {
+        return Err("no EDID header".to_string());
+    }
+    if base.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+        return Err("checksum mismatch".to_string());
+    }
+
+    let vendor = u16::from_be_bytes([base[8], base[9]]);
+    let manufacturer: String = [10, 5, 0]
+        .iter()
+        .map(|shift| (b'A' - 1 + ((vendor >> shift) & 0x1f) as u8) as char)
+        .collect();
+    let number = u32::from_le_bytes([base[12], base[13], base[14], base[15]]);
+    let mut edid = Edid {
+        manufacturer,
+        product_code: u16::from_le_bytes([base[10], base[11]]),
+        name: None,
+        serial: (number != 0).then(|| number.to_string()),
+        width_mm: base[21] as u32 * 10,
+        height_mm: base[22] as u32 * 10,
+        preferred: None,
+    };
+
+    for d in base[54..126].chunks(18) {
+        if d[0] != 0 || d[1] != 0 {
+            // Detailed timing; the first is the preferred mode
+            if edid.preferred.is_some() {
+                continue;
+            }
+            let clock_hz = u16::from_le_bytes([d[0], d[1]]) as f64 * 10_000.0;
+            let width = d[2] as u32 | ((d[4] as u32 >> 4) << 8);
+            let height = d[5] as u32 | ((d[7] as u32 >> 4) << 8);
+            let total_width = width + (d[3] as u32 | ((d[4] as u32 & 0x0f) << 8));
+            let total_height = height + (d[6] as u32 | ((d[7] as u32 & 0x0f) << 8));
+            let width_mm = d[12] as u32 | ((d[14] as u32 >> 4) << 8);
+            let height_mm = d[13] as u32 | ((d[14] as u32 & 0x0f) << 8);
+            if width_mm >= MIN_WIDTH_MM {
+                edid.width_mm = width_mm;
+                edid.height_mm = height_mm;
+            }
+            edid.preferred = Some(Mode {
+                width,
+                height,
+                refresh_hz: if total_width * total_height > 0 {
+                    (clock_hz / (total_width * total_height) as f64 * 100.0).round() / 100.0
+                } else {
+                    0.0
+                },
+            });
+            continue;
+        }
+        match d[3] {
+            0xfc => edid.name = descriptor_text(&d[5..]),
+            0xff => edid.serial = descriptor_text(&d[5..]).or(edid.serial.take()),
+            _ => {}
+        }
+    }
+    Ok(edid)
+}
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Per-user monitor layout files
+//!
+//! GNOME (mutter) keeps layouts in `~/.config/monitors.xml`, Plasma 6 in
+//! `~/.config/kwinoutputconfig.json` and Plasma 5 in
+//! `~/.local/share/kscreen/`. Each records a scale per logical monitor,
+//! for every combination of monitors seen; with the file moved aside the
+//! desktop starts over from its defaults at the next login.
+
+use crate::system;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Compiled system dconf databases
+const DCONF_DB: &str = "/etc/dconf/db";
+
+/// Distribution defaults, in `*.gschema.override` files
+const SCHEMA_DIR: &str = "/usr/share/glib-2.0/schemas";
+
+/// mutter's experimental features that allow scales between integers
+const FRACTIONAL_FEATURES: &[&str] = &["scale-monitor-framebuffer", "x11-randr-fractional-scaling"];
+
+/// Reads the scales out of a layout file or directory
+type Reader = fn(&Path) -> Result<Vec<Scale>, String>;
+
+#[derive(Debug, Clone)]
+pub struct Home {
+    pub user: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Scale {
+    /// Connector as the desktop names it, e.g. `DP-1` or `HDMI-1`
+    pub connector: String,
+    /// EDID vendor and monitor name, where the file records them
+    pub vendor: Option<String>,
+    pub product: Option<String>,
+    pub scale: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutFile {
+    pub user: String,
+    /// `GNOME` or `KDE`
+    pub desktop: &'static str,
+    pub path: String,
+    /// Seconds since the epoch
+    pub modified: Option<u64>,
+    pub scales: Vec<Scale>,
+    /// Why the file cannot be read as a layout
+    pub error: Option<String>,
+    /// What is wrong with it, filled in by the scaling section
+    pub problem: Option<String>,
+}
+
+/// Human users: UID 1000 and up with an existing home
+pub fn homes() -> Vec<Home> {
+    system::read("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            let uid: u32 = fields.get(2)?.parse().ok()?;
+            let path = PathBuf::from(fields.get(5)?);
+            ((1000..65534).contains(&uid) && path.is_dir()).then(|| Home {
+                user: fields[0].to_string(),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Text of the first `<name>` element in `xml`
+fn tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..start + end].trim())
+}
+
+fn gnome_scales(text: &str) -> Result<Vec<Scale>, String> {
+    if !text.contains("<monitors") || !text.contains("</monitors>") {
+        return Err("not a complete <monitors> document".to_string());
+    }
+    let mut scales = Vec::new();
+    for block in text.split("<logicalmonitor>").skip(1) {
+        let block = block.split("</logicalmonitor>").next().unwrap_or_default();
+        let scale = match tag(block, "scale") {
+            Some(s) => s
+                .parse::<f64>()
+                .map_err(|_| format!("scale '{}' is not a number", s))?,
+            None => 1.0,
+        };
+        for spec in block.split("<monitorspec>").skip(1) {
+            let Some(connector) = tag(spec, "connector") else {
+                continue;
+            };
+            scales.push(Scale {
+                connector: connector.to_string(),
+                vendor: tag(spec, "vendor").map(str::to_string),
+                product: tag(spec, "product").map(str::to_string),
+                scale,
+            });
+        }
+    }
+    Ok(scales)
+}
+
+/// Outputs anywhere in a KWin or KScreen JSON document: Plasma 6 names
+/// them `connectorName`, Plasma 5 `metadata.name`
+fn kde_scales(value: &Value, scales: &mut Vec<Scale>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|v| kde_scales(v, scales)),
+        Value::Object(map) => {
+            let connector = map
+                .get("connectorName")
+                .or_else(|| map.get("metadata").and_then(|m| m.get("name")))
+                .and_then(Value::as_str);
+            match (connector, map.get("scale").and_then(Value::as_f64)) {
+                (Some(connector), Some(scale)) => scales.push(Scale {
+                    connector: connector.to_string(),
+                    vendor: None,
+                    product: None,
+                    scale,
+                }),
+                _ => map.values().for_each(|v| kde_scales(v, scales)),
+            }
+        }
+        _ => {}
+    }
+}
+
+fn modified(path: &Path) -> Option<u64> {
+    let time = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(time.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+fn layout_file(home: &Home, desktop: &'static str, path: PathBuf, read: Reader) -> LayoutFile {
+    let (scales, error) = match read(&path) {
+        Ok(scales) => (scales, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    LayoutFile {
+        user: home.user.clone(),
+        desktop,
+        modified: modified(&path),
+        path: path.display().to_string(),
+        scales,
+        error,
+        problem: None,
+    }
+}
+
+fn gnome_file(path: &Path) -> Result<Vec<Scale>, String> {
+    gnome_scales(&std::fs::read_to_string(path).map_err(|e| e.to_string())?)
+}
+
+fn kde_file(path: &Path) -> Result<Vec<Scale>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    let mut scales = Vec::new();
+    kde_scales(&value, &mut scales);
+    Ok(scales)
+}
+
+/// Plasma 5 keeps one document per monitor combination
+fn kscreen_dir(path: &Path) -> Result<Vec<Scale>, String> {
+    let mut scales = Vec::new();
+    for entry in std::fs::read_dir(path)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let document = entry.path();
+        if document.is_file() {
+            scales
+                .extend(kde_file(&document).map_err(|e| format!("{}: {}", document.display(), e))?);
+        }
+    }
+    Ok(scales)
+}
+
+/// Every layout file of every user
+pub fn find(homes: &[Home]) -> Vec<LayoutFile> {
+    let mut files = Vec::new();
+    for home in homes {
+        let candidates: [(&'static str, &str, Reader); 3] = [
+            ("GNOME", ".config/monitors.xml", gnome_file),
+            ("KDE", ".config/kwinoutputconfig.json", kde_file),
+            ("KDE", ".local/share/kscreen", kscreen_dir),
+        ];
+        for (desktop, relative, read) in candidates {
+            let path = home.path.join(relative);
+            if path.exists() {
+                files.push(layout_file(home, desktop, path, read));
+            }
+        }
+    }
+    files
+}
+
+/// Whether mutter is allowed fractional scales for the user at `home`,
+/// judged by the feature names appearing in their dconf database or in
+/// the system defaults
+pub fn fractional_enabled(home: &Path) -> bool {
+    let mentions = |path: &Path| {
+        std::fs::read(path).is_ok_and(|bytes| {
+            FRACTIONAL_FEATURES.iter().any(|feature| {
+                bytes
+                    .windows(feature.len())
+                    .any(|w| w == feature.as_bytes())
+            })
+        })
+    };
+    let any_in = |dir: &str, suffix: &str| {
+        std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && p.to_string_lossy().ends_with(suffix))
+                .any(|p| mentions(&p))
+        })
+    };
+    mentions(&home.join(".config/dconf/user"))
+        || any_in(DCONF_DB, "")
+        || any_in(SCHEMA_DIR, ".gschema.override")
+}
@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Display Ambulance backend
+//!
+//! Checks what stands between the GPU and a usable picture: connected
+//! outputs and their EDID, compositor crashes, and per-monitor scaling in
+//! the GNOME and KDE layout files and the session environment. Resets
+//! broken layouts, asking first. `--json` output follows the network
+//! ambulance's report model.
+
+mod diagnostics;
+mod edid;
+mod layout;
+mod repairs;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Error};
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Display Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: display-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all display diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+    println!("  -y, --yes            Approve changes that ask for confirmation");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Display Ambulance");
+    println!("=================\n");
+    result.outputs.print(verbose);
+    result.compositor.print(verbose);
+    result.scaling.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Outputs", &result.outputs.warnings),
+        ("Compositor", &result.compositor.warnings),
+        ("Scaling", &result.scaling.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    let connected = result
+        .outputs
+        .outputs
+        .iter()
+        .filter(|o| o.connected())
+        .count();
+    if result.outputs.error.is_none() {
+        println!("Connected outputs: {}", connected);
+    }
+    exit::findings(result.has_warnings())
+}
+
+/// Ask on the terminal; without one (or in JSON mode) nothing is approved
+fn prompt(question: &str, json: bool) -> bool {
+    if json || !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn run_repair(target: &str, json: bool, yes: bool) -> ExitCode {
+    if let Err(e) = ambulance_privilege::require(&format!("display-ambulance repair {}", target)) {
+        return fail(e, json);
+    }
+
+    let mut confirm = |question: &str| yes || prompt(question, json);
+    let result = match repairs::run(target, &mut confirm) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [("layout", "Layout Reset", &result.layout_repair)];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Display Ambulance - Repair Mode");
+        println!("===============================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let yes = args.iter().any(|a| a == "-y" || a == "--yes");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, json, yes),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Display Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Reset monitor layouts
+//!
+//! A layout file with a problem, or one changed just before the
+//! compositor started crashing, is renamed with a timestamp suffix rather
+//! than deleted. GNOME and KDE write a fresh one from defaults at the
+//! next login; renaming it back restores the old layout.
+
+use crate::diagnostics;
+use crate::report::RepairOutcome;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let diag = diagnostics::run();
+    let reset: Vec<_> = diag
+        .scaling
+        .layouts
+        .iter()
+        .filter_map(|l| {
+            let why = l.problem.clone().or_else(|| {
+                diag.compositor
+                    .suspect_layouts
+                    .contains(&l.path)
+                    .then(|| "changed just before the compositor began crashing".to_string())
+            })?;
+            Some((l, why))
+        })
+        .collect();
+    if reset.is_empty() {
+        return RepairOutcome::not_needed("No layout file needs resetting, no repair needed");
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut result = RepairOutcome::default();
+    for (layout, why) in reset {
+        if !confirm(&format!(
+            "Move {} aside ({} layout of {}: {})?",
+            layout.path, layout.desktop, layout.user, why
+        )) {
+            result.errors.push(format!(
+                "{} not confirmed, left in place (rerun with --yes to confirm)",
+                layout.path
+            ));
+            continue;
+        }
+        let aside = format!("{}.display-ambulance-{}", layout.path, stamp);
+        match std::fs::rename(&layout.path, &aside) {
+            Ok(()) => result.actions.push(format!(
+                "Moved {} to {}; {} starts from defaults at the next login",
+                layout.path, aside, layout.user
+            )),
+            Err(e) => result.errors.push(format!("{}: {}", layout.path, e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Display repairs, one module per target
+
+pub mod layout;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["layout", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each layout file is moved.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        layout_repair: if selected("layout") {
+            layout::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
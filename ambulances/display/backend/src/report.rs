@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    compositor::CompositorDiagnostics, outputs::OutputDiagnostics, scaling::ScalingDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "display-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub outputs: OutputDiagnostics,
+    pub compositor: CompositorDiagnostics,
+    pub scaling: ScalingDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.outputs.warnings,
+            &self.compositor.warnings,
+            &self.scaling.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub layout_repair: RepairOutcome,
+}
@@ -0,0 +1,9 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thin helpers over the external tools and files we read
+
+use std::path::Path;
+
+/// A file's contents; `None` if absent or unreadable
+pub fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
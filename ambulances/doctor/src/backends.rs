@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Finding the installed ambulance backends
+//!
+//! Backends are executables named `<name>-ambulance`, looked for next to
+//! the doctor itself first, so a build tree or an install prefix is
+//! used as a whole, then on `PATH`. The network ambulance's CLI backend
+//! is `network-ambulance-d`; `network-ambulance` is its desktop app and
+//! is never run.
+
+use std::collections::BTreeMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+const SUFFIX: &str = "-ambulance";
+
+/// Backends whose executable is not `<name>-ambulance`, as `(name, executable)`
+const RENAMED: &[(&str, &str)] = &[("network", "network-ambulance-d")];
+
+#[derive(Debug, Clone)]
+pub struct Backend {
+    /// e.g. `disk`
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Backend {
+    /// The executable's name, which is also the `tool` its reports carry
+    pub fn tool(&self) -> String {
+        self.path
+            .file_name()
+            .map_or(self.name.clone(), |f| f.to_string_lossy().into_owned())
+    }
+}
+
+fn executable(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// The backend's name if `file` is one
+fn name_of(file: &str) -> Option<&str> {
+    if let Some((name, _)) = RENAMED.iter().find(|(_, exe)| *exe == file) {
+        return Some(name);
+    }
+    let name = file.strip_suffix(SUFFIX)?;
+    let renamed = RENAMED.iter().any(|(n, _)| *n == name);
+    (!name.is_empty() && !renamed).then_some(name)
+}
+
+fn search_path() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(dir);
+    }
+    if let Some(path) = std::env::var_os("PATH") {
+        dirs.extend(std::env::split_paths(&path));
+    }
+    dirs
+}
+
+/// Every installed backend by name; the first found of each name wins
+pub fn discover() -> Vec<Backend> {
+    let mut found: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in search_path() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().into_owned();
+            let Some(name) = name_of(&file) else {
+                continue;
+            };
+            let path = entry.path();
+            if !found.contains_key(name) && executable(&path) {
+                found.insert(name.to_string(), path);
+            }
+        }
+    }
+    found
+        .into_iter()
+        .map(|(name, path)| Backend { name, path })
+        .collect()
+}
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Journal messages known issues look for
+//!
+//! Only the `MESSAGE_ID`s some rule names are matched, so the journal
+//! does the filtering and a week of a busy log stays cheap to read.
+
+use ambulance_core::Facts;
+
+/// Messages counted back over this window
+const WINDOW_DAYS: u64 = 7;
+
+/// Journal entries read at most
+const MAX_ENTRIES: usize = 100_000;
+
+#[cfg(target_os = "linux")]
+fn count(ids: &[&str], facts: &mut Facts) -> std::io::Result<()> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use systemd_shim::journal::{self, Journal};
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64);
+    let since = now.saturating_sub(WINDOW_DAYS * 86_400 * 1_000_000);
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    // Matches on the same field are ORed
+    for id in ids {
+        journal.add_match(&format!("MESSAGE_ID={}", id))?;
+    }
+    journal.seek_tail()?;
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? || journal.realtime_usec()? < since {
+            break;
+        }
+        if let Some(id) = journal.field("MESSAGE_ID") {
+            *facts.message_ids.entry(id.to_lowercase()).or_default() += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count(_ids: &[&str], _facts: &mut Facts) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// How often each of `ids` was logged this week; empty when the journal
+/// cannot be read
+pub fn facts(ids: &[&str]) -> Facts {
+    let mut facts = Facts::default();
+    if !ids.is_empty() {
+        if let Err(e) = count(ids, &mut facts) {
+            eprintln!("Warning: cannot read the journal: {}", e);
+        }
+    }
+    facts
+}
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! System Tools Doctor
+//!
+//! One entry point to every installed ambulance backend. `doctor <name>`
+//! hands the rest of the command line to that backend, `diagnose` when
+//! nothing follows; `doctor all` runs every backend's diagnostics at
+//! once and combines them into one report, typed by ambulance-core, for
+//! servers and support scripts.
+//!
+//! The combined report is matched against known issues: the rule files
+//! installed in ambulance-core's known-issue directories, and any given
+//! with `--rules`.
+//!
+//! With telemetry on (`doctor telemetry on`), `doctor all` also keeps a
+//! local history of its checks and adds findings about the ones that got
+//! worse over the last two weeks; see ambulance-telemetry.
+//!
+//! `doctor all --submit <url>` also sends the combined report to a fleet
+//! server (ambulance-fleet), with the token in `SYSTEM_TOOLS_FLEET_TOKEN`.
+//!
+//! `doctor all` is a quick scan unless given `--deep`: every ambulance
+//! has the scan's budget, a few seconds, and is stopped shortly after
+//! it; slow probes answer from their cache. `--timings` shows where the
+//! time went.
+//!
+//! Exit codes follow ambulance-core's scheme: `doctor all` exits 0 when
+//! nothing was found, 1 when some finding is above `info`, and 5 when a
+//! backend could not run; `doctor <name>` exits with the backend's code.
+
+mod backends;
+mod journal;
+mod render;
+mod run;
+mod telemetry;
+
+use ambulance_core::error::{exit, Category, Error};
+use ambulance_core::knowledge::{self, KnowledgeBase};
+use ambulance_core::scan::{Mode, Scan};
+use ambulance_core::Severity;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long past the scan's budget `doctor all` waits for each backend,
+/// unless `--timeout` says otherwise; backends need a moment to start and
+/// to print what they found once their budget ran out
+const GRACE: Duration = Duration::from_secs(2);
+
+fn print_help() {
+    println!("System Tools Doctor v{}", VERSION);
+    println!();
+    println!("Usage: doctor <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  all                  Run every ambulance's diagnostics as one report");
+    println!("  list                 List the installed ambulances");
+    println!("  telemetry [command]  Local check history: status, on, off, trends, export, clear");
+    println!("  <ambulance> [args]   Run one ambulance (default: diagnose)");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options for all:");
+    println!("  -v, --verbose        Show passing checks too");
+    println!("  -j, --json           JSON output format");
+    println!("  --deep               Full scan: probe everything again, with a longer budget");
+    println!(
+        "  --budget <secs>      Time each ambulance's checks may take (default {} quick, {} deep)",
+        ambulance_core::scan::QUICK_BUDGET.as_secs(),
+        ambulance_core::scan::DEEP_BUDGET.as_secs()
+    );
+    println!(
+        "  --timeout <secs>     Give up on an ambulance after this long (default: budget + {}s)",
+        GRACE.as_secs()
+    );
+    println!("  --timings            Show how long each ambulance and check took");
+    println!("  --rules <path>       Known-issue file or directory to add (repeatable)");
+    println!("  --submit <url>       Also send the report to a fleet server");
+}
+
+/// The installed known issues, then `extra`; a file that cannot be read
+/// is skipped with a warning rather than losing the report
+fn known_issues(extra: &[&str]) -> KnowledgeBase {
+    let mut kb = KnowledgeBase::default();
+    let installed = knowledge::DIRECTORIES
+        .iter()
+        .map(Path::new)
+        .filter(|d| d.is_dir());
+    for path in installed.chain(extra.iter().map(Path::new)) {
+        if let Err(e) = kb.add_path(path) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+    kb
+}
+
+/// The positive number of seconds after `option`, if it was given
+fn seconds(args: &[String], option: &str) -> Result<Option<Duration>, Error> {
+    let Some(i) = args.iter().position(|a| a == option) else {
+        return Ok(None);
+    };
+    args.get(i + 1)
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|s| s.is_finite() && *s > 0.0)
+        .map(|secs| Some(Duration::from_secs_f64(secs)))
+        .ok_or_else(|| {
+            Error::new(
+                "usage.bad-option",
+                Category::Usage,
+                format!("{} needs a number of seconds", option),
+            )
+        })
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report("doctor", VERSION, json)
+}
+
+fn run_list() -> ExitCode {
+    let backends = backends::discover();
+    if backends.is_empty() {
+        println!("No ambulance installed");
+    }
+    for b in &backends {
+        println!("{:<18} {}", b.name, b.path.display());
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_all(args: &[String]) -> ExitCode {
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let timings = args.iter().any(|a| a == "--timings");
+    let mode = if args.iter().any(|a| a == "--deep") {
+        Mode::Deep
+    } else {
+        Mode::Quick
+    };
+    let mut scan = Scan::new(mode);
+    match seconds(args, "--budget") {
+        Ok(Some(budget)) => scan = scan.budget(budget),
+        Ok(None) => {}
+        Err(e) => return fail(e, json),
+    }
+    let timeout = match seconds(args, "--timeout") {
+        Ok(timeout) => timeout.unwrap_or(scan.budget + GRACE),
+        Err(e) => return fail(e, json),
+    };
+
+    let submit = match args.iter().position(|a| a == "--submit") {
+        None => None,
+        Some(i) => match args.get(i + 1) {
+            Some(url) => Some(url.as_str()),
+            None => {
+                let e = Error::new("usage.bad-option", Category::Usage, "--submit needs a URL")
+                    .with_hint(format!(
+                        "Usage: doctor all --submit http://host:{}",
+                        ambulance_fleet::DEFAULT_PORT
+                    ));
+                return fail(e, json);
+            }
+        },
+    };
+
+    let mut rules = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--rules" {
+            match args.get(i + 1) {
+                Some(path) => rules.push(path.as_str()),
+                None => {
+                    let e = Error::new(
+                        "usage.bad-option",
+                        Category::Usage,
+                        "--rules needs a file or directory",
+                    );
+                    return fail(e, json);
+                }
+            }
+        }
+    }
+
+    let backends = backends::discover();
+    if backends.is_empty() {
+        let e = Error::new(
+            "unavailable.no-ambulance",
+            Category::Unavailable,
+            "No ambulance installed",
+        )
+        .with_hint("Run 'doctor list' to see where it looked");
+        return fail(e, json);
+    }
+    let (mut combined, elapsed) = run::all(&backends, scan, timeout);
+    telemetry::record(&mut combined, &elapsed);
+    let kb = known_issues(&rules);
+    combined.advise(&kb, &journal::facts(&kb.message_ids()));
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&combined).unwrap_or_default()
+        );
+    } else {
+        render::print(&combined, verbose);
+        if timings {
+            render::print_timings(&combined, &scan);
+        }
+    }
+    if let Some(url) = submit {
+        let token = std::env::var(ambulance_fleet::TOKEN_VAR).ok();
+        if let Err(e) = ambulance_fleet::submit(url, &combined, token.as_deref()) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    if !combined.failures.is_empty() {
+        ExitCode::from(exit::FAILED)
+    } else if combined.worst().is_some_and(|s| s > Severity::Info) {
+        ExitCode::from(exit::FINDINGS)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("all") => run_all(&args[1..]),
+        Some("list") => run_list(),
+        Some("telemetry") => telemetry::run(&args[1..], VERSION),
+        Some("version") => {
+            println!("System Tools Doctor v{}", VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | Some("-h") | Some("--help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(name) => {
+            let backends = backends::discover();
+            let Some(backend) = backends.iter().find(|b| b.name == name) else {
+                let e = Error::new(
+                    "usage.unknown-ambulance",
+                    Category::Usage,
+                    format!("No ambulance called {} is installed", name),
+                )
+                .with_hint("Run 'doctor list' to see the installed ones");
+                return fail(e, false);
+            };
+            let rest: Vec<String> = if args.len() > 1 {
+                args[1..].to_vec()
+            } else {
+                vec!["diagnose".to_string()]
+            };
+            run::passthrough(backend, &rest)
+        }
+    }
+}
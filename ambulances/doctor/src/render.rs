@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The combined report as text
+//!
+//! One block per ambulance, failing checks first-class and passing ones
+//! only with `--verbose`, then the root causes found across them as
+//! trees, the known issues that matched, and a tally by severity. Notes use the symbols the ambulance
+//! CLIs print.
+
+use ambulance_core::scan::Scan;
+use ambulance_core::wire::mark;
+use ambulance_core::{Chain, CheckStatus, CombinedReport, DiagnosticReport, Severity, Timing};
+
+pub fn print_report(report: &DiagnosticReport, verbose: bool) {
+    let failed = report
+        .checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Failed)
+        .count();
+    println!(
+        "{} {}: {}",
+        mark(failed == 0),
+        report.tool,
+        match failed {
+            0 => "no problems".to_string(),
+            1 => "1 check found problems".to_string(),
+            n => format!("{} checks found problems", n),
+        }
+    );
+    for check in &report.checks {
+        let ok = check.status == CheckStatus::Passed;
+        if ok && !verbose {
+            continue;
+        }
+        println!("  {} {}", mark(ok), check.id);
+        for finding in &check.findings {
+            println!(
+                "      {} [{}] {}",
+                finding.severity.symbol(),
+                finding.severity,
+                finding.summary
+            );
+            if let Some(rec) = &finding.recommendation {
+                println!("        → {}", rec.text);
+            }
+        }
+        for rec in &check.recommendations {
+            println!("      → {}", rec.text);
+        }
+    }
+}
+
+/// `cause`, then what it explains, one level deeper each
+fn print_cause(combined: &CombinedReport, chain: &Chain, cause: &str, depth: usize) {
+    for link in chain.explained_by(cause) {
+        if let Some((tool, finding)) = combined.finding(&link.effect) {
+            println!(
+                "{}└ {} {}: {}",
+                "  ".repeat(depth + 2),
+                finding.severity.symbol(),
+                tool,
+                finding.summary
+            );
+            println!("{}  because {}", "  ".repeat(depth + 2), link.because);
+        }
+        print_cause(combined, chain, &link.effect, depth + 1);
+    }
+}
+
+fn print_chains(combined: &CombinedReport) {
+    if combined.chains.is_empty() {
+        return;
+    }
+    println!("\nRoot causes:");
+    for chain in &combined.chains {
+        let Some((tool, root)) = combined.finding(&chain.root) else {
+            continue;
+        };
+        println!("  {} {}: {}", root.severity.symbol(), tool, root.summary);
+        print_cause(combined, chain, &chain.root, 0);
+    }
+}
+
+fn print_advice(combined: &CombinedReport) {
+    if combined.advice.is_empty() {
+        return;
+    }
+    println!("\nKnown issues:");
+    for advice in &combined.advice {
+        println!("  ● {}", advice.title.as_deref().unwrap_or(&advice.issue));
+        println!("    {}", advice.explanation);
+        if let Some(rec) = &advice.recommendation {
+            println!("    → {}", rec.text);
+        }
+    }
+}
+
+pub fn print(combined: &CombinedReport, verbose: bool) {
+    println!("System Tools Doctor");
+    println!("===================\n");
+    println!("Host: {}\n", combined.host);
+    for report in &combined.reports {
+        print_report(report, verbose);
+    }
+    for failure in &combined.failures {
+        println!("✗ {}: not run, {}", failure.tool, failure.error);
+    }
+    print_chains(combined);
+    print_advice(combined);
+
+    let tally: Vec<String> = Severity::ALL
+        .iter()
+        .filter(|&&s| s > Severity::Info || verbose)
+        .map(|&s| format!("{} {}", combined.count(s), s))
+        .collect();
+    println!(
+        "\nSummary: {} across {} ambulance(s){}",
+        tally.join(", "),
+        combined.reports.len(),
+        if combined.failures.is_empty() {
+            String::new()
+        } else {
+            format!(", {} could not run", combined.failures.len())
+        }
+    );
+}
+
+fn print_timing(timing: &Timing, indent: usize) {
+    println!(
+        "{}{:<width$} {:>7.2}s{}",
+        " ".repeat(indent),
+        timing.id,
+        timing.millis as f64 / 1000.0,
+        timing
+            .status
+            .as_deref()
+            .map(|s| format!("  ({})", s))
+            .unwrap_or_default(),
+        width = 28 - indent
+    );
+}
+
+/// Each backend's time, slowest first, with its checks' under it
+pub fn print_timings(combined: &CombinedReport, scan: &Scan) {
+    println!(
+        "\nTimings ({} scan, budget {}s):",
+        scan.mode,
+        scan.budget.as_secs_f64()
+    );
+    for timing in &combined.timings {
+        print_timing(timing, 2);
+        let Some(report) = combined.reports.iter().find(|r| r.tool == timing.id) else {
+            continue;
+        };
+        let mut checks: Vec<&Timing> = report.timings.iter().collect();
+        checks.sort_by_key(|t| std::cmp::Reverse(t.millis));
+        for check in checks {
+            print_timing(check, 6);
+        }
+    }
+}
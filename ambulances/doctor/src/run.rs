@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Running backends, alone or all at once
+//!
+//! `doctor all` runs every backend's `diagnose --json` at the same time,
+//! each in its own thread, and kills any that outlive the timeout; a
+//! journal scan on a slow disk should not hold up the rest of the report.
+//! Each backend is told the scan, quick or deep, and its budget, which
+//! the backends that run their checks as jobs keep to.
+
+use crate::backends::Backend;
+use ambulance_core::error::{exit, Category, Error, ErrorEnvelope};
+use ambulance_core::scan::Scan;
+use ambulance_core::{CombinedReport, DiagnosticReport, Failure, Timing};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::process::{Command, ExitCode, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often a running backend is checked on
+const POLL: Duration = Duration::from_millis(50);
+
+/// Run `backend` with `args`, handing it the terminal, and pass on its exit status
+pub fn passthrough(backend: &Backend, args: &[String]) -> ExitCode {
+    match Command::new(&backend.path).args(args).status() {
+        Ok(status) => ExitCode::from(status.code().unwrap_or(1).clamp(0, 255) as u8),
+        Err(e) => Error::new(
+            "unavailable.backend",
+            Category::Unavailable,
+            format!("Failed to run {}: {}", backend.path.display(), e),
+        )
+        .report("doctor", env!("CARGO_PKG_VERSION"), false),
+    }
+}
+
+/// Everything `pipe` yields, read on a thread of its own
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut out = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut out);
+        }
+        out
+    })
+}
+
+/// `diagnose --json` from one backend, read into the typed schema
+fn diagnose(backend: &Backend, scan: Scan, timeout: Duration) -> Result<DiagnosticReport, Error> {
+    let mut child = Command::new(&backend.path)
+        .args(["diagnose", "--json"])
+        .envs(scan.env())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            Error::new(
+                "unavailable.backend",
+                Category::Unavailable,
+                format!("failed to run {}: {}", backend.path.display(), e),
+            )
+        })?;
+
+    // Read while waiting, or a report bigger than the pipe blocks the backend
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let e = Error::new(
+                    "timeout.backend",
+                    Category::Timeout,
+                    format!("timed out after {}s", timeout.as_secs_f64()),
+                );
+                return Err(if scan.deep() {
+                    e
+                } else {
+                    e.with_hint("Run 'doctor all --deep' to give every ambulance longer")
+                });
+            }
+            Ok(None) => thread::sleep(POLL),
+            Err(e) => {
+                return Err(Error::new(
+                    "internal.wait",
+                    Category::Internal,
+                    e.to_string(),
+                ))
+            }
+        }
+    };
+    let out = String::from_utf8_lossy(&stdout.join().unwrap_or_default()).into_owned();
+    // 1 is a report with findings, like 0 one without
+    if !status.success() && status.code() != Some(exit::FINDINGS.into()) {
+        // A backend that speaks the envelope says what went wrong itself
+        if let Some(envelope) = ErrorEnvelope::parse(&out) {
+            return Err(envelope.error);
+        }
+        let err = stderr.join().unwrap_or_default();
+        let category = status
+            .code()
+            .and_then(Category::from_exit_code)
+            .unwrap_or(Category::Failed);
+        return Err(Error::new(
+            "failed.backend",
+            category,
+            format!(
+                "exited with {}: {}",
+                status,
+                String::from_utf8_lossy(&err).trim()
+            ),
+        ));
+    }
+    DiagnosticReport::parse(&out).map_err(|e| {
+        Error::new(
+            "failed.unreadable-report",
+            Category::Failed,
+            format!("unreadable report: {}", e),
+        )
+    })
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Every backend's report, in backend order, with the chains linking
+/// them and each backend's timing, and how long each report took by tool
+pub fn all(
+    backends: &[Backend],
+    scan: Scan,
+    timeout: Duration,
+) -> (CombinedReport, BTreeMap<String, Duration>) {
+    let generated = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut combined = CombinedReport::new(&hostname(), generated);
+    let mut elapsed = BTreeMap::new();
+    let handles: Vec<_> = backends
+        .iter()
+        .cloned()
+        .map(|b| {
+            thread::spawn(move || {
+                let started = Instant::now();
+                (diagnose(&b, scan, timeout), started.elapsed())
+            })
+        })
+        .collect();
+    for (backend, handle) in backends.iter().zip(handles) {
+        let (result, took) = handle.join().unwrap_or_else(|_| {
+            let e = Error::new(
+                "internal.panic",
+                Category::Internal,
+                "the doctor's runner thread panicked",
+            );
+            (Err(e), Duration::ZERO)
+        });
+        combined.timings.push(Timing {
+            id: backend.tool(),
+            millis: took.as_millis() as u64,
+            status: result.as_ref().err().map(Error::to_string),
+        });
+        match result {
+            Ok(report) => {
+                elapsed.insert(report.tool.clone(), took);
+                combined.reports.push(report);
+            }
+            Err(error) => combined.failures.push(Failure::new(&backend.tool(), error)),
+        }
+    }
+    combined
+        .timings
+        .sort_by_key(|t| std::cmp::Reverse(t.millis));
+    combined.correlate();
+    (combined, elapsed)
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! `doctor telemetry`, and what `doctor all` keeps when it is on
+//!
+//! Nothing is recorded until the user runs `doctor telemetry on`. From
+//! then on each `doctor all` adds its reports to the local store, drops
+//! days past the retention, and appends the trends report.
+
+use crate::render;
+use ambulance_core::error::{Category, Error};
+use ambulance_core::CombinedReport;
+use ambulance_telemetry::{trends, Settings, Store};
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::time::Duration;
+
+fn store_path() -> Result<std::path::PathBuf, Error> {
+    ambulance_telemetry::store_path().ok_or_else(|| {
+        Error::new(
+            "unavailable.home",
+            Category::Unavailable,
+            "No home directory to keep telemetry in",
+        )
+    })
+}
+
+fn failed(e: String) -> Error {
+    Error::new("failed.telemetry", Category::Failed, e)
+}
+
+fn load() -> Result<Store, Error> {
+    Store::load(&store_path()?).map_err(failed)
+}
+
+/// Add `combined` to the store when telemetry is on, then its trends;
+/// a store that cannot be read or written costs the trends, not the report
+pub fn record(combined: &mut CombinedReport, elapsed: &BTreeMap<String, Duration>) {
+    let settings = Settings::load();
+    if !settings.enabled {
+        return;
+    }
+    let today = ambulance_telemetry::today();
+    let result = store_path().and_then(|path| {
+        let mut store = Store::load(&path).map_err(failed)?;
+        for report in &combined.reports {
+            store.record(today, report, elapsed.get(&report.tool).copied());
+        }
+        store.prune(today, settings.retention_days);
+        store.save(&path).map_err(failed)?;
+        Ok(store)
+    });
+    match result {
+        Ok(store) => combined.reports.push(trends::report(&store, today)),
+        Err(e) => eprintln!("Warning: telemetry: {}", e),
+    }
+}
+
+fn status() -> Result<(), Error> {
+    let settings = Settings::load();
+    let store = load()?;
+    println!(
+        "Telemetry:  {}",
+        if settings.enabled { "on" } else { "off" }
+    );
+    println!("Retention:  {} days", settings.retention_days);
+    if let Some(path) = ambulance_telemetry::store_path() {
+        println!("Store:      {}", path.display());
+    }
+    match (store.days.keys().next(), store.days.keys().next_back()) {
+        (Some(first), Some(last)) => println!(
+            "Recorded:   {} day{}, {} to {}",
+            store.days.len(),
+            if store.days.len() == 1 { "" } else { "s" },
+            ambulance_telemetry::date(*first),
+            ambulance_telemetry::date(*last)
+        ),
+        _ => println!("Recorded:   nothing"),
+    }
+    Ok(())
+}
+
+fn switch(enabled: bool) -> Result<(), Error> {
+    let settings = Settings {
+        enabled,
+        ..Settings::load()
+    };
+    settings.save().map_err(failed)?;
+    if enabled {
+        println!("Telemetry on: 'doctor all' now keeps a local history of its checks");
+    } else {
+        println!("Telemetry off; run 'doctor telemetry clear' to delete what was kept");
+    }
+    Ok(())
+}
+
+fn clear() -> Result<(), Error> {
+    let path = store_path()?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => println!("Deleted {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => println!("Nothing to delete"),
+        Err(e) => {
+            return Err(failed(format!(
+                "Failed to delete {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn show_trends(json: bool) -> Result<(), Error> {
+    let report = trends::report(&load()?, ambulance_telemetry::today());
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+    } else {
+        render::print_report(&report, true);
+    }
+    Ok(())
+}
+
+pub fn run(args: &[String], version: &str) -> ExitCode {
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let result = match args.first().map(String::as_str) {
+        None | Some("status") => status(),
+        Some("on") => switch(true),
+        Some("off") => switch(false),
+        Some("clear") => clear(),
+        Some("trends") => show_trends(json),
+        Some("export") => load().map(|store| {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&store.export()).unwrap_or_default()
+            )
+        }),
+        Some(other) => Err(Error::new(
+            "usage.unknown-command",
+            Category::Usage,
+            format!("Unknown telemetry command: {}", other),
+        )
+        .with_hint("Usage: doctor telemetry [status|on|off|trends|export|clear]")),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => e.report("doctor", version, json),
+    }
+}
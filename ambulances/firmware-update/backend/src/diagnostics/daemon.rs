@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! fwupd itself and the freshness of its metadata
+//!
+//! fwupd only offers the releases its remotes' metadata lists, so an
+//! enabled remote last refreshed a month ago hides every update shipped
+//! since. Remotes with no download time are local directories and are
+//! never stale.
+
+use crate::fwupd::{self, Remote, FWUPD, FWUPD_PATH};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+/// Metadata older than this is stale
+pub const STALE_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DaemonDiagnostics {
+    pub reachable: bool,
+    pub error: Option<String>,
+    pub version: Option<String>,
+    /// The machine as fwupd names it, e.g. `ThinkPad X1 Carbon`
+    pub host_product: Option<String>,
+    pub remotes: Vec<Remote>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl DaemonDiagnostics {
+    /// Enabled remotes whose metadata is out of date
+    pub fn stale(&self) -> impl Iterator<Item = &Remote> {
+        self.remotes
+            .iter()
+            .filter(|r| r.enabled && r.age_days.is_some_and(|d| d > STALE_DAYS))
+    }
+}
+
+pub fn diagnose() -> DaemonDiagnostics {
+    let mut diag = DaemonDiagnostics::default();
+    let remotes = Bus::system().and_then(|bus| {
+        let remotes = fwupd::remotes(&bus)?;
+        let property = |name| {
+            bus.get_property_string(FWUPD, FWUPD_PATH, FWUPD, name)
+                .ok()
+                .filter(|s| !s.is_empty())
+        };
+        diag.version = property("DaemonVersion");
+        diag.host_product = property("HostProduct");
+        Ok(remotes)
+    });
+    match remotes {
+        Ok(remotes) => {
+            diag.reachable = true;
+            diag.remotes = remotes;
+        }
+        Err(e) => {
+            diag.warnings
+                .push(format!("fwupd is not reachable on the system bus: {}", e));
+            diag.recommendations
+                .push("Install fwupd, or start it: systemctl start fwupd.service".to_string());
+            diag.error = Some(e.to_string());
+            return diag;
+        }
+    }
+
+    if !diag.remotes.iter().any(|r| r.enabled) {
+        diag.warnings
+            .push("No fwupd remote is enabled, so no update can be found".to_string());
+        diag.recommendations
+            .push("Enable the LVFS: fwupdmgr enable-remote lvfs".to_string());
+    }
+    let stale: Vec<String> = diag
+        .stale()
+        .map(|r| format!("{} ({} days)", r.id, r.age_days.unwrap_or(0)))
+        .collect();
+    if !stale.is_empty() {
+        diag.warnings.push(format!(
+            "Firmware metadata is out of date: {}",
+            stale.join(", ")
+        ));
+        diag.recommendations
+            .push("Refresh the metadata: firmware-update-ambulance repair refresh".to_string());
+    }
+    diag
+}
+
+impl DaemonDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== fwupd ===");
+        match &self.error {
+            Some(e) => println!("✗ Not reachable: {}", e),
+            None => println!(
+                "✓ fwupd {}{}",
+                self.version.as_deref().unwrap_or("(unknown version)"),
+                self.host_product
+                    .as_ref()
+                    .map(|p| format!(" on {}", p))
+                    .unwrap_or_default()
+            ),
+        }
+        for remote in &self.remotes {
+            if !remote.enabled && !verbose {
+                continue;
+            }
+            let fresh = !remote.age_days.is_some_and(|d| d > STALE_DAYS);
+            println!(
+                "{} {}: {}{}",
+                mark(!remote.enabled || fresh),
+                remote.id,
+                if remote.enabled {
+                    "enabled"
+                } else {
+                    "disabled"
+                },
+                remote
+                    .age_days
+                    .map(|d| format!(", refreshed {} day(s) ago", d))
+                    .unwrap_or_default()
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Devices running a firmware version with a known serious defect
+//!
+//! Every device fwupd knows is compared with the known-issue table,
+//! updatable or not. Where fwupd offers no fix, the vendor's own tool
+//! usually does.
+
+use crate::fwupd::{self, FLAG_UPDATABLE};
+use crate::known_issues;
+use crate::report::print_notes;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Affected {
+    pub device: String,
+    pub device_id: String,
+    pub version: String,
+    pub fixed: &'static str,
+    pub issue: &'static str,
+    /// fwupd offers a newer release for the device
+    pub update_offered: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KnownIssueDiagnostics {
+    pub error: Option<String>,
+    pub affected: Vec<Affected>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose() -> KnownIssueDiagnostics {
+    let mut diag = KnownIssueDiagnostics::default();
+    let affected = Bus::system().and_then(|bus| {
+        let devices = fwupd::devices(&bus)?;
+        Ok(devices
+            .iter()
+            .filter_map(|device| {
+                let version = device.version.as_deref()?;
+                let known = known_issues::find(&device.name, version)?;
+                Some(Affected {
+                    device: device.name.clone(),
+                    device_id: device.id.clone(),
+                    version: version.to_string(),
+                    fixed: known.fixed,
+                    issue: known.issue,
+                    update_offered: device.has(FLAG_UPDATABLE)
+                        && !fwupd::upgrades(&bus, device).is_empty(),
+                })
+            })
+            .collect())
+    });
+    match affected {
+        Ok(affected) => diag.affected = affected,
+        Err(e) => {
+            diag.error = Some(e.to_string());
+            return diag;
+        }
+    }
+
+    for a in &diag.affected {
+        diag.warnings.push(format!(
+            "{} runs firmware {}, on which {}",
+            a.device, a.version, a.issue
+        ));
+        diag.recommendations.push(if a.update_offered {
+            format!(
+                "Update {} to {} or later: firmware-update-ambulance repair update",
+                a.device, a.fixed
+            )
+        } else {
+            format!(
+                "fwupd offers no update for {}; get {} or later from the vendor",
+                a.device, a.fixed
+            )
+        });
+    }
+    diag
+}
+
+impl KnownIssueDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Known Firmware Issues ===");
+        if let Some(e) = &self.error {
+            println!("✗ Devices unavailable: {}", e);
+            println!();
+            return;
+        }
+        if self.affected.is_empty() {
+            println!("✓ No device runs a known-bad version");
+        }
+        for a in &self.affected {
+            println!("✗ {} {}: fixed in {}", a.device, a.version, a.fixed);
+            if verbose {
+                println!("    {}", a.device_id);
+            }
+        }
+        if verbose {
+            println!(
+                "- {} known-bad version(s) checked",
+                known_issues::KNOWN_ISSUES.len()
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
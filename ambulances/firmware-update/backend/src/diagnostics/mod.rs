@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Firmware diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget; each asks fwupd on its own connection.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod daemon;
+pub mod known_issues;
+pub mod updates;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; fwupd answers slowly while it probes
+/// devices
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+enum Section {
+    Daemon(daemon::DaemonDiagnostics),
+    Updates(updates::UpdateDiagnostics),
+    KnownIssues(known_issues::KnownIssueDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("daemon", |_| {
+            Ok(Section::Daemon(daemon::diagnose()))
+        }))
+        .job(Job::blocking("updates", |_| {
+            Ok(Section::Updates(updates::diagnose()))
+        }))
+        .job(Job::blocking("known_issues", |_| {
+            Ok(Section::KnownIssues(known_issues::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        daemon: Default::default(),
+        updates: Default::default(),
+        known_issues: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Daemon(d)) => result.daemon = d,
+            Some(Section::Updates(d)) => result.updates = d,
+            Some(Section::KnownIssues(d)) => result.known_issues = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "daemon" => &mut result.daemon.warnings,
+                    "updates" => &mut result.updates.warnings,
+                    _ => &mut result.known_issues.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Pending firmware updates, failed ones, and updates waiting for a reboot
+//!
+//! Each updatable device is asked for newer releases. Releases marked
+//! high or critical urgency, or fixing a CVE, are warnings; the rest are
+//! recommendations. Whether the power supply allows an update now is
+//! shown next to each one, so the repair's refusals come as no surprise.
+
+use crate::fwupd::{self, Device, Release, FLAG_LOCKED, FLAG_UPDATABLE};
+use crate::power::Power;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceStatus {
+    pub device: Device,
+    /// Newer releases, best first
+    pub upgrades: Vec<Release>,
+    /// Why an update would be refused now
+    pub blocker: Option<String>,
+}
+
+impl DeviceStatus {
+    pub fn healthy(&self) -> bool {
+        self.upgrades.is_empty() && !self.device.update_failed() && !self.device.needs_reboot()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateDiagnostics {
+    pub error: Option<String>,
+    pub power: Power,
+    /// Every device fwupd knows
+    pub device_count: usize,
+    /// Updatable devices, and any with a failed or unfinished update
+    pub devices: Vec<DeviceStatus>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl UpdateDiagnostics {
+    pub fn upgradable(&self) -> impl Iterator<Item = &DeviceStatus> {
+        self.devices.iter().filter(|d| !d.upgrades.is_empty())
+    }
+}
+
+/// The devices worth reporting, each with its upgrades; also used by the repair
+pub fn statuses(bus: &Bus, power: &Power) -> std::io::Result<(usize, Vec<DeviceStatus>)> {
+    let devices = fwupd::devices(bus)?;
+    let count = devices.len();
+    let statuses = devices
+        .into_iter()
+        .filter(|d| d.has(FLAG_UPDATABLE) || d.update_failed() || d.needs_reboot())
+        .map(|device| DeviceStatus {
+            upgrades: if device.has(FLAG_UPDATABLE) {
+                fwupd::upgrades(bus, &device)
+            } else {
+                Vec::new()
+            },
+            blocker: power.blocker(&device),
+            device,
+        })
+        .collect();
+    Ok((count, statuses))
+}
+
+pub fn diagnose() -> UpdateDiagnostics {
+    let mut diag = UpdateDiagnostics::default();
+    let found = Bus::system().and_then(|bus| {
+        diag.power = Power::read(&bus);
+        statuses(&bus, &diag.power)
+    });
+    match found {
+        Ok((count, devices)) => {
+            diag.device_count = count;
+            diag.devices = devices;
+        }
+        Err(e) => {
+            diag.error = Some(e.to_string());
+            return diag;
+        }
+    }
+
+    for status in &diag.devices {
+        let device = &status.device;
+        if device.update_failed() {
+            diag.warnings.push(format!(
+                "The last update of {} failed{}",
+                device.describe(),
+                device
+                    .update_error
+                    .as_ref()
+                    .map(|e| format!(": {}", e))
+                    .unwrap_or_default()
+            ));
+        }
+        if device.needs_reboot() {
+            diag.warnings.push(format!(
+                "An update of {} waits for a reboot to finish",
+                device.name
+            ));
+        }
+        let Some(best) = status.upgrades.first() else {
+            continue;
+        };
+        let offer = format!(
+            "{} can be updated to {} ({} urgency{})",
+            device.describe(),
+            best.version,
+            best.urgency,
+            if best.issues.is_empty() {
+                String::new()
+            } else {
+                format!(", fixes {}", best.issues.join(", "))
+            }
+        );
+        if best.important() {
+            diag.warnings.push(offer);
+        } else {
+            diag.recommendations.push(offer);
+        }
+        if device.has(FLAG_LOCKED) {
+            diag.recommendations.push(format!(
+                "Unlock {} before updating it: fwupdmgr unlock {}",
+                device.name, device.id
+            ));
+        }
+        if let Some(blocker) = &status.blocker {
+            diag.recommendations.push(format!(
+                "{} cannot be updated now: {}",
+                device.name, blocker
+            ));
+        }
+    }
+    if diag.devices.iter().any(|d| d.device.needs_reboot()) {
+        diag.recommendations
+            .push("Reboot to finish the staged firmware updates".to_string());
+    }
+    let count = diag.upgradable().count();
+    if count > 0 {
+        diag.recommendations.push(format!(
+            "Apply {} update(s): firmware-update-ambulance repair update",
+            count
+        ));
+    }
+    diag
+}
+
+impl UpdateDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Updates ===");
+        if let Some(e) = &self.error {
+            println!("✗ Devices unavailable: {}", e);
+            println!();
+            return;
+        }
+        println!(
+            "- Power: {}{}",
+            match self.power.on_battery {
+                Some(true) => "on battery",
+                Some(false) => "on mains",
+                None => "unknown (UPower not running)",
+            },
+            self.power
+                .battery_percent
+                .map(|p| format!(", battery at {:.0}%", p))
+                .unwrap_or_default()
+        );
+        for status in &self.devices {
+            let device = &status.device;
+            let state = match status.upgrades.first() {
+                Some(best) => format!("{} available", best.version),
+                None if device.update_failed() => "last update failed".to_string(),
+                None if device.needs_reboot() => "waiting for reboot".to_string(),
+                None => "up to date".to_string(),
+            };
+            println!(
+                "{} {}: {}",
+                mark(status.healthy()),
+                device.describe(),
+                state
+            );
+            if verbose {
+                if let Some(plugin) = &device.plugin {
+                    println!("    {} via {}", device.id, plugin);
+                }
+                for release in &status.upgrades {
+                    println!(
+                        "    {}: {}{}",
+                        release.version,
+                        release.urgency,
+                        release
+                            .summary
+                            .as_ref()
+                            .map(|s| format!(", {}", s))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+        }
+        if verbose {
+            println!("- {} device(s) known to fwupd", self.device_count);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
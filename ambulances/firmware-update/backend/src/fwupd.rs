@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! fwupd's devices, releases and remotes, read over the system bus
+//!
+//! fwupd hands each object out as an `a{sv}` dictionary keyed as in
+//! libfwupd; only the keys read here are named. Flag and state numbers
+//! are libfwupd's `FwupdDeviceFlags` and `FwupdUpdateState`.
+
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::bus::{Bus, Dict};
+
+/// fwupd's bus name, which is also its interface
+pub const FWUPD: &str = "org.freedesktop.fwupd";
+pub const FWUPD_PATH: &str = "/";
+
+pub const FLAG_UPDATABLE: u64 = 1 << 1;
+pub const FLAG_REQUIRE_AC: u64 = 1 << 3;
+pub const FLAG_LOCKED: u64 = 1 << 4;
+pub const FLAG_NEEDS_REBOOT: u64 = 1 << 8;
+pub const FLAG_NEEDS_SHUTDOWN: u64 = 1 << 17;
+
+/// `BatteryLevel` when the device has no battery or does not know
+const BATTERY_UNKNOWN: u64 = 101;
+
+/// Remote `Flags` bit of fwupd 1.9 and later; older versions send `Enabled`
+const REMOTE_ENABLED: u64 = 1 << 0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+    pub plugin: Option<String>,
+    #[serde(skip)]
+    pub flags: u64,
+    /// State of the last update: `pending`, `success`, `failed`...
+    pub update_state: &'static str,
+    pub update_error: Option<String>,
+    /// Charge of a device with its own battery, such as a wireless keyboard
+    pub battery_level: Option<u64>,
+    /// Charge that device needs before it can be updated
+    pub battery_threshold: Option<u64>,
+}
+
+impl Device {
+    pub fn has(&self, flag: u64) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// An update is staged, or was applied, and waits for a reboot
+    pub fn needs_reboot(&self) -> bool {
+        self.has(FLAG_NEEDS_REBOOT)
+            || self.has(FLAG_NEEDS_SHUTDOWN)
+            || matches!(self.update_state, "pending" | "needs reboot")
+    }
+
+    /// The last update failed, and the device may still be on the old version
+    pub fn update_failed(&self) -> bool {
+        matches!(self.update_state, "failed" | "failed transient")
+    }
+
+    /// `name (version)`, for messages
+    pub fn describe(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} ({})", self.name, version),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Release {
+    pub version: String,
+    pub summary: Option<String>,
+    /// `low`, `medium`, `high`, `critical`, or `unknown`
+    pub urgency: &'static str,
+    /// CVEs the release fixes
+    pub issues: Vec<String>,
+}
+
+impl Release {
+    pub fn important(&self) -> bool {
+        matches!(self.urgency, "high" | "critical") || !self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Remote {
+    pub id: String,
+    pub enabled: bool,
+    /// Days since the metadata was last downloaded; `None` for local remotes
+    pub age_days: Option<u64>,
+}
+
+fn update_state(state: u64) -> &'static str {
+    match state {
+        1 => "pending",
+        2 => "success",
+        3 => "failed",
+        4 => "needs reboot",
+        5 => "failed transient",
+        _ => "unknown",
+    }
+}
+
+fn urgency(urgency: u64) -> &'static str {
+    match urgency {
+        1 => "low",
+        2 => "medium",
+        3 => "high",
+        4 => "critical",
+        _ => "unknown",
+    }
+}
+
+fn string(dict: &Dict, key: &str) -> Option<String> {
+    dict.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+}
+
+fn number(dict: &Dict, key: &str) -> Option<u64> {
+    dict.get(key).and_then(|v| v.as_u64())
+}
+
+fn device(dict: &Dict) -> Option<Device> {
+    Some(Device {
+        id: string(dict, "DeviceId")?,
+        name: string(dict, "Name").unwrap_or_else(|| "unnamed device".to_string()),
+        vendor: string(dict, "Vendor"),
+        version: string(dict, "Version"),
+        plugin: string(dict, "Plugin"),
+        flags: number(dict, "Flags").unwrap_or(0),
+        update_state: update_state(number(dict, "UpdateState").unwrap_or(0)),
+        update_error: string(dict, "UpdateError"),
+        battery_level: number(dict, "BatteryLevel").filter(|&l| l < BATTERY_UNKNOWN),
+        battery_threshold: number(dict, "BatteryThreshold").filter(|&l| l < BATTERY_UNKNOWN),
+    })
+}
+
+pub fn devices(bus: &Bus) -> std::io::Result<Vec<Device>> {
+    let dicts = bus.call_dicts(FWUPD, FWUPD_PATH, FWUPD, "GetDevices", None)?;
+    Ok(dicts.iter().filter_map(device).collect())
+}
+
+/// Newer releases for a device, best first
+///
+/// fwupd answers with an error rather than an empty list when there is
+/// nothing newer, or no metadata for the device, so errors count as none.
+pub fn upgrades(bus: &Bus, device: &Device) -> Vec<Release> {
+    let Ok(dicts) = bus.call_dicts(FWUPD, FWUPD_PATH, FWUPD, "GetUpgrades", Some(&device.id))
+    else {
+        return Vec::new();
+    };
+    dicts
+        .iter()
+        .filter_map(|dict| {
+            Some(Release {
+                version: string(dict, "Version")?,
+                summary: string(dict, "Summary"),
+                urgency: urgency(number(dict, "Urgency").unwrap_or(0)),
+                issues: dict
+                    .get("Issues")
+                    .and_then(|v| v.as_strings())
+                    .unwrap_or_default()
+                    .to_vec(),
+            })
+        })
+        .collect()
+}
+
+pub fn remotes(bus: &Bus) -> std::io::Result<Vec<Remote>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let dicts = bus.call_dicts(FWUPD, FWUPD_PATH, FWUPD, "GetRemotes", None)?;
+    Ok(dicts
+        .iter()
+        .filter_map(|dict| {
+            let enabled = match dict.get("Enabled").and_then(|v| v.as_bool()) {
+                Some(enabled) => enabled,
+                None => number(dict, "Flags").unwrap_or(0) & REMOTE_ENABLED != 0,
+            };
+            Some(Remote {
+                id: string(dict, "RemoteId")?,
+                enabled,
+                age_days: number(dict, "ModificationTime")
+                    .filter(|&t| t > 0)
+                    .map(|t| now.saturating_sub(t) / 86_400),
+            })
+        })
+        .collect())
+}
+
+/// The daemon's `BatteryThreshold`: the system charge below which it
+/// refuses to update
+pub fn battery_threshold(bus: &Bus) -> Option<u32> {
+    bus.get_property_u32(FWUPD, FWUPD_PATH, FWUPD, "BatteryThreshold")
+        .ok()
+        .filter(|&t| (t as u64) < BATTERY_UNKNOWN)
+}
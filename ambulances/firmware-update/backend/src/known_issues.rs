@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Firmware versions with published, serious defects
+//!
+//! Only versions the vendor itself withdrew or replaced with a fix are
+//! listed, matched by a fragment of the model name fwupd reports. Being
+//! on one of them is worth an update even when nothing else is urgent.
+
+pub struct KnownIssue {
+    /// Part of the device name, compared without case
+    pub model: &'static str,
+    pub versions: &'static [&'static str],
+    /// First version with the fix
+    pub fixed: &'static str,
+    /// What goes wrong, as a clause
+    pub issue: &'static str,
+}
+
+pub const KNOWN_ISSUES: &[KnownIssue] = &[
+    KnownIssue {
+        model: "SSD 980 PRO",
+        versions: &["3B2QGXA7"],
+        fixed: "5B2QGXA7",
+        issue: "the drive's health drops quickly until it turns read-only",
+    },
+    KnownIssue {
+        model: "SSD 990 PRO",
+        versions: &["0B2QJXD7"],
+        fixed: "1B2QJXD7",
+        issue: "the drive wears out at many times the expected rate",
+    },
+    KnownIssue {
+        model: "SSDSC2KB",
+        versions: &["XCV10100"],
+        fixed: "XCV10110",
+        issue: "a D3-S4510 stops responding for good after 1700 hours powered on",
+    },
+    KnownIssue {
+        model: "SSDSC2KG",
+        versions: &["XCV10100"],
+        fixed: "XCV10110",
+        issue: "a D3-S4610 stops responding for good after 1700 hours powered on",
+    },
+];
+
+/// The known issue of a device on `version`, if any
+pub fn find(name: &str, version: &str) -> Option<&'static KnownIssue> {
+    let name = name.to_uppercase();
+    KNOWN_ISSUES
+        .iter()
+        .find(|k| name.contains(&k.model.to_uppercase()) && k.versions.contains(&version))
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Whether it is safe to flash firmware now
+//!
+//! A flash cut short by a flat battery can leave a device that no longer
+//! starts, so updates wait for mains power where the device asks for it
+//! and for enough charge everywhere else. The system's power comes from
+//! UPower; a device with its own battery reports its charge to fwupd.
+
+use crate::fwupd::{self, Device, FLAG_REQUIRE_AC};
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const UPOWER: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const DISPLAY_DEVICE: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const DEVICE: &str = "org.freedesktop.UPower.Device";
+
+/// Least system charge to update on battery, stricter than fwupd's
+/// default of 10% where the daemon allows less
+pub const MIN_BATTERY_PERCENT: u32 = 30;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Power {
+    /// `None` when UPower is not running
+    pub on_battery: Option<bool>,
+    /// Charge of the system battery, if there is one
+    pub battery_percent: Option<f64>,
+    /// Charge required to update on battery
+    pub threshold_percent: u32,
+}
+
+impl Power {
+    pub fn read(bus: &Bus) -> Power {
+        let on_battery = bus
+            .get_property_bool(UPOWER, UPOWER_PATH, UPOWER, "OnBattery")
+            .ok();
+        let present = bus
+            .get_property_bool(UPOWER, DISPLAY_DEVICE, DEVICE, "IsPresent")
+            .unwrap_or(false);
+        let battery_percent = present
+            .then(|| {
+                bus.get_property_f64(UPOWER, DISPLAY_DEVICE, DEVICE, "Percentage")
+                    .ok()
+            })
+            .flatten();
+        Power {
+            on_battery,
+            battery_percent,
+            threshold_percent: fwupd::battery_threshold(bus)
+                .map_or(MIN_BATTERY_PERCENT, |t| t.max(MIN_BATTERY_PERCENT)),
+        }
+    }
+
+    /// Why `device` must not be updated now, if it must not
+    pub fn blocker(&self, device: &Device) -> Option<String> {
+        if self.on_battery == Some(true) {
+            if device.has(FLAG_REQUIRE_AC) {
+                return Some("needs mains power and the system is on battery".to_string());
+            }
+            if let Some(percent) = self
+                .battery_percent
+                .filter(|&p| p < self.threshold_percent as f64)
+            {
+                return Some(format!(
+                    "system battery at {:.0}%, below the {}% needed on battery",
+                    percent, self.threshold_percent
+                ));
+            }
+        }
+        if let (Some(level), Some(threshold)) = (device.battery_level, device.battery_threshold) {
+            if level < threshold {
+                return Some(format!(
+                    "its own battery is at {}%, below the {}% it needs",
+                    level, threshold
+                ));
+            }
+        }
+        None
+    }
+}
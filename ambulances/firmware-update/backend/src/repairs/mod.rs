@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Firmware repairs, one module per target
+//!
+//! `all` refreshes the metadata first, so the updates applied are the
+//! newest the remotes offer.
+
+pub mod refresh;
+pub mod update;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["refresh", "update", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each device is flashed.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        refresh_repair: if selected("refresh") {
+            refresh::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        update_repair: if selected("update") {
+            update::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Download fresh firmware metadata
+//!
+//! Only runs when an enabled remote is stale. `--force` is passed since
+//! fwupdmgr otherwise declines a refresh it judges recent by its own
+//! reckoning.
+
+use crate::diagnostics::daemon;
+use crate::report::RepairOutcome;
+use crate::system;
+
+pub fn repair() -> RepairOutcome {
+    let diag = daemon::diagnose();
+    if let Some(e) = diag.error {
+        let mut result = RepairOutcome::default();
+        result.errors.push(format!("fwupd is not reachable: {}", e));
+        return result;
+    }
+    let stale: Vec<String> = diag.stale().map(|r| r.id.clone()).collect();
+    if stale.is_empty() {
+        return RepairOutcome::not_needed("Firmware metadata is up to date, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    if !system::has("fwupdmgr") {
+        result
+            .errors
+            .push("fwupdmgr is not installed; it downloads the metadata".to_string());
+        return result;
+    }
+    match system::run("fwupdmgr", &["refresh", "--force"]) {
+        Ok(_) => result
+            .actions
+            .push(format!("Refreshed metadata for {}", stale.join(", "))),
+        Err(e) => result.errors.push(e),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
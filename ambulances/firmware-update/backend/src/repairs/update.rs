@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Apply the available firmware updates, one device at a time
+//!
+//! Each device is checked against the power supply just before it is
+//! flashed, and skipped if the check fails or it is locked. fwupdmgr
+//! downloads and verifies the release and installs it; fwupd's own
+//! `Install` call needs the archive already downloaded. Reboots are left
+//! to the user and reported, since a staged update may need one.
+
+use crate::diagnostics::updates;
+use crate::fwupd::{self, FLAG_LOCKED};
+use crate::power::Power;
+use crate::report::RepairOutcome;
+use crate::system;
+use systemd_shim::bus::Bus;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let found = Bus::system().and_then(|bus| {
+        let power = Power::read(&bus);
+        let (_, statuses) = updates::statuses(&bus, &power)?;
+        Ok((bus, statuses))
+    });
+    let (bus, statuses) = match found {
+        Ok(found) => found,
+        Err(e) => {
+            result.errors.push(format!("fwupd is not reachable: {}", e));
+            return result;
+        }
+    };
+    let pending: Vec<_> = statuses
+        .into_iter()
+        .filter(|s| !s.upgrades.is_empty())
+        .collect();
+    if pending.is_empty() {
+        return RepairOutcome::not_needed("No firmware update is available, no repair needed");
+    }
+    if !system::has("fwupdmgr") {
+        result
+            .errors
+            .push("fwupdmgr is not installed; it downloads and applies updates".to_string());
+        return result;
+    }
+
+    for status in pending {
+        let device = &status.device;
+        let version = &status.upgrades[0].version;
+        if let Some(blocker) = &status.blocker {
+            result
+                .errors
+                .push(format!("{} not updated: {}", device.name, blocker));
+            continue;
+        }
+        if device.has(FLAG_LOCKED) {
+            result.errors.push(format!(
+                "{} is locked; unlock it first: fwupdmgr unlock {}",
+                device.name, device.id
+            ));
+            continue;
+        }
+        if !confirm(&format!(
+            "Update {} to {} (keep it powered and connected until done)?",
+            device.describe(),
+            version
+        )) {
+            result.errors.push(format!(
+                "{} not confirmed, left as is (rerun with --yes to confirm)",
+                device.name
+            ));
+            continue;
+        }
+        match system::run(
+            "fwupdmgr",
+            &["update", "--assume-yes", "--no-reboot-check", &device.id],
+        ) {
+            Ok(_) => result
+                .actions
+                .push(format!("Updated {} to {}", device.describe(), version)),
+            Err(e) => {
+                result.errors.push(e);
+                continue;
+            }
+        }
+        let after = fwupd::devices(&bus)
+            .ok()
+            .and_then(|devices| devices.into_iter().find(|d| d.id == device.id));
+        if after.is_some_and(|d| d.needs_reboot()) {
+            result
+                .actions
+                .push(format!("Reboot to finish updating {}", device.name));
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
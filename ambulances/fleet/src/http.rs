@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Just enough HTTP/1.1 for reports to travel
+//!
+//! One request per connection, bodies sized by `Content-Length`, and
+//! plain `http://` only: put the server behind a TLS proxy to reach it
+//! across networks you do not trust.
+
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Largest body accepted; a combined report is tens of kilobytes
+pub const MAX_BODY: usize = 16 * 1024 * 1024;
+
+/// Largest request line and headers accepted
+pub const MAX_HEAD: usize = 64 * 1024;
+
+/// How long a connection may stall before it is dropped
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Names lowercased
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn new(method: &str, path: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Read one request from `stream`
+    pub fn read(stream: &mut impl Read) -> Result<Request, String> {
+        let (mut request, rest) = Request::read_head(stream)?;
+        request.read_body(stream, rest)?;
+        Ok(request)
+    }
+
+    /// Read the request line and headers from `stream`, leaving the body
+    /// empty so the caller can refuse it before it is sent; returns what
+    /// of the body came with the head, for `read_body`
+    pub fn read_head(stream: &mut impl Read) -> Result<(Request, Vec<u8>), String> {
+        let (head, rest) = read_head(stream)?;
+        let mut lines = head.split("\r\n");
+        let mut first = lines.next().unwrap_or_default().split(' ');
+        let (Some(method), Some(path)) = (first.next(), first.next()) else {
+            return Err("malformed request line".to_string());
+        };
+        let mut request = Request::new(method, path);
+        for line in lines.filter(|l| !l.is_empty()) {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("malformed header: {}", line))?;
+            request
+                .headers
+                .push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+        Ok((request, rest))
+    }
+
+    /// Read the body `Content-Length` announces, after `rest` from
+    /// `read_head`
+    pub fn read_body(&mut self, stream: &mut impl Read, rest: Vec<u8>) -> Result<(), String> {
+        let length: usize = match self.header("content-length") {
+            Some(n) => n.parse().map_err(|_| "bad Content-Length".to_string())?,
+            None => 0,
+        };
+        if length > MAX_BODY {
+            return Err(format!("body of {} bytes is over the limit", length));
+        }
+        let mut body = rest;
+        while body.len() < length {
+            let mut chunk = vec![0; (length - body.len()).min(64 * 1024)];
+            let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+            if n == 0 {
+                return Err("connection closed mid-body".to_string());
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        body.truncate(length);
+        self.body = body;
+        Ok(())
+    }
+}
+
+/// The head up to the blank line, and whatever of the body came with it
+fn read_head(stream: &mut impl Read) -> Result<(String, Vec<u8>), String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).into_owned();
+            return Ok((head, buf[end + 4..].to_vec()));
+        }
+        if buf.len() > MAX_HEAD {
+            return Err("headers too long".to_string());
+        }
+        let n = stream.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("connection closed before the headers ended".to_string());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// JSON
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, value: &impl Serialize) -> Response {
+        Response {
+            status,
+            body: serde_json::to_string_pretty(value).unwrap_or_default(),
+        }
+    }
+
+    /// `{"error": message}`
+    pub fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    pub fn write_to(&self, stream: &mut impl Write) -> std::io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+/// Split `http://host:port/path` into the address and the path
+fn split_url(url: &str) -> Result<(String, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("{} is not an http:// URL", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(format!("{} names no host", url));
+    }
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    Ok((address, path.to_string()))
+}
+
+/// POST `body` as JSON to `url`, with `token` as a bearer token, and
+/// return the status and body of the answer
+pub fn post(url: &str, body: &[u8], token: Option<&str>) -> Result<(u16, String), String> {
+    let (address, path) = split_url(url)?;
+    let mut stream = TcpStream::connect(&address)
+        .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+    let host = address
+        .rsplit_once(':')
+        .map_or(address.as_str(), |(h, _)| h);
+    let mut head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        path,
+        host,
+        body.len()
+    );
+    if let Some(token) = token {
+        head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    head.push_str("\r\n");
+    // A server refusing the request, say for its token, answers and
+    // closes without taking the body; its answer says more than the
+    // broken pipe does
+    let sent = stream
+        .write_all(head.as_bytes())
+        .and_then(|()| stream.write_all(body))
+        .map_err(|e| format!("Failed to send to {}: {}", address, e));
+
+    let mut answer = Vec::new();
+    if let Err(e) = stream.read_to_end(&mut answer) {
+        sent?;
+        return Err(format!("Failed to read the answer from {}: {}", address, e));
+    }
+    if answer.is_empty() {
+        sent?;
+    }
+    let answer = String::from_utf8_lossy(&answer);
+    let (head, body) = answer.split_once("\r\n\r\n").unwrap_or((&answer, ""));
+    let status = head
+        .split(' ')
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| format!("{} did not answer in HTTP", address))?;
+    Ok((status, body.to_string()))
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Fleet aggregation
+//!
+//! Machines send the `CombinedReport` of `doctor all --submit <url>` to
+//! a [`server::Server`], which keeps the latest from each and answers
+//! with a [`Summary`] of the whole fleet: each finding once, with the
+//! machines that have it, such as 14 of 200 machines with DNS pointing
+//! at a decommissioned server. [`summarize`] does the same for reports
+//! collected any other way, as files.
+
+pub mod http;
+pub mod server;
+pub mod summary;
+
+pub use server::Server;
+pub use summary::{summarize, Group, Issue, Machine, Summary};
+
+use ambulance_core::CombinedReport;
+
+/// Port the server listens on unless told otherwise
+pub const DEFAULT_PORT: u16 = 9477;
+
+/// Environment variable holding the bearer token, for the server and
+/// for `doctor all --submit`; tokens stay off command lines, where any
+/// user can read them
+pub const TOKEN_VAR: &str = "SYSTEM_TOOLS_FLEET_TOKEN";
+
+/// Send `report` to the server at `url`, e.g. `http://fleet:9477`
+pub fn submit(url: &str, report: &CombinedReport, token: Option<&str>) -> Result<(), String> {
+    let body = serde_json::to_vec(report).map_err(|e| e.to_string())?;
+    let url = format!("{}/reports", url.trim_end_matches('/'));
+    match http::post(&url, &body, token)? {
+        (200, _) => Ok(()),
+        (status, body) => {
+            let reason = serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v["error"].as_str().map(str::to_string))
+                .unwrap_or(body);
+            Err(format!("{} answered {}: {}", url, status, reason.trim()))
+        }
+    }
+}
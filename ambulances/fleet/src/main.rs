@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Fleet
+//!
+//! `fleet serve` collects `doctor all --submit` reports from many
+//! machines over HTTP and answers with a fleet-wide summary; `fleet
+//! summary` prints the same summary from report files.
+
+use ambulance_core::error::{Category, Error};
+use ambulance_core::{CombinedReport, Severity};
+use ambulance_fleet::{summarize, Server, Summary, DEFAULT_PORT, TOKEN_VAR};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+const TOOL: &str = "fleet";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+fn print_help() {
+    println!("Fleet v{}", VERSION);
+    println!();
+    println!("Usage: fleet <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  serve                Collect reports over HTTP and serve the summary");
+    println!("  summary <path>...    Summarize report files, or directories of them");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!(
+        "  --listen <addr>      Address to serve on (default 127.0.0.1:{})",
+        DEFAULT_PORT
+    );
+    println!("  --data <dir>         Keep each machine's latest report here");
+    println!(
+        "  --min <severity>     Leave out issues below info|warn|degraded|broken (default warn)"
+    );
+    println!("  -j, --json           JSON output format");
+    println!();
+    println!(
+        "The bearer token machines must send is read from ${}; without one",
+        TOKEN_VAR
+    );
+    println!("serve only listens on loopback addresses.");
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(TOOL, VERSION, json)
+}
+
+/// The value after `flag`; `Err` when the flag ends the command line
+fn option<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a str>, Error> {
+    match args.iter().position(|a| a == flag) {
+        None => Ok(None),
+        Some(i) => args.get(i + 1).map(|v| Some(v.as_str())).ok_or_else(|| {
+            Error::new(
+                "usage.bad-option",
+                Category::Usage,
+                format!("{} needs a value", flag),
+            )
+        }),
+    }
+}
+
+fn token() -> Option<String> {
+    std::env::var(TOKEN_VAR).ok().filter(|t| !t.is_empty())
+}
+
+fn run_serve(listen: Option<&str>, data: Option<&str>, json: bool) -> ExitCode {
+    let listen = listen.map_or_else(|| format!("127.0.0.1:{}", DEFAULT_PORT), str::to_string);
+    let token = token();
+    // Without a token, anyone who can connect may submit: only this
+    // machine's own users, then
+    let loopback = listen
+        .to_socket_addrs()
+        .is_ok_and(|mut addrs| addrs.all(|a| a.ip().is_loopback()));
+    if token.is_none() && !loopback {
+        let e = Error::new(
+            "usage.no-token",
+            Category::Usage,
+            format!("Refusing to serve on {} without a token", listen),
+        )
+        .with_hint(format!(
+            "Set ${} to the token machines send, or listen on 127.0.0.1",
+            TOKEN_VAR
+        ));
+        return fail(e, json);
+    }
+    let server = match Server::new(data.map(Path::new), token) {
+        Ok(server) => server,
+        Err(e) => return fail(Error::new("failed.data", Category::Failed, e), json),
+    };
+    let listener = match TcpListener::bind(&listen) {
+        Ok(listener) => listener,
+        Err(e) => {
+            let e = Error::new(
+                "unavailable.listen",
+                Category::Unavailable,
+                format!("Cannot listen on {}: {}", listen, e),
+            );
+            return fail(e, json);
+        }
+    };
+    eprintln!("Fleet v{} listening on {}", VERSION, listen);
+    Arc::new(server).serve(listener);
+    ExitCode::SUCCESS
+}
+
+/// Every report in `paths`, reading directories one level deep; files
+/// that are not reports are skipped with a warning
+fn read_reports(paths: &[&str]) -> Vec<CombinedReport> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(Path::new) {
+        match std::fs::read_dir(path) {
+            Ok(entries) => files.extend(entries.flatten().map(|e| e.path())),
+            Err(_) => files.push(path.to_path_buf()),
+        }
+    }
+    files.sort();
+    files
+        .iter()
+        .filter_map(|file| {
+            let report = std::fs::read_to_string(file)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()));
+            report
+                .map_err(|e| eprintln!("Warning: skipped {}: {}", file.display(), e))
+                .ok()
+        })
+        .collect()
+}
+
+fn print_summary(summary: &Summary, min: Severity) {
+    let count = summary.machines.len();
+    println!(
+        "Fleet: {} machine{}",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    println!("==============\n");
+    let lines = summary.lines(min);
+    if lines.is_empty() {
+        println!("Nothing at {} or worse on any machine", min);
+    }
+    for line in lines {
+        println!("  {}", line);
+    }
+}
+
+fn run_summary(paths: &[&str], min: Severity, json: bool) -> ExitCode {
+    if paths.is_empty() {
+        let e = Error::new(
+            "usage.missing-path",
+            Category::Usage,
+            "summary needs report files or directories",
+        )
+        .with_hint("Usage: fleet summary <path>...");
+        return fail(e, json);
+    }
+    let summary = summarize(&read_reports(paths));
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).unwrap_or_default()
+        );
+    } else {
+        print_summary(&summary, min);
+    }
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let (listen, data, min) = match (
+        option(&args, "--listen"),
+        option(&args, "--data"),
+        option(&args, "--min"),
+    ) {
+        (Ok(listen), Ok(data), Ok(min)) => (listen, data, min),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return fail(e, json),
+    };
+    let min = match min {
+        None => Severity::Warn,
+        Some(level) => match Severity::ALL.into_iter().find(|s| s.label() == level) {
+            Some(severity) => severity,
+            None => {
+                let e = Error::new(
+                    "usage.bad-option",
+                    Category::Usage,
+                    format!("Unknown severity: {}", level),
+                )
+                .with_hint("Use info, warn, degraded or broken");
+                return fail(e, json);
+            }
+        },
+    };
+    let flags = ["--listen", "--data", "--min"];
+    let positional: Vec<&str> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with('-') && (*i == 0 || !flags.contains(&args[i - 1].as_str())))
+        .map(|(_, a)| a.as_str())
+        .collect();
+
+    match positional.first().copied() {
+        Some("serve") => run_serve(listen, data, json),
+        Some("summary") => run_summary(&positional[1..], min, json),
+        Some("version") => {
+            println!("Fleet v{}", VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, TOOL), json),
+    }
+}
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The aggregation server
+//!
+//! | Request | Answer |
+//! |---------|--------|
+//! | `POST /reports` | Store a machine's `CombinedReport`, replacing its last |
+//! | `GET /summary` | The fleet [`Summary`] |
+//! | `GET /machines` | Each machine's latest report, in brief |
+//! | `GET /health` | `{"status": "ok"}`, without a token |
+//!
+//! With a token set, every other request needs `Authorization: Bearer
+//! <token>`. With a directory, each machine's latest report is kept
+//! there as `<host>.json` and read back on start.
+
+use crate::http::{Request, Response, MAX_HEAD, TIMEOUT};
+use crate::summary::{summarize, Summary};
+use ambulance_core::{CombinedReport, SCHEMA_VERSION};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::net::TcpListener;
+use std::net::{Shutdown, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Connections answered at once; more are turned away with a 503
+pub const MAX_CONNECTIONS: usize = 64;
+
+pub struct Server {
+    dir: Option<PathBuf>,
+    token: Option<String>,
+    reports: Mutex<BTreeMap<String, CombinedReport>>,
+    connections: AtomicUsize,
+}
+
+/// `host` as a file name that stays inside the directory, and differs
+/// for every host: bytes other than letters, digits, `-` and `_`, and
+/// dots leading the name, are written `%XX`
+fn file_name(host: &str) -> String {
+    let mut name = String::new();
+    for (i, b) in host.bytes().enumerate() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || (b == b'.' && i > 0) {
+            name.push(b as char);
+        } else {
+            name.push_str(&format!("%{:02X}", b));
+        }
+    }
+    format!("{}.json", name)
+}
+
+/// Compare without stopping at the first difference, so the time taken
+/// says nothing about how much of a guess was right
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Server {
+    /// A server keeping reports in `dir`, if given, and loading those
+    /// already there
+    pub fn new(dir: Option<&Path>, token: Option<String>) -> Result<Server, String> {
+        let mut reports = BTreeMap::new();
+        if let Some(dir) = dir {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let entries = std::fs::read_dir(dir)
+                .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+            for path in entries.flatten().map(|e| e.path()) {
+                let report = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|text| serde_json::from_str::<CombinedReport>(&text).ok());
+                if let Some(report) = report {
+                    reports.insert(report.host.clone(), report);
+                }
+            }
+        }
+        Ok(Server {
+            dir: dir.map(Path::to_path_buf),
+            token: token.filter(|t| !t.is_empty()),
+            reports: Mutex::new(reports),
+            connections: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn summary(&self) -> Summary {
+        let reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        summarize(&reports.values().cloned().collect::<Vec<_>>())
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        request
+            .header("authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .is_some_and(|given| same(given.trim().as_bytes(), token.as_bytes()))
+    }
+
+    fn store(&self, request: &Request) -> Response {
+        let report: CombinedReport = match serde_json::from_slice(&request.body) {
+            Ok(report) => report,
+            Err(e) => return Response::error(400, &format!("not a combined report: {}", e)),
+        };
+        if report.schema > SCHEMA_VERSION {
+            let message = format!(
+                "report schema {} is newer than this server's {}",
+                report.schema, SCHEMA_VERSION
+            );
+            return Response::error(400, &message);
+        }
+        if report.host.trim().is_empty() {
+            return Response::error(400, "the report names no host");
+        }
+        // Held while writing, so the file and the map agree on the latest
+        let mut reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(dir) = &self.dir {
+            let path = dir.join(file_name(&report.host));
+            if let Err(e) = std::fs::write(&path, &request.body) {
+                let message = format!("Failed to write {}: {}", path.display(), e);
+                return Response::error(500, &message);
+            }
+        }
+        let host = report.host.clone();
+        reports.insert(host.clone(), report);
+        Response::json(
+            200,
+            &serde_json::json!({ "host": host, "machines": reports.len() }),
+        )
+    }
+
+    /// Whether `request` may go on to have its body read, judged from its
+    /// head alone
+    pub fn admits(&self, request: &Request) -> bool {
+        request.path == "/health" || self.authorized(request)
+    }
+
+    /// Answer one request
+    pub fn handle(&self, request: &Request) -> Response {
+        if request.path == "/health" {
+            return Response::json(200, &serde_json::json!({ "status": "ok" }));
+        }
+        if !self.admits(request) {
+            return Response::error(401, "missing or wrong bearer token");
+        }
+        match (request.method.as_str(), request.path.as_str()) {
+            ("POST", "/reports") => self.store(request),
+            ("GET", "/summary") => Response::json(200, &self.summary()),
+            ("GET", "/machines") => Response::json(200, &self.summary().machines),
+            (_, "/reports" | "/summary" | "/machines") => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    /// Answer connections on `listener`, each in its own thread, up to
+    /// `MAX_CONNECTIONS` at once
+    pub fn serve(self: Arc<Self>, listener: TcpListener) {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            if self.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                self.connections.fetch_sub(1, Ordering::SeqCst);
+                let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                let _ = Response::error(503, "too many connections").write_to(&mut stream);
+                continue;
+            }
+            let server = Arc::clone(&self);
+            thread::spawn(move || {
+                server.answer(&mut stream);
+                server.connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    }
+
+    /// Read and answer the request on `stream`, checking the token before
+    /// taking the body, so no one without it can make the server hold
+    /// `MAX_BODY` bytes
+    fn answer(&self, stream: &mut TcpStream) {
+        let _ = stream.set_read_timeout(Some(TIMEOUT));
+        let _ = stream.set_write_timeout(Some(TIMEOUT));
+        let (mut request, rest) = match Request::read_head(stream) {
+            Ok(head) => head,
+            Err(e) => {
+                let _ = Response::error(400, &e).write_to(stream);
+                return;
+            }
+        };
+        if !self.admits(&request) {
+            let _ = Response::error(401, "missing or wrong bearer token").write_to(stream);
+            // Take a little of the unread body before closing, so the
+            // client sees the answer rather than a reset
+            let _ = stream.shutdown(Shutdown::Write);
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+            let _ = std::io::copy(&mut stream.take(MAX_HEAD as u64), &mut std::io::sink());
+            return;
+        }
+        let response = match request.read_body(stream, rest) {
+            Ok(()) => self.handle(&request),
+            Err(e) => Response::error(400, &e),
+        };
+        let _ = response.write_to(stream);
+    }
+}
@@ -0,0 +1,234 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! One summary from many machines' reports
+//!
+//! The same problem reads a little differently on each machine: "/var is
+//! 97% full" here, "/var is 98% full" there. Findings are grouped by
+//! tool, check and their summary with every number replaced by `#`, but
+//! addresses kept whole, so "DNS server 10.0.0.53 does not answer" on 14
+//! machines is one issue and a different server is another. Known issues
+//! are grouped by id, and backends that would not run by tool.
+
+use ambulance_core::schema::short_name;
+use ambulance_core::{CombinedReport, Severity};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// One machine's latest report, in brief
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Machine {
+    pub host: String,
+    /// Seconds since the epoch
+    pub generated: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worst: Option<Severity>,
+    pub findings: usize,
+}
+
+/// A finding seen on one or more machines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Issue {
+    pub tool: String,
+    pub check: String,
+    /// The worst severity any machine gave it
+    pub severity: Severity,
+    /// The summary with its numbers as `#`
+    pub pattern: String,
+    /// The summary as one machine worded it
+    pub example: String,
+    pub hosts: Vec<String>,
+}
+
+/// A known issue, or a backend failure, and the machines it was on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub machines: Vec<Machine>,
+    /// Most widespread first, then the most severe
+    pub issues: Vec<Issue>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known_issues: Vec<Group>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<Group>,
+}
+
+/// An IPv4 or IPv6 address, possibly with a prefix or port, which names
+/// a thing rather than measuring it
+fn is_address(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+    let dotted = word.split('.').count() == 4
+        && word.split('.').all(|p| {
+            !p.is_empty()
+                && p.chars()
+                    .all(|c| c.is_ascii_digit() || c == '/' || c == ':')
+        });
+    let colons =
+        word.matches(':').count() >= 2 && word.chars().all(|c| c.is_ascii_hexdigit() || c == ':');
+    dotted || colons
+}
+
+/// `summary` with each run of digits outside addresses as `#`
+pub fn pattern(summary: &str) -> String {
+    summary
+        .split(' ')
+        .map(|word| {
+            if is_address(word) {
+                return word.to_string();
+            }
+            let mut out = String::with_capacity(word.len());
+            let mut digits = false;
+            for c in word.chars() {
+                if c.is_ascii_digit() {
+                    if !digits {
+                        out.push('#');
+                    }
+                    digits = true;
+                } else {
+                    out.push(c);
+                    digits = false;
+                }
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn push_host(hosts: &mut Vec<String>, host: &str) {
+    if !hosts.iter().any(|h| h == host) {
+        hosts.push(host.to_string());
+    }
+}
+
+/// Summarize one report per machine; several from one host count once,
+/// the newest
+pub fn summarize(reports: &[CombinedReport]) -> Summary {
+    let mut latest: BTreeMap<&str, &CombinedReport> = BTreeMap::new();
+    for report in reports {
+        let newer = latest
+            .get(report.host.as_str())
+            .map_or(true, |r| r.generated <= report.generated);
+        if newer {
+            latest.insert(&report.host, report);
+        }
+    }
+
+    let mut summary = Summary::default();
+    let mut issues: BTreeMap<(String, String, String), Issue> = BTreeMap::new();
+    let mut known: BTreeMap<String, Group> = BTreeMap::new();
+    let mut failures: BTreeMap<String, Group> = BTreeMap::new();
+    for (host, combined) in latest {
+        let mut findings = 0;
+        for report in &combined.reports {
+            let tool = short_name(&report.tool);
+            for check in &report.checks {
+                for finding in &check.findings {
+                    findings += 1;
+                    let pattern = pattern(&finding.summary);
+                    let key = (tool.to_string(), check.id.clone(), pattern.clone());
+                    let issue = issues.entry(key).or_insert_with(|| Issue {
+                        tool: tool.to_string(),
+                        check: check.id.clone(),
+                        severity: finding.severity,
+                        pattern,
+                        example: finding.summary.clone(),
+                        hosts: Vec::new(),
+                    });
+                    issue.severity = issue.severity.max(finding.severity);
+                    push_host(&mut issue.hosts, host);
+                }
+            }
+        }
+        for advice in &combined.advice {
+            let group = known.entry(advice.issue.clone()).or_insert_with(|| Group {
+                id: advice.issue.clone(),
+                title: advice.title.clone(),
+                hosts: Vec::new(),
+            });
+            push_host(&mut group.hosts, host);
+        }
+        for failure in &combined.failures {
+            let group = failures
+                .entry(failure.tool.clone())
+                .or_insert_with(|| Group {
+                    id: failure.tool.clone(),
+                    title: None,
+                    hosts: Vec::new(),
+                });
+            push_host(&mut group.hosts, host);
+        }
+        summary.machines.push(Machine {
+            host: host.to_string(),
+            generated: combined.generated,
+            worst: combined.worst(),
+            findings,
+        });
+    }
+
+    summary.issues = issues.into_values().collect();
+    summary.issues.sort_by(|a, b| {
+        b.hosts
+            .len()
+            .cmp(&a.hosts.len())
+            .then(b.severity.cmp(&a.severity))
+    });
+    summary.known_issues = known.into_values().collect();
+    summary.known_issues.sort_by_key(|g| Reverse(g.hosts.len()));
+    summary.failures = failures.into_values().collect();
+    summary.failures.sort_by_key(|g| Reverse(g.hosts.len()));
+    summary
+}
+
+impl Summary {
+    /// Issues at `min` or worse
+    pub fn at_least(&self, min: Severity) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(move |i| i.severity >= min)
+    }
+
+    /// The summary as lines such as `14 of 200 machines: [warn] ...`
+    pub fn lines(&self, min: Severity) -> Vec<String> {
+        let total = self.machines.len();
+        let of = |n: usize| {
+            if total == 1 {
+                "1 machine".to_string()
+            } else {
+                format!("{} of {} machines", n, total)
+            }
+        };
+        let mut lines: Vec<String> = self
+            .at_least(min)
+            .map(|i| {
+                format!(
+                    "{}: [{}] {}.{}: {}",
+                    of(i.hosts.len()),
+                    i.severity,
+                    i.tool,
+                    i.check,
+                    i.example
+                )
+            })
+            .collect();
+        for group in &self.known_issues {
+            lines.push(format!(
+                "{}: known issue {}",
+                of(group.hosts.len()),
+                group.title.as_deref().unwrap_or(&group.id)
+            ));
+        }
+        for group in &self.failures {
+            lines.push(format!(
+                "{}: {} did not run",
+                of(group.hosts.len()),
+                group.id
+            ));
+        }
+        lines
+    }
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Summaries across machines, and the server that collects them
+
+use ambulance_core::{CombinedReport, DiagnosticReport, Severity};
+use ambulance_fleet::http::{Request, MAX_BODY};
+use ambulance_fleet::{submit, summarize, Server, Summary};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn machine(host: &str, generated: u64, dns: &[&str], disk: &[&str]) -> CombinedReport {
+    let report = |tool: &str, section: &str, warnings: &[&str]| {
+        DiagnosticReport::from_wire(&json!({
+            "version": "0.1.0",
+            "tool": tool,
+            section: {"warnings": warnings, "recommendations": []},
+        }))
+        .unwrap()
+    };
+    let mut combined = CombinedReport::new(host, generated);
+    combined.reports = vec![
+        report("network-ambulance-d", "dns", dns),
+        report("disk-ambulance", "usage", disk),
+    ];
+    combined
+}
+
+#[test]
+fn findings_are_counted_once_per_machine_across_wordings() {
+    let reports = vec![
+        machine(
+            "a",
+            10,
+            &["DNS server 10.0.0.53 does not answer"],
+            &["/var is 97% full"],
+        ),
+        machine(
+            "b",
+            10,
+            &["DNS server 10.0.0.53 does not answer"],
+            &["/var is 98% full"],
+        ),
+        machine("c", 10, &["DNS server 10.9.0.53 does not answer"], &[]),
+        // Superseded by c's report above
+        machine("c", 5, &["DNS server 10.0.0.53 does not answer"], &[]),
+    ];
+    let summary = summarize(&reports);
+
+    assert_eq!(summary.machines.len(), 3);
+    let hosts = |example: &str| {
+        let issue = summary
+            .issues
+            .iter()
+            .find(|i| i.example.contains(example))
+            .unwrap();
+        issue.hosts.clone()
+    };
+    assert_eq!(hosts("10.0.0.53"), ["a", "b"]);
+    assert_eq!(hosts("10.9.0.53"), ["c"]);
+    assert_eq!(hosts("/var"), ["a", "b"]);
+    let disk = summary.issues.iter().find(|i| i.tool == "disk").unwrap();
+    assert_eq!(disk.pattern, "/var is #% full");
+    assert_eq!(
+        summary.lines(Severity::Warn)[0],
+        "2 of 3 machines: [warn] disk.usage: /var is 97% full"
+    );
+}
+
+fn request(method: &str, path: &str, body: &Value, token: Option<&str>) -> Request {
+    let mut request = Request::new(method, path);
+    request.body = serde_json::to_vec(body).unwrap();
+    if let Some(token) = token {
+        request
+            .headers
+            .push(("authorization".into(), format!("Bearer {}", token)));
+    }
+    request
+}
+
+#[test]
+fn server_needs_the_token_and_keeps_reports_across_restarts() {
+    let dir = std::env::temp_dir().join(format!("ambulance-fleet-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let server = Server::new(Some(&dir), Some("s3cret".into())).unwrap();
+    let report = serde_json::to_value(machine(
+        "a",
+        10,
+        &["DNS server 10.0.0.53 does not answer"],
+        &[],
+    ))
+    .unwrap();
+
+    let health = server.handle(&request("GET", "/health", &json!(null), None));
+    assert_eq!(health.status, 200);
+    let refused = server.handle(&request("POST", "/reports", &report, Some("guess")));
+    assert_eq!(refused.status, 401);
+    let stored = server.handle(&request("POST", "/reports", &report, Some("s3cret")));
+    assert_eq!(stored.status, 200, "{}", stored.body);
+    let bad = server.handle(&request(
+        "POST",
+        "/reports",
+        &json!({"host": "x"}),
+        Some("s3cret"),
+    ));
+    assert_eq!(bad.status, 400);
+    let wrong = server.handle(&request("PUT", "/summary", &json!(null), Some("s3cret")));
+    assert_eq!(wrong.status, 405);
+
+    let restarted = Server::new(Some(&dir), Some("s3cret".into())).unwrap();
+    let answer = restarted.handle(&request("GET", "/summary", &json!(null), Some("s3cret")));
+    let summary: Summary = serde_json::from_str(&answer.body).unwrap();
+    assert_eq!(summary.machines[0].host, "a");
+    assert_eq!(summary.issues[0].hosts, ["a"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn doctor_submissions_reach_the_server_over_http() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = Arc::new(Server::new(None, Some("s3cret".into())).unwrap());
+    let serving = Arc::clone(&server);
+    thread::spawn(move || serving.serve(listener));
+
+    for host in ["a", "b"] {
+        let report = machine(host, 10, &["DNS server 10.0.0.53 does not answer"], &[]);
+        submit(&url, &report, Some("s3cret")).unwrap();
+    }
+    let refused = submit(&url, &machine("c", 10, &[], &[]), None).unwrap_err();
+    assert!(refused.contains("401"), "{}", refused);
+
+    let summary = server.summary();
+    assert_eq!(summary.machines.len(), 2);
+    assert_eq!(
+        summary.lines(Severity::Warn),
+        ["2 of 2 machines: [warn] network.dns: DNS server 10.0.0.53 does not answer"]
+    );
+}
+
+#[test]
+fn hosts_differing_only_in_odd_characters_are_kept_apart() {
+    let dir = std::env::temp_dir().join(format!("ambulance-fleet-names-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let server = Server::new(Some(&dir), None).unwrap();
+    for host in ["a/b", "a_b", "a%2Fb", ".."] {
+        let report = serde_json::to_value(machine(host, 10, &[], &[])).unwrap();
+        let stored = server.handle(&request("POST", "/reports", &report, None));
+        assert_eq!(stored.status, 200, "{}", stored.body);
+    }
+
+    let restarted = Server::new(Some(&dir), None).unwrap();
+    let mut hosts: Vec<_> = restarted
+        .summary()
+        .machines
+        .into_iter()
+        .map(|m| m.host)
+        .collect();
+    hosts.sort();
+    assert_eq!(hosts, ["..", "a%2Fb", "a/b", "a_b"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn a_wrong_token_is_refused_before_the_body_is_read() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = Arc::new(Server::new(None, Some("s3cret".into())).unwrap());
+    thread::spawn(move || server.serve(listener));
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    write!(
+        stream,
+        "POST /reports HTTP/1.1\r\nAuthorization: Bearer guess\r\nContent-Length: {}\r\n\r\n",
+        MAX_BODY
+    )
+    .unwrap();
+    let mut answer = String::new();
+    stream.read_to_string(&mut answer).unwrap();
+    assert!(answer.starts_with("HTTP/1.1 401"), "{}", answer);
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Vulkan and EGL availability
+//!
+//! Installed Vulkan ICDs and glvnd EGL vendors are read from their
+//! manifest directories. When `vulkaninfo` and `eglinfo` are installed
+//! they also reveal a silent fallback to software rendering (llvmpipe).
+
+use super::drivers::{self, Gpu};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+const VULKAN_ICD_DIRS: &[&str] = &["/usr/share/vulkan/icd.d", "/etc/vulkan/icd.d"];
+const EGL_VENDOR_DIRS: &[&str] = &["/usr/share/glvnd/egl_vendor.d", "/etc/glvnd/egl_vendor.d"];
+
+/// Substring of the ICD manifest name each kernel driver needs
+const ICD_FOR_DRIVER: &[(&str, &str)] = &[
+    ("amdgpu", "radeon"),
+    ("i915", "intel"),
+    ("xe", "intel"),
+    ("nvidia", "nvidia"),
+    ("nouveau", "nouveau"),
+];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApiDiagnostics {
+    pub vulkan_icds: Vec<String>,
+    pub egl_vendors: Vec<String>,
+    /// Device names `vulkaninfo` reports, when it is installed
+    pub vulkan_devices: Option<Vec<String>>,
+    /// `OpenGL renderer string` from `eglinfo`, when it is installed
+    pub egl_renderer: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn manifests(dirs: &[&str]) -> Vec<String> {
+    let mut names: Vec<String> = dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(Path::new(dir)).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|n| n.ends_with(".json"))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn stdout_of(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn value_after<'a>(text: &'a str, key: &str) -> impl Iterator<Item = String> + 'a {
+    let key = key.to_string();
+    text.lines().filter_map(move |l| {
+        let (k, v) = l.split_once(if l.contains('=') { '=' } else { ':' })?;
+        (k.trim() == key).then(|| v.trim().to_string())
+    })
+}
+
+pub fn diagnose() -> ApiDiagnostics {
+    let mut diag = ApiDiagnostics {
+        vulkan_icds: manifests(VULKAN_ICD_DIRS),
+        egl_vendors: manifests(EGL_VENDOR_DIRS),
+        vulkan_devices: stdout_of("vulkaninfo", &["--summary"])
+            .map(|text| value_after(&text, "deviceName").collect()),
+        egl_renderer: stdout_of("eglinfo", &["-B"])
+            .and_then(|text| value_after(&text, "OpenGL renderer string").next()),
+        ..ApiDiagnostics::default()
+    };
+    let gpus: Vec<Gpu> = drivers::gpus().unwrap_or_default();
+
+    for gpu in &gpus {
+        let Some(driver) = gpu.driver.as_deref() else {
+            continue;
+        };
+        let Some((_, icd)) = ICD_FOR_DRIVER.iter().find(|(d, _)| *d == driver) else {
+            continue;
+        };
+        if !diag.vulkan_icds.iter().any(|name| name.contains(icd)) {
+            diag.warnings.push(format!(
+                "No Vulkan driver installed for {} ({})",
+                gpu.slot, driver
+            ));
+            diag.recommendations.push(match driver {
+                "nvidia" => "Install the Vulkan package matching the NVIDIA driver version".to_string(),
+                _ => "Install Mesa's Vulkan drivers (mesa-vulkan-drivers or vulkan-radeon / vulkan-intel)".to_string(),
+            });
+        }
+    }
+    if diag.egl_vendors.is_empty() && !gpus.is_empty() {
+        diag.warnings
+            .push("No EGL vendor library is registered with glvnd".to_string());
+        diag.recommendations
+            .push("Install Mesa's EGL library (libegl-mesa0 or mesa-libEGL)".to_string());
+    }
+
+    let software = |name: &str| name.contains("llvmpipe") || name.contains("softpipe");
+    if let Some(devices) = &diag.vulkan_devices {
+        if !devices.is_empty() && devices.iter().all(|d| software(d)) {
+            diag.warnings
+                .push("Vulkan only finds the llvmpipe software renderer".to_string());
+        }
+    }
+    if diag.egl_renderer.as_deref().is_some_and(software) {
+        diag.warnings
+            .push("OpenGL renders in software (llvmpipe)".to_string());
+    }
+    if diag.warnings.iter().any(|w| w.contains("llvmpipe")) {
+        diag.recommendations.push(
+            "Software rendering means the GPU driver is missing or failed; see the driver and firmware sections".to_string(),
+        );
+    }
+    diag
+}
+
+impl ApiDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Graphics API Diagnostics ===");
+        println!(
+            "{} Vulkan drivers: {}",
+            mark(!self.vulkan_icds.is_empty()),
+            self.vulkan_icds.len()
+        );
+        println!(
+            "{} EGL vendors: {}",
+            mark(!self.egl_vendors.is_empty()),
+            self.egl_vendors.len()
+        );
+        if let Some(renderer) = &self.egl_renderer {
+            println!("- Renderer: {}", renderer);
+        }
+        if verbose {
+            for name in self.vulkan_icds.iter().chain(&self.egl_vendors) {
+                println!("    {}", name);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
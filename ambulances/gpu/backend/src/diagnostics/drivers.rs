@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Which kernel driver drives each GPU
+//!
+//! Display controllers are PCI class 0x03. The bound driver and the DRM
+//! card node come from sd-device; kernel parameters that change driver
+//! behaviour come from `/proc/cmdline` and `/sys/module`.
+
+use crate::report::{mark, print_notes};
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::device;
+
+/// PCI vendors whose names the hardware database may not supply
+const VENDORS: &[(&str, &str)] = &[
+    ("0x8086", "Intel"),
+    ("0x1002", "AMD"),
+    ("0x10de", "NVIDIA"),
+    ("0x1af4", "virtio"),
+    ("0x15ad", "VMware"),
+    ("0x1234", "QEMU"),
+    ("0x1414", "Microsoft"),
+    ("0x80ee", "VirtualBox"),
+    ("0x1a03", "ASPEED"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Gpu {
+    /// PCI address, e.g. `0000:00:02.0`
+    pub slot: String,
+    pub vendor: String,
+    pub vendor_id: String,
+    pub device_id: String,
+    pub driver: Option<String>,
+    /// The GPU the firmware initialised the console on
+    pub boot_vga: bool,
+    /// e.g. `card0`; `None` when no DRM driver registered a card for it
+    pub drm_card: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriverDiagnostics {
+    pub gpus: Vec<Gpu>,
+    pub nomodeset: bool,
+    /// `nvidia-drm.modeset`, when the NVIDIA driver is loaded
+    pub nvidia_modeset: Option<bool>,
+    /// Why devices could not be enumerated
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn vendor_name(id: &str) -> String {
+    VENDORS
+        .iter()
+        .find(|(v, _)| *v == id)
+        .map_or_else(|| id.to_string(), |(_, name)| name.to_string())
+}
+
+/// Display-class PCI devices with the DRM cards that belong to them
+pub fn gpus() -> std::io::Result<Vec<Gpu>> {
+    let cards: Vec<(String, String)> = device::enumerate("drm")?
+        .iter()
+        .filter_map(|card| {
+            let name = card.sysname()?;
+            // Connectors are `card0-HDMI-A-1`; only the cards themselves
+            let is_card = name
+                .strip_prefix("card")
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+            is_card.then_some((card.parent()?.sysname()?, name))
+        })
+        .collect();
+
+    Ok(device::enumerate("pci")?
+        .iter()
+        .filter(|d| d.sysattr("class").is_some_and(|c| c.starts_with("0x03")))
+        .map(|d| {
+            let slot = d.sysname().unwrap_or_default();
+            let vendor_id = d.sysattr("vendor").unwrap_or_default();
+            Gpu {
+                vendor: d
+                    .property("ID_VENDOR_FROM_DATABASE")
+                    .unwrap_or_else(|| vendor_name(&vendor_id)),
+                device_id: d.sysattr("device").unwrap_or_default(),
+                driver: d.driver(),
+                boot_vga: d.sysattr("boot_vga").as_deref() == Some("1"),
+                drm_card: cards
+                    .iter()
+                    .find(|(parent, _)| *parent == slot)
+                    .map(|(_, card)| card.clone()),
+                slot,
+                vendor_id,
+            }
+        })
+        .collect())
+}
+
+fn cmdline_has(param: &str) -> bool {
+    std::fs::read_to_string(host::path("/proc/cmdline"))
+        .unwrap_or_default()
+        .split_whitespace()
+        .any(|p| p == param)
+}
+
+pub fn diagnose() -> DriverDiagnostics {
+    let mut diag = DriverDiagnostics {
+        nomodeset: cmdline_has("nomodeset"),
+        nvidia_modeset: std::fs::read_to_string(host::path(
+            "/sys/module/nvidia_drm/parameters/modeset",
+        ))
+        .ok()
+        .map(|v| v.trim() == "Y"),
+        ..DriverDiagnostics::default()
+    };
+    match gpus() {
+        Ok(gpus) => diag.gpus = gpus,
+        Err(e) => {
+            diag.error = Some(format!("cannot enumerate devices: {}", e));
+            return diag;
+        }
+    }
+
+    if diag.gpus.is_empty() {
+        diag.warnings
+            .push("No display controller found".to_string());
+    }
+    if diag.nomodeset {
+        diag.warnings.push(
+            "`nomodeset` is on the kernel command line; GPU drivers cannot set display modes"
+                .to_string(),
+        );
+        diag.recommendations.push(
+            "Remove `nomodeset` from GRUB_CMDLINE_LINUX_DEFAULT in /etc/default/grub and update the bootloader".to_string(),
+        );
+    }
+
+    for gpu in &diag.gpus {
+        let label = format!("{} ({})", gpu.slot, gpu.vendor);
+        match gpu.driver.as_deref() {
+            None => {
+                diag.warnings.push(format!("{} has no driver bound", label));
+                diag.recommendations.push(match gpu.vendor_id.as_str() {
+                    "0x10de" => "Install the NVIDIA driver for your distribution, or check that nouveau is not blacklisted".to_string(),
+                    _ => format!("Check `journalctl -k -b` for why no driver claimed {}", gpu.slot),
+                });
+            }
+            Some("vfio-pci") if gpu.boot_vga => {
+                diag.warnings.push(format!(
+                    "{} is the boot display but is bound to vfio-pci for passthrough",
+                    label
+                ));
+                diag.recommendations.push(
+                    "Remove the GPU's IDs from the vfio-pci.ids= kernel parameter to use it on the host"
+                        .to_string(),
+                );
+            }
+            Some("nouveau") => diag.recommendations.push(format!(
+                "{} runs on nouveau; the proprietary NVIDIA driver is faster and reclocks the GPU",
+                label
+            )),
+            Some("radeon") => diag.recommendations.push(format!(
+                "If {} is a GCN 1.0/2.0 card, amdgpu supports Vulkan on it: radeon.si_support=0 amdgpu.si_support=1 (or the cik_ equivalents)",
+                label
+            )),
+            Some(_) => {}
+        }
+        if gpu.driver.is_some()
+            && gpu.drm_card.is_none()
+            && gpu.driver.as_deref() != Some("vfio-pci")
+        {
+            diag.warnings
+                .push(format!("{} has a driver but no DRM card", label));
+        }
+    }
+    if diag.nvidia_modeset == Some(false) {
+        diag.warnings
+            .push("NVIDIA kernel modesetting is off; Wayland sessions will not start".to_string());
+        diag.recommendations
+            .push("Add `nvidia-drm.modeset=1` to the kernel command line".to_string());
+    }
+    diag
+}
+
+impl DriverDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Driver Diagnostics ===");
+        if let Some(e) = &self.error {
+            println!("✗ {}", e);
+        }
+        for gpu in &self.gpus {
+            println!(
+                "{} {} {}: {}{}",
+                mark(gpu.driver.is_some()),
+                gpu.slot,
+                gpu.vendor,
+                gpu.driver.as_deref().unwrap_or("no driver"),
+                if gpu.boot_vga { " (boot display)" } else { "" }
+            );
+            if verbose {
+                println!(
+                    "    device {}, {}",
+                    gpu.device_id,
+                    gpu.drm_card.as_deref().unwrap_or("no DRM card")
+                );
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! GPU firmware the kernel failed to load this boot
+//!
+//! Intel needs DMC/GuC/HuC blobs for power saving and media, AMD cannot
+//! initialise at all without its firmware, and nouveau needs signed
+//! firmware for acceleration. Failures are read from this boot's kernel
+//! messages in the journal.
+
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::journal::{self, Journal};
+
+/// Driver names, and the firmware directories they load from
+const GPU_DRIVERS: &[&str] = &["i915", "xe", "amdgpu", "radeon", "nouveau", "nvidia"];
+
+const FAILURES: &[&str] = &[
+    "failed",
+    "not found",
+    "missing",
+    "error -2",
+    "unable to load",
+];
+
+const MAX_SAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirmwareDiagnostics {
+    pub journal_available: bool,
+    /// Matching kernel messages, oldest first
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn is_gpu_firmware_failure(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    lower.contains("firmware")
+        && GPU_DRIVERS.iter().any(|d| lower.contains(d))
+        && FAILURES.iter().any(|f| lower.contains(f))
+}
+
+fn kernel_messages() -> std::io::Result<Vec<String>> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.match_this_boot()?;
+    journal.seek_tail()?;
+    let mut messages = Vec::new();
+    while journal.previous_entry()? {
+        if let Some(message) = journal.field("MESSAGE") {
+            messages.push(message);
+        }
+    }
+    messages.reverse();
+    Ok(messages)
+}
+
+pub fn diagnose() -> FirmwareDiagnostics {
+    let mut diag = FirmwareDiagnostics::default();
+    match kernel_messages() {
+        Ok(messages) => {
+            diag.journal_available = !messages.is_empty();
+            diag.errors = messages
+                .into_iter()
+                .filter(|m| is_gpu_firmware_failure(m))
+                .take(MAX_SAMPLES)
+                .collect();
+        }
+        Err(e) => diag
+            .warnings
+            .push(format!("Cannot read the journal: {}", e)),
+    }
+    if !diag.journal_available {
+        diag.recommendations.push(
+            "No kernel messages for this boot: run as root or in the systemd-journal group, and check systemd-journald is running".to_string(),
+        );
+    }
+
+    diag.warnings
+        .extend(diag.errors.iter().map(|e| format!("Firmware: {}", e)));
+    if !diag.errors.is_empty() {
+        diag.recommendations.push(
+            "Install the GPU firmware (linux-firmware; firmware-misc-nonfree or firmware-amd-graphics on Debian), then regenerate the initramfs and reboot".to_string(),
+        );
+    }
+    diag
+}
+
+impl FirmwareDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Firmware Diagnostics ===");
+        if self.journal_available {
+            println!(
+                "{} GPU firmware load errors: {}",
+                mark(self.errors.is_empty()),
+                self.errors.len()
+            );
+        } else {
+            println!("- GPU firmware load errors: unknown");
+        }
+        if verbose {
+            for e in &self.errors {
+                println!("    {}", e);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
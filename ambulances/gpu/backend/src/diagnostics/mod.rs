@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! GPU diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget, so a graphics API probe stuck on a wedged GPU does not
+//! hold up the driver and firmware checks.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod apis;
+pub mod drivers;
+pub mod firmware;
+pub mod sessions;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; a wedged GPU can hang the API probes
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Drivers(drivers::DriverDiagnostics),
+    Firmware(firmware::FirmwareDiagnostics),
+    Apis(apis::ApiDiagnostics),
+    Sessions(sessions::SessionDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("drivers", |_| {
+            Ok(Section::Drivers(drivers::diagnose()))
+        }))
+        .job(Job::blocking("firmware", |_| {
+            Ok(Section::Firmware(firmware::diagnose()))
+        }))
+        .job(Job::blocking("apis", |_| {
+            Ok(Section::Apis(apis::diagnose()))
+        }))
+        .job(Job::blocking("sessions", |_| {
+            Ok(Section::Sessions(sessions::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        drivers: Default::default(),
+        firmware: Default::default(),
+        apis: Default::default(),
+        sessions: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Drivers(d)) => result.drivers = d,
+            Some(Section::Firmware(d)) => result.firmware = d,
+            Some(Section::Apis(d)) => result.apis = d,
+            Some(Section::Sessions(d)) => result.sessions = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "drivers" => &mut result.drivers.warnings,
+                    "firmware" => &mut result.firmware.warnings,
+                    "apis" => &mut result.apis.warnings,
+                    _ => &mut result.sessions.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
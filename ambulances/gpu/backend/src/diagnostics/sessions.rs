@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Display servers and compositors stuck in a crash loop
+//!
+//! systemd-coredump logs every crash with the process name and time. A
+//! display server that crashes again and again usually means a driver
+//! problem, and the login screen quietly bouncing back is its symptom.
+
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// `MESSAGE_ID` of systemd-coredump's crash reports
+const COREDUMP_MESSAGE: &str = "fc2e22bc6ee647b6b90729ab34a250b1";
+
+/// Process names (`comm`, at most 15 characters) of display servers
+const DISPLAY_SERVERS: &[&str] = &[
+    "Xorg",
+    "X",
+    "Xwayland",
+    "gnome-shell",
+    "kwin_wayland",
+    "kwin_x11",
+    "plasmashell",
+    "sway",
+    "Hyprland",
+    "weston",
+    "mutter",
+    "gamescope",
+    "cosmic-comp",
+    "labwc",
+    "wayfire",
+];
+
+/// Crashes counted over this window
+const WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Crashes in the window that make a crash loop
+pub const CRASH_LOOP: u32 = 3;
+
+/// Coredump entries read at most
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Crashes {
+    pub process: String,
+    pub count: u32,
+    /// Signal of the most recent crash, e.g. `SIGSEGV`
+    pub last_signal: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionDiagnostics {
+    /// `XDG_SESSION_TYPE` of the calling session, if any
+    pub session_type: Option<String>,
+    pub crashes: Vec<Crashes>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Display-server crashes within the window, by process
+fn recent_crashes() -> std::io::Result<Vec<Crashes>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64);
+    let since = now.saturating_sub(WINDOW_SECS * 1_000_000);
+
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match(&format!("MESSAGE_ID={}", COREDUMP_MESSAGE))?;
+    journal.seek_tail()?;
+    let mut by_process: BTreeMap<String, Crashes> = BTreeMap::new();
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? {
+            break;
+        }
+        let Some(timestamp) = journal
+            .field("COREDUMP_TIMESTAMP")
+            .and_then(|t| t.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        // Newest first, so the first old entry ends the window
+        if timestamp < since {
+            break;
+        }
+        let Some(comm) = journal.field("COREDUMP_COMM") else {
+            continue;
+        };
+        if !DISPLAY_SERVERS.contains(&comm.as_str()) {
+            continue;
+        }
+        let signal = journal.field("COREDUMP_SIGNAL_NAME");
+        let entry = by_process.entry(comm.clone()).or_insert(Crashes {
+            process: comm,
+            count: 0,
+            last_signal: signal,
+        });
+        entry.count += 1;
+    }
+    Ok(by_process.into_values().collect())
+}
+
+pub fn diagnose() -> SessionDiagnostics {
+    let mut diag = SessionDiagnostics {
+        session_type: std::env::var("XDG_SESSION_TYPE").ok(),
+        ..SessionDiagnostics::default()
+    };
+    match recent_crashes() {
+        Ok(crashes) => diag.crashes = crashes,
+        Err(e) => diag
+            .warnings
+            .push(format!("Cannot read the journal: {}", e)),
+    }
+
+    let looping: Vec<&Crashes> = diag
+        .crashes
+        .iter()
+        .filter(|c| c.count >= CRASH_LOOP)
+        .collect();
+    for c in &looping {
+        diag.warnings.push(format!(
+            "{} crashed {} times in the last 24 hours{}",
+            c.process,
+            c.count,
+            c.last_signal
+                .as_deref()
+                .map_or(String::new(), |s| format!(" (last: {})", s))
+        ));
+    }
+    if looping
+        .iter()
+        .any(|c| c.process.starts_with('X') && c.process != "Xwayland")
+    {
+        diag.recommendations.push(
+            "Xorg keeps crashing: choose a Wayland session at the login screen, and check the driver section".to_string(),
+        );
+    }
+    if looping
+        .iter()
+        .any(|c| !c.process.starts_with('X') || c.process == "Xwayland")
+    {
+        diag.recommendations.push(
+            "The Wayland compositor keeps crashing: try the Xorg session, and on NVIDIA make sure nvidia-drm.modeset=1 is set".to_string(),
+        );
+    }
+    if !looping.is_empty() {
+        diag.recommendations.push(format!(
+            "Inspect the backtrace: coredumpctl info {}",
+            looping[0].process
+        ));
+    }
+    diag
+}
+
+impl SessionDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Session Diagnostics ===");
+        if let Some(kind) = &self.session_type {
+            println!("Session: {}", kind);
+        }
+        let loops = self
+            .crashes
+            .iter()
+            .filter(|c| c.count >= CRASH_LOOP)
+            .count();
+        println!("{} Display server crash loops: {}", mark(loops == 0), loops);
+        if verbose {
+            for c in &self.crashes {
+                println!("    {}: {} crash(es)", c.process, c.count);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`. This ambulance only
+//! recommends; driver and boot-parameter changes are left to the user.
+
+use crate::diagnostics::{
+    apis::ApiDiagnostics, drivers::DriverDiagnostics, firmware::FirmwareDiagnostics,
+    sessions::SessionDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes};
+
+pub const TOOL: &str = "gpu-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub drivers: DriverDiagnostics,
+    pub firmware: FirmwareDiagnostics,
+    pub apis: ApiDiagnostics,
+    pub sessions: SessionDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.drivers.warnings,
+            &self.firmware.warnings,
+            &self.apis.warnings,
+            &self.sessions.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! journald.conf and its drop-ins
+//!
+//! Read the way journald reads them: the main file, then every drop-in
+//! from `/usr/lib`, `/run` and `/etc` in file name order, a same-named
+//! file in a later directory masking the earlier one and a later setting
+//! replacing an earlier one. Only the `[Journal]` section is kept.
+
+use crate::system;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const MAIN: &str = "/etc/systemd/journald.conf";
+
+const DROPIN_DIRS: &[&str] = &[
+    "/usr/lib/systemd/journald.conf.d",
+    "/run/systemd/journald.conf.d",
+    DROPIN_DIR,
+];
+
+pub const DROPIN_DIR: &str = "/etc/systemd/journald.conf.d";
+
+/// Drop-in written by `repair retention`
+pub const DROPIN: &str = "/etc/systemd/journald.conf.d/journal-ambulance.conf";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Setting {
+    pub value: String,
+    /// File the effective value came from
+    pub file: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct JournaldConfig {
+    pub settings: BTreeMap<String, Setting>,
+}
+
+impl JournaldConfig {
+    /// Effective value of `key`; an empty assignment resets it to the default
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.settings
+            .get(key)
+            .map(|s| s.value.as_str())
+            .filter(|v| !v.is_empty())
+    }
+
+    pub fn file(&self, key: &str) -> Option<&str> {
+        self.settings.get(key).map(|s| s.file.as_str())
+    }
+
+    /// Drop-in setting `key` that sorts after `DROPIN` and so overrides it
+    pub fn overriding(&self, key: &str) -> Option<&str> {
+        let file = self.file(key)?;
+        let name = Path::new(file).file_name()?;
+        (file != MAIN && file != DROPIN && name > Path::new(DROPIN).file_name()?).then_some(file)
+    }
+}
+
+fn files() -> Vec<PathBuf> {
+    let mut dropins: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in DROPIN_DIRS {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".conf") {
+                dropins.insert(name, entry.path());
+            }
+        }
+    }
+    std::iter::once(PathBuf::from(MAIN))
+        .chain(dropins.into_values())
+        .collect()
+}
+
+pub fn load() -> JournaldConfig {
+    let mut config = JournaldConfig::default();
+    for path in files() {
+        let Some(text) = system::read(&path) else {
+            continue;
+        };
+        let mut in_journal = false;
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_journal = line == "[Journal]";
+                continue;
+            }
+            if !in_journal || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                config.settings.insert(
+                    key.trim().to_string(),
+                    Setting {
+                        value: value.trim().to_string(),
+                        file: path.display().to_string(),
+                    },
+                );
+            }
+        }
+    }
+    config
+}
+
+/// `500M`, `1G`, `4096`: journald's sizes, suffixes 1024-based
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.chars().last()?.to_ascii_uppercase() {
+        'K' => (&value[..value.len() - 1], 1u64 << 10),
+        'M' => (&value[..value.len() - 1], 1 << 20),
+        'G' => (&value[..value.len() - 1], 1 << 30),
+        'T' => (&value[..value.len() - 1], 1 << 40),
+        'P' => (&value[..value.len() - 1], 1 << 50),
+        'E' => (&value[..value.len() - 1], 1 << 60),
+        _ => (value, 1),
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    (number >= 0.0).then_some((number * multiplier as f64) as u64)
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Units flooding the log
+//!
+//! Counts what each unit logged over the last hour with the shim's rate
+//! statistics, and collects journald's own "Suppressed N messages"
+//! reports from the last day: those mean a unit hit the rate limit and
+//! its messages, possibly the one that mattered, were dropped.
+
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// Window the rates are measured over
+const WINDOW_SECS: u64 = 3600;
+
+/// Entries per window from one source that count as flooding: one a second
+const FLOOD_ENTRIES: u64 = 3600;
+
+/// Busiest sources kept in the report
+const TOP: usize = 10;
+
+/// `SD_MESSAGE_JOURNAL_DROPPED`, logged when the rate limit drops messages
+const DROPPED_ID: &str = "MESSAGE_ID=a596d6fe7bfa4994828e72309e95d61e";
+
+const SUPPRESSED_WINDOW_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Source {
+    /// Unit, or syslog identifier for messages outside one
+    pub source: String,
+    pub entries: u64,
+    pub message_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Suppression {
+    pub unit: String,
+    /// Messages dropped over the last day
+    pub dropped: u64,
+    /// Times the limit was hit
+    pub events: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FloodingDiagnostics {
+    pub window_secs: u64,
+    pub total_entries: u64,
+    /// Busiest first, at most `TOP`
+    pub sources: Vec<Source>,
+    /// Sources over `FLOOD_ENTRIES`
+    pub flooding: Vec<Source>,
+    /// Most dropped first
+    pub suppressions: Vec<Suppression>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn now_usec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64)
+}
+
+/// `Suppressed 1234 messages from /system.slice/foo.service`
+fn suppressed(message: &str) -> Option<(u64, String)> {
+    let rest = message.strip_prefix("Suppressed ")?;
+    let (count, rest) = rest.split_once(" messages from ")?;
+    let unit = rest.trim().rsplit('/').next()?;
+    Some((count.parse().ok()?, unit.to_string()))
+}
+
+fn suppressions(since: u64) -> std::io::Result<Vec<Suppression>> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match(DROPPED_ID)?;
+    journal.seek_tail()?;
+    let mut found: Vec<Suppression> = Vec::new();
+    while journal.previous_entry()? {
+        if journal.realtime_usec()? < since {
+            break;
+        }
+        let Some((count, unit)) = journal.field("MESSAGE").as_deref().and_then(suppressed) else {
+            continue;
+        };
+        let dropped = journal
+            .field("N_DROPPED")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(count);
+        match found.iter_mut().find(|s| s.unit == unit) {
+            Some(s) => {
+                s.dropped += dropped;
+                s.events += 1;
+            }
+            None => found.push(Suppression {
+                unit,
+                dropped,
+                events: 1,
+            }),
+        }
+    }
+    found.sort_by_key(|s| Reverse(s.dropped));
+    Ok(found)
+}
+
+fn remedy(source: &str) -> String {
+    if source.ends_with(".service") || source.ends_with(".scope") {
+        format!(
+            "Quiet {0}: fix what it keeps logging, or `systemctl edit {0}` and set LogLevelMax=notice",
+            source
+        )
+    } else if source == "kernel" {
+        "The kernel floods the log: see kernel-ambulance diagnose".to_string()
+    } else {
+        format!("Find what runs as {} and lower its log level", source)
+    }
+}
+
+pub fn diagnose() -> FloodingDiagnostics {
+    let mut diag = FloodingDiagnostics {
+        window_secs: WINDOW_SECS,
+        ..FloodingDiagnostics::default()
+    };
+    let now = now_usec();
+    let stats = match journal::rate_stats(now.saturating_sub(WINDOW_SECS * 1_000_000)) {
+        Ok(stats) => stats,
+        Err(e) => {
+            diag.error = Some(format!("Could not read the journal: {}", e));
+            return diag;
+        }
+    };
+    let sources: Vec<Source> = stats
+        .into_iter()
+        .map(|s| Source {
+            source: s.source,
+            entries: s.entries,
+            message_bytes: s.message_bytes,
+        })
+        .collect();
+    diag.total_entries = sources.iter().map(|s| s.entries).sum();
+    diag.flooding = sources
+        .iter()
+        .filter(|s| s.entries >= FLOOD_ENTRIES)
+        .cloned()
+        .collect();
+    diag.sources = sources.into_iter().take(TOP).collect();
+    diag.suppressions =
+        suppressions(now.saturating_sub(SUPPRESSED_WINDOW_SECS * 1_000_000)).unwrap_or_default();
+
+    for s in &diag.flooding {
+        diag.warnings.push(format!(
+            "{} logged {} messages in the last hour",
+            s.source, s.entries
+        ));
+        diag.recommendations.push(remedy(&s.source));
+    }
+    for s in &diag.suppressions {
+        diag.warnings.push(format!(
+            "journald dropped {} messages from {} over the last day (rate limit hit {} time(s))",
+            s.dropped, s.unit, s.events
+        ));
+        if !diag.flooding.iter().any(|f| f.source == s.unit) {
+            diag.recommendations.push(remedy(&s.unit));
+        }
+    }
+
+    diag
+}
+
+impl FloodingDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Flooding ===");
+        if let Some(error) = &self.error {
+            println!("- Rates: unknown ({})", error);
+            println!();
+            return;
+        }
+        println!(
+            "{} Messages in the last hour: {}",
+            mark(self.flooding.is_empty() && self.suppressions.is_empty()),
+            self.total_entries
+        );
+        if verbose {
+            for s in &self.sources {
+                println!("  {:>8}  {}", s.entries, s.source);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Journal diagnostics, one module per report section
+//!
+//! journald's configuration is read once and shared by the sections that
+//! judge it. The sections run as ambulance-core jobs within the scan's
+//! budget: usage needs storage's answer on where the journal lives, so
+//! it waits on it, and flooding runs alongside both. A section not done
+//! in time says so in its warnings, as does usage when storage is not;
+//! one the budget cut short is left empty and its timing says why.
+
+pub mod flooding;
+pub mod storage;
+pub mod usage;
+
+use crate::config;
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Inputs, Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a section may take; reading back through a large journal
+/// for its oldest entry, or its floods, takes a while
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+enum Section {
+    Storage(storage::StorageDiagnostics),
+    Usage(usage::UsageDiagnostics),
+    Flooding(flooding::FloodingDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let config = Arc::new(config::load());
+    let storage_config = Arc::clone(&config);
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("storage", move |_| {
+            Ok(Section::Storage(storage::diagnose(&storage_config)))
+        }))
+        .job(
+            Job::blocking("usage", move |inputs: Inputs<Section>| {
+                let Some(Section::Storage(storage)) = inputs.get("storage") else {
+                    return Err("no storage section".to_string());
+                };
+                Ok(Section::Usage(usage::diagnose(&config, storage)))
+            })
+            .after(&["storage"]),
+        )
+        .job(Job::blocking("flooding", |_| {
+            Ok(Section::Flooding(flooding::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        storage: Default::default(),
+        usage: Default::default(),
+        flooding: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Storage(d)) => result.storage = d,
+            Some(Section::Usage(d)) => result.usage = d,
+            Some(Section::Flooding(d)) => result.flooding = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "storage" => &mut result.storage.warnings,
+                    "usage" => &mut result.usage.warnings,
+                    _ => &mut result.flooding.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
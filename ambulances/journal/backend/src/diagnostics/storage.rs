@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Persistent or volatile storage
+//!
+//! With the default `Storage=auto` journald only keeps logs across
+//! reboots when `/var/log/journal` exists; otherwise they live in
+//! `/run/log/journal` and vanish at shutdown, taking the evidence of
+//! whatever caused the last crash with them.
+
+use crate::config::JournaldConfig;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::path::Path;
+
+pub const PERSISTENT_DIR: &str = "/var/log/journal";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageDiagnostics {
+    /// `Storage=` as configured, `auto` by default
+    pub setting: String,
+    /// `persistent`, `volatile` or `none`
+    pub mode: &'static str,
+    pub persistent_dir: bool,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(config: &JournaldConfig) -> StorageDiagnostics {
+    let setting = config.get("Storage").unwrap_or("auto").to_string();
+    let persistent_dir = Path::new(PERSISTENT_DIR).is_dir();
+    let mode = match setting.as_str() {
+        "persistent" => "persistent",
+        "volatile" => "volatile",
+        "none" => "none",
+        _ if persistent_dir => "persistent",
+        _ => "volatile",
+    };
+    let mut diag = StorageDiagnostics {
+        setting,
+        mode,
+        persistent_dir,
+        ..StorageDiagnostics::default()
+    };
+
+    match mode {
+        "none" => {
+            diag.warnings
+                .push("journald discards every message (Storage=none)".to_string());
+            diag.recommendations.push(format!(
+                "Remove Storage=none from {} unless another logger gets everything",
+                config.file("Storage").unwrap_or("journald.conf")
+            ));
+        }
+        "volatile" => {
+            diag.warnings
+                .push("Logs are kept in memory only and lost at every reboot".to_string());
+            if diag.setting == "volatile" {
+                diag.recommendations.push(format!(
+                    "Set Storage=persistent in {} to keep them",
+                    config.file("Storage").unwrap_or("journald.conf")
+                ));
+            } else {
+                diag.recommendations.push(format!(
+                    "Keep them: mkdir -p {0} && systemd-tmpfiles --create --prefix {0} && journalctl --flush",
+                    PERSISTENT_DIR
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    diag
+}
+
+impl StorageDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Storage ===");
+        println!(
+            "{} Mode: {} (Storage={})",
+            mark(self.mode == "persistent"),
+            self.mode,
+            self.setting
+        );
+        if verbose {
+            println!(
+                "- {}: {}",
+                PERSISTENT_DIR,
+                if self.persistent_dir {
+                    "present"
+                } else {
+                    "absent"
+                }
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Journal size, cap and retention
+//!
+//! Without `SystemMaxUse=` journald lets the journal take 10% of its
+//! filesystem, up to 4G, and rotates away the oldest files beyond that.
+//! Too generous a cap wastes space; too tight a one, or a flood of
+//! messages, means only hours of history survive. How far back the
+//! oldest entry goes shows which of the two is happening.
+
+use crate::config::{self, human_bytes, JournaldConfig};
+use crate::diagnostics::storage::StorageDiagnostics;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// Cap `repair retention` sets, and `repair vacuum` shrinks to when
+/// none is configured
+pub const RECOMMENDED_MAX_USE: u64 = 1 << 30;
+
+/// journald's own ceiling on its default cap
+const DEFAULT_CEILING: u64 = 4 << 30;
+
+/// History shorter than this with the journal full means it rotates too fast
+const SHORT_RETENTION_DAYS: f64 = 3.0;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageDiagnostics {
+    pub usage_bytes: Option<u64>,
+    /// `SystemMaxUse=` (or `RuntimeMaxUse=` when volatile) in bytes,
+    /// or journald's default for the filesystem
+    pub max_use_bytes: Option<u64>,
+    pub max_use_configured: bool,
+    pub archived_files: usize,
+    /// `.journal~` files journald renamed after finding them damaged
+    pub corrupt_files: usize,
+    /// Age of the oldest entry
+    pub retention_days: Option<f64>,
+    /// Rotation settings that are set: `SystemMaxFileSize=`,
+    /// `MaxFileSec=`, `MaxRetentionSec=`, `SystemKeepFree=`
+    pub rotation: Vec<(String, String)>,
+    /// Whether `repair retention` would change the cap
+    pub retune: bool,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+// statvfs field widths differ between targets
+#[allow(clippy::unnecessary_cast)]
+fn filesystem_size(path: &str) -> Option<u64> {
+    let path = CString::new(path).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    Some(stat.f_blocks as u64 * stat.f_frsize as u64)
+}
+
+/// Age of the oldest entry in any journal file, in days
+fn retention_days() -> std::io::Result<Option<f64>> {
+    let mut journal = Journal::open(0)?;
+    journal.seek_head()?;
+    if !journal.next_entry()? {
+        return Ok(None);
+    }
+    let oldest = journal.realtime_usec()?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64);
+    Ok(Some(now.saturating_sub(oldest) as f64 / 86_400e6))
+}
+
+pub fn diagnose(config: &JournaldConfig, storage: &StorageDiagnostics) -> UsageDiagnostics {
+    let (key, dir) = if storage.mode == "persistent" {
+        ("SystemMaxUse", "/var/log/journal")
+    } else {
+        ("RuntimeMaxUse", "/run/log/journal")
+    };
+    let archived = journal::archived_files();
+    let corrupt_files = archived
+        .iter()
+        .filter(|f| f.path.to_string_lossy().ends_with(".journal~"))
+        .count();
+    let configured = config.get(key).and_then(config::parse_size);
+    let default = filesystem_size(dir).map(|size| (size / 10).min(DEFAULT_CEILING));
+    let mut diag = UsageDiagnostics {
+        max_use_bytes: configured.or(default),
+        max_use_configured: configured.is_some(),
+        archived_files: archived.len(),
+        corrupt_files,
+        rotation: [
+            "SystemMaxFileSize",
+            "MaxFileSec",
+            "MaxRetentionSec",
+            "SystemKeepFree",
+        ]
+        .iter()
+        .filter_map(|k| config.get(k).map(|v| (k.to_string(), v.to_string())))
+        .collect(),
+        ..UsageDiagnostics::default()
+    };
+    let usage = match Journal::open(0).and_then(|mut j| j.usage()) {
+        Ok(usage) => usage,
+        Err(e) => {
+            diag.error = Some(format!("Could not open the journal: {}", e));
+            return diag;
+        }
+    };
+    diag.usage_bytes = Some(usage);
+    diag.retention_days = retention_days().ok().flatten();
+
+    let persistent = storage.mode == "persistent" && Path::new(dir).is_dir();
+    let full = diag.max_use_bytes.is_some_and(|cap| usage >= cap / 10 * 9);
+    let short = full
+        && diag
+            .retention_days
+            .is_some_and(|days| days < SHORT_RETENTION_DAYS);
+    let roomy = default.is_some_and(|d| d >= RECOMMENDED_MAX_USE);
+
+    if persistent
+        && configured.is_none()
+        && default.is_some_and(|d| d > RECOMMENDED_MAX_USE)
+        && usage >= RECOMMENDED_MAX_USE
+    {
+        diag.retune = true;
+        diag.warnings.push(format!(
+            "The journal uses {} and may grow to {} (10% of the filesystem)",
+            human_bytes(usage),
+            human_bytes(default.unwrap_or_default())
+        ));
+        diag.recommendations.push(format!(
+            "Cap it at {} and shrink it now: journal-ambulance repair all",
+            human_bytes(RECOMMENDED_MAX_USE)
+        ));
+    }
+    if short {
+        diag.warnings.push(format!(
+            "Only {:.1} days of history fit before the cap of {} rotates them away",
+            diag.retention_days.unwrap_or_default(),
+            human_bytes(diag.max_use_bytes.unwrap_or_default())
+        ));
+        if persistent && configured.is_some_and(|c| c < RECOMMENDED_MAX_USE) && roomy {
+            diag.retune = true;
+            diag.recommendations.push(format!(
+                "Raise {}= to {}: journal-ambulance repair retention",
+                key,
+                human_bytes(RECOMMENDED_MAX_USE)
+            ));
+        }
+        diag.recommendations
+            .push("See the flooding section for what fills the journal".to_string());
+    }
+    if corrupt_files > 0 {
+        diag.warnings.push(format!(
+            "{} journal file(s) were damaged, usually by a crash or power loss",
+            corrupt_files
+        ));
+        diag.recommendations.push(
+            "`journalctl --verify` checks the rest; damaged files go with the oldest on a vacuum"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl UsageDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Usage and Retention ===");
+        let Some(usage) = self.usage_bytes else {
+            println!(
+                "- Usage: unknown ({})",
+                self.error.as_deref().unwrap_or("not read")
+            );
+            println!();
+            return;
+        };
+        let cap = self.max_use_bytes.map_or("unknown".to_string(), |cap| {
+            format!(
+                "{}{}",
+                human_bytes(cap),
+                if self.max_use_configured {
+                    ""
+                } else {
+                    ", default"
+                }
+            )
+        });
+        println!(
+            "{} Usage: {} of {}",
+            mark(self.warnings.is_empty()),
+            human_bytes(usage),
+            cap
+        );
+        if let Some(days) = self.retention_days {
+            println!("- History: {:.1} days", days);
+        }
+        if verbose {
+            println!(
+                "- Archived files: {} ({} damaged)",
+                self.archived_files, self.corrupt_files
+            );
+            for (key, value) in &self.rotation {
+                println!("- {}={}", key, value);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Journal repairs, one module per target
+//!
+//! `all` tunes retention before vacuuming, so the vacuum works to the
+//! new cap.
+
+pub mod retention;
+pub mod vacuum;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["vacuum", "retention", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change, with what it does.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    let retention_repair = if selected("retention") {
+        retention::repair(confirm)
+    } else {
+        RepairOutcome::default()
+    };
+    let vacuum_repair = if selected("vacuum") {
+        vacuum::repair(confirm)
+    } else {
+        RepairOutcome::default()
+    };
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        vacuum_repair,
+        retention_repair,
+    })
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Tune the journal's size cap
+//!
+//! Sets `SystemMaxUse=` in a drop-in of our own, capping a journal that
+//! may take gigabytes or giving room to one that only holds a few days,
+//! then restarts journald to apply it. Deleting the drop-in undoes it.
+
+use crate::config::{self, DROPIN};
+use crate::diagnostics::{storage, usage};
+use crate::report::RepairOutcome;
+use systemd_shim::bus::Bus;
+
+/// `usage::RECOMMENDED_MAX_USE` as journald.conf spells it
+const MAX_USE: &str = "1G";
+
+const JOURNALD: &str = "systemd-journald.service";
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let config = config::load();
+    let diag = usage::diagnose(&config, &storage::diagnose(&config));
+    if !diag.retune {
+        return RepairOutcome::not_needed("The journal's cap suits it, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    if let Some(file) = config.overriding("SystemMaxUse") {
+        result.errors.push(format!(
+            "{} sorts after {} and would override it; change SystemMaxUse= there instead",
+            file, DROPIN
+        ));
+        return result;
+    }
+    let question = format!(
+        "Set SystemMaxUse={} in {} and restart journald?",
+        MAX_USE, DROPIN
+    );
+    if !confirm(&question) {
+        result.errors.push(
+            "Retention change not confirmed, cap left as is (rerun with --yes to confirm)"
+                .to_string(),
+        );
+        return result;
+    }
+
+    let content = format!(
+        "# Written by journal-ambulance; delete this file to undo\n[Journal]\nSystemMaxUse={}\n",
+        MAX_USE
+    );
+    if let Err(e) =
+        std::fs::create_dir_all(config::DROPIN_DIR).and_then(|_| std::fs::write(DROPIN, content))
+    {
+        result.errors.push(format!("{}: {}", DROPIN, e));
+        return result;
+    }
+    result
+        .actions
+        .push(format!("Set SystemMaxUse={} in {}", MAX_USE, DROPIN));
+
+    match Bus::system().and_then(|bus| bus.restart_unit(JOURNALD, "replace")) {
+        Ok(_) => result.actions.push(format!(
+            "Restarted {}; it trims archived files over the cap as it rotates",
+            JOURNALD
+        )),
+        Err(e) => result.errors.push(format!("{}: {}", JOURNALD, e)),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Vacuum the journal down to its cap
+//!
+//! The cap is `SystemMaxUse=` when configured, otherwise the one `repair
+//! retention` would set. Archived files go oldest first through the shim,
+//! as `journalctl --vacuum-size` would; active files are journald's.
+
+use crate::config::{self, human_bytes};
+use crate::diagnostics::{storage, usage};
+use crate::report::RepairOutcome;
+use systemd_shim::journal::{self, ArchivedFile};
+
+/// Archived files vacuuming to `target` would remove, oldest first
+fn plan(usage: u64, target: u64) -> Vec<ArchivedFile> {
+    let mut remaining = usage;
+    journal::archived_files()
+        .into_iter()
+        .take_while(|file| {
+            let take = remaining > target;
+            remaining = remaining.saturating_sub(file.bytes);
+            take
+        })
+        .collect()
+}
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let config = config::load();
+    let diag = usage::diagnose(&config, &storage::diagnose(&config));
+    let Some(usage) = diag.usage_bytes else {
+        return RepairOutcome {
+            errors: vec![diag.error.unwrap_or_default()],
+            ..RepairOutcome::default()
+        };
+    };
+    let target = match diag.max_use_bytes {
+        Some(cap) if diag.max_use_configured => cap,
+        cap => cap.map_or(usage::RECOMMENDED_MAX_USE, |cap| {
+            cap.min(usage::RECOMMENDED_MAX_USE)
+        }),
+    };
+    let plan = plan(usage, target);
+    if plan.is_empty() {
+        return RepairOutcome::not_needed(&format!(
+            "The journal uses {} of {}, no repair needed",
+            human_bytes(usage),
+            human_bytes(target)
+        ));
+    }
+
+    let mut result = RepairOutcome::default();
+    let freed: u64 = plan.iter().map(|f| f.bytes).sum();
+    let question = format!(
+        "Delete the {} oldest archived journal files, freeing {}?",
+        plan.len(),
+        human_bytes(freed)
+    );
+    if !confirm(&question) {
+        result.errors.push(format!(
+            "Journal vacuum not confirmed, {} left in place (rerun with --yes to confirm)",
+            human_bytes(freed)
+        ));
+        return result;
+    }
+    match journal::vacuum(target) {
+        Ok(vacuum) => {
+            result.actions.push(format!(
+                "Removed {} archived file(s), freeing {}",
+                vacuum.removed.len(),
+                human_bytes(vacuum.removed.iter().map(|f| f.bytes).sum())
+            ));
+            for (file, e) in &vacuum.failed {
+                result
+                    .errors
+                    .push(format!("Could not remove {}: {}", file.path.display(), e));
+            }
+            if let Ok(usage) = journal::Journal::open(0).and_then(|mut j| j.usage()) {
+                result
+                    .actions
+                    .push(format!("The journal now uses {}", human_bytes(usage)));
+            }
+        }
+        Err(e) => result.errors.push(format!("Vacuum failed: {}", e)),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
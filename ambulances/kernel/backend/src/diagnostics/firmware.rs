@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Firmware the kernel failed to load
+//!
+//! Drivers ask for their blobs by file name; a missing one leaves the
+//! device dead or degraded. Debian splits the blobs into several
+//! non-free packages, everyone else ships most of them in linux-firmware,
+//! so the file name is mapped to the package that carries it here.
+
+use crate::messages::KernelLog;
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use std::path::Path;
+
+const FIRMWARE_DIRS: &[&str] = &["/lib/firmware", "/usr/lib/firmware"];
+
+/// Compressed forms the kernel loads as well
+const EXTENSIONS: &[&str] = &["", ".xz", ".zst"];
+
+/// File name prefix, Debian package, Fedora package
+const PACKAGES: &[(&str, &str, &str)] = &[
+    ("iwlwifi-", "firmware-iwlwifi", "iwlwifi-mvm-firmware"),
+    ("amdgpu/", "firmware-amd-graphics", "amd-gpu-firmware"),
+    ("radeon/", "firmware-amd-graphics", "linux-firmware"),
+    ("i915/", "firmware-misc-nonfree", "intel-gpu-firmware"),
+    ("xe/", "firmware-misc-nonfree", "intel-gpu-firmware"),
+    ("nvidia/", "firmware-misc-nonfree", "nvidia-gpu-firmware"),
+    ("rtl_nic/", "firmware-realtek", "realtek-firmware"),
+    ("rtlwifi/", "firmware-realtek", "realtek-firmware"),
+    ("rtw88/", "firmware-realtek", "realtek-firmware"),
+    ("rtw89/", "firmware-realtek", "realtek-firmware"),
+    ("rtl_bt/", "firmware-realtek", "realtek-firmware"),
+    ("brcm/", "firmware-brcm80211", "brcmfmac-firmware"),
+    ("ath10k/", "firmware-atheros", "atheros-firmware"),
+    ("ath11k/", "firmware-atheros", "atheros-firmware"),
+    ("qca/", "firmware-atheros", "atheros-firmware"),
+    ("mediatek/", "firmware-misc-nonfree", "mt7xxx-firmware"),
+    ("intel/sof", "firmware-sof-signed", "alsa-sof-firmware"),
+    ("regulatory.db", "wireless-regdb", "wireless-regdb"),
+    ("amd-ucode/", "amd64-microcode", "amd-ucode-firmware"),
+    ("intel-ucode/", "intel-microcode", "microcode_ctl"),
+];
+
+/// Debian package names Ubuntu uses too; the rest is in linux-firmware
+const UBUNTU_TOO: &[&str] = &[
+    "firmware-sof-signed",
+    "wireless-regdb",
+    "amd64-microcode",
+    "intel-microcode",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FirmwareFailure {
+    /// Path below /lib/firmware, as the driver asked for it
+    pub file: String,
+    pub driver: Option<String>,
+    pub count: usize,
+    /// The file exists now, e.g. installed since the failure
+    pub present: bool,
+    /// Package carrying it for this system's package manager
+    pub package: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirmwareDiagnostics {
+    pub failures: Vec<FirmwareFailure>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// File and driver from `<driver> <device>: Direct firmware load for
+/// <file> failed with error -2` or `firmware: failed to load <file> (-2)`
+fn failure(message: &str) -> Option<(String, Option<String>)> {
+    if let Some((head, rest)) = message.split_once("Direct firmware load for ") {
+        let file = rest.split_once(" failed")?.0;
+        let driver = head.split_whitespace().next().map(str::to_string);
+        return Some((file.to_string(), driver));
+    }
+    let file = message
+        .split_once("failed to load ")?
+        .1
+        .split(" (")
+        .next()?;
+    (!file.contains(' ') && (file.contains('.') || file.contains('/')))
+        .then(|| (file.to_string(), None))
+}
+
+pub fn present(file: &str) -> bool {
+    FIRMWARE_DIRS.iter().any(|dir| {
+        EXTENSIONS
+            .iter()
+            .any(|ext| Path::new(&format!("{}/{}{}", dir, file, ext)).exists())
+    })
+}
+
+fn is_ubuntu() -> bool {
+    system::read("/etc/os-release").is_some_and(|release| {
+        release
+            .lines()
+            .any(|l| (l.starts_with("ID=") || l.starts_with("ID_LIKE=")) && l.contains("ubuntu"))
+    })
+}
+
+/// The package carrying `file` for this system's package manager
+fn package(file: &str) -> Option<String> {
+    let entry = PACKAGES
+        .iter()
+        .find(|(prefix, ..)| file.starts_with(prefix));
+    let package = if system::has("apt-get") {
+        let ubuntu = is_ubuntu();
+        match entry {
+            Some((_, debian, _)) if !ubuntu || UBUNTU_TOO.contains(debian) => *debian,
+            _ if ubuntu => "linux-firmware",
+            _ => "firmware-linux-nonfree",
+        }
+    } else if system::has("dnf") {
+        entry.map_or("linux-firmware", |(_, _, fedora)| *fedora)
+    } else if system::has("pacman") {
+        "linux-firmware"
+    } else if system::has("zypper") {
+        "kernel-firmware-all"
+    } else {
+        return None;
+    };
+    Some(package.to_string())
+}
+
+pub fn diagnose(log: &KernelLog) -> FirmwareDiagnostics {
+    let mut diag = FirmwareDiagnostics::default();
+    for message in &log.messages {
+        let Some((file, driver)) = failure(&message.text) else {
+            continue;
+        };
+        match diag.failures.iter_mut().find(|f| f.file == file) {
+            Some(existing) => existing.count += 1,
+            None => diag.failures.push(FirmwareFailure {
+                present: present(&file),
+                package: package(&file),
+                file,
+                driver,
+                count: 1,
+            }),
+        }
+    }
+    // iwlwifi asks for the newest API first and settles for an older file
+    let iwlwifi_loaded = log
+        .messages
+        .iter()
+        .any(|m| m.text.starts_with("iwlwifi") && m.text.contains("loaded firmware version"));
+    if iwlwifi_loaded {
+        diag.failures.retain(|f| !f.file.starts_with("iwlwifi-"));
+    }
+
+    for failure in &diag.failures {
+        let driver = failure
+            .driver
+            .as_deref()
+            .map(|d| format!(" ({})", d))
+            .unwrap_or_default();
+        if failure.present {
+            diag.warnings.push(format!(
+                "{}{} failed to load but is installed now",
+                failure.file, driver
+            ));
+        } else {
+            diag.warnings
+                .push(format!("Missing firmware {}{}", failure.file, driver));
+        }
+    }
+    let mut packages: Vec<&str> = diag
+        .failures
+        .iter()
+        .filter(|f| !f.present)
+        .filter_map(|f| f.package.as_deref())
+        .collect();
+    packages.sort_unstable();
+    packages.dedup();
+    if !packages.is_empty() {
+        diag.recommendations.push(format!(
+            "Install {}, then reboot: kernel-ambulance repair firmware",
+            packages.join(", ")
+        ));
+    }
+    if diag.failures.iter().any(|f| f.present) {
+        diag.recommendations
+            .push("Reboot (or reload the driver) to load firmware installed since".to_string());
+    }
+
+    diag
+}
+
+impl FirmwareDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Firmware ===");
+        let missing = self.failures.iter().filter(|f| !f.present).count();
+        println!("{} Missing firmware files: {}", mark(missing == 0), missing);
+        if verbose {
+            for failure in &self.failures {
+                println!(
+                    "    {} {} -> {}",
+                    mark(failure.present),
+                    failure.file,
+                    failure.package.as_deref().unwrap_or("?")
+                );
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Hung tasks, lockups and RCU stalls
+//!
+//! The hung-task detector names a task stuck in uninterruptible sleep for
+//! two minutes, almost always waiting on storage or a network filesystem.
+//! Lockups and RCU stalls mean a CPU stopped scheduling altogether, which
+//! points at a driver, firmware or the hardware itself.
+
+use crate::messages::KernelLog;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hang {
+    /// `hung task`, `soft lockup`, `hard lockup` or `rcu stall`
+    pub kind: &'static str,
+    /// Task named by the report, without its pid
+    pub task: Option<String>,
+    /// Microseconds since the epoch
+    pub timestamp: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HangDiagnostics {
+    /// Oldest first
+    pub hangs: Vec<Hang>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// `INFO: task jbd2/sda1-8:312 blocked for more than 120 seconds.`
+fn hung_task(message: &str) -> Option<String> {
+    let rest = message.split_once("INFO: task ")?.1;
+    let (task, _) = rest.split_once(" blocked for more than")?;
+    Some(
+        task.rsplit_once(':')
+            .map_or(task, |(name, _)| name)
+            .to_string(),
+    )
+}
+
+/// `watchdog: BUG: soft lockup - CPU#2 stuck for 23s! [Xorg:1234]`
+fn bracketed_task(message: &str) -> Option<String> {
+    let inner = message.trim_end().strip_suffix(']')?.rsplit_once('[')?.1;
+    Some(
+        inner
+            .rsplit_once(':')
+            .map_or(inner, |(name, _)| name)
+            .to_string(),
+    )
+}
+
+fn classify(message: &str) -> Option<(&'static str, Option<String>)> {
+    if let Some(task) = hung_task(message) {
+        Some(("hung task", Some(task)))
+    } else if message.contains("soft lockup") {
+        Some(("soft lockup", bracketed_task(message)))
+    } else if message.contains("hard LOCKUP") {
+        Some(("hard lockup", None))
+    } else if message.contains("rcu") && message.contains("detected stall") {
+        Some(("rcu stall", None))
+    } else {
+        None
+    }
+}
+
+pub fn diagnose(log: &KernelLog) -> HangDiagnostics {
+    let mut diag = HangDiagnostics::default();
+    for message in &log.messages {
+        if let Some((kind, task)) = classify(&message.text) {
+            diag.hangs.push(Hang {
+                kind,
+                task,
+                timestamp: message.timestamp,
+                message: message.text.clone(),
+            });
+        }
+    }
+
+    let count = |kind: &str| diag.hangs.iter().filter(|h| h.kind == kind).count();
+    let hung = count("hung task");
+    if hung > 0 {
+        let mut tasks: Vec<&str> = diag
+            .hangs
+            .iter()
+            .filter(|h| h.kind == "hung task")
+            .filter_map(|h| h.task.as_deref())
+            .collect();
+        tasks.sort_unstable();
+        tasks.dedup();
+        diag.warnings.push(format!(
+            "{} hung task report(s): {}",
+            hung,
+            tasks.join(", ")
+        ));
+        diag.recommendations.push(
+            "Tasks blocked on I/O: check the io section and disk health (disk-ambulance), and any NFS/CIFS mounts"
+                .to_string(),
+        );
+    }
+    let lockups = count("soft lockup") + count("hard lockup") + count("rcu stall");
+    if lockups > 0 {
+        diag.warnings
+            .push(format!("{} CPU lockup or RCU stall report(s)", lockups));
+        diag.recommendations.push(
+            "A CPU stopped scheduling: update the kernel and firmware, and check the oops section for the driver involved"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl HangDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Hangs and Lockups ===");
+        println!(
+            "{} Reports: {}",
+            mark(self.hangs.is_empty()),
+            self.hangs.len()
+        );
+        if verbose {
+            for hang in &self.hangs {
+                println!("  {}", hang.message);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
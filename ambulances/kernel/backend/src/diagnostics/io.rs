@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Storage I/O errors, by device
+//!
+//! The block layer, libata, the NVMe driver and the filesystems each
+//! report failures their own way; all are reduced to a device and a
+//! count, so one dying disk reads as one line rather than a thousand.
+
+use crate::messages::KernelLog;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use std::cmp::Reverse;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceErrors {
+    /// `sda`, `sda1`, `ata3`, `nvme0`, `dm-0`...
+    pub device: String,
+    /// `block`, `ata`, `nvme` or `filesystem`
+    pub kind: &'static str,
+    pub count: usize,
+    pub last_message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IoDiagnostics {
+    /// Most errors first
+    pub devices: Vec<DeviceErrors>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Text after `marker` up to the first of `ends`
+fn between<'a>(message: &'a str, marker: &str, ends: &[char]) -> Option<&'a str> {
+    let rest = message.split_once(marker)?.1;
+    let device = rest.split(ends).next()?.trim();
+    (!device.is_empty()).then_some(device)
+}
+
+fn classify(message: &str) -> Option<(&'static str, &str)> {
+    if message.contains("I/O error") {
+        if let Some(device) = between(message, ", dev ", &[',', ' '])
+            .or_else(|| between(message, " on dev ", &[',', ' ']))
+        {
+            return Some(("block", device));
+        }
+    }
+    if message.contains("critical medium error") || message.contains("critical target error") {
+        return Some(("block", between(message, ", dev ", &[',', ' '])?));
+    }
+    if ["EXT4-fs error", "BTRFS error", "BTRFS critical"]
+        .iter()
+        .any(|prefix| message.starts_with(prefix))
+    {
+        return Some(("filesystem", between(message, "(device ", &[')', ':'])?));
+    }
+    if message.starts_with("XFS (")
+        && (message.contains("I/O error") || message.contains("Corruption"))
+    {
+        return Some(("filesystem", between(message, "XFS (", &[')'])?));
+    }
+    if message.starts_with("ata")
+        && (message.contains("failed command")
+            || message.contains("exception Emask")
+            || message.contains("SError"))
+    {
+        return Some(("ata", message.split(['.', ':']).next()?));
+    }
+    if message.starts_with("nvme ")
+        && (message.contains("timeout") || message.contains("controller is down"))
+    {
+        return Some(("nvme", between(message, "nvme ", &[':'])?));
+    }
+    None
+}
+
+pub fn diagnose(log: &KernelLog) -> IoDiagnostics {
+    let mut diag = IoDiagnostics::default();
+    for message in &log.messages {
+        let Some((kind, device)) = classify(&message.text) else {
+            continue;
+        };
+        match diag
+            .devices
+            .iter_mut()
+            .find(|d| d.device == device && d.kind == kind)
+        {
+            Some(entry) => {
+                entry.count += 1;
+                entry.last_message = message.text.clone();
+            }
+            None => diag.devices.push(DeviceErrors {
+                device: device.to_string(),
+                kind,
+                count: 1,
+                last_message: message.text.clone(),
+            }),
+        }
+    }
+    diag.devices.sort_by_key(|d| Reverse(d.count));
+
+    for device in &diag.devices {
+        diag.warnings.push(format!(
+            "{} {} error(s) on {}",
+            device.count, device.kind, device.device
+        ));
+    }
+    let has = |kind: &str| diag.devices.iter().any(|d| d.kind == kind);
+    if has("block") || has("nvme") {
+        diag.recommendations.push(
+            "Back up now, then check the disk's SMART health: disk-ambulance diagnose".to_string(),
+        );
+    }
+    if has("ata") {
+        diag.recommendations.push(
+            "SATA link errors are often the cable: reseat or replace it before blaming the drive"
+                .to_string(),
+        );
+    }
+    if has("filesystem") {
+        diag.recommendations.push(
+            "Check the filesystem from a rescue boot (fsck, btrfs check or xfs_repair)".to_string(),
+        );
+    }
+
+    diag
+}
+
+impl IoDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== I/O Errors ===");
+        let total: usize = self.devices.iter().map(|d| d.count).sum();
+        println!("{} Errors: {}", mark(total == 0), total);
+        for device in &self.devices {
+            println!("    {} ({}) x{}", device.device, device.kind, device.count);
+            if verbose {
+                println!("      {}", device.last_message);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
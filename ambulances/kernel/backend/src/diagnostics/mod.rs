@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Kernel diagnostics, one module per report section
+//!
+//! The kernel log is read once, as an ambulance-core job, and handed to
+//! every section that scans it; those run after it at the same time, and
+//! taint alongside, all within the scan's budget. When the log is not
+//! read in time its error says so and the sections that need it are
+//! skipped, saying so in their warnings; a section the budget cut short
+//! is left empty and its timing says why.
+
+pub mod firmware;
+pub mod hangs;
+pub mod io;
+pub mod oops;
+pub mod signatures;
+pub mod taint;
+
+use crate::messages::{self, KernelLog};
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Inputs, Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; a large journal takes a while to read
+/// back to the start of the window
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+enum Section {
+    Log(KernelLog),
+    Taint(taint::TaintDiagnostics),
+    Oops(oops::OopsDiagnostics),
+    Hangs(hangs::HangDiagnostics),
+    Io(io::IoDiagnostics),
+    Firmware(firmware::FirmwareDiagnostics),
+    Signatures(signatures::SignatureDiagnostics),
+}
+
+/// A job scanning the kernel log
+fn scan(id: &'static str, diagnose: fn(&KernelLog) -> Section) -> Job<Section> {
+    Job::blocking(id, move |inputs: Inputs<Section>| match inputs.get("log") {
+        Some(Section::Log(log)) => Ok(diagnose(log)),
+        _ => Err("no kernel log".to_string()),
+    })
+    .after(&["log"])
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("log", |_| Ok(Section::Log(messages::load()))))
+        .job(Job::blocking("taint", |_| {
+            Ok(Section::Taint(taint::diagnose()))
+        }))
+        .job(scan("oops", |log| Section::Oops(oops::diagnose(log))))
+        .job(scan("hangs", |log| Section::Hangs(hangs::diagnose(log))))
+        .job(scan("io", |log| Section::Io(io::diagnose(log))))
+        .job(scan("firmware", |log| {
+            Section::Firmware(firmware::diagnose(log))
+        }))
+        .job(scan("signatures", |log| {
+            Section::Signatures(signatures::diagnose(log))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        log: Default::default(),
+        taint: Default::default(),
+        oops: Default::default(),
+        hangs: Default::default(),
+        io: Default::default(),
+        firmware: Default::default(),
+        signatures: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Log(d)) => result.log = d,
+            Some(Section::Taint(d)) => result.taint = d,
+            Some(Section::Oops(d)) => result.oops = d,
+            Some(Section::Hangs(d)) => result.hangs = d,
+            Some(Section::Io(d)) => result.io = d,
+            Some(Section::Firmware(d)) => result.firmware = d,
+            Some(Section::Signatures(d)) => result.signatures = d,
+            None if outcome.status == Status::OverBudget => {}
+            None if outcome.id == "log" => {
+                result.log.error = Some(format!("reading it {}", outcome.status));
+            }
+            None => {
+                let warnings = match outcome.id {
+                    "taint" => &mut result.taint.warnings,
+                    "oops" => &mut result.oops.warnings,
+                    "hangs" => &mut result.hangs.warnings,
+                    "io" => &mut result.io.warnings,
+                    "firmware" => &mut result.firmware.warnings,
+                    _ => &mut result.signatures.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Oopses, BUGs and warnings
+//!
+//! Each report starts with a headline (`Oops:`, `BUG: ...`, `WARNING:
+//! CPU: ...`) and names the faulting function and module on the `RIP:`
+//! line a few messages later; the module is what a report, an update or a
+//! blacklist would be about. Panics never reach the journal, but
+//! systemd-pstore keeps the ones the firmware stored.
+
+use crate::diagnostics::taint;
+use crate::messages::KernelLog;
+use crate::report::{mark, print_notes};
+use ambulance_core::host;
+use serde::Serialize;
+use std::cmp::Reverse;
+
+/// Headline prefixes (searched anywhere in the message) and the kind
+const HEADLINES: &[(&str, &str)] = &[
+    ("Oops:", "oops"),
+    ("general protection fault", "oops"),
+    ("kernel BUG at", "bug"),
+    ("BUG: unable to handle", "bug"),
+    ("BUG: kernel NULL pointer dereference", "bug"),
+    ("BUG: Bad page", "bug"),
+    ("WARNING: CPU:", "warning"),
+    ("Kernel panic - not syncing", "panic"),
+];
+
+/// Messages after a headline searched for its `RIP:` line
+const LOOKAHEAD: usize = 40;
+
+/// Where systemd-pstore archives panic logs, and the live pstore
+const PSTORE: &[&str] = &["/var/lib/systemd/pstore", "/sys/fs/pstore"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OopsReport {
+    /// `oops`, `bug`, `warning` or `panic`
+    pub kind: &'static str,
+    /// Function the report points at, e.g. `intel_atomic_commit_tail`
+    pub function: Option<String>,
+    pub module: Option<String>,
+    pub count: usize,
+    /// Microseconds since the epoch, of the latest
+    pub last_seen: u64,
+    pub headline: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OopsDiagnostics {
+    /// Grouped by kind and function, most frequent first
+    pub reports: Vec<OopsReport>,
+    /// Panic logs kept by pstore
+    pub pstore: Vec<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn kind(message: &str) -> Option<&'static str> {
+    // Lockups carry `BUG:` too but belong to the hangs section
+    if message.contains("soft lockup") || message.contains("workqueue lockup") {
+        return None;
+    }
+    HEADLINES
+        .iter()
+        .find(|(headline, _)| message.contains(headline))
+        .map(|(_, kind)| *kind)
+}
+
+/// Function and module from `...func+0x1a/0x2b0 [module]`
+fn location(line: &str) -> (Option<String>, Option<String>) {
+    let module = line
+        .trim_end()
+        .strip_suffix(']')
+        .and_then(|l| l.rsplit_once('['))
+        .map(|(_, m)| m.trim().to_string())
+        .filter(|m| !m.is_empty() && !m.contains(' ') && !m.starts_with('#'));
+    let function = line
+        .split_whitespace()
+        .find(|w| w.contains("+0x"))
+        .and_then(|w| w.split('+').next())
+        .map(|f| f.rsplit(':').next().unwrap_or(f).to_string());
+    (function, module)
+}
+
+fn pstore_files() -> Vec<String> {
+    let mut files = Vec::new();
+    for dir in PSTORE {
+        let Ok(entries) = std::fs::read_dir(host::path(dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // systemd-pstore archives into one directory per crash
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if path.is_dir() || name.starts_with("dmesg") {
+                files.push(path.display().to_string());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+pub fn diagnose(log: &KernelLog) -> OopsDiagnostics {
+    let mut diag = OopsDiagnostics {
+        pstore: pstore_files(),
+        ..OopsDiagnostics::default()
+    };
+    let messages = &log.messages;
+    for (i, message) in messages.iter().enumerate() {
+        let Some(kind) = kind(&message.text) else {
+            continue;
+        };
+        let (mut function, mut module) = location(&message.text);
+        if function.is_none() {
+            if let Some(rip) = messages
+                .iter()
+                .skip(i + 1)
+                .take(LOOKAHEAD)
+                .find(|m| m.text.contains("RIP:") || m.text.starts_with("pc :"))
+            {
+                (function, module) = location(&rip.text);
+            }
+        }
+        match diag
+            .reports
+            .iter_mut()
+            .find(|r| r.kind == kind && r.function == function)
+        {
+            Some(report) => {
+                report.count += 1;
+                report.last_seen = message.timestamp;
+            }
+            None => diag.reports.push(OopsReport {
+                kind,
+                function,
+                module,
+                count: 1,
+                last_seen: message.timestamp,
+                headline: message.text.clone(),
+            }),
+        }
+    }
+    diag.reports.sort_by_key(|r| Reverse(r.count));
+
+    let tainting = taint::tainting_modules();
+    for report in &diag.reports {
+        let place = match (&report.function, &report.module) {
+            (Some(function), Some(module)) => format!(" in {} [{}]", function, module),
+            (Some(function), None) => format!(" in {}", function),
+            _ => String::new(),
+        };
+        diag.warnings
+            .push(format!("Kernel {}{} x{}", report.kind, place, report.count));
+        let Some(module) = &report.module else {
+            continue;
+        };
+        if tainting.iter().any(|m| &m.module == module) {
+            diag.recommendations.push(format!(
+                "{} is an out-of-tree or proprietary module: update it (or the kernel it was built for), or uninstall it",
+                module
+            ));
+        } else {
+            diag.recommendations.push(format!(
+                "Update the kernel; if {} keeps failing, report it upstream with the full trace from `journalctl -k`",
+                module
+            ));
+        }
+    }
+    if !diag.reports.is_empty() && diag.reports.iter().all(|r| r.module.is_none()) {
+        diag.recommendations.push(
+            "Update the kernel; if it recurs, report it with the full trace from `journalctl -k`"
+                .to_string(),
+        );
+    }
+    if !diag.pstore.is_empty() {
+        diag.warnings.push(format!(
+            "{} kernel panic log(s) kept by pstore",
+            diag.pstore.len()
+        ));
+        diag.recommendations.push(format!(
+            "Read the panic logs in {} for what brought the machine down",
+            PSTORE[0]
+        ));
+    }
+    diag.recommendations.dedup();
+
+    diag
+}
+
+impl OopsDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Oopses and Warnings ===");
+        let total: usize = self.reports.iter().map(|r| r.count).sum();
+        println!("{} Reports: {}", mark(total == 0), total);
+        println!(
+            "{} Panic logs: {}",
+            mark(self.pstore.is_empty()),
+            self.pstore.len()
+        );
+        if verbose {
+            for report in &self.reports {
+                println!("  x{}  {}", report.count, report.headline);
+            }
+            for file in &self.pstore {
+                println!("  {}", file);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
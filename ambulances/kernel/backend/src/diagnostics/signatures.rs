@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Known kernel message signatures
+//!
+//! Messages that look alarming but have a well-known cause and fix. A
+//! signature matches when a message contains all of its fragments; each
+//! carries an explanation and the usual remedy: a module blacklist, a
+//! kernel parameter or advice. Only blacklists are applied by a repair,
+//! and only for modules listed here.
+
+use crate::messages::KernelLog;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum Action {
+    /// Blacklist this module
+    Blacklist(&'static str),
+    /// Add this kernel command line parameter
+    Parameter(&'static str),
+    Advice(&'static str),
+}
+
+struct Signature {
+    id: &'static str,
+    fragments: &'static [&'static str],
+    explanation: &'static str,
+    action: Action,
+}
+
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        id: "pcie-aer-corrected",
+        fragments: &["PCIe Bus Error", "severity=Corrected"],
+        explanation: "A PCIe device reports corrected link errors; harmless, but they flood the log and can keep the CPU awake",
+        action: Action::Parameter("pcie_aspm=off"),
+    },
+    Signature {
+        id: "acpi-bios-bug",
+        fragments: &["ACPI BIOS Error (bug)"],
+        explanation: "The firmware's ACPI tables have bugs the kernel works around; usually harmless",
+        action: Action::Advice("Update the BIOS/UEFI firmware if a device actually misbehaves"),
+    },
+    Signature {
+        id: "usb-enumeration",
+        fragments: &["device descriptor read", "error -71"],
+        explanation: "A USB device fails to enumerate, usually a bad cable, a worn port or too little power",
+        action: Action::Advice("Try another cable or port, or a powered hub"),
+    },
+    Signature {
+        id: "nvme-apst",
+        fragments: &["nvme", "controller is down; will reset"],
+        explanation: "The NVMe drive stopped responding, typically on leaving a deep power state (APST)",
+        action: Action::Parameter("nvme_core.default_ps_max_latency_us=0"),
+    },
+    Signature {
+        id: "sata-link-reset",
+        fragments: &["ata", "hard resetting link"],
+        explanation: "A SATA link keeps resetting, usually the cable or a failing drive",
+        action: Action::Advice("Reseat or replace the SATA cable, then check SMART with disk-ambulance"),
+    },
+    Signature {
+        id: "machine-check",
+        fragments: &["mce:", "Hardware Error"],
+        explanation: "The CPU reported a machine check: a fault in the CPU, memory or a bus",
+        action: Action::Advice("Run a memory test and check cooling; recurring errors mean failing hardware"),
+    },
+    Signature {
+        id: "edac-corrected",
+        fragments: &["EDAC", "CE memory"],
+        explanation: "ECC corrected a memory error; recurring ones point at one failing DIMM",
+        action: Action::Advice("Replace the DIMM named in the message if the count keeps growing"),
+    },
+    Signature {
+        id: "i915-gpu-hang",
+        fragments: &["i915", "GPU HANG"],
+        explanation: "The Intel GPU hung and was reset",
+        action: Action::Advice("Update the kernel and Mesa; see gpu-ambulance"),
+    },
+    Signature {
+        id: "amdgpu-ring-timeout",
+        fragments: &["amdgpu", "ring", "timeout"],
+        explanation: "The AMD GPU stopped responding and was reset",
+        action: Action::Advice("Update the kernel, Mesa and linux-firmware; see gpu-ambulance"),
+    },
+    Signature {
+        id: "nouveau-fault",
+        fragments: &["nouveau", "fault"],
+        explanation: "nouveau, the reverse-engineered NVIDIA driver, hit a GPU fault; it is unreliable on recent NVIDIA cards",
+        action: Action::Blacklist("nouveau"),
+    },
+    Signature {
+        id: "acer-wmi-unsupported",
+        fragments: &["acer_wmi", "Unable to detect available"],
+        explanation: "acer_wmi does not support this laptop and can leave Wi-Fi hard-blocked",
+        action: Action::Blacklist("acer_wmi"),
+    },
+    Signature {
+        id: "iwlwifi-firmware-crash",
+        fragments: &["iwlwifi", "Microcode SW error"],
+        explanation: "The Intel Wi-Fi firmware crashed and was restarted",
+        action: Action::Advice("Update linux-firmware; if it recurs, set `options iwlwifi power_save=0`"),
+    },
+    Signature {
+        id: "bluetooth-tx-timeout",
+        fragments: &["Bluetooth: hci", "tx timeout"],
+        explanation: "The Bluetooth controller stopped answering, often USB autosuspend",
+        action: Action::Parameter("btusb.enable_autosuspend=0"),
+    },
+    Signature {
+        id: "split-lock",
+        fragments: &["split lock detection", "took a split_lock trap"],
+        explanation: "A program uses split-locked memory accesses, which the kernel slows down on purpose; common with games under Proton",
+        action: Action::Parameter("split_lock_detect=off"),
+    },
+    Signature {
+        id: "critical-temperature",
+        fragments: &["critical temperature reached"],
+        explanation: "A sensor reached its critical temperature and the system shut down to protect itself",
+        action: Action::Advice("Clean the fans and heatsink and check the cooling"),
+    },
+];
+
+/// Modules that must not be blacklisted unless the driver replacing them
+/// is installed
+pub const REPLACEMENTS: &[(&str, &str)] = &[("nouveau", "nvidia")];
+
+/// Matches of one signature
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureMatch {
+    pub id: &'static str,
+    pub explanation: &'static str,
+    pub action: Action,
+    pub count: usize,
+    /// The latest matching message
+    pub example: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SignatureDiagnostics {
+    /// In table order
+    pub matches: Vec<SignatureMatch>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(log: &KernelLog) -> SignatureDiagnostics {
+    let mut diag = SignatureDiagnostics::default();
+    for signature in SIGNATURES {
+        let matching: Vec<&str> = log
+            .messages
+            .iter()
+            .map(|m| m.text.as_str())
+            .filter(|text| signature.fragments.iter().all(|f| text.contains(f)))
+            .collect();
+        let Some(example) = matching.last() else {
+            continue;
+        };
+        diag.matches.push(SignatureMatch {
+            id: signature.id,
+            explanation: signature.explanation,
+            action: signature.action,
+            count: matching.len(),
+            example: example.to_string(),
+        });
+    }
+
+    for m in &diag.matches {
+        diag.warnings
+            .push(format!("{} (x{}): {}", m.id, m.count, m.explanation));
+        diag.recommendations.push(match m.action {
+            Action::Blacklist(module) => match REPLACEMENTS.iter().find(|(m, _)| *m == module) {
+                Some((_, replacement)) => format!(
+                    "Install the {} driver, then blacklist {}: kernel-ambulance repair blacklist",
+                    replacement, module
+                ),
+                None => format!("Blacklist {}: kernel-ambulance repair blacklist", module),
+            },
+            Action::Parameter(parameter) => {
+                format!("Add `{}` to the kernel command line", parameter)
+            }
+            Action::Advice(advice) => advice.to_string(),
+        });
+    }
+
+    diag
+}
+
+impl SignatureDiagnostics {
+    /// Modules a matched signature says to blacklist
+    pub fn blacklist(&self) -> Vec<&'static str> {
+        self.matches
+            .iter()
+            .filter_map(|m| match m.action {
+                Action::Blacklist(module) => Some(module),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn print(&self, verbose: bool) {
+        println!("=== Known Signatures ===");
+        println!(
+            "{} Matched: {}",
+            mark(self.matches.is_empty()),
+            self.matches.len()
+        );
+        if verbose {
+            for m in &self.matches {
+                println!("  {} x{}: {}", m.id, m.count, m.example);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
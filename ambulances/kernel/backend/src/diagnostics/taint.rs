@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Kernel taint flags
+//!
+//! `/proc/sys/kernel/tainted` is a bit mask of things that happened since
+//! boot which make upstream ignore a bug report: a proprietary module, an
+//! oops, a machine check. Each module that tainted the kernel lists its
+//! letters in `/sys/module/<name>/taint`.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+
+/// Bit, letter, meaning and whether it points at a problem rather than
+/// a deliberate choice such as a proprietary driver
+const FLAGS: &[(u32, char, &str, bool)] = &[
+    (0, 'P', "proprietary module loaded", false),
+    (1, 'F', "module force-loaded", true),
+    (2, 'S', "running on out-of-spec hardware", true),
+    (3, 'R', "module force-unloaded", true),
+    (4, 'M', "machine check exception occurred", true),
+    (5, 'B', "bad page referenced", true),
+    (6, 'U', "tainted on request from user space", false),
+    (7, 'D', "kernel died recently (oops or BUG)", true),
+    (8, 'A', "ACPI table overridden", false),
+    (9, 'W', "kernel issued a warning", true),
+    (10, 'C', "staging driver loaded", false),
+    (11, 'I', "platform firmware bug worked around", false),
+    (12, 'O', "out-of-tree module loaded", false),
+    (13, 'E', "unsigned module loaded", false),
+    (14, 'L', "soft lockup occurred", true),
+    (15, 'K', "kernel live-patched", false),
+    (16, 'X', "distribution-defined taint", false),
+    (17, 'T', "built with struct randomization", false),
+    (18, 'N', "in-kernel test run", false),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaintFlag {
+    pub letter: char,
+    pub meaning: &'static str,
+    pub problem: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaintingModule {
+    pub module: String,
+    /// Letters from `/sys/module/<name>/taint`, e.g. `POE`
+    pub flags: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaintDiagnostics {
+    pub value: Option<u64>,
+    pub flags: Vec<TaintFlag>,
+    pub modules: Vec<TaintingModule>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Modules that tainted the kernel, by name
+pub fn tainting_modules() -> Vec<TaintingModule> {
+    let Ok(entries) = std::fs::read_dir(host::path("/sys/module")) else {
+        return Vec::new();
+    };
+    let mut modules: Vec<TaintingModule> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let flags = system::read(entry.path().join("taint"))?.trim().to_string();
+            (!flags.is_empty()).then(|| TaintingModule {
+                module: entry.file_name().to_string_lossy().into_owned(),
+                flags,
+            })
+        })
+        .collect();
+    modules.sort_by(|a, b| a.module.cmp(&b.module));
+    modules
+}
+
+pub fn diagnose() -> TaintDiagnostics {
+    let mut diag = TaintDiagnostics {
+        value: system::read(host::path("/proc/sys/kernel/tainted"))
+            .and_then(|v| v.trim().parse().ok()),
+        modules: tainting_modules(),
+        ..TaintDiagnostics::default()
+    };
+    let Some(value) = diag.value else {
+        diag.warnings
+            .push("Cannot read /proc/sys/kernel/tainted".to_string());
+        return diag;
+    };
+    diag.flags = FLAGS
+        .iter()
+        .filter(|(bit, ..)| value & (1 << bit) != 0)
+        .map(|&(_, letter, meaning, problem)| TaintFlag {
+            letter,
+            meaning,
+            problem,
+        })
+        .collect();
+
+    for flag in diag.flags.iter().filter(|f| f.problem) {
+        diag.warnings.push(format!(
+            "Kernel tainted ({}): {}",
+            flag.letter, flag.meaning
+        ));
+    }
+    let has = |letter: char| diag.flags.iter().any(|f| f.letter == letter);
+    if has('D') || has('W') {
+        diag.recommendations
+            .push("See the oops section for what the kernel complained about".to_string());
+    }
+    if has('M') || has('B') {
+        diag.recommendations.push(
+            "Machine checks and bad pages point at failing memory or CPU: run a memory test"
+                .to_string(),
+        );
+    }
+    let out_of_tree: Vec<&str> = diag
+        .modules
+        .iter()
+        .filter(|m| m.flags.contains('O') || m.flags.contains('P'))
+        .map(|m| m.module.as_str())
+        .collect();
+    if (has('D') || has('W')) && !out_of_tree.is_empty() {
+        diag.recommendations.push(format!(
+            "Reproduce without {} before reporting upstream; `dkms status` shows how they were built",
+            out_of_tree.join(", ")
+        ));
+    }
+
+    diag
+}
+
+impl TaintDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Taint ===");
+        match self.value {
+            Some(0) => println!("✓ Kernel not tainted"),
+            Some(value) => {
+                let letters: String = self.flags.iter().map(|f| f.letter).collect();
+                println!(
+                    "{} Tainted: {} ({})",
+                    mark(!self.flags.iter().any(|f| f.problem)),
+                    letters,
+                    value
+                );
+                if verbose {
+                    for flag in &self.flags {
+                        println!("  {}  {}", flag.letter, flag.meaning);
+                    }
+                }
+            }
+            None => println!("- Taint: unknown"),
+        }
+        if !self.modules.is_empty() {
+            println!(
+                "- Tainting modules: {}",
+                self.modules
+                    .iter()
+                    .map(|m| format!("{} ({})", m.module, m.flags))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Kernel messages, from the journal or the ring buffer
+//!
+//! The journal keeps kernel messages across reboots, which matters when
+//! the oops that froze the machine happened last boot. Without a readable
+//! journal the ring buffer in `/dev/kmsg` still has the running boot, as
+//! far back as its size allows.
+
+use ambulance_core::host;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// Messages read back over this window
+pub const WINDOW_DAYS: u64 = 7;
+
+/// Journal entries read at most
+const MAX_ENTRIES: usize = 200_000;
+
+#[derive(Debug, Clone)]
+pub struct KernelMessage {
+    /// Microseconds since the epoch
+    pub timestamp: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KernelLog {
+    /// `journal` or `kmsg`; `None` when neither could be read
+    pub source: Option<&'static str>,
+    pub count: usize,
+    /// Oldest first
+    #[serde(skip)]
+    pub messages: Vec<KernelMessage>,
+    pub error: Option<String>,
+}
+
+fn now_usec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64)
+}
+
+fn from_journal() -> std::io::Result<Vec<KernelMessage>> {
+    let since = now_usec().saturating_sub(WINDOW_DAYS * 86_400 * 1_000_000);
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.seek_tail()?;
+    let mut messages = Vec::new();
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? {
+            break;
+        }
+        let timestamp = journal.realtime_usec()?;
+        if timestamp < since {
+            break;
+        }
+        if let Some(text) = journal.field("MESSAGE") {
+            messages.push(KernelMessage { timestamp, text });
+        }
+    }
+    messages.reverse();
+    Ok(messages)
+}
+
+/// Boot time in microseconds since the epoch, from `btime` in /proc/stat
+fn boot_usec() -> u64 {
+    std::fs::read_to_string(host::path("/proc/stat"))
+        .ok()
+        .and_then(|stat| {
+            stat.lines()
+                .find_map(|l| l.strip_prefix("btime "))
+                .and_then(|s| s.trim().parse::<u64>().ok())
+        })
+        .map_or(0, |secs| secs * 1_000_000)
+}
+
+/// Every record in the ring buffer; `/dev/kmsg` hands out one per read
+/// as `priority,sequence,usec,flags;text`, followed by indented
+/// key=value lines
+fn from_kmsg() -> std::io::Result<Vec<KernelMessage>> {
+    let mut kmsg = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open("/dev/kmsg")?;
+    let boot = boot_usec();
+    let mut messages = Vec::new();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let n = match kmsg.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            // Records overwritten while reading; carry on with the next
+            Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+            Err(e) => return Err(e),
+        };
+        let record = String::from_utf8_lossy(&buf[..n]);
+        let Some((header, text)) = record.split_once(';') else {
+            continue;
+        };
+        let usec = header
+            .split(',')
+            .nth(2)
+            .and_then(|u| u.parse::<u64>().ok())
+            .unwrap_or(0);
+        messages.push(KernelMessage {
+            timestamp: boot + usec,
+            text: text.lines().next().unwrap_or_default().to_string(),
+        });
+    }
+    Ok(messages)
+}
+
+pub fn load() -> KernelLog {
+    let mut log = KernelLog::default();
+    let (source, messages) = match from_journal() {
+        Ok(messages) if !messages.is_empty() => ("journal", Ok(messages)),
+        Ok(_) => (
+            "kmsg",
+            from_kmsg().map_err(|e| format!("journal has no kernel messages; /dev/kmsg: {}", e)),
+        ),
+        Err(journal_error) => (
+            "kmsg",
+            from_kmsg().map_err(|e| format!("journal: {}; /dev/kmsg: {}", journal_error, e)),
+        ),
+    };
+    match messages {
+        Ok(messages) => {
+            log.source = Some(source);
+            log.count = messages.len();
+            log.messages = messages;
+        }
+        Err(e) => log.error = Some(e),
+    }
+    log
+}
+
+impl KernelLog {
+    pub fn print(&self, _verbose: bool) {
+        match (self.source, &self.error) {
+            (Some("journal"), _) => println!(
+                "Kernel messages: {} from the journal, last {} days\n",
+                self.count, WINDOW_DAYS
+            ),
+            (Some(source), _) => println!(
+                "Kernel messages: {} from {}, this boot only\n",
+                self.count, source
+            ),
+            (None, Some(error)) => println!("✗ Kernel messages unreadable: {}\n", error),
+            (None, None) => {}
+        }
+    }
+}
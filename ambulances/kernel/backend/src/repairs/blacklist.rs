@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Blacklist modules known to misbehave here
+//!
+//! Only modules a matched signature names are blacklisted, each asked for
+//! separately, in a modprobe.d file of our own so removing it undoes the
+//! lot. The initramfs is regenerated because it loads modules before the
+//! root filesystem, and with it `/etc/modprobe.d`, is mounted.
+
+use crate::diagnostics::signatures::{self, REPLACEMENTS};
+use crate::messages;
+use crate::report::RepairOutcome;
+use crate::system;
+use std::path::Path;
+
+const CONF: &str = "/etc/modprobe.d/kernel-ambulance-blacklist.conf";
+
+/// Initramfs generators: Debian and Ubuntu, Fedora and openSUSE, Arch
+const INITRAMFS: &[(&str, &[&str])] = &[
+    ("update-initramfs", &["-u"]),
+    ("dracut", &["--force"]),
+    ("mkinitcpio", &["-P"]),
+];
+
+fn blacklisted() -> Vec<String> {
+    system::read(CONF)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("blacklist "))
+        .map(|m| m.trim().to_string())
+        .collect()
+}
+
+fn module_available(module: &str) -> bool {
+    Path::new("/sys/module").join(module).exists() || system::run("modinfo", &[module]).is_ok()
+}
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let modules = signatures::diagnose(&messages::load()).blacklist();
+    if modules.is_empty() {
+        return RepairOutcome::not_needed("No module to blacklist, no repair needed");
+    }
+    let existing = blacklisted();
+    let pending: Vec<&str> = modules
+        .into_iter()
+        .filter(|m| !existing.iter().any(|e| e == m))
+        .collect();
+    if pending.is_empty() {
+        return RepairOutcome::not_needed(&format!(
+            "Already blacklisted in {}, reboot if the modules are still loaded",
+            CONF
+        ));
+    }
+
+    let mut result = RepairOutcome::default();
+    let mut added = Vec::new();
+    for module in pending {
+        if let Some((_, replacement)) = REPLACEMENTS.iter().find(|(m, _)| *m == module) {
+            if !module_available(replacement) {
+                result.errors.push(format!(
+                    "Not blacklisting {}: the {} driver is not installed to take over",
+                    module, replacement
+                ));
+                continue;
+            }
+        }
+        if !confirm(&format!("Blacklist the {} module?", module)) {
+            result.errors.push(format!(
+                "{} not confirmed, left loadable (rerun with --yes to confirm)",
+                module
+            ));
+            continue;
+        }
+        added.push(module);
+    }
+    if added.is_empty() {
+        return result;
+    }
+
+    let mut conf = system::read(CONF)
+        .unwrap_or_else(|| "# Written by kernel-ambulance; delete this file to undo\n".to_string());
+    for module in &added {
+        conf.push_str(&format!("blacklist {}\n", module));
+    }
+    if let Err(e) = std::fs::write(CONF, conf) {
+        result.errors.push(format!("{}: {}", CONF, e));
+        return result;
+    }
+    result
+        .actions
+        .push(format!("Blacklisted {} in {}", added.join(", "), CONF));
+
+    match INITRAMFS.iter().find(|(tool, _)| system::has(tool)) {
+        Some((tool, args)) => match system::run(tool, args) {
+            Ok(_) => result
+                .actions
+                .push(format!("Regenerated the initramfs with {}", tool)),
+            Err(e) => result.errors.push(e),
+        },
+        None => result
+            .actions
+            .push("No initramfs generator found; nothing to regenerate".to_string()),
+    }
+    result
+        .actions
+        .push("Reboot for the blacklist to take effect".to_string());
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Install missing firmware
+//!
+//! Installs the packages carrying the files the kernel failed to load,
+//! then checks the files are there. Drivers only ask again when they
+//! probe, so the firmware is used from the next boot.
+
+use crate::diagnostics::firmware;
+use crate::messages;
+use crate::report::RepairOutcome;
+use crate::system;
+
+/// Non-interactive install command of each package manager
+const INSTALL: &[(&str, &[&str])] = &[
+    ("apt-get", &["install", "-y"]),
+    ("dnf", &["install", "-y"]),
+    ("pacman", &["-S", "--needed", "--noconfirm"]),
+    ("zypper", &["--non-interactive", "install"]),
+];
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let diag = firmware::diagnose(&messages::load());
+    let missing: Vec<&firmware::FirmwareFailure> =
+        diag.failures.iter().filter(|f| !f.present).collect();
+    if missing.is_empty() {
+        return RepairOutcome::not_needed("No firmware missing, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    let Some((manager, install)) = INSTALL.iter().find(|(manager, _)| system::has(manager)) else {
+        result
+            .errors
+            .push("No supported package manager to install firmware with".to_string());
+        return result;
+    };
+    let mut packages: Vec<&str> = missing
+        .iter()
+        .filter_map(|f| f.package.as_deref())
+        .collect();
+    packages.sort_unstable();
+    packages.dedup();
+
+    let question = format!(
+        "Install {} for {} missing firmware file(s)?",
+        packages.join(", "),
+        missing.len()
+    );
+    if !confirm(&question) {
+        result.errors.push(format!(
+            "Firmware not confirmed, {} not installed (rerun with --yes to confirm)",
+            packages.join(", ")
+        ));
+        return result;
+    }
+
+    let mut args = install.to_vec();
+    args.extend(&packages);
+    match system::run(manager, &args) {
+        Ok(_) => result
+            .actions
+            .push(format!("Installed {}", packages.join(", "))),
+        Err(e) => {
+            result.errors.push(e);
+            if *manager == "apt-get" {
+                result.errors.push(
+                    "Debian ships firmware in the non-free-firmware component; enable it in the apt sources"
+                        .to_string(),
+                );
+            }
+            return result;
+        }
+    }
+    for failure in missing {
+        if firmware::present(&failure.file) {
+            result
+                .actions
+                .push(format!("{} is installed", failure.file));
+        } else {
+            result.errors.push(format!(
+                "{} is still missing; {} does not carry it here",
+                failure.file,
+                failure.package.as_deref().unwrap_or("no package")
+            ));
+        }
+    }
+    result
+        .actions
+        .push("Reboot for the drivers to load the firmware".to_string());
+    result.success = result.errors.is_empty();
+    result
+}
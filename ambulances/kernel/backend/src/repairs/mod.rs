@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Kernel repairs, one module per target
+//!
+//! Both take effect at the next boot; each asks before changing anything.
+
+pub mod blacklist;
+pub mod firmware;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["firmware", "blacklist", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        firmware_repair: if selected("firmware") {
+            firmware::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        blacklist_repair: if selected("blacklist") {
+            blacklist::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
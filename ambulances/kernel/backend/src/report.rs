@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    firmware::FirmwareDiagnostics, hangs::HangDiagnostics, io::IoDiagnostics,
+    oops::OopsDiagnostics, signatures::SignatureDiagnostics, taint::TaintDiagnostics,
+};
+use crate::messages::KernelLog;
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "kernel-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    /// Where the kernel messages came from
+    pub log: KernelLog,
+    pub taint: TaintDiagnostics,
+    pub oops: OopsDiagnostics,
+    pub hangs: HangDiagnostics,
+    pub io: IoDiagnostics,
+    pub firmware: FirmwareDiagnostics,
+    pub signatures: SignatureDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.taint.warnings,
+            &self.oops.warnings,
+            &self.hangs.warnings,
+            &self.io.warnings,
+            &self.firmware.warnings,
+            &self.signatures.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub firmware_repair: RepairOutcome,
+    pub blacklist_repair: RepairOutcome,
+}
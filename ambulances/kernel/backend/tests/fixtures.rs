@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The kernel checks against broken fake systems
+
+use ambulance_testkit::{CheckExt, Entry, ReportExt, System};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_kernel-ambulance");
+
+#[test]
+fn an_oops_in_an_out_of_tree_module_blames_the_module() {
+    let mut system = System::new();
+    // D and W, from a module that is proprietary and out of tree
+    system.tainted((1 << 7) | (1 << 9)).module("nvidia", "POE");
+    system
+        .journal(
+            Entry::new("Oops: 0000 [#1] PREEMPT SMP NOPTI")
+                .kernel()
+                .ago(120),
+        )
+        .journal(
+            Entry::new("RIP: 0010:_nv000912rm+0x1d/0x40 [nvidia]")
+                .kernel()
+                .ago(119),
+        );
+    let report = system.diagnose(BINARY);
+
+    let taint = report.check("taint");
+    assert_eq!(
+        taint.summaries(),
+        [
+            "Kernel tainted (D): kernel died recently (oops or BUG)",
+            "Kernel tainted (W): kernel issued a warning"
+        ]
+    );
+    assert!(taint
+        .advice()
+        .iter()
+        .any(|r| r.starts_with("Reproduce without nvidia")));
+    let oops = report.check("oops");
+    assert_eq!(oops.summaries(), ["Kernel oops in _nv000912rm [nvidia] x1"]);
+    assert!(oops
+        .advice()
+        .iter()
+        .any(|r| r.starts_with("nvidia is an out-of-tree or proprietary module")));
+}
+
+#[test]
+fn hung_tasks_are_named() {
+    let mut system = System::new();
+    system.tainted(0);
+    for (pid, ago) in [(312, 600), (312, 480), (977, 300)] {
+        let task = if pid == 312 {
+            "jbd2/sda1-8"
+        } else {
+            "postgres"
+        };
+        system.journal(
+            Entry::new(&format!(
+                "INFO: task {}:{} blocked for more than 120 seconds.",
+                task, pid
+            ))
+            .kernel()
+            .ago(ago),
+        );
+    }
+    let report = system.diagnose(BINARY);
+
+    assert_eq!(
+        report.check("hangs").summaries(),
+        ["3 hung task report(s): jbd2/sda1-8, postgres"]
+    );
+}
+
+#[test]
+fn a_deliberate_taint_alone_passes() {
+    let mut system = System::new();
+    // P only: a proprietary driver is a choice, not a problem
+    system.tainted(1).module("nvidia", "PO");
+    system.journal(Entry::new("Linux version 6.9.0").kernel().ago(60));
+    let report = system.diagnose(BINARY);
+
+    for id in ["taint", "oops", "hangs"] {
+        assert!(report.check(id).summaries().is_empty(), "{:?}", report);
+    }
+}
@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Card readers, fingerprint readers and enrolled fingers
+//!
+//! Card readers are USB interfaces of the smart card class, found in
+//! sysfs whether or not pcscd is running. Fingerprint readers are the
+//! ones fprintd drives. A user whose prints are stored but whom fprintd
+//! lists no fingers for on the current reader enrolled on another one,
+//! typically before the reader or the machine's board was replaced, and
+//! has to enroll again.
+
+use crate::fprint::{self, Reader};
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const USB_DEVICES: &str = "/sys/bus/usb/devices";
+
+/// `bInterfaceClass` of smart card readers (CCID)
+const CLASS_SMART_CARD: &str = "0b";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CardReader {
+    /// sysfs name of the USB device, e.g. `1-2`
+    pub device: String,
+    pub product: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Enrollment {
+    pub user: String,
+    /// Fingers fprintd matches on the current reader
+    pub fingers: Vec<String>,
+}
+
+impl Enrollment {
+    /// Prints are stored but none is for the current reader
+    pub fn stale(&self) -> bool {
+        self.fingers.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviceDiagnostics {
+    pub card_readers: Vec<CardReader>,
+    /// Why fingerprint readers could not be listed, usually no fprintd
+    pub fprintd_error: Option<String>,
+    pub fingerprint_readers: Vec<String>,
+    /// Users with stored prints; `None` without root
+    pub enrollments: Option<Vec<Enrollment>>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl DeviceDiagnostics {
+    pub fn stale(&self) -> impl Iterator<Item = &Enrollment> {
+        self.enrollments.iter().flatten().filter(|e| e.stale())
+    }
+}
+
+/// USB devices with a smart card interface
+pub fn card_readers() -> Vec<CardReader> {
+    let devices = host::path(USB_DEVICES);
+    let Ok(entries) = std::fs::read_dir(&devices) else {
+        return Vec::new();
+    };
+    let mut readers: Vec<CardReader> = entries
+        .flatten()
+        .filter(|e| {
+            system::read(e.path().join("bInterfaceClass")).as_deref() == Some(CLASS_SMART_CARD)
+        })
+        .filter_map(|e| {
+            // Interfaces are named `<device>:<config>.<interface>`
+            let name = e.file_name().to_string_lossy().into_owned();
+            let device = name.split(':').next()?.to_string();
+            Some(CardReader {
+                product: system::read(devices.join(&device).join("product")),
+                device,
+            })
+        })
+        .collect();
+    readers.sort_by(|a, b| a.device.cmp(&b.device));
+    readers.dedup_by(|a, b| a.device == b.device);
+    readers
+}
+
+/// Each user with stored prints and what the first reader matches for them
+pub fn enrollments(bus: &Bus, readers: &[Reader]) -> Option<Vec<Enrollment>> {
+    let users = fprint::stored_users()?;
+    Some(
+        users
+            .into_iter()
+            .map(|user| Enrollment {
+                fingers: readers
+                    .first()
+                    .map(|r| fprint::enrolled(bus, r, &user))
+                    .unwrap_or_default(),
+                user,
+            })
+            .collect(),
+    )
+}
+
+pub fn diagnose() -> DeviceDiagnostics {
+    let mut diag = DeviceDiagnostics {
+        card_readers: card_readers(),
+        ..DeviceDiagnostics::default()
+    };
+    let found = Bus::system().and_then(|bus| {
+        let readers = fprint::readers(&bus)?;
+        Ok((enrollments(&bus, &readers), readers))
+    });
+    match found {
+        Ok((enrollments, readers)) => {
+            diag.fingerprint_readers = readers.into_iter().map(|r| r.name).collect();
+            diag.enrollments = enrollments;
+        }
+        Err(e) => diag.fprintd_error = Some(e.to_string()),
+    }
+
+    let stale: Vec<String> = diag.stale().map(|e| e.user.clone()).collect();
+    if !stale.is_empty() && diag.fingerprint_readers.is_empty() {
+        diag.warnings.push(format!(
+            "Fingerprints are enrolled for {} but no fingerprint reader is found",
+            stale.join(", ")
+        ));
+        diag.recommendations.push(
+            "Check the reader is connected and supported by libfprint (lsusb, journalctl -u fprintd)"
+                .to_string(),
+        );
+    } else if !stale.is_empty() {
+        diag.warnings.push(format!(
+            "Fingerprints of {} were enrolled on another reader and no longer match",
+            stale.join(", ")
+        ));
+        diag.recommendations.push(
+            "Enroll them again at the reader: login-hardware-ambulance repair enroll".to_string(),
+        );
+    }
+    diag
+}
+
+impl DeviceDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Devices ===");
+        if self.card_readers.is_empty() {
+            println!("- No smart card reader");
+        }
+        for reader in &self.card_readers {
+            println!(
+                "✓ Card reader {}: {}",
+                reader.device,
+                reader.product.as_deref().unwrap_or("unknown")
+            );
+        }
+        match &self.fprintd_error {
+            Some(e) if verbose => println!("- Fingerprint readers unavailable: {}", e),
+            Some(_) => println!("- Fingerprint readers unavailable (fprintd not running)"),
+            None if self.fingerprint_readers.is_empty() => println!("- No fingerprint reader"),
+            None => {}
+        }
+        for name in &self.fingerprint_readers {
+            println!("✓ Fingerprint reader: {}", name);
+        }
+        match &self.enrollments {
+            Some(enrollments) => {
+                for e in enrollments {
+                    println!(
+                        "{} {}: {}",
+                        mark(!e.stale()),
+                        e.user,
+                        if e.stale() {
+                            "enrolled on another reader".to_string()
+                        } else {
+                            e.fingers.join(", ")
+                        }
+                    );
+                }
+            }
+            None if self.fprintd_error.is_none() => {
+                println!("- Enrolled fingers need root to check")
+            }
+            None => {}
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
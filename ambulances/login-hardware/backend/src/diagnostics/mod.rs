@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Login hardware diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod devices;
+pub mod pam;
+pub mod services;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; fprintd can hang on a reader that stopped
+/// answering
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Services(services::ServiceDiagnostics),
+    Devices(devices::DeviceDiagnostics),
+    Pam(pam::PamDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("services", |_| {
+            Ok(Section::Services(services::diagnose()))
+        }))
+        .job(Job::blocking("devices", |_| {
+            Ok(Section::Devices(devices::diagnose()))
+        }))
+        .job(Job::blocking("pam", |_| Ok(Section::Pam(pam::diagnose()))))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        services: Default::default(),
+        devices: Default::default(),
+        pam: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Services(d)) => result.services = d,
+            Some(Section::Devices(d)) => result.devices = d,
+            Some(Section::Pam(d)) => result.pam = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "services" => &mut result.services.warnings,
+                    "devices" => &mut result.devices.warnings,
+                    _ => &mut result.pam.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
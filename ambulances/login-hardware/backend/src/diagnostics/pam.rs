@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! PAM stacks that use a card, fingerprint or security key
+//!
+//! Every file in `/etc/pam.d` is read. A module that is not installed
+//! fails its line, which locks everyone out of that service when the
+//! line is required; lines starting with `-` are skipped silently by PAM
+//! and not reported. A hardware module marked `required` or `requisite`
+//! in an `auth` stack refuses the login whenever its device is missing,
+//! with no fallback to the password.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use std::path::Path;
+
+const PAM_DIR: &str = "/etc/pam.d";
+
+/// Modules that authenticate with a device, and what the device is
+const HARDWARE_MODULES: &[(&str, &str)] = &[
+    ("pam_fprintd.so", "fingerprint"),
+    ("pam_pkcs11.so", "smartcard"),
+    ("pam_p11.so", "smartcard"),
+    ("pam_u2f.so", "security key"),
+];
+
+const PKCS11_CONFIG: &str = "/etc/pam_pkcs11/pam_pkcs11.conf";
+
+/// Where distributions install PAM modules; multiarch directories are added
+const MODULE_DIRS: &[&str] = &[
+    "/lib/security",
+    "/lib64/security",
+    "/usr/lib/security",
+    "/usr/lib64/security",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PamLine {
+    pub file: String,
+    pub line: usize,
+    /// `auth`, `account`, `password` or `session`
+    pub kind: String,
+    pub control: String,
+    pub module: String,
+    /// Prefixed with `-`: skipped when the module is missing
+    pub optional: bool,
+}
+
+impl PamLine {
+    pub fn hardware(&self) -> Option<&'static str> {
+        let name = self.module.rsplit('/').next().unwrap_or(&self.module);
+        HARDWARE_MODULES
+            .iter()
+            .find(|(module, _)| *module == name)
+            .map(|(_, device)| *device)
+    }
+
+    /// A hardware `auth` line that refuses the login when its device is absent
+    pub fn locks_out(&self) -> bool {
+        self.hardware().is_some()
+            && self.kind == "auth"
+            && matches!(self.control.as_str(), "required" | "requisite")
+    }
+
+    fn location(&self) -> String {
+        format!("{}:{}", self.file, self.line)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PamDiagnostics {
+    /// Lines using a hardware module
+    pub hardware_lines: Vec<PamLine>,
+    /// Lines naming a module that is not installed
+    pub missing_modules: Vec<PamLine>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn parse_line(file: &str, number: usize, line: &str) -> Option<PamLine> {
+    let line = line.split('#').next()?.trim();
+    if line.is_empty() || line.starts_with('@') {
+        return None;
+    }
+    let (kind, rest) = line.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    // Bracketed controls like `[success=1 default=ignore]` hold spaces
+    let (control, rest) = if rest.starts_with('[') {
+        let end = rest.find(']')?;
+        (&rest[..=end], &rest[end + 1..])
+    } else {
+        rest.split_once(char::is_whitespace)?
+    };
+    // These name another stack, not a module
+    if matches!(control, "include" | "substack") {
+        return None;
+    }
+    Some(PamLine {
+        file: file.to_string(),
+        line: number,
+        kind: kind.trim_start_matches('-').to_string(),
+        control: control.to_string(),
+        module: rest.split_whitespace().next()?.to_string(),
+        optional: kind.starts_with('-'),
+    })
+}
+
+fn lines() -> Vec<PamLine> {
+    let Ok(entries) = std::fs::read_dir(PAM_DIR) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    files.sort();
+    files
+        .iter()
+        .filter_map(|path| Some((path.display().to_string(), system::read(path)?)))
+        .flat_map(|(file, text)| {
+            text.lines()
+                .enumerate()
+                .filter_map(|(i, line)| parse_line(&file, i + 1, line))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn module_dirs() -> Vec<String> {
+    let mut dirs: Vec<String> = MODULE_DIRS.iter().map(|d| d.to_string()).collect();
+    for lib in ["/lib", "/usr/lib"] {
+        let Ok(entries) = std::fs::read_dir(lib) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.contains("-linux-") {
+                dirs.push(format!("{}/{}/security", lib, name));
+            }
+        }
+    }
+    dirs
+}
+
+fn installed(module: &str, dirs: &[String]) -> bool {
+    if module.starts_with('/') {
+        return Path::new(module).exists();
+    }
+    dirs.iter().any(|d| Path::new(d).join(module).exists())
+}
+
+pub fn diagnose() -> PamDiagnostics {
+    let dirs = module_dirs();
+    let all = lines();
+    let mut diag = PamDiagnostics {
+        missing_modules: all
+            .iter()
+            .filter(|l| !l.optional && !installed(&l.module, &dirs))
+            .cloned()
+            .collect(),
+        hardware_lines: all.into_iter().filter(|l| l.hardware().is_some()).collect(),
+        ..PamDiagnostics::default()
+    };
+
+    for l in &diag.missing_modules {
+        diag.warnings.push(format!(
+            "{} uses {}, which is not installed, so that line always fails",
+            l.location(),
+            l.module
+        ));
+    }
+    if !diag.missing_modules.is_empty() {
+        diag.recommendations.push(
+            "Install the missing modules, or prefix their lines with '-' so PAM skips them"
+                .to_string(),
+        );
+    }
+    for l in diag.hardware_lines.iter().filter(|l| l.locks_out()) {
+        diag.warnings.push(format!(
+            "{} makes {} {}: nobody logs in while the {} is missing",
+            l.location(),
+            l.module,
+            l.control,
+            l.hardware().unwrap_or("device")
+        ));
+        diag.recommendations.push(format!(
+            "Change {} to 'sufficient' in {} so the password still works",
+            l.control,
+            l.location()
+        ));
+    }
+    if diag
+        .hardware_lines
+        .iter()
+        .any(|l| l.module.ends_with("pam_pkcs11.so"))
+        && !Path::new(PKCS11_CONFIG).exists()
+    {
+        diag.warnings.push(format!(
+            "pam_pkcs11 is used but {} is missing, so no card is accepted",
+            PKCS11_CONFIG
+        ));
+        diag.recommendations.push(format!(
+            "Create {} from the example shipped with pam_pkcs11",
+            PKCS11_CONFIG
+        ));
+    }
+    diag
+}
+
+impl PamDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== PAM ===");
+        if self.hardware_lines.is_empty() {
+            println!("- No PAM stack uses a card, fingerprint or security key");
+        }
+        for l in &self.hardware_lines {
+            println!(
+                "{} {}: {} {} {}",
+                mark(
+                    !l.locks_out()
+                        && !self
+                            .missing_modules
+                            .iter()
+                            .any(|m| m.location() == l.location())
+                ),
+                l.location(),
+                l.kind,
+                l.control,
+                l.module
+            );
+        }
+        if verbose {
+            for l in &self.missing_modules {
+                println!("✗ {}: {} not installed", l.location(), l.module);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! pcscd and fprintd unit state
+//!
+//! Both daemons start on demand: pcscd through its socket unit and
+//! fprintd through the bus, so an inactive service is normal. A failed
+//! one is not, and neither is pcscd's socket being down while a card
+//! reader is plugged in, since no smartcard login can reach the card.
+
+use crate::diagnostics::devices;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::bus::{self, Bus};
+
+/// Units checked, with what they serve
+pub const UNITS: &[(&str, &str)] = &[
+    ("pcscd.socket", "smartcard"),
+    ("pcscd.service", "smartcard"),
+    ("fprintd.service", "fingerprint"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnitStatus {
+    pub unit: &'static str,
+    pub serves: &'static str,
+    /// `ActiveState`; `None` when not installed
+    pub state: Option<String>,
+    /// Needs a repair: failed, or the socket down while a reader is present
+    pub broken: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServiceDiagnostics {
+    pub error: Option<String>,
+    pub units: Vec<UnitStatus>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// `ActiveState` of a unit that is installed
+pub fn unit_state(bus: &Bus, unit: &str) -> Option<String> {
+    let path = bus::unit_path(unit).ok()?;
+    let load = bus
+        .get_property_string(bus::SYSTEMD, &path, bus::UNIT, "LoadState")
+        .ok()?;
+    if load != "loaded" {
+        return None;
+    }
+    bus.get_property_string(bus::SYSTEMD, &path, bus::UNIT, "ActiveState")
+        .ok()
+}
+
+/// Every unit in `UNITS` with its state
+pub fn statuses(bus: &Bus) -> Vec<UnitStatus> {
+    let readers_present = !devices::card_readers().is_empty();
+    UNITS
+        .iter()
+        .map(|&(unit, serves)| {
+            let state = unit_state(bus, unit);
+            let broken = match state.as_deref() {
+                Some("failed") => true,
+                Some("active") | None => false,
+                Some(_) => unit == "pcscd.socket" && readers_present,
+            };
+            UnitStatus {
+                unit,
+                serves,
+                state,
+                broken,
+            }
+        })
+        .collect()
+}
+
+pub fn diagnose() -> ServiceDiagnostics {
+    let mut diag = ServiceDiagnostics::default();
+    match Bus::system() {
+        Ok(bus) => diag.units = statuses(&bus),
+        Err(e) => {
+            diag.error = Some(e.to_string());
+            return diag;
+        }
+    }
+
+    for s in diag.units.iter().filter(|s| s.broken) {
+        diag.warnings.push(match s.state.as_deref() {
+            Some("failed") => format!("{} has failed; {} login cannot work", s.unit, s.serves),
+            _ => format!(
+                "{} is not listening although a card reader is plugged in",
+                s.unit
+            ),
+        });
+    }
+    if diag.units.iter().any(|s| s.broken) {
+        diag.recommendations.push(
+            "Restart the login services: login-hardware-ambulance repair services".to_string(),
+        );
+    }
+    if diag.units.iter().all(|s| s.state.is_none()) {
+        diag.recommendations.push(
+            "Neither pcscd nor fprintd is installed; install them to log in with a card or finger"
+                .to_string(),
+        );
+    }
+    diag
+}
+
+impl ServiceDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Services ===");
+        if let Some(e) = &self.error {
+            println!("✗ System bus unavailable: {}", e);
+            println!();
+            return;
+        }
+        for s in &self.units {
+            println!(
+                "{} {} ({}): {}",
+                mark(!s.broken),
+                s.unit,
+                s.serves,
+                s.state.as_deref().unwrap_or("not installed")
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! fprintd's readers and enrolled fingers, over the system bus
+//!
+//! fprintd is started on demand by the bus, so asking it anything starts
+//! it if needed. It keeps each user's prints under `/var/lib/fprint`,
+//! readable by root only, in directories named after the reader's driver
+//! and device; prints made on another reader are kept but never match.
+
+use systemd_shim::bus::Bus;
+
+const FPRINT: &str = "net.reactivated.Fprint";
+const MANAGER_PATH: &str = "/net/reactivated/Fprint/Manager";
+const MANAGER: &str = "net.reactivated.Fprint.Manager";
+const DEVICE: &str = "net.reactivated.Fprint.Device";
+
+/// Where fprintd stores prints, one directory per user
+pub const STORAGE: &str = "/var/lib/fprint";
+
+#[derive(Debug, Clone)]
+pub struct Reader {
+    pub path: String,
+    pub name: String,
+}
+
+/// Readers in fprintd's order; the first is the one PAM uses
+pub fn readers(bus: &Bus) -> std::io::Result<Vec<Reader>> {
+    let paths = bus.call_object_paths(FPRINT, MANAGER_PATH, MANAGER, "GetDevices")?;
+    Ok(paths
+        .into_iter()
+        .map(|path| Reader {
+            name: bus
+                .get_property_string(FPRINT, &path, DEVICE, "name")
+                .unwrap_or_else(|_| "unnamed reader".to_string()),
+            path,
+        })
+        .collect())
+}
+
+/// Fingers `user` has enrolled on `reader`
+///
+/// fprintd answers with an error when there are none, so errors count
+/// as none.
+pub fn enrolled(bus: &Bus, reader: &Reader, user: &str) -> Vec<String> {
+    bus.call_strings(
+        FPRINT,
+        &reader.path,
+        DEVICE,
+        "ListEnrolledFingers",
+        Some(user),
+    )
+    .unwrap_or_default()
+}
+
+/// Users with prints stored, on any reader; `None` when the storage
+/// cannot be read, as without root
+pub fn stored_users() -> Option<Vec<String>> {
+    let entries = std::fs::read_dir(STORAGE).ok()?;
+    let mut users: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter(|e| {
+            std::fs::read_dir(e.path())
+                .map(|mut d| d.next().is_some())
+                .unwrap_or(false)
+        })
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    users.sort();
+    Some(users)
+}
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Login Hardware Ambulance backend
+//!
+//! Checks what smartcard, fingerprint and security key logins depend on:
+//! the pcscd and fprintd services, the readers and the fingers enrolled
+//! on them, and the PAM stacks that use them. Repairs restart the
+//! services and enroll fingers again, asking first. `--json` output
+//! follows the network ambulance's report model.
+
+mod diagnostics;
+mod fprint;
+mod repairs;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Category, Error};
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Login Hardware Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: login-hardware-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all login hardware diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+    println!("  -y, --yes            Approve changes that ask for confirmation");
+    println!("  -u, --user <name>    Enroll only this user");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Login Hardware Ambulance");
+    println!("========================\n");
+    result.services.print(verbose);
+    result.devices.print(verbose);
+    result.pam.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Services", &result.services.warnings),
+        ("Devices", &result.devices.warnings),
+        ("PAM", &result.pam.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    println!(
+        "Card readers: {}, fingerprint readers: {}",
+        result.devices.card_readers.len(),
+        result.devices.fingerprint_readers.len()
+    );
+    exit::findings(result.has_warnings())
+}
+
+/// Ask on the terminal; without one (or in JSON mode) nothing is approved
+fn prompt(question: &str, json: bool) -> bool {
+    if json || !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn run_repair(target: &str, only: Option<&str>, json: bool, yes: bool) -> ExitCode {
+    if let Err(e) =
+        ambulance_privilege::require(&format!("login-hardware-ambulance repair {}", target))
+    {
+        return fail(e, json);
+    }
+
+    let mut confirm = |question: &str| yes || prompt(question, json);
+    let result = match repairs::run(target, only, &mut confirm) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [
+        ("services", "Service Restart", &result.services_repair),
+        ("enroll", "Fingerprint Enrollment", &result.enroll_repair),
+    ];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Login Hardware Ambulance - Repair Mode");
+        println!("======================================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let yes = args.iter().any(|a| a == "-y" || a == "--yes");
+    let user_flag = args.iter().position(|a| a == "-u" || a == "--user");
+    let only = match user_flag {
+        Some(i) => args.get(i + 1).map(String::as_str),
+        None => args.iter().find_map(|a| a.strip_prefix("--user=")),
+    };
+    let positional: Vec<&str> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with('-') && user_flag.map_or(true, |u| *i != u + 1))
+        .map(|(_, a)| a.as_str())
+        .collect();
+    if user_flag.is_some() && only.is_none() {
+        let e = Error::new(
+            "usage.missing-user",
+            Category::Usage,
+            "--user requires a user name",
+        );
+        return fail(e, json);
+    }
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, only, json, yes),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Login Hardware Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
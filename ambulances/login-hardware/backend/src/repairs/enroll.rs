@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Enroll fingerprints again on the current reader
+//!
+//! `fprintd-enroll` runs on this terminal and tells the user when to
+//! touch or swipe the reader, so someone has to be there. Prints that
+//! still match the reader, as when `--user` names someone whose finger
+//! is recognised poorly, are deleted first; prints made on an older
+//! reader are left, since fprintd never offers them again.
+
+use crate::diagnostics::devices;
+use crate::fprint;
+use crate::report::RepairOutcome;
+use crate::system;
+use std::io::IsTerminal;
+use std::process::Command;
+use systemd_shim::bus::Bus;
+
+pub fn repair(only: Option<&str>, confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let users: Vec<String> = match only {
+        Some(user) => vec![user.to_string()],
+        None => devices::diagnose()
+            .stale()
+            .map(|e| e.user.clone())
+            .collect(),
+    };
+    if users.is_empty() {
+        return RepairOutcome::not_needed(
+            "Every enrolled finger matches the reader, no repair needed",
+        );
+    }
+
+    let found = Bus::system().and_then(|bus| {
+        let readers = fprint::readers(&bus)?;
+        Ok((bus, readers))
+    });
+    let (bus, reader) = match found {
+        Ok((bus, readers)) => match readers.into_iter().next() {
+            Some(reader) => (bus, reader),
+            None => {
+                result
+                    .errors
+                    .push("No fingerprint reader found; connect it first".to_string());
+                return result;
+            }
+        },
+        Err(e) => {
+            result
+                .errors
+                .push(format!("fprintd is not reachable: {}", e));
+            return result;
+        }
+    };
+    if !system::has("fprintd-enroll") {
+        result
+            .errors
+            .push("fprintd-enroll is not installed (it comes with fprintd)".to_string());
+        return result;
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        result.errors.push(
+            "Enrolling needs someone at the reader; run this repair from a terminal".to_string(),
+        );
+        return result;
+    }
+
+    for user in users {
+        if !confirm(&format!(
+            "Enroll {}'s right index finger on {} (they need to be at the reader)?",
+            user, reader.name
+        )) {
+            result.errors.push(format!(
+                "{} not confirmed, left as is (rerun with --yes to confirm)",
+                user
+            ));
+            continue;
+        }
+        if !fprint::enrolled(&bus, &reader, &user).is_empty() {
+            match system::run("fprintd-delete", &[&user]) {
+                Ok(_) => result.actions.push(format!(
+                    "Deleted {}'s fingerprints on {}",
+                    user, reader.name
+                )),
+                Err(e) => {
+                    result.errors.push(e);
+                    continue;
+                }
+            }
+        }
+        match Command::new("fprintd-enroll").arg(&user).status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                result
+                    .errors
+                    .push(format!("fprintd-enroll {} failed ({})", user, status));
+                continue;
+            }
+            Err(e) => {
+                result
+                    .errors
+                    .push(format!("failed to run fprintd-enroll: {}", e));
+                continue;
+            }
+        }
+        let fingers = fprint::enrolled(&bus, &reader, &user);
+        if fingers.is_empty() {
+            result.errors.push(format!(
+                "{} still has no finger enrolled on {}",
+                user, reader.name
+            ));
+        } else {
+            result.actions.push(format!(
+                "Enrolled {} for {} on {}",
+                fingers.join(", "),
+                user,
+                reader.name
+            ));
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
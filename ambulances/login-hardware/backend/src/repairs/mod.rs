@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Login hardware repairs, one module per target
+//!
+//! `all` restarts the services first, since enrolling goes through
+//! fprintd.
+
+pub mod enroll;
+pub mod services;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["services", "enroll", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `only` narrows enrolling to one user. `confirm` is asked before each
+/// service restart and each enrollment.
+pub fn run(
+    target: &str,
+    only: Option<&str>,
+    confirm: &mut dyn FnMut(&str) -> bool,
+) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        services_repair: if selected("services") {
+            services::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        enroll_repair: if selected("enroll") {
+            enroll::repair(only, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Restart failed login services
+//!
+//! A failed unit has its failed state cleared before the restart, since
+//! one that hit its start limit refuses to start until it is. pcscd's
+//! socket is started rather than the service, which it then activates.
+
+use crate::diagnostics::services::{self, unit_state};
+use crate::report::RepairOutcome;
+use systemd_shim::bus::Bus;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            result.errors.push(format!("system bus: {}", e));
+            return result;
+        }
+    };
+    let broken: Vec<_> = services::statuses(&bus)
+        .into_iter()
+        .filter(|s| s.broken)
+        .collect();
+    if broken.is_empty() {
+        return RepairOutcome::not_needed("Every login service is healthy, no repair needed");
+    }
+
+    for s in broken {
+        if !confirm(&format!("Restart {}?", s.unit)) {
+            result.errors.push(format!(
+                "{} restart not confirmed, left as is (rerun with --yes to confirm)",
+                s.unit
+            ));
+            continue;
+        }
+        if s.state.as_deref() == Some("failed") {
+            match bus.reset_failed_unit(s.unit) {
+                Ok(()) => result
+                    .actions
+                    .push(format!("Cleared the failed state of {}", s.unit)),
+                Err(e) => {
+                    result.errors.push(format!("{}: {}", s.unit, e));
+                    continue;
+                }
+            }
+        }
+        if let Err(e) = bus.restart_unit(s.unit, "replace") {
+            result.errors.push(format!("{}: {}", s.unit, e));
+            continue;
+        }
+        match unit_state(&bus, s.unit).as_deref() {
+            Some("failed") | None => result.errors.push(format!(
+                "{} failed again after the restart; see journalctl -u {}",
+                s.unit, s.unit
+            )),
+            _ => result.actions.push(format!("Restarted {}", s.unit)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
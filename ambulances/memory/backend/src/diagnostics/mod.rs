@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Memory diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget; a machine short of memory is often slow to answer.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod oom;
+pub mod pressure;
+pub mod swap;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; systemd can be slow to answer the unit
+/// limit lookups under memory pressure
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Pressure(pressure::PressureDiagnostics),
+    Swap(swap::SwapDiagnostics),
+    Oom(oom::OomDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("pressure", |_| {
+            Ok(Section::Pressure(pressure::diagnose()))
+        }))
+        .job(Job::blocking("swap", |_| {
+            Ok(Section::Swap(swap::diagnose()))
+        }))
+        .job(Job::blocking("oom", |_| Ok(Section::Oom(oom::diagnose()))))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        pressure: Default::default(),
+        swap: Default::default(),
+        oom: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Pressure(d)) => result.pressure = d,
+            Some(Section::Swap(d)) => result.swap = d,
+            Some(Section::Oom(d)) => result.oom = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "pressure" => &mut result.pressure.warnings,
+                    "swap" => &mut result.swap.warnings,
+                    _ => &mut result.oom.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! OOM-kill history, attributed to units
+//!
+//! The kernel logs each kill as one `oom-kill:` line naming the victim's
+//! cgroup and, for a cgroup limit, the cgroup whose limit was hit.
+//! systemd-oomd logs the cgroup it killed. Both are mapped to units with
+//! the shim's cgroup helpers, so repeat offenders can be given a limit and
+//! units killed by their own limit can have it raised.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::bus::{self, Bus};
+use systemd_shim::cgroup;
+use systemd_shim::journal::{self, Journal};
+
+/// Kills counted over this window
+const WINDOW_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Journal entries read at most; kernel messages make up most of them
+const MAX_ENTRIES: usize = 100_000;
+
+/// Kills kept in the report, newest first
+const MAX_KILLS: usize = 20;
+
+/// Kills of one unit by the global OOM killer that make it a repeat offender
+const REPEAT_KILLS: u32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OomKill {
+    /// Microseconds since the epoch
+    pub timestamp: u64,
+    /// `kernel` or `systemd-oomd`
+    pub killer: &'static str,
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+    pub cgroup: String,
+    /// cgroup whose `memory.max` was hit, for cgroup-limit kills
+    pub limit_cgroup: Option<String>,
+    pub unit: Option<String>,
+    /// Unit inside a user manager, e.g. an app scope
+    pub user_unit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnitKills {
+    pub unit: String,
+    pub kills: u32,
+    /// Kills caused by a cgroup limit rather than the system running out
+    pub limit_kills: u32,
+    /// Unit or slice whose `MemoryMax=` was hit, e.g. `foo.service` or `user.slice`
+    pub limited_by: Option<String>,
+    /// Its current `MemoryMax=` in MiB, `None` when unlimited or unknown
+    pub memory_max_mb: Option<u64>,
+    pub last_process: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OomDiagnostics {
+    pub journal_available: bool,
+    pub total_kills: usize,
+    pub kills: Vec<OomKill>,
+    pub units: Vec<UnitKills>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl OomKill {
+    fn new(timestamp: u64, killer: &'static str, cgroup: String) -> OomKill {
+        OomKill {
+            timestamp,
+            killer,
+            process: None,
+            pid: None,
+            unit: cgroup::unit(&cgroup),
+            user_unit: cgroup::user_unit(&cgroup),
+            cgroup,
+            limit_cgroup: None,
+        }
+    }
+
+    /// The most specific unit, as a user would name it
+    pub fn owner(&self) -> Option<&str> {
+        self.user_unit.as_deref().or(self.unit.as_deref())
+    }
+}
+
+/// Parse the kernel's `oom-kill:constraint=...,task_memcg=...,task=...,pid=...` line
+fn kernel_kill(timestamp: u64, message: &str) -> Option<OomKill> {
+    let fields = message.strip_prefix("oom-kill:")?;
+    let value = |key: &str| {
+        fields
+            .split(',')
+            .find_map(|f| f.strip_prefix(key)?.strip_prefix('='))
+    };
+    let mut kill = OomKill::new(timestamp, "kernel", value("task_memcg")?.to_string());
+    kill.process = value("task").map(str::to_string);
+    kill.pid = value("pid").and_then(|p| p.parse().ok());
+    if value("constraint") == Some("CONSTRAINT_MEMCG") {
+        kill.limit_cgroup = value("oom_memcg").map(str::to_string);
+    }
+    Some(kill)
+}
+
+/// Parse systemd-oomd's `Killed <cgroup> due to ...`
+fn oomd_kill(timestamp: u64, message: &str) -> Option<OomKill> {
+    let (cgroup, _) = message.strip_prefix("Killed ")?.split_once(" due to ")?;
+    Some(OomKill::new(timestamp, "systemd-oomd", cgroup.to_string()))
+}
+
+fn recent_kills() -> std::io::Result<Vec<OomKill>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64);
+    let since = now.saturating_sub(WINDOW_SECS * 1_000_000);
+
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.add_disjunction()?;
+    journal.add_match("SYSLOG_IDENTIFIER=systemd-oomd")?;
+    journal.seek_tail()?;
+    let mut kills = Vec::new();
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? {
+            break;
+        }
+        let timestamp = journal.realtime_usec()?;
+        if timestamp < since {
+            break;
+        }
+        let Some(message) = journal.field("MESSAGE") else {
+            continue;
+        };
+        if let Some(kill) =
+            kernel_kill(timestamp, &message).or_else(|| oomd_kill(timestamp, &message))
+        {
+            kills.push(kill);
+        }
+    }
+    Ok(kills)
+}
+
+/// `MemoryMax=` of a system unit or slice in MiB, if limited
+fn memory_max_mb(bus: &Bus, unit: &str) -> Option<u64> {
+    let interface = match unit.rsplit_once('.')?.1 {
+        "service" => bus::SERVICE,
+        "scope" => bus::SCOPE,
+        "slice" => bus::SLICE,
+        _ => return None,
+    };
+    let path = bus::unit_path(unit).ok()?;
+    bus.get_property_u64(bus::SYSTEMD, &path, interface, "MemoryMax")
+        .ok()
+        .filter(|&max| max != u64::MAX)
+        .map(system::mib)
+}
+
+/// Kills grouped by owning unit, most killed first
+fn by_unit(kills: &[OomKill]) -> Vec<UnitKills> {
+    let mut units: Vec<UnitKills> = Vec::new();
+    // Newest first, so the first kill seen per unit is its last
+    for kill in kills {
+        let Some(owner) = kill.owner() else {
+            continue;
+        };
+        let index = match units.iter().position(|u| u.unit == owner) {
+            Some(index) => index,
+            None => {
+                units.push(UnitKills {
+                    unit: owner.to_string(),
+                    kills: 0,
+                    limit_kills: 0,
+                    limited_by: None,
+                    memory_max_mb: None,
+                    last_process: kill.process.clone(),
+                });
+                units.len() - 1
+            }
+        };
+        let unit = &mut units[index];
+        unit.kills += 1;
+        if let Some(limit) = &kill.limit_cgroup {
+            unit.limit_kills += 1;
+            if unit.limited_by.is_none() {
+                unit.limited_by = limit.rsplit('/').next().map(str::to_string);
+            }
+        }
+    }
+
+    // Limits inside a user manager are not on the system bus
+    if let Ok(bus) = Bus::system() {
+        for unit in &mut units {
+            let Some(limited_by) = &unit.limited_by else {
+                continue;
+            };
+            let in_user_manager = kills.iter().any(|k| {
+                k.limit_cgroup
+                    .as_deref()
+                    .is_some_and(|c| c.ends_with(limited_by.as_str()) && c.contains("/user@"))
+            });
+            if !in_user_manager {
+                unit.memory_max_mb = memory_max_mb(&bus, limited_by);
+            }
+        }
+    }
+    units.sort_by_key(|u| std::cmp::Reverse(u.kills));
+    units
+}
+
+pub fn diagnose() -> OomDiagnostics {
+    let mut diag = OomDiagnostics::default();
+    match recent_kills() {
+        Ok(kills) => {
+            diag.journal_available = true;
+            diag.units = by_unit(&kills);
+            diag.total_kills = kills.len();
+            diag.kills = kills;
+            diag.kills.truncate(MAX_KILLS);
+        }
+        Err(e) => {
+            diag.warnings
+                .push(format!("Could not read the journal: {}", e));
+            return diag;
+        }
+    }
+
+    if diag.total_kills > 0 {
+        diag.warnings.push(format!(
+            "{} process(es) killed for lack of memory in the last 30 days",
+            diag.total_kills
+        ));
+    }
+    for u in &diag.units {
+        let global_kills = u.kills - u.limit_kills;
+        if let Some(limited_by) = &u.limited_by {
+            let current = u
+                .memory_max_mb
+                .map(|mb| format!(" (now {} MiB)", mb))
+                .unwrap_or_default();
+            diag.recommendations.push(format!(
+                "{} was killed {} time(s) at the memory limit of {}: raise it with `systemctl set-property {} MemoryMax=...`{}",
+                u.unit, u.limit_kills, limited_by, limited_by, current
+            ));
+        }
+        if global_kills >= REPEAT_KILLS {
+            diag.recommendations.push(format!(
+                "{} was OOM-killed {} times: cap it with MemoryHigh=/MemoryMax= so it is throttled before the whole system runs out",
+                u.unit, global_kills
+            ));
+        }
+    }
+    let swap_total = system::meminfo().get("SwapTotal").copied().unwrap_or(0);
+    let global_kill = diag.kills.iter().any(|k| k.limit_cgroup.is_none());
+    if global_kill && swap_total == 0 {
+        diag.recommendations
+            .push("Add zram or a swap file so the kernel can reclaim before killing".to_string());
+    }
+    if !diag.kills.is_empty() && diag.units.is_empty() {
+        diag.recommendations
+            .push("Kills could not be mapped to units; is the system running systemd?".to_string());
+    }
+
+    diag
+}
+
+impl OomDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== OOM Kill Diagnostics ===");
+        if !self.journal_available {
+            println!("- Journal not readable");
+            print_notes(&self.warnings, &self.recommendations);
+            return;
+        }
+        println!(
+            "{} OOM kills (30 days): {}",
+            mark(self.total_kills == 0),
+            self.total_kills
+        );
+        for u in &self.units {
+            println!(
+                "    {} x{}{}",
+                u.unit,
+                u.kills,
+                u.last_process
+                    .as_deref()
+                    .map(|p| format!(", last {}", p))
+                    .unwrap_or_default()
+            );
+        }
+        if verbose {
+            for k in &self.kills {
+                println!(
+                    "    {} {} {}",
+                    k.killer,
+                    k.process.as_deref().unwrap_or("-"),
+                    k.cgroup
+                );
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
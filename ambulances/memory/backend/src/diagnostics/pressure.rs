@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Memory pressure from PSI
+//!
+//! `/proc/pressure/memory` gives the share of time tasks stalled waiting
+//! for memory. `some` means at least one task stalled; `full` means every
+//! runnable task did, which is when the desktop freezes. Free memory alone
+//! hides this: a system can thrash its page cache with gigabytes "free".
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+
+/// `full` avg60 at which the system is thrashing
+pub const FULL_STALL: f64 = 5.0;
+
+/// `some` avg60 at which tasks regularly wait for memory
+pub const SOME_STALL: f64 = 20.0;
+
+/// Available memory below this share of total is about to run out
+const LOW_AVAILABLE_PERCENT: u64 = 5;
+
+/// Stall percentages over the kernel's three windows
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Stall {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PressureDiagnostics {
+    /// `None` on kernels without PSI (or booted with `psi=0`)
+    pub some: Option<Stall>,
+    pub full: Option<Stall>,
+    pub total_mb: u64,
+    pub available_mb: u64,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Parse one `some`/`full` line of a PSI file
+fn stall(psi: &str, kind: &str) -> Option<Stall> {
+    let line = psi.lines().find(|l| l.starts_with(kind))?;
+    let value = |key: &str| {
+        line.split_whitespace()
+            .find_map(|f| f.strip_prefix(key)?.strip_prefix('='))
+            .and_then(|v| v.parse().ok())
+    };
+    Some(Stall {
+        avg10: value("avg10")?,
+        avg60: value("avg60")?,
+        avg300: value("avg300")?,
+    })
+}
+
+pub fn diagnose() -> PressureDiagnostics {
+    let psi = system::read(host::path("/proc/pressure/memory")).unwrap_or_default();
+    let meminfo = system::meminfo();
+    let kb = |key: &str| meminfo.get(key).copied().unwrap_or(0);
+    let mut diag = PressureDiagnostics {
+        some: stall(&psi, "some"),
+        full: stall(&psi, "full"),
+        total_mb: kb("MemTotal") / 1024,
+        available_mb: kb("MemAvailable") / 1024,
+        ..PressureDiagnostics::default()
+    };
+
+    match (&diag.some, &diag.full) {
+        (_, Some(full)) if full.avg60 >= FULL_STALL => {
+            diag.warnings.push(format!(
+                "System is thrashing: all tasks stalled on memory {:.1}% of the last minute",
+                full.avg60
+            ));
+            diag.recommendations.push(
+                "Close memory-heavy programs now, then add swap or zram (see the swap section)"
+                    .to_string(),
+            );
+        }
+        (Some(some), _) if some.avg60 >= SOME_STALL => {
+            diag.warnings.push(format!(
+                "Tasks waited on memory {:.1}% of the last minute",
+                some.avg60
+            ));
+        }
+        (None, None) => diag
+            .recommendations
+            .push("PSI is unavailable; boot with psi=1 to measure memory pressure".to_string()),
+        _ => {}
+    }
+    if diag.total_mb > 0 && diag.available_mb * 100 < diag.total_mb * LOW_AVAILABLE_PERCENT {
+        diag.warnings.push(format!(
+            "Only {} MiB of {} MiB available",
+            diag.available_mb, diag.total_mb
+        ));
+    }
+
+    diag
+}
+
+impl PressureDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Memory Pressure Diagnostics ===");
+        println!(
+            "- Memory: {} MiB available of {} MiB",
+            self.available_mb, self.total_mb
+        );
+        for (label, stall, limit) in [
+            ("some", &self.some, SOME_STALL),
+            ("full", &self.full, FULL_STALL),
+        ] {
+            match stall {
+                Some(s) if verbose => println!(
+                    "{} Stall ({}): {:.2}% / {:.2}% / {:.2}% (10s / 60s / 300s)",
+                    mark(s.avg60 < limit),
+                    label,
+                    s.avg10,
+                    s.avg60,
+                    s.avg300
+                ),
+                Some(s) => println!(
+                    "{} Stall ({}): {:.1}% of the last minute",
+                    mark(s.avg60 < limit),
+                    label,
+                    s.avg60
+                ),
+                None => println!("- Stall ({}): unknown", label),
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
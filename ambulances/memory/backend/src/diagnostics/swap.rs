@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Swap and zram configuration
+//!
+//! With no swap at all, anonymous memory cannot be reclaimed and the OOM
+//! killer arrives early. zram gives compressed swap in RAM that is much
+//! faster than disk, but only helps if it is used before disk swap: it
+//! needs the higher priority.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::Path;
+
+/// Swap use above this percentage leaves no headroom
+const FULL_PERCENT: u64 = 80;
+
+/// RAM size below which running without swap is a problem
+const SMALL_RAM_MB: u64 = 16 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapDevice {
+    pub name: String,
+    /// `partition` or `file`
+    pub kind: String,
+    pub size_mb: u64,
+    pub used_mb: u64,
+    pub priority: i32,
+    pub zram: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ZramDevice {
+    pub name: String,
+    pub disksize_mb: u64,
+    /// Selected compressor, e.g. `zstd`
+    pub algorithm: Option<String>,
+    /// Data stored, before and after compression
+    pub stored_mb: u64,
+    pub compressed_mb: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SwapDiagnostics {
+    pub devices: Vec<SwapDevice>,
+    pub zram: Vec<ZramDevice>,
+    pub swappiness: Option<u32>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Active swap areas from `/proc/swaps`
+fn swaps() -> Vec<SwapDevice> {
+    system::read(host::path("/proc/swaps"))
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [name, kind, size, used, priority] = fields[..] else {
+                return None;
+            };
+            Some(SwapDevice {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                size_mb: size.parse::<u64>().ok()? / 1024,
+                used_mb: used.parse::<u64>().ok()? / 1024,
+                priority: priority.parse().ok()?,
+                zram: name.starts_with("/dev/zram"),
+            })
+        })
+        .collect()
+}
+
+/// Configured zram devices, swap or not
+fn zram_devices() -> Vec<ZramDevice> {
+    let Ok(entries) = std::fs::read_dir(host::path("/sys/block")) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<ZramDevice> = entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("zram"))
+        .filter_map(|e| zram_device(&e.path()))
+        .filter(|z| z.disksize_mb > 0)
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+fn zram_device(dir: &Path) -> Option<ZramDevice> {
+    let disksize: u64 = system::read(dir.join("disksize"))?.parse().ok()?;
+    let mm_stat: Vec<u64> = system::read(dir.join("mm_stat"))
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|f| f.parse().ok())
+        .collect();
+    let algorithm = system::read(dir.join("comp_algorithm")).and_then(|algs| {
+        algs.split_whitespace()
+            .find(|a| a.starts_with('['))
+            .map(|a| a.trim_start_matches('[').trim_end_matches(']').to_string())
+    });
+    Some(ZramDevice {
+        name: dir.file_name()?.to_string_lossy().into_owned(),
+        disksize_mb: system::mib(disksize),
+        algorithm,
+        stored_mb: system::mib(mm_stat.first().copied().unwrap_or(0)),
+        compressed_mb: system::mib(mm_stat.get(1).copied().unwrap_or(0)),
+    })
+}
+
+pub fn diagnose() -> SwapDiagnostics {
+    let mut diag = SwapDiagnostics {
+        devices: swaps(),
+        zram: zram_devices(),
+        swappiness: system::read(host::path("/proc/sys/vm/swappiness"))
+            .and_then(|s| s.parse().ok()),
+        ..SwapDiagnostics::default()
+    };
+    let total_ram_mb = system::meminfo().get("MemTotal").copied().unwrap_or(0) / 1024;
+
+    if diag.devices.is_empty() && total_ram_mb < SMALL_RAM_MB {
+        diag.warnings.push(format!(
+            "No swap with {} MiB of RAM; the OOM killer runs as soon as RAM fills",
+            total_ram_mb
+        ));
+        diag.recommendations.push(
+            "Enable zram swap: install zram-generator with `zram-size = ram / 2`".to_string(),
+        );
+    }
+
+    let size: u64 = diag.devices.iter().map(|d| d.size_mb).sum();
+    let used: u64 = diag.devices.iter().map(|d| d.used_mb).sum();
+    if size > 0 && used * 100 > size * FULL_PERCENT {
+        diag.warnings.push(format!(
+            "Swap is {}% full ({} of {} MiB)",
+            used * 100 / size,
+            used,
+            size
+        ));
+        diag.recommendations
+            .push("Add swap space, or find the unit using it in the OOM section".to_string());
+    }
+
+    let zram_priority = diag
+        .devices
+        .iter()
+        .filter(|d| d.zram)
+        .map(|d| d.priority)
+        .min();
+    if let Some(zram_priority) = zram_priority {
+        let ahead: Vec<_> = diag
+            .devices
+            .iter()
+            .filter(|d| !d.zram && d.priority >= zram_priority)
+            .collect();
+        for disk in &ahead {
+            diag.warnings.push(format!(
+                "{} (priority {}) is used before zram (priority {})",
+                disk.name, disk.priority, zram_priority
+            ));
+        }
+        if !ahead.is_empty() {
+            diag.recommendations.push(
+                "Give zram the higher priority, e.g. `swap-priority = 100` in zram-generator.conf"
+                    .to_string(),
+            );
+        }
+        if diag.swappiness.is_some_and(|s| s < 100) {
+            diag.recommendations.push(
+                "Raise vm.swappiness to 180 so the kernel prefers cheap zram swap over dropping cache"
+                    .to_string(),
+            );
+        }
+    }
+
+    diag
+}
+
+impl SwapDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Swap Diagnostics ===");
+        if self.devices.is_empty() {
+            println!("- Swap: none");
+        }
+        for d in &self.devices {
+            println!(
+                "{} {}: {} of {} MiB used, priority {}",
+                mark(d.size_mb == 0 || d.used_mb * 100 <= d.size_mb * FULL_PERCENT),
+                d.name,
+                d.used_mb,
+                d.size_mb,
+                d.priority
+            );
+        }
+        if verbose {
+            for z in &self.zram {
+                println!(
+                    "- {}: {} MiB, {}, {} MiB stored in {} MiB",
+                    z.name,
+                    z.disksize_mb,
+                    z.algorithm.as_deref().unwrap_or("unknown compressor"),
+                    z.stored_mb,
+                    z.compressed_mb
+                );
+            }
+            if let Some(s) = self.swappiness {
+                println!("- Swappiness: {}", s);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
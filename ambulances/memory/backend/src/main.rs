@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Memory Ambulance backend
+//!
+//! Reports memory pressure, the swap and zram setup, and which units the
+//! OOM killer and systemd-oomd have been killing, with swap and
+//! `MemoryMax=` recommendations. It changes nothing.
+//! `--json` output follows the network ambulance's report model.
+
+mod diagnostics;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Error};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Memory Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: memory-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all memory diagnostics");
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Memory Ambulance");
+    println!("================\n");
+    result.pressure.print(verbose);
+    result.swap.print(verbose);
+    result.oom.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Pressure", &result.pressure.warnings),
+        ("Swap", &result.swap.warnings),
+        ("OOM kills", &result.oom.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    exit::findings(result.has_warnings())
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Memory Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`. This ambulance only
+//! recommends; swap and unit limits are left to the user.
+
+use crate::diagnostics::{
+    oom::OomDiagnostics, pressure::PressureDiagnostics, swap::SwapDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes};
+
+pub const TOOL: &str = "memory-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub pressure: PressureDiagnostics,
+    pub swap: SwapDiagnostics,
+    pub oom: OomDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.pressure.warnings,
+            &self.swap.warnings,
+            &self.oom.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
@@ -0,0 +1,30 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thin helpers over procfs and sysfs
+
+use ambulance_core::host;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A procfs or sysfs file, trimmed; `None` if absent or unreadable
+pub fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// `/proc/meminfo` in kB, keyed by field name
+pub fn meminfo() -> HashMap<String, u64> {
+    read(host::path("/proc/meminfo"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let kb = value.split_whitespace().next()?.parse().ok()?;
+            Some((key.to_string(), kb))
+        })
+        .collect()
+}
+
+pub fn mib(bytes: u64) -> u64 {
+    bytes / (1024 * 1024)
+}
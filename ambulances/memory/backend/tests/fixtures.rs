@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The memory checks against broken fake systems
+
+use ambulance_testkit::{CheckExt, Entry, ReportExt, System, ANY_PATH};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_memory-ambulance");
+
+#[test]
+fn a_thrashing_machine_without_swap_is_warned_about() {
+    let mut system = System::new();
+    system.meminfo(4096, 100).memory_pressure(40.0, 12.0);
+    let report = system.diagnose(BINARY);
+
+    let pressure = report.check("pressure").summaries();
+    assert!(pressure
+        .iter()
+        .any(|s| s.starts_with("System is thrashing")));
+    assert!(pressure.contains(&"Only 100 MiB of 4096 MiB available"));
+    let swap = report.check("swap").summaries();
+    assert!(swap.iter().any(|s| s.starts_with("No swap with 4096 MiB")));
+}
+
+#[test]
+fn a_healthy_machine_passes() {
+    let mut system = System::new();
+    system
+        .meminfo_with_swap(16384, 12000, 8192, 8192)
+        .swap("/dev/zram0", 8192, 0, 100)
+        .memory_pressure(0.0, 0.0);
+    let report = system.diagnose(BINARY);
+
+    for id in ["pressure", "swap", "oom"] {
+        assert!(report.check(id).summaries().is_empty(), "{:?}", report);
+    }
+}
+
+#[test]
+fn kills_at_a_unit_limit_name_the_limit() {
+    let mut system = System::new();
+    system.meminfo(8192, 4096).memory_pressure(0.0, 0.0);
+    for pid in [123, 456] {
+        system.journal(
+            Entry::new(&format!(
+                "oom-kill:constraint=CONSTRAINT_MEMCG,nodemask=(null),cpuset=/,mems_allowed=0,\
+                 oom_memcg=/system.slice/foo.service,task_memcg=/system.slice/foo.service,task=foo,pid={},uid=0",
+                pid
+            ))
+            .kernel()
+            .priority(3)
+            .ago(3600),
+        );
+    }
+    system.bus().property(
+        ANY_PATH,
+        "org.freedesktop.systemd1.Service",
+        "MemoryMax",
+        256u64 << 20,
+    );
+    let report = system.diagnose(BINARY);
+
+    let oom = report.check("oom");
+    assert_eq!(
+        oom.summaries(),
+        ["2 process(es) killed for lack of memory in the last 30 days"]
+    );
+    let advice: Vec<&str> = oom
+        .recommendations
+        .iter()
+        .map(|r| r.text.as_str())
+        .collect();
+    assert!(
+        advice.iter().any(|r| r
+            .starts_with("foo.service was killed 2 time(s) at the memory limit of foo.service")
+            && r.ends_with("(now 256 MiB)")),
+        "{:?}",
+        advice
+    );
+    let calls = system.bus().calls();
+    assert!(calls
+        .iter()
+        .any(|c| c.member == "Get" && c.path == "/org/freedesktop/systemd1/unit/foo_2eservice"));
+}
@@ -0,0 +1,471 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Network configuration snapshots
+//!
+//! A [`Snapshot`] is one JSON document holding everything that decides
+//! how a Linux machine joins its network: the interfaces and routes as
+//! they are, the resolver files, the NetworkManager, systemd-networkd
+//! and netplan profiles, the firewall's files and its live ruleset. It
+//! is written before a repair so the repair can be undone, and can be
+//! carried to another machine to give it a working configuration.
+//!
+//! Interfaces and routes are recorded to compare against, not restored:
+//! they follow from the profiles, which are. See [`restore`] for what a
+//! restore changes and [`Snapshot::for_clone`] for what a clone leaves
+//! out.
+//!
+//! Profiles hold Wi-Fi passwords and VPN keys, so snapshots are saved
+//! readable by their owner only.
+
+pub mod restore;
+
+pub use restore::{Action, Change, Plan, Restored};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Newest snapshot format this reader understands
+pub const FORMAT: u32 = 1;
+
+/// Where snapshots are kept
+pub const DIRECTORY: &str = "/var/lib/system-tools/network-snapshots";
+
+/// Single files captured, relative to the root
+const FILES: &[(Kind, &str)] = &[
+    (Kind::Resolver, "etc/resolv.conf"),
+    (Kind::Resolver, "etc/hosts"),
+    (Kind::Resolver, "etc/systemd/resolved.conf"),
+    (Kind::Profile, "etc/NetworkManager/NetworkManager.conf"),
+    (Kind::Firewall, "etc/nftables.conf"),
+];
+
+/// Directories captured whole, without descending
+const DIRECTORIES: &[(Kind, &str)] = &[
+    (Kind::Resolver, "etc/systemd/resolved.conf.d"),
+    (Kind::Profile, "etc/NetworkManager/system-connections"),
+    (Kind::Profile, "etc/NetworkManager/conf.d"),
+    (Kind::Profile, "etc/systemd/network"),
+    (Kind::Profile, "etc/netplan"),
+    (Kind::Firewall, "etc/firewalld/zones"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Resolver,
+    Profile,
+    Firewall,
+}
+
+/// A configuration file as it was captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct File {
+    pub kind: Kind,
+    /// Relative to the root, e.g. `etc/resolv.conf`
+    pub path: String,
+    pub mode: u32,
+    /// Where the file pointed, when it was a symlink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contents: Option<String>,
+    /// Why neither of the above was captured; restore leaves such a file be
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interface {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u64>,
+    pub state: String,
+    /// `address/prefix`, IPv4 and IPv6
+    pub addresses: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    /// `default` or `network/prefix`
+    pub destination: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<u64>,
+}
+
+/// The firewall's live rules, as its save command prints them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ruleset {
+    /// `nft` or `iptables`
+    pub tool: String,
+    pub rules: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub format: u32,
+    /// Unix seconds
+    pub captured_at: u64,
+    pub hostname: String,
+    pub interfaces: Vec<Interface>,
+    pub routes: Vec<Route>,
+    pub files: Vec<File>,
+    /// Captured directories whose every entry is in `files`; restore
+    /// removes what has appeared in them since
+    #[serde(default)]
+    pub directories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firewall: Option<Ruleset>,
+}
+
+/// The commands a snapshot reads the live network with, and a restore
+/// reloads it with; tests stand in for the machine here
+pub trait Host {
+    /// Run `program` with `input` on stdin and return its stdout, or why
+    /// it failed
+    fn run(&self, program: &str, args: &[&str], input: Option<&str>) -> Result<String, String>;
+}
+
+/// The machine this runs on
+pub struct Live;
+
+impl Host for Live {
+    fn run(&self, program: &str, args: &[&str], input: Option<&str>) -> Result<String, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin
+                .write_all(input.as_bytes())
+                .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// `ip -j address` as interfaces
+pub fn parse_interfaces(json: &str) -> Vec<Interface> {
+    let list: Vec<Value> = serde_json::from_str(json).unwrap_or_default();
+    list.iter()
+        .filter_map(|i| {
+            let addresses = i["addr_info"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|a| Some(format!("{}/{}", a["local"].as_str()?, a["prefixlen"])))
+                .collect();
+            Some(Interface {
+                name: i["ifname"].as_str()?.to_string(),
+                mac: i["address"].as_str().map(str::to_string),
+                mtu: i["mtu"].as_u64(),
+                state: i["operstate"].as_str().unwrap_or("UNKNOWN").to_string(),
+                addresses,
+            })
+        })
+        .collect()
+}
+
+/// `ip -j route` as routes
+pub fn parse_routes(json: &str) -> Vec<Route> {
+    let list: Vec<Value> = serde_json::from_str(json).unwrap_or_default();
+    list.iter()
+        .filter_map(|r| {
+            Some(Route {
+                destination: r["dst"].as_str()?.to_string(),
+                gateway: r["gateway"].as_str().map(str::to_string),
+                device: r["dev"].as_str().map(str::to_string),
+                metric: r["metric"].as_u64(),
+            })
+        })
+        .collect()
+}
+
+fn capture_file(root: &Path, kind: Kind, path: &str) -> Option<File> {
+    let full = root.join(path);
+    let meta = std::fs::symlink_metadata(&full).ok()?;
+    let mut file = File {
+        kind,
+        path: path.to_string(),
+        mode: mode(&meta),
+        link: None,
+        contents: None,
+        error: None,
+    };
+    if meta.file_type().is_symlink() {
+        match std::fs::read_link(&full) {
+            Ok(target) => file.link = Some(target.to_string_lossy().into_owned()),
+            Err(e) => file.error = Some(e.to_string()),
+        }
+    } else if meta.is_file() {
+        match std::fs::read(&full).map(String::from_utf8) {
+            Ok(Ok(text)) => file.contents = Some(text),
+            Ok(Err(_)) => file.error = Some("not text".to_string()),
+            Err(e) => file.error = Some(e.to_string()),
+        }
+    } else {
+        return None;
+    }
+    Some(file)
+}
+
+/// Whether `capture` could have taken `path`: one of `FILES`, or an entry
+/// directly inside one of `DIRECTORIES`
+fn covered(path: &str) -> bool {
+    FILES.iter().any(|(_, known)| *known == path)
+        || DIRECTORIES.iter().any(|(_, dir)| {
+            path.strip_prefix(dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|name| !matches!(name, "" | "." | "..") && !name.contains('/'))
+        })
+}
+
+#[cfg(unix)]
+fn mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn mode(_meta: &std::fs::Metadata) -> u32 {
+    0o644
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl Snapshot {
+    /// The configuration under `root`, and the live state `host` reports
+    pub fn capture(root: &Path, host: &dyn Host) -> Snapshot {
+        let mut files = Vec::new();
+        let mut directories = Vec::new();
+        for (kind, path) in FILES {
+            files.extend(capture_file(root, *kind, path));
+        }
+        for (kind, dir) in DIRECTORIES {
+            let Ok(entries) = std::fs::read_dir(root.join(dir)) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            for name in names {
+                files.extend(capture_file(root, *kind, &format!("{}/{}", dir, name)));
+            }
+            directories.push(dir.to_string());
+        }
+
+        let run = |program: &str, args: &[&str]| host.run(program, args, None).ok();
+        let mut routes = Vec::new();
+        for family in ["-4", "-6"] {
+            if let Some(json) = run("ip", &["-j", family, "route", "show"]) {
+                routes.extend(parse_routes(&json));
+            }
+        }
+        let firewall = run("nft", &["list", "ruleset"])
+            .map(|rules| Ruleset {
+                tool: "nft".to_string(),
+                rules,
+            })
+            .or_else(|| {
+                run("iptables-save", &[]).map(|rules| Ruleset {
+                    tool: "iptables".to_string(),
+                    rules,
+                })
+            });
+        Snapshot {
+            format: FORMAT,
+            captured_at: now(),
+            hostname: std::fs::read_to_string(root.join("etc/hostname"))
+                .map(|h| h.trim().to_string())
+                .unwrap_or_default(),
+            interfaces: run("ip", &["-j", "address", "show"])
+                .map(|json| parse_interfaces(&json))
+                .unwrap_or_default(),
+            routes,
+            files,
+            directories,
+            firewall,
+        }
+    }
+
+    /// Files that could not be read, with why; run as root to have them
+    pub fn unreadable(&self) -> impl Iterator<Item = &File> {
+        self.files.iter().filter(|f| f.error.is_some())
+    }
+
+    /// The snapshot for another machine: without the hosts file, which
+    /// names this one, without the lines binding profiles to this
+    /// machine's interface names and MAC addresses, and without removing
+    /// the other machine's own profiles
+    pub fn for_clone(&self) -> Snapshot {
+        let mut clone = self.clone();
+        clone.files.retain(|f| f.path != "etc/hosts");
+        for file in &mut clone.files {
+            let keys: &[&str] = if file.path.ends_with(".nmconnection") {
+                &["mac-address=", "interface-name="]
+            } else if file.path.starts_with("etc/systemd/network/") {
+                &["MACAddress="]
+            } else {
+                continue;
+            };
+            if let Some(contents) = &mut file.contents {
+                *contents = contents
+                    .lines()
+                    .filter(|l| !keys.iter().any(|k| l.trim_start().starts_with(k)))
+                    .map(|l| format!("{}\n", l))
+                    .collect();
+            }
+        }
+        clone.directories.clear();
+        clone
+    }
+
+    /// How the live state in `other` differs from this one's, as lines
+    /// such as `wlan0: address 192.168.1.20/24 missing`
+    pub fn differences(&self, other: &Snapshot) -> Vec<String> {
+        let mut lines = Vec::new();
+        for iface in &self.interfaces {
+            let Some(now) = other.interfaces.iter().find(|i| i.name == iface.name) else {
+                lines.push(format!("{}: interface missing", iface.name));
+                continue;
+            };
+            if now.state != iface.state {
+                lines.push(format!(
+                    "{}: {} instead of {}",
+                    iface.name, now.state, iface.state
+                ));
+            }
+            for address in &iface.addresses {
+                if !now.addresses.contains(address) {
+                    lines.push(format!("{}: address {} missing", iface.name, address));
+                }
+            }
+        }
+        for route in &self.routes {
+            if !other.routes.contains(route) {
+                let via = route
+                    .gateway
+                    .as_ref()
+                    .map(|g| format!(" via {}", g))
+                    .unwrap_or_default();
+                lines.push(format!("route {}{} missing", route.destination, via));
+            }
+        }
+        lines
+    }
+
+    /// Read a snapshot, refusing one that names files outside what
+    /// `capture` takes: a restore runs as root and writes wherever the
+    /// snapshot says, and it may have come from another machine
+    pub fn load(path: &Path) -> Result<Snapshot, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut snapshot: Snapshot = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        if snapshot.format > FORMAT {
+            return Err(format!(
+                "{} uses snapshot format {}, this reader knows up to {}",
+                path.display(),
+                snapshot.format,
+                FORMAT
+            ));
+        }
+        if let Some(dir) = snapshot
+            .directories
+            .iter()
+            .find(|d| !DIRECTORIES.iter().any(|(_, known)| known == d))
+        {
+            return Err(format!(
+                "{} lists the directory {}, which snapshots do not cover",
+                path.display(),
+                dir
+            ));
+        }
+        if let Some(file) = snapshot.files.iter().find(|f| !covered(&f.path)) {
+            return Err(format!(
+                "{} holds the file {}, which snapshots do not cover",
+                path.display(),
+                file.path
+            ));
+        }
+        // No setuid, setgid or sticky bits, whatever the file says
+        for file in &mut snapshot.files {
+            file.mode &= 0o777;
+        }
+        Ok(snapshot)
+    }
+
+    /// Write the snapshot to `path`, readable by its owner only
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(path)
+            .and_then(|mut f| f.write_all(text.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Save into `dir` under the capture time, and return the path
+    pub fn store(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = dir.join(format!("{}.json", self.captured_at));
+        self.save(&path)?;
+        Ok(path)
+    }
+}
+
+/// The snapshots in `dir`, oldest first; files that do not parse are skipped
+pub fn list(dir: &Path) -> Vec<(PathBuf, Snapshot)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<(PathBuf, Snapshot)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .filter_map(|p| Snapshot::load(&p).ok().map(|s| (p, s)))
+        .collect();
+    snapshots.sort_by_key(|(_, s)| s.captured_at);
+    snapshots
+}
@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Network Snapshot
+//!
+//! Saves the network configuration before a repair and puts it back
+//! after one that made things worse, or restores a snapshot taken on a
+//! working machine onto a broken one with `--clone`. The network app runs
+//! it, elevated, around its repairs.
+
+use ambulance_core::error::{exit, Category, Error};
+use network_snapshot::{Action, Live, Snapshot, DIRECTORY};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const TOOL: &str = "network-snapshot";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+fn print_help() {
+    println!("Network Snapshot v{}", VERSION);
+    println!();
+    println!("Usage: network-snapshot <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  capture              Save the current network configuration");
+    println!("  list                 List the saved snapshots");
+    println!("  diff <file>          Show what restoring a snapshot would change");
+    println!("  restore <file>       Restore a snapshot, saving the current one first");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -j, --json           JSON output format");
+    println!(
+        "  -o, --output <file>  Where capture saves (default {})",
+        DIRECTORY
+    );
+    println!("  --clone              Restore another machine's snapshot: keep this");
+    println!("                       machine's hosts file, interface names and profiles");
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(TOOL, VERSION, json)
+}
+
+fn failed(message: String) -> Error {
+    Error::new("failed.snapshot", Category::Failed, message)
+}
+
+fn print_json(value: serde_json::Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    );
+}
+
+fn capture(output: Option<&str>) -> Result<(PathBuf, Snapshot), Error> {
+    let snapshot = Snapshot::capture(Path::new("/"), &Live);
+    let path = match output {
+        Some(path) => {
+            snapshot.save(Path::new(path)).map_err(failed)?;
+            PathBuf::from(path)
+        }
+        None => snapshot.store(Path::new(DIRECTORY)).map_err(failed)?,
+    };
+    Ok((path, snapshot))
+}
+
+fn run_capture(output: Option<&str>, json: bool) -> ExitCode {
+    let (path, snapshot) = match capture(output) {
+        Ok(saved) => saved,
+        Err(e) => return fail(e, json),
+    };
+    let unreadable: Vec<&str> = snapshot.unreadable().map(|f| f.path.as_str()).collect();
+    if json {
+        print_json(json!({
+            "version": VERSION,
+            "tool": TOOL,
+            "path": path,
+            "files": snapshot.files.len(),
+            "unreadable": unreadable,
+        }));
+    } else {
+        println!(
+            "Saved {} files and the live state to {}",
+            snapshot.files.len(),
+            path.display()
+        );
+        for file in snapshot.unreadable() {
+            println!(
+                "  ⚠ {} not captured: {}",
+                file.path,
+                file.error.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_list(json: bool) -> ExitCode {
+    let snapshots = network_snapshot::list(Path::new(DIRECTORY));
+    if json {
+        let list: Vec<_> = snapshots
+            .iter()
+            .map(|(path, s)| {
+                json!({
+                    "path": path,
+                    "captured_at": s.captured_at,
+                    "hostname": s.hostname,
+                    "files": s.files.len(),
+                })
+            })
+            .collect();
+        print_json(json!({"version": VERSION, "tool": TOOL, "snapshots": list}));
+        return ExitCode::SUCCESS;
+    }
+    if snapshots.is_empty() {
+        println!("No snapshots in {}", DIRECTORY);
+    }
+    for (path, s) in &snapshots {
+        println!(
+            "{}  {:<16} {} files",
+            path.display(),
+            s.hostname,
+            s.files.len()
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// The snapshot at `path`, made fit for this machine with `clone`
+fn load(path: &str, clone: bool) -> Result<Snapshot, Error> {
+    let snapshot = Snapshot::load(Path::new(path)).map_err(|e| {
+        Error::new("usage.bad-snapshot", Category::Usage, e)
+            .with_hint("Run 'network-snapshot list' to see the saved snapshots")
+    })?;
+    Ok(if clone {
+        snapshot.for_clone()
+    } else {
+        snapshot
+    })
+}
+
+fn label(action: Action) -> &'static str {
+    match action {
+        Action::Create => "create",
+        Action::Replace => "replace",
+        Action::Relink => "relink",
+        Action::Remove => "remove",
+    }
+}
+
+fn run_diff(path: &str, clone: bool, json: bool) -> ExitCode {
+    let snapshot = match load(path, clone) {
+        Ok(snapshot) => snapshot,
+        Err(e) => return fail(e, json),
+    };
+    let root = Path::new("/");
+    let plan = snapshot.plan(root, &Live);
+    let differences = snapshot.differences(&Snapshot::capture(root, &Live));
+    if json {
+        print_json(json!({
+            "version": VERSION,
+            "tool": TOOL,
+            "plan": plan,
+            "differences": differences,
+        }));
+        return ExitCode::SUCCESS;
+    }
+    if plan.is_empty() {
+        println!("The configuration matches the snapshot");
+    }
+    for change in &plan.changes {
+        println!("  {:<8} /{}", label(change.action), change.path);
+    }
+    if plan.firewall {
+        println!("  {:<8} firewall ruleset", "reload");
+    }
+    if !differences.is_empty() {
+        println!();
+        println!("Live state unlike the snapshot's:");
+        for line in &differences {
+            println!("  {}", line);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn run_restore(path: &str, clone: bool, json: bool) -> ExitCode {
+    if let Err(e) = ambulance_privilege::require("network-snapshot restore") {
+        return fail(e, json);
+    }
+    let snapshot = match load(path, clone) {
+        Ok(snapshot) => snapshot,
+        Err(e) => return fail(e, json),
+    };
+    // The configuration being replaced, so the restore can be undone too
+    let undo = match capture(None) {
+        Ok((undo, _)) => undo,
+        Err(e) => return fail(e, json),
+    };
+    let restored = snapshot.restore(Path::new("/"), &Live);
+
+    if json {
+        print_json(json!({
+            "version": VERSION,
+            "tool": TOOL,
+            "success": restored.succeeded(),
+            "undo": undo,
+            "restored": restored,
+        }));
+    } else {
+        println!("Network Snapshot - Restore");
+        println!("==========================\n");
+        for change in &restored.changes {
+            println!("  ✓ {:<8} /{}", label(change.action), change.path);
+        }
+        if restored.firewall {
+            println!("  ✓ reloaded the firewall ruleset");
+        }
+        for command in &restored.reloaded {
+            println!("  ✓ ran {}", command);
+        }
+        for error in &restored.errors {
+            println!("  ✗ {}", error);
+        }
+        println!("\nTo undo: network-snapshot restore {}", undo.display());
+    }
+
+    if restored.succeeded() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(exit::FAILED)
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let clone = args.iter().any(|a| a == "--clone");
+    let output = args.iter().position(|a| a == "-o" || a == "--output");
+    let positional: Vec<&str> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with('-') && output.map_or(true, |o| *i != o + 1))
+        .map(|(_, a)| a.as_str())
+        .collect();
+
+    let file = |command: &str| {
+        positional.get(1).copied().ok_or_else(|| {
+            Error::new(
+                "usage.missing-snapshot",
+                Category::Usage,
+                format!("{} needs a snapshot file", command),
+            )
+            .with_hint(format!("Usage: {} {} <file>", TOOL, command))
+        })
+    };
+    match positional.first().copied() {
+        Some("capture") => match output.map(|o| args.get(o + 1)) {
+            Some(None) => {
+                let e = Error::new("usage.bad-option", Category::Usage, "--output needs a file");
+                fail(e, json)
+            }
+            Some(Some(path)) => run_capture(Some(path), json),
+            None => run_capture(None, json),
+        },
+        Some("list") => run_list(json),
+        Some("diff") => match file("diff") {
+            Ok(path) => run_diff(path, clone, json),
+            Err(e) => fail(e, json),
+        },
+        Some("restore") => match file("restore") {
+            Ok(path) => run_restore(path, clone, json),
+            Err(e) => fail(e, json),
+        },
+        Some("version") => {
+            println!("Network Snapshot v{}", VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, TOOL), json),
+    }
+}
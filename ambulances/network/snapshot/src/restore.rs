@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Putting a snapshot back
+//!
+//! [`Snapshot::plan`] compares a snapshot with the files under a root:
+//! each captured file is created, replaced or relinked where it differs,
+//! in contents, mode or symlink target, and a file that has appeared
+//! since in a directory captured whole is removed. A file whose capture
+//! failed is left as it is. [`Snapshot::restore`] carries the plan out,
+//! reloads the services whose files changed, then loads the captured
+//! firewall ruleset if the live one still differs.
+
+use crate::{Host, Ruleset, Snapshot};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// What reloads the files under each prefix
+const RELOADS: &[(&str, &str, &[&str])] = &[
+    ("etc/NetworkManager/", "nmcli", &["connection", "reload"]),
+    ("etc/systemd/network/", "networkctl", &["reload"]),
+    ("etc/netplan/", "netplan", &["apply"]),
+    (
+        "etc/systemd/resolved.conf",
+        "systemctl",
+        &["try-restart", "systemd-resolved"],
+    ),
+    ("etc/firewalld/", "firewall-cmd", &["--reload"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Create,
+    Replace,
+    Relink,
+    Remove,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    pub path: String,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    pub changes: Vec<Change>,
+    /// The live firewall ruleset differs from the captured one
+    pub firewall: bool,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && !self.firewall
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Restored {
+    /// The changes made; failed ones are in `errors` instead
+    pub changes: Vec<Change>,
+    pub firewall: bool,
+    /// The reload commands run
+    pub reloaded: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl Restored {
+    pub fn succeeded(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+fn firewall_differs(ruleset: &Ruleset, host: &dyn Host) -> bool {
+    let live = match ruleset.tool.as_str() {
+        "nft" => host.run("nft", &["list", "ruleset"], None),
+        _ => host.run("iptables-save", &[], None),
+    };
+    // iptables-save stamps its output with the time; compare the rules
+    let rules = |text: &str| -> Vec<String> {
+        text.lines()
+            .filter(|l| !l.starts_with('#'))
+            .map(str::to_string)
+            .collect()
+    };
+    live.map_or(true, |live| rules(&live) != rules(&ruleset.rules))
+}
+
+fn load_firewall(ruleset: &Ruleset, host: &dyn Host) -> Result<(), String> {
+    match ruleset.tool.as_str() {
+        "nft" => {
+            let input = format!("flush ruleset\n{}", ruleset.rules);
+            host.run("nft", &["-f", "-"], Some(&input)).map(drop)
+        }
+        _ => host
+            .run("iptables-restore", &[], Some(&ruleset.rules))
+            .map(drop),
+    }
+}
+
+#[cfg(unix)]
+fn same_mode(path: &Path, mode: u32) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o7777 == mode)
+}
+
+#[cfg(not(unix))]
+fn same_mode(_path: &Path, _mode: u32) -> bool {
+    true
+}
+
+/// Replace `path` with `contents` through a file beside it, so a reader
+/// never sees half of it
+fn write(path: &Path, contents: &str, mode: u32) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.restore", name));
+    std::fs::write(&tmp, contents)
+        .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(mode))
+            .map_err(|e| format!("Failed to set the mode of {}: {}", tmp.display(), e))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+#[cfg(unix)]
+fn relink(path: &Path, target: &str) -> Result<(), String> {
+    if std::fs::symlink_metadata(path).is_ok() {
+        std::fs::remove_file(path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    std::os::unix::fs::symlink(target, path)
+        .map_err(|e| format!("Failed to link {} to {}: {}", path.display(), target, e))
+}
+
+#[cfg(not(unix))]
+fn relink(path: &Path, _target: &str) -> Result<(), String> {
+    Err(format!(
+        "Cannot recreate the symlink {} here",
+        path.display()
+    ))
+}
+
+impl Snapshot {
+    /// What restoring onto `root` would change
+    pub fn plan(&self, root: &Path, host: &dyn Host) -> Plan {
+        let mut changes = Vec::new();
+        let mut change = |path: &str, action| {
+            changes.push(Change {
+                path: path.to_string(),
+                action,
+            })
+        };
+        for file in &self.files {
+            let full = root.join(&file.path);
+            let meta = std::fs::symlink_metadata(&full).ok();
+            if let Some(target) = &file.link {
+                let current = std::fs::read_link(&full).ok();
+                if current.as_deref() != Some(Path::new(target)) {
+                    change(&file.path, Action::Relink);
+                }
+            } else if let Some(contents) = &file.contents {
+                match meta {
+                    None => change(&file.path, Action::Create),
+                    Some(m) if m.file_type().is_symlink() => change(&file.path, Action::Replace),
+                    Some(_) => {
+                        let current = std::fs::read_to_string(&full).ok();
+                        if current.as_ref() != Some(contents) || !same_mode(&full, file.mode) {
+                            change(&file.path, Action::Replace);
+                        }
+                    }
+                }
+            }
+        }
+        for dir in &self.directories {
+            let Ok(entries) = std::fs::read_dir(root.join(dir)) else {
+                continue;
+            };
+            let mut strays: Vec<String> = entries
+                .flatten()
+                .map(|e| format!("{}/{}", dir, e.file_name().to_string_lossy()))
+                .filter(|p| !self.files.iter().any(|f| &f.path == p))
+                .collect();
+            strays.sort();
+            for path in strays {
+                change(&path, Action::Remove);
+            }
+        }
+        Plan {
+            changes,
+            firewall: self
+                .firewall
+                .as_ref()
+                .is_some_and(|r| firewall_differs(r, host)),
+        }
+    }
+
+    /// Restore onto `root`, going on past a change that fails so as much
+    /// of the configuration as possible is back
+    pub fn restore(&self, root: &Path, host: &dyn Host) -> Restored {
+        let plan = self.plan(root, host);
+        let mut restored = Restored::default();
+        for change in plan.changes {
+            let full = root.join(&change.path);
+            let file = self.files.iter().find(|f| f.path == change.path);
+            let result = match (change.action, file) {
+                (Action::Remove, _) => std::fs::remove_file(&full)
+                    .map_err(|e| format!("Failed to remove {}: {}", full.display(), e)),
+                (Action::Relink, Some(f)) => relink(&full, f.link.as_deref().unwrap_or_default()),
+                (_, Some(f)) => write(&full, f.contents.as_deref().unwrap_or_default(), f.mode),
+                (_, None) => continue,
+            };
+            match result {
+                Ok(()) => restored.changes.push(change),
+                Err(e) => restored.errors.push(e),
+            }
+        }
+
+        for (prefix, program, args) in RELOADS {
+            if !restored.changes.iter().any(|c| c.path.starts_with(prefix)) {
+                continue;
+            }
+            let command = format!("{} {}", program, args.join(" "));
+            match host.run(program, args, None) {
+                Ok(_) => restored.reloaded.push(command),
+                Err(e) => restored.errors.push(e),
+            }
+        }
+
+        if let Some(ruleset) = &self.firewall {
+            if plan.firewall && firewall_differs(ruleset, host) {
+                match load_firewall(ruleset, host) {
+                    Ok(()) => restored.firewall = true,
+                    Err(e) => restored.errors.push(e),
+                }
+            }
+        }
+        restored
+    }
+}
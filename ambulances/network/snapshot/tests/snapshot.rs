@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Capturing a configuration tree and restoring it, against a fake host
+
+use network_snapshot::{Action, Host, Snapshot};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+
+const ADDRESSES: &str = r#"[{"ifname":"wlan0","address":"aa:bb:cc:dd:ee:ff","mtu":1500,
+    "operstate":"UP","addr_info":[{"family":"inet","local":"192.168.1.20","prefixlen":24}]}]"#;
+const ROUTES: &str = r#"[{"dst":"default","gateway":"192.168.1.1","dev":"wlan0","metric":600}]"#;
+
+/// Answers `ip` and `nft` from fixed output, and remembers every call
+#[derive(Default)]
+struct FakeHost {
+    ruleset: RefCell<String>,
+    calls: RefCell<Vec<String>>,
+}
+
+impl Host for FakeHost {
+    fn run(&self, program: &str, args: &[&str], input: Option<&str>) -> Result<String, String> {
+        self.calls
+            .borrow_mut()
+            .push(format!("{} {}", program, args.join(" ")));
+        match (program, args) {
+            ("ip", [_, "-4", "route", "show"]) => Ok(ROUTES.to_string()),
+            ("ip", [_, "-6", "route", "show"]) => Ok("[]".to_string()),
+            ("ip", [_, "address", "show"]) => Ok(ADDRESSES.to_string()),
+            ("nft", ["list", "ruleset"]) => Ok(self.ruleset.borrow().clone()),
+            ("nft", ["-f", "-"]) => {
+                let rules = input.unwrap_or_default();
+                *self.ruleset.borrow_mut() = rules.trim_start_matches("flush ruleset\n").into();
+                Ok(String::new())
+            }
+            _ => Ok(String::new()),
+        }
+    }
+}
+
+/// A fresh root under the temp directory, with a working configuration
+fn root(name: &str) -> PathBuf {
+    let root =
+        std::env::temp_dir().join(format!("network-snapshot-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&root);
+    let connections = root.join("etc/NetworkManager/system-connections");
+    fs::create_dir_all(&connections).unwrap();
+    fs::write(root.join("etc/hostname"), "laptop\n").unwrap();
+    fs::write(root.join("etc/hosts"), "127.0.1.1 laptop\n").unwrap();
+    fs::write(root.join("etc/resolv.conf"), "nameserver 192.168.1.1\n").unwrap();
+    fs::write(
+        connections.join("home.nmconnection"),
+        "[connection]\nid=home\ninterface-name=wlan0\n[wifi]\nmac-address=AA:BB:CC:DD:EE:FF\nssid=home\n",
+    )
+    .unwrap();
+    root
+}
+
+#[test]
+fn captures_files_live_state_and_firewall() {
+    let root = root("capture");
+    let host = FakeHost::default();
+    *host.ruleset.borrow_mut() = "table inet filter {\n}\n".into();
+    let snapshot = Snapshot::capture(&root, &host);
+
+    assert_eq!(snapshot.hostname, "laptop");
+    assert_eq!(snapshot.interfaces[0].addresses, ["192.168.1.20/24"]);
+    assert_eq!(snapshot.routes[0].gateway.as_deref(), Some("192.168.1.1"));
+    assert_eq!(snapshot.firewall.as_ref().unwrap().tool, "nft");
+    let paths: Vec<&str> = snapshot.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "etc/resolv.conf",
+            "etc/hosts",
+            "etc/NetworkManager/system-connections/home.nmconnection",
+        ]
+    );
+    assert_eq!(
+        snapshot.directories,
+        ["etc/NetworkManager/system-connections"]
+    );
+    assert_eq!(snapshot.unreadable().count(), 0);
+}
+
+#[test]
+fn restore_undoes_a_broken_repair() {
+    let root = root("restore");
+    let host = FakeHost::default();
+    *host.ruleset.borrow_mut() = "table inet filter {\n}\n".into();
+    let snapshot = Snapshot::capture(&root, &host);
+
+    // What a bad repair might leave behind
+    let connections = root.join("etc/NetworkManager/system-connections");
+    fs::write(root.join("etc/resolv.conf"), "nameserver 127.0.0.53\n").unwrap();
+    fs::remove_file(connections.join("home.nmconnection")).unwrap();
+    fs::write(connections.join("broken.nmconnection"), "[connection]\n").unwrap();
+    *host.ruleset.borrow_mut() = "table inet filter {\n  chain input { drop }\n}\n".into();
+
+    let plan = snapshot.plan(&root, &host);
+    let actions: Vec<(&str, Action)> = plan
+        .changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.action))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            ("etc/resolv.conf", Action::Replace),
+            (
+                "etc/NetworkManager/system-connections/home.nmconnection",
+                Action::Create
+            ),
+            (
+                "etc/NetworkManager/system-connections/broken.nmconnection",
+                Action::Remove
+            ),
+        ]
+    );
+    assert!(plan.firewall);
+
+    let restored = snapshot.restore(&root, &host);
+    assert!(restored.succeeded(), "{:?}", restored.errors);
+    assert!(restored.firewall);
+    assert_eq!(restored.reloaded, ["nmcli connection reload"]);
+    assert_eq!(
+        fs::read_to_string(root.join("etc/resolv.conf")).unwrap(),
+        "nameserver 192.168.1.1\n"
+    );
+    assert!(!connections.join("broken.nmconnection").exists());
+    assert_eq!(*host.ruleset.borrow(), "table inet filter {\n}\n");
+    assert!(snapshot.plan(&root, &host).is_empty());
+}
+
+#[test]
+fn clone_drops_what_ties_the_snapshot_to_its_machine() {
+    let source = root("clone-source");
+    let host = FakeHost::default();
+    let clone = Snapshot::capture(&source, &host).for_clone();
+
+    assert!(clone.files.iter().all(|f| f.path != "etc/hosts"));
+    assert!(clone.directories.is_empty());
+    let profile = clone
+        .files
+        .iter()
+        .find(|f| f.path.ends_with("home.nmconnection"))
+        .unwrap();
+    assert_eq!(
+        profile.contents.as_deref(),
+        Some("[connection]\nid=home\n[wifi]\nssid=home\n")
+    );
+
+    // The target keeps its own profiles and hosts file
+    let target = root("clone-target");
+    let connections = target.join("etc/NetworkManager/system-connections");
+    fs::write(connections.join("wired.nmconnection"), "[connection]\n").unwrap();
+    let restored = clone.restore(&target, &host);
+    assert!(restored.succeeded(), "{:?}", restored.errors);
+    assert!(connections.join("wired.nmconnection").exists());
+    assert_eq!(
+        fs::read_to_string(target.join("etc/hosts")).unwrap(),
+        "127.0.1.1 laptop\n"
+    );
+}
+
+#[test]
+fn differences_name_missing_addresses_and_routes() {
+    let root = root("differences");
+    let host = FakeHost::default();
+    let working = Snapshot::capture(&root, &host);
+    let mut broken = working.clone();
+    broken.interfaces[0].addresses.clear();
+    broken.routes.clear();
+
+    assert_eq!(
+        working.differences(&broken),
+        [
+            "wlan0: address 192.168.1.20/24 missing",
+            "route default via 192.168.1.1 missing",
+        ]
+    );
+}
+
+#[test]
+fn loading_refuses_files_outside_what_snapshots_cover() {
+    let root = root("load");
+    let host = FakeHost::default();
+    let mut snapshot = Snapshot::capture(&root, &host);
+    snapshot.files[0].mode = 0o4755;
+    let path = root.join("snapshot.json");
+    snapshot.save(&path).unwrap();
+    assert_eq!(Snapshot::load(&path).unwrap().files[0].mode, 0o755);
+
+    for bad in [
+        "/etc/sudoers.d/x",
+        "etc/sudoers.d/x",
+        "etc/netplan/../sudoers",
+        "etc/netplan/..",
+        "etc/NetworkManager/system-connections/a/b",
+    ] {
+        let mut tampered = snapshot.clone();
+        tampered.files[0].path = bad.to_string();
+        tampered.save(&path).unwrap();
+        assert!(Snapshot::load(&path).is_err(), "{}", bad);
+    }
+
+    // A listed directory has everything else in it removed
+    let mut tampered = snapshot.clone();
+    tampered.directories.push("etc".to_string());
+    tampered.save(&path).unwrap();
+    assert!(Snapshot::load(&path).is_err());
+}
@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Known-good network baseline
+//!
+//! A baseline records what the network looked like while it worked:
+//! interfaces, DNS servers, gateway and the latency range seen for each
+//! probe target. Comparing a fresh diagnosis against it reports explicit
+//! deviations ("gateway changed from 192.168.1.1 to 10.0.0.1") even when
+//! every absolute check still passes.
+
+use crate::DiagnosticResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Latency may exceed the recorded maximum by this factor before flagging
+const LATENCY_TOLERANCE: f64 = 1.5;
+/// Absolute slack so near-zero LAN latencies do not flag on jitter
+const LATENCY_SLACK_MS: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceBaseline {
+    pub name: String,
+    pub is_up: bool,
+    pub has_carrier: bool,
+    pub ipv4_addresses: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LatencyRange {
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Unix seconds of the first capture
+    pub captured_at: u64,
+    /// Number of diagnostic runs folded into the ranges
+    pub samples: u32,
+    pub interfaces: Vec<InterfaceBaseline>,
+    pub dns_servers: Vec<String>,
+    pub gateway: String,
+    pub latency: BTreeMap<String, LatencyRange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Deviation {
+    pub kind: &'static str,
+    pub subject: String,
+    pub expected: String,
+    pub actual: String,
+    pub severity: Severity,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub baseline_captured_at: u64,
+    pub deviations: Vec<Deviation>,
+    pub result: DiagnosticResult,
+}
+
+fn path() -> PathBuf {
+    crate::config::config_dir().join("baseline.json")
+}
+
+fn interfaces(result: &DiagnosticResult) -> Vec<InterfaceBaseline> {
+    result.interfaces["interfaces"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|i| serde_json::from_value(i.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn dns_servers(result: &DiagnosticResult) -> Vec<String> {
+    let mut servers: Vec<String> = result.dns["servers"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|s| s["address"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    servers.sort();
+    servers
+}
+
+/// Latency of each reachable probe target
+fn latencies(result: &DiagnosticResult) -> BTreeMap<String, f64> {
+    result.connectivity["tests"]
+        .as_array()
+        .map(|tests| {
+            tests
+                .iter()
+                .filter(|t| t["reachable"] == true)
+                .filter_map(|t| {
+                    Some((t["target"].as_str()?.to_string(), t["latency_ms"].as_f64()?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn unreachable_targets(result: &DiagnosticResult) -> Vec<String> {
+    result.connectivity["tests"]
+        .as_array()
+        .map(|tests| {
+            tests
+                .iter()
+                .filter(|t| t["reachable"] == false)
+                .filter_map(|t| t["target"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl Baseline {
+    pub fn capture(result: &DiagnosticResult) -> Baseline {
+        let captured_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Baseline {
+            captured_at,
+            samples: 1,
+            interfaces: interfaces(result),
+            dns_servers: dns_servers(result),
+            gateway: result.routing["gateway_ip"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            latency: latencies(result)
+                .into_iter()
+                .map(|(target, ms)| {
+                    (
+                        target,
+                        LatencyRange {
+                            min_ms: ms,
+                            max_ms: ms,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Widen latency ranges with another known-good sample
+    pub fn absorb(&mut self, result: &DiagnosticResult) {
+        for (target, ms) in latencies(result) {
+            self.latency
+                .entry(target)
+                .and_modify(|r| {
+                    r.min_ms = r.min_ms.min(ms);
+                    r.max_ms = r.max_ms.max(ms);
+                })
+                .or_insert(LatencyRange {
+                    min_ms: ms,
+                    max_ms: ms,
+                });
+        }
+        self.samples += 1;
+    }
+
+    pub fn load() -> Result<Option<Baseline>, String> {
+        let path = path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize baseline: {}", e))?;
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Every way `result` differs from the baseline
+    pub fn compare(&self, result: &DiagnosticResult) -> Vec<Deviation> {
+        let mut deviations = Vec::new();
+        let mut deviate = |kind, subject: &str, expected: String, actual: String, severity| {
+            deviations.push(Deviation {
+                kind,
+                subject: subject.to_string(),
+                expected,
+                actual,
+                severity,
+            })
+        };
+
+        let current = interfaces(result);
+        for known in &self.interfaces {
+            match current.iter().find(|i| i.name == known.name) {
+                None => deviate(
+                    "interface_missing",
+                    &known.name,
+                    "present".into(),
+                    "absent".into(),
+                    Severity::Warning,
+                ),
+                Some(now) => {
+                    if known.is_up && !now.is_up {
+                        deviate(
+                            "interface_down",
+                            &known.name,
+                            "up".into(),
+                            "down".into(),
+                            Severity::Warning,
+                        );
+                    }
+                    if known.has_carrier && !now.has_carrier {
+                        deviate(
+                            "carrier_lost",
+                            &known.name,
+                            "carrier".into(),
+                            "no carrier".into(),
+                            Severity::Warning,
+                        );
+                    }
+                    if !known.ipv4_addresses.is_empty()
+                        && now.ipv4_addresses != known.ipv4_addresses
+                    {
+                        deviate(
+                            "addresses_changed",
+                            &known.name,
+                            known.ipv4_addresses.join(", "),
+                            now.ipv4_addresses.join(", "),
+                            Severity::Info,
+                        );
+                    }
+                }
+            }
+        }
+        for now in &current {
+            if !self.interfaces.iter().any(|i| i.name == now.name) {
+                deviate(
+                    "interface_new",
+                    &now.name,
+                    "absent".into(),
+                    "present".into(),
+                    Severity::Info,
+                );
+            }
+        }
+
+        let servers = dns_servers(result);
+        if servers != self.dns_servers {
+            deviate(
+                "dns_servers_changed",
+                "dns",
+                self.dns_servers.join(", "),
+                servers.join(", "),
+                Severity::Warning,
+            );
+        }
+
+        let gateway = result.routing["gateway_ip"].as_str().unwrap_or_default();
+        if gateway != self.gateway {
+            deviate(
+                "gateway_changed",
+                "routing",
+                self.gateway.clone(),
+                gateway.to_string(),
+                Severity::Warning,
+            );
+        }
+
+        let now = latencies(result);
+        for (target, range) in &self.latency {
+            match now.get(target) {
+                Some(&ms) if ms > range.max_ms * LATENCY_TOLERANCE + LATENCY_SLACK_MS => deviate(
+                    "latency_above_range",
+                    target,
+                    format!("{:.1}-{:.1} ms", range.min_ms, range.max_ms),
+                    format!("{:.1} ms", ms),
+                    Severity::Warning,
+                ),
+                Some(_) => {}
+                None if unreachable_targets(result).contains(target) => deviate(
+                    "target_unreachable",
+                    target,
+                    "reachable".into(),
+                    "unreachable".into(),
+                    Severity::Warning,
+                ),
+                None => {}
+            }
+        }
+
+        deviations
+    }
+}
@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Application configuration
+//!
+//! Settings live in `config.toml` under the platform config directory
+//! (`~/.config/network-ambulance/` on Linux). The file is watched and
+//! reloaded on change; a file that fails to parse or validate is logged
+//! and the previous configuration stays in effect.
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+const APP_DIR: &str = "network-ambulance";
+pub const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub probes: ProbeConfig,
+    pub timeouts: TimeoutConfig,
+    pub backend: BackendConfig,
+    pub auto_repair: AutoRepairConfig,
+    pub redaction: RedactionConfig,
+    pub metrics: MetricsConfig,
+    pub plugins: PluginConfig,
+}
+
+/// Endpoints used by connectivity and DNS probes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeConfig {
+    pub ping_targets: Vec<String>,
+    pub dns_names: Vec<String>,
+    pub http_urls: Vec<String>,
+}
+
+/// Timeouts in seconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    pub ping_secs: u64,
+    pub dns_secs: u64,
+    pub http_secs: u64,
+    /// Upper bound for a whole backend invocation
+    pub backend_secs: u64,
+}
+
+/// Which diagnostics backend to run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    /// Executable implementing `diagnose --json` and `repair <target> --json`;
+    /// repairs run it only from where root alone can write, see
+    /// [`admin_program`]
+    pub executable: String,
+    /// `network-snapshot`, run before each repair so it can be rolled
+    /// back; empty to repair without a snapshot. Held to the same rule
+    pub snapshot: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoRepairMode {
+    /// Never repair without an explicit request
+    Off,
+    /// Repair `allowed_targets` automatically when diagnostics flag them
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoRepairConfig {
+    pub mode: AutoRepairMode,
+    pub allowed_targets: Vec<String>,
+}
+
+/// What to scrub from logs and exported reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RedactionConfig {
+    pub ip_addresses: bool,
+    pub mac_addresses: bool,
+    pub hostname: bool,
+    /// Extra regular expressions whose matches are replaced
+    pub patterns: Vec<String>,
+}
+
+/// Prometheus exporter; off unless explicitly enabled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub listen: String,
+}
+
+/// Third-party plugin loading and WASM sandbox limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginConfig {
+    pub enabled: bool,
+    /// Native plugins run unsandboxed, so they must be allowed explicitly
+    pub allow_native: bool,
+    /// Fuel (roughly, instructions) granted to each WASM call
+    pub wasm_fuel: u64,
+    pub wasm_memory_mb: u32,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        ProbeConfig {
+            ping_targets: vec!["1.1.1.1".into(), "8.8.8.8".into()],
+            dns_names: vec!["example.com".into()],
+            http_urls: vec!["http://connectivity-check.ubuntu.com/".into()],
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        TimeoutConfig {
+            ping_secs: 3,
+            dns_secs: 5,
+            http_secs: 10,
+            backend_secs: 120,
+        }
+    }
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig {
+            executable: "./bin/network-ambulance-d".into(),
+            snapshot: "./bin/network-snapshot".into(),
+        }
+    }
+}
+
+/// `program` resolved to the file an administrator run may start: it and
+/// every directory above it owned by root and writable by no one else.
+/// The configuration is the user's to edit, so without this it could name
+/// any program of theirs for root to run
+pub fn admin_program(program: &str) -> Result<PathBuf, String> {
+    let path = Path::new(program)
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        for part in path.ancestors() {
+            let meta = fs::metadata(part).map_err(|e| format!("{}: {}", part.display(), e))?;
+            if meta.uid() != 0 || meta.mode() & 0o022 != 0 {
+                return Err(format!(
+                    "{} is not run as administrator: {} is not root's alone",
+                    path.display(),
+                    part.display()
+                ));
+            }
+        }
+    }
+    Ok(path)
+}
+
+impl Default for AutoRepairConfig {
+    fn default() -> Self {
+        AutoRepairConfig {
+            mode: AutoRepairMode::Off,
+            allowed_targets: vec!["dns".into()],
+        }
+    }
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        RedactionConfig {
+            ip_addresses: false,
+            mac_addresses: true,
+            hostname: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: false,
+            listen: "127.0.0.1:9469".into(),
+        }
+    }
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        PluginConfig {
+            enabled: true,
+            allow_native: false,
+            wasm_fuel: 500_000_000,
+            wasm_memory_mb: 64,
+        }
+    }
+}
+
+impl Config {
+    /// Reject values that would make the app unusable
+    pub fn validate(&self) -> Result<(), String> {
+        if self.backend.executable.trim().is_empty() {
+            return Err("backend.executable must not be empty".to_string());
+        }
+        let t = &self.timeouts;
+        if t.ping_secs == 0 || t.dns_secs == 0 || t.http_secs == 0 || t.backend_secs == 0 {
+            return Err("timeouts must be at least one second".to_string());
+        }
+        if self.probes.ping_targets.is_empty() {
+            return Err("probes.ping_targets must list at least one target".to_string());
+        }
+        for target in &self.auto_repair.allowed_targets {
+            if !matches!(target.as_str(), "dns" | "interface" | "routing" | "all") {
+                return Err(format!("unknown auto_repair target: {}", target));
+            }
+        }
+        for pattern in &self.redaction.patterns {
+            regex::Regex::new(pattern)
+                .map_err(|e| format!("invalid redaction pattern {:?}: {}", pattern, e))?;
+        }
+        if self.plugins.wasm_fuel == 0 || self.plugins.wasm_memory_mb == 0 {
+            return Err("plugins.wasm_fuel and plugins.wasm_memory_mb must be positive".to_string());
+        }
+        // Unattended repairs would run these as root with no one to notice
+        if self.auto_repair.mode == AutoRepairMode::Auto {
+            admin_program(&self.backend.executable)
+                .map_err(|e| format!("auto_repair needs backend.executable: {}", e))?;
+            if !self.backend.snapshot.is_empty() {
+                admin_program(&self.backend.snapshot)
+                    .map_err(|e| format!("auto_repair needs backend.snapshot: {}", e))?;
+            }
+        }
+        if self.metrics.listen.parse::<std::net::SocketAddr>().is_err() {
+            return Err(format!(
+                "metrics.listen is not a socket address: {}",
+                self.metrics.listen
+            ));
+        }
+        Ok(())
+    }
+
+    /// Environment passed to the backend so it probes what the user configured
+    pub fn backend_env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("NETWORK_AMBULANCE_PING_TARGETS", self.probes.ping_targets.join(" ")),
+            ("NETWORK_AMBULANCE_DNS_NAMES", self.probes.dns_names.join(" ")),
+            ("NETWORK_AMBULANCE_HTTP_URLS", self.probes.http_urls.join(" ")),
+            ("PING_TIMEOUT", self.timeouts.ping_secs.to_string()),
+            ("DNS_TIMEOUT", self.timeouts.dns_secs.to_string()),
+            ("HTTP_TIMEOUT", self.timeouts.http_secs.to_string()),
+        ]
+    }
+}
+
+/// Directory holding config, baselines and other per-user state
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_DIR)
+}
+
+fn load_from(path: &Path) -> Result<Config, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config: Config =
+        toml::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Shared, live-reloaded configuration
+pub struct ConfigStore {
+    path: PathBuf,
+    current: Arc<RwLock<Config>>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl ConfigStore {
+    /// Load the config file, falling back to defaults if it is missing or invalid
+    pub fn load() -> Self {
+        let path = config_dir().join(CONFIG_FILE);
+        let config = if path.exists() {
+            load_from(&path).unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "invalid config; using defaults");
+                Config::default()
+            })
+        } else {
+            Config::default()
+        };
+
+        ConfigStore {
+            path,
+            current: Arc::new(RwLock::new(config)),
+            _watcher: None,
+        }
+    }
+
+    pub fn get(&self) -> Config {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Validate, persist and apply a new configuration
+    pub fn set(&self, config: Config) -> Result<(), String> {
+        config.validate()?;
+        let text = toml::to_string_pretty(&config)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        // Write then rename so the watcher never sees a half-written file
+        let tmp = self.path.with_extension("toml.tmp");
+        fs::write(&tmp, text).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("Failed to replace {}: {}", self.path.display(), e))?;
+
+        *self.current.write().unwrap() = config;
+        Ok(())
+    }
+
+    /// Watch the config file and call `on_change` after each successful reload
+    pub fn watch<F>(&mut self, on_change: F) -> Result<(), String>
+    where
+        F: Fn(&Config) + Send + 'static,
+    {
+        let dir = self.path.parent().map(Path::to_path_buf).unwrap_or_default();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let path = self.path.clone();
+        let current = Arc::clone(&self.current);
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else { return };
+            if !event.paths.iter().any(|p| p == &path) || !path.exists() {
+                return;
+            }
+            match load_from(&path) {
+                Ok(config) => {
+                    let mut guard = current.write().unwrap();
+                    if *guard != config {
+                        *guard = config.clone();
+                        drop(guard);
+                        tracing::info!("config reloaded");
+                        on_change(&config);
+                    }
+                }
+                Err(e) => tracing::warn!(error = %e, "ignoring config change"),
+            }
+        })
+        .map_err(|e| format!("Failed to create config watcher: {}", e))?;
+
+        // Watch the directory: editors replace the file rather than writing in place
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+        self._watcher = Some(watcher);
+        Ok(())
+    }
+}
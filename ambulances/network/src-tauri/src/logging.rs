@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Application logging
+//!
+//! On Linux every tracing event becomes a structured journal entry written
+//! through the systemd shim and tagged `SYSLOG_IDENTIFIER=network-ambulance`.
+//! Elsewhere events go to daily-rotated files under the platform data
+//! directory. `recent` reads either source back for problem reports.
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+pub const IDENTIFIER: &str = "network-ambulance";
+
+/// Keeps the background file writer alive; hold it for the life of the app
+pub struct LogGuard {
+    _file: Option<tracing_appender::non_blocking::WorkerGuard>,
+}
+
+/// Install the global subscriber
+pub fn init() -> LogGuard {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let console = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+
+    #[cfg(target_os = "linux")]
+    {
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(console)
+            .with(journald::JournalLayer)
+            .init();
+        LogGuard { _file: None }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let appender = tracing_appender::rolling::daily(log_dir(), format!("{}.log", IDENTIFIER));
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(console)
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(writer),
+            )
+            .init();
+        LogGuard { _file: Some(guard) }
+    }
+}
+
+/// Where rotated log files are written on platforms without a journal
+#[cfg(not(target_os = "linux"))]
+pub fn log_dir() -> std::path::PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| ".".into())
+        .join(IDENTIFIER)
+        .join("logs")
+}
+
+/// The newest `limit` log lines, oldest first
+pub fn recent(limit: usize) -> Result<Vec<String>, String> {
+    #[cfg(target_os = "linux")]
+    {
+        journald::recent(limit)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        recent_from_files(limit)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn recent_from_files(limit: usize) -> Result<Vec<String>, String> {
+    let dir = log_dir();
+    let newest = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(IDENTIFIER))
+        })
+        .max();
+
+    let Some(path) = newest else {
+        return Ok(Vec::new());
+    };
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let start = lines.len().saturating_sub(limit);
+    Ok(lines[start..].to_vec())
+}
+
+#[cfg(target_os = "linux")]
+mod journald {
+    use super::IDENTIFIER;
+    use std::fmt::Write;
+    use systemd_shim::journal::{self, Journal};
+    use tracing::field::{Field, Visit};
+    use tracing::{Event, Level, Subscriber};
+    use tracing_subscriber::layer::{Context, Layer};
+
+    /// Forwards each event to the journal as one structured entry
+    pub struct JournalLayer;
+
+    #[derive(Default)]
+    struct Fields {
+        message: String,
+        extra: Vec<(String, String)>,
+    }
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.message.push_str(value);
+            } else {
+                self.extra.push((journal_name(field.name()), value.to_string()));
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.message, "{:?}", value);
+            } else {
+                self.extra
+                    .push((journal_name(field.name()), format!("{:?}", value)));
+            }
+        }
+    }
+
+    /// Journal field names are upper case ASCII letters, digits and underscores
+    fn journal_name(name: &str) -> String {
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    fn priority(level: &Level) -> &'static str {
+        match *level {
+            Level::ERROR => "3",
+            Level::WARN => "4",
+            Level::INFO => "6",
+            Level::DEBUG | Level::TRACE => "7",
+        }
+    }
+
+    fn level_name(priority: &str) -> &'static str {
+        match priority {
+            "0" | "1" | "2" | "3" => "ERROR",
+            "4" => "WARN",
+            "5" | "6" => "INFO",
+            _ => "DEBUG",
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for JournalLayer {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let meta = event.metadata();
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+
+            let mut entry: Vec<(String, String)> = vec![
+                ("MESSAGE".into(), fields.message),
+                ("PRIORITY".into(), priority(meta.level()).into()),
+                ("SYSLOG_IDENTIFIER".into(), IDENTIFIER.into()),
+                ("TARGET".into(), meta.target().into()),
+            ];
+            if let Some(file) = meta.file() {
+                entry.push(("CODE_FILE".into(), file.into()));
+            }
+            if let Some(line) = meta.line() {
+                entry.push(("CODE_LINE".into(), line.to_string()));
+            }
+            // Prefix custom fields so they cannot shadow journal-trusted ones
+            entry.extend(
+                fields
+                    .extra
+                    .into_iter()
+                    .map(|(k, v)| (format!("NA_{}", k), v)),
+            );
+
+            // Nowhere left to report a logging failure
+            let _ = journal::send(&entry);
+        }
+    }
+
+    pub fn recent(limit: usize) -> Result<Vec<String>, String> {
+        let mut j = Journal::open(journal::LOCAL_ONLY)
+            .map_err(|e| format!("Failed to open journal: {}", e))?;
+        j.add_match(&format!("SYSLOG_IDENTIFIER={}", IDENTIFIER))
+            .map_err(|e| format!("Failed to filter journal: {}", e))?;
+        j.seek_tail()
+            .map_err(|e| format!("Failed to seek journal: {}", e))?;
+
+        let mut lines = Vec::new();
+        while lines.len() < limit {
+            match j.previous_entry() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => return Err(format!("Failed to read journal: {}", e)),
+            }
+            let priority = j.field("PRIORITY").unwrap_or_default();
+            let target = j.field("TARGET").unwrap_or_default();
+            let message = j.field("MESSAGE").unwrap_or_default();
+            lines.push(format!("{} {}: {}", level_name(&priority), target, message));
+        }
+        lines.reverse();
+        Ok(lines)
+    }
+}
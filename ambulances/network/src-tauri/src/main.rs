@@ -2,117 +2,625 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod baseline;
+mod config;
+mod logging;
+mod metrics;
+mod plugins;
+mod redact;
+mod selftest;
+
+use ambulance_core::error::{Category, Error, ErrorEnvelope};
+use ambulance_core::{RepairOutcome, Section};
+use baseline::{Baseline, Comparison};
+use config::{AutoRepairMode, Config, ConfigStore};
+use metrics::Metrics;
+use network_snapshot::Restored;
+use plugins::{PluginHost, PluginInfo, RepairOutput};
+use selftest::Readiness;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use tauri::Manager;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct DiagnosticResult {
     version: String,
     tool: String,
-    dns: serde_json::Value,
-    routing: serde_json::Value,
-    connectivity: serde_json::Value,
-    interfaces: serde_json::Value,
+    dns: Section,
+    routing: Section,
+    connectivity: Section,
+    interfaces: Section,
+    /// Results from third-party plugins, keyed by plugin then check
+    #[serde(default)]
+    plugins: serde_json::Value,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct RepairResult {
     version: String,
     tool: String,
-    dns_repair: serde_json::Value,
-    interface_repair: serde_json::Value,
-    routing_repair: serde_json::Value,
+    dns_repair: RepairOutcome,
+    interface_repair: RepairOutcome,
+    routing_repair: RepairOutcome,
+    /// The snapshot taken before the repair, to roll it back with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot: Option<String>,
 }
 
-/// Run network diagnostics by calling the D backend
-#[tauri::command]
-async fn run_diagnostics() -> Result<DiagnosticResult, String> {
-    let output = Command::new("./bin/network-ambulance-d")
-        .args(["diagnose", "--json"])
-        .output()
-        .map_err(|e| format!("Failed to execute D backend: {}", e))?;
+/// What `network-snapshot restore --json` prints
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotRestore {
+    success: bool,
+    /// The snapshot taken just before, to undo the restore with
+    undo: String,
+    restored: Restored,
+}
 
-    if !output.status.success() {
-        return Err(format!(
-            "D backend failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+impl DiagnosticResult {
+    fn sections(&self) -> [(&'static str, &Section); 4] {
+        [
+            ("dns", &self.dns),
+            ("routing", &self.routing),
+            ("connectivity", &self.connectivity),
+            ("interfaces", &self.interfaces),
+        ]
     }
 
-    let result: DiagnosticResult = serde_json::from_slice(&output.stdout)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    /// Latency of the connectivity probe aimed at the default gateway, if any
+    fn gateway_latency_ms(&self) -> Option<f64> {
+        let gateway = self.routing["gateway_ip"].as_str().filter(|g| !g.is_empty())?;
+        self.connectivity["tests"]
+            .as_array()?
+            .iter()
+            .find(|t| t["target"].as_str() == Some(gateway) && t["reachable"] == true)
+            .and_then(|t| t["latency_ms"].as_f64())
+    }
 
-    Ok(result)
+    /// Whether the section a repair target addresses reported problems
+    fn flags(&self, target: &str) -> bool {
+        match target {
+            "dns" => self.dns.has_warnings(),
+            "interface" => self.interfaces.has_warnings(),
+            "routing" => self.routing.has_warnings(),
+            "all" => self.sections().iter().any(|(_, s)| s.has_warnings()),
+            _ => false,
+        }
+    }
+
+    fn record(&self, metrics: &Metrics) {
+        for (name, section) in self.sections() {
+            metrics.record_check(name, !section.has_warnings());
+        }
+        metrics.set_gateway_latency(self.gateway_latency_ms());
+    }
 }
 
-/// Run network repairs by calling the D backend
-#[tauri::command]
-async fn run_repair(target: String) -> Result<RepairResult, String> {
-    // Check for root/admin privileges
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let metadata = std::fs::metadata("/").map_err(|e| e.to_string())?;
-        if metadata.permissions().mode() & 0o700 != 0o700 {
-            return Err("Repair operations require administrator privileges".to_string());
+impl RepairResult {
+    fn succeeded(&self, target: &str) -> bool {
+        let ok = |outcome: &RepairOutcome| outcome.success;
+        match target {
+            "dns" => ok(&self.dns_repair),
+            "interface" => ok(&self.interface_repair),
+            "routing" => ok(&self.routing_repair),
+            _ => [&self.dns_repair, &self.interface_repair, &self.routing_repair]
+                .into_iter()
+                .all(ok),
         }
     }
+}
+
+/// Why a backend run failed: its own error envelope when it printed one,
+/// else its exit code and stderr
+fn backend_error(what: &str, code: Option<i32>, stdout: &[u8], stderr: &[u8]) -> Error {
+    if let Some(envelope) = ErrorEnvelope::parse(&String::from_utf8_lossy(stdout)) {
+        return envelope.error;
+    }
+    let category = code
+        .and_then(Category::from_exit_code)
+        .unwrap_or(Category::Failed);
+    let stderr = String::from_utf8_lossy(stderr);
+    Error::new(
+        "failed.backend",
+        category,
+        format!("{} failed: {}", what, stderr.trim()),
+    )
+}
+
+/// Stops a command on a module's own error, filed under `code`
+fn failed(code: &'static str) -> impl Fn(String) -> Error {
+    move |message| Error::new(code, Category::Failed, message)
+}
 
-    let output = Command::new("./bin/network-ambulance-d")
-        .args(["repair", &target, "--json"])
-        .output()
-        .map_err(|e| format!("Failed to execute D backend: {}", e))?;
+/// Run `program`, the backend or a tool beside it, and return its stdout
+async fn run_backend(
+    config: &Config,
+    program: &str,
+    args: &[&str],
+    what: &str,
+) -> Result<Vec<u8>, Error> {
+    let mut command = tokio::process::Command::new(program);
+    command
+        .args(args)
+        .envs(config.backend_env())
+        .kill_on_drop(true);
+
+    let limit = Duration::from_secs(config.timeouts.backend_secs);
+    let output = tokio::time::timeout(limit, command.output())
+        .await
+        .map_err(|_| {
+            Error::new(
+                "timeout.backend",
+                Category::Timeout,
+                format!("{} timed out after {}s", what, limit.as_secs()),
+            )
+        })?
+        .map_err(|e| {
+            Error::new(
+                "unavailable.backend",
+                Category::Unavailable,
+                format!("Failed to execute {}: {}", program, e),
+            )
+            .with_hint("Check the [backend] section of the configuration")
+        })?;
 
     if !output.status.success() {
-        return Err(format!(
-            "D backend repair failed: {}",
-            String::from_utf8_lossy(&output.stderr)
+        return Err(backend_error(
+            what,
+            output.status.code(),
+            &output.stdout,
+            &output.stderr,
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Run `program` with administrator privileges, via ambulance-privilege
+///
+/// No timeout applies: it would run down while the user reads the
+/// password prompt.
+async fn run_backend_elevated(
+    config: &Config,
+    program: &str,
+    args: &[&str],
+    what: &str,
+) -> Result<Vec<u8>, Error> {
+    let program = std::path::PathBuf::from(program);
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let env = config.backend_env();
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        ambulance_privilege::run(&program, &args, &env)
+    })
+    .await
+    .map_err(|e| {
+        Error::new(
+            "internal.panic",
+            Category::Internal,
+            format!("{} panicked: {}", what, e),
+        )
+    })?
+    .map_err(|e| Error::new("privilege.elevation-failed", Category::Privilege, e))?;
+
+    if !output.success() {
+        return Err(backend_error(
+            what,
+            output.code,
+            &output.stdout,
+            &output.stderr,
         ));
     }
 
-    let result: RepairResult = serde_json::from_slice(&output.stdout)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    Ok(output.stdout)
+}
+
+/// Run `program` as administrator: directly when the app already is,
+/// else through the elevation method ambulance-privilege finds
+async fn run_as_admin(
+    config: &Config,
+    program: &str,
+    args: &[&str],
+    what: &str,
+) -> Result<Vec<u8>, Error> {
+    let program = config::admin_program(program).map_err(|e| {
+        Error::new("privilege.untrusted-program", Category::Privilege, e).with_hint(
+            "Install the backend where only root can write, and point the [backend] section at it",
+        )
+    })?;
+    let program = &*program.to_string_lossy();
+    if ambulance_privilege::is_elevated() {
+        tracing::info!(what, "running");
+        return run_backend(config, program, args, what).await;
+    }
+    let method = ambulance_privilege::method().ok_or_else(|| {
+        Error::new(
+            "privilege.required",
+            Category::Privilege,
+            "Repair operations require administrator privileges",
+        )
+        .with_hint("Install pkexec or the system-tools helper, or run the app as administrator")
+    })?;
+    tracing::info!(what, via = method.label(), "running elevated");
+    run_backend_elevated(config, program, args, what).await
+}
+
+/// Save the network configuration with `network-snapshot` and return
+/// where it went
+async fn take_snapshot(config: &Config) -> Result<String, Error> {
+    let args = ["capture", "--json"];
+    let stdout = run_as_admin(config, &config.backend.snapshot, &args, "snapshot").await?;
+    serde_json::from_slice::<serde_json::Value>(&stdout)
+        .ok()
+        .and_then(|v| v["path"].as_str().map(str::to_string))
+        .ok_or_else(|| {
+            Error::new(
+                "failed.unreadable-report",
+                Category::Failed,
+                "network-snapshot did not say where it saved",
+            )
+        })
+}
+
+/// Run the D backend and plugin checks, recording metrics for both
+async fn diagnose(
+    config: &Config,
+    metrics: &Arc<Metrics>,
+    plugins: &Arc<PluginHost>,
+) -> Result<DiagnosticResult, Error> {
+    tracing::info!("running diagnostics");
+    let started = Instant::now();
+    let executable = &config.backend.executable;
+    let stdout = run_backend(config, executable, &["diagnose", "--json"], "D backend")
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "diagnostics failed");
+            e
+        })?;
+    metrics.record_duration("diagnose", started.elapsed().as_secs_f64());
+
+    let mut result: DiagnosticResult = serde_json::from_slice(&stdout).map_err(|e| {
+        Error::new(
+            "failed.unreadable-report",
+            Category::Failed,
+            format!("Failed to parse JSON: {}", e),
+        )
+    })?;
+    result.record(metrics);
+
+    let host = Arc::clone(plugins);
+    let plugin_metrics = Arc::clone(metrics);
+    result.plugins = tauri::async_runtime::spawn_blocking(move || {
+        host.run_checks(|check, out, seconds| {
+            plugin_metrics.record_check(check, out.passed);
+            plugin_metrics.record_duration(check, seconds);
+        })
+    })
+    .await
+    .map_err(|e| {
+        Error::new(
+            "internal.panic",
+            Category::Internal,
+            format!("Plugin checks panicked: {}", e),
+        )
+    })?;
 
     Ok(result)
 }
 
-/// Check if running with elevated privileges
+/// Run network diagnostics by calling the D backend
 #[tauri::command]
-async fn check_privileges() -> Result<bool, String> {
-    #[cfg(unix)]
-    {
-        Ok(unsafe { libc::geteuid() } == 0)
+async fn run_diagnostics(
+    app: tauri::AppHandle,
+    config: tauri::State<'_, ConfigStore>,
+    metrics: tauri::State<'_, Arc<Metrics>>,
+    plugins: tauri::State<'_, Arc<PluginHost>>,
+) -> Result<DiagnosticResult, Error> {
+    let config = config.get();
+    let result = diagnose(&config, &metrics, &plugins).await?;
+
+    if config.auto_repair.mode == AutoRepairMode::Auto {
+        for target in config.auto_repair.allowed_targets.clone() {
+            if !result.flags(&target) {
+                continue;
+            }
+            let app = app.clone();
+            let config = config.clone();
+            let metrics = Arc::clone(&metrics);
+            tracing::info!(repair = %target, "starting automatic repair");
+            tauri::async_runtime::spawn(async move {
+                let payload = match repair(&config, &metrics, &target).await {
+                    Ok(result) => serde_json::json!({ "target": target, "result": result }),
+                    Err(e) => serde_json::json!({ "target": target, "error": e }),
+                };
+                let _ = app.emit("auto-repair", payload);
+            });
+        }
     }
 
-    #[cfg(windows)]
-    {
-        // Windows privilege check would go here
-        Ok(false)
+    Ok(result)
+}
+
+/// Run network repairs by calling the D backend
+#[tauri::command]
+async fn run_repair(
+    target: String,
+    config: tauri::State<'_, ConfigStore>,
+    metrics: tauri::State<'_, Arc<Metrics>>,
+) -> Result<RepairResult, Error> {
+    repair(&config.get(), &metrics, &target).await
+}
+
+/// Run a repair, after a snapshot to roll it back with unless
+/// `backend.snapshot` is empty; a snapshot that fails is logged and the
+/// repair goes ahead without one
+async fn repair(config: &Config, metrics: &Metrics, target: &str) -> Result<RepairResult, Error> {
+    let snapshot = if config.backend.snapshot.is_empty() {
+        None
+    } else {
+        take_snapshot(config)
+            .await
+            .map_err(|e| tracing::warn!(repair = %target, error = %e, "no snapshot before repair"))
+            .ok()
+    };
+    let args = ["repair", target, "--json"];
+    let executable = &config.backend.executable;
+    let stdout = run_as_admin(config, executable, &args, "D backend repair").await;
+    let stdout = stdout.map_err(|e| {
+        tracing::error!(repair = %target, error = %e, "repair failed");
+        metrics.record_repair(target, false);
+        e
+    })?;
+
+    let result: RepairResult = serde_json::from_slice(&stdout).map_err(|e| {
+        metrics.record_repair(target, false);
+        Error::new(
+            "failed.unreadable-report",
+            Category::Failed,
+            format!("Failed to parse JSON: {}", e),
+        )
+    })?;
+    metrics.record_repair(target, result.succeeded(target));
+
+    Ok(RepairResult { snapshot, ..result })
+}
+
+/// Run a repair offered by a plugin, addressed as `plugin/repair-id`
+#[tauri::command]
+async fn run_plugin_repair(
+    address: String,
+    plugins: tauri::State<'_, Arc<PluginHost>>,
+    metrics: tauri::State<'_, Arc<Metrics>>,
+) -> Result<RepairOutput, Error> {
+    let host = Arc::clone(&plugins);
+    let target = address.clone();
+    tracing::info!(repair = %address, "running plugin repair");
+    let outcome = tauri::async_runtime::spawn_blocking(move || host.run_repair(&target))
+        .await
+        .map_err(|e| {
+            Error::new(
+                "internal.panic",
+                Category::Internal,
+                format!("Plugin repair panicked: {}", e),
+            )
+        })?;
+    metrics.record_repair(&address, outcome.as_ref().is_ok_and(|o| o.success));
+    outcome.map_err(failed("failed.plugin"))
+}
+
+/// Plugins that loaded, with the checks and repairs they offer
+#[tauri::command]
+async fn list_plugins(
+    plugins: tauri::State<'_, Arc<PluginHost>>,
+) -> Result<Vec<PluginInfo>, Error> {
+    Ok(plugins.list())
+}
+
+/// Record the current network as the known-good baseline
+///
+/// With `extend`, the run is folded into the existing baseline instead,
+/// widening its latency ranges.
+#[tauri::command]
+async fn capture_baseline(
+    extend: Option<bool>,
+    config: tauri::State<'_, ConfigStore>,
+    metrics: tauri::State<'_, Arc<Metrics>>,
+    plugins: tauri::State<'_, Arc<PluginHost>>,
+) -> Result<Baseline, Error> {
+    let result = diagnose(&config.get(), &metrics, &plugins).await?;
+    let baseline = match Baseline::load().map_err(failed("failed.baseline"))? {
+        Some(mut existing) if extend.unwrap_or(false) => {
+            existing.absorb(&result);
+            existing
+        }
+        _ => Baseline::capture(&result),
+    };
+    baseline.save().map_err(failed("failed.baseline"))?;
+    tracing::info!(samples = baseline.samples, "baseline captured");
+    Ok(baseline)
+}
+
+/// The saved baseline, if one has been captured
+#[tauri::command]
+async fn get_baseline() -> Result<Option<Baseline>, Error> {
+    Baseline::load().map_err(failed("failed.baseline"))
+}
+
+/// Diagnose and report every deviation from the saved baseline
+#[tauri::command]
+async fn compare_baseline(
+    config: tauri::State<'_, ConfigStore>,
+    metrics: tauri::State<'_, Arc<Metrics>>,
+    plugins: tauri::State<'_, Arc<PluginHost>>,
+) -> Result<Comparison, Error> {
+    let baseline = Baseline::load()
+        .map_err(failed("failed.baseline"))?
+        .ok_or_else(|| {
+            Error::new(
+                "usage.no-baseline",
+                Category::Usage,
+                "No baseline captured yet",
+            )
+            .with_hint("Capture one while the network works")
+        })?;
+    let result = diagnose(&config.get(), &metrics, &plugins).await?;
+    let deviations = baseline.compare(&result);
+    if !deviations.is_empty() {
+        tracing::warn!(count = deviations.len(), "network deviates from baseline");
     }
+    Ok(Comparison {
+        baseline_captured_at: baseline.captured_at,
+        deviations,
+        result,
+    })
+}
+
+/// Save the current network configuration, and return the snapshot's path
+#[tauri::command]
+async fn capture_snapshot(config: tauri::State<'_, ConfigStore>) -> Result<String, Error> {
+    take_snapshot(&config.get()).await
+}
 
-    #[cfg(not(any(unix, windows)))]
-    {
-        Ok(false)
+/// Restore a snapshot, such as the one a repair took; with `clone`, one
+/// from another machine, keeping this one's hosts file and profiles
+#[tauri::command]
+async fn restore_snapshot(
+    path: String,
+    clone: Option<bool>,
+    config: tauri::State<'_, ConfigStore>,
+) -> Result<SnapshotRestore, Error> {
+    let config = config.get();
+    let mut args = vec!["restore", path.as_str(), "--json"];
+    if clone.unwrap_or(false) {
+        args.push("--clone");
     }
+    let stdout = run_as_admin(&config, &config.backend.snapshot, &args, "snapshot restore").await?;
+    let result: SnapshotRestore = serde_json::from_slice(&stdout).map_err(|e| {
+        Error::new(
+            "failed.unreadable-report",
+            Category::Failed,
+            format!("Failed to parse JSON: {}", e),
+        )
+    })?;
+    tracing::info!(
+        snapshot = %path,
+        changes = result.restored.changes.len(),
+        undo = %result.undo,
+        "snapshot restored"
+    );
+    Ok(result)
+}
+
+/// Check if running with elevated privileges
+#[tauri::command]
+async fn check_privileges() -> Result<bool, Error> {
+    Ok(ambulance_privilege::is_elevated())
 }
 
 /// Get platform information
 #[tauri::command]
-async fn get_platform_info() -> Result<String, String> {
+async fn get_platform_info() -> Result<String, Error> {
     Ok(std::env::consts::OS.to_string())
 }
 
+/// Check config, backend, ICMP permission and helper tools before diagnosing
+#[tauri::command]
+async fn self_test(config: tauri::State<'_, ConfigStore>) -> Result<Readiness, Error> {
+    Ok(selftest::run(&config.get()).await)
+}
+
+/// Get the active configuration
+#[tauri::command]
+async fn get_config(config: tauri::State<'_, ConfigStore>) -> Result<Config, Error> {
+    Ok(config.get())
+}
+
+/// Validate, save and apply a new configuration
+#[tauri::command]
+async fn set_config(
+    app: tauri::AppHandle,
+    new_config: Config,
+    config: tauri::State<'_, ConfigStore>,
+    metrics: tauri::State<'_, Arc<Metrics>>,
+) -> Result<(), Error> {
+    config
+        .set(new_config.clone())
+        .map_err(failed("failed.config"))?;
+    if new_config.metrics.enabled {
+        metrics.serve(new_config.metrics.listen.clone());
+    }
+    let _ = app.emit("config-changed", &new_config);
+    Ok(())
+}
+
+/// Recent application log lines for problem reports, redacted per config
+#[tauri::command]
+async fn get_app_logs(
+    limit: Option<usize>,
+    config: tauri::State<'_, ConfigStore>,
+) -> Result<Vec<String>, Error> {
+    let rules = config.get().redaction;
+    let lines = logging::recent(limit.unwrap_or(500)).map_err(failed("failed.logs"))?;
+    Ok(lines
+        .iter()
+        .map(|line| redact::redact(line, &rules))
+        .collect())
+}
+
 fn main() {
+    let _log_guard = logging::init();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .invoke_handler(tauri::generate_handler![
             run_diagnostics,
             run_repair,
             check_privileges,
-            get_platform_info
+            get_platform_info,
+            get_config,
+            set_config,
+            get_app_logs,
+            run_plugin_repair,
+            list_plugins,
+            capture_baseline,
+            get_baseline,
+            compare_baseline,
+            capture_snapshot,
+            restore_snapshot,
+            self_test
         ])
         .setup(|app| {
+            let metrics = Arc::new(Metrics::default());
+            let mut config = ConfigStore::load();
+            let initial = config.get();
+            if initial.metrics.enabled {
+                metrics.serve(initial.metrics.listen.clone());
+            }
+
+            let handle = app.handle().clone();
+            let watched_metrics = Arc::clone(&metrics);
+            if let Err(e) = config.watch(move |new_config| {
+                // Enabling takes effect live; disabling needs a restart
+                if new_config.metrics.enabled {
+                    watched_metrics.serve(new_config.metrics.listen.clone());
+                }
+                let _ = handle.emit("config-changed", new_config);
+            }) {
+                tracing::warn!(error = %e, "config live reload disabled");
+            }
+            app.manage(Arc::new(PluginHost::discover(&initial.plugins)));
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let report = selftest::run(&initial).await;
+                if !report.ready {
+                    tracing::warn!("self-test found problems");
+                }
+                let _ = handle.emit("self-test", &report);
+            });
+            app.manage(config);
+            app.manage(metrics);
+
             #[cfg(debug_assertions)]
             {
                 let window = app.get_webview_window("main").unwrap();
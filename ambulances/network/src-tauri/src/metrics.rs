@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Prometheus metrics exporter
+//!
+//! Opt-in via `[metrics] enabled = true`. Serves the text exposition format
+//! on `GET /metrics` so a fleet already scraped by node_exporter can scrape
+//! ambulance health from the same Prometheus.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const PREFIX: &str = "network_ambulance";
+
+/// Largest request line and headers accepted
+const MAX_HEAD: usize = 8 * 1024;
+
+/// How long a scraper has to send its request before it is dropped
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct Timing {
+    last: f64,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+struct State {
+    durations: BTreeMap<String, Timing>,
+    checks: BTreeMap<String, bool>,
+    gateway_latency_ms: Option<f64>,
+    repairs: BTreeMap<(String, &'static str), u64>,
+}
+
+/// In-process metric registry
+#[derive(Default)]
+pub struct Metrics {
+    state: Mutex<State>,
+    serving: AtomicBool,
+}
+
+impl Metrics {
+    pub fn record_duration(&self, check: &str, seconds: f64) {
+        let mut state = self.state.lock().unwrap();
+        let d = state.durations.entry(check.to_string()).or_default();
+        d.last = seconds;
+        d.sum += seconds;
+        d.count += 1;
+    }
+
+    pub fn record_check(&self, check: &str, passed: bool) {
+        self.state
+            .lock()
+            .unwrap()
+            .checks
+            .insert(check.to_string(), passed);
+    }
+
+    pub fn set_gateway_latency(&self, latency_ms: Option<f64>) {
+        self.state.lock().unwrap().gateway_latency_ms = latency_ms;
+    }
+
+    pub fn record_repair(&self, target: &str, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        *self
+            .state
+            .lock()
+            .unwrap()
+            .repairs
+            .entry((target.to_string(), result))
+            .or_default() += 1;
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP {p}_check_duration_seconds Wall-clock time of the most recent run.\n\
+             # TYPE {p}_check_duration_seconds gauge",
+            p = PREFIX
+        );
+        for (check, d) in &state.durations {
+            let _ = writeln!(
+                out,
+                "{}_check_duration_seconds{{check=\"{}\"}} {}",
+                PREFIX,
+                escape(check),
+                d.last
+            );
+        }
+        let _ = writeln!(
+            out,
+            "# HELP {p}_check_run_seconds Time spent running checks since startup.\n\
+             # TYPE {p}_check_run_seconds summary",
+            p = PREFIX
+        );
+        for (check, d) in &state.durations {
+            let _ = writeln!(
+                out,
+                "{p}_check_run_seconds_sum{{check=\"{c}\"}} {}\n\
+                 {p}_check_run_seconds_count{{check=\"{c}\"}} {}",
+                d.sum,
+                d.count,
+                p = PREFIX,
+                c = escape(check)
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP {p}_check_passed Whether the check passed on its last run (1) or not (0).\n\
+             # TYPE {p}_check_passed gauge",
+            p = PREFIX
+        );
+        for (check, passed) in &state.checks {
+            let _ = writeln!(
+                out,
+                "{}_check_passed{{check=\"{}\"}} {}",
+                PREFIX,
+                escape(check),
+                u8::from(*passed)
+            );
+        }
+
+        if let Some(latency) = state.gateway_latency_ms {
+            let _ = writeln!(
+                out,
+                "# HELP {p}_gateway_latency_milliseconds Round-trip time to the default gateway.\n\
+                 # TYPE {p}_gateway_latency_milliseconds gauge\n\
+                 {p}_gateway_latency_milliseconds {}",
+                latency,
+                p = PREFIX
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP {p}_repairs_total Repairs attempted, by target and result.\n\
+             # TYPE {p}_repairs_total counter",
+            p = PREFIX
+        );
+        for ((target, result), count) in &state.repairs {
+            let _ = writeln!(
+                out,
+                "{}_repairs_total{{target=\"{}\",result=\"{}\"}} {}",
+                PREFIX,
+                escape(target),
+                result,
+                count
+            );
+        }
+
+        out
+    }
+
+    /// Start the HTTP listener unless it is already running
+    pub fn serve(self: &Arc<Self>, listen: String) {
+        if self.serving.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let metrics = Arc::clone(self);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = Arc::clone(&metrics).run(&listen).await {
+                tracing::error!(error = %e, %listen, "metrics endpoint stopped");
+                metrics.serving.store(false, Ordering::SeqCst);
+            }
+        });
+    }
+
+    async fn run(self: Arc<Self>, listen: &str) -> std::io::Result<()> {
+        let listener = TcpListener::bind(listen).await?;
+        tracing::info!(%listen, "serving Prometheus metrics");
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let metrics = Arc::clone(&self);
+            tauri::async_runtime::spawn(async move { metrics.respond(stream).await });
+        }
+    }
+
+    async fn respond(&self, mut stream: TcpStream) {
+        let request = match tokio::time::timeout(TIMEOUT, read_head(&mut stream)).await {
+            Ok(Some(head)) => head,
+            _ => return,
+        };
+        let response = if request.starts_with("GET /metrics ") {
+            let body = self.render();
+            format!(
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        } else {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string()
+        };
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+}
+
+/// The request line and headers, up to the blank line; `None` when the
+/// connection closes first or they run past `MAX_HEAD`
+async fn read_head(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            return Some(String::from_utf8_lossy(&buf[..end]).into_owned());
+        }
+        if buf.len() > MAX_HEAD {
+            return None;
+        }
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Escape a label value per the exposition format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
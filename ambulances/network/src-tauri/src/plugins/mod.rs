@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Third-party diagnostic plugins
+//!
+//! Each plugin lives in its own directory under `<config dir>/plugins/`
+//! with a `plugin.toml` manifest next to either a WASM module (run in a
+//! fuel- and memory-limited sandbox with no host imports) or a native
+//! library exporting the versioned C ABI described in `docs/PLUGINS.adoc`.
+//! Check results are merged into the diagnostic report under `plugins`.
+
+mod native;
+mod wasm;
+
+use crate::config::PluginConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Plugin ABI implemented by this build
+pub const ABI_VERSION: u32 = 1;
+
+const MANIFEST: &str = "plugin.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    Wasm,
+    Native,
+}
+
+/// Contents of `plugin.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    pub abi_version: u32,
+    pub kind: PluginKind,
+    /// Module or library file, relative to the plugin directory
+    pub library: String,
+}
+
+/// What a plugin offers, as reported by its `describe` export
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Description {
+    #[serde(default)]
+    pub checks: Vec<Entry>,
+    #[serde(default)]
+    pub repairs: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: String,
+    pub title: String,
+}
+
+/// One check result, shaped like the built-in report sections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckOutput {
+    pub passed: bool,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub recommendations: Vec<String>,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepairOutput {
+    pub success: bool,
+    #[serde(default)]
+    pub actions: Vec<String>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// Calls shared by both plugin kinds; all payloads are JSON
+trait Backend: Send + Sync {
+    fn abi_version(&self) -> Result<u32, String>;
+    fn call(&self, export: &str, arg: Option<&str>) -> Result<String, String>;
+}
+
+pub struct Plugin {
+    pub manifest: Manifest,
+    pub description: Description,
+    backend: Box<dyn Backend>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub kind: PluginKind,
+    pub checks: Vec<Entry>,
+    pub repairs: Vec<Entry>,
+}
+
+impl Plugin {
+    fn load(dir: &Path, settings: &PluginConfig) -> Result<Plugin, String> {
+        let manifest_path = dir.join(MANIFEST);
+        let text = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?;
+        let manifest: Manifest = toml::from_str(&text)
+            .map_err(|e| format!("Failed to parse {}: {}", manifest_path.display(), e))?;
+
+        if manifest.abi_version != ABI_VERSION {
+            return Err(format!(
+                "{} targets plugin ABI {} (supported: {})",
+                manifest.name, manifest.abi_version, ABI_VERSION
+            ));
+        }
+
+        let library = dir.join(&manifest.library);
+        let backend: Box<dyn Backend> = match manifest.kind {
+            PluginKind::Wasm => Box::new(wasm::WasmPlugin::load(&library, settings)?),
+            PluginKind::Native if settings.allow_native => {
+                Box::new(native::NativePlugin::load(&library)?)
+            }
+            PluginKind::Native => {
+                return Err(format!(
+                    "{} is a native plugin and plugins.allow_native is off",
+                    manifest.name
+                ))
+            }
+        };
+
+        let reported = backend.abi_version()?;
+        if reported != ABI_VERSION {
+            return Err(format!(
+                "{} reports plugin ABI {} (supported: {})",
+                manifest.name, reported, ABI_VERSION
+            ));
+        }
+
+        let description: Description = serde_json::from_str(&backend.call("describe", None)?)
+            .map_err(|e| format!("{}: invalid describe output: {}", manifest.name, e))?;
+
+        Ok(Plugin {
+            manifest,
+            description,
+            backend,
+        })
+    }
+
+    pub fn run_check(&self, id: &str) -> Result<CheckOutput, String> {
+        let out = self.backend.call("run_check", Some(id))?;
+        serde_json::from_str(&out).map_err(|e| format!("invalid check output: {}", e))
+    }
+
+    pub fn run_repair(&self, id: &str) -> Result<RepairOutput, String> {
+        let out = self.backend.call("run_repair", Some(id))?;
+        serde_json::from_str(&out).map_err(|e| format!("invalid repair output: {}", e))
+    }
+
+    pub fn info(&self) -> PluginInfo {
+        PluginInfo {
+            name: self.manifest.name.clone(),
+            version: self.manifest.version.clone(),
+            kind: self.manifest.kind,
+            checks: self.description.checks.clone(),
+            repairs: self.description.repairs.clone(),
+        }
+    }
+}
+
+pub fn plugins_dir() -> PathBuf {
+    crate::config::config_dir().join("plugins")
+}
+
+/// Every plugin that loaded successfully
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Plugin>,
+}
+
+impl PluginHost {
+    /// Load each plugin directory, skipping (and logging) broken ones
+    pub fn discover(settings: &PluginConfig) -> PluginHost {
+        if !settings.enabled {
+            return PluginHost::default();
+        }
+        let Ok(entries) = fs::read_dir(plugins_dir()) else {
+            return PluginHost::default();
+        };
+
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.join(MANIFEST).is_file())
+            .collect();
+        dirs.sort();
+
+        let mut plugins = Vec::new();
+        for dir in dirs {
+            match Plugin::load(&dir, settings) {
+                Ok(plugin) => {
+                    tracing::info!(
+                        plugin = %plugin.manifest.name,
+                        version = %plugin.manifest.version,
+                        "loaded plugin"
+                    );
+                    plugins.push(plugin);
+                }
+                Err(e) => tracing::warn!(dir = %dir.display(), error = %e, "skipping plugin"),
+            }
+        }
+        PluginHost { plugins }
+    }
+
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.plugins.iter().map(Plugin::info).collect()
+    }
+
+    /// Run every plugin check, calling `on_result` with its duration as it completes
+    ///
+    /// Returns `{ plugin: { check: result-or-error } }` for the report.
+    pub fn run_checks<F>(&self, mut on_result: F) -> serde_json::Value
+    where
+        F: FnMut(&str, &CheckOutput, f64),
+    {
+        let mut report = serde_json::Map::new();
+        for plugin in &self.plugins {
+            let mut checks = serde_json::Map::new();
+            for check in &plugin.description.checks {
+                let started = std::time::Instant::now();
+                let value = match plugin.run_check(&check.id) {
+                    Ok(out) => {
+                        let name = format!("{}/{}", plugin.manifest.name, check.id);
+                        on_result(&name, &out, started.elapsed().as_secs_f64());
+                        serde_json::to_value(out).unwrap_or_default()
+                    }
+                    Err(e) => serde_json::json!({ "error": e }),
+                };
+                checks.insert(check.id.clone(), value);
+            }
+            report.insert(plugin.manifest.name.clone(), checks.into());
+        }
+        report.into()
+    }
+
+    /// Run a repair addressed as `plugin/repair-id`
+    pub fn run_repair(&self, address: &str) -> Result<RepairOutput, String> {
+        let (name, id) = address
+            .split_once('/')
+            .ok_or_else(|| format!("plugin repair must be <plugin>/<repair>: {}", address))?;
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|p| p.manifest.name == name)
+            .ok_or_else(|| format!("no such plugin: {}", name))?;
+        if !plugin.description.repairs.iter().any(|r| r.id == id) {
+            return Err(format!("{} offers no repair {}", name, id));
+        }
+        plugin.run_repair(id)
+    }
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Native plugins: shared libraries exporting the `ambulance_plugin_*` C ABI
+//!
+//! These run in-process with the app's privileges, so loading them is
+//! opt-in via `plugins.allow_native`.
+
+use super::Backend;
+use libloading::{Library, Symbol};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type DescribeFn = unsafe extern "C" fn() -> *mut c_char;
+type RunFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+pub struct NativePlugin {
+    library: Library,
+}
+
+impl NativePlugin {
+    pub fn load(path: &Path) -> Result<NativePlugin, String> {
+        // Loading runs the library's initialisers; the caller has opted in
+        let library = unsafe { Library::new(path) }
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+        Ok(NativePlugin { library })
+    }
+
+    fn symbol<T>(&self, name: &str) -> Result<Symbol<'_, T>, String> {
+        let symbol = format!("ambulance_plugin_{}", name);
+        unsafe { self.library.get(symbol.as_bytes()) }
+            .map_err(|e| format!("missing export {}: {}", symbol, e))
+    }
+
+    /// Copy a plugin-owned string and hand it back to the plugin to free
+    fn take_string(&self, ptr: *mut c_char) -> Result<String, String> {
+        if ptr.is_null() {
+            return Err("plugin returned no result".to_string());
+        }
+        let free: Symbol<FreeFn> = self.symbol("free")?;
+        let text = unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned();
+        unsafe { free(ptr) };
+        Ok(text)
+    }
+}
+
+impl Backend for NativePlugin {
+    fn abi_version(&self) -> Result<u32, String> {
+        let f: Symbol<AbiVersionFn> = self.symbol("abi_version")?;
+        Ok(unsafe { f() })
+    }
+
+    fn call(&self, export: &str, arg: Option<&str>) -> Result<String, String> {
+        let ptr = match arg {
+            None => {
+                let f: Symbol<DescribeFn> = self.symbol(export)?;
+                unsafe { f() }
+            }
+            Some(arg) => {
+                let f: Symbol<RunFn> = self.symbol(export)?;
+                let arg = CString::new(arg).map_err(|e| e.to_string())?;
+                unsafe { f(arg.as_ptr()) }
+            }
+        };
+        self.take_string(ptr)
+    }
+}
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! WASM plugins, run sandboxed
+//!
+//! Modules get no host imports at all (no WASI, so no files, sockets or
+//! clock), a fuel budget per call and a capped linear memory. Every call
+//! instantiates afresh so no state leaks between checks.
+//!
+//! Exports: `memory`, `alloc(len) -> ptr`, `abi_version() -> i32`, and
+//! `describe()`, `run_check(ptr, len)`, `run_repair(ptr, len)` each
+//! returning `(ptr << 32) | len` of a UTF-8 JSON result in `memory`.
+
+use super::Backend;
+use crate::config::PluginConfig;
+use std::path::Path;
+use wasmtime::{Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Largest result a call may return; reports are a few kilobytes
+const MAX_OUTPUT: usize = 4 * 1024 * 1024;
+
+pub struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+    memory_bytes: usize,
+}
+
+impl WasmPlugin {
+    pub fn load(path: &Path, settings: &PluginConfig) -> Result<WasmPlugin, String> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| format!("{:#}", e))?;
+        let module = Module::from_file(&engine, path)
+            .map_err(|e| format!("Failed to load {}: {:#}", path.display(), e))?;
+
+        Ok(WasmPlugin {
+            engine,
+            module,
+            fuel: settings.wasm_fuel,
+            memory_bytes: settings.wasm_memory_mb as usize * 1024 * 1024,
+        })
+    }
+
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, Instance), String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.memory_bytes)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel).map_err(|e| format!("{:#}", e))?;
+
+        // An empty linker: any import makes instantiation fail
+        let instance = Linker::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .map_err(|e| format!("Failed to instantiate plugin: {:#}", e))?;
+        Ok((store, instance))
+    }
+}
+
+impl Backend for WasmPlugin {
+    fn abi_version(&self) -> Result<u32, String> {
+        let (mut store, instance) = self.instantiate()?;
+        let f = instance
+            .get_typed_func::<(), i32>(&mut store, "abi_version")
+            .map_err(|e| format!("{:#}", e))?;
+        let version = f.call(&mut store, ()).map_err(|e| format!("{:#}", e))?;
+        Ok(version as u32)
+    }
+
+    fn call(&self, export: &str, arg: Option<&str>) -> Result<String, String> {
+        let (mut store, instance) = self.instantiate()?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or("plugin exports no memory")?;
+
+        let packed = match arg {
+            None => instance
+                .get_typed_func::<(), i64>(&mut store, export)
+                .and_then(|f| f.call(&mut store, ())),
+            Some(arg) => {
+                let alloc = instance
+                    .get_typed_func::<i32, i32>(&mut store, "alloc")
+                    .map_err(|e| format!("{:#}", e))?;
+                let len = arg.len() as i32;
+                let ptr = alloc
+                    .call(&mut store, len)
+                    .map_err(|e| format!("{:#}", e))?;
+                memory
+                    .write(&mut store, ptr as u32 as usize, arg.as_bytes())
+                    .map_err(|e| format!("{:#}", e))?;
+                instance
+                    .get_typed_func::<(i32, i32), i64>(&mut store, export)
+                    .and_then(|f| f.call(&mut store, (ptr, len)))
+            }
+        }
+        .map_err(|e| format!("{} failed: {:#}", export, e))?;
+
+        // Both halves are the plugin's to choose: check them against its
+        // memory and the cap before copying anything out
+        let ptr = (packed as u64 >> 32) as usize;
+        let len = (packed as u64 & 0xffff_ffff) as usize;
+        if len > MAX_OUTPUT {
+            return Err(format!(
+                "{} returned {} bytes, more than the {} a result may have",
+                export, len, MAX_OUTPUT
+            ));
+        }
+        let out = ptr
+            .checked_add(len)
+            .and_then(|end| memory.data(&store).get(ptr..end))
+            .ok_or_else(|| format!("{} returned a result outside its memory", export))?;
+        String::from_utf8(out.to_vec())
+            .map_err(|e| format!("{} returned invalid UTF-8: {}", export, e))
+    }
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Scrub identifying details from text that leaves the machine
+
+use crate::config::RedactionConfig;
+use regex::Regex;
+
+const MAC: &str = r"\b(?:[0-9A-Fa-f]{2}[:-]){5}[0-9A-Fa-f]{2}\b";
+const IPV4: &str = r"\b(?:\d{1,3}\.){3}\d{1,3}\b";
+const IPV6: &str = r"\b(?:[0-9A-Fa-f]{1,4}:){2,7}[0-9A-Fa-f]{1,4}\b";
+
+/// Apply the configured redaction rules to `text`
+pub fn redact(text: &str, rules: &RedactionConfig) -> String {
+    let mut out = text.to_string();
+
+    // MACs first: they would otherwise match the IPv6 pattern
+    if rules.mac_addresses {
+        out = replace(&out, MAC, "[mac]");
+    }
+    if rules.ip_addresses {
+        out = replace(&out, IPV4, "[ip]");
+        out = replace(&out, IPV6, "[ip]");
+    }
+    if rules.hostname {
+        if let Some(name) = hostname() {
+            out = out.replace(&name, "[hostname]");
+        }
+    }
+    for pattern in &rules.patterns {
+        // Patterns are validated when the config is loaded
+        out = replace(&out, pattern, "[redacted]");
+    }
+    out
+}
+
+fn replace(text: &str, pattern: &str, with: &str) -> String {
+    match Regex::new(pattern) {
+        Ok(re) => re.replace_all(text, with).into_owned(),
+        Err(_) => text.to_string(),
+    }
+}
+
+fn hostname() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    let name = std::fs::read_to_string("/proc/sys/kernel/hostname").ok();
+    #[cfg(not(target_os = "linux"))]
+    let name = std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok();
+
+    name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
+}
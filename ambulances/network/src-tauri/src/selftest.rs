@@ -0,0 +1,269 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Startup self-test
+//!
+//! Verifies up front what a diagnosis will need (valid config, a working
+//! backend, permission to send ICMP, and the helper tools the backend
+//! shells out to) so the UI can point at the missing piece instead of
+//! failing halfway through a run.
+
+use crate::config::{self, Config};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long `backend version` may take before the backend counts as broken
+const BACKEND_PROBE: Duration = Duration::from_secs(5);
+
+/// Tools the backend cannot diagnose without
+const REQUIRED_HELPERS: &[&str] = &["ip", "ping"];
+/// Tools only some checks and repairs use
+const OPTIONAL_HELPERS: &[&str] = &["dig", "systemctl", "networkctl", "dhclient"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pass,
+    /// Works, but some checks or repairs will be unavailable
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// What the user can do about a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remedy: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    /// No check failed; diagnostics can run
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Pass,
+            detail: detail.into(),
+            remedy: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, remedy: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, remedy: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+}
+
+pub async fn run(config: &Config) -> Readiness {
+    let checks = vec![
+        check_config(config),
+        check_backend(config).await,
+        check_icmp(),
+        check_helpers(),
+    ];
+    let ready = checks.iter().all(|c| c.status != Status::Fail);
+    Readiness { ready, checks }
+}
+
+fn check_config(config: &Config) -> Check {
+    match config.validate() {
+        Ok(()) => Check::pass("config", "configuration is valid"),
+        Err(e) => Check::fail(
+            "config",
+            e,
+            format!("Fix {}", config::config_dir().join(config::CONFIG_FILE).display()),
+        ),
+    }
+}
+
+async fn check_backend(config: &Config) -> Check {
+    let executable = &config.backend.executable;
+    let mut command = tokio::process::Command::new(executable);
+    command.arg("version").kill_on_drop(true);
+
+    let remedy = format!(
+        "Build the D backend (see BUILD_D.md) or point backend.executable at it (currently {})",
+        executable
+    );
+    match tokio::time::timeout(BACKEND_PROBE, command.output()).await {
+        Err(_) => Check::fail("backend", "backend did not answer `version` in time", remedy),
+        Ok(Err(e)) => Check::fail("backend", format!("cannot run {}: {}", executable, e), remedy),
+        Ok(Ok(output)) if !output.status.success() => Check::fail(
+            "backend",
+            format!("`{} version` exited with {}", executable, output.status),
+            remedy,
+        ),
+        Ok(Ok(output)) => Check::pass(
+            "backend",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+    }
+}
+
+/// Effective user and group ids, from `/proc/self/status`
+#[cfg(target_os = "linux")]
+fn effective_ids(status: &str) -> Option<(u32, u32)> {
+    let field = |key: &str| {
+        status
+            .lines()
+            .find_map(|l| l.strip_prefix(key))?
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()
+    };
+    Some((field("Uid:")?, field("Gid:")?))
+}
+
+/// Whether `getcap`'s line for a file, `<path> cap_a,cap_b=ep` or
+/// libcap 2.2x's `<path> = cap_a+ep`, grants CAP_NET_RAW as permitted;
+/// a bare `=ep` grants every capability
+#[cfg(target_os = "linux")]
+fn grants_net_raw(getcap: &str) -> bool {
+    getcap
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|clause| {
+            let at = clause.find(['=', '+', '-'])?;
+            Some((&clause[..at], &clause[at..]))
+        })
+        .any(|(names, flags)| {
+            (names.is_empty() || names.split(',').any(|n| n == "cap_net_raw" || n == "all"))
+                && !flags.starts_with('-')
+                && flags.contains('p')
+        })
+}
+
+/// File capabilities of `path`, as `getcap` prints them; libcap ships it
+/// with the `setcap` the remedy suggests
+#[cfg(target_os = "linux")]
+fn file_capabilities(path: &Path) -> Option<String> {
+    let output = std::process::Command::new("getcap")
+        .arg(path)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether ping can work: root, CAP_NET_RAW, unprivileged ICMP sockets
+/// covering our group, or a ping binary that is setuid root or granted
+/// CAP_NET_RAW as a file capability
+#[cfg(target_os = "linux")]
+fn check_icmp() -> Check {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    const CAP_NET_RAW: u32 = 13;
+
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let (uid, gid) = effective_ids(&status).unwrap_or((u32::MAX, u32::MAX));
+    if uid == 0 {
+        return Check::pass("icmp", "running as root");
+    }
+
+    let cap_eff = status
+        .lines()
+        .find_map(|l| l.strip_prefix("CapEff:"))
+        .and_then(|v| u64::from_str_radix(v.trim(), 16).ok())
+        .unwrap_or(0);
+    if cap_eff & (1 << CAP_NET_RAW) != 0 {
+        return Check::pass("icmp", "CAP_NET_RAW is effective");
+    }
+
+    let range = std::fs::read_to_string("/proc/sys/net/ipv4/ping_group_range").unwrap_or_default();
+    let mut bounds = range.split_whitespace().filter_map(|v| v.parse::<u32>().ok());
+    if let (Some(low), Some(high)) = (bounds.next(), bounds.next()) {
+        if (low..=high).contains(&gid) {
+            return Check::pass("icmp", "unprivileged ICMP sockets are allowed for this group");
+        }
+    }
+
+    if let Some(ping) = find_helper("ping") {
+        if let Ok(meta) = std::fs::metadata(&ping) {
+            if meta.uid() == 0 && meta.permissions().mode() & 0o4000 != 0 {
+                return Check::pass("icmp", format!("{} is setuid root", ping.display()));
+            }
+        }
+        if file_capabilities(&ping).is_some_and(|caps| grants_net_raw(&caps)) {
+            return Check::pass("icmp", format!("{} has CAP_NET_RAW", ping.display()));
+        }
+    }
+
+    Check::fail(
+        "icmp",
+        "no raw socket access: not root, no CAP_NET_RAW for us or ping, and ping_group_range excludes this group",
+        "Run `sudo setcap cap_net_raw+ep $(command -v ping)` or widen net.ipv4.ping_group_range",
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_icmp() -> Check {
+    Check::pass("icmp", "not checked on this platform")
+}
+
+fn find_helper(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.with_extension("exe").is_file()
+}
+
+fn check_helpers() -> Check {
+    let missing = |names: &[&'static str]| -> Vec<&'static str> {
+        names
+            .iter()
+            .copied()
+            .filter(|name| find_helper(name).is_none())
+            .collect()
+    };
+    let required = missing(REQUIRED_HELPERS);
+    let optional = missing(OPTIONAL_HELPERS);
+
+    if !required.is_empty() {
+        Check::fail(
+            "helpers",
+            format!("missing required tools: {}", required.join(", ")),
+            "Install iproute2 and iputils-ping (or your distribution's equivalents)",
+        )
+    } else if !optional.is_empty() {
+        Check::warn(
+            "helpers",
+            format!("missing optional tools: {}", optional.join(", ")),
+            "Some DNS and interface repairs will be skipped until these are installed",
+        )
+    } else {
+        Check::pass("helpers", "all helper tools found")
+    }
+}
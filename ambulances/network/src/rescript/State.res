@@ -112,10 +112,7 @@ let executeCmd = (cmd: cmd, dispatch: msg => unit): unit => {
       Promise.resolve()
     })
     ->Promise.catch(err => {
-      let message = switch err {
-      | Exn.Error(obj) => Exn.message(obj)->Option.getOr("Unknown error")
-      | _ => "Unknown error"
-      }
+      let message = TauriBindings.errorMessage(err)
       dispatch(DiagnosticsComplete(Error(message)))
       Promise.resolve()
     })
@@ -128,10 +125,7 @@ let executeCmd = (cmd: cmd, dispatch: msg => unit): unit => {
       Promise.resolve()
     })
     ->Promise.catch(err => {
-      let message = switch err {
-      | Exn.Error(obj) => Exn.message(obj)->Option.getOr("Unknown error")
-      | _ => "Unknown error"
-      }
+      let message = TauriBindings.errorMessage(err)
       dispatch(RepairComplete(Error(message)))
       Promise.resolve()
     })
@@ -8,6 +8,29 @@ external invoke: (string, 'a) => promise<'b> = "invoke"
 @module("@tauri-apps/api/core")
 external invokeSimple: string => promise<'a> = "invoke"
 
+// Commands reject with ambulance-core's error envelope
+type commandError = {
+  code: string,
+  category: string,
+  message: string,
+  hint?: string,
+}
+
+// The message of a rejected command, and its hint when it has one
+let errorMessage = (err: exn): string => {
+  switch err {
+  | Exn.Error(obj) => {
+      let error: commandError = Obj.magic(obj)
+      switch (Exn.message(obj), error.hint) {
+      | (Some(message), Some(hint)) => `${message}. ${hint}`
+      | (Some(message), None) => message
+      | (None, _) => "Unknown error"
+      }
+    }
+  | _ => "Unknown error"
+  }
+}
+
 // Run diagnostics command
 let runDiagnostics = (): promise<Types.diagnosticResult> => {
   invokeSimple("run_diagnostics")
@@ -28,6 +51,61 @@ let getPlatformInfo = (): promise<string> => {
   invokeSimple("get_platform_info")
 }
 
+// Readiness report: config, backend, ICMP permission and helper tools
+let selfTest = (): promise<Js.Json.t> => {
+  invokeSimple("self_test")
+}
+
+// Get the active configuration
+let getConfig = (): promise<Js.Json.t> => {
+  invokeSimple("get_config")
+}
+
+// Validate, save and apply a new configuration
+let setConfig = (config: Js.Json.t): promise<unit> => {
+  invoke("set_config", {"newConfig": config})
+}
+
+// Recent application log lines for problem reports
+let getAppLogs = (limit: option<int>): promise<array<string>> => {
+  invoke("get_app_logs", {"limit": limit})
+}
+
+// Plugins that loaded, with the checks and repairs they offer
+let listPlugins = (): promise<array<Js.Json.t>> => {
+  invokeSimple("list_plugins")
+}
+
+// Run a plugin repair addressed as "plugin/repair-id"
+let runPluginRepair = (address: string): promise<Js.Json.t> => {
+  invoke("run_plugin_repair", {"address": address})
+}
+
+// Capture the current network as the known-good baseline; extend widens latency ranges
+let captureBaseline = (extend: bool): promise<Js.Json.t> => {
+  invoke("capture_baseline", {"extend": extend})
+}
+
+// The saved baseline, if one has been captured
+let getBaseline = (): promise<Js.Nullable.t<Js.Json.t>> => {
+  invokeSimple("get_baseline")
+}
+
+// Diagnose and list deviations from the saved baseline
+let compareBaseline = (): promise<Js.Json.t> => {
+  invokeSimple("compare_baseline")
+}
+
+// Save the current network configuration; resolves to the snapshot's path
+let captureSnapshot = (): promise<string> => {
+  invokeSimple("capture_snapshot")
+}
+
+// Restore a snapshot, such as a repair's; clone keeps this machine's own profiles
+let restoreSnapshot = (path: string, clone: bool): promise<Js.Json.t> => {
+  invoke("restore_snapshot", {"path": path, "clone": clone})
+}
+
 // Event listeners for Tauri events
 @module("@tauri-apps/api/event")
 external listen: (string, 'payload => unit) => promise<unit> = "listen"
@@ -114,6 +114,8 @@ type repairResult = {
     "added_routes": array<route>,
     "removed_routes": array<route>,
   },
+  // Snapshot taken before the repair, to roll it back with
+  snapshot: option<string>,
 }
 
 // Application State
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Installed packages with unmet dependencies
+
+use crate::managers::PackageManager;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyDiagnostics {
+    pub broken: Vec<String>,
+    /// Why the check could not run
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(manager: &dyn PackageManager) -> DependencyDiagnostics {
+    let mut diag = DependencyDiagnostics::default();
+    match manager.broken() {
+        Ok(broken) => diag.broken = broken,
+        Err(e) => diag.error = Some(e),
+    }
+    for problem in &diag.broken {
+        diag.warnings.push(format!("Unmet dependency: {}", problem));
+    }
+    if !diag.broken.is_empty() {
+        // Every manager's fix can remove packages, so it stays manual
+        diag.recommendations.push(match manager.name() {
+            "apt" => "Review what `apt-get -f install` would change, then run it".to_string(),
+            "dnf" => "Review `dnf distro-sync`, then run it".to_string(),
+            _ => "Install the missing dependencies or run `pacman -Syu`".to_string(),
+        });
+    }
+    diag
+}
+
+impl DependencyDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Dependency Diagnostics ===");
+        match &self.error {
+            Some(e) => println!("✗ {}", e),
+            None => println!(
+                "{} Broken dependencies: {}",
+                mark(self.broken.is_empty()),
+                self.broken.len()
+            ),
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
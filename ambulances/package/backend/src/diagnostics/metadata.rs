@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Age of the cached repository metadata
+//!
+//! Stale metadata hides available updates, security fixes among them, and
+//! makes installs fail on files the mirror has since removed.
+
+use crate::managers::PackageManager;
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+
+/// Metadata older than this is stale
+pub const STALE_DAYS: u64 = 7;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetadataDiagnostics {
+    pub path: String,
+    /// `None` when the cache is missing
+    pub age_days: Option<u64>,
+    pub stale: bool,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(manager: &dyn PackageManager) -> MetadataDiagnostics {
+    let dir = manager.metadata_dir();
+    let age_days = system::age(dir).map(|age| age.as_secs() / 86_400);
+    let mut diag = MetadataDiagnostics {
+        path: dir.display().to_string(),
+        age_days,
+        stale: age_days.map_or(true, |days| days >= STALE_DAYS),
+        ..MetadataDiagnostics::default()
+    };
+
+    match age_days {
+        Some(days) if diag.stale => diag
+            .warnings
+            .push(format!("Repository metadata is {} days old", days)),
+        None => diag
+            .warnings
+            .push(format!("No repository metadata in {}", diag.path)),
+        _ => {}
+    }
+    if diag.stale {
+        diag.recommendations
+            .push("Refresh the metadata: sudo package-ambulance repair metadata".to_string());
+    }
+    diag
+}
+
+impl MetadataDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Metadata Diagnostics ===");
+        match self.age_days {
+            Some(days) => println!("{} Metadata age: {} days", mark(!self.stale), days),
+            None => println!("✗ Metadata: missing"),
+        }
+        if verbose {
+            println!("    {}", self.path);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Package diagnostics, one module per report section
+//!
+//! The package manager is found once and shared by every section. The
+//! sections are independent, so they run as ambulance-core jobs at the
+//! same time, within the scan's budget; a section not done in time says
+//! so in its warnings, and one the budget cut short is left empty with
+//! its timing saying why.
+
+pub mod dependencies;
+pub mod metadata;
+pub mod packages;
+pub mod security;
+pub mod transactions;
+
+use crate::managers::{self, PackageManager};
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use ambulance_core::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a section may take; dnf and apt can wait on each other's
+/// lock, and on the network when their metadata is stale
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+enum Section {
+    Dependencies(dependencies::DependencyDiagnostics),
+    Transactions(transactions::TransactionDiagnostics),
+    Packages(packages::PackageDiagnostics),
+    Metadata(metadata::MetadataDiagnostics),
+    Security(security::SecurityDiagnostics),
+}
+
+pub fn run() -> Result<DiagnosticResult, Error> {
+    let manager: Arc<dyn PackageManager> = managers::required()?.into();
+    let job = |id, diagnose: fn(&dyn PackageManager) -> Section| {
+        let manager = Arc::clone(&manager);
+        Job::blocking(id, move |_| Ok(diagnose(manager.as_ref())))
+    };
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(job("dependencies", |m| {
+            Section::Dependencies(dependencies::diagnose(m))
+        }))
+        .job(job("transactions", |m| {
+            Section::Transactions(transactions::diagnose(m))
+        }))
+        .job(job("packages", |m| {
+            Section::Packages(packages::diagnose(m))
+        }))
+        .job(job("metadata", |m| {
+            Section::Metadata(metadata::diagnose(m))
+        }))
+        .job(job("security", |m| {
+            Section::Security(security::diagnose(m))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        manager: manager.name(),
+        dependencies: Default::default(),
+        transactions: Default::default(),
+        packages: Default::default(),
+        metadata: Default::default(),
+        security: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Dependencies(d)) => result.dependencies = d,
+            Some(Section::Transactions(d)) => result.transactions = d,
+            Some(Section::Packages(d)) => result.packages = d,
+            Some(Section::Metadata(d)) => result.metadata = d,
+            Some(Section::Security(d)) => result.security = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "dependencies" => &mut result.dependencies.warnings,
+                    "transactions" => &mut result.transactions.warnings,
+                    "packages" => &mut result.packages.warnings,
+                    "metadata" => &mut result.metadata.warnings,
+                    _ => &mut result.security.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Duplicate packages and packages no repository provides
+//!
+//! Foreign packages are not errors, just unmanaged: they get no updates,
+//! security fixes included. They are listed, not warned about one by one.
+
+use crate::managers::PackageManager;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PackageDiagnostics {
+    pub duplicates: Vec<String>,
+    pub foreign: Vec<String>,
+    /// Why the foreign package list could not be read
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(manager: &dyn PackageManager) -> PackageDiagnostics {
+    let mut diag = PackageDiagnostics {
+        duplicates: manager.duplicates(),
+        ..PackageDiagnostics::default()
+    };
+    match manager.foreign() {
+        Ok(foreign) => diag.foreign = foreign,
+        Err(e) => diag.error = Some(e),
+    }
+
+    for name in &diag.duplicates {
+        diag.warnings
+            .push(format!("{} is installed more than once", name));
+    }
+    if !diag.duplicates.is_empty() {
+        diag.recommendations
+            .push("Remove the older copies after review: dnf remove --duplicates".to_string());
+    }
+    if !diag.foreign.is_empty() {
+        diag.warnings.push(format!(
+            "{} package(s) come from no configured repository and receive no updates",
+            diag.foreign.len()
+        ));
+        diag.recommendations.push(
+            "Check the foreign packages are still needed and that their sources are maintained"
+                .to_string(),
+        );
+    }
+    diag
+}
+
+impl PackageDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Package Diagnostics ===");
+        println!(
+            "{} Duplicate packages: {}",
+            mark(self.duplicates.is_empty()),
+            self.duplicates.len()
+        );
+        match &self.error {
+            Some(e) => println!("- Foreign packages: unknown ({})", e),
+            None => println!("- Foreign packages: {}", self.foreign.len()),
+        }
+        if verbose {
+            for name in &self.foreign {
+                println!("    {}", name);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
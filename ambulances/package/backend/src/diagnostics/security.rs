@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Pending updates that fix security issues
+//!
+//! Only as current as the cached metadata; the metadata section says when
+//! that is stale.
+
+use crate::managers::PackageManager;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SecurityDiagnostics {
+    pub updates: Vec<String>,
+    /// Why advisories could not be read
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(manager: &dyn PackageManager) -> SecurityDiagnostics {
+    let mut diag = SecurityDiagnostics::default();
+    match manager.security_updates() {
+        Ok(mut updates) => {
+            updates.sort();
+            updates.dedup();
+            diag.updates = updates;
+        }
+        Err(e) => diag.error = Some(e),
+    }
+    if !diag.updates.is_empty() {
+        diag.warnings.push(format!(
+            "{} security update(s) pending: {}",
+            diag.updates.len(),
+            diag.updates.join(", ")
+        ));
+        diag.recommendations.push(match manager.name() {
+            "apt" => "Install them: sudo apt-get upgrade".to_string(),
+            "dnf" => "Install them: sudo dnf upgrade --security".to_string(),
+            _ => "Install them: sudo pacman -Syu".to_string(),
+        });
+    }
+    diag
+}
+
+impl SecurityDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Security Update Diagnostics ===");
+        match &self.error {
+            Some(e) => println!("- Security updates: unknown ({})", e),
+            None => println!(
+                "{} Security updates pending: {}",
+                mark(self.updates.is_empty()),
+                self.updates.len()
+            ),
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Installs and upgrades that stopped half way
+
+use crate::managers::PackageManager;
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TransactionDiagnostics {
+    pub interrupted: Vec<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose(manager: &dyn PackageManager) -> TransactionDiagnostics {
+    let mut diag = TransactionDiagnostics {
+        interrupted: manager.interrupted(),
+        ..TransactionDiagnostics::default()
+    };
+    diag.warnings.extend(diag.interrupted.iter().cloned());
+    if !diag.interrupted.is_empty() {
+        diag.recommendations.push(
+            "Complete the interrupted transaction: sudo package-ambulance repair transactions"
+                .to_string(),
+        );
+    }
+    diag
+}
+
+impl TransactionDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Transaction Diagnostics ===");
+        println!(
+            "{} Interrupted transactions: {}",
+            mark(self.interrupted.is_empty()),
+            if self.interrupted.is_empty() {
+                "none"
+            } else {
+                "found"
+            }
+        );
+        if verbose {
+            for line in &self.interrupted {
+                println!("    {}", line);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
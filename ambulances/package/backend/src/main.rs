@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Package Ambulance backend
+//!
+//! Diagnoses package manager health on apt, dnf and pacman systems: broken
+//! dependencies, interrupted transactions, duplicate and foreign packages,
+//! stale metadata and pending security updates. Repairs are limited to
+//! completing interrupted transactions and refreshing metadata. `--json`
+//! output follows the network ambulance's report model.
+
+mod diagnostics;
+mod managers;
+mod repairs;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Error};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Package Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: package-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all package diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = match diagnostics::run() {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Package Ambulance ({})", result.manager);
+    println!("=================\n");
+    result.dependencies.print(verbose);
+    result.transactions.print(verbose);
+    result.packages.print(verbose);
+    result.metadata.print(verbose);
+    result.security.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = match diagnostics::run() {
+        Ok(result) => result,
+        Err(e) => return fail(e, false),
+    };
+    let sections = [
+        ("Dependencies", &result.dependencies.warnings),
+        ("Transactions", &result.transactions.warnings),
+        ("Packages", &result.packages.warnings),
+        ("Metadata", &result.metadata.warnings),
+        ("Security", &result.security.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    exit::findings(result.has_warnings())
+}
+
+fn run_repair(target: &str, json: bool) -> ExitCode {
+    if let Err(e) = ambulance_privilege::require(&format!("package-ambulance repair {}", target)) {
+        return fail(e, json);
+    }
+
+    let result = match repairs::run(target) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [
+        (
+            "transactions",
+            "Transaction Completion",
+            &result.transactions_repair,
+        ),
+        ("metadata", "Metadata Refresh", &result.metadata_repair),
+    ];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Package Ambulance - Repair Mode");
+        println!("===============================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, json),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Package Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
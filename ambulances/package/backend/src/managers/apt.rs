@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Debian and derivatives: apt over dpkg
+
+use super::{lines, PackageManager};
+use crate::system;
+use std::path::Path;
+
+/// dpkg keeps a journal of in-flight status changes here
+const DPKG_UPDATES: &str = "/var/lib/dpkg/updates";
+
+pub struct Apt;
+
+impl PackageManager for Apt {
+    fn name(&self) -> &'static str {
+        "apt"
+    }
+
+    fn broken(&self) -> Result<Vec<String>, String> {
+        let output = system::output("apt-get", &["check", "-q"]).ok_or("failed to run apt-get")?;
+        if output.status.success() {
+            return Ok(Vec::new());
+        }
+        // ` foo : Depends: bar (>= 2) but it is not going to be installed`
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let broken: Vec<String> = stdout
+            .lines()
+            .filter(|l| l.starts_with(' ') && l.contains(" : "))
+            .map(|l| l.trim().to_string())
+            .collect();
+        if broken.is_empty() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("apt-get check failed: {}", stderr.trim()));
+        }
+        Ok(broken)
+    }
+
+    fn interrupted(&self) -> Vec<String> {
+        let mut evidence = Vec::new();
+        let pending = std::fs::read_dir(DPKG_UPDATES)
+            .map(|entries| entries.flatten().count())
+            .unwrap_or(0);
+        if pending > 0 {
+            evidence.push(format!(
+                "dpkg was interrupted: {} journal entries in {}",
+                pending, DPKG_UPDATES
+            ));
+        }
+        // --audit lists half-installed and unconfigured packages, indented
+        if let Some(output) = system::output("dpkg", &["--audit"]) {
+            evidence.extend(
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|l| l.starts_with(' '))
+                    .filter_map(|l| l.split_whitespace().next())
+                    .map(|pkg| format!("{} is not fully installed", pkg)),
+            );
+        }
+        evidence
+    }
+
+    fn foreign(&self) -> Result<Vec<String>, String> {
+        // `foo/now 1.0 amd64 [installed,local]`
+        Ok(lines("apt", &["list", "--installed"])?
+            .into_iter()
+            .filter(|l| l.ends_with(",local]"))
+            .filter_map(|l| l.split('/').next().map(str::to_string))
+            .collect())
+    }
+
+    fn metadata_dir(&self) -> &'static Path {
+        Path::new("/var/lib/apt/lists")
+    }
+
+    fn security_updates(&self) -> Result<Vec<String>, String> {
+        // `Inst openssl [3.0.1] (3.0.2 Debian-Security:12/stable-security [amd64])`
+        Ok(lines("apt-get", &["-s", "-q", "dist-upgrade"])?
+            .into_iter()
+            .filter(|l| l.starts_with("Inst ") && l.contains("-security"))
+            .filter_map(|l| l.split_whitespace().nth(1).map(str::to_string))
+            .collect())
+    }
+
+    fn complete_interrupted(&self) -> Result<Vec<String>, String> {
+        system::run("dpkg", &["--configure", "-a"])?;
+        Ok(vec![
+            "Configured every unpacked package (dpkg --configure -a)".to_string(),
+        ])
+    }
+
+    fn refresh_metadata(&self) -> Result<String, String> {
+        system::run("apt-get", &["update", "-q"])?;
+        Ok("Refreshed package lists (apt-get update)".to_string())
+    }
+}
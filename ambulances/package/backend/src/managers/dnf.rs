@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Fedora and RHEL: dnf over rpm
+//!
+//! rpm has no journal to resume, so an interrupted transaction shows up as
+//! the same package installed twice. Removing the stale copies is left to
+//! the user, as it uninstalls packages.
+
+use super::{lines, PackageManager};
+use crate::system;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Packages rpm installs side by side on purpose
+const INSTALL_ONLY: &[&str] = &["kernel", "gpg-pubkey", "kmod-"];
+
+pub struct Dnf;
+
+impl PackageManager for Dnf {
+    fn name(&self) -> &'static str {
+        "dnf"
+    }
+
+    fn broken(&self) -> Result<Vec<String>, String> {
+        // Exits non-zero and prints one problem per line when anything is wrong
+        lines("dnf", &["check", "--dependencies", "-q"])
+    }
+
+    fn interrupted(&self) -> Vec<String> {
+        let duplicates = self.duplicates();
+        if duplicates.is_empty() {
+            return Vec::new();
+        }
+        vec![format!(
+            "{} package(s) installed twice, left by an interrupted transaction",
+            duplicates.len()
+        )]
+    }
+
+    fn duplicates(&self) -> Vec<String> {
+        let Ok(installed) = lines("rpm", &["-qa", "--qf", "%{NAME}.%{ARCH}\\n"]) else {
+            return Vec::new();
+        };
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        for name in installed {
+            *counts.entry(name).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|(name, count)| *count > 1 && !INSTALL_ONLY.iter().any(|p| name.starts_with(p)))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn foreign(&self) -> Result<Vec<String>, String> {
+        // `Extra Packages` header, then `name.arch  version  @repo`
+        Ok(lines("dnf", &["list", "--extras", "-q"])?
+            .into_iter()
+            .filter(|l| !l.starts_with("Extra Packages"))
+            .filter_map(|l| l.split_whitespace().next().map(str::to_string))
+            .collect())
+    }
+
+    fn metadata_dir(&self) -> &'static Path {
+        if Path::new("/var/cache/libdnf5").is_dir() {
+            Path::new("/var/cache/libdnf5")
+        } else {
+            Path::new("/var/cache/dnf")
+        }
+    }
+
+    fn security_updates(&self) -> Result<Vec<String>, String> {
+        // `FEDORA-2024-1a2b3c Important/Sec. openssl-3.1.4-1.fc39.x86_64`
+        Ok(lines("dnf", &["updateinfo", "list", "--security", "-q"])?
+            .into_iter()
+            .filter_map(|l| l.split_whitespace().nth(2).map(str::to_string))
+            .collect())
+    }
+
+    fn complete_interrupted(&self) -> Result<Vec<String>, String> {
+        Err(
+            "dnf cannot resume an interrupted transaction; review `dnf remove --duplicates` \
+             and run it yourself"
+                .to_string(),
+        )
+    }
+
+    fn refresh_metadata(&self) -> Result<String, String> {
+        system::run("dnf", &["makecache", "-q"])?;
+        Ok("Refreshed repository metadata (dnf makecache)".to_string())
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! One implementation per package manager
+//!
+//! The diagnostics and repairs only talk to `PackageManager`; each module
+//! maps those questions onto its tool's commands and on-disk state, and
+//! says so plainly where a tool has no safe answer.
+
+mod apt;
+mod dnf;
+mod pacman;
+
+use crate::system;
+use ambulance_core::error::{Category, Error};
+use std::path::Path;
+
+/// `Send` and `Sync` so the diagnostics can ask it from their own threads
+pub trait PackageManager: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Installed packages whose dependencies are not satisfied
+    fn broken(&self) -> Result<Vec<String>, String>;
+
+    /// Evidence that a previous install or upgrade stopped half way
+    fn interrupted(&self) -> Vec<String>;
+
+    /// Packages installed more than once
+    fn duplicates(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Installed packages no configured repository provides
+    fn foreign(&self) -> Result<Vec<String>, String>;
+
+    /// Where the repository metadata is cached
+    fn metadata_dir(&self) -> &'static Path;
+
+    /// Pending updates that fix security issues
+    fn security_updates(&self) -> Result<Vec<String>, String>;
+
+    /// Finish or clean up after an interrupted transaction
+    fn complete_interrupted(&self) -> Result<Vec<String>, String>;
+
+    /// Download fresh repository metadata without changing any package
+    fn refresh_metadata(&self) -> Result<String, String>;
+}
+
+/// The system's package manager, apt first since dnf and pacman can be
+/// installed on Debian for building other distributions' images
+pub fn detect() -> Option<Box<dyn PackageManager>> {
+    if system::has("apt-get") && system::has("dpkg") {
+        Some(Box::new(apt::Apt))
+    } else if system::has("dnf") && system::has("rpm") {
+        Some(Box::new(dnf::Dnf))
+    } else if system::has("pacman") {
+        Some(Box::new(pacman::Pacman))
+    } else {
+        None
+    }
+}
+
+/// [`detect`], for commands that cannot go on without a package manager
+pub fn required() -> Result<Box<dyn PackageManager>, Error> {
+    detect().ok_or_else(|| {
+        Error::new(
+            "unavailable.package-manager",
+            Category::Unavailable,
+            "No supported package manager found (apt, dnf or pacman)",
+        )
+    })
+}
+
+/// Non-empty stdout lines of a command, whatever its exit status
+fn lines(program: &str, args: &[&str]) -> Result<Vec<String>, String> {
+    let output =
+        system::output(program, args).ok_or_else(|| format!("failed to run {}", program))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Arch and derivatives: pacman
+//!
+//! Refreshing the sync databases without upgrading (`pacman -Sy`) leads to
+//! partial upgrades, which Arch does not support, so metadata is never
+//! refreshed on its own here.
+
+use super::{lines, PackageManager};
+use crate::system;
+use std::path::Path;
+
+/// Held for the length of every transaction
+const DB_LOCK: &str = "/var/lib/pacman/db.lck";
+
+pub struct Pacman;
+
+impl Pacman {
+    fn stale_lock(&self) -> bool {
+        Path::new(DB_LOCK).exists() && !system::is_running("pacman")
+    }
+}
+
+impl PackageManager for Pacman {
+    fn name(&self) -> &'static str {
+        "pacman"
+    }
+
+    fn broken(&self) -> Result<Vec<String>, String> {
+        // `missing required dependency: foo for bar`; exits non-zero when any
+        lines("pacman", &["-Dkq"])
+    }
+
+    fn interrupted(&self) -> Vec<String> {
+        if self.stale_lock() {
+            vec![format!(
+                "{} exists but pacman is not running; a transaction was interrupted",
+                DB_LOCK
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn foreign(&self) -> Result<Vec<String>, String> {
+        lines("pacman", &["-Qmq"])
+    }
+
+    fn metadata_dir(&self) -> &'static Path {
+        Path::new("/var/lib/pacman/sync")
+    }
+
+    fn security_updates(&self) -> Result<Vec<String>, String> {
+        if !system::has("arch-audit") {
+            return Err("install arch-audit to check for security advisories".to_string());
+        }
+        // -u limits to advisories an upgrade fixes, -q to package names
+        lines("arch-audit", &["-uq"])
+    }
+
+    fn complete_interrupted(&self) -> Result<Vec<String>, String> {
+        if !self.stale_lock() {
+            return Ok(Vec::new());
+        }
+        std::fs::remove_file(DB_LOCK).map_err(|e| format!("cannot remove {}: {}", DB_LOCK, e))?;
+        Ok(vec![
+            format!("Removed stale {}", DB_LOCK),
+            "Finish the interrupted upgrade with: pacman -Syu".to_string(),
+        ])
+    }
+
+    fn refresh_metadata(&self) -> Result<String, String> {
+        Err(
+            "not refreshed: `pacman -Sy` alone risks a partial upgrade; run `pacman -Syu`"
+                .to_string(),
+        )
+    }
+}
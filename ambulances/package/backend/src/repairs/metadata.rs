@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Refresh the repository metadata
+
+use crate::managers::PackageManager;
+use crate::report::RepairOutcome;
+
+pub fn repair(manager: &dyn PackageManager) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    match manager.refresh_metadata() {
+        Ok(action) => result.actions.push(action),
+        Err(e) => result.errors.push(e),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Package repairs, one module per target
+//!
+//! Only operations that cannot remove or downgrade a package are offered;
+//! everything else stays a recommendation.
+
+pub mod metadata;
+pub mod transactions;
+
+use crate::managers;
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["transactions", "metadata", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+pub fn run(target: &str) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let manager = managers::required()?;
+    let selected = |name: &str| target == name || target == "all";
+
+    // Finish interrupted work before touching the metadata it was using
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        transactions_repair: if selected("transactions") {
+            transactions::repair(manager.as_ref())
+        } else {
+            RepairOutcome::default()
+        },
+        metadata_repair: if selected("metadata") {
+            metadata::repair(manager.as_ref())
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
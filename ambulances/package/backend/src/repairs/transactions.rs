@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Complete an interrupted install or upgrade
+
+use crate::managers::PackageManager;
+use crate::report::RepairOutcome;
+
+pub fn repair(manager: &dyn PackageManager) -> RepairOutcome {
+    if manager.interrupted().is_empty() {
+        return RepairOutcome::not_needed("No interrupted transaction, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    match manager.complete_interrupted() {
+        Ok(actions) => result.actions = actions,
+        Err(e) => result.errors.push(e),
+    }
+    for left in manager.interrupted() {
+        result.errors.push(format!("Still interrupted: {}", left));
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, manager, <section>...}` where
+//! every section carries `warnings` and `recommendations`; `repair --json`
+//! emits `{version, tool, <target>_repair...}` where each carries
+//! `success`, `actions` and `errors`.
+
+use crate::diagnostics::{
+    dependencies::DependencyDiagnostics, metadata::MetadataDiagnostics,
+    packages::PackageDiagnostics, security::SecurityDiagnostics,
+    transactions::TransactionDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "package-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    /// `apt`, `dnf` or `pacman`
+    pub manager: &'static str,
+    pub dependencies: DependencyDiagnostics,
+    pub transactions: TransactionDiagnostics,
+    pub packages: PackageDiagnostics,
+    pub metadata: MetadataDiagnostics,
+    pub security: SecurityDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.dependencies.warnings,
+            &self.transactions.warnings,
+            &self.packages.warnings,
+            &self.metadata.warnings,
+            &self.security.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub transactions_repair: RepairOutcome,
+    pub metadata_repair: RepairOutcome,
+}
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thin helpers over the external tools and kernel interfaces we read
+
+use ambulance_core::host;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime};
+
+/// Run a program, returning its output whatever the exit status
+///
+/// `None` means the program could not be started (usually not installed).
+/// Package tools translate their messages, so they run in the C locale.
+pub fn output(program: &str, args: &[&str]) -> Option<Output> {
+    Command::new(program)
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .ok()
+}
+
+/// Run a program that must succeed, returning its stdout
+pub fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = output(program, args).ok_or_else(|| format!("failed to run {}", program))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether `program` is found on PATH
+pub fn has(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// Whether any process is named `name`
+pub fn is_running(name: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(host::path("/proc")) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .filter_map(|e| std::fs::read_to_string(e.path().join("comm")).ok())
+        .any(|comm| comm.trim() == name)
+}
+
+fn newest(path: &Path, depth: u32) -> Option<SystemTime> {
+    let own = std::fs::metadata(path).ok()?.modified().ok();
+    if depth == 0 || !path.is_dir() {
+        return own;
+    }
+    std::fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter_map(|e| newest(&e.path(), depth - 1))
+        .chain(own)
+        .max()
+}
+
+/// Time since anything in `dir`, or one level below it, last changed
+pub fn age(dir: &Path) -> Option<Duration> {
+    SystemTime::now().duration_since(newest(dir, 2)?).ok()
+}
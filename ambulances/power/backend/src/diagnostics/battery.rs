@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Battery health and wear
+//!
+//! UPower is asked first because it normalises vendor quirks (charge vs
+//! energy counters, bogus design capacities). Without it the same numbers
+//! come from `/sys/class/power_supply`. Health is full capacity as a share
+//! of design capacity.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::Path;
+use systemd_shim::bus::Bus;
+
+const UPOWER: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const DEVICE: &str = "org.freedesktop.UPower.Device";
+
+/// UPower `Type` of a battery
+const TYPE_BATTERY: u32 = 2;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// Health below this is worth knowing about
+pub const WORN_PERCENT: f64 = 80.0;
+
+/// Health below this means the battery should be replaced
+pub const REPLACE_PERCENT: f64 = 50.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Battery {
+    /// Kernel name, e.g. `BAT0`
+    pub name: String,
+    /// `upower` or `sysfs`
+    pub source: &'static str,
+    pub model: Option<String>,
+    /// `charging`, `discharging`, `full`...
+    pub state: String,
+    pub percentage: Option<f64>,
+    /// Full capacity as a percentage of design capacity
+    pub health_percent: Option<f64>,
+    pub cycles: Option<u32>,
+    /// Current charge or discharge rate in watts
+    pub rate_watts: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatteryDiagnostics {
+    pub batteries: Vec<Battery>,
+    /// Running on mains power
+    pub on_ac: Option<bool>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn upower_state(state: u32) -> &'static str {
+    match state {
+        1 => "charging",
+        2 => "discharging",
+        3 => "empty",
+        4 => "full",
+        5 => "pending charge",
+        6 => "pending discharge",
+        _ => "unknown",
+    }
+}
+
+fn upower_battery(bus: &Bus, path: &str) -> Option<Battery> {
+    let u32_prop = |name| bus.get_property_u32(UPOWER, path, DEVICE, name).ok();
+    let f64_prop = |name| bus.get_property_f64(UPOWER, path, DEVICE, name).ok();
+    let str_prop = |name| {
+        bus.get_property_string(UPOWER, path, DEVICE, name)
+            .ok()
+            .filter(|s| !s.is_empty())
+    };
+
+    // Peripheral batteries (mice, headsets) do not power the system
+    let power_supply = bus
+        .get_property_bool(UPOWER, path, DEVICE, "PowerSupply")
+        .unwrap_or(false);
+    if u32_prop("Type")? != TYPE_BATTERY || !power_supply {
+        return None;
+    }
+
+    let full = f64_prop("EnergyFull").unwrap_or(0.0);
+    let design = f64_prop("EnergyFullDesign").unwrap_or(0.0);
+    let cycles = bus
+        .get_property_i32(UPOWER, path, DEVICE, "ChargeCycles")
+        .ok()
+        .and_then(|c| u32::try_from(c).ok())
+        .filter(|&c| c > 0);
+    Some(Battery {
+        name: str_prop("NativePath").unwrap_or_else(|| path.to_string()),
+        source: "upower",
+        model: str_prop("Model"),
+        state: upower_state(u32_prop("State").unwrap_or(0)).to_string(),
+        percentage: f64_prop("Percentage"),
+        health_percent: (design > 0.0).then(|| full / design * 100.0),
+        cycles,
+        rate_watts: f64_prop("EnergyRate").filter(|&r| r > 0.0),
+    })
+}
+
+fn upower_batteries() -> std::io::Result<(Vec<Battery>, Option<bool>)> {
+    let bus = Bus::system()?;
+    let paths = bus.call_object_paths(UPOWER, UPOWER_PATH, UPOWER, "EnumerateDevices")?;
+    let batteries = paths
+        .iter()
+        .filter_map(|p| upower_battery(&bus, p))
+        .collect();
+    let on_battery = bus
+        .get_property_bool(UPOWER, UPOWER_PATH, UPOWER, "OnBattery")
+        .ok();
+    Ok((batteries, on_battery.map(|b| !b)))
+}
+
+fn sysfs_battery(dir: &Path) -> Option<Battery> {
+    let attr = |name: &str| system::read(dir.join(name));
+    let num = |name: &str| system::read_u64(dir.join(name));
+    if attr("type")? != "Battery" || attr("scope").as_deref() == Some("Device") {
+        return None;
+    }
+
+    // Some firmware reports charge (µAh), some energy (µWh); the ratio is the same
+    let full = num("energy_full").or_else(|| num("charge_full"));
+    let design = num("energy_full_design").or_else(|| num("charge_full_design"));
+    let power = num("power_now").or_else(|| {
+        let microamps = num("current_now")?;
+        let microvolts = num("voltage_now")?;
+        Some(microamps * microvolts / 1_000_000)
+    });
+    Some(Battery {
+        name: dir.file_name()?.to_string_lossy().into_owned(),
+        source: "sysfs",
+        model: attr("model_name").filter(|s| !s.is_empty()),
+        state: attr("status").unwrap_or_default().to_lowercase(),
+        percentage: num("capacity").map(|c| c as f64),
+        health_percent: match (full, design) {
+            (Some(full), Some(design)) if design > 0 => Some(full as f64 / design as f64 * 100.0),
+            _ => None,
+        },
+        cycles: num("cycle_count")
+            .and_then(|c| u32::try_from(c).ok())
+            .filter(|&c| c > 0),
+        rate_watts: power.filter(|&p| p > 0).map(|p| p as f64 / 1_000_000.0),
+    })
+}
+
+/// Whether any mains adapter reports `online`
+fn sysfs_on_ac(supplies: &[std::path::PathBuf]) -> Option<bool> {
+    let adapters: Vec<_> = supplies
+        .iter()
+        .filter(|d| system::read(d.join("type")).as_deref() == Some("Mains"))
+        .collect();
+    if adapters.is_empty() {
+        return None;
+    }
+    Some(
+        adapters
+            .iter()
+            .any(|d| system::read(d.join("online")).as_deref() == Some("1")),
+    )
+}
+
+fn sysfs_batteries() -> (Vec<Battery>, Option<bool>) {
+    let mut supplies: Vec<_> = std::fs::read_dir(host::path(POWER_SUPPLY))
+        .map(|dir| dir.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    supplies.sort();
+    let batteries = supplies.iter().filter_map(|d| sysfs_battery(d)).collect();
+    (batteries, sysfs_on_ac(&supplies))
+}
+
+/// System batteries and whether mains power is connected
+pub fn batteries() -> (Vec<Battery>, Option<bool>) {
+    match upower_batteries() {
+        Ok((batteries, on_ac)) if !batteries.is_empty() => (batteries, on_ac),
+        _ => sysfs_batteries(),
+    }
+}
+
+pub fn diagnose() -> BatteryDiagnostics {
+    let (batteries, on_ac) = batteries();
+    let mut diag = BatteryDiagnostics {
+        batteries,
+        on_ac,
+        ..BatteryDiagnostics::default()
+    };
+
+    for b in &diag.batteries {
+        let Some(health) = b.health_percent else {
+            continue;
+        };
+        if health < REPLACE_PERCENT {
+            diag.warnings.push(format!(
+                "{} holds {:.0}% of its design capacity",
+                b.name, health
+            ));
+            diag.recommendations.push(format!(
+                "Replace {}; it is worn past the point of holding a useful charge",
+                b.name
+            ));
+        } else if health < WORN_PERCENT {
+            diag.warnings.push(format!(
+                "{} is worn: {:.0}% of design capacity{}",
+                b.name,
+                health,
+                b.cycles
+                    .map(|c| format!(" after {} cycles", c))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+    if diag.batteries.iter().any(|b| {
+        b.health_percent
+            .is_some_and(|h| (REPLACE_PERCENT..WORN_PERCENT).contains(&h))
+    }) {
+        diag.recommendations.push(
+            "Limit the charge threshold to 80% (charge_control_end_threshold) to slow further wear"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl BatteryDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Battery Diagnostics ===");
+        if self.batteries.is_empty() {
+            println!("- No system battery found");
+        }
+        for b in &self.batteries {
+            let health = b.health_percent.map_or("health unknown".to_string(), |h| {
+                format!("{:.0}% health", h)
+            });
+            println!(
+                "{} {}: {}, {}",
+                mark(b.health_percent.map_or(true, |h| h >= WORN_PERCENT)),
+                b.name,
+                b.state,
+                health
+            );
+            if verbose {
+                if let Some(model) = &b.model {
+                    println!("    Model: {}", model);
+                }
+                if let Some(p) = b.percentage {
+                    println!("    Charge: {:.0}%", p);
+                }
+                if let Some(c) = b.cycles {
+                    println!("    Cycles: {}", c);
+                }
+                if let Some(r) = b.rate_watts {
+                    println!("    Rate: {:.1} W", r);
+                }
+                println!("    Source: {}", b.source);
+            }
+        }
+        match self.on_ac {
+            Some(true) => println!("- Power: AC adapter"),
+            Some(false) => println!("- Power: battery"),
+            None => {}
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
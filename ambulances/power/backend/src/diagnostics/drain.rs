@@ -0,0 +1,194 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Where the power goes
+//!
+//! Without powertop's per-process accounting, the kernel still says which
+//! interrupt sources keep waking the CPUs (sampled from `/proc/interrupts`)
+//! and which wakeup sources have pulled the system out of suspend or
+//! aborted it (`/sys/class/wakeup`).
+
+use crate::diagnostics::battery;
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Interval between the two `/proc/interrupts` reads
+const SAMPLE: Duration = Duration::from_secs(1);
+
+/// Discharge rate above which the laptop is not idling
+pub const HIGH_DRAIN_WATTS: f64 = 15.0;
+
+/// Interrupts per second from one non-timer source that keep CPUs out of deep idle
+const BUSY_IRQ_RATE: f64 = 1000.0;
+
+/// Sources listed in the report
+const TOP: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IrqRate {
+    /// IRQ number or name, e.g. `128` or `RES`
+    pub irq: String,
+    /// What raised it, e.g. `xhci_hcd` or `Rescheduling interrupts`
+    pub source: String,
+    pub per_second: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WakeupSource {
+    pub name: String,
+    /// Times it woke the system or aborted a suspend
+    pub wakeup_count: u64,
+    pub event_count: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DrainDiagnostics {
+    /// Discharge rate in watts, when running on battery
+    pub discharge_watts: Option<f64>,
+    pub top_interrupts: Vec<IrqRate>,
+    pub top_wakeup_sources: Vec<WakeupSource>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Per-source interrupt totals across all CPUs, with their descriptions
+fn interrupts() -> HashMap<String, (u64, String)> {
+    let Some(text) = system::read(host::path("/proc/interrupts")) else {
+        return HashMap::new();
+    };
+    let mut lines = text.lines();
+    let cpus = lines.next().map_or(0, |l| l.split_whitespace().count());
+    lines
+        .filter_map(|line| {
+            let (irq, rest) = line.split_once(':')?;
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            let counts = fields
+                .iter()
+                .take(cpus)
+                .map_while(|f| f.parse::<u64>().ok());
+            let total: u64 = counts.clone().sum();
+            let source = fields[counts.count()..].join(" ");
+            Some((irq.trim().to_string(), (total, source)))
+        })
+        .collect()
+}
+
+fn is_timer(irq: &IrqRate) -> bool {
+    irq.irq == "LOC" || irq.source.to_lowercase().contains("timer")
+}
+
+/// Busiest interrupt sources over one sample
+fn top_interrupts() -> Vec<IrqRate> {
+    let before = interrupts();
+    std::thread::sleep(SAMPLE);
+    let after = interrupts();
+    let mut rates: Vec<IrqRate> = after
+        .into_iter()
+        .filter_map(|(irq, (count, source))| {
+            let delta = count.saturating_sub(before.get(&irq)?.0);
+            (delta > 0).then(|| IrqRate {
+                irq,
+                source,
+                per_second: delta as f64 / SAMPLE.as_secs_f64(),
+            })
+        })
+        .collect();
+    rates.sort_by(|a, b| b.per_second.total_cmp(&a.per_second));
+    rates.truncate(TOP);
+    rates
+}
+
+/// Wakeup sources that have actually woken the system, most first
+fn top_wakeup_sources() -> Vec<WakeupSource> {
+    let mut sources: Vec<WakeupSource> = std::fs::read_dir(host::path("/sys/class/wakeup"))
+        .map(|dir| dir.flatten().map(|e| e.path()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|dir| {
+            Some(WakeupSource {
+                name: system::read(dir.join("name"))?,
+                wakeup_count: system::read_u64(dir.join("wakeup_count"))?,
+                event_count: system::read_u64(dir.join("event_count")).unwrap_or(0),
+            })
+        })
+        .filter(|s| s.wakeup_count > 0)
+        .collect();
+    sources.sort_by_key(|s| std::cmp::Reverse(s.wakeup_count));
+    sources.truncate(TOP);
+    sources
+}
+
+pub fn diagnose() -> DrainDiagnostics {
+    let (batteries, _) = battery::batteries();
+    let mut diag = DrainDiagnostics {
+        discharge_watts: batteries
+            .iter()
+            .filter(|b| b.state == "discharging")
+            .filter_map(|b| b.rate_watts)
+            .reduce(|a, b| a + b),
+        top_interrupts: top_interrupts(),
+        top_wakeup_sources: top_wakeup_sources(),
+        ..DrainDiagnostics::default()
+    };
+
+    if let Some(watts) = diag.discharge_watts.filter(|&w| w > HIGH_DRAIN_WATTS) {
+        diag.warnings.push(format!(
+            "Battery is draining at {:.1} W; an idle laptop usually draws under 10 W",
+            watts
+        ));
+        diag.recommendations.push(
+            "Check `top` for busy processes and close background GPU users like browsers with video"
+                .to_string(),
+        );
+    }
+    for irq in diag
+        .top_interrupts
+        .iter()
+        .filter(|i| !is_timer(i) && i.per_second > BUSY_IRQ_RATE)
+    {
+        diag.warnings.push(format!(
+            "IRQ {} ({}) fires {:.0} times a second",
+            irq.irq, irq.source, irq.per_second
+        ));
+    }
+    if let Some(first) = diag.top_wakeup_sources.first() {
+        if diag.discharge_watts.is_some() || first.wakeup_count > 100 {
+            diag.recommendations.push(format!(
+                "{} has woken the system {} times; disable it in /proc/acpi/wakeup if suspend keeps ending early",
+                first.name, first.wakeup_count
+            ));
+        }
+    }
+
+    diag
+}
+
+impl DrainDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Power Drain Diagnostics ===");
+        match self.discharge_watts {
+            Some(w) => println!("{} Discharge rate: {:.1} W", mark(w <= HIGH_DRAIN_WATTS), w),
+            None => println!("- Discharge rate: not on battery"),
+        }
+        let busy = self
+            .top_interrupts
+            .iter()
+            .filter(|i| !is_timer(i) && i.per_second > BUSY_IRQ_RATE)
+            .count();
+        println!("{} Interrupt sources: {} busy", mark(busy == 0), busy);
+        if verbose {
+            for irq in &self.top_interrupts {
+                println!("    {:>6.0}/s  {} {}", irq.per_second, irq.irq, irq.source);
+            }
+            if !self.top_wakeup_sources.is_empty() {
+                println!("- Wakeup sources:");
+            }
+            for s in &self.top_wakeup_sources {
+                println!("    {:>6}  {}", s.wakeup_count, s.name);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Inhibitor locks that keep the system awake
+//!
+//! logind lists who took each lock, but the lock lives as long as anyone
+//! holds its FIFO (`/run/systemd/inhibit/<id>.ref`). When the process that
+//! took it has exited and a child inherited the FIFO, or the holder is
+//! stopped, the lock outlives its purpose and suspend never happens.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const INHIBIT_DIR: &str = "/run/systemd/inhibit";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Holder {
+    pub pid: u32,
+    pub comm: String,
+    pub state: char,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InhibitorLock {
+    pub what: String,
+    pub who: String,
+    pub why: String,
+    pub mode: String,
+    pub pid: u32,
+    /// Processes with the lock's FIFO open
+    pub holders: Vec<Holder>,
+    /// Why the lock is stale, if it is
+    pub broken: Option<String>,
+}
+
+impl InhibitorLock {
+    pub fn blocks_sleep(&self) -> bool {
+        self.mode == "block" && self.what.split(':').any(|w| w == "sleep" || w == "idle")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InhibitorDiagnostics {
+    pub locks: Vec<InhibitorLock>,
+    /// Why logind could not be asked
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// FIFO path of the lock logind recorded for `pid` and `what`
+fn fifo(pid: u32, what: &str) -> Option<String> {
+    for entry in std::fs::read_dir(INHIBIT_DIR).ok()?.flatten() {
+        let Some(state) = system::read(entry.path()) else {
+            continue;
+        };
+        let value = |key: &str| {
+            state
+                .lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+        };
+        if value("PID") == Some(&pid.to_string()) && value("WHAT") == Some(what) {
+            return value("FIFO").map(str::to_string);
+        }
+    }
+    None
+}
+
+/// Processes with `path` open; only our own are visible without root
+fn holders(path: &str) -> Vec<Holder> {
+    let Ok(procs) = std::fs::read_dir(host::path("/proc")) else {
+        return Vec::new();
+    };
+    procs
+        .flatten()
+        .filter_map(|p| p.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            std::fs::read_dir(host::path(format!("/proc/{}/fd", pid))).is_ok_and(|fds| {
+                fds.flatten().any(|fd| {
+                    std::fs::read_link(fd.path()).is_ok_and(|target| target.as_os_str() == path)
+                })
+            })
+        })
+        .filter_map(|pid| {
+            let (comm, state) = system::process(pid)?;
+            Some(Holder { pid, comm, state })
+        })
+        .collect()
+}
+
+fn broken(lock: &InhibitorLock) -> Option<String> {
+    match system::process(lock.pid) {
+        None if !lock.holders.is_empty() => Some(format!(
+            "{} (pid {}) exited and the lock was inherited",
+            lock.who, lock.pid
+        )),
+        Some((comm, 'T')) => Some(format!("{} (pid {}) is stopped", comm, lock.pid)),
+        _ => lock
+            .holders
+            .iter()
+            .find(|h| h.state == 'T')
+            .map(|h| format!("holder {} (pid {}) is stopped", h.comm, h.pid)),
+    }
+}
+
+/// Every lock logind holds, with stale ones marked
+pub fn locks() -> std::io::Result<Vec<InhibitorLock>> {
+    let bus = Bus::system()?;
+    Ok(bus
+        .list_inhibitors()?
+        .into_iter()
+        .map(|i| {
+            let holders = fifo(i.pid, &i.what)
+                .map(|path| holders(&path))
+                .unwrap_or_default();
+            let mut lock = InhibitorLock {
+                what: i.what,
+                who: i.who,
+                why: i.why,
+                mode: i.mode,
+                pid: i.pid,
+                holders,
+                broken: None,
+            };
+            lock.broken = broken(&lock);
+            lock
+        })
+        .collect())
+}
+
+pub fn diagnose() -> InhibitorDiagnostics {
+    let mut diag = InhibitorDiagnostics::default();
+    match locks() {
+        Ok(locks) => diag.locks = locks,
+        Err(e) => {
+            diag.error = Some(format!("logind not reachable: {}", e));
+            return diag;
+        }
+    }
+
+    for lock in &diag.locks {
+        if let Some(reason) = &lock.broken {
+            diag.warnings.push(format!(
+                "Stale '{}' lock from {}: {}",
+                lock.what, lock.who, reason
+            ));
+        } else if lock.blocks_sleep() {
+            diag.warnings
+                .push(format!("{} blocks {}: {}", lock.who, lock.what, lock.why));
+        }
+    }
+    if diag.locks.iter().any(|l| l.broken.is_some()) {
+        diag.recommendations
+            .push("Clear stale locks: power-ambulance repair inhibitors".to_string());
+    }
+    if !ambulance_privilege::is_elevated() && diag.locks.iter().any(|l| l.holders.is_empty()) {
+        diag.recommendations
+            .push("Run as root to see which processes hold each lock".to_string());
+    }
+
+    diag
+}
+
+impl InhibitorDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Inhibitor Diagnostics ===");
+        if let Some(e) = &self.error {
+            println!("- Locks: unknown ({})", e);
+            print_notes(&self.warnings, &self.recommendations);
+            return;
+        }
+        let blocking = self.locks.iter().filter(|l| l.blocks_sleep()).count();
+        let stale = self.locks.iter().filter(|l| l.broken.is_some()).count();
+        println!(
+            "{} Locks: {} blocking sleep, {} stale",
+            mark(stale == 0),
+            blocking,
+            stale
+        );
+        if verbose {
+            for lock in &self.locks {
+                println!(
+                    "    {} {} ({} {}): {}",
+                    lock.who, lock.pid, lock.mode, lock.what, lock.why
+                );
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
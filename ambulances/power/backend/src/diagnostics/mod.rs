@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Power diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod battery;
+pub mod drain;
+pub mod inhibitors;
+pub mod suspend;
+pub mod usb;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; UPower can hang on a battery controller
+/// that stopped answering
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Battery(battery::BatteryDiagnostics),
+    Drain(drain::DrainDiagnostics),
+    Suspend(suspend::SuspendDiagnostics),
+    Inhibitors(inhibitors::InhibitorDiagnostics),
+    Usb(usb::UsbDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("battery", |_| {
+            Ok(Section::Battery(battery::diagnose()))
+        }))
+        .job(Job::blocking("drain", |_| {
+            Ok(Section::Drain(drain::diagnose()))
+        }))
+        .job(Job::blocking("suspend", |_| {
+            Ok(Section::Suspend(suspend::diagnose()))
+        }))
+        .job(Job::blocking("inhibitors", |_| {
+            Ok(Section::Inhibitors(inhibitors::diagnose()))
+        }))
+        .job(Job::blocking("usb", |_| Ok(Section::Usb(usb::diagnose()))))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        battery: Default::default(),
+        drain: Default::default(),
+        suspend: Default::default(),
+        inhibitors: Default::default(),
+        usb: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Battery(d)) => result.battery = d,
+            Some(Section::Drain(d)) => result.drain = d,
+            Some(Section::Suspend(d)) => result.suspend = d,
+            Some(Section::Inhibitors(d)) => result.inhibitors = d,
+            Some(Section::Usb(d)) => result.usb = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "battery" => &mut result.battery.warnings,
+                    "drain" => &mut result.drain.warnings,
+                    "suspend" => &mut result.suspend.warnings,
+                    "inhibitors" => &mut result.inhibitors.warnings,
+                    _ => &mut result.usb.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
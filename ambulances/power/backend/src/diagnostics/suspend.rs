@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Failed suspend and resume cycles
+//!
+//! systemd-sleep logs the start and end of every sleep and says when the
+//! kernel refused to go down. A boot whose last sleep record is a start
+//! never came back: the resume hung, or the battery ran flat while asleep.
+//! The kernel's own messages name the device or task that blocked suspend.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// `MESSAGE_ID` systemd-sleep logs before sleeping
+const SLEEP_START: &str = "6bbd95ee977941e497c48be27c254128";
+
+/// `MESSAGE_ID` systemd-sleep logs after waking
+const SLEEP_STOP: &str = "8811e6df2a8e40f58a94cea26f8ebf14";
+
+/// Cycles counted over this window
+const WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Journal entries read at most; kernel messages make up most of them
+const MAX_ENTRIES: usize = 50_000;
+
+/// Kernel messages naming what blocked a suspend
+const CULPRIT_PATTERNS: &[&str] = &[
+    "failed to suspend",
+    "failed to freeze",
+    "Freezing of tasks failed",
+    "Freezing user space processes failed",
+    "Some devices failed to suspend",
+];
+
+/// Culprit messages kept
+const MAX_CULPRITS: usize = 5;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SuspendDiagnostics {
+    pub attempts: u32,
+    /// Sleeps the kernel refused
+    pub failures: u32,
+    /// Earlier boots that ended while asleep
+    pub failed_resumes: u32,
+    /// Kernel messages naming the blocking device or task
+    pub culprits: Vec<String>,
+    /// Selected `/sys/power/mem_sleep` mode, `s2idle` or `deep`
+    pub mem_sleep: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn mem_sleep() -> Option<String> {
+    let modes = system::read(host::path("/sys/power/mem_sleep"))?;
+    let selected = modes.split_whitespace().find(|m| m.starts_with('['))?;
+    Some(
+        selected
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+    )
+}
+
+fn scan(diag: &mut SuspendDiagnostics) -> std::io::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64);
+    let since = now.saturating_sub(WINDOW_SECS * 1_000_000);
+    let this_boot = system::read(host::path("/proc/sys/kernel/random/boot_id"))
+        .unwrap_or_default()
+        .replace('-', "");
+
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("SYSLOG_IDENTIFIER=systemd-sleep")?;
+    journal.add_disjunction()?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.seek_tail()?;
+
+    // Newest sleep record of each boot: was it a start?
+    let mut ended_asleep: HashMap<String, bool> = HashMap::new();
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? {
+            break;
+        }
+        if journal.realtime_usec()? < since {
+            break;
+        }
+        let message = journal.field("MESSAGE").unwrap_or_default();
+        match journal.field("MESSAGE_ID").as_deref() {
+            Some(id) if id == SLEEP_START || id == SLEEP_STOP => {
+                if id == SLEEP_START {
+                    diag.attempts += 1;
+                }
+                if let Some(boot) = journal.field("_BOOT_ID") {
+                    ended_asleep.entry(boot).or_insert(id == SLEEP_START);
+                }
+            }
+            _ if message.starts_with("Failed to put system to sleep") => diag.failures += 1,
+            _ if CULPRIT_PATTERNS.iter().any(|p| message.contains(p))
+                && diag.culprits.len() < MAX_CULPRITS
+                && !diag.culprits.contains(&message) =>
+            {
+                diag.culprits.push(message)
+            }
+            _ => {}
+        }
+    }
+    diag.failed_resumes = ended_asleep
+        .iter()
+        .filter(|(boot, asleep)| **asleep && **boot != this_boot)
+        .count() as u32;
+    Ok(())
+}
+
+pub fn diagnose() -> SuspendDiagnostics {
+    let mut diag = SuspendDiagnostics {
+        mem_sleep: mem_sleep(),
+        ..SuspendDiagnostics::default()
+    };
+    if let Err(e) = scan(&mut diag) {
+        diag.warnings
+            .push(format!("Could not read the journal: {}", e));
+        return diag;
+    }
+
+    if diag.failures > 0 {
+        diag.warnings.push(format!(
+            "{} of {} suspend attempts in the last 7 days failed",
+            diag.failures, diag.attempts
+        ));
+        if diag.culprits.is_empty() {
+            diag.recommendations.push(
+                "Run `journalctl -k -b` after the next failed suspend to see what blocked it"
+                    .to_string(),
+            );
+        } else {
+            diag.recommendations.push(
+                "Unbind or unload the driver named in the kernel messages before suspending"
+                    .to_string(),
+            );
+        }
+    }
+    if diag.failed_resumes > 0 {
+        diag.warnings.push(format!(
+            "{} boot(s) ended while suspended: resume hung or the battery ran out",
+            diag.failed_resumes
+        ));
+        if diag.mem_sleep.as_deref() == Some("deep") {
+            diag.recommendations.push(
+                "Try mem_sleep_default=s2idle on the kernel command line if resume hangs"
+                    .to_string(),
+            );
+        } else {
+            diag.recommendations
+                .push("Check the end of the previous boot with `journalctl -b -1 -e`".to_string());
+        }
+    }
+
+    diag
+}
+
+impl SuspendDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Suspend Diagnostics ===");
+        println!(
+            "{} Suspend: {} of {} attempts failed",
+            mark(self.failures == 0),
+            self.failures,
+            self.attempts
+        );
+        println!(
+            "{} Resume: {} boot(s) ended asleep",
+            mark(self.failed_resumes == 0),
+            self.failed_resumes
+        );
+        if verbose {
+            if let Some(mode) = &self.mem_sleep {
+                println!("- Sleep mode: {}", mode);
+            }
+            for culprit in &self.culprits {
+                println!("    {}", culprit);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
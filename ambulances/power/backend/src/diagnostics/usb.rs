@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! USB runtime power management
+//!
+//! A USB device with `power/control` set to `on` never autosuspends and
+//! keeps its controller (and often the package C-state) awake. Input
+//! devices are left alone: autosuspended keyboards and mice can drop the
+//! first keystroke or stutter on wake.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const USB_DEVICES: &str = "/sys/bus/usb/devices";
+
+/// `usbcore.autosuspend`; -1 disables autosuspend for every device
+const AUTOSUSPEND_DELAY: &str = "/sys/module/usbcore/parameters/autosuspend";
+
+/// `bInterfaceClass` of human interface devices
+const CLASS_HID: &str = "03";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsbDevice {
+    /// sysfs name, e.g. `1-2`
+    pub name: String,
+    /// `vendor:product`
+    pub id: String,
+    pub product: Option<String>,
+    /// `power/control`: `auto` or `on`
+    pub control: String,
+    /// Keyboard, mouse or other HID; autosuspend stays off for these
+    pub input: bool,
+}
+
+impl UsbDevice {
+    pub fn control_path(&self) -> PathBuf {
+        Path::new(USB_DEVICES)
+            .join(&self.name)
+            .join("power/control")
+    }
+
+    /// Autosuspend is off and could safely be turned on
+    pub fn can_autosuspend(&self) -> bool {
+        self.control == "on" && !self.input
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsbDiagnostics {
+    pub devices: Vec<UsbDevice>,
+    /// Autosuspend disabled for every device by the kernel parameter
+    pub globally_disabled: bool,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Whether any interface of the device is a HID
+fn is_input(dir: &Path, name: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    let prefix = format!("{}:", name);
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+        .any(|e| system::read(e.path().join("bInterfaceClass")).as_deref() == Some(CLASS_HID))
+}
+
+/// USB devices (not interfaces) with runtime power control
+pub fn devices() -> Vec<UsbDevice> {
+    let Ok(entries) = std::fs::read_dir(host::path(USB_DEVICES)) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<UsbDevice> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            if name.contains(':') {
+                return None;
+            }
+            let dir = e.path();
+            let control = system::read(dir.join("power/control"))?;
+            Some(UsbDevice {
+                id: format!(
+                    "{}:{}",
+                    system::read(dir.join("idVendor")).unwrap_or_default(),
+                    system::read(dir.join("idProduct")).unwrap_or_default()
+                ),
+                product: system::read(dir.join("product")),
+                control,
+                input: is_input(&dir, &name),
+                name,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+pub fn diagnose() -> UsbDiagnostics {
+    let mut diag = UsbDiagnostics {
+        devices: devices(),
+        globally_disabled: system::read(host::path(AUTOSUSPEND_DELAY)).as_deref() == Some("-1"),
+        ..UsbDiagnostics::default()
+    };
+
+    if diag.globally_disabled {
+        diag.warnings.push(
+            "USB autosuspend is disabled for all devices (usbcore.autosuspend=-1)".to_string(),
+        );
+        diag.recommendations.push(
+            "Remove usbcore.autosuspend=-1 from the kernel command line unless a device needs it"
+                .to_string(),
+        );
+    }
+    let awake: Vec<_> = diag
+        .devices
+        .iter()
+        .filter(|d| d.can_autosuspend())
+        .collect();
+    if !awake.is_empty() {
+        diag.warnings.push(format!(
+            "{} USB device(s) never autosuspend: {}",
+            awake.len(),
+            awake
+                .iter()
+                .map(|d| d.product.as_deref().unwrap_or(&d.id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        diag.recommendations
+            .push("Re-enable autosuspend: power-ambulance repair usb-autosuspend".to_string());
+    }
+
+    diag
+}
+
+impl UsbDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== USB Power Diagnostics ===");
+        let awake = self.devices.iter().filter(|d| d.can_autosuspend()).count();
+        println!(
+            "{} Autosuspend: {} of {} device(s) kept awake",
+            mark(awake == 0 && !self.globally_disabled),
+            awake,
+            self.devices.len()
+        );
+        if verbose {
+            for d in &self.devices {
+                println!(
+                    "    {} {} {}{}",
+                    d.name,
+                    d.control,
+                    d.product.as_deref().unwrap_or(&d.id),
+                    if d.input { " (input)" } else { "" }
+                );
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
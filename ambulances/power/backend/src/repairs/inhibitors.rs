@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Clear stale inhibitor locks
+//!
+//! logind has no call to drop someone else's lock; it goes away when the
+//! last holder of its FIFO exits. Each holder of a stale lock is sent
+//! SIGTERM (and SIGCONT, so a stopped one can act on it) after the caller
+//! confirms.
+
+use crate::diagnostics::inhibitors;
+use crate::report::RepairOutcome;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let locks = match inhibitors::locks() {
+        Ok(locks) => locks,
+        Err(e) => {
+            return RepairOutcome {
+                errors: vec![format!("Could not list inhibitors from logind: {}", e)],
+                ..RepairOutcome::default()
+            }
+        }
+    };
+    let stale: Vec<_> = locks.iter().filter(|l| l.broken.is_some()).collect();
+    if stale.is_empty() {
+        return RepairOutcome::not_needed("No stale inhibitor locks, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for lock in stale {
+        if lock.holders.is_empty() {
+            result.errors.push(format!(
+                "'{}' lock from {}: no process holding it was found",
+                lock.what, lock.who
+            ));
+            continue;
+        }
+        for holder in &lock.holders {
+            let question = format!(
+                "Terminate {} (pid {}), which holds the stale '{}' lock from {}?",
+                holder.comm, holder.pid, lock.what, lock.who
+            );
+            if !confirm(&question) {
+                result.errors.push(format!(
+                    "{} (pid {}): not confirmed, left running (rerun with --yes to confirm)",
+                    holder.comm, holder.pid
+                ));
+                continue;
+            }
+            let pid = holder.pid as libc::pid_t;
+            if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+                result.errors.push(format!(
+                    "{} (pid {}): {}",
+                    holder.comm,
+                    holder.pid,
+                    std::io::Error::last_os_error()
+                ));
+                continue;
+            }
+            unsafe { libc::kill(pid, libc::SIGCONT) };
+            result.actions.push(format!(
+                "Terminated {} (pid {}), releasing the '{}' lock from {}",
+                holder.comm, holder.pid, lock.what, lock.who
+            ));
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
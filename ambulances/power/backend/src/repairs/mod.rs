@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Power repairs, one module per target
+
+pub mod inhibitors;
+pub mod usb;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["usb-autosuspend", "inhibitors", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each process is signalled.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        usb_autosuspend_repair: if selected("usb-autosuspend") {
+            usb::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        inhibitors_repair: if selected("inhibitors") {
+            inhibitors::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
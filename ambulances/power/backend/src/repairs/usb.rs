@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Re-enable USB autosuspend
+//!
+//! Sets `power/control` back to `auto` on non-input devices. The change
+//! lasts until the device is replugged or the system reboots; a udev rule
+//! or TLP is the place to make it permanent.
+
+use crate::diagnostics::usb;
+use crate::report::RepairOutcome;
+
+pub fn repair() -> RepairOutcome {
+    let awake: Vec<_> = usb::devices()
+        .into_iter()
+        .filter(|d| d.can_autosuspend())
+        .collect();
+    if awake.is_empty() {
+        return RepairOutcome::not_needed(
+            "All non-input USB devices autosuspend, no repair needed",
+        );
+    }
+
+    let mut result = RepairOutcome::default();
+    for device in &awake {
+        let label = device.product.as_deref().unwrap_or(&device.id);
+        match std::fs::write(device.control_path(), "auto") {
+            Ok(()) => result.actions.push(format!(
+                "Enabled autosuspend for {} ({})",
+                label, device.name
+            )),
+            Err(e) => result.errors.push(format!("{}: {}", device.name, e)),
+        }
+    }
+    if !result.actions.is_empty() {
+        result.actions.push(
+            "Runtime only: add a udev rule setting power/control=auto to keep it".to_string(),
+        );
+    }
+    result.success = result.errors.is_empty();
+    result
+}
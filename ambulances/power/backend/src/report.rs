@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    battery::BatteryDiagnostics, drain::DrainDiagnostics, inhibitors::InhibitorDiagnostics,
+    suspend::SuspendDiagnostics, usb::UsbDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "power-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub battery: BatteryDiagnostics,
+    pub drain: DrainDiagnostics,
+    pub suspend: SuspendDiagnostics,
+    pub inhibitors: InhibitorDiagnostics,
+    pub usb: UsbDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.battery.warnings,
+            &self.drain.warnings,
+            &self.suspend.warnings,
+            &self.inhibitors.warnings,
+            &self.usb.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub usb_autosuspend_repair: RepairOutcome,
+    pub inhibitors_repair: RepairOutcome,
+}
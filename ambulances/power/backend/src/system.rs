@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thin helpers over sysfs, procfs and privileges
+
+use ambulance_core::host;
+use std::path::Path;
+
+/// A sysfs or procfs attribute, trimmed; `None` if absent or unreadable
+pub fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// Numeric attribute
+pub fn read_u64(path: impl AsRef<Path>) -> Option<u64> {
+    read(path)?.parse().ok()
+}
+
+/// `comm` and state letter (`R`, `S`, `T`, `Z`...) of a process
+pub fn process(pid: u32) -> Option<(String, char)> {
+    let stat = read(host::path(format!("/proc/{}/stat", pid)))?;
+    // comm is parenthesised and may itself contain spaces or parentheses
+    let (head, tail) = stat.rsplit_once(')')?;
+    let comm = head.split_once('(')?.1.to_string();
+    let state = tail.trim_start().chars().next()?;
+    Some((comm, state))
+}
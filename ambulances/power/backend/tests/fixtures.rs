@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The power checks against broken fake systems
+
+use ambulance_testkit::{CheckExt, ReportExt, System};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_power-ambulance");
+
+/// A USB device under `/sys/bus/usb/devices` with one interface of `class`
+fn usb(system: &mut System, name: &str, product: &str, control: &str, class: &str) {
+    let dir = format!("/sys/bus/usb/devices/{}", name);
+    system
+        .file(&format!("{}/idVendor", dir), "046d\n")
+        .file(&format!("{}/idProduct", dir), "c52b\n")
+        .file(&format!("{}/product", dir), &format!("{}\n", product))
+        .file(&format!("{}/power/control", dir), &format!("{}\n", control))
+        .file(
+            &format!("{}/{}:1.0/bInterfaceClass", dir, name),
+            &format!("{}\n", class),
+        );
+}
+
+#[test]
+fn worn_batteries_are_told_apart_from_dead_ones() {
+    let mut system = System::new();
+    system
+        .battery("BAT0", 70, 90, "Discharging")
+        .file("/sys/class/power_supply/BAT0/cycle_count", "612\n")
+        .battery("BAT1", 40, 100, "Full")
+        .mains("AC", false);
+    let report = system.diagnose(BINARY);
+
+    let battery = report.check("battery");
+    assert_eq!(
+        battery.summaries(),
+        [
+            "BAT0 is worn: 70% of design capacity after 612 cycles",
+            "BAT1 holds 40% of its design capacity"
+        ]
+    );
+    let advice: Vec<&str> = battery
+        .recommendations
+        .iter()
+        .map(|r| r.text.as_str())
+        .collect();
+    assert!(advice.iter().any(|r| r.starts_with("Replace BAT1")));
+    assert!(advice
+        .iter()
+        .any(|r| r.starts_with("Limit the charge threshold")));
+}
+
+#[test]
+fn usb_devices_kept_awake_are_named_but_input_devices_are_not() {
+    let mut system = System::new();
+    usb(&mut system, "1-1", "Webcam", "on", "0e");
+    usb(&mut system, "1-2", "Keyboard", "on", "03");
+    usb(&mut system, "1-3", "Card reader", "auto", "08");
+    let report = system.diagnose(BINARY);
+
+    assert_eq!(
+        report.check("usb").summaries(),
+        ["1 USB device(s) never autosuspend: Webcam"]
+    );
+}
+
+#[test]
+fn autosuspend_disabled_on_the_command_line_is_found() {
+    let mut system = System::new();
+    system.file("/sys/module/usbcore/parameters/autosuspend", "-1\n");
+    let report = system.diagnose(BINARY);
+
+    assert_eq!(
+        report.check("usb").summaries(),
+        ["USB autosuspend is disabled for all devices (usbcore.autosuspend=-1)"]
+    );
+}
+
+#[test]
+fn a_healthy_battery_passes() {
+    let mut system = System::new();
+    system.battery("BAT0", 95, 80, "Charging").mains("AC", true);
+    usb(&mut system, "1-1", "Webcam", "auto", "0e");
+    let report = system.diagnose(BINARY);
+
+    for id in ["battery", "usb"] {
+        assert!(report.check(id).summaries().is_empty(), "{:?}", report);
+    }
+}
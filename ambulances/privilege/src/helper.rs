@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The helper daemon: a root service that runs ambulance repairs on request
+//!
+//! Where a desktop app must not prompt for a password each time, or on
+//! macOS where a privileged helper replaces the AppleScript prompt, a
+//! daemon running as root listens on [`SOCKET`]. Who may connect is
+//! decided by the socket's owner and mode, which [`bind`] sets to 0660
+//! so an installer can hand it to an admin group. What a caller may run
+//! is decided by the daemon's [`Policy`]: listed programs only, with a
+//! listed first argument, and only the listed environment variables.
+//!
+//! Each connection carries one request and one response, each a line of
+//! JSON. The daemon handles connections one at a time, so two repairs
+//! never run at once, and writes an audit line for each to stderr. A
+//! client gets [`IO_TIMEOUT`] to send its request and take the reply,
+//! so one that stalls holds up the others only that long.
+//!
+//! `system-tools-helper` is the daemon, with its policy read from
+//! [`POLICY`].
+
+use crate::Output;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+#[cfg(target_os = "macos")]
+pub const SOCKET: &str = "/var/run/system-tools/helper.sock";
+#[cfg(not(target_os = "macos"))]
+pub const SOCKET: &str = "/run/system-tools/helper.sock";
+
+/// Where `system-tools-helper` reads its [`Policy`]
+pub const POLICY: &str = "/etc/system-tools/helper.toml";
+
+/// Largest request read, in bytes
+const MAX_REQUEST: u64 = 64 * 1024;
+
+/// How long a client may take to send its request, or to read the reply
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after a failed `accept`, so running out of descriptors does not spin
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// `PATH` the programs run with
+const SAFE_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn new(program: &Path, args: &[&str], env: &[(&str, String)]) -> Request {
+        Request {
+            program: program.to_path_buf(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env: env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Response {
+    /// `None` when the program was killed by a signal or never ran
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the daemon refused or failed to run the program
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What the daemon agrees to run
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Absolute paths of the programs allowed
+    pub programs: Vec<PathBuf>,
+    /// First arguments allowed, e.g. `repair`
+    pub subcommands: Vec<String>,
+    /// Environment variables a caller may set
+    pub env: Vec<String>,
+}
+
+impl Policy {
+    /// The policy in the TOML file at `path`, with `programs`,
+    /// `subcommands` and `env` lists
+    pub fn load(path: &Path) -> Result<Policy, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// The program `request` may run, resolved: [`execute`] runs this
+    /// path and not the one sent, which the caller could repoint once
+    /// checked
+    pub fn check(&self, request: &Request) -> Result<PathBuf, String> {
+        // Compared after resolving links, so a symlink elsewhere cannot stand in
+        let program = request
+            .program
+            .canonicalize()
+            .map_err(|e| format!("{}: {}", request.program.display(), e))?;
+        if !self
+            .programs
+            .iter()
+            .any(|p| p.canonicalize().is_ok_and(|p| p == program))
+        {
+            return Err(format!("{} is not an allowed program", program.display()));
+        }
+        match request.args.first() {
+            Some(sub) if self.subcommands.contains(sub) => {}
+            _ => return Err("subcommand not allowed".to_string()),
+        }
+        if let Some((name, _)) = request.env.iter().find(|(k, _)| !self.env.contains(k)) {
+            return Err(format!("environment variable {} not allowed", name));
+        }
+        Ok(program)
+    }
+}
+
+/// The daemon is installed and its socket is there
+pub fn available() -> bool {
+    Path::new(SOCKET).exists()
+}
+
+/// Sends `request` to the daemon and waits for the program to finish
+pub fn call(request: &Request) -> Result<Output, String> {
+    let mut stream =
+        UnixStream::connect(SOCKET).map_err(|e| format!("helper daemon at {}: {}", SOCKET, e))?;
+    let mut line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|e| format!("helper daemon: {}", e))?;
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .map_err(|e| format!("helper daemon: {}", e))?;
+    let response: Response = serde_json::from_str(reply.trim())
+        .map_err(|e| format!("helper daemon replied with unreadable JSON: {}", e))?;
+    if let Some(error) = response.error {
+        return Err(format!("helper daemon refused: {}", error));
+    }
+    Ok(Output {
+        code: response.code,
+        stdout: response.stdout.into_bytes(),
+        stderr: response.stderr.into_bytes(),
+    })
+}
+
+/// Listens at `path`, replacing a stale socket, readable by owner and group
+pub fn bind(path: &Path) -> std::io::Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))?;
+    Ok(listener)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (rc == 0).then_some(cred.uid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+    let (mut uid, mut gid) = (0, 0);
+    let rc = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    (rc == 0).then_some(uid)
+}
+
+/// Runs `program`, as [`Policy::check`] resolved it, with `request`'s
+/// arguments and environment
+pub fn execute(program: &Path, request: &Request) -> Response {
+    let output = Command::new(program)
+        .args(&request.args)
+        .env_clear()
+        .env("PATH", SAFE_PATH)
+        .env("LC_ALL", "C.UTF-8")
+        .envs(request.env.iter().map(|(k, v)| (k, v)))
+        .output();
+    match output {
+        Ok(o) => Response {
+            code: o.status.code(),
+            stdout: String::from_utf8_lossy(&o.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&o.stderr).into_owned(),
+            error: None,
+        },
+        Err(e) => Response {
+            error: Some(format!("{}: {}", program.display(), e)),
+            ..Response::default()
+        },
+    }
+}
+
+fn handle(stream: UnixStream, policy: &Policy) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let uid = peer_uid(&stream);
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST)).read_line(&mut line)?;
+    let response = match serde_json::from_str::<Request>(line.trim()) {
+        Err(e) => Response {
+            error: Some(format!("unreadable request: {}", e)),
+            ..Response::default()
+        },
+        Ok(request) => {
+            let response = match policy.check(&request) {
+                Ok(program) => execute(&program, &request),
+                Err(e) => Response {
+                    error: Some(e),
+                    ..Response::default()
+                },
+            };
+            eprintln!(
+                "uid {} ran {} {}: {}",
+                uid.map_or("?".to_string(), |u| u.to_string()),
+                request.program.display(),
+                request.args.join(" "),
+                match (&response.error, response.code) {
+                    (Some(e), _) => format!("refused, {}", e),
+                    (None, Some(code)) => format!("exit {}", code),
+                    (None, None) => "killed".to_string(),
+                }
+            );
+            response
+        }
+    };
+    let mut reply = serde_json::to_string(&response).unwrap_or_default();
+    reply.push('\n');
+    (&stream).write_all(reply.as_bytes())
+}
+
+/// Serves requests on `listener`; a connection that fails, or cannot be
+/// accepted, is logged and the next one served
+pub fn serve(listener: &UnixListener, policy: &Policy) -> ! {
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(e) = handle(stream, policy) {
+                    eprintln!("helper connection failed: {}", e);
+                }
+            }
+            Err(e) => {
+                eprintln!("helper could not accept a connection: {}", e);
+                std::thread::sleep(ACCEPT_BACKOFF);
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Privilege checks and elevation shared by the ambulances
+//!
+//! Repairs need root on Unix and an elevated token on Windows. Whether
+//! the process has them is asked of the OS, the effective uid or the
+//! token's integrity level, never guessed from file modes. A CLI run
+//! without them stops with [`require`]'s message; an app that runs a
+//! backend for an unprivileged user elevates it with [`run`], by the
+//! first route [`method`] finds:
+//!
+//! 1. the helper daemon, when its socket exists ([`helper`])
+//! 2. pkexec in a Linux graphical session, sudo in a terminal
+//! 3. an administrator prompt through osascript on macOS
+//! 4. a UAC prompt on Windows
+//!
+//! Repairs that delete inside users' homes go through [`userfs`], which
+//! will not follow a user's symlink out of the home.
+
+#[cfg(unix)]
+pub mod helper;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(unix)]
+pub mod userfs;
+#[cfg(windows)]
+mod windows;
+
+use ambulance_core::error::{Category, Error};
+use std::path::Path;
+use std::process::Command;
+
+/// How [`run`] gets its privileges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// The process is elevated already
+    Already,
+    Helper,
+    Pkexec,
+    Sudo,
+    AppleScript,
+    Uac,
+}
+
+impl Method {
+    pub fn label(self) -> &'static str {
+        match self {
+            Method::Already => "already elevated",
+            Method::Helper => "helper daemon",
+            Method::Pkexec => "pkexec",
+            Method::Sudo => "sudo",
+            Method::AppleScript => "macOS administrator prompt",
+            Method::Uac => "UAC prompt",
+        }
+    }
+}
+
+/// What an elevated program printed, and how it exited
+#[derive(Debug, Clone, Default)]
+pub struct Output {
+    /// `None` when it was killed by a signal
+    pub code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl Output {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    fn from_std(output: std::process::Output) -> Output {
+        Output {
+            code: output.status.code(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+        }
+    }
+}
+
+/// Root on Unix, an elevated or SYSTEM token on Windows
+pub fn is_elevated() -> bool {
+    #[cfg(unix)]
+    {
+        unsafe { libc::geteuid() == 0 }
+    }
+    #[cfg(windows)]
+    {
+        windows::is_elevated()
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        false
+    }
+}
+
+/// Stops a CLI command that needs privileges, telling the user how to rerun `command`
+pub fn require(command: &str) -> Result<(), Error> {
+    if is_elevated() {
+        return Ok(());
+    }
+    let (message, hint) = if cfg!(windows) {
+        (
+            "Repair operations require administrator privileges",
+            format!("Please run from an elevated prompt: {}", command),
+        )
+    } else {
+        (
+            "Repair operations require root privileges",
+            format!("Please run with sudo: sudo {}", command),
+        )
+    };
+    Err(Error::new("privilege.required", Category::Privilege, message).with_hint(hint))
+}
+
+#[cfg(unix)]
+fn has(program: &str) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        std::fs::metadata(dir.join(program))
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    })
+}
+
+/// The route [`run`] would take, if any
+pub fn method() -> Option<Method> {
+    if is_elevated() {
+        return Some(Method::Already);
+    }
+    #[cfg(unix)]
+    if helper::available() {
+        return Some(Method::Helper);
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let graphical =
+            std::env::var_os("WAYLAND_DISPLAY").is_some() || std::env::var_os("DISPLAY").is_some();
+        // pkexec needs an authentication agent, which only a desktop session runs
+        if graphical && has("pkexec") {
+            return Some(Method::Pkexec);
+        }
+        if has("sudo") && std::path::Path::new("/dev/tty").exists() {
+            return Some(Method::Sudo);
+        }
+        None
+    }
+    #[cfg(target_os = "macos")]
+    {
+        Some(Method::AppleScript)
+    }
+    #[cfg(windows)]
+    {
+        Some(Method::Uac)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    {
+        None
+    }
+}
+
+/// `program args` with `env` added, through `elevator` (`pkexec` or `sudo`)
+///
+/// Both clear the environment, so it is handed over by `env`.
+#[cfg(target_os = "linux")]
+fn via(elevator: &str, program: &Path, args: &[&str], env: &[(&str, String)]) -> Command {
+    let mut command = Command::new(elevator);
+    if !env.is_empty() {
+        command.arg("env");
+        command.args(env.iter().map(|(k, v)| format!("{}={}", k, v)));
+    }
+    command.arg(program).args(args);
+    command
+}
+
+/// Runs `program` with privileges, waiting for the user to authenticate
+///
+/// `program` should be an absolute path: the elevated side does not
+/// search the caller's `PATH`. Nothing but `env` reaches it from the
+/// caller's environment.
+pub fn run(program: &Path, args: &[&str], env: &[(&str, String)]) -> Result<Output, String> {
+    let method = method().ok_or("No way to gain administrator privileges here")?;
+    let output = match method {
+        Method::Already => Command::new(program)
+            .args(args)
+            .envs(env.iter().cloned())
+            .output(),
+        #[cfg(unix)]
+        Method::Helper => return helper::call(&helper::Request::new(program, args, env)),
+        #[cfg(target_os = "linux")]
+        Method::Pkexec => via("pkexec", program, args, env).output(),
+        #[cfg(target_os = "linux")]
+        Method::Sudo => via("sudo", program, args, env).output(),
+        #[cfg(target_os = "macos")]
+        Method::AppleScript => macos::command(program, args, env).output(),
+        #[cfg(windows)]
+        Method::Uac => return windows::run(program, args, env),
+        #[allow(unreachable_patterns)]
+        other => return Err(format!("{} is not available here", other.label())),
+    };
+    output
+        .map(Output::from_std)
+        .map_err(|e| format!("{} could not start: {}", method.label(), e))
+}
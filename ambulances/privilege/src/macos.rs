@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The administrator prompt of macOS
+//!
+//! `do shell script ... with administrator privileges` asks for an
+//! administrator's password in the standard dialog and runs one shell
+//! command as root, returning what it printed. Without a helper daemon
+//! installed this is the only route that needs no signed helper tool.
+
+use std::path::Path;
+use std::process::Command;
+
+/// `s` as one word for `/bin/sh`
+fn shell_word(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// `s` inside an AppleScript string literal
+fn applescript_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn command(program: &Path, args: &[&str], env: &[(&str, String)]) -> Command {
+    let mut words: Vec<String> = env
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, shell_word(v)))
+        .collect();
+    words.push(shell_word(&program.to_string_lossy()));
+    words.extend(args.iter().map(|a| shell_word(a)));
+    let script = format!(
+        "do shell script \"{}\" with administrator privileges without altering line endings",
+        applescript_string(&words.join(" "))
+    );
+    let mut command = Command::new("osascript");
+    command.arg("-e").arg(script);
+    command
+}
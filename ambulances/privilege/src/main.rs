@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! System Tools Helper
+//!
+//! The root daemon behind `ambulance_privilege::helper`: it listens on
+//! the helper socket and runs the repairs its policy allows. An
+//! installer runs it as a service, as root, and hands the socket's group
+//! to the users who may ask.
+
+use ambulance_core::error::{Category, Error};
+use std::process::ExitCode;
+
+const TOOL: &str = "system-tools-helper";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+fn print_help() {
+    println!("System Tools Helper v{}", VERSION);
+    println!();
+    println!("Usage: system-tools-helper [options]");
+    println!();
+    println!("Options:");
+    #[cfg(unix)]
+    {
+        println!(
+            "  --policy <file>      Policy to enforce (default {})",
+            ambulance_privilege::helper::POLICY
+        );
+        println!(
+            "  --socket <path>      Socket to listen on (default {})",
+            ambulance_privilege::helper::SOCKET
+        );
+    }
+    println!("  -h, --help           Show this help");
+    println!("  --version            Show version");
+}
+
+/// The value after `flag`; `Err` when the flag ends the command line
+#[cfg(unix)]
+fn option<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a str>, Error> {
+    match args.iter().position(|a| a == flag) {
+        None => Ok(None),
+        Some(i) => args.get(i + 1).map(|v| Some(v.as_str())).ok_or_else(|| {
+            Error::new(
+                "usage.bad-option",
+                Category::Usage,
+                format!("{} needs a value", flag),
+            )
+        }),
+    }
+}
+
+#[cfg(unix)]
+fn run(args: &[String]) -> Result<(), Error> {
+    use ambulance_privilege::helper::{self, Policy};
+    use std::path::Path;
+
+    let policy = option(args, "--policy")?.unwrap_or(helper::POLICY);
+    let socket = option(args, "--socket")?.unwrap_or(helper::SOCKET);
+    ambulance_privilege::require(TOOL)?;
+    let policy = Policy::load(Path::new(policy))
+        .map_err(|e| Error::new("failed.policy", Category::Failed, e))?;
+    let listener = helper::bind(Path::new(socket)).map_err(|e| {
+        Error::new(
+            "unavailable.listen",
+            Category::Unavailable,
+            format!("Cannot listen on {}: {}", socket, e),
+        )
+    })?;
+    eprintln!("System Tools Helper v{} listening on {}", VERSION, socket);
+    helper::serve(&listener, &policy)
+}
+
+#[cfg(not(unix))]
+fn run(_args: &[String]) -> Result<(), Error> {
+    Err(Error::new(
+        "unavailable.platform",
+        Category::Unavailable,
+        "The helper daemon runs on Unix only",
+    ))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print_help();
+        return ExitCode::SUCCESS;
+    }
+    if args.iter().any(|a| a == "--version") {
+        println!("System Tools Helper v{}", VERSION);
+        return ExitCode::SUCCESS;
+    }
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => e.report(TOOL, VERSION, false),
+    }
+}
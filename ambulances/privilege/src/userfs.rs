@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Deleting and changing owners and modes inside users' homes as root
+//!
+//! Everything under a home is its user's to rearrange. A repair that
+//! empties `~/.cache` as root must not follow `~/.cache`, or anything in
+//! or above it, when the user has made it a symlink to `/etc`. [`empty`]
+//! walks down from the home one component at a time with `openat` and
+//! `O_NOFOLLOW`, refuses a symlink on the way, and removes entries
+//! relative to the directories it holds open, so swapping a component
+//! for a symlink mid-repair gains nothing either. [`chown`] and
+//! [`chmod`] walk down the same way, and change the entry through the
+//! directory or descriptor they hold, never through its path.
+
+use std::ffi::{CStr, CString, OsStr};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path};
+
+fn cstring(name: &OsStr) -> io::Result<CString> {
+    CString::new(name.as_bytes()).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))
+}
+
+/// `name` in `at` as a directory, failing with `ELOOP` on a symlink
+/// unless `follow`
+fn open_dir(at: RawFd, name: &CStr, follow: bool) -> io::Result<OwnedFd> {
+    let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+    if !follow {
+        flags |= libc::O_NOFOLLOW;
+    }
+    let fd = unsafe { libc::openat(at, name.as_ptr(), flags) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// The names in `dir`, without `.` and `..`
+fn names(dir: &OwnedFd) -> io::Result<Vec<CString>> {
+    let fd = unsafe { libc::dup(dir.as_raw_fd()) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let stream = unsafe { libc::fdopendir(fd) };
+    if stream.is_null() {
+        let e = io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        return Err(e);
+    }
+    let mut names = Vec::new();
+    loop {
+        let entry = unsafe { libc::readdir(stream) };
+        if entry.is_null() {
+            break;
+        }
+        let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+        if name.to_bytes() != b"." && name.to_bytes() != b".." {
+            names.push(name.to_owned());
+        }
+    }
+    unsafe { libc::closedir(stream) };
+    Ok(names)
+}
+
+/// Remove everything in `dir`, returning the space it held
+fn clear(dir: &OwnedFd) -> io::Result<u64> {
+    let mut freed = 0;
+    for name in names(dir)? {
+        let st = stat_at(dir.as_raw_fd(), &name)?;
+        let flags = if is_dir(&st) {
+            freed += clear(&open_dir(dir.as_raw_fd(), &name, false)?)?;
+            libc::AT_REMOVEDIR
+        } else {
+            0
+        };
+        if unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), flags) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        freed += st.st_blocks as u64 * 512;
+    }
+    Ok(freed)
+}
+
+fn stat_at(at: RawFd, name: &CStr) -> io::Result<libc::stat> {
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    let r = unsafe { libc::fstatat(at, name.as_ptr(), &mut st, libc::AT_SYMLINK_NOFOLLOW) };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(st)
+}
+
+fn is_dir(st: &libc::stat) -> bool {
+    st.st_mode & libc::S_IFMT == libc::S_IFDIR
+}
+
+/// The home itself, which is root's to place and may be a link
+fn open_home(home: &Path) -> io::Result<OwnedFd> {
+    open_dir(libc::AT_FDCWD, &cstring(home.as_os_str())?, true)
+}
+
+/// `home/relative` as a directory, reached without a symlink below
+/// `home`; `ENOENT` when a component does not exist
+fn descend(home: &Path, relative: &Path) -> io::Result<OwnedFd> {
+    let mut dir = open_home(home)?;
+    let mut reached = home.to_path_buf();
+    for component in relative.components() {
+        let Component::Normal(name) = component else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} leaves {}", relative.display(), home.display()),
+            ));
+        };
+        reached.push(name);
+        dir = match open_dir(dir.as_raw_fd(), &cstring(name)?, false) {
+            Ok(next) => next,
+            Err(e) if matches!(e.raw_os_error(), Some(libc::ELOOP | libc::ENOTDIR)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} is not a directory but a link or file, left alone",
+                        reached.display()
+                    ),
+                ))
+            }
+            Err(e) => return Err(e),
+        };
+    }
+    Ok(dir)
+}
+
+/// The directory holding `home/relative`, reached as [`descend`] does,
+/// and the entry's name in it; `None` for the home itself
+fn parent(home: &Path, relative: &Path) -> io::Result<Option<(OwnedFd, CString)>> {
+    let Some(name) = relative.file_name() else {
+        return match relative.components().next() {
+            None => Ok(None),
+            Some(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} leaves {}", relative.display(), home.display()),
+            )),
+        };
+    };
+    let dir = descend(home, relative.parent().unwrap_or(Path::new("")))?;
+    Ok(Some((dir, cstring(name)?)))
+}
+
+/// Remove everything inside `home/relative`, keeping the directory, and
+/// return the space it held. `relative` must stay under `home`; a symlink
+/// anywhere below `home` on the way is refused with an error naming it.
+/// A directory that does not exist frees nothing
+pub fn empty(home: &Path, relative: &Path) -> io::Result<u64> {
+    match descend(home, relative) {
+        Ok(dir) => clear(&dir),
+        Err(e) if e.raw_os_error() == Some(libc::ENOENT) => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Give everything in `dir` on device `dev` to `uid:gid`, counting it
+fn chown_in(dir: &OwnedFd, dev: libc::dev_t, uid: u32, gid: u32) -> io::Result<usize> {
+    let mut count = 0;
+    for name in names(dir)? {
+        count += chown_at(dir.as_raw_fd(), &name, dev, uid, gid)?;
+    }
+    Ok(count)
+}
+
+/// Give `name` in `at`, and what is below it on device `dev`, to
+/// `uid:gid`; symlinks are changed themselves, not followed
+fn chown_at(at: RawFd, name: &CStr, dev: libc::dev_t, uid: u32, gid: u32) -> io::Result<usize> {
+    let st = stat_at(at, name)?;
+    if st.st_dev != dev {
+        return Ok(0);
+    }
+    let r = unsafe { libc::fchownat(at, name.as_ptr(), uid, gid, libc::AT_SYMLINK_NOFOLLOW) };
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if !is_dir(&st) {
+        return Ok(1);
+    }
+    // Refuses a symlink swapped in since the stat
+    Ok(1 + chown_in(&open_dir(at, name, false)?, dev, uid, gid)?)
+}
+
+/// Give `home/relative`, and what is below it, to `uid:gid`, as `chown
+/// -R` would without crossing into another filesystem, and return how
+/// many entries changed. An empty `relative` is the home itself; a
+/// symlink on the way is refused as [`empty`] refuses it
+pub fn chown(home: &Path, relative: &Path, uid: u32, gid: u32) -> io::Result<usize> {
+    match parent(home, relative)? {
+        Some((dir, name)) => {
+            let dev = stat_at(dir.as_raw_fd(), &name)?.st_dev;
+            chown_at(dir.as_raw_fd(), &name, dev, uid, gid)
+        }
+        None => {
+            let dir = open_home(home)?;
+            let mut st: libc::stat = unsafe { std::mem::zeroed() };
+            if unsafe { libc::fstat(dir.as_raw_fd(), &mut st) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if unsafe { libc::fchown(dir.as_raw_fd(), uid, gid) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(1 + chown_in(&dir, st.st_dev, uid, gid)?)
+        }
+    }
+}
+
+/// Set the mode of `home/relative` to `mode`, through a descriptor that
+/// refuses a symlink; an empty `relative` is the home itself
+pub fn chmod(home: &Path, relative: &Path, mode: u32) -> io::Result<()> {
+    let file = match parent(home, relative)? {
+        None => open_home(home)?,
+        Some((dir, name)) => {
+            // Non-blocking, so a FIFO the user left there does not hang the repair
+            let flags = libc::O_RDONLY
+                | libc::O_NOFOLLOW
+                | libc::O_NONBLOCK
+                | libc::O_NOCTTY
+                | libc::O_CLOEXEC;
+            let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags) };
+            if fd < 0 {
+                let e = io::Error::last_os_error();
+                if e.raw_os_error() == Some(libc::ELOOP) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{} is a link, left alone", relative.display()),
+                    ));
+                }
+                return Err(e);
+            }
+            unsafe { OwnedFd::from_raw_fd(fd) }
+        }
+    };
+    if unsafe { libc::fchmod(file.as_raw_fd(), mode as libc::mode_t) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Whether `home/relative` is a directory reached without a symlink below
+/// `home`, for listing what `empty` would take
+pub fn is_plain_dir(home: &Path, relative: &Path) -> bool {
+    let mut path = home.to_path_buf();
+    relative.components().all(|component| {
+        let Component::Normal(name) = component else {
+            return false;
+        };
+        path.push(name);
+        std::fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir())
+    })
+}
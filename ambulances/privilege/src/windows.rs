@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Elevation checks and UAC prompts on Windows
+//!
+//! An elevated token carries the High (S-1-16-12288) or System
+//! (S-1-16-16384) mandatory label, which `whoami /groups` lists. A
+//! process started through UAC cannot have its output piped back, so
+//! the elevated `cmd.exe` writes it to temporary files read afterwards.
+
+use crate::Output;
+use std::path::Path;
+use std::process::Command;
+
+const ELEVATED_LABELS: &[&str] = &["S-1-16-12288", "S-1-16-16384"];
+
+pub fn is_elevated() -> bool {
+    Command::new("whoami")
+        .arg("/groups")
+        .output()
+        .is_ok_and(|o| {
+            let groups = String::from_utf8_lossy(&o.stdout);
+            ELEVATED_LABELS.iter().any(|l| groups.contains(l))
+        })
+}
+
+/// `s` as one argument for `cmd.exe`; quotes inside cannot be escaped
+fn cmd_word(s: &str) -> Result<String, String> {
+    if s.contains('"') {
+        return Err(format!("Cannot pass {} through cmd.exe", s));
+    }
+    Ok(format!("\"{}\"", s))
+}
+
+/// `s` inside a PowerShell single-quoted string
+fn powershell_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+pub fn run(program: &Path, args: &[&str], env: &[(&str, String)]) -> Result<Output, String> {
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let out = dir.join(format!("ambulance-elevated-{}.out", id));
+    let err = dir.join(format!("ambulance-elevated-{}.err", id));
+
+    let mut line = String::new();
+    for (k, v) in env {
+        line.push_str(&format!("set {}&& ", cmd_word(&format!("{}={}", k, v))?));
+    }
+    line.push_str(&cmd_word(&program.to_string_lossy())?);
+    for a in args {
+        line.push(' ');
+        line.push_str(&cmd_word(a)?);
+    }
+    line.push_str(&format!(
+        " > {} 2> {}",
+        cmd_word(&out.to_string_lossy())?,
+        cmd_word(&err.to_string_lossy())?
+    ));
+    // /s keeps cmd.exe from mangling the quotes inside the outer pair
+    let script = format!(
+        "$p = Start-Process -FilePath cmd.exe -ArgumentList {} -Verb RunAs -Wait -PassThru -WindowStyle Hidden; exit $p.ExitCode",
+        powershell_string(&format!("/d /s /c \"{}\"", line))
+    );
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .map_err(|e| format!("UAC prompt could not start: {}", e))?;
+
+    let read = |path: &Path| {
+        let bytes = std::fs::read(path).unwrap_or_default();
+        let _ = std::fs::remove_file(path);
+        bytes
+    };
+    let mut output = Output {
+        code: status.status.code(),
+        stdout: read(&out),
+        stderr: read(&err),
+    };
+    // A declined prompt makes Start-Process fail before anything ran
+    if output.stderr.is_empty() {
+        output.stderr = status.stderr;
+    }
+    Ok(output)
+}
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The helper daemon's policy, against a caller who repoints a symlink
+#![cfg(unix)]
+
+use ambulance_privilege::helper::{self, Policy, Request};
+use std::fs;
+use std::os::unix::fs::{symlink, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// A directory with an allowed program and one the caller wrote
+fn setup(name: &str) -> (PathBuf, Policy) {
+    let dir = std::env::temp_dir().join(format!("helper-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (program, says) in [("repair", "allowed"), ("evil", "evil")] {
+        let path = dir.join(program);
+        fs::write(&path, format!("#!/bin/sh\necho {}\n", says)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let policy = Policy {
+        programs: vec![dir.join("repair")],
+        subcommands: vec!["repair".to_string()],
+        env: vec!["PING_TIMEOUT".to_string()],
+    };
+    (dir, policy)
+}
+
+#[test]
+fn runs_the_checked_program_when_the_link_is_repointed() {
+    let (dir, policy) = setup("swap");
+    let link = dir.join("link");
+    symlink(dir.join("repair"), &link).unwrap();
+    let request = Request::new(&link, &["repair"], &[]);
+
+    let program = policy.check(&request).unwrap();
+    assert_eq!(program, dir.join("repair").canonicalize().unwrap());
+    fs::remove_file(&link).unwrap();
+    symlink(dir.join("evil"), &link).unwrap();
+
+    let response = helper::execute(&program, &request);
+    assert_eq!(response.error, None);
+    assert_eq!(response.stdout, "allowed\n");
+}
+
+#[test]
+fn refuses_what_the_policy_does_not_list() {
+    let (dir, policy) = setup("refuse");
+    let check = |program: &Path, args: &[&str], env: &[(&str, String)]| {
+        policy.check(&Request::new(program, args, env))
+    };
+    assert!(check(&dir.join("evil"), &["repair"], &[]).is_err());
+    assert!(check(&dir.join("repair"), &["diagnose"], &[]).is_err());
+    assert!(check(
+        &dir.join("repair"),
+        &["repair"],
+        &[("LD_PRELOAD", "x".into())]
+    )
+    .is_err());
+    assert!(check(
+        &dir.join("repair"),
+        &["repair"],
+        &[("PING_TIMEOUT", "5".into())]
+    )
+    .is_ok());
+}
+
+#[test]
+fn loads_a_policy_file() {
+    let (dir, _) = setup("load");
+    let path = dir.join("helper.toml");
+    fs::write(
+        &path,
+        "programs = [\"/usr/bin/disk-ambulance\"]\nsubcommands = [\"repair\"]\n",
+    )
+    .unwrap();
+    let policy = Policy::load(&path).unwrap();
+    assert_eq!(policy.programs, [PathBuf::from("/usr/bin/disk-ambulance")]);
+    assert!(policy.env.is_empty());
+    fs::write(&path, "programs = \"/usr/bin/disk-ambulance\"\n").unwrap();
+    assert!(Policy::load(&path).is_err());
+}
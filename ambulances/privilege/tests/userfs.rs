@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Emptying a directory in a home that its user has laced with symlinks
+#![cfg(unix)]
+
+use ambulance_privilege::userfs;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// A home and, beside it, a directory standing in for /etc
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let base = std::env::temp_dir().join(format!("userfs-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&base);
+    let (home, etc) = (base.join("home"), base.join("etc"));
+    fs::create_dir_all(home.join(".cache/thumbnails/large")).unwrap();
+    fs::create_dir_all(etc.join("thumbnails")).unwrap();
+    fs::write(etc.join("shadow"), "root:x\n").unwrap();
+    fs::write(etc.join("thumbnails/passwd"), "root\n").unwrap();
+    (home, etc)
+}
+
+#[test]
+fn empties_the_directory_and_only_unlinks_links_inside_it() {
+    let (home, etc) = setup("inside");
+    let thumbnails = home.join(".cache/thumbnails");
+    fs::write(thumbnails.join("large/a.png"), vec![0; 8192]).unwrap();
+    symlink(&etc, thumbnails.join("etc")).unwrap();
+
+    let freed = userfs::empty(&home, Path::new(".cache/thumbnails")).unwrap();
+    assert!(freed >= 8192);
+    assert_eq!(fs::read_dir(&thumbnails).unwrap().count(), 0);
+    assert!(etc.join("shadow").exists());
+    assert_eq!(
+        userfs::empty(&home, Path::new(".cache/missing")).unwrap(),
+        0
+    );
+}
+
+#[test]
+fn refuses_a_symlink_on_the_way_down() {
+    let (home, etc) = setup("on-the-way");
+    fs::remove_dir_all(home.join(".cache")).unwrap();
+    symlink(&etc, home.join(".cache")).unwrap();
+
+    assert!(!userfs::is_plain_dir(&home, Path::new(".cache/thumbnails")));
+    let e = userfs::empty(&home, Path::new(".cache/thumbnails")).unwrap_err();
+    assert!(e.to_string().contains(".cache"), "{}", e);
+    assert!(etc.join("thumbnails/passwd").exists());
+
+    // The last component counts too
+    fs::remove_file(home.join(".cache")).unwrap();
+    fs::create_dir(home.join(".cache")).unwrap();
+    symlink(etc.join("thumbnails"), home.join(".cache/thumbnails")).unwrap();
+    assert!(userfs::empty(&home, Path::new(".cache/thumbnails")).is_err());
+    assert!(etc.join("thumbnails/passwd").exists());
+    assert!(userfs::empty(&home, Path::new("../etc")).is_err());
+}
+
+#[test]
+fn chmod_refuses_a_link_where_the_entry_was() {
+    use std::os::unix::fs::PermissionsExt;
+    let (home, etc) = setup("chmod");
+    let mode = |path: &Path| fs::symlink_metadata(path).unwrap().permissions().mode() & 0o7777;
+    fs::create_dir(home.join(".ssh")).unwrap();
+    fs::write(home.join(".ssh/id_x"), "key\n").unwrap();
+    fs::set_permissions(etc.join("shadow"), fs::Permissions::from_mode(0o600)).unwrap();
+
+    userfs::chmod(&home, Path::new(".ssh/id_x"), 0o600).unwrap();
+    assert_eq!(mode(&home.join(".ssh/id_x")), 0o600);
+    userfs::chmod(&home, Path::new(""), 0o750).unwrap();
+    assert_eq!(mode(&home), 0o750);
+
+    // Swapped for a link once the diagnosis has looked
+    fs::remove_file(home.join(".ssh/id_x")).unwrap();
+    symlink(etc.join("shadow"), home.join(".ssh/id_x")).unwrap();
+    assert!(userfs::chmod(&home, Path::new(".ssh/id_x"), 0o755).is_err());
+    fs::remove_dir_all(home.join(".ssh")).unwrap();
+    symlink(&etc, home.join(".ssh")).unwrap();
+    assert!(userfs::chmod(&home, Path::new(".ssh/shadow"), 0o755).is_err());
+    assert_eq!(mode(&etc.join("shadow")), 0o600);
+}
+
+#[test]
+fn chown_changes_links_and_not_what_they_point_at() {
+    use std::os::unix::fs::MetadataExt;
+    // Only root can give files away
+    if unsafe { libc::geteuid() } != 0 {
+        return;
+    }
+    let (home, etc) = setup("chown");
+    let owner = |path: &Path| fs::symlink_metadata(path).unwrap().uid();
+    let config = home.join(".config/foo");
+    fs::create_dir_all(config.join("sub")).unwrap();
+    fs::write(config.join("sub/settings"), "x\n").unwrap();
+    symlink(&etc, config.join("etc")).unwrap();
+
+    let count = userfs::chown(&home, Path::new(".config/foo"), 4242, 4242).unwrap();
+    assert_eq!(count, 4);
+    assert_eq!(owner(&config.join("sub/settings")), 4242);
+    assert_eq!(owner(&config.join("etc")), 4242);
+    assert_eq!(owner(&etc.join("shadow")), 0);
+
+    fs::remove_dir_all(home.join(".config")).unwrap();
+    symlink(&etc, home.join(".config")).unwrap();
+    assert!(userfs::chown(&home, Path::new(".config/thumbnails"), 4242, 4242).is_err());
+    assert_eq!(owner(&etc.join("thumbnails/passwd")), 0);
+    assert!(userfs::chown(&home, Path::new(".."), 4242, 4242).is_err());
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! OnCalendar= expressions
+//!
+//! systemd is the judge of what parses: every expression goes through
+//! `systemd-analyze calendar`, which also gives the next two elapses and
+//! so the interval a timer is expected to fire at. `fix` rewrites the
+//! mistakes people make most, cron syntax first among them, and only
+//! offers a rewrite systemd accepts.
+
+use crate::system;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Calendar {
+    pub expression: String,
+    /// Unit file or drop-in the expression is set in
+    pub file: String,
+    /// systemd's normalized form, when it parses
+    pub normalized: Option<String>,
+    /// Time between the next two elapses, in seconds
+    pub interval_secs: Option<u64>,
+    pub error: Option<String>,
+    /// A corrected expression systemd accepts, for one that does not parse
+    pub fix: Option<String>,
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// `Thu 2026-10-15 22:00:00 UTC` as seconds since the epoch
+fn parse_utc(value: &str) -> Option<i64> {
+    let mut parts = value.split_whitespace().skip(1);
+    let mut date = parts.next()?.split('-').map(|p| p.parse::<i64>().ok());
+    let mut time = parts.next()?.split(':').map(|p| p.parse::<i64>().ok());
+    let days = days_from_civil(date.next()??, date.next()??, date.next()??);
+    Some(days * 86_400 + time.next()?? * 3600 + time.next()?? * 60 + time.next()??)
+}
+
+/// Check `expression` with systemd, filling in its normalized form and
+/// interval, or the error
+pub fn analyze(expression: &str, file: &str) -> Calendar {
+    let mut calendar = Calendar {
+        expression: expression.to_string(),
+        file: file.to_string(),
+        normalized: None,
+        interval_secs: None,
+        error: None,
+        fix: None,
+    };
+    // Without systemd-analyze nothing can be judged, so nothing is flagged
+    if !system::has("systemd-analyze") {
+        return calendar;
+    }
+    let output = match system::run(
+        "systemd-analyze",
+        &["calendar", "--iterations=2", expression],
+    ) {
+        Ok(output) => output,
+        Err(e) => {
+            calendar.error = Some(
+                e.split_once(" failed: ")
+                    .map_or(e.as_str(), |(_, reason)| reason)
+                    .to_string(),
+            );
+            calendar.fix = fix(expression);
+            return calendar;
+        }
+    };
+
+    // Each elapse is followed by its UTC form unless the local zone is UTC
+    let mut elapses: Vec<i64> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        let Some((label, value)) = line.split_once(": ") else {
+            continue;
+        };
+        match label {
+            "Normalized form" => calendar.normalized = Some(value.to_string()),
+            "Next elapse" | "Iter. #2" if value.ends_with(" UTC") => {
+                elapses.extend(parse_utc(value))
+            }
+            "(in UTC)" => elapses.extend(parse_utc(value)),
+            _ => {}
+        }
+    }
+    if let [first, second, ..] = elapses[..] {
+        calendar.interval_secs = u64::try_from(second - first).ok();
+    }
+    calendar
+}
+
+/// Whether systemd accepts `expression`
+fn valid(expression: &str) -> bool {
+    system::run("systemd-analyze", &["calendar", expression]).is_ok()
+}
+
+const SHORTHANDS: &[(&str, &str, &str)] = &[
+    // word, shorthand, date part used when a time is added
+    ("minute", "minutely", "*-*-*"),
+    ("hour", "hourly", "*-*-*"),
+    ("day", "daily", "*-*-*"),
+    ("week", "weekly", "Mon *-*-*"),
+    ("month", "monthly", "*-*-01"),
+    ("year", "yearly", "*-01-01"),
+];
+
+const WEEKDAYS: &[&str] = &["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// One cron field in systemd's syntax: ranges `a-b` become `a..b` and
+/// steps over everything start at zero
+fn cron_field(field: &str, names: &[&str], first: usize) -> Option<String> {
+    let named = |value: &str| -> Option<String> {
+        if value.chars().all(|c| c.is_ascii_digit()) {
+            return Some(value.to_string());
+        }
+        let index = names.iter().position(|n| value.eq_ignore_ascii_case(n))?;
+        Some((index + first).to_string())
+    };
+    field
+        .split(',')
+        .map(|item| {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (item, None),
+            };
+            let range = match range {
+                "*" if step.is_some() => first.to_string(),
+                "*" => "*".to_string(),
+                _ => match range.split_once('-') {
+                    Some((a, b)) => format!("{}..{}", named(a)?, named(b)?),
+                    None => named(range)?,
+                },
+            };
+            Some(match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|items| items.join(","))
+}
+
+/// Cron's day-of-week field as systemd weekday names
+fn cron_weekdays(field: &str) -> Option<String> {
+    let day = |value: &str| -> Option<&str> {
+        match value.parse::<usize>() {
+            Ok(n) => WEEKDAYS.get(n).copied(),
+            Err(_) => WEEKDAYS
+                .iter()
+                .find(|d| value.eq_ignore_ascii_case(d))
+                .copied(),
+        }
+    };
+    field
+        .split(',')
+        .map(|item| match item.split_once('-') {
+            Some((a, b)) => Some(format!("{}..{}", day(a)?, day(b)?)),
+            None => day(item).map(str::to_string),
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|days| days.join(","))
+}
+
+/// `30 3 * * 1-5` as `Mon..Fri *-*-* 3:30:00`
+fn from_cron(expression: &str) -> Option<String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let [minute, hour, dom, month, dow] = fields[..] else {
+        return None;
+    };
+    // cron runs when either day field matches, systemd when both do
+    if dom != "*" && dow != "*" {
+        return None;
+    }
+    let pad = |field: String| {
+        if field.len() == 1 && field != "*" {
+            format!("0{}", field)
+        } else {
+            field
+        }
+    };
+    let time = format!(
+        "{}:{}:00",
+        pad(cron_field(hour, &[], 0)?),
+        pad(cron_field(minute, &[], 0)?)
+    );
+    let date = format!(
+        "*-{}-{}",
+        cron_field(month, MONTHS, 1)?,
+        cron_field(dom, &[], 1)?
+    );
+    Some(if dow == "*" {
+        format!("{} {}", date, time)
+    } else {
+        format!("{} {} {}", cron_weekdays(dow)?, date, time)
+    })
+}
+
+/// Plain-language and cron-shorthand forms: `every day`, `@daily`,
+/// `daily at 03:00`, `every tuesday`
+fn from_words(expression: &str) -> Option<String> {
+    let lower = expression.to_ascii_lowercase();
+    let words: Vec<&str> = lower
+        .split_whitespace()
+        .filter(|w| *w != "at" && *w != "every")
+        .collect();
+    let (first, time) = match words[..] {
+        [first] => (first, None),
+        [first, time] => (first, Some(time)),
+        _ => return None,
+    };
+    let first = first.trim_start_matches('@');
+    let first = if first == "everyday" || first == "midnight" {
+        "day"
+    } else if first == "annually" {
+        "year"
+    } else {
+        first
+    };
+    let time = match time {
+        Some(time) if !time.contains(':') => return None,
+        time => time,
+    };
+    if let Some(day) = WEEKDAYS.iter().find(|d| {
+        first.starts_with(&d.to_ascii_lowercase()) && (first.len() == 3 || first.ends_with("day"))
+    }) {
+        return Some(format!("{} *-*-* {}", day, time.unwrap_or("00:00:00")));
+    }
+    let (_, shorthand, date) = SHORTHANDS
+        .iter()
+        .find(|(word, shorthand, _)| first == *word || first == *shorthand)?;
+    Some(match time {
+        None => shorthand.to_string(),
+        Some(time) => format!("{} {}", date, time),
+    })
+}
+
+/// A corrected form of `expression` that systemd accepts
+pub fn fix(expression: &str) -> Option<String> {
+    let trimmed = expression
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .trim_end_matches([';', ','])
+        .replace("24:00", "00:00");
+    [
+        Some(trimmed.clone()),
+        from_cron(&trimmed),
+        from_words(&trimmed),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|candidate| candidate != expression && valid(candidate))
+}
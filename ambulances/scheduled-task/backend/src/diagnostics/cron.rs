@@ -0,0 +1,466 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Cron jobs
+//!
+//! Crontabs are checked the way cron reads them, including the quiet
+//! ways it drops a job: a last line without a newline, a `%` that turns
+//! the rest of the command into standard input, a cron.d file cron
+//! refuses for its owner, mode or name. Job failures come from cron's
+//! own lines in the journal: Debian's cron logs the exit status of each
+//! job that failed, from the same process that logged the command.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// Crontabs with a user field, then directories of them
+const SYSTEM_CRONTABS: &[&str] = &["/etc/crontab"];
+const SYSTEM_DIRS: &[&str] = &["/etc/cron.d"];
+
+/// Per-user crontabs: Debian, then Fedora and Arch
+const USER_DIRS: &[&str] = &["/var/spool/cron/crontabs", "/var/spool/cron"];
+
+const DAEMONS: &[&str] = &["cron", "crond", "fcron"];
+
+const SPECIALS: &[&str] = &[
+    "@reboot",
+    "@yearly",
+    "@annually",
+    "@monthly",
+    "@weekly",
+    "@daily",
+    "@midnight",
+    "@hourly",
+];
+
+/// Field name, lowest and highest value, and names allowed in place of numbers
+const FIELDS: &[(&str, u32, u32, &[&str])] = &[
+    ("minute", 0, 59, &[]),
+    ("hour", 0, 23, &[]),
+    ("day of month", 1, 31, &[]),
+    (
+        "month",
+        1,
+        12,
+        &[
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ],
+    ),
+    (
+        "day of week",
+        0,
+        7,
+        &["sun", "mon", "tue", "wed", "thu", "fri", "sat"],
+    ),
+];
+
+const WINDOW_DAYS: u64 = 7;
+
+/// Cron journal lines read at most
+const MAX_ENTRIES: usize = 50_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CronProblem {
+    pub file: String,
+    /// 1-based; absent for a problem with the whole file
+    pub line: Option<usize>,
+    pub problem: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CronFailure {
+    pub user: String,
+    pub command: String,
+    pub failures: usize,
+    pub last_status: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CronDiagnostics {
+    /// Cron daemon found running
+    pub daemon: Option<String>,
+    pub crontabs: usize,
+    pub jobs: usize,
+    pub problems: Vec<CronProblem>,
+    /// Over the last `WINDOW_DAYS`, most failures first
+    pub failures: Vec<CronFailure>,
+    /// Times cron threw job output away for want of a mail transport
+    pub discarded_output: usize,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn running_daemon() -> Option<String> {
+    std::fs::read_dir(host::path("/proc"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| system::read(entry.path().join("comm")))
+        .map(|comm| comm.trim().to_string())
+        .find(|comm| DAEMONS.contains(&comm.as_str()))
+}
+
+fn users() -> Vec<String> {
+    system::read("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.split(':').next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check one field, returning why it is wrong
+fn check_field(value: &str, (name, low, high, names): (&str, u32, u32, &[&str])) -> Option<String> {
+    let number = |v: &str| -> Result<u32, String> {
+        if let Some(i) = names.iter().position(|n| v.eq_ignore_ascii_case(n)) {
+            return Ok(i as u32 + low);
+        }
+        let n: u32 = v
+            .parse()
+            .map_err(|_| format!("The {} field has '{}', not a number", name, v))?;
+        if n < low || n > high {
+            return Err(format!(
+                "The {} field has {}, outside {}-{}",
+                name, n, low, high
+            ));
+        }
+        Ok(n)
+    };
+    for item in value.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+        if let Some(step) = step {
+            if !step.parse::<u32>().is_ok_and(|s| s > 0) {
+                return Some(format!("The {} field has step '{}'", name, step));
+            }
+        }
+        let result = match range {
+            "*" => Ok(()),
+            _ => match range.split_once('-') {
+                Some((a, b)) => match (number(a), number(b)) {
+                    (Ok(a), Ok(b)) if a > b => Err(format!(
+                        "The {} field has the backwards range {}",
+                        name, range
+                    )),
+                    (Ok(_), Ok(_)) => Ok(()),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                },
+                None => number(range).map(|_| ()),
+            },
+        };
+        if let Err(e) = result {
+            return Some(e);
+        }
+    }
+    None
+}
+
+/// Whether `line` sets an environment variable rather than a job
+fn is_assignment(line: &str) -> bool {
+    line.split_once('=').is_some_and(|(name, _)| {
+        let name = name.trim();
+        !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// Check one crontab; system crontabs carry a user field
+fn check_crontab(path: &Path, with_user: bool, users: &[String], diag: &mut CronDiagnostics) {
+    let Some(text) = system::read(path) else {
+        return;
+    };
+    let file = path.display().to_string();
+    diag.crontabs += 1;
+    let problem = |line: Option<usize>, problem: String| CronProblem {
+        file: file.clone(),
+        line,
+        problem,
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    for (index, raw) in lines.iter().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') || is_assignment(line) {
+            continue;
+        }
+        diag.jobs += 1;
+        let number = Some(index + 1);
+        let mut fields = line.split_whitespace();
+        let first = fields.next().unwrap_or_default();
+        if first.starts_with('@') {
+            if !SPECIALS.contains(&first) {
+                diag.problems
+                    .push(problem(number, format!("Unknown schedule {}", first)));
+                continue;
+            }
+        } else {
+            let schedule: Vec<&str> = std::iter::once(first)
+                .chain(fields.by_ref().take(4))
+                .collect();
+            if schedule.len() < 5 {
+                diag.problems.push(problem(
+                    number,
+                    "Fewer than the five schedule fields".to_string(),
+                ));
+                continue;
+            }
+            if let Some(error) = schedule
+                .iter()
+                .zip(FIELDS)
+                .find_map(|(value, field)| check_field(value, *field))
+            {
+                diag.problems.push(problem(number, error));
+                continue;
+            }
+        }
+        if with_user {
+            match fields.next() {
+                Some(user) if !users.iter().any(|u| u == user) => {
+                    diag.problems.push(problem(
+                        number,
+                        format!("User {} does not exist, so the job never runs", user),
+                    ));
+                    continue;
+                }
+                Some(_) => {}
+                None => {
+                    diag.problems
+                        .push(problem(number, "No user and no command".to_string()));
+                    continue;
+                }
+            }
+        }
+        let command: Vec<&str> = fields.collect();
+        if command.is_empty() {
+            diag.problems
+                .push(problem(number, "No command".to_string()));
+            continue;
+        }
+        let command = command.join(" ");
+        if command.replace("\\%", "").contains('%') {
+            diag.problems.push(problem(
+                number,
+                "Unescaped % ends the command there and feeds the rest to its input; write \\%"
+                    .to_string(),
+            ));
+        }
+    }
+    if !text.is_empty() && !text.ends_with('\n') {
+        diag.problems.push(problem(
+            Some(lines.len()),
+            "No newline at the end of the file, so cron ignores this last line".to_string(),
+        ));
+    }
+}
+
+/// cron.d files cron skips over who owns them or their mode or name
+fn check_dropin(path: &Path, debian: bool, diag: &mut CronDiagnostics) -> bool {
+    let file = path.display().to_string();
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Ok(meta) = std::fs::metadata(path) else {
+        return false;
+    };
+    let skipped = if meta.uid() != 0 {
+        Some("is not owned by root, so cron ignores it".to_string())
+    } else if meta.mode() & 0o022 != 0 {
+        Some("is group- or world-writable, so cron ignores it".to_string())
+    } else if debian
+        && !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Some("has a name Debian's cron skips: only letters, digits, - and _ are read".to_string())
+    } else {
+        None
+    };
+    if let Some(problem) = skipped {
+        diag.problems.push(CronProblem {
+            file,
+            line: None,
+            problem: format!("{} {}", name, problem),
+        });
+        return false;
+    }
+    true
+}
+
+fn files(dir: &str) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            !name.starts_with('.') && !name.ends_with('~') && name != "placeholder"
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// `(root) CMD (command)` and `(CRON) error (grandchild #123 failed with exit status 1)`
+fn failures(diag: &mut CronDiagnostics) -> std::io::Result<()> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    for (i, identifier) in ["CRON", "cron", "crond", "CROND"].iter().enumerate() {
+        if i > 0 {
+            journal.add_disjunction()?;
+        }
+        journal.add_match(&format!("SYSLOG_IDENTIFIER={}", identifier))?;
+    }
+    journal.seek_tail()?;
+    let since = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64)
+        .saturating_sub(WINDOW_DAYS * 86_400 * 1_000_000);
+
+    let mut commands: HashMap<String, (String, String)> = HashMap::new();
+    let mut failed: Vec<(String, i32)> = Vec::new();
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? || journal.realtime_usec()? < since {
+            break;
+        }
+        let message = journal.field("MESSAGE").unwrap_or_default();
+        let pid = journal.field("_PID").unwrap_or_default();
+        if message.contains("No MTA installed, discarding output") {
+            diag.discarded_output += 1;
+        } else if let Some((_, status)) = message.split_once("failed with exit status ") {
+            if let Ok(status) = status.trim_end_matches(')').trim().parse() {
+                failed.push((pid, status));
+            }
+        } else if let Some((user, command)) = message.split_once(") CMD (") {
+            let user = user.trim_start_matches('(').to_string();
+            let command = command.strip_suffix(')').unwrap_or(command).to_string();
+            commands.insert(pid, (user, command));
+        }
+    }
+
+    // Read newest first, so the first status seen per job is its last
+    for (pid, status) in failed {
+        let (user, command) = commands
+            .get(&pid)
+            .cloned()
+            .unwrap_or_else(|| ("?".to_string(), "(command not logged)".to_string()));
+        match diag
+            .failures
+            .iter_mut()
+            .find(|f| f.user == user && f.command == command)
+        {
+            Some(f) => f.failures += 1,
+            None => diag.failures.push(CronFailure {
+                user,
+                command,
+                failures: 1,
+                last_status: status,
+            }),
+        }
+    }
+    diag.failures.sort_by_key(|f| Reverse(f.failures));
+    Ok(())
+}
+
+pub fn diagnose() -> CronDiagnostics {
+    let mut diag = CronDiagnostics {
+        daemon: running_daemon(),
+        ..CronDiagnostics::default()
+    };
+    let users = users();
+    let debian = Path::new("/etc/debian_version").exists();
+    for path in SYSTEM_CRONTABS {
+        check_crontab(Path::new(path), true, &users, &mut diag);
+    }
+    for dir in SYSTEM_DIRS {
+        for path in files(dir) {
+            if check_dropin(&path, debian, &mut diag) {
+                check_crontab(&path, true, &users, &mut diag);
+            }
+        }
+    }
+    for dir in USER_DIRS {
+        for path in files(dir) {
+            check_crontab(&path, false, &users, &mut diag);
+        }
+    }
+    // Without a readable journal there are simply no failures to report
+    let _ = failures(&mut diag);
+
+    if diag.daemon.is_none() && diag.jobs > 0 {
+        diag.warnings.push(format!(
+            "{} cron job(s) are set up but no cron daemon is running",
+            diag.jobs
+        ));
+        diag.recommendations
+            .push("Start it: systemctl enable --now cron (crond on Fedora and Arch)".to_string());
+    }
+    for p in &diag.problems {
+        diag.warnings.push(match p.line {
+            Some(line) => format!("{}:{}: {}", p.file, line, p.problem),
+            None => format!("{}: {}", p.file, p.problem),
+        });
+    }
+    if !diag.problems.is_empty() {
+        diag.recommendations.push(
+            "Fix the lines above with `crontab -e` (per-user) or in the file (system)".to_string(),
+        );
+    }
+    for f in &diag.failures {
+        diag.warnings.push(format!(
+            "Cron job of {} failed {} time(s) in {} days, last with status {}: {}",
+            f.user, f.failures, WINDOW_DAYS, f.last_status, f.command
+        ));
+    }
+    if !diag.failures.is_empty() {
+        diag.recommendations.push(
+            "Run the failing command by hand as its user; cron's PATH is only /usr/bin:/bin"
+                .to_string(),
+        );
+    }
+    if diag.discarded_output > 0 {
+        diag.warnings.push(format!(
+            "Cron discarded job output {} time(s): no mail transport is installed",
+            diag.discarded_output
+        ));
+        diag.recommendations.push(
+            "Redirect job output to a file (>> /var/log/job.log 2>&1) or install an MTA to receive it"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl CronDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Cron ===");
+        println!(
+            "{} Daemon: {}",
+            mark(self.daemon.is_some() || self.jobs == 0),
+            self.daemon.as_deref().unwrap_or("not running")
+        );
+        println!(
+            "{} Jobs: {} in {} crontab(s), {} problem(s), {} failing",
+            mark(self.problems.is_empty() && self.failures.is_empty()),
+            self.jobs,
+            self.crontabs,
+            self.problems.len(),
+            self.failures.len()
+        );
+        if verbose && self.discarded_output > 0 {
+            println!("- Output discarded: {} time(s)", self.discarded_output);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Scheduled task diagnostics, one module per report section
+//!
+//! Timers and cron jobs are looked at as ambulance-core jobs at the same
+//! time, within the scan's budget, so parsing every crontab does not wait
+//! on systemd listing its units. A section the budget cut short is left
+//! empty and its timing says why; one that failed or timed out says so
+//! in its warnings.
+
+pub mod cron;
+pub mod timers;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+use systemd_shim::bus::Bus;
+
+/// How long a section may take; systemd-analyze checks every timer's
+/// calendar expression in turn
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Timers(timers::TimerDiagnostics),
+    Cron(cron::CronDiagnostics),
+}
+
+/// The timers section, or why the system bus could not give one
+fn timers() -> timers::TimerDiagnostics {
+    match Bus::system().and_then(|bus| {
+        let units = bus.list_units()?;
+        Ok(timers::diagnose(&bus, &units))
+    }) {
+        Ok(timers) => timers,
+        Err(e) => timers::TimerDiagnostics {
+            error: Some(format!("system bus: {}", e)),
+            ..timers::TimerDiagnostics::default()
+        },
+    }
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("timers", |_| Ok(Section::Timers(timers()))))
+        .job(Job::blocking("cron", |_| {
+            Ok(Section::Cron(cron::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        timers: Default::default(),
+        cron: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Timers(d)) => result.timers = d,
+            Some(Section::Cron(d)) => result.cron = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "timers" => &mut result.timers.warnings,
+                    _ => &mut result.cron.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
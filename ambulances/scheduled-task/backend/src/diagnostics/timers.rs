@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! systemd timers
+//!
+//! Timers and their state come from the manager over the system bus, the
+//! `OnCalendar=` lines from the unit files, checked by systemd itself.
+//! A timer is overdue when it has gone well past its calendar interval,
+//! or past its next elapse, without firing. The last run of the service
+//! it starts is taken from the manager's messages in the journal.
+
+use crate::calendar::{self, Calendar};
+use crate::report::{mark, print_notes};
+use crate::units;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::bus::{self, Bus, UnitInfo};
+use systemd_shim::journal::{self, Journal};
+
+const TIMER: &str = "org.freedesktop.systemd1.Timer";
+
+/// Lateness tolerated on top of the schedule, for `AccuracySec=` and
+/// `RandomizedDelaySec=`
+const SLACK_SECS: u64 = 15 * 60;
+
+/// Manager messages searched for a service's last run
+const MANAGER_SCAN: usize = 200;
+
+/// Lines of a failed service's output kept
+const LOG_LINES: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LastRun {
+    /// Microseconds since the epoch
+    pub finished: u64,
+    /// `success`, or the result the manager gave, e.g. `exit-code`
+    pub result: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimerReport {
+    pub name: String,
+    /// Unit the timer starts
+    pub service: Option<String>,
+    pub active_state: String,
+    pub calendars: Vec<Calendar>,
+    /// Microseconds since the epoch, absent if it never fired
+    pub last_trigger: Option<u64>,
+    pub next_elapse: Option<u64>,
+    pub persistent: bool,
+    /// How long past its schedule an active timer is without firing
+    pub overdue_secs: Option<u64>,
+    pub last_run: Option<LastRun>,
+    /// Main process exit of a failed run, e.g. `exit status 1`
+    pub exit: Option<String>,
+    /// Most recent lines the service wrote, oldest first, for a failed run
+    pub log: Vec<String>,
+}
+
+impl TimerReport {
+    pub fn failed_run(&self) -> bool {
+        self.last_run
+            .as_ref()
+            .is_some_and(|r| r.result != "success")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TimerDiagnostics {
+    pub timers: Vec<TimerReport>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn human_secs(secs: u64) -> String {
+    match secs {
+        0..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86_400, secs % 86_400 / 3600),
+    }
+}
+
+fn now_usec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64)
+}
+
+/// The manager's verdict on the last run of `service`
+fn last_run(service: &str) -> Option<LastRun> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY).ok()?;
+    journal.add_match(&format!("UNIT={}", service)).ok()?;
+    journal.add_match("_PID=1").ok()?;
+    journal.seek_tail().ok()?;
+    for _ in 0..MANAGER_SCAN {
+        if !journal.previous_entry().ok()? {
+            break;
+        }
+        let message = journal.field("MESSAGE").unwrap_or_default();
+        let result = if let Some(rest) = message.split_once("Failed with result '") {
+            rest.1.split('\'').next().unwrap_or_default().to_string()
+        } else if message.ends_with("Deactivated successfully.") || message.ends_with("Succeeded.")
+        {
+            "success".to_string()
+        } else {
+            continue;
+        };
+        return Some(LastRun {
+            finished: journal.realtime_usec().ok()?,
+            result,
+        });
+    }
+    None
+}
+
+/// The last `limit` lines `service` wrote, oldest first
+fn service_log(service: &str, limit: usize) -> Vec<String> {
+    let Ok(mut journal) = Journal::open(journal::LOCAL_ONLY) else {
+        return Vec::new();
+    };
+    if journal
+        .add_match(&format!("_SYSTEMD_UNIT={}", service))
+        .is_err()
+        || journal.seek_tail().is_err()
+    {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    while lines.len() < limit && journal.previous_entry().unwrap_or(false) {
+        if let Some(message) = journal.field("MESSAGE") {
+            lines.push(message);
+        }
+    }
+    lines.reverse();
+    lines
+}
+
+/// `ExecMainCode`/`ExecMainStatus` of a service, `None` for a clean exit
+fn exit(bus: &Bus, service: &str) -> Option<String> {
+    let path = bus::unit_path(service).ok()?;
+    let code = bus
+        .get_property_i32(bus::SYSTEMD, &path, bus::SERVICE, "ExecMainCode")
+        .ok()?;
+    let status = bus
+        .get_property_i32(bus::SYSTEMD, &path, bus::SERVICE, "ExecMainStatus")
+        .ok()?;
+    match code {
+        1 if status != 0 => Some(format!("exit status {}", status)),
+        2 => Some(format!("killed by signal {}", status)),
+        3 => Some(format!("dumped core on signal {}", status)),
+        _ => None,
+    }
+}
+
+fn inspect(bus: &Bus, unit: &UnitInfo, now: u64) -> TimerReport {
+    let u64_property = |interface: &str, member: &str| {
+        bus.get_property_u64(bus::SYSTEMD, &unit.path, interface, member)
+            .ok()
+            .filter(|v| *v > 0)
+    };
+    let fragment = bus
+        .get_property_string(bus::SYSTEMD, &unit.path, bus::UNIT, "FragmentPath")
+        .ok()
+        .filter(|p| !p.is_empty());
+    let calendars: Vec<Calendar> = units::on_calendar(&unit.name, fragment.as_deref())
+        .iter()
+        .map(|(expression, file)| calendar::analyze(expression, file))
+        .collect();
+    let service = bus
+        .get_property_string(bus::SYSTEMD, &unit.path, TIMER, "Unit")
+        .ok()
+        .filter(|s| !s.is_empty());
+    let last_trigger = u64_property(TIMER, "LastTriggerUSec");
+    let next_elapse = u64_property(TIMER, "NextElapseUSecRealtime");
+
+    let mut overdue_secs = None;
+    if unit.active_state == "active" {
+        let since = last_trigger
+            .into_iter()
+            .chain(u64_property(bus::UNIT, "ActiveEnterTimestamp"))
+            .max()
+            .unwrap_or(now);
+        let elapsed = now.saturating_sub(since) / 1_000_000;
+        if let Some(interval) = calendars.iter().filter_map(|c| c.interval_secs).min() {
+            if elapsed > interval * 2 + SLACK_SECS {
+                overdue_secs = Some(elapsed - interval);
+            }
+        }
+        if let Some(next) = next_elapse {
+            let late = now.saturating_sub(next) / 1_000_000;
+            if late > SLACK_SECS {
+                overdue_secs = Some(overdue_secs.map_or(late, |o: u64| o.max(late)));
+            }
+        }
+    }
+
+    let last_run = service.as_deref().and_then(last_run);
+    let failed = last_run.as_ref().is_some_and(|r| r.result != "success");
+    let (exit, log) = match service.as_deref() {
+        Some(service) if failed => (exit(bus, service), service_log(service, LOG_LINES)),
+        _ => (None, Vec::new()),
+    };
+
+    TimerReport {
+        name: unit.name.clone(),
+        service,
+        active_state: unit.active_state.clone(),
+        calendars,
+        last_trigger,
+        next_elapse,
+        persistent: bus
+            .get_property_bool(bus::SYSTEMD, &unit.path, TIMER, "Persistent")
+            .unwrap_or(false),
+        overdue_secs,
+        last_run,
+        exit,
+        log,
+    }
+}
+
+pub fn diagnose(bus: &Bus, units: &[UnitInfo]) -> TimerDiagnostics {
+    let now = now_usec();
+    let mut diag = TimerDiagnostics {
+        timers: units
+            .iter()
+            .filter(|u| u.name.ends_with(".timer") && u.load_state == "loaded")
+            .map(|u| inspect(bus, u, now))
+            .collect(),
+        ..TimerDiagnostics::default()
+    };
+
+    for timer in &diag.timers {
+        if timer.active_state == "failed" {
+            diag.warnings.push(format!("{} has failed", timer.name));
+            diag.recommendations.push(format!(
+                "Restart {}: scheduled-task-ambulance repair timers",
+                timer.name
+            ));
+        }
+        for c in timer.calendars.iter().filter(|c| c.error.is_some()) {
+            diag.warnings.push(format!(
+                "{}: OnCalendar={} does not parse, so systemd ignores it ({})",
+                timer.name,
+                c.expression,
+                c.error.as_deref().unwrap_or_default()
+            ));
+            diag.recommendations.push(match &c.fix {
+                Some(fix) => format!(
+                    "Use OnCalendar={}: scheduled-task-ambulance repair calendar",
+                    fix
+                ),
+                None => format!(
+                    "Correct it in {}; `systemd-analyze calendar '<expression>'` checks a fix",
+                    c.file
+                ),
+            });
+        }
+        if let Some(overdue) = timer.overdue_secs {
+            diag.warnings.push(format!(
+                "{} is {} past its schedule without firing",
+                timer.name,
+                human_secs(overdue)
+            ));
+            diag.recommendations.push(format!(
+                "Re-arm it: scheduled-task-ambulance repair timers{}",
+                if timer.persistent {
+                    ""
+                } else {
+                    "; Persistent=true catches up on runs missed while the machine was off"
+                }
+            ));
+        }
+        if timer.failed_run() {
+            let run = timer.last_run.as_ref().map_or("", |r| r.result.as_str());
+            let service = timer.service.as_deref().unwrap_or_default();
+            diag.warnings.push(format!(
+                "{}, started by {}, failed its last run ({}{})",
+                service,
+                timer.name,
+                run,
+                timer
+                    .exit
+                    .as_deref()
+                    .map(|e| format!(", {}", e))
+                    .unwrap_or_default()
+            ));
+            diag.recommendations.push(format!(
+                "See why with `journalctl -u {}`, then run it by hand with `systemctl start {}`",
+                service, service
+            ));
+        }
+    }
+
+    diag
+}
+
+impl TimerDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Timers ===");
+        if let Some(error) = &self.error {
+            println!("- Timers: unknown ({})", error);
+            println!();
+            return;
+        }
+        let troubled = |t: &TimerReport| {
+            t.active_state == "failed"
+                || t.overdue_secs.is_some()
+                || t.failed_run()
+                || t.calendars.iter().any(|c| c.error.is_some())
+        };
+        println!(
+            "{} Timers: {} ({} with problems)",
+            mark(!self.timers.iter().any(troubled)),
+            self.timers.len(),
+            self.timers.iter().filter(|t| troubled(t)).count()
+        );
+        for timer in &self.timers {
+            if !verbose && !troubled(timer) {
+                continue;
+            }
+            println!(
+                "  {} {} -> {} ({})",
+                mark(!troubled(timer)),
+                timer.name,
+                timer.service.as_deref().unwrap_or("?"),
+                timer.active_state
+            );
+            if verbose {
+                for c in &timer.calendars {
+                    println!(
+                        "      OnCalendar={}{}",
+                        c.expression,
+                        c.normalized
+                            .as_deref()
+                            .map(|n| format!(" ({})", n))
+                            .unwrap_or_default()
+                    );
+                }
+                for line in &timer.log {
+                    println!("      {}", line);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Correct OnCalendar= expressions systemd rejects
+//!
+//! A drop-in of our own clears the timer's `OnCalendar=` list and sets
+//! it again, each broken expression replaced by its fix and the rest kept
+//! as they were; the manager then reloads and the timer restarts.
+//! Deleting the drop-in undoes it.
+
+use crate::calendar;
+use crate::diagnostics::timers::{self, TimerReport};
+use crate::report::RepairOutcome;
+use crate::units;
+use systemd_shim::bus::Bus;
+
+fn dropin(timer: &TimerReport) -> String {
+    let mut content = String::from(
+        "# Written by scheduled-task-ambulance; delete this file to undo\n[Timer]\nOnCalendar=\n",
+    );
+    for c in &timer.calendars {
+        if units::applies_after_dropin(&c.file) {
+            continue;
+        }
+        if let Some(expression) = if c.error.is_some() {
+            c.fix.as_deref()
+        } else {
+            Some(c.expression.as_str())
+        } {
+            content.push_str(&format!("OnCalendar={}\n", expression));
+        }
+    }
+    content
+}
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            result.errors.push(format!("system bus: {}", e));
+            return result;
+        }
+    };
+    let units = match bus.list_units() {
+        Ok(units) => units,
+        Err(e) => {
+            result.errors.push(format!("listing units: {}", e));
+            return result;
+        }
+    };
+    let diag = timers::diagnose(&bus, &units);
+    let broken: Vec<&TimerReport> = diag
+        .timers
+        .iter()
+        .filter(|t| t.calendars.iter().any(|c| c.error.is_some()))
+        .collect();
+    if broken.is_empty() {
+        return RepairOutcome::not_needed("Every OnCalendar= expression parses, no repair needed");
+    }
+
+    let mut changed = Vec::new();
+    for timer in broken {
+        let invalid: Vec<&calendar::Calendar> = timer
+            .calendars
+            .iter()
+            .filter(|c| c.error.is_some())
+            .collect();
+        if let Some(c) = invalid
+            .iter()
+            .find(|c| units::applies_after_dropin(&c.file))
+        {
+            result.errors.push(format!(
+                "{}: OnCalendar={} is in {}, applied after our drop-in; correct it there",
+                timer.name, c.expression, c.file
+            ));
+            continue;
+        }
+        let fixes: Vec<String> = invalid
+            .iter()
+            .filter_map(|c| {
+                c.fix
+                    .as_ref()
+                    .map(|fix| format!("{} -> {}", c.expression, fix))
+            })
+            .collect();
+        let unfixable = invalid.iter().filter(|c| c.fix.is_none());
+        if fixes.is_empty() {
+            for c in unfixable {
+                result.errors.push(format!(
+                    "{}: no fix known for OnCalendar={}; correct it in {}",
+                    timer.name, c.expression, c.file
+                ));
+            }
+            continue;
+        }
+        if !confirm(&format!(
+            "Rewrite OnCalendar= of {} ({})?",
+            timer.name,
+            fixes.join(", ")
+        )) {
+            result.errors.push(format!(
+                "{} not confirmed, left as is (rerun with --yes to confirm)",
+                timer.name
+            ));
+            continue;
+        }
+        let path = units::dropin_path(&timer.name);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, dropin(timer)));
+        match written {
+            Ok(()) => {
+                result.actions.push(format!(
+                    "{}: {} in {}",
+                    timer.name,
+                    fixes.join(", "),
+                    path.display()
+                ));
+                changed.push(timer.name.as_str());
+                // systemd ignored these anyway; the drop-in leaves them out
+                for c in unfixable {
+                    result.errors.push(format!(
+                        "{}: no fix known for OnCalendar={}, left out; add the schedule it meant to {}",
+                        timer.name,
+                        c.expression,
+                        path.display()
+                    ));
+                }
+            }
+            Err(e) => result.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    if changed.is_empty() {
+        return result;
+    }
+
+    match bus.reload() {
+        Ok(()) => result.actions.push("Reloaded the manager".to_string()),
+        Err(e) => {
+            result.errors.push(format!("daemon-reload: {}", e));
+            return result;
+        }
+    }
+    for name in changed {
+        match bus.restart_unit(name, "replace") {
+            Ok(_) => result.actions.push(format!("Restarted {}", name)),
+            Err(e) => result.errors.push(format!("{}: {}", name, e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
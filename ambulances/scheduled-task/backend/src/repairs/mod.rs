@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Scheduled task repairs, one module per target
+//!
+//! `all` corrects calendar expressions before restarting timers, since
+//! a timer with a broken expression only fires again once it is fixed.
+
+pub mod calendar;
+pub mod timers;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["calendar", "timers", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change, naming the timer.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        calendar_repair: if selected("calendar") {
+            calendar::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        timers_repair: if selected("timers") {
+            timers::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Restart failed and overdue timers
+//!
+//! A failed timer has its failed state cleared before the restart; an
+//! overdue one is restarted, which makes the manager work out its next
+//! elapse afresh. Timers with an expression that does not parse are left
+//! to `repair calendar`.
+
+use crate::diagnostics::timers::{self, human_secs};
+use crate::report::RepairOutcome;
+use systemd_shim::bus::{self, Bus};
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            result.errors.push(format!("system bus: {}", e));
+            return result;
+        }
+    };
+    let units = match bus.list_units() {
+        Ok(units) => units,
+        Err(e) => {
+            result.errors.push(format!("listing units: {}", e));
+            return result;
+        }
+    };
+    let diag = timers::diagnose(&bus, &units);
+    let stuck: Vec<_> = diag
+        .timers
+        .iter()
+        .filter(|t| t.active_state == "failed" || t.overdue_secs.is_some())
+        .filter(|t| t.calendars.iter().all(|c| c.error.is_none()))
+        .collect();
+    if stuck.is_empty() {
+        return RepairOutcome::not_needed("No failed or overdue timer, no repair needed");
+    }
+
+    for timer in stuck {
+        let why = match timer.overdue_secs {
+            Some(overdue) if timer.active_state != "failed" => {
+                format!("{} past its schedule", human_secs(overdue))
+            }
+            _ => "failed".to_string(),
+        };
+        if !confirm(&format!("Restart {} ({})?", timer.name, why)) {
+            result.errors.push(format!(
+                "{} not confirmed, left as is (rerun with --yes to confirm)",
+                timer.name
+            ));
+            continue;
+        }
+        if timer.active_state == "failed" {
+            if let Err(e) = bus.reset_failed_unit(&timer.name) {
+                result.errors.push(format!("{}: {}", timer.name, e));
+                continue;
+            }
+        }
+        if let Err(e) = bus.restart_unit(&timer.name, "replace") {
+            result.errors.push(format!("{}: {}", timer.name, e));
+            continue;
+        }
+        let state = bus::unit_path(&timer.name)
+            .and_then(|path| bus.get_property_string(bus::SYSTEMD, &path, bus::UNIT, "ActiveState"))
+            .unwrap_or_default();
+        if state == "active" {
+            result.actions.push(format!("Restarted {}", timer.name));
+        } else {
+            result.errors.push(format!(
+                "{} is {} after the restart; see systemctl status {}",
+                timer.name, state, timer.name
+            ));
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
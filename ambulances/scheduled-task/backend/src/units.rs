@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Timer unit files and their drop-ins
+//!
+//! `OnCalendar=` is a list setting: each line adds an expression and an
+//! empty assignment clears those before it. Drop-ins apply after the main
+//! file in file name order, a drop-in in `/etc` masking a same-named one
+//! in `/run` or `/usr/lib`.
+
+use crate::system;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Unit directories, highest priority first
+const UNIT_DIRS: &[&str] = &[
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// Name of the drop-in `repair calendar` writes
+pub const DROPIN_NAME: &str = "scheduled-task-ambulance.conf";
+
+/// Where `repair calendar` writes its drop-in for `unit`
+pub fn dropin_path(unit: &str) -> PathBuf {
+    Path::new(UNIT_DIRS[0])
+        .join(format!("{}.d", unit))
+        .join(DROPIN_NAME)
+}
+
+/// `unit`'s drop-ins in the order systemd applies them
+fn dropins(unit: &str) -> Vec<PathBuf> {
+    let mut found: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in UNIT_DIRS {
+        let Ok(entries) = std::fs::read_dir(Path::new(dir).join(format!("{}.d", unit))) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".conf") {
+                found.entry(name).or_insert_with(|| entry.path());
+            }
+        }
+    }
+    found.into_values().collect()
+}
+
+/// `OnCalendar=` expressions in effect for `unit`, each with its file
+pub fn on_calendar(unit: &str, fragment: Option<&str>) -> Vec<(String, String)> {
+    let files = fragment.map(PathBuf::from).into_iter().chain(dropins(unit));
+    let mut expressions = Vec::new();
+    for path in files {
+        let Some(text) = system::read(&path) else {
+            continue;
+        };
+        let mut in_timer = false;
+        for line in text.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_timer = line == "[Timer]";
+                continue;
+            }
+            let Some(value) = line.strip_prefix("OnCalendar=") else {
+                continue;
+            };
+            if !in_timer {
+                continue;
+            }
+            if value.trim().is_empty() {
+                expressions.clear();
+            } else {
+                expressions.push((value.trim().to_string(), path.display().to_string()));
+            }
+        }
+    }
+    expressions
+}
+
+/// Whether `file` is a drop-in applied after ours, so that clearing
+/// `OnCalendar=` in ours leaves its expressions in place
+pub fn applies_after_dropin(file: &str) -> bool {
+    let path = Path::new(file);
+    path.parent()
+        .and_then(|dir| dir.file_name())
+        .is_some_and(|dir| dir.to_string_lossy().ends_with(".d"))
+        && path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().as_ref() > DROPIN_NAME)
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Whether incoming traffic is filtered at all
+//!
+//! firewalld and ufw are recognised by their units, read over the system
+//! bus; either way the rules end up in nftables or iptables, which are
+//! inspected (as root) for an input hook that drops or rejects anything.
+//! How much a missing firewall matters depends on what listens.
+
+use crate::diagnostics::listeners::ListenerDiagnostics;
+use crate::report::{mark, Finding, Severity};
+use crate::system;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const SECTION: &str = "firewall";
+
+/// Units of the firewall front-ends, with their names
+const FRONTENDS: &[(&str, &str)] = &[
+    ("firewalld.service", "firewalld"),
+    ("ufw.service", "ufw"),
+    ("nftables.service", "nftables"),
+    ("iptables.service", "iptables"),
+    ("netfilter-persistent.service", "netfilter-persistent"),
+];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FirewallDiagnostics {
+    /// Active front-end units
+    pub frontends: Vec<String>,
+    /// Where filtering rules were found: `nftables` or `iptables`
+    pub backend: Option<String>,
+    /// `None` when the rules could not be read
+    pub filtering: Option<bool>,
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+}
+
+fn frontends() -> Vec<String> {
+    let Ok(units) = Bus::system().and_then(|bus| bus.list_units()) else {
+        return Vec::new();
+    };
+    FRONTENDS
+        .iter()
+        .filter(|(unit, _)| {
+            units
+                .iter()
+                .any(|u| u.name == *unit && u.active_state == "active")
+        })
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Whether an nftables input chain drops or rejects anything
+fn nftables() -> Option<bool> {
+    let ruleset = system::run("nft", &["list", "ruleset"]).ok()?;
+    let mut in_input = false;
+    let mut filtering = false;
+    for line in ruleset.lines().map(str::trim) {
+        if line.starts_with("chain ") {
+            in_input = false;
+        } else if line.contains("hook input") {
+            in_input = true;
+            filtering |= line.contains("policy drop");
+        } else if in_input {
+            filtering |= line
+                .split_whitespace()
+                .any(|w| w == "drop" || w == "reject");
+        }
+    }
+    Some(filtering)
+}
+
+/// Whether the iptables INPUT chain drops or rejects anything
+fn iptables() -> Option<bool> {
+    let rules = system::run("iptables", &["-S", "INPUT"]).ok()?;
+    Some(
+        rules
+            .lines()
+            .any(|l| l == "-P INPUT DROP" || l.ends_with("-j DROP") || l.contains("-j REJECT")),
+    )
+}
+
+pub fn diagnose(listeners: &ListenerDiagnostics) -> FirewallDiagnostics {
+    let mut diag = FirewallDiagnostics {
+        frontends: frontends(),
+        ..FirewallDiagnostics::default()
+    };
+    for (backend, filtering) in [("nftables", nftables()), ("iptables", iptables())] {
+        let Some(filtering) = filtering else {
+            continue;
+        };
+        diag.filtering = Some(diag.filtering.unwrap_or(false) || filtering);
+        if filtering {
+            diag.backend = Some(backend.to_string());
+            break;
+        }
+    }
+
+    let exposed = listeners
+        .listeners
+        .iter()
+        .filter(|l| l.all_interfaces)
+        .count();
+    match diag.filtering {
+        Some(true) => {}
+        Some(false) => {
+            let severity = if exposed > 0 {
+                Severity::High
+            } else {
+                Severity::Low
+            };
+            diag.findings.push(Finding::new(
+                severity,
+                SECTION,
+                format!(
+                    "No incoming traffic is filtered; {} services listen on all interfaces",
+                    exposed
+                ),
+                "Enable a firewall that denies incoming by default (`ufw enable` or `systemctl enable --now firewalld`)",
+            ));
+        }
+        None if !ambulance_privilege::is_elevated() && diag.frontends.is_empty() => diag
+            .findings
+            .push(Finding::new(
+            Severity::Info,
+            SECTION,
+            "Firewall rules not audited: readable by root only, and no firewall service is running",
+            "Run the audit as root",
+        )),
+        None if diag.frontends.is_empty() => diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            "No firewall tooling installed (nft or iptables) and no firewall service running",
+            "Install and enable a firewall such as ufw or firewalld",
+        )),
+        None => {}
+    }
+
+    diag
+}
+
+impl FirewallDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Firewall ===");
+        match self.filtering {
+            Some(filtering) => println!(
+                "{} Incoming filtered: {}",
+                mark(filtering),
+                if filtering { "yes" } else { "no" }
+            ),
+            None if !ambulance_privilege::is_elevated() => {
+                println!("- Incoming filtered: unknown (run as root)")
+            }
+            None => println!("- Incoming filtered: unknown (no nft or iptables)"),
+        }
+        if !self.frontends.is_empty() || verbose {
+            println!(
+                "- Firewall services: {}",
+                if self.frontends.is_empty() {
+                    "none".to_string()
+                } else {
+                    self.frontends.join(", ")
+                }
+            );
+        }
+        if verbose {
+            if let Some(backend) = &self.backend {
+                println!("- Rules in: {}", backend);
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Services listening on every interface
+//!
+//! Read from `/proc/net/{tcp,tcp6,udp,udp6}`; a wildcard bind (`0.0.0.0`
+//! or `::`) answers on every network the machine joins. Owning processes
+//! are found by matching socket inodes in `/proc/*/fd`, which needs root
+//! for other users' processes.
+
+use crate::report::{Finding, Severity};
+use ambulance_core::host;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const SECTION: &str = "listeners";
+
+/// TCP `LISTEN`, and UDP `CLOSE` (how an unconnected bound socket shows)
+const TCP_LISTEN: &str = "0A";
+const UDP_BOUND: &str = "07";
+
+/// Services that should never face the network, by protocol and port
+const RISKY: &[(&str, u16, &str, Severity)] = &[
+    ("tcp", 23, "telnet", Severity::Critical),
+    ("tcp", 2375, "Docker API without TLS", Severity::Critical),
+    ("tcp", 21, "FTP", Severity::High),
+    ("tcp", 3306, "MySQL", Severity::High),
+    ("tcp", 5432, "PostgreSQL", Severity::High),
+    ("tcp", 6379, "Redis", Severity::High),
+    ("tcp", 9200, "Elasticsearch", Severity::High),
+    ("tcp", 11211, "memcached", Severity::High),
+    ("tcp", 27017, "MongoDB", Severity::High),
+    ("tcp", 5900, "VNC", Severity::High),
+    ("udp", 11211, "memcached", Severity::High),
+    ("tcp", 111, "rpcbind", Severity::Medium),
+    ("udp", 111, "rpcbind", Severity::Medium),
+    ("tcp", 139, "NetBIOS", Severity::Medium),
+    ("tcp", 445, "SMB", Severity::Medium),
+    ("udp", 161, "SNMP", Severity::Medium),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Listener {
+    pub protocol: &'static str,
+    pub address: String,
+    pub port: u16,
+    /// Bound to `0.0.0.0` or `::`
+    pub all_interfaces: bool,
+    pub process: Option<String>,
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListenerDiagnostics {
+    pub listeners: Vec<Listener>,
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+}
+
+/// Address in `/proc/net` form: 32-bit words printed in host byte order
+fn address(hex: &str) -> Option<String> {
+    let words: Vec<u32> = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<_>>()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Socket inode to `(pid, comm)`
+fn socket_owners() -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    let Ok(procs) = std::fs::read_dir(host::path("/proc")) else {
+        return owners;
+    };
+    for entry in procs.flatten() {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let comm = std::fs::read_to_string(entry.path().join("comm"))
+            .map(|c| c.trim().to_string())
+            .unwrap_or_default();
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let target = target.to_string_lossy();
+            if let Some(inode) = target
+                .strip_prefix("socket:[")
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse().ok())
+            {
+                owners.entry(inode).or_insert((pid, comm.clone()));
+            }
+        }
+    }
+    owners
+}
+
+pub fn listeners() -> Vec<Listener> {
+    let owners = socket_owners();
+    let mut listeners = Vec::new();
+    for (file, protocol, state) in [
+        ("tcp", "tcp", TCP_LISTEN),
+        ("tcp6", "tcp", TCP_LISTEN),
+        ("udp", "udp", UDP_BOUND),
+        ("udp6", "udp", UDP_BOUND),
+    ] {
+        let Ok(table) = std::fs::read_to_string(host::path(format!("/proc/net/{}", file))) else {
+            continue;
+        };
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // sl local rem st tx:rx tr:when retrnsmt uid timeout inode
+            let (Some(local), Some(st), Some(inode)) =
+                (fields.get(1), fields.get(3), fields.get(9))
+            else {
+                continue;
+            };
+            if *st != state {
+                continue;
+            }
+            let Some((host, port)) = local.split_once(':') else {
+                continue;
+            };
+            let (Some(address), Ok(port)) = (address(host), u16::from_str_radix(port, 16)) else {
+                continue;
+            };
+            let owner = inode.parse().ok().and_then(|i: u64| owners.get(&i));
+            listeners.push(Listener {
+                protocol,
+                all_interfaces: host.chars().all(|c| c == '0'),
+                address,
+                port,
+                process: owner.map(|(_, comm)| comm.clone()),
+                pid: owner.map(|(pid, _)| *pid),
+            });
+        }
+    }
+    // A wildcard service usually binds both 0.0.0.0 and ::
+    listeners.sort_by_key(|l| (l.protocol, l.port, !l.all_interfaces));
+    listeners.dedup_by(|a, b| {
+        a.all_interfaces && b.all_interfaces && a.protocol == b.protocol && a.port == b.port
+    });
+    listeners
+}
+
+fn describe(listener: &Listener) -> String {
+    match &listener.process {
+        Some(process) => format!("{}/{} ({})", listener.port, listener.protocol, process),
+        None => format!("{}/{}", listener.port, listener.protocol),
+    }
+}
+
+pub fn diagnose() -> ListenerDiagnostics {
+    let mut diag = ListenerDiagnostics {
+        listeners: listeners(),
+        ..ListenerDiagnostics::default()
+    };
+
+    let mut others = Vec::new();
+    for listener in diag.listeners.iter().filter(|l| l.all_interfaces) {
+        let risky = RISKY.iter().find(|(protocol, port, _, _)| {
+            *protocol == listener.protocol && *port == listener.port
+        });
+        match risky {
+            Some((_, _, service, severity)) => diag.findings.push(Finding::new(
+                *severity,
+                SECTION,
+                format!(
+                    "{} listens on all interfaces: {}",
+                    service,
+                    describe(listener)
+                ),
+                format!(
+                    "Bind it to 127.0.0.1 or a private address, or firewall port {}",
+                    listener.port
+                ),
+            )),
+            None if listener.protocol == "tcp" => others.push(describe(listener)),
+            None => {}
+        }
+    }
+    if !others.is_empty() {
+        diag.findings.push(Finding::new(
+            Severity::Info,
+            SECTION,
+            format!("Also listening on all interfaces: {}", others.join(", ")),
+            "Check each needs to be reachable from the network",
+        ));
+    }
+
+    diag
+}
+
+impl ListenerDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Listening Services ===");
+        let exposed: Vec<_> = self.listeners.iter().filter(|l| l.all_interfaces).collect();
+        println!(
+            "- {} listening, {} on all interfaces",
+            self.listeners.len(),
+            exposed.len()
+        );
+        let shown = if verbose {
+            self.listeners.iter().collect()
+        } else {
+            exposed
+        };
+        for listener in shown {
+            println!("  {} {}", listener.address, describe(listener));
+        }
+        println!();
+    }
+}
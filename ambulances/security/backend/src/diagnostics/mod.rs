@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Security diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs within the scan's budget; the
+//! firewall's audit asks which listeners it must cover, so it waits on
+//! them, and the rest run alongside. A section not done in time is an
+//! informational finding that it was not audited, as is the firewall
+//! when the listeners are not; one the budget cut short is left empty and
+//! its timing says why.
+
+pub mod firewall;
+pub mod listeners;
+pub mod path;
+pub mod ssh;
+pub mod sudoers;
+pub mod updates;
+
+use crate::report::{DiagnosticResult, Finding, Severity, TOOL, VERSION};
+use ambulance_core::runner::{Inputs, Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// How long a section may take; the update timers are asked of a system
+/// manager that can be slow to answer, and nft of a large ruleset
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Listeners(listeners::ListenerDiagnostics),
+    Firewall(firewall::FirewallDiagnostics),
+    Path(path::PathDiagnostics),
+    Sudoers(sudoers::SudoersDiagnostics),
+    Ssh(ssh::SshDiagnostics),
+    Updates(updates::UpdateDiagnostics),
+}
+
+/// Run every section and rank their findings, most severe first
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("listeners", |_| {
+            Ok(Section::Listeners(listeners::diagnose()))
+        }))
+        .job(
+            Job::blocking("firewall", |inputs: Inputs<Section>| {
+                let Some(Section::Listeners(listeners)) = inputs.get("listeners") else {
+                    return Err("no listeners section".to_string());
+                };
+                Ok(Section::Firewall(firewall::diagnose(listeners)))
+            })
+            .after(&["listeners"]),
+        )
+        .job(Job::blocking("path", |_| {
+            Ok(Section::Path(path::diagnose()))
+        }))
+        .job(Job::blocking("sudoers", |_| {
+            Ok(Section::Sudoers(sudoers::diagnose()))
+        }))
+        .job(Job::blocking("ssh", |_| Ok(Section::Ssh(ssh::diagnose()))))
+        .job(Job::blocking("updates", |_| {
+            Ok(Section::Updates(updates::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        findings: Vec::new(),
+        listeners: Default::default(),
+        path: Default::default(),
+        sudoers: Default::default(),
+        ssh: Default::default(),
+        firewall: Default::default(),
+        updates: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Listeners(d)) => result.listeners = d,
+            Some(Section::Firewall(d)) => result.firewall = d,
+            Some(Section::Path(d)) => result.path = d,
+            Some(Section::Sudoers(d)) => result.sudoers = d,
+            Some(Section::Ssh(d)) => result.ssh = d,
+            Some(Section::Updates(d)) => result.updates = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let findings = match outcome.id {
+                    "listeners" => &mut result.listeners.findings,
+                    "firewall" => &mut result.firewall.findings,
+                    "path" => &mut result.path.findings,
+                    "sudoers" => &mut result.sudoers.findings,
+                    "ssh" => &mut result.ssh.findings,
+                    _ => &mut result.updates.findings,
+                };
+                findings.push(Finding::new(
+                    Severity::Info,
+                    outcome.id,
+                    format!("{} not audited: the check {}", outcome.id, outcome.status),
+                    "Run the audit again",
+                ));
+            }
+        }
+    }
+
+    for section in [
+        &mut result.listeners.findings,
+        &mut result.path.findings,
+        &mut result.sudoers.findings,
+        &mut result.ssh.findings,
+        &mut result.firewall.findings,
+        &mut result.updates.findings,
+    ] {
+        result.findings.append(section);
+    }
+    // Stable, so equal severities keep section order
+    result.findings.sort_by_key(|f| Reverse(f.severity));
+    result
+}
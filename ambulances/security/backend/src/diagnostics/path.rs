@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Writable directories in PATH
+//!
+//! Anyone who can write to a directory in someone's PATH can plant a
+//! program under a common name and have it run as that someone. Checks
+//! this process's PATH, the system default in `/etc/environment` and
+//! sudo's `secure_path`, and each directory's parents up to `/`.
+
+use crate::diagnostics::sudoers;
+use crate::report::{mark, Finding, Severity};
+use crate::system;
+use serde::Serialize;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+const SECTION: &str = "path";
+
+const S_IWGRP: u32 = 0o020;
+const S_IWOTH: u32 = 0o002;
+const S_ISVTX: u32 = 0o1000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathEntry {
+    pub directory: String,
+    /// Where the entry came from: `env`, `/etc/environment` or `secure_path`
+    pub source: &'static str,
+    /// What makes it writable by others, if anything
+    pub problem: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PathDiagnostics {
+    pub entries: Vec<PathEntry>,
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+}
+
+/// PATH from `/etc/environment`, quoted or not
+fn etc_environment() -> Option<String> {
+    system::read("/etc/environment")?.lines().find_map(|l| {
+        let value = l.trim().strip_prefix("PATH=")?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// Why others than root and `owner` can write to `dir` or a parent of it;
+/// `None` if nobody else can
+fn writable_by_others(dir: &str, owner: (u32, u32)) -> Option<(String, Severity)> {
+    if dir.is_empty() || !dir.starts_with('/') {
+        return Some((
+            "relative entry, resolves to the working directory".to_string(),
+            Severity::High,
+        ));
+    }
+    for path in Path::new(dir).ancestors() {
+        let Ok(meta) = std::fs::metadata(path) else {
+            continue;
+        };
+        let shown = path.display();
+        let mode = meta.mode();
+        // Sticky world-writable directories (/tmp) still stop renames and
+        // deletions, but anyone may create new names in them
+        if mode & S_IWOTH != 0 {
+            let sticky = if mode & S_ISVTX != 0 { " (sticky)" } else { "" };
+            return Some((
+                format!("{} is world-writable{}", shown, sticky),
+                Severity::Critical,
+            ));
+        }
+        if meta.uid() != 0 && meta.uid() != owner.0 {
+            return Some((
+                format!("{} is owned by uid {}", shown, meta.uid()),
+                Severity::Medium,
+            ));
+        }
+        if mode & S_IWGRP != 0 && meta.gid() != 0 && meta.gid() != owner.1 {
+            return Some((
+                format!("{} is writable by gid {}", shown, meta.gid()),
+                Severity::Medium,
+            ));
+        }
+    }
+    None
+}
+
+pub fn diagnose() -> PathDiagnostics {
+    let mut sources: Vec<(&'static str, String)> = Vec::new();
+    if let Ok(path) = std::env::var("PATH") {
+        sources.push(("env", path));
+    }
+    if let Some(path) = etc_environment() {
+        sources.push(("/etc/environment", path));
+    }
+    if let Some(path) = sudoers::secure_path() {
+        sources.push(("secure_path", path));
+    }
+
+    let mut diag = PathDiagnostics::default();
+    for (source, path) in sources {
+        for dir in path.split(':') {
+            if diag.entries.iter().any(|e| e.directory == dir) {
+                continue;
+            }
+            // The caller's own directories are only fine in the caller's PATH
+            let owner = if source == "env" {
+                system::ids()
+            } else {
+                (0, 0)
+            };
+            let problem = writable_by_others(dir, owner);
+            if let Some((why, severity)) = &problem {
+                diag.findings.push(Finding::new(
+                    *severity,
+                    SECTION,
+                    format!("PATH entry '{}' ({}): {}", dir, source, why),
+                    "Remove it from PATH or make it writable only by root",
+                ));
+            }
+            diag.entries.push(PathEntry {
+                directory: dir.to_string(),
+                source,
+                problem: problem.map(|(why, _)| why),
+            });
+        }
+    }
+
+    diag
+}
+
+impl PathDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== PATH ===");
+        let bad = self.entries.iter().filter(|e| e.problem.is_some()).count();
+        println!(
+            "{} {} directories, {} writable by others",
+            mark(bad == 0),
+            self.entries.len(),
+            bad
+        );
+        if verbose {
+            for entry in &self.entries {
+                println!(
+                    "  {} {} ({})",
+                    mark(entry.problem.is_none()),
+                    entry.directory,
+                    entry.source
+                );
+            }
+        }
+        println!();
+    }
+}
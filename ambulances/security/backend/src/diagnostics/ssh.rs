@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Weak SSH server configuration
+//!
+//! The effective settings come from `sshd -T` when run as root; otherwise
+//! `sshd_config` and the drop-ins it includes are read the way sshd reads
+//! them, first value winning and `Match` blocks ignored. Unset options
+//! take current OpenSSH defaults.
+
+use crate::report::{mark, Finding, Severity};
+use crate::system;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+const SECTION: &str = "ssh";
+
+const CONFIG: &str = "/etc/ssh/sshd_config";
+
+/// Defaults of the options checked, for when they are not set
+const DEFAULTS: &[(&str, &str)] = &[
+    ("permitrootlogin", "prohibit-password"),
+    ("passwordauthentication", "yes"),
+    ("permitemptypasswords", "no"),
+    ("x11forwarding", "no"),
+    ("maxauthtries", "6"),
+];
+
+/// Algorithms broken or deprecated upstream
+const WEAK_ALGORITHMS: &[&str] = &[
+    "3des-cbc",
+    "aes128-cbc",
+    "aes192-cbc",
+    "aes256-cbc",
+    "arcfour",
+    "arcfour128",
+    "arcfour256",
+    "blowfish-cbc",
+    "cast128-cbc",
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-sha1-96",
+    "diffie-hellman-group1-sha1",
+    "diffie-hellman-group-exchange-sha1",
+];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SshDiagnostics {
+    pub installed: bool,
+    /// `sshd -T` or `sshd_config`
+    pub source: Option<String>,
+    /// Effective values of the checked options, lower-case keys
+    pub settings: HashMap<String, String>,
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+}
+
+/// Options from `sshd -T`, which prints every effective setting
+fn effective() -> Option<HashMap<String, String>> {
+    let output = system::run("sshd", &["-T"]).ok()?;
+    Some(
+        output
+            .lines()
+            .filter_map(|l| l.split_once(' '))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    )
+}
+
+/// Options as written in `file` and the files it includes, in place
+///
+/// A `Match` block runs to the end of the file it is in, so reading that
+/// file stops there.
+fn read_config(file: &Path, settings: &mut HashMap<String, String>) {
+    let Some(text) = system::read(file) else {
+        return;
+    };
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let key = key.to_ascii_lowercase();
+        let value = value.trim_start_matches([' ', '\t', '=']).trim();
+        match key.as_str() {
+            "match" => return,
+            "include" => {
+                // Only the usual `dir/*.conf` form is expanded
+                for pattern in value.split_whitespace() {
+                    let pattern = if pattern.starts_with('/') {
+                        pattern.to_string()
+                    } else {
+                        format!("/etc/ssh/{}", pattern)
+                    };
+                    let files = match pattern.strip_suffix("/*.conf") {
+                        Some(dir) => system::dropins(dir, Some("conf")),
+                        None => vec![PathBuf::from(pattern)],
+                    };
+                    for included in files {
+                        read_config(&included, settings);
+                    }
+                }
+            }
+            _ => {
+                settings.entry(key).or_insert_with(|| value.to_string());
+            }
+        }
+    }
+}
+
+fn check_host_keys(findings: &mut Vec<Finding>) {
+    for key in system::dropins("/etc/ssh", None) {
+        let name = key.file_name().unwrap_or_default().to_string_lossy();
+        if !name.starts_with("ssh_host_") || !name.ends_with("_key") {
+            continue;
+        }
+        let Ok(meta) = std::fs::metadata(&key) else {
+            continue;
+        };
+        if meta.mode() & 0o077 != 0 {
+            findings.push(Finding::new(
+                Severity::High,
+                SECTION,
+                format!(
+                    "Host key {} is readable by others (mode {:o})",
+                    key.display(),
+                    meta.mode() & 0o777
+                ),
+                format!("chmod 0600 {}", key.display()),
+            ));
+        }
+    }
+}
+
+pub fn diagnose() -> SshDiagnostics {
+    let mut diag = SshDiagnostics {
+        installed: Path::new(CONFIG).exists() || system::has("sshd"),
+        ..SshDiagnostics::default()
+    };
+    if !diag.installed {
+        return diag;
+    }
+    let (source, mut settings) = match effective() {
+        Some(settings) => ("sshd -T", settings),
+        None => {
+            let mut settings = HashMap::new();
+            read_config(Path::new(CONFIG), &mut settings);
+            ("sshd_config", settings)
+        }
+    };
+    for (key, value) in DEFAULTS {
+        settings
+            .entry(key.to_string())
+            .or_insert_with(|| value.to_string());
+    }
+    let get = |key: &str| settings.get(key).map(String::as_str).unwrap_or_default();
+
+    if get("permitemptypasswords") == "yes" {
+        diag.findings.push(Finding::new(
+            Severity::Critical,
+            SECTION,
+            "PermitEmptyPasswords yes: accounts without a password can log in",
+            "Set PermitEmptyPasswords no",
+        ));
+    }
+    if get("permitrootlogin") == "yes" {
+        diag.findings.push(Finding::new(
+            Severity::High,
+            SECTION,
+            "PermitRootLogin yes: root can log in with a password",
+            "Set PermitRootLogin prohibit-password (or no)",
+        ));
+    }
+    if get("passwordauthentication") == "yes" {
+        diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            "PasswordAuthentication yes: passwords can be brute-forced",
+            "Set PasswordAuthentication no once every user has a key",
+        ));
+    }
+    if get("x11forwarding") == "yes" {
+        diag.findings.push(Finding::new(
+            Severity::Low,
+            SECTION,
+            "X11Forwarding yes",
+            "Set X11Forwarding no unless remote X clients are needed",
+        ));
+    }
+    if get("maxauthtries").parse::<u32>().is_ok_and(|n| n > 6) {
+        diag.findings.push(Finding::new(
+            Severity::Low,
+            SECTION,
+            format!(
+                "MaxAuthTries {} allows many guesses per connection",
+                get("maxauthtries")
+            ),
+            "Set MaxAuthTries 6 or lower",
+        ));
+    }
+    for option in ["ciphers", "macs", "kexalgorithms"] {
+        let weak: Vec<&str> = get(option)
+            .split(',')
+            // `-name` removes an algorithm from the defaults
+            .filter(|a| !a.starts_with('-'))
+            .map(|a| a.trim_start_matches(['+', '^']))
+            .filter(|a| WEAK_ALGORITHMS.contains(a))
+            .collect();
+        if !weak.is_empty() {
+            diag.findings.push(Finding::new(
+                Severity::Medium,
+                SECTION,
+                format!("Weak {} enabled: {}", option, weak.join(", ")),
+                format!("Remove them from the {} option", option),
+            ));
+        }
+    }
+    check_host_keys(&mut diag.findings);
+
+    diag.source = Some(source.to_string());
+    diag.settings = DEFAULTS
+        .iter()
+        .map(|(key, _)| (key.to_string(), get(key).to_string()))
+        .collect();
+    diag
+}
+
+impl SshDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== SSH Server ===");
+        if !self.installed {
+            println!("- sshd not installed");
+            println!();
+            return;
+        }
+        let setting = |key: &str| self.settings.get(key).map(String::as_str).unwrap_or("?");
+        println!(
+            "{} Root login: {}",
+            mark(setting("permitrootlogin") != "yes"),
+            setting("permitrootlogin")
+        );
+        println!(
+            "{} Password authentication: {}",
+            mark(setting("passwordauthentication") != "yes"),
+            setting("passwordauthentication")
+        );
+        if verbose {
+            if let Some(source) = &self.source {
+                println!("- Read from: {}", source);
+            }
+        }
+        println!();
+    }
+}
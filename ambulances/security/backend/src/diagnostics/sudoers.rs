@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Weak sudo configuration
+//!
+//! Reads `/etc/sudoers` and the drop-ins sudo includes from
+//! `/etc/sudoers.d` (skipping names with a `.` or ending in `~`, as sudo
+//! does). Both are readable by root only.
+
+use crate::report::{mark, Finding, Severity};
+use crate::system;
+use serde::Serialize;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+const SECTION: &str = "sudoers";
+
+const SUDOERS: &str = "/etc/sudoers";
+const SUDOERS_D: &str = "/etc/sudoers.d";
+
+/// Variables that let the caller inject code into whatever sudo runs
+const DANGEROUS_ENV: &[&str] = &[
+    "LD_PRELOAD",
+    "LD_LIBRARY_PATH",
+    "PYTHONPATH",
+    "PERL5LIB",
+    "RUBYLIB",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SudoRule {
+    pub file: String,
+    pub rule: String,
+    /// Runs without asking for a password
+    pub nopasswd: bool,
+    /// Allows any command
+    pub any_command: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SudoersDiagnostics {
+    pub installed: bool,
+    /// False when not run as root
+    pub readable: bool,
+    pub files: Vec<String>,
+    pub rules: Vec<SudoRule>,
+    pub secure_path: Option<String>,
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+}
+
+fn files() -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(SUDOERS)];
+    files.extend(system::dropins(SUDOERS_D, None).into_iter().filter(|p| {
+        let name = p.file_name().unwrap_or_default().to_string_lossy();
+        !name.contains('.') && !name.ends_with('~')
+    }));
+    files
+}
+
+/// Logical lines of a sudoers file: comments dropped, continuations joined
+fn lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for raw in text.lines() {
+        let line = raw.trim();
+        // `#include` and `#includedir` are directives, not comments
+        if line.starts_with('#') && !line.starts_with("#include") {
+            continue;
+        }
+        match line.strip_suffix('\\') {
+            Some(head) => {
+                current.push_str(head);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                if !current.trim().is_empty() {
+                    lines.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+        }
+    }
+    lines
+}
+
+fn is_rule(line: &str) -> bool {
+    let first = line.split_whitespace().next().unwrap_or_default();
+    line.contains('=')
+        && !first.starts_with("Defaults")
+        && !first.ends_with("_Alias")
+        && !first.starts_with('#')
+        && !first.starts_with('@')
+}
+
+/// `Defaults` settings of one logical line, e.g. `!env_reset` or
+/// `secure_path="/usr/bin"`
+fn defaults(line: &str) -> Vec<String> {
+    let Some(rest) = line.strip_prefix("Defaults") else {
+        return Vec::new();
+    };
+    // Skip the scope suffix of `Defaults:user`, `Defaults>root`...
+    let rest = rest.split_once(char::is_whitespace).map_or("", |(_, r)| r);
+    rest.split(',').map(|s| s.trim().to_string()).collect()
+}
+
+/// `secure_path` from the sudoers files, the last one winning
+pub fn secure_path() -> Option<String> {
+    files()
+        .iter()
+        .filter_map(system::read)
+        .flat_map(|text| lines(&text))
+        .flat_map(|line| defaults(&line))
+        .filter_map(|setting| {
+            let value = setting.strip_prefix("secure_path")?.trim_start();
+            Some(
+                value
+                    .strip_prefix('=')?
+                    .trim()
+                    .trim_matches('"')
+                    .to_string(),
+            )
+        })
+        .next_back()
+}
+
+fn check_permissions(path: &Path, findings: &mut Vec<Finding>) {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return;
+    };
+    let shown = path.display();
+    if meta.mode() & 0o002 != 0 {
+        findings.push(Finding::new(
+            Severity::Critical,
+            SECTION,
+            format!("{} is world-writable", shown),
+            format!("chown root:root {} && chmod 0440 {}", shown, shown),
+        ));
+    } else if meta.uid() != 0 || meta.mode() & 0o020 != 0 {
+        findings.push(Finding::new(
+            Severity::High,
+            SECTION,
+            format!("{} is writable by someone other than root", shown),
+            format!("chown root:root {} && chmod 0440 {}", shown, shown),
+        ));
+    }
+}
+
+pub fn diagnose() -> SudoersDiagnostics {
+    let mut diag = SudoersDiagnostics {
+        installed: Path::new(SUDOERS).exists(),
+        ..SudoersDiagnostics::default()
+    };
+    if !diag.installed {
+        return diag;
+    }
+    diag.readable = system::read(SUDOERS).is_some();
+    if !diag.readable {
+        diag.findings.push(Finding::new(
+            Severity::Info,
+            SECTION,
+            "sudoers not audited: readable by root only",
+            "Run the audit as root",
+        ));
+        return diag;
+    }
+
+    for path in files() {
+        check_permissions(&path, &mut diag.findings);
+        let Some(text) = system::read(&path) else {
+            continue;
+        };
+        let file = path.display().to_string();
+        for line in lines(&text) {
+            for setting in defaults(&line) {
+                let setting = setting.replace(' ', "");
+                if setting == "!authenticate" {
+                    diag.findings.push(Finding::new(
+                        Severity::High,
+                        SECTION,
+                        format!("{}: `Defaults !authenticate` disables passwords", file),
+                        "Remove it; grant NOPASSWD per command where needed",
+                    ));
+                } else if setting == "!env_reset" {
+                    diag.findings.push(Finding::new(
+                        Severity::High,
+                        SECTION,
+                        format!(
+                            "{}: `Defaults !env_reset` passes the caller's environment",
+                            file
+                        ),
+                        "Remove it so sudo starts commands with a clean environment",
+                    ));
+                } else if setting.starts_with("env_keep") {
+                    for var in DANGEROUS_ENV.iter().filter(|v| setting.contains(*v)) {
+                        diag.findings.push(Finding::new(
+                            Severity::High,
+                            SECTION,
+                            format!("{}: env_keep keeps {}, which injects code", file, var),
+                            format!("Remove {} from env_keep", var),
+                        ));
+                    }
+                }
+            }
+            if !is_rule(&line) {
+                continue;
+            }
+            let (who, commands) = line.split_once('=').unwrap_or_default();
+            let nopasswd = commands.contains("NOPASSWD:");
+            let any_command = commands
+                .split(',')
+                .filter_map(|c| c.split_whitespace().last())
+                .any(|c| c.rsplit(':').next() == Some("ALL"));
+            let everyone = who.split_whitespace().next() == Some("ALL");
+            if everyone && any_command {
+                diag.findings.push(Finding::new(
+                    Severity::Critical,
+                    SECTION,
+                    format!("{}: every user may run anything: {}", file, line),
+                    "Restrict the rule to an admin group such as %sudo or %wheel",
+                ));
+            } else if nopasswd && any_command {
+                diag.findings.push(Finding::new(
+                    Severity::Medium,
+                    SECTION,
+                    format!("{}: passwordless root for anything: {}", file, line),
+                    "Drop NOPASSWD, or limit it to the commands that need it",
+                ));
+            }
+            diag.rules.push(SudoRule {
+                file: file.clone(),
+                rule: line.clone(),
+                nopasswd,
+                any_command,
+            });
+        }
+        diag.files.push(file);
+    }
+
+    diag.secure_path = secure_path();
+    if diag.secure_path.is_none() {
+        diag.findings.push(Finding::new(
+            Severity::Low,
+            SECTION,
+            "No secure_path: sudo searches the caller's PATH",
+            "Add `Defaults secure_path=\"/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin\"`",
+        ));
+    }
+
+    diag
+}
+
+impl SudoersDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== sudo ===");
+        if !self.installed {
+            println!("- sudo not installed");
+            println!();
+            return;
+        }
+        if !self.readable {
+            println!("- Not audited (run as root)");
+            println!();
+            return;
+        }
+        let risky = self
+            .rules
+            .iter()
+            .filter(|r| r.nopasswd && r.any_command)
+            .count();
+        println!(
+            "{} {} rules, {} passwordless for any command",
+            mark(risky == 0),
+            self.rules.len(),
+            risky
+        );
+        if verbose {
+            for rule in &self.rules {
+                println!("  {}: {}", rule.file, rule.rule);
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Automatic security updates
+//!
+//! Debian and Ubuntu install security fixes with unattended-upgrades, run
+//! by `apt-daily-upgrade.timer` once `APT::Periodic::Unattended-Upgrade`
+//! is on; Fedora and RHEL use dnf-automatic timers. Timer state is read
+//! from the manager over the system bus.
+
+use crate::report::{mark, Finding, Severity};
+use crate::system;
+use serde::Serialize;
+use std::path::Path;
+use std::time::SystemTime;
+use systemd_shim::bus::Bus;
+
+const SECTION: &str = "updates";
+
+/// Days without an unattended run before updates count as stalled
+const STALE_DAYS: u64 = 14;
+
+const APT_CONF_D: &str = "/etc/apt/apt.conf.d";
+const APT_TIMER: &str = "apt-daily-upgrade.timer";
+/// Written on every unattended-upgrades run
+const APT_STAMPS: &[&str] = &[
+    "/var/lib/apt/periodic/unattended-upgrades-stamp",
+    "/var/log/unattended-upgrades/unattended-upgrades.log",
+];
+
+/// dnf-automatic timers; only the `-install` one applies updates regardless
+/// of `apply_updates`
+const DNF_TIMERS: &[&str] = &[
+    "dnf-automatic-install.timer",
+    "dnf-automatic.timer",
+    "dnf5-automatic.timer",
+];
+const DNF_AUTOMATIC_CONF: &str = "/etc/dnf/automatic.conf";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateDiagnostics {
+    /// `apt`, `dnf`, or `None` for package managers without a standard
+    /// unattended mechanism
+    pub manager: Option<String>,
+    pub installed: bool,
+    pub enabled: bool,
+    /// Timer active, `None` when the manager could not be asked
+    pub timer_active: Option<bool>,
+    /// Updates are installed, not only downloaded
+    pub applies: bool,
+    pub last_run_days: Option<u64>,
+    #[serde(skip)]
+    pub findings: Vec<Finding>,
+}
+
+fn active_timers(names: &[&str]) -> Option<Vec<String>> {
+    let units = Bus::system().and_then(|bus| bus.list_units()).ok()?;
+    Some(
+        units
+            .into_iter()
+            .filter(|u| names.contains(&u.name.as_str()) && u.active_state == "active")
+            .map(|u| u.name)
+            .collect(),
+    )
+}
+
+fn days_since(path: &str) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    Some(age.as_secs() / 86_400)
+}
+
+/// Last value of `APT::Periodic::Unattended-Upgrade` across apt.conf.d
+fn apt_periodic_enabled() -> bool {
+    system::dropins(APT_CONF_D, None)
+        .iter()
+        .filter_map(system::read)
+        .flat_map(|text| {
+            text.lines()
+                .filter_map(|l| {
+                    let value = l.trim().strip_prefix("APT::Periodic::Unattended-Upgrade")?;
+                    Some(value.trim().trim_end_matches(';').trim().trim_matches('"') != "0")
+                })
+                .collect::<Vec<_>>()
+        })
+        .next_back()
+        .unwrap_or(false)
+}
+
+fn apt(diag: &mut UpdateDiagnostics) {
+    diag.installed = Path::new("/usr/bin/unattended-upgrade").exists();
+    if !diag.installed {
+        diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            "unattended-upgrades is not installed; security fixes wait for a manual upgrade",
+            "apt install unattended-upgrades && dpkg-reconfigure -plow unattended-upgrades",
+        ));
+        return;
+    }
+    diag.enabled = apt_periodic_enabled();
+    diag.applies = true;
+    diag.timer_active = active_timers(&[APT_TIMER]).map(|t| !t.is_empty());
+    diag.last_run_days = APT_STAMPS.iter().filter_map(|s| days_since(s)).min();
+
+    if !diag.enabled {
+        diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            "unattended-upgrades is installed but APT::Periodic::Unattended-Upgrade is off",
+            "dpkg-reconfigure -plow unattended-upgrades",
+        ));
+    } else if diag.timer_active == Some(false) {
+        diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            format!(
+                "{} is not active, so unattended-upgrades never runs",
+                APT_TIMER
+            ),
+            format!("systemctl enable --now {}", APT_TIMER),
+        ));
+    } else if let Some(days) = diag.last_run_days.filter(|d| *d > STALE_DAYS) {
+        diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            format!("unattended-upgrades last ran {} days ago", days),
+            "Check /var/log/unattended-upgrades/ for why it stopped",
+        ));
+    }
+}
+
+fn dnf(diag: &mut UpdateDiagnostics) {
+    diag.installed = Path::new(DNF_AUTOMATIC_CONF).exists()
+        || system::has("dnf-automatic")
+        || Path::new("/usr/lib/systemd/system/dnf5-automatic.timer").exists();
+    let Some(active) = active_timers(DNF_TIMERS) else {
+        return;
+    };
+    diag.timer_active = Some(!active.is_empty());
+    diag.enabled = !active.is_empty();
+    let apply_updates = system::read(DNF_AUTOMATIC_CONF).is_some_and(|conf| {
+        conf.lines().any(|l| {
+            let l = l.replace(' ', "");
+            l == "apply_updates=yes" || l == "apply_updates=True" || l == "apply_updates=true"
+        })
+    });
+    diag.applies = active.iter().any(|t| t == DNF_TIMERS[0]) || apply_updates;
+
+    if !diag.enabled {
+        diag.findings.push(Finding::new(
+            Severity::Medium,
+            SECTION,
+            "No dnf-automatic timer is active; security fixes wait for a manual upgrade",
+            "dnf install dnf-automatic && systemctl enable --now dnf-automatic-install.timer",
+        ));
+    } else if !diag.applies {
+        diag.findings.push(Finding::new(
+            Severity::Low,
+            SECTION,
+            "dnf-automatic only downloads updates",
+            "Set apply_updates = yes in /etc/dnf/automatic.conf",
+        ));
+    }
+}
+
+pub fn diagnose() -> UpdateDiagnostics {
+    let mut diag = UpdateDiagnostics::default();
+    if system::has("apt-get") {
+        diag.manager = Some("apt".to_string());
+        apt(&mut diag);
+    } else if system::has("dnf") {
+        diag.manager = Some("dnf".to_string());
+        dnf(&mut diag);
+    } else {
+        diag.findings.push(Finding::new(
+            Severity::Info,
+            SECTION,
+            "No standard unattended update mechanism for this package manager",
+            "Install updates regularly by hand",
+        ));
+    }
+    diag
+}
+
+impl UpdateDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Automatic Updates ===");
+        let Some(manager) = &self.manager else {
+            println!("- Not supported for this package manager");
+            println!();
+            return;
+        };
+        let ok = self.enabled && self.applies && self.timer_active != Some(false);
+        println!(
+            "{} Automatic security updates ({}): {}",
+            mark(ok),
+            manager,
+            if !self.installed {
+                "not installed"
+            } else if ok {
+                "on"
+            } else {
+                "off"
+            }
+        );
+        if verbose {
+            if let Some(days) = self.last_run_days {
+                println!("- Last run: {} days ago", days);
+            }
+        }
+        println!();
+    }
+}
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Security Ambulance backend
+//!
+//! Baseline audit of services exposed on all interfaces, writable PATH
+//! directories, sudoers and sshd weaknesses, the firewall and automatic
+//! updates, reported as findings ranked by severity. It changes nothing.
+//! `--json` output follows the network ambulance's report model.
+
+mod diagnostics;
+mod report;
+mod system;
+
+use ambulance_core::error::{exit, Error};
+use report::Severity;
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Security Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: security-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run the security audit");
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Security Ambulance");
+    println!("==================\n");
+    result.listeners.print(verbose);
+    result.path.print(verbose);
+    result.sudoers.print(verbose);
+    result.ssh.print(verbose);
+    result.firewall.print(verbose);
+    result.updates.print(verbose);
+    result.print_findings();
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Listeners", "listeners"),
+        ("PATH", "path"),
+        ("sudo", "sudoers"),
+        ("SSH", "ssh"),
+        ("Firewall", "firewall"),
+        ("Updates", "updates"),
+    ];
+    for (name, section) in sections {
+        // Findings are ranked, so the first is the worst
+        let worst = result
+            .findings
+            .iter()
+            .find(|f| f.section == section && f.severity > Severity::Info);
+        println!("{}: {}", name, worst.map_or("OK", |f| f.severity.label()));
+    }
+    let counts: Vec<String> = Severity::ALL
+        .iter()
+        .map(|s| format!("{} {}", result.count(*s), s.label().to_lowercase()))
+        .collect();
+    println!("Findings: {}", counts.join(", "));
+    exit::findings(result.has_warnings())
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Security Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
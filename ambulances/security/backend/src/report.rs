@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance, ranked by severity
+//!
+//! `diagnose --json` emits `{version, tool, findings, <section>...}`.
+//! Sections carry what was inspected; every problem is a finding in the
+//! top-level `findings` list, most severe first, naming its section and
+//! how to fix it.
+
+use crate::diagnostics::{
+    firewall::FirewallDiagnostics, listeners::ListenerDiagnostics, path::PathDiagnostics,
+    ssh::SshDiagnostics, sudoers::SudoersDiagnostics, updates::UpdateDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::mark;
+
+pub const TOOL: &str = "security-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 5] = [
+        Severity::Critical,
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+        Severity::Info,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub section: &'static str,
+    pub title: String,
+    pub remedy: String,
+}
+
+impl Finding {
+    pub fn new(
+        severity: Severity,
+        section: &'static str,
+        title: impl Into<String>,
+        remedy: impl Into<String>,
+    ) -> Finding {
+        Finding {
+            severity,
+            section,
+            title: title.into(),
+            remedy: remedy.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    /// Most severe first
+    pub findings: Vec<Finding>,
+    pub listeners: ListenerDiagnostics,
+    pub path: PathDiagnostics,
+    pub sudoers: SudoersDiagnostics,
+    pub ssh: SshDiagnostics,
+    pub firewall: FirewallDiagnostics,
+    pub updates: UpdateDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether anything worse than informational was found
+    pub fn has_warnings(&self) -> bool {
+        self.findings.iter().any(|f| f.severity > Severity::Info)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    /// Print the ranked findings the way the network CLI prints notes
+    pub fn print_findings(&self) {
+        println!("=== Findings ===");
+        if self.findings.is_empty() {
+            println!("✓ Nothing found");
+        }
+        for finding in &self.findings {
+            println!(
+                "[{}] {}: {}",
+                finding.severity.label(),
+                finding.section,
+                finding.title
+            );
+            println!("  → {}", finding.remedy);
+        }
+        println!();
+    }
+}
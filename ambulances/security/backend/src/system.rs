@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thin helpers over the external tools and files we read
+
+use std::path::Path;
+use std::process::Command;
+
+/// Run a program that must succeed, returning its stdout
+pub fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether `program` is found on PATH or in the sbin directories
+pub fn has(program: &str) -> bool {
+    let sbin = ["/usr/sbin", "/sbin"].map(std::path::PathBuf::from);
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .iter()
+        .chain(sbin.iter())
+        .any(|dir| dir.join(program).is_file())
+}
+
+/// A file's contents; `None` if absent or unreadable
+pub fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+/// `*.conf`-style drop-ins of `dir` in the order their tools read them
+pub fn dropins(dir: &str, extension: Option<&str>) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| match extension {
+            Some(ext) => p.extension().is_some_and(|e| e == ext),
+            None => true,
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Effective user and group ID
+pub fn ids() -> (u32, u32) {
+    unsafe { (libc::geteuid(), libc::getegid()) }
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Units in the failed state
+
+use super::unit::{self, UnitReport};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FailedDiagnostics {
+    pub units: Vec<UnitReport>,
+    /// Why the manager could not be asked
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose() -> FailedDiagnostics {
+    let mut diag = FailedDiagnostics::default();
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            diag.error = Some(format!("cannot connect to the system bus: {}", e));
+            return diag;
+        }
+    };
+    let units = match bus.list_units() {
+        Ok(units) => units,
+        Err(e) => {
+            diag.error = Some(format!("cannot list units: {}", e));
+            return diag;
+        }
+    };
+
+    diag.units = units
+        .iter()
+        .filter(|u| u.active_state == "failed")
+        .map(|u| unit::inspect(&bus, u))
+        .collect();
+
+    for u in &diag.units {
+        diag.warnings.push(u.summary());
+    }
+    if diag.units.iter().any(|u| !u.config_errors.is_empty()) {
+        diag.recommendations.push(
+            "Fix the unit file lines shown, then: service-ambulance repair restart".to_string(),
+        );
+    }
+    if diag
+        .units
+        .iter()
+        .any(|u| u.result.as_deref() == Some("start-limit-hit"))
+    {
+        diag.recommendations.push(
+            "Clear the start limit before restarting: service-ambulance repair reset-failed"
+                .to_string(),
+        );
+    }
+    if diag.units.iter().any(|u| u.config_errors.is_empty()) {
+        diag.recommendations
+            .push("Restart the failed units: service-ambulance repair restart".to_string());
+    }
+    diag
+}
+
+impl FailedDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Failed Unit Diagnostics ===");
+        if let Some(e) = &self.error {
+            println!("✗ {}", e);
+        } else {
+            println!(
+                "{} Failed units: {}",
+                mark(self.units.is_empty()),
+                self.units.len()
+            );
+        }
+        for u in &self.units {
+            u.print(verbose);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
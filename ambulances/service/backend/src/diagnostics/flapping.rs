@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Services stuck in a restart loop
+//!
+//! A flapping service is not failed yet: it sits in `auto-restart`, or has
+//! been restarted by `Restart=` several times since it was last started.
+//! Left alone it usually ends in `start-limit-hit`.
+
+use super::unit::{self, UnitReport};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+/// Automatic restarts after which a running service counts as flapping
+pub const FLAPPING_RESTARTS: u32 = 3;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FlappingDiagnostics {
+    pub units: Vec<UnitReport>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose() -> FlappingDiagnostics {
+    let mut diag = FlappingDiagnostics::default();
+    // The failed section already reports a missing bus
+    let Ok(bus) = Bus::system() else {
+        return diag;
+    };
+    let Ok(units) = bus.list_units() else {
+        return diag;
+    };
+
+    diag.units = units
+        .iter()
+        .filter(|u| u.name.ends_with(".service") && u.active_state != "failed")
+        .filter(|u| u.sub_state == "auto-restart" || unit::restarts(&bus, u) >= FLAPPING_RESTARTS)
+        .map(|u| unit::inspect(&bus, u))
+        .collect();
+
+    for u in &diag.units {
+        diag.warnings.push(u.summary());
+    }
+    if !diag.units.is_empty() {
+        diag.recommendations.push(
+            "Restarting will not help a crash loop; read the log lines and exit status above"
+                .to_string(),
+        );
+    }
+    if diag.units.iter().any(|u| !u.config_errors.is_empty()) {
+        diag.recommendations
+            .push("Show the failing config lines: service-ambulance repair config".to_string());
+    }
+    diag
+}
+
+impl FlappingDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Flapping Unit Diagnostics ===");
+        println!(
+            "{} Restart loops: {}",
+            mark(self.units.is_empty()),
+            self.units.len()
+        );
+        for u in &self.units {
+            u.print(verbose);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
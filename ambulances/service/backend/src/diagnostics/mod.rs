@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Service diagnostics, one module per report section
+//!
+//! Failed and flapping units are looked for as ambulance-core jobs at the
+//! same time, within the scan's budget.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod failed;
+pub mod flapping;
+pub mod unit;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; systemd can be slow to list its units on
+/// a busy machine
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Failed(failed::FailedDiagnostics),
+    Flapping(flapping::FlappingDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("failed", |_| {
+            Ok(Section::Failed(failed::diagnose()))
+        }))
+        .job(Job::blocking("flapping", |_| {
+            Ok(Section::Flapping(flapping::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        failed: Default::default(),
+        flapping: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Failed(d)) => result.failed = d,
+            Some(Section::Flapping(d)) => result.flapping = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "failed" => &mut result.failed.warnings,
+                    _ => &mut result.flapping.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
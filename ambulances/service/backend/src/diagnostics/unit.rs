@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Everything we can learn about one unit
+//!
+//! Properties come from the manager over the system bus; the unit's own
+//! output and the manager's messages about it come from the journal. The
+//! manager logs unit-file parse errors with `CONFIG_FILE` and
+//! `CONFIG_LINE` fields, which is how an error is tied to its exact line.
+
+use serde::Serialize;
+use systemd_shim::bus::{self, Bus, UnitInfo};
+use systemd_shim::journal::{self, Journal};
+
+/// Journal entries kept per unit and per source
+const LOG_LINES: usize = 10;
+
+/// How far back the manager log is searched for config errors
+const MANAGER_SCAN: usize = 500;
+
+/// Exit codes systemd itself uses when it cannot set up a service
+const SYSTEMD_EXIT_CODES: &[(i32, &str)] = &[
+    (200, "CHDIR: the working directory is missing"),
+    (203, "EXEC: the ExecStart program could not be run"),
+    (205, "LIMITS: resource limits could not be applied"),
+    (209, "STDOUT: standard output could not be set up"),
+    (210, "CHROOT: the root directory could not be entered"),
+    (217, "USER: the User= or Group= does not exist"),
+    (226, "NAMESPACE: a sandboxing path does not exist"),
+    (227, "NO_NEW_PRIVILEGES: could not be applied"),
+    (230, "KEYRING: the kernel keyring could not be set up"),
+    (243, "CREDENTIALS: a credential could not be loaded"),
+];
+
+/// `CLD_*` values of `ExecMainCode`
+const CLD_EXITED: i32 = 1;
+const CLD_KILLED: i32 = 2;
+const CLD_DUMPED: i32 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigError {
+    pub file: String,
+    pub line: u32,
+    /// The offending line as it is in the file now
+    pub text: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnitReport {
+    pub name: String,
+    pub description: String,
+    pub active_state: String,
+    pub sub_state: String,
+    /// Why the unit last stopped, e.g. `exit-code`, `signal`, `start-limit-hit`
+    pub result: Option<String>,
+    /// Main process exit, e.g. `exit status 1` or `killed by SIGKILL`
+    pub exit: Option<String>,
+    /// Automatic restarts since the unit was last started by hand
+    pub restarts: u32,
+    pub unit_file_state: Option<String>,
+    pub fragment_path: Option<String>,
+    /// Most recent lines the unit wrote, oldest first
+    pub log: Vec<String>,
+    /// Most recent manager messages about the unit, oldest first
+    pub manager_log: Vec<String>,
+    pub config_errors: Vec<ConfigError>,
+}
+
+/// The type-specific interface carrying `Result`, by unit suffix
+fn type_interface(name: &str) -> Option<&'static str> {
+    Some(match name.rsplit_once('.')?.1 {
+        "service" => bus::SERVICE,
+        "socket" => "org.freedesktop.systemd1.Socket",
+        "mount" => "org.freedesktop.systemd1.Mount",
+        "swap" => "org.freedesktop.systemd1.Swap",
+        "timer" => "org.freedesktop.systemd1.Timer",
+        "path" => "org.freedesktop.systemd1.Path",
+        "automount" => "org.freedesktop.systemd1.Automount",
+        _ => return None,
+    })
+}
+
+fn signal_name(signal: i32) -> String {
+    match signal {
+        1 => "SIGHUP".to_string(),
+        2 => "SIGINT".to_string(),
+        6 => "SIGABRT".to_string(),
+        9 => "SIGKILL".to_string(),
+        11 => "SIGSEGV".to_string(),
+        15 => "SIGTERM".to_string(),
+        n => format!("signal {}", n),
+    }
+}
+
+/// Describe `ExecMainCode`/`ExecMainStatus`, or `None` for a clean exit
+pub fn describe_exit(code: i32, status: i32) -> Option<String> {
+    match code {
+        CLD_EXITED if status == 0 => None,
+        CLD_EXITED => Some(
+            match SYSTEMD_EXIT_CODES.iter().find(|(c, _)| *c == status) {
+                Some((_, meaning)) => format!("exit status {} ({})", status, meaning),
+                None => format!("exit status {}", status),
+            },
+        ),
+        CLD_KILLED => Some(format!("killed by {}", signal_name(status))),
+        CLD_DUMPED => Some(format!("dumped core on {}", signal_name(status))),
+        _ => None,
+    }
+}
+
+/// Number of automatic restarts of a service, 0 for other unit types
+pub fn restarts(bus: &Bus, unit: &UnitInfo) -> u32 {
+    if !unit.name.ends_with(".service") {
+        return 0;
+    }
+    bus.get_property_u32(bus::SYSTEMD, &unit.path, bus::SERVICE, "NRestarts")
+        .unwrap_or(0)
+}
+
+/// The last `limit` messages matching `m`, oldest first
+fn recent(m: &str, limit: usize) -> Vec<String> {
+    let Ok(mut journal) = Journal::open(journal::LOCAL_ONLY) else {
+        return Vec::new();
+    };
+    if journal.add_match(m).is_err() || journal.seek_tail().is_err() {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    while lines.len() < limit && journal.previous_entry().unwrap_or(false) {
+        if let Some(message) = journal.field("MESSAGE") {
+            lines.push(message);
+        }
+    }
+    lines.reverse();
+    lines
+}
+
+/// Parse errors the manager logged for `name`, each file and line once
+pub fn config_errors(name: &str) -> Vec<ConfigError> {
+    let Ok(mut journal) = Journal::open(journal::LOCAL_ONLY) else {
+        return Vec::new();
+    };
+    if journal.add_match(&format!("UNIT={}", name)).is_err()
+        || journal.add_match("_PID=1").is_err()
+        || journal.seek_tail().is_err()
+    {
+        return Vec::new();
+    }
+
+    let mut errors: Vec<ConfigError> = Vec::new();
+    for _ in 0..MANAGER_SCAN {
+        if !journal.previous_entry().unwrap_or(false) {
+            break;
+        }
+        let (Some(file), Some(line)) = (journal.field("CONFIG_FILE"), journal.field("CONFIG_LINE"))
+        else {
+            continue;
+        };
+        let Ok(line) = line.parse::<u32>() else {
+            continue;
+        };
+        if errors.iter().any(|e| e.file == file && e.line == line) {
+            continue;
+        }
+        let text = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|contents| contents.lines().nth(line as usize - 1).map(str::to_string));
+        errors.push(ConfigError {
+            message: journal.field("MESSAGE").unwrap_or_default(),
+            file,
+            line,
+            text,
+        });
+    }
+    errors
+}
+
+/// Gather properties, exit status, logs and config errors for `unit`
+pub fn inspect(bus: &Bus, unit: &UnitInfo) -> UnitReport {
+    let property = |interface: &str, member: &str| {
+        bus.get_property_string(bus::SYSTEMD, &unit.path, interface, member)
+            .ok()
+            .filter(|v| !v.is_empty())
+    };
+    let exit = if unit.name.ends_with(".service") {
+        let code = bus.get_property_i32(bus::SYSTEMD, &unit.path, bus::SERVICE, "ExecMainCode");
+        let status = bus.get_property_i32(bus::SYSTEMD, &unit.path, bus::SERVICE, "ExecMainStatus");
+        match (code, status) {
+            (Ok(code), Ok(status)) => describe_exit(code, status),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    UnitReport {
+        name: unit.name.clone(),
+        description: unit.description.clone(),
+        active_state: unit.active_state.clone(),
+        sub_state: unit.sub_state.clone(),
+        result: type_interface(&unit.name)
+            .and_then(|interface| property(interface, "Result"))
+            .filter(|r| r != "success"),
+        exit,
+        restarts: restarts(bus, unit),
+        unit_file_state: property(bus::UNIT, "UnitFileState"),
+        fragment_path: property(bus::UNIT, "FragmentPath"),
+        log: recent(&format!("_SYSTEMD_UNIT={}", unit.name), LOG_LINES),
+        manager_log: recent(&format!("UNIT={}", unit.name), LOG_LINES),
+        config_errors: config_errors(&unit.name),
+    }
+}
+
+impl UnitReport {
+    /// One-line reason for the warning list
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} ({})", self.active_state, self.sub_state)];
+        parts.extend(self.result.clone());
+        parts.extend(self.exit.clone());
+        if self.restarts > 0 {
+            parts.push(format!("{} restarts", self.restarts));
+        }
+        format!("{}: {}", self.name, parts.join(", "))
+    }
+
+    pub fn print(&self, verbose: bool) {
+        println!("✗ {}", self.summary());
+        for e in &self.config_errors {
+            println!("    {}:{}: {}", e.file, e.line, e.message);
+            if let Some(text) = &e.text {
+                println!("        {}", text);
+            }
+        }
+        if verbose {
+            for line in self.manager_log.iter().chain(&self.log) {
+                println!("    | {}", line);
+            }
+        } else if let Some(last) = self.log.last() {
+            println!("    | {}", last);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Show the exact unit-file lines the manager rejected
+//!
+//! Read-only: the lines are reported for the user to fix, since only they
+//! know what the directive was meant to say.
+
+use crate::diagnostics::unit;
+use crate::report::RepairOutcome;
+use systemd_shim::bus::UnitInfo;
+
+pub fn repair(units: &[UnitInfo]) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    for u in units {
+        for e in unit::config_errors(&u.name) {
+            result
+                .actions
+                .push(format!("{}:{}: {}", e.file, e.line, e.message));
+            match e.text {
+                Some(text) => result.actions.push(format!("    {}", text)),
+                None => result
+                    .actions
+                    .push(format!("    (line {} is no longer in the file)", e.line)),
+            }
+        }
+    }
+    if result.actions.is_empty() {
+        return RepairOutcome::not_needed("No unit-file errors logged for these units");
+    }
+    result
+        .actions
+        .push("Edit the files above, then run: service-ambulance repair restart".to_string());
+    result.success = true;
+    result
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Service repairs, one module per target
+//!
+//! Without a unit name every target works on the units `diagnose` flags;
+//! with one it works on that unit alone.
+
+pub mod config;
+pub mod reenable;
+pub mod reset_failed;
+pub mod restart;
+
+use crate::diagnostics::{flapping, unit};
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::error::{Category, Error};
+use systemd_shim::bus::{Bus, UnitInfo};
+
+pub const TARGETS: &[&str] = &["config", "reset-failed", "reenable", "restart", "all"];
+
+/// Failed and flapping units, or just `name`
+fn candidates(bus: &Bus, name: Option<&str>) -> Result<Vec<UnitInfo>, Error> {
+    let units = bus.list_units().map_err(|e| {
+        Error::new(
+            "unavailable.systemd",
+            Category::Unavailable,
+            format!("cannot list units: {}", e),
+        )
+    })?;
+    match name {
+        Some(name) => units
+            .into_iter()
+            .find(|u| u.name == name)
+            .map(|u| vec![u])
+            .ok_or_else(|| {
+                Error::new(
+                    "usage.unknown-unit",
+                    Category::Usage,
+                    format!("{} is not loaded", name),
+                )
+                .with_hint("List the loaded units with: systemctl list-units --all")
+            }),
+        None => Ok(units
+            .into_iter()
+            .filter(|u| {
+                u.active_state == "failed"
+                    || u.sub_state == "auto-restart"
+                    || unit::restarts(bus, u) >= flapping::FLAPPING_RESTARTS
+            })
+            .collect()),
+    }
+}
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change that needs explicit approval.
+pub fn run(
+    target: &str,
+    unit: Option<&str>,
+    confirm: &mut dyn FnMut(&str) -> bool,
+) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let bus = Bus::system().map_err(|e| {
+        Error::new(
+            "unavailable.system-bus",
+            Category::Unavailable,
+            format!("cannot connect to the system bus: {}", e),
+        )
+    })?;
+    let units = candidates(&bus, unit)?;
+    let selected = |name: &str| target == name || target == "all";
+
+    // Clearing the failed state first lets a start-limited unit restart
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        config_repair: if selected("config") {
+            config::repair(&units)
+        } else {
+            RepairOutcome::default()
+        },
+        reset_failed_repair: if selected("reset-failed") {
+            reset_failed::repair(&bus, &units)
+        } else {
+            RepairOutcome::default()
+        },
+        reenable_repair: if selected("reenable") {
+            reenable::repair(&bus, &units, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        restart_repair: if selected("restart") {
+            restart::repair(&bus, &units, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Recreate the install links of enabled units that failed
+//!
+//! Equivalent to `systemctl reenable`: links left over from an older
+//! `[Install]` section (after a package update moved `WantedBy=`, say) are
+//! removed and the current ones created. Asks before each unit.
+
+use crate::report::RepairOutcome;
+use systemd_shim::bus::{self, Bus, UnitInfo};
+
+pub fn repair(
+    bus: &Bus,
+    units: &[UnitInfo],
+    confirm: &mut dyn FnMut(&str) -> bool,
+) -> RepairOutcome {
+    let enabled: Vec<_> = units
+        .iter()
+        .filter(|u| u.active_state == "failed")
+        .filter(|u| {
+            bus.get_property_string(bus::SYSTEMD, &u.path, bus::UNIT, "UnitFileState")
+                .is_ok_and(|state| state == "enabled")
+        })
+        .collect();
+    if enabled.is_empty() {
+        return RepairOutcome::not_needed("No failed enabled units, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for u in enabled {
+        if !confirm(&format!(
+            "Re-enable {} (recreate its install links)?",
+            u.name
+        )) {
+            result.errors.push(format!(
+                "{}: not confirmed, nothing changed (rerun with --yes to confirm)",
+                u.name
+            ));
+            continue;
+        }
+        match bus.reenable_unit_files(&[&u.name]) {
+            Ok(()) => result.actions.push(format!("Re-enabled {}", u.name)),
+            Err(e) => result.errors.push(format!("{}: {}", u.name, e)),
+        }
+    }
+    if !result.actions.is_empty() {
+        match bus.reload() {
+            Ok(()) => result.actions.push("Reloaded the manager".to_string()),
+            Err(e) => result.errors.push(format!("daemon reload: {}", e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Clear the failed state and start-rate limit of failed units
+
+use crate::report::RepairOutcome;
+use systemd_shim::bus::{Bus, UnitInfo};
+
+pub fn repair(bus: &Bus, units: &[UnitInfo]) -> RepairOutcome {
+    let failed: Vec<_> = units
+        .iter()
+        .filter(|u| u.active_state == "failed")
+        .collect();
+    if failed.is_empty() {
+        return RepairOutcome::not_needed("No failed units, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for u in failed {
+        match bus.reset_failed_unit(&u.name) {
+            Ok(()) => result
+                .actions
+                .push(format!("Reset failed state of {}", u.name)),
+            Err(e) => result.errors.push(format!("{}: {}", u.name, e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
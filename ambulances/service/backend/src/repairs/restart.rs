@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Restart failed units
+//!
+//! The manager rereads unit files first so edits made after `repair
+//! config` take effect. Each unit is restarted only after the caller
+//! confirms, then watched briefly to see whether it stays up.
+
+use crate::diagnostics::unit;
+use crate::report::RepairOutcome;
+use std::time::{Duration, Instant};
+use systemd_shim::bus::{self, Bus, UnitInfo};
+
+/// How long a restarted unit has to reach `active`
+const SETTLE: Duration = Duration::from_secs(5);
+
+fn wait_for_state(bus: &Bus, unit: &UnitInfo) -> String {
+    let deadline = Instant::now() + SETTLE;
+    loop {
+        let state = bus
+            .get_property_string(bus::SYSTEMD, &unit.path, bus::UNIT, "ActiveState")
+            .unwrap_or_else(|_| "unknown".to_string());
+        if state != "activating" && state != "reloading" || Instant::now() >= deadline {
+            return state;
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
+
+pub fn repair(
+    bus: &Bus,
+    units: &[UnitInfo],
+    confirm: &mut dyn FnMut(&str) -> bool,
+) -> RepairOutcome {
+    let failed: Vec<_> = units
+        .iter()
+        .filter(|u| u.active_state == "failed")
+        .collect();
+    if failed.is_empty() {
+        return RepairOutcome::not_needed("No failed units, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    match bus.reload() {
+        Ok(()) => result.actions.push("Reloaded unit files".to_string()),
+        Err(e) => result.errors.push(format!("daemon reload: {}", e)),
+    }
+
+    for u in failed {
+        if !confirm(&format!("Restart {}?", u.name)) {
+            result.errors.push(format!(
+                "{}: not confirmed, not restarted (rerun with --yes to confirm)",
+                u.name
+            ));
+            continue;
+        }
+        if let Err(e) = bus.restart_unit(&u.name, "replace") {
+            result.errors.push(format!("{}: {}", u.name, e));
+            continue;
+        }
+        match wait_for_state(bus, u).as_str() {
+            "active" => result
+                .actions
+                .push(format!("Restarted {}, now active", u.name)),
+            state => {
+                let hint = if unit::config_errors(&u.name).is_empty() {
+                    ""
+                } else {
+                    "; see service-ambulance repair config"
+                };
+                result
+                    .errors
+                    .push(format!("{}: restarted but {}{}", u.name, state, hint));
+            }
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Package and thumbnail caches
+//!
+//! Downloaded package archives are kept after installing, and thumbnail
+//! caches grow with every image ever browsed. Both are regenerated on
+//! demand, so anything here is safe to reclaim.
+
+use crate::report::{mark, print_notes};
+use crate::scan::{self, human_bytes};
+use crate::system;
+use ambulance_privilege::userfs;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Package caches with the tool and arguments that clean each
+pub const PACKAGE_CACHES: &[(&str, &str, &[&str])] = &[
+    ("/var/cache/apt/archives", "apt-get", &["clean"]),
+    ("/var/cache/dnf", "dnf", &["clean", "packages"]),
+    ("/var/cache/libdnf5", "dnf", &["clean", "packages"]),
+    ("/var/cache/zypp/packages", "zypper", &["clean", "--all"]),
+    // Keeps the newest version of each package for downgrades
+    ("/var/cache/pacman/pkg", "paccache", &["-rk1"]),
+];
+
+/// Per-user thumbnail cache, relative to the home directory
+pub const THUMBNAILS: &str = ".cache/thumbnails";
+
+/// Total from which the caches are reported as large
+const LARGE_BYTES: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cache {
+    pub path: String,
+    pub bytes: u64,
+    /// Command that cleans it, or `None` when its contents are deleted
+    pub cleaner: Option<String>,
+    /// The home a user's cache is in; the repair deletes from there
+    /// without following the user's symlinks
+    #[serde(skip)]
+    pub home: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheDiagnostics {
+    pub caches: Vec<Cache>,
+    pub reclaimable_bytes: u64,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Home directories of regular users and root
+fn homes() -> Vec<String> {
+    let mut homes = vec!["/root".to_string()];
+    if let Ok(entries) = std::fs::read_dir("/home") {
+        homes.extend(entries.flatten().map(|e| e.path().display().to_string()));
+    }
+    homes
+}
+
+/// Non-empty caches present on this system
+pub fn caches() -> Vec<Cache> {
+    let mut caches = Vec::new();
+    for (path, tool, args) in PACKAGE_CACHES {
+        if !Path::new(path).is_dir() || !system::has(tool) {
+            continue;
+        }
+        caches.push(Cache {
+            path: path.to_string(),
+            bytes: scan::size(Path::new(path)),
+            cleaner: Some(format!("{} {}", tool, args.join(" "))),
+            home: None,
+        });
+    }
+    for home in homes() {
+        // A user may link any part of it elsewhere, say to /etc
+        let home = PathBuf::from(home);
+        if userfs::is_plain_dir(&home, Path::new(THUMBNAILS)) {
+            let path = home.join(THUMBNAILS);
+            caches.push(Cache {
+                bytes: scan::size(&path),
+                path: path.display().to_string(),
+                cleaner: None,
+                home: Some(home),
+            });
+        }
+    }
+    caches.retain(|c| c.bytes > 0);
+    caches
+}
+
+pub fn diagnose() -> CacheDiagnostics {
+    let caches = caches();
+    let mut diag = CacheDiagnostics {
+        reclaimable_bytes: caches.iter().map(|c| c.bytes).sum(),
+        caches,
+        ..CacheDiagnostics::default()
+    };
+
+    if diag.reclaimable_bytes >= LARGE_BYTES {
+        diag.warnings.push(format!(
+            "Package and thumbnail caches hold {}",
+            human_bytes(diag.reclaimable_bytes)
+        ));
+        diag.recommendations
+            .push("Clean them: storage-space-ambulance repair caches".to_string());
+    }
+
+    diag
+}
+
+impl CacheDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Caches ===");
+        println!(
+            "{} Reclaimable: {}",
+            mark(self.reclaimable_bytes < LARGE_BYTES),
+            human_bytes(self.reclaimable_bytes)
+        );
+        if verbose {
+            for cache in &self.caches {
+                println!("- {:>8}  {}", human_bytes(cache.bytes), cache.path);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Container images no container uses
+//!
+//! Every pull and rebuild leaves the previous image behind. Both Docker
+//! and Podman report what `image prune -a` could reclaim through
+//! `system df`. Rootless Podman keeps images per user; run as that user
+//! to see them.
+
+use crate::report::{mark, print_notes};
+use crate::scan::human_bytes;
+use crate::system;
+use serde::Serialize;
+
+/// Engines that share the `system df` and `image prune` interface
+pub const ENGINES: &[&str] = &["docker", "podman"];
+
+/// Images unused for this long are what `repair images` removes
+pub const IMAGE_AGE: &str = "720h";
+
+/// Unused image space from which an engine is reported
+const LARGE_BYTES: u64 = 1 << 30;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Engine {
+    pub name: String,
+    pub images_bytes: u64,
+    /// Images no container uses
+    pub unused_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContainerDiagnostics {
+    pub engines: Vec<Engine>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Size as the engines print it: decimal units, e.g. `1.234GB` or `512kB`
+fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = text.split_at(split);
+    let scale = match unit.to_ascii_uppercase().as_str() {
+        "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((number.trim().parse::<f64>().ok()? * scale) as u64)
+}
+
+/// Image usage of one engine, from `system df`
+pub fn engine(name: &str) -> Result<Engine, String> {
+    let df = system::run(
+        name,
+        &[
+            "system",
+            "df",
+            "--format",
+            "{{.Type}}\t{{.Size}}\t{{.Reclaimable}}",
+        ],
+    )?;
+    let images = df
+        .lines()
+        .find(|l| l.starts_with("Images"))
+        .ok_or_else(|| format!("{} system df: no image line", name))?;
+    let fields: Vec<&str> = images.split('\t').collect();
+    let size = |i: usize| {
+        fields
+            .get(i)
+            // Reclaimable reads "1.2GB (40%)"
+            .and_then(|f| parse_size(f.split(" (").next().unwrap_or(f)))
+            .unwrap_or(0)
+    };
+    Ok(Engine {
+        name: name.to_string(),
+        images_bytes: size(1),
+        unused_bytes: size(2),
+    })
+}
+
+pub fn diagnose() -> ContainerDiagnostics {
+    let mut diag = ContainerDiagnostics::default();
+    for name in ENGINES.iter().filter(|e| system::has(e)) {
+        match engine(name) {
+            Ok(engine) => diag.engines.push(engine),
+            Err(e) => diag.errors.push(e),
+        }
+    }
+
+    for engine in &diag.engines {
+        if engine.unused_bytes >= LARGE_BYTES {
+            diag.warnings.push(format!(
+                "{} holds {} of images no container uses",
+                engine.name,
+                human_bytes(engine.unused_bytes)
+            ));
+        }
+    }
+    if !diag.warnings.is_empty() {
+        diag.recommendations.push(
+            "Remove unused images older than 30 days: storage-space-ambulance repair images"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl ContainerDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Container Images ===");
+        if self.engines.is_empty() && self.errors.is_empty() {
+            println!("- No container engine installed");
+        }
+        for engine in &self.engines {
+            println!(
+                "{} {}: {} unused of {}",
+                mark(engine.unused_bytes < LARGE_BYTES),
+                engine.name,
+                human_bytes(engine.unused_bytes),
+                human_bytes(engine.images_bytes)
+            );
+        }
+        for error in &self.errors {
+            println!("- Unknown: {}", error);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Stored core dumps
+//!
+//! systemd-coredump keeps dumps under `/var/lib/systemd/coredump` and
+//! apport under `/var/crash`. A crash loop in a large program fills either
+//! within hours, long before tmpfiles ages them out.
+
+use crate::report::{mark, print_notes};
+use crate::scan::human_bytes;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::os::unix::fs::MetadataExt;
+
+pub const DIRECTORIES: &[&str] = &["/var/lib/systemd/coredump", "/var/crash"];
+
+/// Total from which core dumps are reported
+const LARGE_BYTES: u64 = 500 << 20;
+
+/// Dumps listed in verbose output
+const LISTED: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreDump {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CoredumpDiagnostics {
+    /// Largest first
+    pub dumps: Vec<CoreDump>,
+    pub total_bytes: u64,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Stored dumps, largest first
+pub fn dumps() -> Vec<CoreDump> {
+    let mut dumps: Vec<CoreDump> = DIRECTORIES
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| CoreDump {
+                path: entry.path().display().to_string(),
+                bytes: meta.blocks() * 512,
+            })
+        })
+        .collect();
+    dumps.sort_by_key(|d| Reverse(d.bytes));
+    dumps
+}
+
+pub fn diagnose() -> CoredumpDiagnostics {
+    let dumps = dumps();
+    let mut diag = CoredumpDiagnostics {
+        total_bytes: dumps.iter().map(|d| d.bytes).sum(),
+        dumps,
+        ..CoredumpDiagnostics::default()
+    };
+
+    if diag.total_bytes >= LARGE_BYTES {
+        diag.warnings.push(format!(
+            "{} core dumps take {}",
+            diag.dumps.len(),
+            human_bytes(diag.total_bytes)
+        ));
+        diag.recommendations.push(
+            "Inspect the crashes with `coredumpctl list`, then: storage-space-ambulance repair coredumps"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl CoredumpDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Core Dumps ===");
+        println!(
+            "{} Stored: {} ({})",
+            mark(self.total_bytes < LARGE_BYTES),
+            self.dumps.len(),
+            human_bytes(self.total_bytes)
+        );
+        if verbose {
+            for dump in self.dumps.iter().take(LISTED) {
+                println!("- {:>8}  {}", human_bytes(dump.bytes), dump.path);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
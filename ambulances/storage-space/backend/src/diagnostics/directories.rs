@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Where the space went
+//!
+//! Walks every mounted block-device filesystem once, totalling directories
+//! down to a few levels, and names the deepest large ones: a directory with
+//! a large child is left out in favour of it, so the list points at
+//! `/var/lib/docker/overlay2` rather than at `/var` and `/var/lib` too.
+//! The walk stops at a time budget; sizes are then lower bounds.
+
+use crate::report::print_notes;
+use crate::scan::human_bytes;
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Levels below each mount point that are totalled
+const DEPTH: usize = 3;
+
+/// Time the whole walk may take
+const BUDGET: Duration = Duration::from_secs(10);
+
+/// Size from which a directory is worth naming
+pub const LARGE_BYTES: u64 = 1 << 30;
+
+/// Directories listed at most
+const LISTED: usize = 10;
+
+/// Read-only image formats, full by design and never worth cleaning
+const IMAGE_TYPES: &[&str] = &["squashfs", "iso9660", "erofs", "udf"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LargeDirectory {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirectoryDiagnostics {
+    /// Mount points walked
+    pub roots: Vec<String>,
+    pub largest: Vec<LargeDirectory>,
+    /// False when the time budget ran out before the walk finished
+    pub complete: bool,
+    /// Directories that could not be read
+    pub unreadable: usize,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+struct Walker {
+    deadline: Instant,
+    complete: bool,
+    unreadable: usize,
+    /// Totals of directories no deeper than `DEPTH`
+    totals: HashMap<PathBuf, u64>,
+}
+
+impl Walker {
+    fn walk(&mut self, path: &Path, dev: u64, depth: usize) -> u64 {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return 0;
+        };
+        if meta.dev() != dev {
+            return 0;
+        }
+        let mut total = meta.blocks() * 512;
+        if meta.is_dir() {
+            if Instant::now() >= self.deadline {
+                self.complete = false;
+                return total;
+            }
+            match std::fs::read_dir(path) {
+                Ok(entries) => {
+                    for entry in entries.flatten() {
+                        total += self.walk(&entry.path(), dev, depth + 1);
+                    }
+                }
+                Err(_) => self.unreadable += 1,
+            }
+            if depth <= DEPTH {
+                self.totals.insert(path.to_path_buf(), total);
+            }
+        }
+        total
+    }
+}
+
+/// Mount points of block-device filesystems, one per device
+fn roots() -> Vec<String> {
+    let mut roots = Vec::new();
+    let mut devices = Vec::new();
+    for line in system::read(host::path("/proc/self/mounts"))
+        .unwrap_or_default()
+        .lines()
+    {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [device, mount_point, fs_type, ..] = fields[..] else {
+            continue;
+        };
+        if !device.starts_with("/dev/") || IMAGE_TYPES.contains(&fs_type) {
+            continue;
+        }
+        // Bind mounts and btrfs subvolumes show the same device again
+        if devices.contains(&device) {
+            continue;
+        }
+        devices.push(device);
+        roots.push(mount_point.replace("\\040", " "));
+    }
+    roots
+}
+
+/// Large directories without a large child
+fn largest(totals: &HashMap<PathBuf, u64>, roots: &[String]) -> Vec<LargeDirectory> {
+    let mut largest: Vec<LargeDirectory> = totals
+        .iter()
+        .filter(|(path, bytes)| {
+            **bytes >= LARGE_BYTES && !roots.iter().any(|r| Path::new(r) == path.as_path())
+        })
+        .filter(|(path, _)| {
+            !totals.iter().any(|(child, child_bytes)| {
+                *child_bytes >= LARGE_BYTES && child.parent() == Some(path.as_path())
+            })
+        })
+        .map(|(path, bytes)| LargeDirectory {
+            path: path.display().to_string(),
+            bytes: *bytes,
+        })
+        .collect();
+    largest.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+    largest.truncate(LISTED);
+    largest
+}
+
+pub fn diagnose() -> DirectoryDiagnostics {
+    let roots = roots();
+    let mut walker = Walker {
+        deadline: Instant::now() + BUDGET,
+        complete: true,
+        unreadable: 0,
+        totals: HashMap::new(),
+    };
+    for root in &roots {
+        let Ok(meta) = std::fs::symlink_metadata(root) else {
+            continue;
+        };
+        walker.walk(Path::new(root), meta.dev(), 0);
+    }
+    let mut diag = DirectoryDiagnostics {
+        largest: largest(&walker.totals, &roots),
+        roots,
+        complete: walker.complete,
+        unreadable: walker.unreadable,
+        ..DirectoryDiagnostics::default()
+    };
+
+    if !diag.complete {
+        diag.warnings.push(format!(
+            "Scan stopped after {} s; sizes are lower bounds",
+            BUDGET.as_secs()
+        ));
+    }
+    if diag.unreadable > 0 && !ambulance_privilege::is_elevated() {
+        diag.recommendations.push(format!(
+            "{} directories could not be read; run as root to measure everything",
+            diag.unreadable
+        ));
+    }
+
+    diag
+}
+
+impl DirectoryDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Large Directories ===");
+        if self.largest.is_empty() {
+            println!("- No directory over {} found", human_bytes(LARGE_BYTES));
+        }
+        for dir in &self.largest {
+            println!("- {:>8}  {}", human_bytes(dir.bytes), dir.path);
+        }
+        if verbose {
+            println!("- Scanned: {}", self.roots.join(", "));
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
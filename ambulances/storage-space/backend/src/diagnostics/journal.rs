@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Journal disk usage
+//!
+//! journald caps itself at 10% of the filesystem (at most 4G) unless
+//! `SystemMaxUse=` says otherwise, which on a small root is still a lot.
+//! Only archived files can be removed; the active ones are journald's.
+
+use crate::report::{mark, print_notes};
+use crate::scan::human_bytes;
+use crate::system;
+use serde::Serialize;
+use systemd_shim::journal::{self, ArchivedFile, Journal};
+
+/// Size `repair journal` vacuums down to
+pub const TARGET_BYTES: u64 = 500 << 20;
+
+/// Usage from which the journal is reported as large
+const LARGE_BYTES: u64 = 1 << 30;
+
+const CONFIGS: &[&str] = &["/etc/systemd/journald.conf", "/etc/systemd/journald.conf.d"];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JournalDiagnostics {
+    pub usage_bytes: Option<u64>,
+    pub archived_files: usize,
+    /// What vacuuming to `TARGET_BYTES` would free
+    pub reclaimable_bytes: u64,
+    /// `SystemMaxUse=`, when configured
+    pub max_use: Option<String>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// `SystemMaxUse=` from journald.conf and its drop-ins, the last one winning
+fn max_use() -> Option<String> {
+    let mut files = vec![std::path::PathBuf::from(CONFIGS[0])];
+    if let Ok(entries) = std::fs::read_dir(CONFIGS[1]) {
+        let mut dropins: Vec<_> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "conf"))
+            .collect();
+        dropins.sort();
+        files.extend(dropins);
+    }
+    files
+        .iter()
+        .filter_map(system::read)
+        .flat_map(|config| {
+            config
+                .lines()
+                .filter_map(|l| l.trim().strip_prefix("SystemMaxUse="))
+                .map(|v| v.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .rfind(|v| !v.is_empty())
+}
+
+/// Archived files a vacuum to `TARGET_BYTES` would remove, oldest first
+pub fn vacuum_plan(usage: u64) -> Vec<ArchivedFile> {
+    let mut remaining = usage;
+    journal::archived_files()
+        .into_iter()
+        .take_while(|file| {
+            let take = remaining > TARGET_BYTES;
+            remaining = remaining.saturating_sub(file.bytes);
+            take
+        })
+        .collect()
+}
+
+pub fn usage() -> std::io::Result<u64> {
+    Journal::open(0)?.usage()
+}
+
+pub fn diagnose() -> JournalDiagnostics {
+    let mut diag = JournalDiagnostics {
+        archived_files: journal::archived_files().len(),
+        max_use: max_use(),
+        ..JournalDiagnostics::default()
+    };
+    let usage = match usage() {
+        Ok(usage) => usage,
+        Err(e) => {
+            diag.error = Some(format!("Could not open the journal: {}", e));
+            return diag;
+        }
+    };
+    diag.usage_bytes = Some(usage);
+    diag.reclaimable_bytes = vacuum_plan(usage).iter().map(|f| f.bytes).sum();
+
+    if usage >= LARGE_BYTES {
+        diag.warnings
+            .push(format!("The journal uses {}", human_bytes(usage)));
+        if diag.reclaimable_bytes > 0 {
+            diag.recommendations.push(format!(
+                "Vacuum it to {}: storage-space-ambulance repair journal",
+                human_bytes(TARGET_BYTES)
+            ));
+        }
+        if diag.max_use.is_none() {
+            diag.recommendations.push(
+                "Cap it for good with SystemMaxUse=500M in /etc/systemd/journald.conf.d/size.conf"
+                    .to_string(),
+            );
+        }
+    }
+
+    diag
+}
+
+impl JournalDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Journal ===");
+        let Some(usage) = self.usage_bytes else {
+            println!(
+                "- Usage: unknown ({})",
+                self.error.as_deref().unwrap_or("not read")
+            );
+            println!();
+            return;
+        };
+        println!(
+            "{} Usage: {} ({} reclaimable)",
+            mark(usage < LARGE_BYTES),
+            human_bytes(usage),
+            human_bytes(self.reclaimable_bytes)
+        );
+        if verbose {
+            println!("- Archived files: {}", self.archived_files);
+            println!(
+                "- SystemMaxUse: {}",
+                self.max_use.as_deref().unwrap_or("default")
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
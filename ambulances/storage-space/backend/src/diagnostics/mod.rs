@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Storage space diagnostics, one module per report section
+//!
+//! Sizing directories, caches and images is disk-bound and slow, so the
+//! sections run as ambulance-core jobs at the same time, within the
+//! scan's budget, and the reclaimable total is added up from those that
+//! finished. A section the budget cut short is left empty and its timing
+//! says why; one that failed or timed out says so in its warnings.
+
+pub mod caches;
+pub mod containers;
+pub mod coredumps;
+pub mod directories;
+pub mod journal;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; walking a large tree, or an image store
+/// on a slow disk, takes a while
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+enum Section {
+    Directories(directories::DirectoryDiagnostics),
+    Journal(journal::JournalDiagnostics),
+    Caches(caches::CacheDiagnostics),
+    Containers(containers::ContainerDiagnostics),
+    Coredumps(coredumps::CoredumpDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("directories", |_| {
+            Ok(Section::Directories(directories::diagnose()))
+        }))
+        .job(Job::blocking("journal", |_| {
+            Ok(Section::Journal(journal::diagnose()))
+        }))
+        .job(Job::blocking("caches", |_| {
+            Ok(Section::Caches(caches::diagnose()))
+        }))
+        .job(Job::blocking("containers", |_| {
+            Ok(Section::Containers(containers::diagnose()))
+        }))
+        .job(Job::blocking("coredumps", |_| {
+            Ok(Section::Coredumps(coredumps::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        reclaimable_bytes: 0,
+        directories: Default::default(),
+        journal: Default::default(),
+        caches: Default::default(),
+        containers: Default::default(),
+        coredumps: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Directories(d)) => result.directories = d,
+            Some(Section::Journal(d)) => result.journal = d,
+            Some(Section::Caches(d)) => result.caches = d,
+            Some(Section::Containers(d)) => result.containers = d,
+            Some(Section::Coredumps(d)) => result.coredumps = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "directories" => &mut result.directories.warnings,
+                    "journal" => &mut result.journal.warnings,
+                    "caches" => &mut result.caches.warnings,
+                    "containers" => &mut result.containers.warnings,
+                    _ => &mut result.coredumps.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result.reclaimable_bytes = result.journal.reclaimable_bytes
+        + result.caches.reclaimable_bytes
+        + result
+            .containers
+            .engines
+            .iter()
+            .map(|e| e.unused_bytes)
+            .sum::<u64>()
+        + result.coredumps.total_bytes;
+    result
+}
@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Clean package and thumbnail caches
+//!
+//! Package caches are cleaned by their own tool, which knows which files
+//! are still needed; thumbnail caches are emptied.
+
+use crate::diagnostics::caches;
+use crate::report::RepairOutcome;
+use crate::scan::{self, human_bytes};
+use crate::system;
+use ambulance_privilege::userfs;
+use std::path::Path;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let caches = caches::caches();
+    if caches.is_empty() {
+        return RepairOutcome::not_needed("All caches are empty, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for cache in caches {
+        let how = cache.cleaner.as_deref().unwrap_or("deleting its contents");
+        let question = format!(
+            "Clean {} ({}) with {}?",
+            cache.path,
+            human_bytes(cache.bytes),
+            how
+        );
+        if !confirm(&question) {
+            result.errors.push(format!(
+                "{}: not confirmed, left in place (rerun with --yes to confirm)",
+                cache.path
+            ));
+            continue;
+        }
+        let tool = caches::PACKAGE_CACHES
+            .iter()
+            .find(|(path, _, _)| *path == cache.path);
+        let cleaned = match (tool, &cache.home) {
+            (Some((_, tool, args)), _) => system::run(tool, args).map(|_| ()),
+            (None, Some(home)) => userfs::empty(home, Path::new(caches::THUMBNAILS))
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            (None, None) => continue,
+        };
+        match cleaned {
+            Ok(()) => {
+                let freed = cache
+                    .bytes
+                    .saturating_sub(scan::size(Path::new(&cache.path)));
+                result.actions.push(format!(
+                    "Cleaned {}: freed {}",
+                    cache.path,
+                    human_bytes(freed)
+                ));
+            }
+            Err(e) => result.errors.push(format!("{}: {}", cache.path, e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
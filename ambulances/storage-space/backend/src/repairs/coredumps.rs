@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Delete stored core dumps
+//!
+//! The crash records stay in the journal, so `coredumpctl list` still
+//! shows what crashed; only the dump files go.
+
+use crate::diagnostics::coredumps;
+use crate::report::RepairOutcome;
+use crate::scan::human_bytes;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let dumps = coredumps::dumps();
+    if dumps.is_empty() {
+        return RepairOutcome::not_needed("No core dumps stored, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    let total: u64 = dumps.iter().map(|d| d.bytes).sum();
+    let question = format!(
+        "Delete {} core dumps, freeing {}?",
+        dumps.len(),
+        human_bytes(total)
+    );
+    if !confirm(&question) {
+        result.errors.push(format!(
+            "Core dumps not confirmed, {} left in place (rerun with --yes to confirm)",
+            human_bytes(total)
+        ));
+        return result;
+    }
+    for dump in dumps {
+        match std::fs::remove_file(&dump.path) {
+            Ok(()) => result.actions.push(format!(
+                "Removed {} ({})",
+                dump.path,
+                human_bytes(dump.bytes)
+            )),
+            Err(e) => result.errors.push(format!("{}: {}", dump.path, e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Prune old container images
+//!
+//! Removes images no container uses that were created more than
+//! `IMAGE_AGE` ago. Anything removed can be pulled or built again.
+
+use crate::diagnostics::containers::{self, ENGINES, IMAGE_AGE};
+use crate::report::RepairOutcome;
+use crate::scan::human_bytes;
+use crate::system;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let engines: Vec<_> = ENGINES
+        .iter()
+        .filter(|e| system::has(e))
+        .filter_map(|e| containers::engine(e).ok())
+        .filter(|e| e.unused_bytes > 0)
+        .collect();
+    if engines.is_empty() {
+        return RepairOutcome::not_needed("No unused container images, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    let filter = format!("until={}", IMAGE_AGE);
+    for engine in engines {
+        let question = format!(
+            "Remove unused {} images older than 30 days (up to {})?",
+            engine.name,
+            human_bytes(engine.unused_bytes)
+        );
+        if !confirm(&question) {
+            result.errors.push(format!(
+                "{}: not confirmed, images left in place (rerun with --yes to confirm)",
+                engine.name
+            ));
+            continue;
+        }
+        let prune = system::run(
+            &engine.name,
+            &["image", "prune", "--all", "--force", "--filter", &filter],
+        );
+        match prune {
+            Ok(_) => {
+                let after = containers::engine(&engine.name)
+                    .map(|e| e.images_bytes)
+                    .unwrap_or(engine.images_bytes);
+                result.actions.push(format!(
+                    "Pruned {} images: freed {}",
+                    engine.name,
+                    human_bytes(engine.images_bytes.saturating_sub(after))
+                ));
+            }
+            Err(e) => result.errors.push(e),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
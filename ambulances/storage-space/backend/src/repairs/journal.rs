@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Vacuum the journal
+//!
+//! Archived files are deleted oldest first through the shim until the
+//! journal fits in `TARGET_BYTES`, as `journalctl --vacuum-size` would.
+
+use crate::diagnostics::journal::{self, TARGET_BYTES};
+use crate::report::RepairOutcome;
+use crate::scan::human_bytes;
+use systemd_shim::journal as sd_journal;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let usage = match journal::usage() {
+        Ok(usage) => usage,
+        Err(e) => {
+            return RepairOutcome {
+                errors: vec![format!("Could not open the journal: {}", e)],
+                ..RepairOutcome::default()
+            }
+        }
+    };
+    let plan = journal::vacuum_plan(usage);
+    if plan.is_empty() {
+        return RepairOutcome::not_needed(&format!(
+            "The journal uses {}, no repair needed",
+            human_bytes(usage)
+        ));
+    }
+
+    let mut result = RepairOutcome::default();
+    let freed: u64 = plan.iter().map(|f| f.bytes).sum();
+    let question = format!(
+        "Delete the {} oldest archived journal files, freeing {}?",
+        plan.len(),
+        human_bytes(freed)
+    );
+    if !confirm(&question) {
+        result.errors.push(format!(
+            "Journal vacuum not confirmed, {} left in place (rerun with --yes to confirm)",
+            human_bytes(freed)
+        ));
+        return result;
+    }
+    match sd_journal::vacuum(TARGET_BYTES) {
+        Ok(vacuum) => {
+            for file in &vacuum.removed {
+                result.actions.push(format!(
+                    "Removed {} ({})",
+                    file.path.display(),
+                    human_bytes(file.bytes)
+                ));
+            }
+            result.actions.push(format!(
+                "Freed {}",
+                human_bytes(vacuum.removed.iter().map(|f| f.bytes).sum())
+            ));
+            for (file, e) in &vacuum.failed {
+                result
+                    .errors
+                    .push(format!("Could not remove {}: {}", file.path.display(), e));
+            }
+        }
+        Err(e) => result.errors.push(format!("Vacuum failed: {}", e)),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Cleanup repairs, one module per target
+//!
+//! Each target works out what it would remove and how much that frees,
+//! and asks before removing anything.
+
+pub mod caches;
+pub mod coredumps;
+pub mod images;
+pub mod journal;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["journal", "caches", "images", "coredumps", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each removal, with what it frees.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        journal_repair: if selected("journal") {
+            journal::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        caches_repair: if selected("caches") {
+            caches::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        images_repair: if selected("images") {
+            images::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        coredumps_repair: if selected("coredumps") {
+            coredumps::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
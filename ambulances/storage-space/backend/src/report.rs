@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Report model shared with the network ambulance
+//!
+//! `diagnose --json` emits `{version, tool, <section>...}` where every
+//! section carries `warnings` and `recommendations`; `repair --json` emits
+//! `{version, tool, <target>_repair...}` where each carries `success`,
+//! `actions` and `errors`.
+
+use crate::diagnostics::{
+    caches::CacheDiagnostics, containers::ContainerDiagnostics, coredumps::CoredumpDiagnostics,
+    directories::DirectoryDiagnostics, journal::JournalDiagnostics,
+};
+use ambulance_core::Timing;
+use serde::Serialize;
+
+pub use ambulance_core::wire::{mark, print_notes, RepairOutcome};
+
+pub const TOOL: &str = "storage-space-ambulance";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Serialize)]
+pub struct DiagnosticResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    /// What every cleanup repair together would free
+    pub reclaimable_bytes: u64,
+    pub directories: DirectoryDiagnostics,
+    pub journal: JournalDiagnostics,
+    pub caches: CacheDiagnostics,
+    pub containers: ContainerDiagnostics,
+    pub coredumps: CoredumpDiagnostics,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timings: Vec<Timing>,
+}
+
+impl DiagnosticResult {
+    /// Whether any section warns
+    pub fn has_warnings(&self) -> bool {
+        [
+            &self.directories.warnings,
+            &self.journal.warnings,
+            &self.caches.warnings,
+            &self.containers.warnings,
+            &self.coredumps.warnings,
+        ]
+        .iter()
+        .any(|w| !w.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepairResult {
+    pub version: &'static str,
+    pub tool: &'static str,
+    pub journal_repair: RepairOutcome,
+    pub caches_repair: RepairOutcome,
+    pub images_repair: RepairOutcome,
+    pub coredumps_repair: RepairOutcome,
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Local telemetry, off until the user turns it on
+//!
+//! With telemetry on, each `doctor all` adds its outcome to a store in
+//! the user's state directory: per day and per check, how often it ran,
+//! how often it failed, its worst severity, and a summary of the numbers
+//! in its details. Nothing else is kept, no summaries, hostnames or
+//! paths, and nothing leaves the machine; [`Store::export`] prints an
+//! anonymized copy for the user to share if they choose.
+//!
+//! [`trends`] reads the store back into findings about checks that got
+//! worse over the last two weeks, such as Wi-Fi failing most days after
+//! a week without trouble.
+
+pub mod store;
+pub mod trends;
+
+pub use store::{Stats, Store, Summary};
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Settings every user on the machine starts from
+pub const SYSTEM_SETTINGS: &str = "/etc/system-tools/telemetry.toml";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Days kept before the oldest are dropped
+    #[serde(default = "default_retention")]
+    pub retention_days: u64,
+}
+
+fn default_retention() -> u64 {
+    90
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            enabled: false,
+            retention_days: default_retention(),
+        }
+    }
+}
+
+/// `$XDG_<var>`, else `~/<fallback>`, with `system-tools` appended
+fn user_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let base = std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(fallback)))?;
+    Some(base.join("system-tools"))
+}
+
+impl Settings {
+    /// The user's own settings file
+    pub fn path() -> Option<PathBuf> {
+        user_dir("XDG_CONFIG_HOME", ".config").map(|d| d.join("telemetry.toml"))
+    }
+
+    /// The user's settings, else the machine's, else off
+    pub fn load() -> Settings {
+        Settings::path()
+            .into_iter()
+            .chain(std::iter::once(PathBuf::from(SYSTEM_SETTINGS)))
+            .find_map(|p| std::fs::read_to_string(p).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Settings::path().ok_or("No home directory to keep settings in")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let text = toml::to_string(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, text)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Where the user's store lives
+pub fn store_path() -> Option<PathBuf> {
+    user_dir("XDG_STATE_HOME", ".local/state").map(|d| d.join("telemetry.json"))
+}
+
+/// Days since the epoch, the store's unit of time
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400)
+}
+
+/// `day` as `YYYY-MM-DD`
+pub fn date(day: u64) -> String {
+    // Howard Hinnant's civil_from_days, for days after 1970
+    let z = day as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
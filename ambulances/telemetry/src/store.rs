@@ -0,0 +1,226 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Daily aggregates of check outcomes
+//!
+//! A report is folded in as soon as it is recorded, so the store grows
+//! by day and check, not by run. Checks are keyed `<tool>.<check>` with
+//! the short tool name, and each tool also gets a key of its own that
+//! holds how long it took, as the metric `duration_ms`.
+
+use ambulance_core::schema::short_name;
+use ambulance_core::{CheckStatus, DiagnosticReport, Severity};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Newest store format this reader understands
+pub const FORMAT: u32 = 1;
+
+/// Count, sum and range of a number seen over a day
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub count: u32,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    fn new(value: f64) -> Summary {
+        Summary {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.sum / f64::from(self.count.max(1))
+    }
+}
+
+/// One check, or one tool, over one day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub runs: u32,
+    pub failed: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worst: Option<Severity>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, Summary>,
+}
+
+impl Stats {
+    fn metric(&mut self, name: &str, value: f64) {
+        match self.metrics.get_mut(name) {
+            Some(summary) => summary.add(value),
+            None => {
+                self.metrics.insert(name.to_string(), Summary::new(value));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Store {
+    pub format: u32,
+    /// Days since the epoch, then `<tool>.<check>` or `<tool>`
+    pub days: BTreeMap<u64, BTreeMap<String, Stats>>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store {
+            format: FORMAT,
+            days: BTreeMap::new(),
+        }
+    }
+}
+
+impl Store {
+    /// The store at `path`; an empty one when there is none yet
+    pub fn load(path: &Path) -> Result<Store, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Store::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let store: Store = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        if store.format > FORMAT {
+            return Err(format!(
+                "{} uses store format {}, this reader knows up to {}",
+                path.display(),
+                store.format,
+                FORMAT
+            ));
+        }
+        Ok(store)
+    }
+
+    /// Write the store, readable by its owner only
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, text)
+            .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600));
+        }
+        std::fs::rename(&tmp, path)
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+    }
+
+    /// Fold `report` into `day`; `elapsed` is how long its tool took
+    pub fn record(&mut self, day: u64, report: &DiagnosticReport, elapsed: Option<Duration>) {
+        let tool = short_name(&report.tool);
+        let stats = self.days.entry(day).or_default();
+        for check in &report.checks {
+            let entry = stats.entry(format!("{}.{}", tool, check.id)).or_default();
+            entry.runs += 1;
+            if check.status == CheckStatus::Failed {
+                entry.failed += 1;
+            }
+            if let Some(worst) = check.findings.iter().map(|f| f.severity).max() {
+                entry.worst = entry.worst.max(Some(worst));
+            }
+            // Only top-level numbers: they are measurements, not names
+            for (name, value) in &check.details {
+                if let Some(n) = value.as_f64().filter(|n| n.is_finite()) {
+                    entry.metric(name, n);
+                }
+            }
+        }
+        let entry = stats.entry(tool.to_string()).or_default();
+        entry.runs += 1;
+        if let Some(elapsed) = elapsed {
+            entry.metric("duration_ms", elapsed.as_secs_f64() * 1000.0);
+        }
+    }
+
+    /// Drop days older than `keep` days before `today`
+    pub fn prune(&mut self, today: u64, keep: u64) {
+        self.days = self
+            .days
+            .split_off(&today.saturating_sub(keep.saturating_sub(1)));
+    }
+
+    /// Every key ever recorded
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .days
+            .values()
+            .flat_map(|d| d.keys().map(String::as_str))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys
+    }
+
+    /// A copy with nothing that places the data: days count back from
+    /// the newest, which is day 0, and numbers keep three significant
+    /// digits
+    pub fn export(&self) -> Value {
+        let newest = self.days.keys().next_back().copied().unwrap_or(0);
+        let days: Vec<Value> = self
+            .days
+            .iter()
+            .map(|(day, stats)| {
+                let checks: BTreeMap<&str, Value> = stats
+                    .iter()
+                    .map(|(key, s)| {
+                        let metrics: BTreeMap<&str, Value> = s
+                            .metrics
+                            .iter()
+                            .map(|(name, m)| {
+                                (
+                                    name.as_str(),
+                                    json!({
+                                        "count": m.count,
+                                        "mean": round(m.mean()),
+                                        "min": round(m.min),
+                                        "max": round(m.max),
+                                    }),
+                                )
+                            })
+                            .collect();
+                        let mut value = json!({"runs": s.runs, "failed": s.failed});
+                        if let Some(worst) = s.worst {
+                            value["worst"] = json!(worst);
+                        }
+                        if !metrics.is_empty() {
+                            value["metrics"] = json!(metrics);
+                        }
+                        (key.as_str(), value)
+                    })
+                    .collect();
+                json!({"day": -((newest - day) as i64), "checks": checks})
+            })
+            .collect();
+        json!({"format": self.format, "days": days})
+    }
+}
+
+/// `n` to three significant digits
+fn round(n: f64) -> f64 {
+    if n == 0.0 {
+        return 0.0;
+    }
+    let scale = 10f64.powi(2 - n.abs().log10().floor() as i32);
+    (n * scale).round() / scale
+}
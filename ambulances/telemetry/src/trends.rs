@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Findings about checks that got worse
+//!
+//! The last [`WINDOW_DAYS`] days are split into this week and the week
+//! before. A check is flagged when it failed on noticeably more of the
+//! days it ran this week; a number in its details, or how long its tool
+//! took, when the average moved by half or more. Both weeks need
+//! [`MIN_DAYS`] days of data, so a fresh store reports nothing.
+
+use crate::store::{Stats, Store};
+use ambulance_core::{
+    Check, CheckStatus, Confidence, DiagnosticReport, Finding, Recommendation, Severity,
+    SCHEMA_VERSION,
+};
+use serde_json::{json, Map};
+
+pub const TOOL: &str = "telemetry";
+
+/// Days looked back over, split into two halves
+pub const WINDOW_DAYS: u64 = 14;
+
+/// Days of data each half needs before it is compared
+pub const MIN_DAYS: usize = 3;
+
+/// Rise in the share of failing days that is worth a finding
+const FAILURE_RISE: f64 = 0.3;
+
+/// Relative change in an average that is worth a finding
+const DRIFT: f64 = 0.5;
+
+/// One key's stats in each half, oldest day first
+struct Halves<'a> {
+    earlier: Vec<&'a Stats>,
+    recent: Vec<&'a Stats>,
+}
+
+impl<'a> Halves<'a> {
+    fn of(store: &'a Store, key: &str, today: u64) -> Halves<'a> {
+        let split = today.saturating_sub(WINDOW_DAYS / 2 - 1);
+        let start = today.saturating_sub(WINDOW_DAYS - 1);
+        let mut halves = Halves {
+            earlier: Vec::new(),
+            recent: Vec::new(),
+        };
+        for (day, stats) in store.days.range(start..=today) {
+            if let Some(s) = stats.get(key).filter(|s| s.runs > 0) {
+                if *day >= split {
+                    halves.recent.push(s);
+                } else {
+                    halves.earlier.push(s);
+                }
+            }
+        }
+        halves
+    }
+
+    fn enough(&self) -> bool {
+        self.earlier.len() >= MIN_DAYS && self.recent.len() >= MIN_DAYS
+    }
+}
+
+fn failing_days(days: &[&Stats]) -> usize {
+    days.iter().filter(|s| s.failed > 0).count()
+}
+
+/// Mean of the daily means of `metric`, over the days that have it
+fn average(days: &[&Stats], metric: &str) -> Option<f64> {
+    let means: Vec<f64> = days
+        .iter()
+        .filter_map(|s| s.metrics.get(metric))
+        .map(|m| m.mean())
+        .collect();
+    (means.len() >= MIN_DAYS).then(|| means.iter().sum::<f64>() / means.len() as f64)
+}
+
+fn drifted(before: f64, now: f64) -> bool {
+    before != 0.0 && ((now - before) / before).abs() >= DRIFT
+}
+
+fn finding(severity: Severity, summary: String, remedy: Option<String>) -> Finding {
+    let recommendation = remedy.map(|r| Recommendation::from_text(&r));
+    Finding {
+        id: String::new(),
+        severity,
+        confidence: Confidence::Medium,
+        summary,
+        evidence: Vec::new(),
+        repairs: recommendation
+            .as_ref()
+            .map(|r| r.repairs.clone())
+            .unwrap_or_default(),
+        recommendation,
+    }
+}
+
+fn failures(key: &str, halves: &Halves) -> Option<Finding> {
+    let (was, now) = (failing_days(&halves.earlier), failing_days(&halves.recent));
+    let rate = |failed: usize, days: &[&Stats]| failed as f64 / days.len() as f64;
+    if now < MIN_DAYS || rate(now, &halves.recent) - rate(was, &halves.earlier) < FAILURE_RISE {
+        return None;
+    }
+    let tool = key.split('.').next().unwrap_or(key);
+    Some(finding(
+        Severity::Warn,
+        format!(
+            "{} failed on {} of the last {} days it ran, against {} of {} in the week before",
+            key,
+            now,
+            halves.recent.len(),
+            was,
+            halves.earlier.len()
+        ),
+        Some(format!(
+            "Run '{}-ambulance diagnose' to see what changed",
+            tool
+        )),
+    ))
+}
+
+fn drift(key: &str, halves: &Halves, findings: &mut Vec<Finding>) {
+    let mut metrics: Vec<&str> = halves
+        .recent
+        .iter()
+        .flat_map(|s| s.metrics.keys().map(String::as_str))
+        .collect();
+    metrics.sort_unstable();
+    metrics.dedup();
+    for metric in metrics {
+        let (Some(before), Some(now)) = (
+            average(&halves.earlier, metric),
+            average(&halves.recent, metric),
+        ) else {
+            continue;
+        };
+        if !drifted(before, now) {
+            continue;
+        }
+        let summary = if metric == "duration_ms" {
+            // Only slowing down is news
+            if now < before {
+                continue;
+            }
+            format!(
+                "{} took {:.0} ms on average this week, against {:.0} ms the week before",
+                key, now, before
+            )
+        } else {
+            format!(
+                "{} {} averaged {} this week, against {} the week before",
+                key,
+                metric,
+                round(now),
+                round(before)
+            )
+        };
+        findings.push(finding(Severity::Info, summary, None));
+    }
+}
+
+fn round(n: f64) -> String {
+    if n.fract() == 0.0 {
+        format!("{:.0}", n)
+    } else {
+        format!("{:.2}", n)
+    }
+}
+
+/// The trend findings for `today`, as a report of their own
+pub fn report(store: &Store, today: u64) -> DiagnosticReport {
+    let mut check = Check {
+        id: "trends".to_string(),
+        status: CheckStatus::Passed,
+        findings: Vec::new(),
+        recommendations: Vec::new(),
+        details: Map::new(),
+    };
+    let mut compared = 0;
+    for key in store.keys() {
+        let halves = Halves::of(store, key, today);
+        if !halves.enough() {
+            continue;
+        }
+        compared += 1;
+        if key.contains('.') {
+            check.findings.extend(failures(key, &halves));
+        }
+        drift(key, &halves, &mut check.findings);
+    }
+    for (i, f) in check.findings.iter_mut().enumerate() {
+        f.id = format!("{}.{}.{}", TOOL, check.id, i);
+    }
+    if check.findings.iter().any(|f| f.severity > Severity::Info) {
+        check.status = CheckStatus::Failed;
+    }
+    let days = store
+        .days
+        .range(today.saturating_sub(WINDOW_DAYS - 1)..=today)
+        .count();
+    let details = &mut check.details;
+    details.insert("window_days".to_string(), json!(WINDOW_DAYS));
+    details.insert("days_recorded".to_string(), json!(days));
+    details.insert("compared".to_string(), json!(compared));
+    DiagnosticReport {
+        schema: SCHEMA_VERSION,
+        tool: TOOL.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        checks: vec![check],
+        timings: Vec::new(),
+    }
+}
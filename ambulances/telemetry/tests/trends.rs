@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Recording reports, reading trends back and exporting the store
+
+use ambulance_core::{CheckStatus, DiagnosticReport, Severity};
+use ambulance_telemetry::{trends, Store};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const TODAY: u64 = 20_000;
+
+fn wifi(failing: bool, signal: i64) -> DiagnosticReport {
+    let warnings: Vec<&str> = if failing {
+        vec!["Wi-Fi dropped 4 times in the last hour"]
+    } else {
+        vec![]
+    };
+    DiagnosticReport::from_wire(&json!({
+        "version": "0.1.0",
+        "tool": "network-ambulance",
+        "wifi": {
+            "interface": "wlan0",
+            "signal_dbm": signal,
+            "warnings": warnings,
+            "recommendations": [],
+        },
+    }))
+    .unwrap()
+}
+
+/// Fourteen days ending today; `failing(i)` for day `i`, 0 the oldest
+fn fortnight(failing: impl Fn(u64) -> bool, signal: impl Fn(u64) -> i64) -> Store {
+    let mut store = Store::default();
+    for i in 0..14 {
+        let day = TODAY - 13 + i;
+        store.record(
+            day,
+            &wifi(failing(i), signal(i)),
+            Some(Duration::from_millis(800)),
+        );
+    }
+    store
+}
+
+#[test]
+fn records_runs_failures_and_numeric_details() {
+    let mut store = Store::default();
+    store.record(TODAY, &wifi(true, -70), Some(Duration::from_millis(500)));
+    store.record(TODAY, &wifi(false, -50), Some(Duration::from_millis(700)));
+
+    let day = &store.days[&TODAY];
+    let check = &day["network.wifi"];
+    assert_eq!((check.runs, check.failed), (2, 1));
+    assert_eq!(check.worst, Some(Severity::Warn));
+    assert_eq!(check.metrics["signal_dbm"].mean(), -60.0);
+    assert!(!check.metrics.contains_key("interface"));
+    assert_eq!(day["network"].metrics["duration_ms"].mean(), 600.0);
+
+    store.prune(TODAY + 10, 10);
+    assert_eq!(store.days.len(), 0);
+}
+
+#[test]
+fn failing_most_of_this_week_is_a_trend() {
+    let store = fortnight(|i| i >= 9, |_| -60);
+    let report = trends::report(&store, TODAY);
+    let check = &report.checks[0];
+
+    assert_eq!(check.status, CheckStatus::Failed);
+    assert_eq!(check.findings.len(), 1);
+    let finding = &check.findings[0];
+    assert_eq!(finding.id, "telemetry.trends.0");
+    assert_eq!(
+        finding.summary,
+        "network.wifi failed on 5 of the last 7 days it ran, against 0 of 7 in the week before"
+    );
+}
+
+#[test]
+fn steady_checks_and_short_histories_report_nothing() {
+    let steady = fortnight(|i| i % 2 == 0, |_| -60);
+    assert!(trends::report(&steady, TODAY).checks[0].findings.is_empty());
+
+    let mut short = Store::default();
+    for day in TODAY - 2..=TODAY {
+        short.record(day, &wifi(true, -60), None);
+    }
+    assert!(trends::report(&short, TODAY).checks[0].findings.is_empty());
+}
+
+#[test]
+fn numbers_that_moved_by_half_are_noted() {
+    let store = fortnight(|_| false, |i| if i < 7 { -40 } else { -80 });
+    let findings = &trends::report(&store, TODAY).checks[0].findings;
+
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].severity, Severity::Info);
+    assert!(findings[0].summary.contains("signal_dbm averaged -80"));
+}
+
+#[test]
+fn export_counts_days_back_and_drops_identifying_details() {
+    let store = fortnight(|i| i == 13, |_| -61);
+    let export = store.export();
+    let text = export.to_string();
+
+    assert!(!text.contains("wlan0"));
+    assert!(!text.contains(&TODAY.to_string()));
+    let days = export["days"].as_array().unwrap();
+    assert_eq!(days.first().unwrap()["day"], json!(-13));
+    let last = days.last().unwrap();
+    assert_eq!(last["day"], json!(0));
+    assert_eq!(last["checks"]["network.wifi"]["failed"], json!(1));
+    assert_eq!(
+        last["checks"]["network.wifi"]["metrics"]["signal_dbm"]["mean"],
+        Value::from(-61.0)
+    );
+}
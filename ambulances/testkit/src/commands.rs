@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Scripted programs
+//!
+//! Each scripted program is a shell script in the system's `bin`
+//! directory, which is all of `PATH` when a backend runs: a program
+//! nobody scripted is missing, as it would be on a minimal install. The
+//! script compares its arguments with each [`Script`] in turn, prints
+//! the first match's output and exits with its status; with no match it
+//! fails with 127. Every run is logged for [`crate::System::calls`].
+
+use std::path::Path;
+
+/// What one program prints, for some arguments or for any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    /// `None` matches any arguments
+    pub args: Option<Vec<String>>,
+    pub stdout: String,
+    pub stderr: String,
+    pub status: i32,
+}
+
+impl Script {
+    /// Print `stdout` and succeed
+    pub fn prints(stdout: &str) -> Script {
+        Script {
+            args: None,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+            status: 0,
+        }
+    }
+
+    /// Print `stderr` and exit with `status`
+    pub fn fails(status: i32, stderr: &str) -> Script {
+        Script {
+            args: None,
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+            status,
+        }
+    }
+
+    /// Only when called with exactly `args`
+    pub fn when(mut self, args: &[&str]) -> Script {
+        self.args = Some(args.iter().map(|a| a.to_string()).collect());
+        self
+    }
+}
+
+/// Separates logged arguments, which may hold spaces
+pub(crate) const SEPARATOR: char = '\u{1f}';
+
+/// `s` as one single-quoted shell word
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// The shell script for `program`, its outputs in files under `data`
+pub(crate) fn write(
+    bin: &Path,
+    data: &Path,
+    program: &str,
+    scripts: &[Script],
+    log: &Path,
+) -> std::io::Result<()> {
+    // The script's own tools come from the real system
+    let mut sh = String::from("#!/bin/sh\nPATH=/usr/bin:/bin\n");
+    sh.push_str(&format!(
+        "{{ printf '%s' {}; for a in \"$@\"; do printf '\\037%s' \"$a\"; done; printf '\\n'; }} >> {}\n",
+        quote(program),
+        quote(&log.display().to_string())
+    ));
+    // Arguments joined the same way, to compare as one word
+    sh.push_str(&format!(
+        "args=''\nfor a in \"$@\"; do args=\"$args{}$a\"; done\n",
+        SEPARATOR
+    ));
+    sh.push_str("case \"$args\" in\n");
+    for (i, script) in scripts.iter().enumerate() {
+        let out = data.join(format!("{}.{}.out", program, i));
+        let err = data.join(format!("{}.{}.err", program, i));
+        std::fs::write(&out, &script.stdout)?;
+        std::fs::write(&err, &script.stderr)?;
+        let pattern = match &script.args {
+            Some(args) => quote(
+                &args
+                    .iter()
+                    .map(|a| format!("{}{}", SEPARATOR, a))
+                    .collect::<String>(),
+            ),
+            None => "*".to_string(),
+        };
+        sh.push_str(&format!(
+            "  {}) cat {}; cat {} >&2; exit {} ;;\n",
+            pattern,
+            quote(&out.display().to_string()),
+            quote(&err.display().to_string()),
+            script.status
+        ));
+    }
+    sh.push_str(&format!(
+        "  *) echo {}\": not scripted for: $*\" >&2; exit 127 ;;\nesac\n",
+        quote(program)
+    ));
+
+    let path = bin.join(program);
+    std::fs::write(&path, sh)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
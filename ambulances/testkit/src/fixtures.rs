@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Common procfs and sysfs files, in the kernel's own formats
+//!
+//! Each writes one file, or one device's directory, the way a running
+//! kernel lays it out, so fixtures read like the machine they describe
+//! rather than a pile of paths. Anything not covered here can still be
+//! written with [`System::file`].
+
+use crate::system::System;
+
+impl System {
+    /// `/proc/meminfo` with `total_mb` of RAM, `available_mb` of it
+    /// available, and no swap
+    pub fn meminfo(&mut self, total_mb: u64, available_mb: u64) -> &mut System {
+        self.meminfo_with_swap(total_mb, available_mb, 0, 0)
+    }
+
+    /// `/proc/meminfo` with swap as well
+    pub fn meminfo_with_swap(
+        &mut self,
+        total_mb: u64,
+        available_mb: u64,
+        swap_total_mb: u64,
+        swap_free_mb: u64,
+    ) -> &mut System {
+        let free_mb = available_mb / 2;
+        let lines = [
+            ("MemTotal", total_mb),
+            ("MemFree", free_mb),
+            ("MemAvailable", available_mb),
+            ("Buffers", 0),
+            ("Cached", available_mb - free_mb),
+            ("SwapTotal", swap_total_mb),
+            ("SwapFree", swap_free_mb),
+        ];
+        let text: String = lines
+            .iter()
+            .map(|(key, mb)| format!("{:<16}{:>8} kB\n", format!("{}:", key), mb * 1024))
+            .collect();
+        self.file("/proc/meminfo", &text)
+    }
+
+    /// One line of `/proc/swaps`; sizes in MiB
+    pub fn swap(&mut self, name: &str, size_mb: u64, used_mb: u64, priority: i32) -> &mut System {
+        let path = self.path("/proc/swaps");
+        let mut text = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n".to_string());
+        let kind = if name.starts_with("/dev/") {
+            "partition"
+        } else {
+            "file"
+        };
+        text.push_str(&format!(
+            "{:<40}{}\t{}\t\t{}\t\t{}\n",
+            name,
+            kind,
+            size_mb * 1024,
+            used_mb * 1024,
+            priority
+        ));
+        self.file("/proc/swaps", &text)
+    }
+
+    /// `/proc/pressure/memory` with these `some` and `full` avg60
+    /// percentages; avg10 and avg300 are the same
+    pub fn memory_pressure(&mut self, some: f64, full: f64) -> &mut System {
+        self.file("/proc/pressure/memory", &psi(some, full))
+    }
+
+    /// `/proc/loadavg`
+    pub fn loadavg(&mut self, one: f64, five: f64, fifteen: f64) -> &mut System {
+        self.file(
+            "/proc/loadavg",
+            &format!("{:.2} {:.2} {:.2} 1/512 4242\n", one, five, fifteen),
+        )
+    }
+
+    /// `/proc/cmdline`
+    pub fn cmdline(&mut self, cmdline: &str) -> &mut System {
+        self.file("/proc/cmdline", &format!("{}\n", cmdline))
+    }
+
+    /// `/proc/sys/kernel/tainted`
+    pub fn tainted(&mut self, value: u64) -> &mut System {
+        self.file("/proc/sys/kernel/tainted", &format!("{}\n", value))
+    }
+
+    /// A loaded module under `/sys/module`, with its taint letters (empty
+    /// for none)
+    pub fn module(&mut self, name: &str, taint: &str) -> &mut System {
+        self.file(
+            &format!("/sys/module/{}/taint", name),
+            &format!("{}\n", taint),
+        )
+    }
+
+    /// `/sys/class/hwmon/hwmon<index>` named `name`, with one temperature
+    /// sensor and its critical limit, in degrees
+    pub fn hwmon(&mut self, index: u32, name: &str, celsius: f64, crit: f64) -> &mut System {
+        let dir = format!("/sys/class/hwmon/hwmon{}", index);
+        self.file(&format!("{}/name", dir), &format!("{}\n", name))
+            .file(&format!("{}/temp1_input", dir), &millidegrees(celsius))
+            .file(&format!("{}/temp1_crit", dir), &millidegrees(crit))
+    }
+
+    /// `/sys/class/thermal/thermal_zone<index>` of type `kind`, with a
+    /// critical trip point
+    pub fn thermal_zone(&mut self, index: u32, kind: &str, celsius: f64, crit: f64) -> &mut System {
+        let dir = format!("/sys/class/thermal/thermal_zone{}", index);
+        self.file(&format!("{}/type", dir), &format!("{}\n", kind))
+            .file(&format!("{}/temp", dir), &millidegrees(celsius))
+            .file(&format!("{}/trip_point_0_type", dir), "critical\n")
+            .file(&format!("{}/trip_point_0_temp", dir), &millidegrees(crit))
+    }
+
+    /// `/sys/class/power_supply/<name>`, a system battery holding
+    /// `health_percent` of its design capacity, `capacity` percent charged
+    pub fn battery(
+        &mut self,
+        name: &str,
+        health_percent: u64,
+        capacity: u64,
+        status: &str,
+    ) -> &mut System {
+        let dir = format!("/sys/class/power_supply/{}", name);
+        let design: u64 = 50_000_000;
+        self.file(&format!("{}/type", dir), "Battery\n")
+            .file(&format!("{}/status", dir), &format!("{}\n", status))
+            .file(&format!("{}/capacity", dir), &format!("{}\n", capacity))
+            .file(
+                &format!("{}/energy_full_design", dir),
+                &format!("{}\n", design),
+            )
+            .file(
+                &format!("{}/energy_full", dir),
+                &format!("{}\n", design / 100 * health_percent),
+            )
+    }
+
+    /// A mains adapter under `/sys/class/power_supply`
+    pub fn mains(&mut self, name: &str, online: bool) -> &mut System {
+        let dir = format!("/sys/class/power_supply/{}", name);
+        self.file(&format!("{}/type", dir), "Mains\n").file(
+            &format!("{}/online", dir),
+            if online { "1\n" } else { "0\n" },
+        )
+    }
+
+    /// `/sys/block/<name>` of `size_mb`, rotational or not
+    pub fn block_device(&mut self, name: &str, size_mb: u64, rotational: bool) -> &mut System {
+        let dir = format!("/sys/block/{}", name);
+        self.file(&format!("{}/size", dir), &format!("{}\n", size_mb * 2048))
+            .file(
+                &format!("{}/queue/rotational", dir),
+                if rotational { "1\n" } else { "0\n" },
+            )
+    }
+}
+
+fn millidegrees(celsius: f64) -> String {
+    format!("{}\n", (celsius * 1000.0).round() as i64)
+}
+
+/// A PSI file with the same percentage over every window
+fn psi(some: f64, full: f64) -> String {
+    let line = |kind: &str, pct: f64| {
+        format!(
+            "{} avg10={:.2} avg60={:.2} avg300={:.2} total={}\n",
+            kind,
+            pct,
+            pct,
+            pct,
+            (pct * 1_000_000.0) as u64
+        )
+    };
+    format!("{}{}", line("some", some), line("full", full))
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Journal entries for the mock journal
+//!
+//! Entries are written as `journalctl -o json` prints them, one object
+//! per line, which is what the shim reads when its mock journal is on.
+
+use serde_json::{Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The fake system's boot id; entries are from this boot unless they
+/// say otherwise
+pub const BOOT_ID: &str = "5e1f0c2ad3b84a6f9c7e0b1d2a3c4f56";
+
+/// A boot before this one
+const PREVIOUS_BOOT_ID: &str = "0b9d8c7a6f5e4d3c2b1a09f8e7d6c5b4";
+
+/// One journal entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    fields: Map<String, Value>,
+    /// Microseconds before now
+    ago: u64,
+}
+
+impl Entry {
+    /// An informational message from this boot, logged just now
+    pub fn new(message: &str) -> Entry {
+        Entry {
+            fields: Map::new(),
+            ago: 0,
+        }
+        .field("MESSAGE", message)
+        .priority(6)
+        .field("_BOOT_ID", BOOT_ID)
+    }
+
+    pub fn field(mut self, name: &str, value: &str) -> Entry {
+        self.fields
+            .insert(name.to_string(), Value::String(value.to_string()));
+        self
+    }
+
+    /// syslog priority, 0 (emerg) to 7 (debug)
+    pub fn priority(self, priority: u8) -> Entry {
+        self.field("PRIORITY", &priority.to_string())
+    }
+
+    /// Logged by a systemd unit
+    pub fn unit(self, unit: &str) -> Entry {
+        self.field("_SYSTEMD_UNIT", unit)
+    }
+
+    /// Logged by the kernel
+    pub fn kernel(self) -> Entry {
+        self.field("_TRANSPORT", "kernel")
+            .field("SYSLOG_IDENTIFIER", "kernel")
+    }
+
+    /// Logged `secs` seconds ago
+    pub fn ago(mut self, secs: u64) -> Entry {
+        self.ago = secs * 1_000_000;
+        self
+    }
+
+    /// Logged during the boot before this one
+    pub fn previous_boot(self) -> Entry {
+        self.field("_BOOT_ID", PREVIOUS_BOOT_ID)
+    }
+
+    /// The entry as one line of `journalctl -o json`
+    pub(crate) fn to_line(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let mut fields = self.fields.clone();
+        fields.insert(
+            "__REALTIME_TIMESTAMP".to_string(),
+            Value::String(now.saturating_sub(self.ago).to_string()),
+        );
+        Value::Object(fields).to_string()
+    }
+}
+
+/// `id` in the dashed form `/proc/sys/kernel/random/boot_id` uses
+pub(crate) fn dashed(id: &str) -> String {
+    format!(
+        "{}-{}-{}-{}-{}",
+        &id[..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..]
+    )
+}
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Fake systems for testing the ambulances
+//!
+//! A [`System`] is a broken machine built for one test: a directory
+//! standing in for `/`, which backends read `/proc` and `/sys` from
+//! through `ambulance_core::host`; programs on `PATH` that print what a
+//! [`Script`] says; a journal of [`Entry`] values, read through the
+//! shim's mock journal; and, when asked for, the shim's mock system bus.
+//! [`System::diagnose`] runs a backend against all of it and returns its
+//! typed report, the same on every machine and in every CI runner.
+//!
+//! ```no_run
+//! use ambulance_testkit::{Entry, Script, System};
+//!
+//! let mut system = System::new();
+//! system
+//!     .meminfo(2048, 40)
+//!     .command("zramctl", Script::prints(""))
+//!     .journal(Entry::new("Out of memory: Killed process 4242 (firefox)").kernel());
+//! let report = system.diagnose("target/debug/memory-ambulance");
+//! ```
+//!
+//! Builders panic rather than return errors: a fixture that cannot be
+//! written is a broken test, not a condition to handle.
+
+pub mod commands;
+pub mod fixtures;
+pub mod journal;
+pub mod report;
+pub mod system;
+
+pub use commands::Script;
+pub use journal::{Entry, BOOT_ID};
+pub use report::{CheckExt, ReportExt};
+pub use system::System;
+pub use systemd_shim::mock::{Arg, Bus, Call, ANY_PATH};
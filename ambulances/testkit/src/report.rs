@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Reading a backend's report in assertions
+
+use ambulance_core::{Check, DiagnosticReport};
+
+pub trait ReportExt {
+    /// The check with `id`; panics, showing the report, when there is none
+    fn check(&self, id: &str) -> &Check;
+}
+
+impl ReportExt for DiagnosticReport {
+    fn check(&self, id: &str) -> &Check {
+        self.checks
+            .iter()
+            .find(|c| c.id == id)
+            .unwrap_or_else(|| panic!("no {} check in {:?}", id, self))
+    }
+}
+
+pub trait CheckExt {
+    /// Each finding's summary, in report order
+    fn summaries(&self) -> Vec<&str>;
+
+    /// Each recommendation's text, in report order
+    fn advice(&self) -> Vec<&str>;
+}
+
+impl CheckExt for Check {
+    fn summaries(&self) -> Vec<&str> {
+        self.findings.iter().map(|f| f.summary.as_str()).collect()
+    }
+
+    fn advice(&self) -> Vec<&str> {
+        self.recommendations
+            .iter()
+            .map(|r| r.text.as_str())
+            .collect()
+    }
+}
@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The fake system a backend runs against
+
+use crate::commands::{self, Script, SEPARATOR};
+use crate::journal::{self, Entry, BOOT_ID};
+use ambulance_core::{cache, host, DiagnosticReport};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use systemd_shim::journal::MOCK_VAR;
+use systemd_shim::mock;
+
+/// A temporary directory holding one fake system, removed on drop
+///
+/// ```text
+/// root/          the fake /
+/// bin/           scripted programs, the whole of PATH
+/// data/          their output
+/// journal.json   the mock journal
+/// calls          one line per program run
+/// cache/         the backend's probe cache, so no result outlives the test
+/// ```
+pub struct System {
+    dir: PathBuf,
+    scripts: BTreeMap<String, Vec<Script>>,
+    bus: Option<mock::Bus>,
+}
+
+fn create(path: &Path) {
+    std::fs::create_dir_all(path)
+        .unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e));
+}
+
+impl System {
+    /// An empty system with only a boot id
+    pub fn new() -> System {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "ambulance-testkit-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        for sub in ["root", "bin", "data"] {
+            create(&dir.join(sub));
+        }
+        let mut system = System {
+            dir,
+            scripts: BTreeMap::new(),
+            bus: None,
+        };
+        system.file(
+            "/proc/sys/kernel/random/boot_id",
+            &format!("{}\n", journal::dashed(BOOT_ID)),
+        );
+        system.file(&system.journal_path().display().to_string(), "");
+        system.file(&system.calls_path().display().to_string(), "");
+        system
+    }
+
+    /// The directory standing in for `/`
+    pub fn root(&self) -> PathBuf {
+        self.dir.join("root")
+    }
+
+    /// Where `path`, absolute on the fake system, is on this one;
+    /// paths already inside the system's directory are kept
+    pub fn path(&self, path: &str) -> PathBuf {
+        if Path::new(path).starts_with(&self.dir) {
+            return PathBuf::from(path);
+        }
+        self.root().join(path.trim_start_matches('/'))
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join("journal.json")
+    }
+
+    fn calls_path(&self) -> PathBuf {
+        self.dir.join("calls")
+    }
+
+    /// Write `contents` to `path`, creating its directories
+    pub fn file(&mut self, path: &str, contents: &str) -> &mut System {
+        let path = self.path(path);
+        if let Some(parent) = path.parent() {
+            create(parent);
+        }
+        std::fs::write(&path, contents)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        self
+    }
+
+    /// An empty directory at `path`
+    pub fn dir(&mut self, path: &str) -> &mut System {
+        create(&self.path(path));
+        self
+    }
+
+    /// A symbolic link at `path` to `target`, which is kept as given, as
+    /// sysfs links are relative
+    pub fn link(&mut self, path: &str, target: &str) -> &mut System {
+        let path = self.path(path);
+        if let Some(parent) = path.parent() {
+            create(parent);
+        }
+        let _ = std::fs::remove_file(&path);
+        std::os::unix::fs::symlink(target, &path)
+            .unwrap_or_else(|e| panic!("Failed to link {}: {}", path.display(), e));
+        self
+    }
+
+    /// Put `program` on `PATH`, answering as `script` says; a program
+    /// given several scripts tries them in the order given
+    pub fn command(&mut self, program: &str, script: Script) -> &mut System {
+        let scripts = self.scripts.entry(program.to_string()).or_default();
+        scripts.push(script);
+        commands::write(
+            &self.dir.join("bin"),
+            &self.dir.join("data"),
+            program,
+            scripts,
+            &self.dir.join("calls"),
+        )
+        .unwrap_or_else(|e| panic!("Failed to script {}: {}", program, e));
+        self
+    }
+
+    /// Add `entry` to the journal
+    pub fn journal(&mut self, entry: Entry) -> &mut System {
+        let path = self.journal_path();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap_or_else(|e| panic!("Failed to open {}: {}", path.display(), e));
+        writeln!(file, "{}", entry.to_line())
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        self
+    }
+
+    /// The system bus, started the first time it is asked for; script it
+    /// before running a backend
+    pub fn bus(&mut self) -> &mock::Bus {
+        self.bus.get_or_insert_with(|| {
+            mock::Bus::new().unwrap_or_else(|e| panic!("Failed to start the mock bus: {}", e))
+        })
+    }
+
+    /// Every scripted program run so far: its name, then its arguments
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        std::fs::read_to_string(self.calls_path())
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.split(SEPARATOR).map(str::to_string).collect())
+            .collect()
+    }
+
+    /// `binary`, set to see only this system
+    pub fn command_for(&self, binary: impl AsRef<Path>) -> Command {
+        let mut command = Command::new(binary.as_ref());
+        // With no bus started, an address nothing listens on: a backend
+        // must cope with the bus being down
+        let bus = match &self.bus {
+            Some(bus) => bus.address(),
+            None => format!("unix:path={}", self.dir.join("no-bus").display()),
+        };
+        command
+            .env("PATH", self.dir.join("bin"))
+            .env(host::ROOT_VAR, self.root())
+            .env(MOCK_VAR, self.journal_path())
+            .env(cache::DIR_VAR, self.dir.join("cache"))
+            .env("DBUS_SYSTEM_BUS_ADDRESS", &bus)
+            .env("DBUS_SESSION_BUS_ADDRESS", &bus)
+            .env("LC_ALL", "C");
+        command
+    }
+
+    /// Run `binary` with `args` against this system
+    pub fn run(&self, binary: impl AsRef<Path>, args: &[&str]) -> Output {
+        let binary = binary.as_ref();
+        self.command_for(binary)
+            .args(args)
+            .output()
+            .unwrap_or_else(|e| panic!("Failed to run {}: {}", binary.display(), e))
+    }
+
+    /// Run a backend's `diagnose --json` and parse its report
+    pub fn diagnose(&self, binary: impl AsRef<Path>) -> DiagnosticReport {
+        let binary = binary.as_ref();
+        let output = self.run(binary, &["diagnose", "--json"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        DiagnosticReport::parse(&stdout).unwrap_or_else(|e| {
+            panic!(
+                "{} printed no report ({}): {}\nstderr: {}",
+                binary.display(),
+                e,
+                stdout,
+                String::from_utf8_lossy(&output.stderr)
+            )
+        })
+    }
+}
+
+impl Default for System {
+    fn default() -> System {
+        System::new()
+    }
+}
+
+impl Drop for System {
+    fn drop(&mut self) {
+        // The bus first, while its directory still exists
+        self.bus = None;
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
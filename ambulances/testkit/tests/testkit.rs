@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The fake system's pieces, each seen the way a backend sees it
+
+use ambulance_testkit::{Arg, Entry, Script, System, ANY_PATH, BOOT_ID};
+use systemd_shim::bus::Bus;
+use systemd_shim::journal::{self, Journal};
+
+#[test]
+fn scripted_commands_answer_by_arguments_and_are_logged() {
+    let mut system = System::new();
+    system
+        .command("lsblk", Script::prints("sda\n").when(&["-n", "-o", "NAME"]))
+        .command("lsblk", Script::fails(2, "lsblk: bad usage\n"));
+
+    let out = system.run("lsblk", &["-n", "-o", "NAME"]);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "sda\n");
+
+    let out = system.run("lsblk", &["--json"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert_eq!(String::from_utf8_lossy(&out.stderr), "lsblk: bad usage\n");
+
+    // Only scripted programs are on PATH
+    let out = system
+        .command_for("/bin/sh")
+        .args(["-c", "command -v ls || echo missing"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "missing\n");
+
+    assert_eq!(
+        system.calls(),
+        vec![vec!["lsblk", "-n", "-o", "NAME"], vec!["lsblk", "--json"],]
+    );
+}
+
+#[test]
+fn arguments_with_spaces_and_quotes_match_whole() {
+    let mut system = System::new();
+    system.command(
+        "systemctl",
+        Script::prints("ok\n").when(&["show", "it's a unit.service"]),
+    );
+    let out = system.run("systemctl", &["show", "it's a unit.service"]);
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "ok\n");
+    let out = system.run("systemctl", &["show", "it's", "a", "unit.service"]);
+    assert_eq!(out.status.code(), Some(127));
+}
+
+#[test]
+fn files_land_under_the_root_backends_read() {
+    let mut system = System::new();
+    system
+        .meminfo(4096, 1024)
+        .memory_pressure(12.5, 6.0)
+        .link("/sys/class/block/sda", "../../block/sda")
+        .block_device("sda", 1024, true);
+
+    let meminfo = std::fs::read_to_string(system.path("/proc/meminfo")).unwrap();
+    assert!(meminfo.contains("MemTotal:        4194304 kB"));
+    assert!(meminfo.contains("MemAvailable:    1048576 kB"));
+    let psi = std::fs::read_to_string(system.path("/proc/pressure/memory")).unwrap();
+    assert!(psi.starts_with("some avg10=12.50 avg60=12.50"));
+    assert!(psi.contains("\nfull avg10=6.00 "));
+    let boot_id = std::fs::read_to_string(system.path("/proc/sys/kernel/random/boot_id"));
+    assert_eq!(boot_id.unwrap().trim().replace('-', ""), BOOT_ID);
+
+    let out = system
+        .command_for("/bin/sh")
+        .args([
+            "-c",
+            "read -r v < \"$SYSTEM_TOOLS_ROOT/sys/class/block/sda/queue/rotational\"; echo \"$v\"",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "1\n");
+}
+
+#[test]
+fn journal_entries_are_read_through_the_shim() {
+    let mut system = System::new();
+    system
+        .journal(Entry::new("old boot").previous_boot().ago(7200))
+        .journal(Entry::new("Out of memory: Killed process 4242 (firefox)").kernel())
+        .journal(Entry::new("Started foo.service").unit("init.scope").ago(60));
+
+    // The one test in this binary that opens a journal
+    let out = system
+        .command_for(std::env::current_exe().unwrap())
+        .args(["--exact", "read_mock_journal", "--ignored", "--nocapture"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{}", stdout);
+    assert!(
+        stdout.contains("MESSAGE=Started foo.service|Out of memory"),
+        "{}",
+        stdout
+    );
+}
+
+/// Run by the test above with the mock journal set up
+#[test]
+#[ignore]
+fn read_mock_journal() {
+    assert!(std::env::var_os(journal::MOCK_VAR).is_some());
+    let mut journal = Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.match_this_boot().unwrap();
+    journal.seek_head().unwrap();
+    let mut messages = Vec::new();
+    while journal.next_entry().unwrap() {
+        messages.push(journal.field("MESSAGE").unwrap());
+    }
+    println!("MESSAGE={}", messages.join("|"));
+}
+
+#[test]
+fn the_bus_answers_properties_and_methods_and_logs_calls() {
+    let mut system = System::new();
+    system
+        .bus()
+        .property(
+            ANY_PATH,
+            "org.freedesktop.systemd1.Service",
+            "MemoryMax",
+            512u64 << 20,
+        )
+        .method(
+            "org.freedesktop.UPower",
+            "EnumerateDevices",
+            vec![Arg::Array(
+                "o".to_string(),
+                vec![Arg::Path(
+                    "/org/freedesktop/UPower/devices/battery_BAT0".to_string(),
+                )],
+            )],
+        );
+    let address = system.bus().address();
+
+    let bus = Bus::at(&address).unwrap();
+    let max = bus
+        .get_property_u64(
+            "org.freedesktop.systemd1",
+            "/org/freedesktop/systemd1/unit/foo_2eservice",
+            "org.freedesktop.systemd1.Service",
+            "MemoryMax",
+        )
+        .unwrap();
+    assert_eq!(max, 512 << 20);
+    let paths = bus
+        .call_object_paths(
+            "org.freedesktop.UPower",
+            "/org/freedesktop/UPower",
+            "org.freedesktop.UPower",
+            "EnumerateDevices",
+        )
+        .unwrap();
+    assert_eq!(paths, ["/org/freedesktop/UPower/devices/battery_BAT0"]);
+    assert!(bus
+        .get_property_bool(
+            "org.freedesktop.UPower",
+            "/org/freedesktop/UPower",
+            "org.freedesktop.UPower",
+            "OnBattery",
+        )
+        .is_err());
+
+    let members: Vec<String> = system.bus().calls().into_iter().map(|c| c.member).collect();
+    assert_eq!(members, ["Get", "EnumerateDevices", "Get"]);
+}
+
+#[test]
+fn without_a_bus_backends_see_it_down() {
+    let system = System::new();
+    let out = system
+        .command_for("/bin/sh")
+        .args(["-c", "echo \"$DBUS_SYSTEM_BUS_ADDRESS\""])
+        .output()
+        .unwrap();
+    let address = String::from_utf8_lossy(&out.stdout);
+    let path = address.trim().strip_prefix("unix:path=").unwrap();
+    assert!(!std::path::Path::new(path).exists());
+}
+
+#[test]
+fn a_manager_is_reached_as_a_peer() {
+    let mut system = System::new();
+    system.bus().method(
+        "org.freedesktop.systemd1.Manager",
+        "RestartUnit",
+        vec![Arg::Path("/org/freedesktop/systemd1/job/7".to_string())],
+    );
+    let manager = Bus::peer(&system.bus().address()).unwrap();
+    let job = manager.restart_unit("dbus.socket", "replace").unwrap();
+    assert_eq!(job, "/org/freedesktop/systemd1/job/7");
+    let calls = system.bus().calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].member, "RestartUnit");
+}
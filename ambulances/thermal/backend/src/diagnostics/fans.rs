@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Fan failures and fans held back by manual control
+//!
+//! A fan standing still is normal for many GPUs and quiet laptops at
+//! idle, so 0 RPM only counts as a failure when the fan is told to spin,
+//! or while something is hot. The chip's own alarm and fault flags
+//! always count. A fan on manual control (`pwmN_enable` = 1) at a low
+//! duty cycle while the machine is hot is usually a fan control program
+//! that died or was misconfigured, and can be handed back to the chip.
+
+use crate::hwmon::{Chip, Fan};
+use crate::report::{mark, print_notes};
+use serde::Serialize;
+
+/// `pwmN` duty cycle, of 255, that should have any fan turning
+const SPINNING_PWM: u64 = 128;
+
+/// `pwmN` duty cycle, of 255, too low to cool a hot machine
+const LOW_PWM: u64 = 80;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FanStatus {
+    pub chip: String,
+    pub fan: Fan,
+    /// `pwmN_enable`, when the fan could be handed back to automatic control
+    pub pwm_enable_path: Option<String>,
+    pub problem: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FanDiagnostics {
+    pub fans: Vec<FanStatus>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl FanDiagnostics {
+    pub fn failed(&self) -> impl Iterator<Item = &FanStatus> {
+        self.fans
+            .iter()
+            .filter(|f| f.problem.is_some() && f.pwm_enable_path.is_none())
+    }
+
+    /// Fans held slow by manual control while hot
+    pub fn held_back(&self) -> impl Iterator<Item = &FanStatus> {
+        self.fans.iter().filter(|f| f.pwm_enable_path.is_some())
+    }
+}
+
+fn problem(fan: &Fan, hot: bool) -> Option<String> {
+    if fan.fault {
+        return Some("the chip reports a fault (broken or disconnected)".to_string());
+    }
+    let rpm = fan.rpm?;
+    if rpm == 0 && fan.pwm.is_some_and(|p| p >= SPINNING_PWM) {
+        return Some(format!(
+            "stands still though driven at {}%",
+            fan.pwm.unwrap_or(0) * 100 / 255
+        ));
+    }
+    if rpm == 0 && hot {
+        return Some("stands still while the machine is hot".to_string());
+    }
+    if fan.alarm || fan.min_rpm.is_some_and(|m| rpm < m) {
+        return Some(format!(
+            "turns at {} RPM, below its minimum of {}",
+            rpm,
+            fan.min_rpm.map_or("?".to_string(), |m| m.to_string())
+        ));
+    }
+    None
+}
+
+/// `hot` says whether any sensor is hot right now
+pub fn diagnose(chips: &[Chip], hot: bool) -> FanDiagnostics {
+    let mut diag = FanDiagnostics::default();
+    for chip in chips {
+        for fan in &chip.fans {
+            let held_back = hot && fan.manual() && fan.pwm.is_some_and(|p| p < LOW_PWM);
+            let problem = if held_back {
+                Some(format!(
+                    "held at {}% by manual control while the machine is hot",
+                    fan.pwm.unwrap_or(0) * 100 / 255
+                ))
+            } else {
+                problem(fan, hot)
+            };
+            diag.fans.push(FanStatus {
+                chip: chip.name.clone(),
+                pwm_enable_path: held_back.then(|| chip.pwm_enable_path(fan).display().to_string()),
+                fan: fan.clone(),
+                problem,
+            });
+        }
+    }
+
+    for f in &diag.fans {
+        if let Some(problem) = &f.problem {
+            diag.warnings
+                .push(format!("{} {}: {}", f.chip, f.fan.name(), problem));
+        }
+    }
+    if diag.failed().next().is_some() {
+        diag.recommendations.push(
+            "Check the failing fans are connected and free of dust or fluff; replace any that stay stopped"
+                .to_string(),
+        );
+    }
+    if diag.held_back().next().is_some() {
+        diag.recommendations.push(
+            "Hand the fans back to automatic control: thermal-ambulance repair fans".to_string(),
+        );
+    }
+    diag
+}
+
+impl FanDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Fans ===");
+        if self.fans.is_empty() {
+            println!("- No fan reported by the sensor chips");
+        }
+        for f in &self.fans {
+            println!(
+                "{} {} {}: {}",
+                mark(f.problem.is_none()),
+                f.chip,
+                f.fan.name(),
+                f.fan
+                    .rpm
+                    .map_or("speed unknown".to_string(), |r| format!("{} RPM", r))
+            );
+            if verbose {
+                if let (Some(pwm), Some(enable)) = (f.fan.pwm, f.fan.pwm_enable) {
+                    println!(
+                        "    pwm {}/255, {}",
+                        pwm,
+                        match enable {
+                            0 => "full speed",
+                            1 => "manual",
+                            _ => "automatic",
+                        }
+                    );
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
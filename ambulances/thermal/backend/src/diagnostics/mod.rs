@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thermal diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs within the scan's budget.
+//! Fans need the sensors to know whether the machine is hot, and
+//! `performance` weighs the other three sections' findings, so each runs
+//! after what it needs; sensors and throttling run at the same time. A
+//! section whose input did not finish is skipped and says so in its
+//! warnings; one the budget cut short is left empty and its timing says
+//! why.
+
+pub mod fans;
+pub mod performance;
+pub mod sensors;
+pub mod throttling;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Inputs, Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; reading a sensor whose chip stopped
+/// answering can block
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Sensors(sensors::SensorDiagnostics),
+    Fans(fans::FanDiagnostics),
+    Throttling(throttling::ThrottleDiagnostics),
+    Performance(performance::PerformanceDiagnostics),
+}
+
+fn sensors(inputs: &Inputs<Section>) -> Result<&sensors::SensorDiagnostics, String> {
+    match inputs.get("sensors") {
+        Some(Section::Sensors(d)) => Ok(d),
+        _ => Err("no sensors section".to_string()),
+    }
+}
+
+fn fans(inputs: &Inputs<Section>) -> Result<&fans::FanDiagnostics, String> {
+    match inputs.get("fans") {
+        Some(Section::Fans(d)) => Ok(d),
+        _ => Err("no fans section".to_string()),
+    }
+}
+
+fn throttling(inputs: &Inputs<Section>) -> Result<&throttling::ThrottleDiagnostics, String> {
+    match inputs.get("throttling") {
+        Some(Section::Throttling(d)) => Ok(d),
+        _ => Err("no throttling section".to_string()),
+    }
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("sensors", |_| {
+            Ok(Section::Sensors(sensors::diagnose()))
+        }))
+        .job(
+            Job::blocking("fans", |inputs| {
+                let sensors = sensors(&inputs)?;
+                Ok(Section::Fans(fans::diagnose(
+                    &sensors.chips,
+                    !sensors.hot().is_empty(),
+                )))
+            })
+            .after(&["sensors"]),
+        )
+        .job(Job::blocking("throttling", |_| {
+            Ok(Section::Throttling(throttling::diagnose()))
+        }))
+        .job(
+            Job::blocking("performance", |inputs| {
+                Ok(Section::Performance(performance::diagnose(
+                    sensors(&inputs)?,
+                    fans(&inputs)?,
+                    throttling(&inputs)?,
+                )))
+            })
+            .after(&["sensors", "fans", "throttling"]),
+        )
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        sensors: Default::default(),
+        fans: Default::default(),
+        throttling: Default::default(),
+        performance: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Sensors(d)) => result.sensors = d,
+            Some(Section::Fans(d)) => result.fans = d,
+            Some(Section::Throttling(d)) => result.throttling = d,
+            Some(Section::Performance(d)) => result.performance = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "sensors" => &mut result.sensors.warnings,
+                    "fans" => &mut result.fans.warnings,
+                    "throttling" => &mut result.throttling.warnings,
+                    _ => &mut result.performance.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
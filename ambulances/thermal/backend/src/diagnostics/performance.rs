@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Whether heat is what makes the machine slow
+//!
+//! Heat slows a machine by lowering its clocks, so a busy CPU running
+//! well under its top frequency while hot, or just after throttling, is
+//! the sign. A machine that is hot while nearly idle, with its fans
+//! turning, is not short of power but of airflow: dust in the heatsink
+//! or dried-out thermal paste. The power profile is read from
+//! power-profiles-daemon, under either of its bus names.
+
+use crate::diagnostics::fans::FanDiagnostics;
+use crate::diagnostics::sensors::SensorDiagnostics;
+use crate::diagnostics::throttling::ThrottleDiagnostics;
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const CPUS: &str = "/sys/devices/system/cpu";
+
+/// power-profiles-daemon as `(name, path)`: UPower's since 0.20, then the original
+pub const PROFILE_DAEMONS: &[(&str, &str)] = &[
+    (
+        "org.freedesktop.UPower.PowerProfiles",
+        "/org/freedesktop/UPower/PowerProfiles",
+    ),
+    ("net.hadess.PowerProfiles", "/net/hadess/PowerProfiles"),
+];
+
+/// One-minute load per CPU from which the machine counts as busy
+const BUSY_LOAD: f64 = 0.5;
+
+/// One-minute load per CPU under which the machine counts as idle
+const IDLE_LOAD: f64 = 0.2;
+
+/// Share of the top CPU frequency under which a busy CPU is held back
+const SLOW_PERCENT: f64 = 60.0;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PerformanceDiagnostics {
+    /// One-minute load average per CPU
+    pub load_per_cpu: Option<f64>,
+    /// Mean current frequency as a share of each CPU's top frequency
+    pub frequency_percent: Option<f64>,
+    /// `performance`, `balanced` or `power-saver`
+    pub power_profile: Option<String>,
+    /// Heat is slowing the machine down now
+    pub thermal_slowdown: bool,
+    /// Hot while idle, with no fan failing
+    pub airflow_suspect: bool,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn load_per_cpu() -> Option<f64> {
+    let load: f64 = system::read(host::path("/proc/loadavg"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let cpus = std::thread::available_parallelism().ok()?.get();
+    Some(load / cpus as f64)
+}
+
+fn frequency_percent() -> Option<f64> {
+    let entries = std::fs::read_dir(host::path(CPUS)).ok()?;
+    let shares: Vec<f64> = entries
+        .flatten()
+        .filter_map(|e| {
+            let dir = e.path().join("cpufreq");
+            let current = system::read_u64(dir.join("scaling_cur_freq"))?;
+            let max = system::read_u64(dir.join("cpuinfo_max_freq")).filter(|&m| m > 0)?;
+            Some(current as f64 * 100.0 / max as f64)
+        })
+        .collect();
+    (!shares.is_empty()).then(|| shares.iter().sum::<f64>() / shares.len() as f64)
+}
+
+/// The active power profile, if power-profiles-daemon runs
+pub fn power_profile(bus: &Bus) -> Option<String> {
+    PROFILE_DAEMONS.iter().find_map(|(name, path)| {
+        bus.get_property_string(name, path, name, "ActiveProfile")
+            .ok()
+    })
+}
+
+pub fn diagnose(
+    sensors: &SensorDiagnostics,
+    fans: &FanDiagnostics,
+    throttling: &ThrottleDiagnostics,
+) -> PerformanceDiagnostics {
+    let mut diag = PerformanceDiagnostics {
+        load_per_cpu: load_per_cpu(),
+        frequency_percent: frequency_percent(),
+        power_profile: Bus::system().ok().and_then(|bus| power_profile(&bus)),
+        ..PerformanceDiagnostics::default()
+    };
+    let hot = !sensors.hot().is_empty();
+    let busy = diag.load_per_cpu.is_some_and(|l| l >= BUSY_LOAD);
+    let idle = diag.load_per_cpu.is_some_and(|l| l < IDLE_LOAD);
+    let slow = diag.frequency_percent.is_some_and(|f| f < SLOW_PERCENT);
+    let fans_turning = fans.fans.iter().any(|f| f.fan.rpm.unwrap_or(0) > 0);
+
+    diag.thermal_slowdown = (busy && slow && (hot || throttling.recent()))
+        || !throttling.active_cooling.is_empty()
+        || throttling.gpus.iter().any(|g| g.throttled);
+    diag.airflow_suspect = hot && idle && fans.failed().next().is_none();
+
+    if diag.thermal_slowdown {
+        diag.warnings.push(match (diag.frequency_percent, diag.load_per_cpu) {
+            (Some(f), Some(l)) => format!(
+                "Heat is slowing the machine: the CPU runs at {:.0}% of its top speed under a load of {:.1} per CPU",
+                f, l
+            ),
+            _ => "Heat is slowing the machine down".to_string(),
+        });
+    }
+    if diag.airflow_suspect {
+        diag.warnings.push(format!(
+            "Hot while nearly idle{}: the cooling is not carrying heat away",
+            if fans_turning {
+                " with the fans turning"
+            } else {
+                ""
+            }
+        ));
+        diag.recommendations.push(
+            "Clean the dust out of the vents, fans and heatsink; if that does not help, renew the thermal paste"
+                .to_string(),
+        );
+    }
+    if throttling.any() && diag.power_profile.as_deref() == Some("performance") {
+        diag.recommendations.push(
+            "Switch from the performance to the balanced power profile: thermal-ambulance repair profile"
+                .to_string(),
+        );
+    } else if throttling.any() && !diag.airflow_suspect {
+        diag.recommendations.push(
+            "Keep the vents clear and the machine on a hard surface while it works hard"
+                .to_string(),
+        );
+    }
+    if throttling.shutdowns > 0 && !diag.airflow_suspect {
+        diag.recommendations.push(
+            "Have the cooling checked before the next thermal shutdown loses unsaved work"
+                .to_string(),
+        );
+    }
+    diag
+}
+
+impl PerformanceDiagnostics {
+    pub fn print(&self, _verbose: bool) {
+        println!("=== Performance ===");
+        if let Some(load) = self.load_per_cpu {
+            println!("- Load: {:.2} per CPU", load);
+        }
+        if let Some(f) = self.frequency_percent {
+            println!("- CPU frequency: {:.0}% of top speed", f);
+        }
+        println!(
+            "- Power profile: {}",
+            self.power_profile
+                .as_deref()
+                .unwrap_or("unknown (power-profiles-daemon not running)")
+        );
+        println!(
+            "{} {}",
+            mark(!self.thermal_slowdown),
+            if self.thermal_slowdown {
+                "Heat is holding the machine back"
+            } else {
+                "Heat is not holding the machine back"
+            }
+        );
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
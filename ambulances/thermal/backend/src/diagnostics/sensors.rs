@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Temperatures from hwmon and the ACPI thermal zones
+//!
+//! A hwmon sensor is hot at its own `max`, or 10°C short of its `crit`;
+//! CPU and GPU sensors that give neither are hot from 95°C. A thermal
+//! zone is hot once it passes its first passive trip point, which is
+//! where the kernel starts slowing the CPU down to cool it.
+
+use crate::hwmon::{self, Chip};
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::Path;
+
+const THERMAL: &str = "/sys/class/thermal";
+
+/// Margin to a critical trip point that counts as hot
+const CRITICAL_MARGIN: f64 = 5.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Zone {
+    /// e.g. `thermal_zone0`
+    pub name: String,
+    /// e.g. `x86_pkg_temp`, `acpitz`
+    pub kind: String,
+    pub celsius: f64,
+    /// Lowest passive trip point
+    pub passive: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+impl Zone {
+    pub fn hot(&self) -> bool {
+        self.passive.is_some_and(|p| self.celsius >= p)
+            || self
+                .critical
+                .is_some_and(|c| self.celsius >= c - CRITICAL_MARGIN)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SensorDiagnostics {
+    pub chips: Vec<Chip>,
+    pub zones: Vec<Zone>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl SensorDiagnostics {
+    /// Hot sensors and zones, as `(description, kind)`
+    pub fn hot(&self) -> Vec<(String, &'static str)> {
+        let mut hot = Vec::new();
+        for chip in &self.chips {
+            for t in chip.temps.iter().filter(|t| t.hot(chip.kind)) {
+                hot.push((
+                    format!("{} {} at {:.0}°C", chip.name, t.label, t.celsius),
+                    chip.kind,
+                ));
+            }
+        }
+        for zone in self.zones.iter().filter(|z| z.hot()) {
+            hot.push((
+                format!("{} ({}) at {:.0}°C", zone.name, zone.kind, zone.celsius),
+                "zone",
+            ));
+        }
+        hot
+    }
+}
+
+fn millidegrees(path: impl AsRef<Path>) -> Option<f64> {
+    let millis: i64 = system::read(path)?.parse().ok()?;
+    Some(millis as f64 / 1000.0)
+}
+
+fn zone(dir: &Path) -> Option<Zone> {
+    let name = dir.file_name()?.to_string_lossy().into_owned();
+    if !name.starts_with("thermal_zone") {
+        return None;
+    }
+    let mut passive: Option<f64> = None;
+    let mut critical: Option<f64> = None;
+    for i in 0.. {
+        let Some(kind) = system::read(dir.join(format!("trip_point_{}_type", i))) else {
+            break;
+        };
+        let Some(temp) = millidegrees(dir.join(format!("trip_point_{}_temp", i))) else {
+            continue;
+        };
+        // Disabled trip points read as 0 or far below zero
+        if temp <= 0.0 {
+            continue;
+        }
+        match kind.as_str() {
+            "passive" => passive = Some(passive.map_or(temp, |p| p.min(temp))),
+            "critical" => critical = Some(temp),
+            _ => {}
+        }
+    }
+    Some(Zone {
+        kind: system::read(dir.join("type")).unwrap_or_default(),
+        celsius: millidegrees(dir.join("temp"))?,
+        name,
+        passive,
+        critical,
+    })
+}
+
+pub fn zones() -> Vec<Zone> {
+    let Ok(entries) = std::fs::read_dir(host::path(THERMAL)) else {
+        return Vec::new();
+    };
+    let mut zones: Vec<Zone> = entries.flatten().filter_map(|e| zone(&e.path())).collect();
+    zones.sort_by(|a, b| a.name.cmp(&b.name));
+    zones
+}
+
+pub fn diagnose() -> SensorDiagnostics {
+    let mut diag = SensorDiagnostics {
+        chips: hwmon::chips(),
+        zones: zones(),
+        ..SensorDiagnostics::default()
+    };
+    if diag.chips.is_empty() && diag.zones.is_empty() {
+        diag.warnings
+            .push("No temperature sensor is visible".to_string());
+        diag.recommendations.push(
+            "Load the sensor driver for this board (sensors-detect from lm-sensors finds it)"
+                .to_string(),
+        );
+        return diag;
+    }
+
+    for (what, _) in diag.hot() {
+        diag.warnings.push(format!("Running hot: {}", what));
+    }
+    diag
+}
+
+impl SensorDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Temperatures ===");
+        for chip in &self.chips {
+            let Some(hottest) = chip.hottest() else {
+                continue;
+            };
+            println!(
+                "{} {} ({}): {:.0}°C{}",
+                mark(!chip.temps.iter().any(|t| t.hot(chip.kind))),
+                chip.name,
+                chip.kind,
+                hottest.celsius,
+                if chip.temps.len() > 1 {
+                    format!(" hottest of {}", chip.temps.len())
+                } else {
+                    String::new()
+                }
+            );
+            if verbose {
+                for t in &chip.temps {
+                    println!(
+                        "    {}: {:.1}°C{}",
+                        t.label,
+                        t.celsius,
+                        t.limit(chip.kind)
+                            .map(|l| format!(" (hot from {:.0}°C)", l))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+        }
+        for zone in &self.zones {
+            if !verbose && !zone.hot() {
+                continue;
+            }
+            println!(
+                "{} {} ({}): {:.0}°C{}",
+                mark(!zone.hot()),
+                zone.name,
+                zone.kind,
+                zone.celsius,
+                zone.passive
+                    .map(|p| format!(", throttles from {:.0}°C", p))
+                    .unwrap_or_default()
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
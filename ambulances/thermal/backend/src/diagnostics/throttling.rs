@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! CPU and GPU thermal throttling, now and over the last week
+//!
+//! Intel CPUs count throttling episodes per core and package since boot.
+//! Thermal zones slow the CPU through their processor cooling devices,
+//! whose state is above 0 while they do. The kernel logs each episode
+//! and every critical-temperature shutdown, which the journal keeps
+//! across reboots. NVIDIA GPUs report thermal slowdown through
+//! `nvidia-smi`; other GPUs only show it as heat.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+const CPUS: &str = "/sys/devices/system/cpu";
+const THERMAL: &str = "/sys/class/thermal";
+
+/// Journal window read back
+pub const WINDOW_DAYS: u64 = 7;
+
+/// Journal entries read at most
+const MAX_ENTRIES: usize = 200_000;
+
+/// Kernel messages that report a throttling episode, lowercased
+const THROTTLE_MESSAGES: &[&str] = &["temperature above threshold", "cpu clock throttled"];
+
+/// Kernel messages that report a thermal shutdown, lowercased
+const CRITICAL_MESSAGES: &[&str] = &["critical temperature reached", "thermal shutdown"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoolingDevice {
+    pub name: String,
+    /// e.g. `Processor`, `intel_powerclamp`
+    pub kind: String,
+    pub state: u64,
+    pub max_state: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuThrottle {
+    pub name: String,
+    pub celsius: Option<f64>,
+    /// Slowed down by the GPU itself or by the driver for heat
+    pub throttled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ThrottleDiagnostics {
+    /// Intel per-core throttling episodes since boot, summed
+    pub core_throttle_count: Option<u64>,
+    /// Intel package throttling episodes since boot
+    pub package_throttle_count: Option<u64>,
+    /// Processor cooling devices currently slowing the CPU
+    pub active_cooling: Vec<CoolingDevice>,
+    /// Throttling messages in the journal over `WINDOW_DAYS`
+    pub events: usize,
+    pub events_last_hour: usize,
+    /// Critical-temperature shutdowns over `WINDOW_DAYS`
+    pub shutdowns: usize,
+    pub journal_error: Option<String>,
+    pub gpus: Vec<GpuThrottle>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl ThrottleDiagnostics {
+    /// Throttled right now, or within the last hour
+    pub fn recent(&self) -> bool {
+        !self.active_cooling.is_empty()
+            || self.events_last_hour > 0
+            || self.gpus.iter().any(|g| g.throttled)
+    }
+
+    /// Throttled at any point this week or this boot
+    pub fn any(&self) -> bool {
+        self.recent()
+            || self.events > 0
+            || self.core_throttle_count.unwrap_or(0) > 0
+            || self.package_throttle_count.unwrap_or(0) > 0
+    }
+}
+
+/// Sum of core episodes and the highest package count, over every CPU
+fn intel_counters() -> (Option<u64>, Option<u64>) {
+    let Ok(entries) = std::fs::read_dir(host::path(CPUS)) else {
+        return (None, None);
+    };
+    let mut core: Option<u64> = None;
+    let mut package: Option<u64> = None;
+    for entry in entries.flatten() {
+        let dir = entry.path().join("thermal_throttle");
+        if let Some(n) = system::read_u64(dir.join("core_throttle_count")) {
+            core = Some(core.unwrap_or(0) + n);
+        }
+        // Every core of a package reports the same package count
+        if let Some(n) = system::read_u64(dir.join("package_throttle_count")) {
+            package = Some(package.unwrap_or(0).max(n));
+        }
+    }
+    (core, package)
+}
+
+fn active_cooling() -> Vec<CoolingDevice> {
+    let Ok(entries) = std::fs::read_dir(host::path(THERMAL)) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<CoolingDevice> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            if !name.starts_with("cooling_device") {
+                return None;
+            }
+            let dir = e.path();
+            let attr = |a: &str| system::read(dir.join(a));
+            Some(CoolingDevice {
+                kind: attr("type")?,
+                state: attr("cur_state")?.parse().ok()?,
+                max_state: attr("max_state")?.parse().ok()?,
+                name,
+            })
+        })
+        // Fans and such are cooling devices too; only those slowing the CPU count
+        .filter(|d| d.state > 0 && matches!(d.kind.as_str(), "Processor" | "intel_powerclamp"))
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+fn now_usec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64)
+}
+
+/// `(events, events in the last hour, shutdowns)` from the kernel's messages
+fn journal_events() -> std::io::Result<(usize, usize, usize)> {
+    let now = now_usec();
+    let since = now.saturating_sub(WINDOW_DAYS * 86_400 * 1_000_000);
+    let hour_ago = now.saturating_sub(3_600 * 1_000_000);
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.seek_tail()?;
+    let (mut events, mut last_hour, mut shutdowns) = (0, 0, 0);
+    for _ in 0..MAX_ENTRIES {
+        if !journal.previous_entry()? {
+            break;
+        }
+        let timestamp = journal.realtime_usec()?;
+        if timestamp < since {
+            break;
+        }
+        let Some(text) = journal.field("MESSAGE") else {
+            continue;
+        };
+        let text = text.to_lowercase();
+        if CRITICAL_MESSAGES.iter().any(|m| text.contains(m)) {
+            shutdowns += 1;
+        } else if THROTTLE_MESSAGES.iter().any(|m| text.contains(m)) {
+            events += 1;
+            if timestamp >= hour_ago {
+                last_hour += 1;
+            }
+        }
+    }
+    Ok((events, last_hour, shutdowns))
+}
+
+fn nvidia() -> Vec<GpuThrottle> {
+    if !system::has("nvidia-smi") {
+        return Vec::new();
+    }
+    let Ok(output) = system::run(
+        "nvidia-smi",
+        &[
+            "--query-gpu=name,temperature.gpu,clocks_throttle_reasons.hw_thermal_slowdown,clocks_throttle_reasons.sw_thermal_slowdown",
+            "--format=csv,noheader,nounits",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            Some(GpuThrottle {
+                name: fields.first()?.to_string(),
+                celsius: fields.get(1).and_then(|t| t.parse().ok()),
+                throttled: fields.iter().skip(2).any(|f| *f == "Active"),
+            })
+        })
+        .collect()
+}
+
+pub fn diagnose() -> ThrottleDiagnostics {
+    let (core, package) = intel_counters();
+    let mut diag = ThrottleDiagnostics {
+        core_throttle_count: core,
+        package_throttle_count: package,
+        active_cooling: active_cooling(),
+        gpus: nvidia(),
+        ..ThrottleDiagnostics::default()
+    };
+    match journal_events() {
+        Ok((events, last_hour, shutdowns)) => {
+            diag.events = events;
+            diag.events_last_hour = last_hour;
+            diag.shutdowns = shutdowns;
+        }
+        Err(e) => diag.journal_error = Some(e.to_string()),
+    }
+
+    if diag.shutdowns > 0 {
+        diag.warnings.push(format!(
+            "The machine shut down {} time(s) in the last {} days at a critical temperature",
+            diag.shutdowns, WINDOW_DAYS
+        ));
+    }
+    for d in &diag.active_cooling {
+        diag.warnings.push(format!(
+            "{} ({}) is slowing the CPU: state {} of {}",
+            d.name, d.kind, d.state, d.max_state
+        ));
+    }
+    if diag.events > 0 {
+        diag.warnings.push(format!(
+            "The CPU was throttled for heat {} time(s) in the last {} days, {} in the last hour",
+            diag.events, WINDOW_DAYS, diag.events_last_hour
+        ));
+    } else if let Some(n) = package.filter(|&n| n > 0).or(core.filter(|&n| n > 0)) {
+        diag.warnings.push(format!(
+            "The CPU was throttled for heat {} time(s) since boot",
+            n
+        ));
+    }
+    for gpu in diag.gpus.iter().filter(|g| g.throttled) {
+        diag.warnings.push(format!(
+            "{} is slowed down for heat{}",
+            gpu.name,
+            gpu.celsius
+                .map(|c| format!(" at {:.0}°C", c))
+                .unwrap_or_default()
+        ));
+    }
+    diag
+}
+
+impl ThrottleDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Throttling ===");
+        if let Some(n) = self.package_throttle_count.or(self.core_throttle_count) {
+            println!("{} CPU throttle episodes since boot: {}", mark(n == 0), n);
+        }
+        match &self.journal_error {
+            Some(e) => println!("- Journal unreadable: {}", e),
+            None => println!(
+                "{} Throttle messages, last {} days: {} ({} in the last hour)",
+                mark(self.events == 0),
+                WINDOW_DAYS,
+                self.events,
+                self.events_last_hour
+            ),
+        }
+        if self.shutdowns > 0 || verbose {
+            println!(
+                "{} Critical-temperature shutdowns: {}",
+                mark(self.shutdowns == 0),
+                self.shutdowns
+            );
+        }
+        for gpu in &self.gpus {
+            println!(
+                "{} {}: {}",
+                mark(!gpu.throttled),
+                gpu.name,
+                if gpu.throttled {
+                    "thermal slowdown"
+                } else {
+                    "full speed"
+                }
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
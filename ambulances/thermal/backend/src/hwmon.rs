@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! hwmon chips: temperatures, fans and fan control
+//!
+//! Attributes follow the kernel's hwmon sysfs ABI: temperatures in
+//! millidegrees Celsius, fan speeds in RPM and `pwmN` duty cycles from
+//! 0 to 255. `pwmN` drives `fanN` on nearly every chip, but the ABI does
+//! not promise it, so the pair is only trusted when both exist.
+
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+pub const HWMON: &str = "/sys/class/hwmon";
+
+/// Chips that measure the CPU
+const CPU_CHIPS: &[&str] = &["coretemp", "k10temp", "zenpower", "cpu_thermal"];
+
+/// Chips that measure a GPU
+const GPU_CHIPS: &[&str] = &["amdgpu", "radeon", "nouveau", "i915", "xe"];
+
+/// Where a CPU or GPU sensor without limits of its own counts as hot
+const HOT_CELSIUS: f64 = 95.0;
+
+/// `pwmN_enable` for manual control; 0 is full speed and 2 or more automatic
+pub const PWM_MANUAL: u64 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Temp {
+    pub label: String,
+    pub celsius: f64,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl Temp {
+    /// The temperature from which this sensor counts as hot, if known
+    pub fn limit(&self, kind: &str) -> Option<f64> {
+        self.max
+            .or(self.crit.map(|c| c - 10.0))
+            .or((kind != "other").then_some(HOT_CELSIUS))
+    }
+
+    pub fn hot(&self, kind: &str) -> bool {
+        self.limit(kind).is_some_and(|l| self.celsius >= l)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Fan {
+    pub index: u32,
+    pub label: Option<String>,
+    pub rpm: Option<u64>,
+    pub min_rpm: Option<u64>,
+    /// The chip flags the fan as below its minimum
+    pub alarm: bool,
+    /// The chip flags the fan as broken or disconnected
+    pub fault: bool,
+    /// Duty cycle of `pwmN`, 0-255
+    pub pwm: Option<u64>,
+    /// `pwmN_enable`
+    pub pwm_enable: Option<u64>,
+}
+
+impl Fan {
+    pub fn name(&self) -> String {
+        self.label
+            .clone()
+            .unwrap_or_else(|| format!("fan{}", self.index))
+    }
+
+    pub fn manual(&self) -> bool {
+        self.pwm_enable == Some(PWM_MANUAL)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Chip {
+    pub name: String,
+    /// sysfs directory, e.g. `/sys/class/hwmon/hwmon2`
+    pub path: String,
+    /// `cpu`, `gpu` or `other`
+    pub kind: &'static str,
+    pub temps: Vec<Temp>,
+    pub fans: Vec<Fan>,
+}
+
+impl Chip {
+    pub fn hottest(&self) -> Option<&Temp> {
+        self.temps
+            .iter()
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius))
+    }
+
+    pub fn pwm_enable_path(&self, fan: &Fan) -> PathBuf {
+        Path::new(&self.path).join(format!("pwm{}_enable", fan.index))
+    }
+}
+
+fn celsius(path: PathBuf) -> Option<f64> {
+    let millis: i64 = system::read(path)?.parse().ok()?;
+    Some(millis as f64 / 1000.0)
+}
+
+/// Indices `N` of the `<prefix>N_input` attributes in `dir`
+fn indices(dir: &Path, prefix: &str) -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut indices: Vec<u32> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.strip_prefix(prefix)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    indices.sort_unstable();
+    indices
+}
+
+fn chip(dir: &Path) -> Option<Chip> {
+    let name = system::read(dir.join("name"))?;
+    let attr = |a: String| dir.join(a);
+    let kind = if CPU_CHIPS.contains(&name.as_str()) {
+        "cpu"
+    } else if GPU_CHIPS.contains(&name.as_str()) {
+        "gpu"
+    } else {
+        "other"
+    };
+    let temps = indices(dir, "temp")
+        .into_iter()
+        .filter_map(|i| {
+            Some(Temp {
+                celsius: celsius(attr(format!("temp{}_input", i)))?,
+                label: system::read(attr(format!("temp{}_label", i)))
+                    .unwrap_or_else(|| format!("temp{}", i)),
+                // Some drivers report 0 or absurd values for missing limits
+                max: celsius(attr(format!("temp{}_max", i))).filter(|&t| t > 0.0 && t < 150.0),
+                crit: celsius(attr(format!("temp{}_crit", i))).filter(|&t| t > 0.0 && t < 150.0),
+            })
+        })
+        .collect();
+    let fans = indices(dir, "fan")
+        .into_iter()
+        .map(|i| Fan {
+            index: i,
+            label: system::read(attr(format!("fan{}_label", i))),
+            rpm: system::read_u64(attr(format!("fan{}_input", i))),
+            min_rpm: system::read_u64(attr(format!("fan{}_min", i))).filter(|&m| m > 0),
+            alarm: system::read_u64(attr(format!("fan{}_alarm", i))) == Some(1),
+            fault: system::read_u64(attr(format!("fan{}_fault", i))) == Some(1),
+            pwm: system::read_u64(attr(format!("pwm{}", i))),
+            pwm_enable: system::read_u64(attr(format!("pwm{}_enable", i))),
+        })
+        .collect();
+    Some(Chip {
+        name,
+        path: dir.display().to_string(),
+        kind,
+        temps,
+        fans,
+    })
+}
+
+/// Every hwmon chip, in sysfs order
+pub fn chips() -> Vec<Chip> {
+    let Ok(entries) = std::fs::read_dir(host::path(HWMON)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    dirs.sort();
+    dirs.iter().filter_map(|d| chip(d)).collect()
+}
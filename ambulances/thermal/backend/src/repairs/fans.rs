@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Hand fans held back by manual control back to the chip
+//!
+//! `pwmN_enable` is set to 2, automatic control in the hwmon ABI, and to
+//! 0, full speed, where the driver refuses that. A fan control program
+//! still running takes the fan back; fancontrol's configuration, or the
+//! program's, then needs correcting.
+
+use crate::diagnostics::{fans, sensors};
+use crate::report::RepairOutcome;
+
+/// `pwmN_enable` values tried, in order: automatic, then full speed
+const HANDBACK: &[&str] = &["2", "0"];
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let sensors = sensors::diagnose();
+    let diag = fans::diagnose(&sensors.chips, !sensors.hot().is_empty());
+    let held: Vec<_> = diag.held_back().collect();
+    if held.is_empty() {
+        return RepairOutcome::not_needed("No fan is held back, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for f in held {
+        let Some(path) = &f.pwm_enable_path else {
+            continue;
+        };
+        let name = format!("{} {}", f.chip, f.fan.name());
+        if !confirm(&format!("Hand {} back to automatic control?", name)) {
+            result.errors.push(format!(
+                "{} not confirmed, left as is (rerun with --yes to confirm)",
+                name
+            ));
+            continue;
+        }
+        let mut last_error = None;
+        let written = HANDBACK
+            .iter()
+            .find(|value| match std::fs::write(path, value) {
+                Ok(()) => true,
+                Err(e) => {
+                    last_error = Some(e);
+                    false
+                }
+            });
+        match written {
+            Some(&"2") => result
+                .actions
+                .push(format!("Handed {} back to automatic control", name)),
+            Some(_) => result.actions.push(format!(
+                "Set {} to full speed; its driver has no automatic control",
+                name
+            )),
+            None => result.errors.push(format!(
+                "{}: {}",
+                path,
+                last_error.map_or("not writable".to_string(), |e| e.to_string())
+            )),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
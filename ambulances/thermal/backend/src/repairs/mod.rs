@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Thermal repairs, one module per target
+//!
+//! Dust, dried paste and dead fans need hands; these repairs only undo
+//! settings that keep a healthy cooling system from doing its job.
+
+pub mod fans;
+pub mod profile;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["profile", "fans", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        profile_repair: if selected("profile") {
+            profile::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        fans_repair: if selected("fans") {
+            fans::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Leave the performance power profile when it overheats the machine
+//!
+//! The balanced profile lets the CPU boost for short bursts, which is
+//! most of what the performance profile gains, without holding it at the
+//! temperature where it throttles. `powerprofilesctl` makes the switch.
+
+use crate::diagnostics::performance::power_profile;
+use crate::diagnostics::throttling;
+use crate::report::RepairOutcome;
+use crate::system;
+use systemd_shim::bus::Bus;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let profile = Bus::system().ok().and_then(|bus| power_profile(&bus));
+    if profile.as_deref() != Some("performance") {
+        return RepairOutcome::not_needed(
+            "The performance power profile is not active, no repair needed",
+        );
+    }
+    if !throttling::diagnose().any() {
+        return RepairOutcome::not_needed(
+            "The CPU has not been throttled, the performance profile can stay",
+        );
+    }
+
+    let mut result = RepairOutcome::default();
+    if !system::has("powerprofilesctl") {
+        result.errors.push(
+            "powerprofilesctl is not installed; switch the profile in the desktop's settings"
+                .to_string(),
+        );
+        return result;
+    }
+    if !confirm("Switch the power profile from performance to balanced?") {
+        result.errors.push(
+            "Power profile not confirmed, left as is (rerun with --yes to confirm)".to_string(),
+        );
+        return result;
+    }
+    match system::run("powerprofilesctl", &["set", "balanced"]) {
+        Ok(_) => result
+            .actions
+            .push("Switched the power profile from performance to balanced".to_string()),
+        Err(e) => result.errors.push(e),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The thermal checks against broken fake systems
+
+use ambulance_testkit::{CheckExt, ReportExt, System};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_thermal-ambulance");
+
+#[test]
+fn a_hot_cpu_with_a_stopped_fan_is_warned_about() {
+    let mut system = System::new();
+    system
+        .hwmon(0, "coretemp", 96.0, 100.0)
+        .file("/sys/class/hwmon/hwmon0/temp1_label", "Package id 0\n")
+        .file("/sys/class/hwmon/hwmon0/fan1_input", "0\n")
+        .file("/sys/class/hwmon/hwmon0/pwm1", "200\n")
+        .thermal_zone(0, "x86_pkg_temp", 96.0, 100.0);
+    let report = system.diagnose(BINARY);
+
+    assert_eq!(
+        report.check("sensors").summaries(),
+        [
+            "Running hot: coretemp Package id 0 at 96°C",
+            "Running hot: thermal_zone0 (x86_pkg_temp) at 96°C"
+        ]
+    );
+    assert_eq!(
+        report.check("fans").summaries(),
+        ["coretemp fan1: stands still though driven at 78%"]
+    );
+}
+
+#[test]
+fn a_fan_on_manual_control_held_slow_while_hot_can_be_handed_back() {
+    let mut system = System::new();
+    system
+        .hwmon(1, "k10temp", 97.0, 0.0)
+        .file("/sys/class/hwmon/hwmon1/fan1_input", "600\n")
+        .file("/sys/class/hwmon/hwmon1/pwm1", "40\n")
+        .file("/sys/class/hwmon/hwmon1/pwm1_enable", "1\n");
+    let report = system.diagnose(BINARY);
+
+    let fans = report.check("fans");
+    assert_eq!(
+        fans.summaries(),
+        ["k10temp fan1: held at 15% by manual control while the machine is hot"]
+    );
+    assert!(fans
+        .recommendations
+        .iter()
+        .any(|r| r.text.ends_with("thermal-ambulance repair fans")));
+}
+
+#[test]
+fn a_cool_machine_passes() {
+    let mut system = System::new();
+    system
+        .hwmon(0, "coretemp", 45.0, 100.0)
+        .file("/sys/class/hwmon/hwmon0/fan1_input", "1200\n")
+        .thermal_zone(0, "acpitz", 40.0, 105.0);
+    let report = system.diagnose(BINARY);
+
+    for id in ["sensors", "fans"] {
+        assert!(report.check(id).summaries().is_empty(), "{:?}", report);
+    }
+}
+
+#[test]
+fn no_sensors_at_all_is_a_finding_of_its_own() {
+    let report = System::new().diagnose(BINARY);
+
+    assert_eq!(
+        report.check("sensors").summaries(),
+        ["No temperature sensor is visible"]
+    );
+}
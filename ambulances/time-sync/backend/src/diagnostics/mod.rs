@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Time diagnostics, one module per report section
+//!
+//! The sections are independent, so they run as ambulance-core jobs at
+//! the same time; the NTP probes wait on the network and no longer hold
+//! up the rest. The run keeps to the scan's budget, and a section the
+//! budget cut short is left empty, with its timing saying so, rather
+//! than reported as a problem.
+
+pub mod ports;
+pub mod rtc;
+pub mod sync;
+pub mod timezone;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; timedated can hang on a stuck D-Bus
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Sync(sync::SyncDiagnostics),
+    Rtc(rtc::RtcDiagnostics),
+    Timezone(timezone::TimezoneDiagnostics),
+    Ports(ports::PortDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("sync", |_| {
+            Ok(Section::Sync(sync::diagnose()))
+        }))
+        .job(Job::blocking("rtc", |_| Ok(Section::Rtc(rtc::diagnose()))))
+        .job(Job::blocking("timezone", |_| {
+            Ok(Section::Timezone(timezone::diagnose()))
+        }))
+        .job(Job::blocking("ports", |_| {
+            Ok(Section::Ports(ports::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        sync: Default::default(),
+        rtc: Default::default(),
+        timezone: Default::default(),
+        ports: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Sync(d)) => result.sync = d,
+            Some(Section::Rtc(d)) => result.rtc = d,
+            Some(Section::Timezone(d)) => result.timezone = d,
+            Some(Section::Ports(d)) => result.ports = d,
+            None if outcome.status == Status::OverBudget => {}
+            // A section that did not finish says so instead of staying blank
+            None => {
+                let warnings = match outcome.id {
+                    "sync" => &mut result.sync.warnings,
+                    "rtc" => &mut result.rtc.warnings,
+                    "timezone" => &mut result.timezone.warnings,
+                    _ => &mut result.ports.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
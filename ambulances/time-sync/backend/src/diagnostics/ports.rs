@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Whether NTP traffic gets out
+//!
+//! Sends one SNTP client request to each configured server on UDP 123.
+//! When names resolve but no server answers, something between here and
+//! the internet drops NTP; a daemon in that state stays unsynchronised
+//! without saying why. Answers also give an offset independent of the
+//! local daemon.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// Used when no daemon configuration names a server
+const DEFAULT_SERVERS: &[&str] = &["pool.ntp.org", "time.cloudflare.com"];
+
+/// Daemon configurations and the keys that name servers in them
+const CONFIGS: &[(&str, &[&str])] = &[
+    ("/etc/systemd/timesyncd.conf", &["NTP=", "FallbackNTP="]),
+    ("/etc/chrony.conf", &["server ", "pool "]),
+    ("/etc/chrony/chrony.conf", &["server ", "pool "]),
+    ("/etc/ntp.conf", &["server ", "pool "]),
+    ("/etc/ntpsec/ntp.conf", &["server ", "pool "]),
+];
+
+/// Servers probed at most
+const MAX_SERVERS: usize = 4;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch
+const NTP_EPOCH_OFFSET: f64 = 2_208_988_800.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Probe {
+    pub server: String,
+    pub resolved: bool,
+    pub answered: bool,
+    /// Local clock minus server time
+    pub offset_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PortDiagnostics {
+    pub probes: Vec<Probe>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Servers named in whichever daemon configurations exist
+fn configured_servers() -> Vec<String> {
+    let mut servers: Vec<String> = Vec::new();
+    for (path, keys) in CONFIGS {
+        let Some(config) = system::read(path) else {
+            continue;
+        };
+        for line in config.lines().map(str::trim) {
+            let Some(value) = keys.iter().find_map(|k| line.strip_prefix(k)) else {
+                continue;
+            };
+            // timesyncd lists several per line; chrony and ntpd one plus options
+            let names: Vec<&str> = if path.contains("timesyncd") {
+                value.split_whitespace().collect()
+            } else {
+                value.split_whitespace().take(1).collect()
+            };
+            for name in names {
+                if !servers.iter().any(|s| s == name) {
+                    servers.push(name.to_string());
+                }
+            }
+        }
+    }
+    if servers.is_empty() {
+        servers = DEFAULT_SERVERS.iter().map(|s| s.to_string()).collect();
+    }
+    servers.truncate(MAX_SERVERS);
+    servers
+}
+
+/// 64-bit NTP timestamp at `bytes[at..at + 8]` as Unix seconds
+fn timestamp(bytes: &[u8], at: usize) -> f64 {
+    let word = |i: usize| u32::from_be_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+    word(at) as f64 + word(at + 4) as f64 / 4_294_967_296.0 - NTP_EPOCH_OFFSET
+}
+
+/// One SNTP exchange with `server`
+fn probe(server: &str) -> Probe {
+    let mut probe = Probe {
+        server: server.to_string(),
+        resolved: false,
+        answered: false,
+        offset_ms: None,
+        error: None,
+    };
+    let address = match (server, 123).to_socket_addrs().map(|mut a| a.next()) {
+        Ok(Some(address)) => address,
+        Ok(None) | Err(_) => {
+            probe.error = Some("does not resolve".to_string());
+            return probe;
+        }
+    };
+    probe.resolved = true;
+
+    let exchange = || -> std::io::Result<f64> {
+        let bind = if address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.set_read_timeout(Some(TIMEOUT))?;
+        // LI 0, version 4, mode 3 (client)
+        let mut request = [0u8; 48];
+        request[0] = 0x23;
+        let sent = system::now();
+        socket.send_to(&request, address)?;
+        let mut reply = [0u8; 48];
+        let (len, _) = socket.recv_from(&mut reply)?;
+        let received = system::now();
+        if len < 48 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "short reply",
+            ));
+        }
+        let server_received = timestamp(&reply, 32);
+        let server_sent = timestamp(&reply, 40);
+        Ok(((sent - server_received) + (received - server_sent)) / 2.0 * 1000.0)
+    };
+    match exchange() {
+        Ok(offset) => {
+            probe.answered = true;
+            probe.offset_ms = Some(offset);
+        }
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            probe.error = Some("no answer on UDP 123".to_string())
+        }
+        Err(e) => probe.error = Some(e.to_string()),
+    }
+    probe
+}
+
+pub fn diagnose() -> PortDiagnostics {
+    let servers = configured_servers();
+    // Probed in parallel so unreachable servers cost one timeout, not several
+    let probes = std::thread::scope(|scope| {
+        let handles: Vec<_> = servers
+            .iter()
+            .map(|s| scope.spawn(move || probe(s)))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    });
+    let mut diag = PortDiagnostics {
+        probes,
+        ..PortDiagnostics::default()
+    };
+
+    let resolved = diag.probes.iter().filter(|p| p.resolved).count();
+    let answered = diag.probes.iter().filter(|p| p.answered).count();
+    if resolved == 0 {
+        diag.warnings
+            .push("No NTP server name resolves; check DNS before NTP".to_string());
+    } else if answered == 0 {
+        diag.warnings.push(
+            "No NTP server answered on UDP 123; a firewall is probably dropping NTP".to_string(),
+        );
+        diag.recommendations.push(
+            "Allow outgoing UDP 123, or use chrony with NTS (TCP 4460) or a local NTP server"
+                .to_string(),
+        );
+    }
+
+    diag
+}
+
+impl PortDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== NTP Reachability Diagnostics ===");
+        for p in &self.probes {
+            let detail = match (p.offset_ms, &p.error) {
+                (Some(offset), _) if verbose => format!("answered, offset {:+.1} ms", offset),
+                (Some(_), _) => "answered".to_string(),
+                (None, Some(e)) => e.clone(),
+                (None, None) => "no answer".to_string(),
+            };
+            println!("{} {}: {}", mark(p.answered), p.server, detail);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Hardware clock against system time
+//!
+//! The RTC sets the clock at boot, before NTP has a say. One that drifts
+//! far or is kept in local time (a Windows dual-boot habit) makes every
+//! boot start wrong, and local time jumps an hour at each DST change.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use crate::timedated;
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const RTC: &str = "/sys/class/rtc/rtc0";
+
+/// Drift the RTC's one-second resolution does not explain
+pub const DRIFT_LIMIT_SECS: f64 = 5.0;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RtcDiagnostics {
+    /// `None` when there is no RTC (containers, some VMs)
+    pub device: Option<String>,
+    /// RTC minus system time, in seconds
+    pub drift_secs: Option<f64>,
+    pub local_rtc: bool,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+pub fn diagnose() -> RtcDiagnostics {
+    let mut diag = RtcDiagnostics {
+        device: system::read(host::path(RTC).join("name")),
+        local_rtc: Bus::system()
+            .and_then(|bus| timedated::state(&bus))
+            .is_ok_and(|s| s.local_rtc),
+        ..RtcDiagnostics::default()
+    };
+    if diag.device.is_none() {
+        return diag;
+    }
+
+    if diag.local_rtc {
+        diag.warnings.push(
+            "The RTC is kept in local time; the clock is wrong after DST changes".to_string(),
+        );
+        diag.recommendations.push(
+            "Keep the RTC in UTC with `timedatectl set-local-rtc 0` (set Windows to UTC too)"
+                .to_string(),
+        );
+        // since_epoch reads the RTC as UTC, so drift is meaningless here
+        return diag;
+    }
+
+    if let Some(rtc) =
+        system::read(host::path(RTC).join("since_epoch")).and_then(|s| s.parse::<f64>().ok())
+    {
+        let drift = rtc - system::now();
+        diag.drift_secs = Some(drift);
+        if drift.abs() > DRIFT_LIMIT_SECS {
+            diag.warnings.push(format!(
+                "The RTC is {:.0} s {} system time",
+                drift.abs(),
+                if drift > 0.0 { "ahead of" } else { "behind" }
+            ));
+            diag.recommendations.push(
+                "Once NTP is synchronised, write the time back with `hwclock --systohc`"
+                    .to_string(),
+            );
+        }
+    }
+
+    diag
+}
+
+impl RtcDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== RTC Diagnostics ===");
+        let Some(device) = &self.device else {
+            println!("- No hardware clock");
+            println!();
+            return;
+        };
+        println!(
+            "{} RTC mode: {}",
+            mark(!self.local_rtc),
+            if self.local_rtc { "local time" } else { "UTC" }
+        );
+        if let Some(drift) = self.drift_secs {
+            println!(
+                "{} Drift: {:+.0} s",
+                mark(drift.abs() <= DRIFT_LIMIT_SECS),
+                drift
+            );
+        }
+        if verbose {
+            println!("- Device: {}", device);
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
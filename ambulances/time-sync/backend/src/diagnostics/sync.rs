@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! NTP synchronisation status
+//!
+//! Reads whether NTP is enabled and the clock synchronised from timedated,
+//! which daemon is running from the manager, and the server and offset
+//! from the daemon itself: timesyncd over the bus, `chronyc tracking` for
+//! chrony and `ntpq -p` for ntpd. Two daemons at once fight over the clock.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use crate::timedated;
+use serde::Serialize;
+use systemd_shim::bus::Bus;
+
+const TIMESYNC: &str = "org.freedesktop.timesync1";
+const TIMESYNC_PATH: &str = "/org/freedesktop/timesync1";
+const TIMESYNC_MANAGER: &str = "org.freedesktop.timesync1.Manager";
+
+/// Offset at which the clock is noticeably wrong
+pub const OFFSET_LIMIT_MS: f64 = 1000.0;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncDiagnostics {
+    /// Running NTP daemons, e.g. `chrony`
+    pub daemons: Vec<String>,
+    pub ntp_enabled: bool,
+    pub ntp_available: bool,
+    pub synchronized: bool,
+    /// Server the daemon is following
+    pub server: Option<String>,
+    /// Local clock minus server time, when the daemon reports it
+    pub offset_ms: Option<f64>,
+    /// Why timedated or the manager could not be asked
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Server and offset from `chronyc -n tracking`
+fn chrony() -> (Option<String>, Option<f64>) {
+    let Ok(tracking) = system::run("chronyc", &["-n", "tracking"]) else {
+        return (None, None);
+    };
+    let field = |key: &str| {
+        tracking.lines().find_map(|l| {
+            let (k, v) = l.split_once(':')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        })
+    };
+    // "C0A80001 (192.168.0.1)"
+    let server = field("Reference ID")
+        .and_then(|r| Some(r.split_once('(')?.1.trim_end_matches(')').to_string()));
+    // "0.000012345 seconds fast of NTP time"
+    let offset = field("System time").and_then(|t| {
+        let mut words = t.split_whitespace();
+        let seconds: f64 = words.next()?.parse().ok()?;
+        let sign = if words.nth(1)? == "slow" { -1.0 } else { 1.0 };
+        Some(sign * seconds * 1000.0)
+    });
+    (server, offset)
+}
+
+/// Server and offset of the selected peer (`*`) in `ntpq -pn`
+fn ntpd() -> (Option<String>, Option<f64>) {
+    let Ok(peers) = system::run("ntpq", &["-pn"]) else {
+        return (None, None);
+    };
+    let Some(selected) = peers.lines().find_map(|l| l.strip_prefix('*')) else {
+        return (None, None);
+    };
+    let fields: Vec<&str> = selected.split_whitespace().collect();
+    // remote refid st t when poll reach delay offset jitter
+    let offset = fields.get(8).and_then(|o| o.parse().ok());
+    (fields.first().map(|s| s.to_string()), offset)
+}
+
+fn timesyncd(bus: &Bus) -> Option<String> {
+    bus.get_property_string(TIMESYNC, TIMESYNC_PATH, TIMESYNC_MANAGER, "ServerName")
+        .ok()
+        .filter(|s| !s.is_empty())
+}
+
+fn read(diag: &mut SyncDiagnostics) -> std::io::Result<()> {
+    let bus = Bus::system()?;
+    let state = timedated::state(&bus)?;
+    diag.ntp_enabled = state.ntp;
+    diag.ntp_available = state.can_ntp;
+    diag.synchronized = state.synchronized;
+    for (_, name) in timedated::active_daemons(&bus)? {
+        if !diag.daemons.iter().any(|d| d == name) {
+            diag.daemons.push(name.to_string());
+        }
+    }
+    (diag.server, diag.offset_ms) = match diag.daemons.first().map(String::as_str) {
+        Some("chrony") => chrony(),
+        Some("ntpd") => ntpd(),
+        Some("timesyncd") => (timesyncd(&bus), None),
+        _ => (None, None),
+    };
+    Ok(())
+}
+
+pub fn diagnose() -> SyncDiagnostics {
+    let mut diag = SyncDiagnostics::default();
+    if let Err(e) = read(&mut diag) {
+        diag.error = Some(format!("timedated not reachable: {}", e));
+        diag.warnings
+            .push("Cannot tell whether the clock is synchronised".to_string());
+        return diag;
+    }
+
+    if diag.daemons.is_empty() {
+        diag.warnings
+            .push("No NTP service is running; the clock drifts freely".to_string());
+        if diag.ntp_available {
+            diag.recommendations
+                .push("Enable NTP: time-sync-ambulance repair ntp".to_string());
+        } else {
+            diag.recommendations
+                .push("Install systemd-timesyncd or chrony, then enable NTP".to_string());
+        }
+    } else if diag.daemons.len() > 1 {
+        diag.warnings.push(format!(
+            "Several NTP services are running ({}) and will fight over the clock",
+            diag.daemons.join(", ")
+        ));
+        diag.recommendations.push(format!(
+            "Keep {} and disable the others with `systemctl disable --now`",
+            diag.daemons[0]
+        ));
+    } else if !diag.synchronized {
+        diag.warnings.push(format!(
+            "{} is running but the clock is not synchronised",
+            diag.daemons[0]
+        ));
+        diag.recommendations
+            .push("Force a sync: time-sync-ambulance repair sync".to_string());
+    }
+    if let Some(offset) = diag.offset_ms.filter(|o| o.abs() > OFFSET_LIMIT_MS) {
+        diag.warnings
+            .push(format!("Clock is {:.0} ms off the NTP server", offset));
+    }
+
+    diag
+}
+
+impl SyncDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Time Sync Diagnostics ===");
+        if let Some(e) = &self.error {
+            println!("✗ {}", e);
+            print_notes(&self.warnings, &self.recommendations);
+            return;
+        }
+        println!(
+            "{} NTP service: {}",
+            mark(self.daemons.len() == 1),
+            if self.daemons.is_empty() {
+                "none".to_string()
+            } else {
+                self.daemons.join(", ")
+            }
+        );
+        println!(
+            "{} Synchronised: {}",
+            mark(self.synchronized),
+            if self.synchronized { "yes" } else { "no" }
+        );
+        if verbose {
+            if let Some(server) = &self.server {
+                println!("- Server: {}", server);
+            }
+            if let Some(offset) = self.offset_ms {
+                println!("- Offset: {:+.3} ms", offset);
+            }
+            println!(
+                "- NTP enabled in timedated: {}",
+                if self.ntp_enabled { "yes" } else { "no" }
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
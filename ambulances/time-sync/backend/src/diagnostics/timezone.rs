@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Timezone configuration
+//!
+//! `/etc/localtime` must link into the zoneinfo database; when it is
+//! missing or dangling, glibc silently falls back to UTC. Debian-based
+//! systems also keep the name in `/etc/timezone`, and a `TZ` variable
+//! overrides both for whoever has it set.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use crate::timedated;
+use serde::Serialize;
+use std::path::Path;
+use systemd_shim::bus::Bus;
+
+const LOCALTIME: &str = "/etc/localtime";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TimezoneDiagnostics {
+    /// Zone timedated reports
+    pub timezone: Option<String>,
+    /// Zone `/etc/localtime` links to
+    pub localtime: Option<String>,
+    /// `/etc/timezone`, where present
+    pub etc_timezone: Option<String>,
+    /// `TZ` of this process
+    pub tz_env: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Zone name from the `/etc/localtime` link, if it resolves
+fn localtime_zone() -> Result<String, String> {
+    let target = std::fs::read_link(LOCALTIME)
+        .map_err(|_| format!("{} is missing or not a symlink", LOCALTIME))?;
+    let target_str = target.to_string_lossy();
+    let zone = target_str
+        .split_once("zoneinfo/")
+        .map(|(_, z)| z.to_string())
+        .ok_or_else(|| format!("{} points outside zoneinfo: {}", LOCALTIME, target_str))?;
+    let resolved = Path::new("/etc").join(&target);
+    if !resolved.exists() {
+        return Err(format!("{} points to missing zone {}", LOCALTIME, zone));
+    }
+    Ok(zone)
+}
+
+pub fn diagnose() -> TimezoneDiagnostics {
+    let mut diag = TimezoneDiagnostics {
+        timezone: Bus::system()
+            .and_then(|bus| timedated::state(&bus))
+            .ok()
+            .map(|s| s.timezone)
+            .filter(|z| !z.is_empty()),
+        etc_timezone: system::read("/etc/timezone"),
+        tz_env: std::env::var("TZ").ok(),
+        ..TimezoneDiagnostics::default()
+    };
+
+    match localtime_zone() {
+        Ok(zone) => diag.localtime = Some(zone),
+        Err(e) => {
+            diag.warnings
+                .push(format!("{}; programs fall back to UTC", e));
+            diag.recommendations
+                .push("Set the zone with `timedatectl set-timezone <Region/City>`".to_string());
+        }
+    }
+    if let (Some(link), Some(file)) = (&diag.localtime, &diag.etc_timezone) {
+        if link != file {
+            diag.warnings.push(format!(
+                "/etc/timezone says {} but /etc/localtime is {}",
+                file, link
+            ));
+            diag.recommendations.push(format!(
+                "Run `timedatectl set-timezone {}` to make them agree",
+                link
+            ));
+        }
+    }
+    if let (Some(tz), Some(link)) = (&diag.tz_env, &diag.localtime) {
+        let tz = tz.trim_start_matches(':');
+        if !tz.is_empty() && tz != link {
+            diag.warnings.push(format!(
+                "TZ={} overrides the system zone {} for this session",
+                tz, link
+            ));
+        }
+    }
+
+    diag
+}
+
+impl TimezoneDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Timezone Diagnostics ===");
+        println!(
+            "{} Timezone: {}",
+            mark(self.localtime.is_some()),
+            self.localtime
+                .as_deref()
+                .or(self.timezone.as_deref())
+                .unwrap_or("unset (UTC)")
+        );
+        if verbose {
+            if let Some(file) = &self.etc_timezone {
+                println!("- /etc/timezone: {}", file);
+            }
+            if let Some(tz) = &self.tz_env {
+                println!("- TZ: {}", tz);
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,148 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Time Sync Ambulance backend
+//!
+//! Diagnoses NTP synchronisation across timesyncd, chrony and ntpd, RTC
+//! drift and mode, timezone configuration and blocked NTP traffic, and
+//! can enable NTP and force a sync. `--json`
+//! output follows the network ambulance's report model.
+
+mod diagnostics;
+mod repairs;
+mod report;
+mod system;
+mod timedated;
+
+use ambulance_core::error::{exit, Error};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("Time Sync Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: time-sync-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all time diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+}
+
+fn run_diagnose(verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("Time Sync Ambulance");
+    println!("===================\n");
+    result.sync.print(verbose);
+    result.rtc.print(verbose);
+    result.timezone.print(verbose);
+    result.ports.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status() -> ExitCode {
+    let result = diagnostics::run();
+    let sections = [
+        ("Sync", &result.sync.warnings),
+        ("RTC", &result.rtc.warnings),
+        ("Timezone", &result.timezone.warnings),
+        ("NTP reachability", &result.ports.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    exit::findings(result.has_warnings())
+}
+
+fn run_repair(target: &str, json: bool) -> ExitCode {
+    if let Err(e) = ambulance_privilege::require(&format!("time-sync-ambulance repair {}", target))
+    {
+        return fail(e, json);
+    }
+
+    let result = match repairs::run(target) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [
+        ("ntp", "Enable NTP", &result.ntp_repair),
+        ("sync", "Immediate Sync", &result.sync_repair),
+    ];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("Time Sync Ambulance - Repair Mode");
+        println!("=================================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !a.starts_with('-'))
+        .collect();
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, json),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(),
+        Some("version") => {
+            println!("Time Sync Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Time repairs, one module per target
+
+pub mod ntp;
+pub mod sync;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["ntp", "sync", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `all` enables NTP before syncing, so a stopped daemon is started first.
+pub fn run(target: &str) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        ntp_repair: if selected("ntp") {
+            ntp::repair()
+        } else {
+            RepairOutcome::default()
+        },
+        sync_repair: if selected("sync") {
+            sync::repair()
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
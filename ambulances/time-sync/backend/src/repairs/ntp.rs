@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Enable NTP through timedated
+//!
+//! timedated enables and starts whichever NTP service it manages (usually
+//! timesyncd, or chrony where it is installed), as `timedatectl set-ntp
+//! true` does.
+
+use crate::report::RepairOutcome;
+use crate::timedated;
+use systemd_shim::bus::Bus;
+
+pub fn repair() -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            result.errors.push(format!("system bus: {}", e));
+            return result;
+        }
+    };
+    let state = match timedated::state(&bus) {
+        Ok(state) => state,
+        Err(e) => {
+            result.errors.push(format!("timedated: {}", e));
+            return result;
+        }
+    };
+    let running = timedated::active_daemons(&bus).is_ok_and(|d| !d.is_empty());
+    if state.ntp && running {
+        return RepairOutcome::not_needed("NTP is already enabled, no repair needed");
+    }
+    if !state.can_ntp {
+        result.errors.push(
+            "No NTP service timedated can manage is installed; install systemd-timesyncd or chrony"
+                .to_string(),
+        );
+        return result;
+    }
+
+    match bus.set_ntp(true) {
+        Ok(()) => result.actions.push("Enabled NTP".to_string()),
+        Err(e) => result.errors.push(format!("SetNTP: {}", e)),
+    }
+    match timedated::active_daemons(&bus) {
+        Ok(daemons) if !daemons.is_empty() => result.actions.push(format!(
+            "{} is running",
+            daemons
+                .iter()
+                .map(|(unit, _)| unit.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        Ok(_) => result
+            .errors
+            .push("NTP is enabled but no NTP service started".to_string()),
+        Err(e) => result.errors.push(format!("listing units: {}", e)),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Force an immediate sync
+//!
+//! chrony is told to step the clock now (`chronyc makestep`); timesyncd
+//! and ntpd are restarted, which makes them query their servers at once
+//! and lets ntpd step a large offset. The kernel's synchronised flag is
+//! then watched for a while.
+
+use crate::report::RepairOutcome;
+use crate::system;
+use crate::timedated;
+use std::time::{Duration, Instant};
+use systemd_shim::bus::Bus;
+
+/// How long the clock has to report synchronised
+const SETTLE: Duration = Duration::from_secs(15);
+
+fn wait_for_sync(bus: &Bus) -> bool {
+    let deadline = Instant::now() + SETTLE;
+    loop {
+        if timedated::state(bus).is_ok_and(|s| s.synchronized) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+pub fn repair() -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let bus = match Bus::system() {
+        Ok(bus) => bus,
+        Err(e) => {
+            result.errors.push(format!("system bus: {}", e));
+            return result;
+        }
+    };
+    let daemons = match timedated::active_daemons(&bus) {
+        Ok(daemons) => daemons,
+        Err(e) => {
+            result.errors.push(format!("listing units: {}", e));
+            return result;
+        }
+    };
+    let Some((unit, name)) = daemons.first() else {
+        result.errors.push(
+            "No NTP service is running; enable one first with: time-sync-ambulance repair ntp"
+                .to_string(),
+        );
+        return result;
+    };
+
+    if *name == "chrony" {
+        match system::run("chronyc", &["makestep"]) {
+            Ok(_) => result
+                .actions
+                .push("Stepped the clock with chrony".to_string()),
+            Err(e) => result.errors.push(e),
+        }
+    } else {
+        match bus.restart_unit(unit, "replace") {
+            Ok(_) => result.actions.push(format!("Restarted {}", unit)),
+            Err(e) => result.errors.push(format!("{}: {}", unit, e)),
+        }
+    }
+    if result.errors.is_empty() {
+        if wait_for_sync(&bus) {
+            result.actions.push("Clock is synchronised".to_string());
+        } else {
+            result.errors.push(format!(
+                "Clock not synchronised after {} s; see time-sync-ambulance diagnose for blocked NTP",
+                SETTLE.as_secs()
+            ));
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Clock state as timedated and the manager see it
+//!
+//! timedated is the authority `timedatectl` shows: it knows the zone, the
+//! RTC mode, whether an NTP service is installed and enabled, and whether
+//! the kernel clock is synchronised. Which NTP daemon actually runs is read
+//! from the manager's unit list.
+
+use std::io;
+use systemd_shim::bus::{self, Bus};
+
+/// NTP daemons by unit name, with the name used in reports
+pub const DAEMONS: &[(&str, &str)] = &[
+    ("systemd-timesyncd.service", "timesyncd"),
+    ("chrony.service", "chrony"),
+    ("chronyd.service", "chrony"),
+    ("ntp.service", "ntpd"),
+    ("ntpd.service", "ntpd"),
+    ("ntpsec.service", "ntpd"),
+    ("openntpd.service", "openntpd"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct State {
+    pub timezone: String,
+    /// RTC kept in local time rather than UTC
+    pub local_rtc: bool,
+    /// An NTP service timedated can manage is installed
+    pub can_ntp: bool,
+    pub ntp: bool,
+    /// The kernel clock is marked synchronised
+    pub synchronized: bool,
+}
+
+pub fn state(bus: &Bus) -> io::Result<State> {
+    let path = bus::TIMEDATE_PATH;
+    let flag = |name| bus.get_property_bool(bus::TIMEDATE, path, bus::TIMEDATE, name);
+    Ok(State {
+        timezone: bus.get_property_string(bus::TIMEDATE, path, bus::TIMEDATE, "Timezone")?,
+        local_rtc: flag("LocalRTC")?,
+        can_ntp: flag("CanNTP")?,
+        ntp: flag("NTP")?,
+        synchronized: flag("NTPSynchronized")?,
+    })
+}
+
+/// Active NTP daemons, as `(unit, name)`
+pub fn active_daemons(bus: &Bus) -> io::Result<Vec<(String, &'static str)>> {
+    Ok(bus
+        .list_units()?
+        .into_iter()
+        .filter(|u| u.active_state == "active")
+        .filter_map(|u| {
+            let (_, name) = DAEMONS.iter().find(|(unit, _)| *unit == u.name)?;
+            Some((u.name, *name))
+        })
+        .collect())
+}
@@ -0,0 +1,324 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Applications broken by their own cache or settings
+//!
+//! An application that crashes again and again for one user, or logs
+//! that a file is corrupt, usually trips over something in that user's
+//! `~/.cache` or `~/.config`. Crashes come from systemd-coredump's
+//! journal entries, corruption from the user's own error messages. The
+//! suspects are the paths those messages name, and the cache and config
+//! directories named after the program.
+
+use crate::report::{mark, print_notes, TOOL};
+use crate::users::User;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_shim::journal::{self, Journal};
+
+/// `MESSAGE_ID` of systemd-coredump's crash reports
+const COREDUMP_MESSAGE: &str = "fc2e22bc6ee647b6b90729ab34a250b1";
+
+const WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Crashes in the window from which a program counts as broken
+const MIN_CRASHES: u32 = 3;
+
+/// Entries read at most from each of the two journal passes
+const MAX_ENTRIES: usize = 5000;
+
+/// Words in an error message that point at a damaged file, lower case
+const CORRUPTION: &[&str] = &[
+    "corrupt",
+    "malformed",
+    "truncated",
+    "unexpected end of file",
+    "invalid header",
+    "bad magic",
+    "checksum mismatch",
+    "not a database",
+];
+
+/// Where per-user state lives, relative to the home
+const XDG_DIRS: &[&str] = &[".cache", ".config", ".local/share"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppTrouble {
+    pub user: String,
+    /// `comm` or syslog identifier
+    pub process: String,
+    pub crashes: u32,
+    /// Corruption errors logged
+    pub errors: u32,
+    pub last_error: Option<String>,
+    /// Seconds since the epoch of the newest crash or error
+    pub last: u64,
+    /// Paths the program most likely chokes on
+    pub suspects: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AppDiagnostics {
+    pub apps: Vec<AppTrouble>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The entry below an XDG directory that `message` names, if any
+fn named_path(message: &str, user: &User) -> Option<PathBuf> {
+    XDG_DIRS.iter().find_map(|dir| {
+        let base = user.home.join(dir);
+        let prefix = format!("{}/", base.display());
+        let rest = &message[message.find(&prefix)? + prefix.len()..];
+        let name: String = rest
+            .chars()
+            .take_while(|c| !matches!(c, '/' | '\'' | '"' | ' ' | ':' | ',' | ')'))
+            .collect();
+        (!name.is_empty()).then(|| base.join(name))
+    })
+}
+
+/// Cache and config directories named like `process`, in any case,
+/// including ones the apps repair has moved aside
+fn named_like(home: &Path, process: &str) -> Vec<PathBuf> {
+    let wanted = process.to_lowercase();
+    let suffix = format!(".{}-", TOOL);
+    let mut found: Vec<PathBuf> = Vec::new();
+    for dir in XDG_DIRS {
+        let base = home.join(dir);
+        for entry in std::fs::read_dir(&base).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let original = name.rsplit_once(&suffix).map_or(name.as_str(), |(o, _)| o);
+            let path = base.join(original);
+            if original.to_lowercase() == wanted && !found.contains(&path) {
+                found.push(path);
+            }
+        }
+    }
+    found
+}
+
+/// Whether `path` was moved aside by the apps repair at or after `time`
+fn reset_since(path: &Path, time: u64) -> bool {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let prefix = format!("{}.{}-", name.to_string_lossy(), TOOL);
+    std::fs::read_dir(parent)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .strip_prefix(&prefix)?
+                .parse::<u64>()
+                .ok()
+        })
+        .any(|stamp| stamp >= time)
+}
+
+type Key = (String, String);
+
+fn entry<'a>(
+    found: &'a mut BTreeMap<Key, AppTrouble>,
+    user: &User,
+    process: String,
+) -> &'a mut AppTrouble {
+    found
+        .entry((user.name.clone(), process.clone()))
+        .or_insert(AppTrouble {
+            user: user.name.clone(),
+            process,
+            crashes: 0,
+            errors: 0,
+            last_error: None,
+            last: 0,
+            suspects: Vec::new(),
+        })
+}
+
+fn scan(users: &[User], since: u64) -> std::io::Result<BTreeMap<Key, AppTrouble>> {
+    let by_uid: BTreeMap<String, &User> = users.iter().map(|u| (u.uid.to_string(), u)).collect();
+    let mut found: BTreeMap<Key, AppTrouble> = BTreeMap::new();
+    if users.is_empty() {
+        return Ok(found);
+    }
+
+    let mut crashes = Journal::open(journal::LOCAL_ONLY)?;
+    crashes.add_match(&format!("MESSAGE_ID={}", COREDUMP_MESSAGE))?;
+    crashes.seek_tail()?;
+    for _ in 0..MAX_ENTRIES {
+        if !crashes.previous_entry()? {
+            break;
+        }
+        // Newest first, so the first old entry ends the window
+        let time = crashes.realtime_usec()? / 1_000_000;
+        if time < since {
+            break;
+        }
+        let (Some(uid), Some(comm)) = (
+            crashes.field("COREDUMP_UID"),
+            crashes.field("COREDUMP_COMM"),
+        ) else {
+            continue;
+        };
+        if let Some(user) = by_uid.get(&uid) {
+            let app = entry(&mut found, user, comm);
+            app.crashes += 1;
+            app.last = app.last.max(time);
+        }
+    }
+
+    let mut errors = Journal::open(journal::LOCAL_ONLY)?;
+    for uid in by_uid.keys() {
+        errors.add_match(&format!("_UID={}", uid))?;
+    }
+    for priority in 0..=3 {
+        errors.add_match(&format!("PRIORITY={}", priority))?;
+    }
+    errors.seek_tail()?;
+    for _ in 0..MAX_ENTRIES {
+        if !errors.previous_entry()? {
+            break;
+        }
+        let time = errors.realtime_usec()? / 1_000_000;
+        if time < since {
+            break;
+        }
+        let (Some(uid), Some(message)) = (errors.field("_UID"), errors.field("MESSAGE")) else {
+            continue;
+        };
+        let Some(user) = by_uid.get(&uid) else {
+            continue;
+        };
+        let lower = message.to_lowercase();
+        if !CORRUPTION.iter().any(|w| lower.contains(w)) {
+            continue;
+        }
+        let Some(process) = errors
+            .field("SYSLOG_IDENTIFIER")
+            .or_else(|| errors.field("_COMM"))
+        else {
+            continue;
+        };
+        let path = named_path(&message, user);
+        let app = entry(&mut found, user, process);
+        app.errors += 1;
+        app.last = app.last.max(time);
+        if app.last_error.is_none() {
+            app.last_error = Some(message);
+        }
+        if let Some(path) = path.map(|p| p.display().to_string()) {
+            if !app.suspects.contains(&path) {
+                app.suspects.push(path);
+            }
+        }
+    }
+
+    for app in found.values_mut() {
+        let Some(user) = users.iter().find(|u| u.name == app.user) else {
+            continue;
+        };
+        for path in named_like(&user.home, &app.process) {
+            let path = path.display().to_string();
+            if !app.suspects.contains(&path) {
+                app.suspects.push(path);
+            }
+        }
+    }
+    // A program whose suspects were all reset after its last crash or
+    // error has not failed since; paths it has not recreated are dropped
+    found.retain(|_, app| {
+        let handled = app
+            .suspects
+            .iter()
+            .any(|path| reset_since(Path::new(path), app.last));
+        app.suspects
+            .retain(|path| Path::new(path).exists() && !reset_since(Path::new(path), app.last));
+        (app.crashes >= MIN_CRASHES || app.errors > 0) && !(handled && app.suspects.is_empty())
+    });
+    Ok(found)
+}
+
+pub fn diagnose(users: &[User]) -> AppDiagnostics {
+    let mut diag = AppDiagnostics::default();
+    match scan(users, now_secs().saturating_sub(WINDOW_SECS)) {
+        Ok(found) => diag.apps = found.into_values().collect(),
+        Err(e) => {
+            diag.warnings
+                .push(format!("Cannot read the journal: {}", e));
+            diag.error = Some(e.to_string());
+            return diag;
+        }
+    }
+
+    for app in &diag.apps {
+        if app.crashes >= MIN_CRASHES {
+            diag.warnings.push(format!(
+                "{} crashed {} time(s) for {} in the last 7 days",
+                app.process, app.crashes, app.user
+            ));
+        }
+        if let Some(error) = &app.last_error {
+            diag.warnings.push(format!(
+                "{} reports damaged data for {}: {}",
+                app.process, app.user, error
+            ));
+        }
+        if app.suspects.is_empty() {
+            diag.recommendations.push(format!(
+                "Run {} from a terminal as {} to see which file it chokes on",
+                app.process, app.user
+            ));
+        }
+    }
+    let users: Vec<&str> = diag
+        .apps
+        .iter()
+        .filter(|a| !a.suspects.is_empty())
+        .map(|a| a.user.as_str())
+        .collect();
+    if !users.is_empty() {
+        diag.recommendations.push(format!(
+            "Move the suspect cache and settings aside: user-env-ambulance repair apps{}",
+            match users.as_slice() {
+                [user, rest @ ..] if rest.iter().all(|u| u == user) => format!(" --user {}", user),
+                _ => String::new(),
+            }
+        ));
+    }
+    diag
+}
+
+impl AppDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Applications ===");
+        if self.error.is_none() && self.apps.is_empty() {
+            println!("✓ No repeated crash or damaged-data error in the last 7 days");
+        }
+        for app in &self.apps {
+            println!(
+                "{} {} ({}): {} crash(es), {} damaged-data error(s)",
+                mark(false),
+                app.process,
+                app.user,
+                app.crashes,
+                app.errors
+            );
+            if verbose {
+                for path in &app.suspects {
+                    println!("    suspect: {}", path);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
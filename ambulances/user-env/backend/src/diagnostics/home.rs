@@ -0,0 +1,259 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Home directory ownership and permissions
+//!
+//! Running a desktop program once under `sudo` leaves root owning a
+//! directory in `~/.config` or `~/.cache`, and the program fails for its
+//! user from then on; a root-owned `~/.Xauthority` or `~/.ICEauthority`
+//! sends the login screen round in a loop. Dotfiles in the home itself
+//! and the entries of the XDG directories are checked; other top-level
+//! entries may be shared on purpose. sshd refuses key logins through a
+//! group- or world-writable home or `~/.ssh`, and ssh will not use a
+//! private key others can read.
+
+use crate::report::{mark, print_notes};
+use crate::users::User;
+use serde::Serialize;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+
+/// Directories whose entries are checked, relative to the home
+const XDG_DIRS: &[&str] = &[
+    ".config",
+    ".cache",
+    ".local",
+    ".local/share",
+    ".local/state",
+];
+
+/// Files whose wrong owner stops a graphical login
+const SESSION_FILES: &[&str] = &[".Xauthority", ".ICEauthority"];
+
+/// Findings listed per user at most; the repair fixes all of them
+const MAX_FINDINGS: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub user: String,
+    pub path: String,
+    /// What is wrong, e.g. `owned by root`
+    pub problem: String,
+    /// Whether the user should own it, and everything below it
+    pub chown: bool,
+    /// Mode to set instead of the current one
+    pub mode: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserHome {
+    pub user: String,
+    pub home: String,
+    pub exists: bool,
+    pub findings: Vec<Finding>,
+    /// Findings beyond `MAX_FINDINGS`, not listed
+    pub more: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HomeDiagnostics {
+    pub homes: Vec<UserHome>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Name of the user with `uid`, or the number
+fn owner_name(uid: u32) -> String {
+    let pw = unsafe { libc::getpwuid(uid) };
+    if pw.is_null() {
+        return uid.to_string();
+    }
+    unsafe { std::ffi::CStr::from_ptr((*pw).pw_name) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+struct Check<'a> {
+    user: &'a User,
+    findings: Vec<Finding>,
+}
+
+impl Check<'_> {
+    /// Record a problem; a path both foreign-owned and too open is one finding
+    fn push(&mut self, path: &Path, problem: String, chown: bool, mode: Option<u32>) {
+        let shown = path.display().to_string();
+        if let Some(found) = self.findings.iter_mut().find(|f| f.path == shown) {
+            found.problem = format!("{}; {}", found.problem, problem);
+            found.chown |= chown;
+            found.mode = found.mode.or(mode);
+            return;
+        }
+        self.findings.push(Finding {
+            user: self.user.name.clone(),
+            path: shown,
+            problem,
+            chown,
+            mode,
+        });
+    }
+
+    /// Flag `path` if someone else owns it; symlinks are left alone
+    fn owner(&mut self, path: &Path) {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        if meta.file_type().is_symlink() || meta.uid() == self.user.uid {
+            return;
+        }
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let problem = if SESSION_FILES.contains(&name) {
+            format!(
+                "owned by {}, which breaks graphical logins",
+                owner_name(meta.uid())
+            )
+        } else {
+            format!("owned by {}", owner_name(meta.uid()))
+        };
+        self.push(path, problem, true, None);
+    }
+
+    /// Flag `path` if its mode has any of `forbidden`, clearing them
+    fn mode(&mut self, path: &Path, forbidden: u32, why: &str) {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        let mode = meta.permissions().mode() & 0o7777;
+        if meta.file_type().is_symlink() || mode & forbidden == 0 {
+            return;
+        }
+        self.push(
+            path,
+            format!("mode {:04o}, {}", mode, why),
+            false,
+            Some(mode & !forbidden),
+        );
+    }
+}
+
+pub fn check(user: &User) -> UserHome {
+    let mut check = Check {
+        user,
+        findings: Vec::new(),
+    };
+    let home = &user.home;
+    let exists = home.is_dir();
+    if exists {
+        check.owner(home);
+        check.mode(home, 0o022, "so sshd refuses key logins");
+
+        let dotfiles = std::fs::read_dir(home)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with('.'));
+        for entry in dotfiles {
+            check.owner(&entry.path());
+        }
+        for dir in XDG_DIRS {
+            let Ok(entries) = std::fs::read_dir(home.join(dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                check.owner(&entry.path());
+            }
+        }
+
+        let ssh = home.join(".ssh");
+        check.mode(&ssh, 0o077, "so sshd refuses key logins");
+        check.mode(&ssh.join("authorized_keys"), 0o022, "so sshd ignores it");
+        for entry in std::fs::read_dir(&ssh).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with("id_") && !name.ends_with(".pub") {
+                check.mode(&entry.path(), 0o077, "so ssh refuses to use the key");
+            }
+        }
+    }
+
+    let findings = check.findings;
+    let more = findings.len().saturating_sub(MAX_FINDINGS);
+    UserHome {
+        user: user.name.clone(),
+        home: home.display().to_string(),
+        exists,
+        findings,
+        more,
+    }
+}
+
+pub fn diagnose(users: &[User]) -> HomeDiagnostics {
+    let mut diag = HomeDiagnostics {
+        homes: users.iter().map(check).collect(),
+        ..HomeDiagnostics::default()
+    };
+
+    for home in &diag.homes {
+        if !home.exists {
+            diag.warnings
+                .push(format!("{}'s home {} does not exist", home.user, home.home));
+            diag.recommendations.push(format!(
+                "Create it with the skeleton files: mkhomedir_helper {}",
+                home.user
+            ));
+            continue;
+        }
+        let owned = home.findings.iter().filter(|f| f.chown).count();
+        let open = home.findings.iter().filter(|f| f.mode.is_some()).count();
+        if owned > 0 {
+            diag.warnings.push(format!(
+                "{} path(s) in {}'s home belong to someone else",
+                owned, home.user
+            ));
+        }
+        if open > 0 {
+            diag.warnings.push(format!(
+                "{} path(s) in {}'s home are too open for ssh",
+                open, home.user
+            ));
+        }
+        if owned + open > 0 {
+            diag.recommendations.push(format!(
+                "Fix them: user-env-ambulance repair ownership --user {}",
+                home.user
+            ));
+        }
+    }
+    diag
+}
+
+impl HomeDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Home Directories ===");
+        if self.homes.is_empty() {
+            println!("- No user to check");
+        }
+        for home in &self.homes {
+            println!(
+                "{} {}: {}",
+                mark(home.exists && home.findings.is_empty()),
+                home.user,
+                if !home.exists {
+                    format!("{} missing", home.home)
+                } else if home.findings.is_empty() {
+                    home.home.clone()
+                } else {
+                    format!("{}, {} problem(s)", home.home, home.findings.len())
+                }
+            );
+            if verbose {
+                for f in home.findings.iter().take(MAX_FINDINGS) {
+                    println!("    {}: {}", f.path, f.problem);
+                }
+                if home.more > 0 {
+                    println!("    ... and {} more", home.more);
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
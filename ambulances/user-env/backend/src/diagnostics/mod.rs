@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! User environment diagnostics, one module per report section
+//!
+//! Each section goes through every user's home, and they run as
+//! ambulance-core jobs at the same time, within the scan's budget. A
+//! section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod apps;
+pub mod home;
+pub mod quota;
+pub mod session;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use crate::users::User;
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a section may take; sizing the caches of every home walks
+/// a lot of files, and a home on a dead NFS server hangs
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+enum Section {
+    Home(home::HomeDiagnostics),
+    Apps(apps::AppDiagnostics),
+    Session(session::SessionDiagnostics),
+    Quota(quota::QuotaDiagnostics),
+}
+
+pub fn run(users: &[User]) -> DiagnosticResult {
+    let users: Arc<[User]> = users.into();
+    let job = |id, diagnose: fn(&[User]) -> Section| {
+        let users = Arc::clone(&users);
+        Job::blocking(id, move |_| Ok(diagnose(&users)))
+    };
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(job("home", |u| Section::Home(home::diagnose(u))))
+        .job(job("apps", |u| Section::Apps(apps::diagnose(u))))
+        .job(job("session", |u| Section::Session(session::diagnose(u))))
+        .job(job("quota", |u| Section::Quota(quota::diagnose(u))))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        home: Default::default(),
+        apps: Default::default(),
+        session: Default::default(),
+        quota: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Home(d)) => result.home = d,
+            Some(Section::Apps(d)) => result.apps = d,
+            Some(Section::Session(d)) => result.session = d,
+            Some(Section::Quota(d)) => result.quota = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "home" => &mut result.home.warnings,
+                    "apps" => &mut result.apps.warnings,
+                    "session" => &mut result.session.warnings,
+                    _ => &mut result.quota.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,222 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Disk quota and space left in each home
+//!
+//! A user over their block quota, or on a full filesystem, cannot save
+//! settings: applications lose them silently and the desktop may not
+//! start at all. Quotas are read with `quota` from the quota tools,
+//! where installed. For a user out of space, what `~/.cache` and the
+//! trash hold is measured, since both can go without losing anything.
+
+use crate::report::{mark, print_notes};
+use crate::scan::{self, human_bytes};
+use crate::system;
+use crate::users::User;
+use ambulance_privilege::userfs;
+use serde::Serialize;
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::process::Command;
+
+/// Filesystem use, in percent, from which a home counts as full
+const FULL_PERCENT: u64 = 95;
+
+/// What can be emptied without losing anything, relative to the home
+pub const DISPOSABLE: &[&str] = &[".cache", ".local/share/Trash"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Quota {
+    pub filesystem: String,
+    /// 1 KiB blocks, as `quota` counts them
+    pub used_kb: u64,
+    pub soft_kb: u64,
+    pub hard_kb: u64,
+}
+
+impl Quota {
+    pub fn exceeded(&self) -> bool {
+        (self.soft_kb > 0 && self.used_kb >= self.soft_kb)
+            || (self.hard_kb > 0 && self.used_kb * 100 >= self.hard_kb * FULL_PERCENT)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserSpace {
+    pub user: String,
+    /// Percent of the home's filesystem in use
+    pub filesystem_used_percent: Option<u64>,
+    pub quotas: Vec<Quota>,
+    /// Bytes in each of `DISPOSABLE`, measured only when out of space
+    pub disposable_bytes: Vec<(String, u64)>,
+}
+
+impl UserSpace {
+    pub fn out_of_space(&self) -> bool {
+        self.filesystem_used_percent
+            .is_some_and(|p| p >= FULL_PERCENT)
+            || self.quotas.iter().any(Quota::exceeded)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QuotaDiagnostics {
+    /// Whether the quota tools are installed
+    pub quota_tool: bool,
+    pub users: Vec<UserSpace>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// Percentage used of the filesystem holding `path`
+// statvfs field widths differ between targets
+#[allow(clippy::unnecessary_cast)]
+fn used_percent(path: &Path) -> Option<u64> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    let used = (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64);
+    let usable = used + stat.f_bavail as u64;
+    (usable > 0).then(|| used * 100 / usable)
+}
+
+/// The user's block quotas; `quota` exits non-zero when one is exceeded,
+/// so its status is ignored
+fn quotas(user: &str) -> Vec<Quota> {
+    let Ok(output) = Command::new("quota")
+        .args(["--no-wrap", "--user", user])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip_while(|l| !l.trim_start().starts_with("Filesystem"))
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number =
+                |i: usize| -> Option<u64> { fields.get(i)?.trim_end_matches('*').parse().ok() };
+            Some(Quota {
+                filesystem: fields.first()?.to_string(),
+                used_kb: number(1)?,
+                soft_kb: number(2)?,
+                hard_kb: number(3)?,
+            })
+        })
+        .filter(|q| q.soft_kb > 0 || q.hard_kb > 0)
+        .collect()
+}
+
+pub fn check(user: &User, quota_tool: bool) -> UserSpace {
+    let mut space = UserSpace {
+        user: user.name.clone(),
+        filesystem_used_percent: used_percent(&user.home),
+        quotas: if quota_tool {
+            quotas(&user.name)
+        } else {
+            Vec::new()
+        },
+        disposable_bytes: Vec::new(),
+    };
+    if space.out_of_space() {
+        space.disposable_bytes = DISPOSABLE
+            .iter()
+            .map(|dir| {
+                // A symlink out of the home is left alone, so holds nothing
+                let plain = userfs::is_plain_dir(&user.home, Path::new(dir));
+                let bytes = if plain {
+                    scan::size(&user.home.join(dir))
+                } else {
+                    0
+                };
+                (dir.to_string(), bytes)
+            })
+            .collect();
+    }
+    space
+}
+
+pub fn diagnose(users: &[User]) -> QuotaDiagnostics {
+    let quota_tool = system::has("quota");
+    let mut diag = QuotaDiagnostics {
+        quota_tool,
+        users: users
+            .iter()
+            .filter(|u| u.home.is_dir())
+            .map(|u| check(u, quota_tool))
+            .collect(),
+        ..QuotaDiagnostics::default()
+    };
+
+    for space in &diag.users {
+        if let Some(percent) = space.filesystem_used_percent.filter(|p| *p >= FULL_PERCENT) {
+            diag.warnings.push(format!(
+                "The filesystem holding {}'s home is {}% full",
+                space.user, percent
+            ));
+        }
+        for q in space.quotas.iter().filter(|q| q.exceeded()) {
+            diag.warnings.push(format!(
+                "{} has used {} of a {} quota on {}",
+                space.user,
+                human_bytes(q.used_kb * 1024),
+                human_bytes(if q.soft_kb > 0 { q.soft_kb } else { q.hard_kb } * 1024),
+                q.filesystem
+            ));
+        }
+        let disposable: u64 = space.disposable_bytes.iter().map(|(_, b)| b).sum();
+        if disposable > 0 {
+            diag.recommendations.push(format!(
+                "Empty {}'s cache and trash ({}): user-env-ambulance repair quota --user {}",
+                space.user,
+                human_bytes(disposable),
+                space.user
+            ));
+        } else if space.out_of_space() {
+            diag.recommendations.push(format!(
+                "Nothing disposable in {}'s home; raise the quota (edquota {}) or free space elsewhere",
+                space.user, space.user
+            ));
+        }
+    }
+    diag
+}
+
+impl QuotaDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Space and Quota ===");
+        if !self.quota_tool {
+            println!("- quota not installed; checking free space only");
+        }
+        for space in &self.users {
+            println!(
+                "{} {}: filesystem {}, {} quota(s)",
+                mark(!space.out_of_space()),
+                space.user,
+                space
+                    .filesystem_used_percent
+                    .map_or("unknown".to_string(), |p| format!("{}% full", p)),
+                space.quotas.len()
+            );
+            if verbose {
+                for q in &space.quotas {
+                    println!(
+                        "    {}: {} used, soft {}, hard {}",
+                        q.filesystem,
+                        human_bytes(q.used_kb * 1024),
+                        human_bytes(q.soft_kb * 1024),
+                        human_bytes(q.hard_kb * 1024)
+                    );
+                }
+                for (dir, bytes) in &space.disposable_bytes {
+                    println!("    ~/{}: {}", dir, human_bytes(*bytes));
+                }
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
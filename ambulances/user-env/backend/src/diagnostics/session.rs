@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Per-user service manager, runtime directory and D-Bus session bus
+//!
+//! Only users with a running `user@UID.service` or a runtime directory
+//! are checked; the others have no session to break. The bus socket
+//! sits in the runtime directory, which must belong to the user and be
+//! closed to everyone else, or the broker and every client refuse it.
+//! A socket nobody listens on means the broker died, and settings,
+//! notifications and most desktop applications with it.
+
+use crate::report::{mark, print_notes};
+use crate::users::User;
+use serde::Serialize;
+use std::io::ErrorKind;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use systemd_shim::bus::{self, Bus};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserSession {
+    pub user: String,
+    pub uid: u32,
+    /// `ActiveState` of `user@UID.service`, `None` if not loaded
+    pub manager_state: Option<String>,
+    pub runtime_dir: String,
+    /// What is wrong with the runtime directory's owner or mode
+    pub runtime_dir_problem: Option<String>,
+    /// Why the session bus socket does not accept connections
+    pub bus_error: Option<String>,
+}
+
+impl UserSession {
+    pub fn manager_unit(&self) -> String {
+        format!("user@{}.service", self.uid)
+    }
+
+    pub fn healthy(&self) -> bool {
+        self.manager_state.as_deref() != Some("failed")
+            && self.runtime_dir_problem.is_none()
+            && self.bus_error.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionDiagnostics {
+    pub sessions: Vec<UserSession>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn manager_state(bus: Option<&Bus>, uid: u32) -> Option<String> {
+    let bus = bus?;
+    let path = bus::unit_path(&format!("user@{}.service", uid)).ok()?;
+    let load = bus
+        .get_property_string(bus::SYSTEMD, &path, bus::UNIT, "LoadState")
+        .ok()?;
+    if load != "loaded" {
+        return None;
+    }
+    bus.get_property_string(bus::SYSTEMD, &path, bus::UNIT, "ActiveState")
+        .ok()
+}
+
+pub fn check(bus: Option<&Bus>, user: &User) -> Option<UserSession> {
+    let manager_state = manager_state(bus, user.uid).filter(|s| s != "inactive");
+    let runtime_dir = user.runtime_dir();
+    let meta = std::fs::metadata(&runtime_dir).ok();
+    if manager_state.is_none() && meta.is_none() {
+        return None;
+    }
+
+    let runtime_dir_problem = meta.as_ref().and_then(|meta| {
+        let mode = meta.permissions().mode() & 0o7777;
+        if meta.uid() != user.uid {
+            Some(format!("owned by UID {}", meta.uid()))
+        } else if mode != 0o700 {
+            Some(format!("mode {:04o} instead of 0700", mode))
+        } else {
+            None
+        }
+    });
+    let socket = runtime_dir.join("bus");
+    let bus_error = match (meta, UnixStream::connect(&socket)) {
+        (None, _) => Some(format!("{} is missing", runtime_dir.display())),
+        (_, Ok(_)) => None,
+        (_, Err(e)) => Some(match e.kind() {
+            ErrorKind::NotFound => format!("no socket at {}", socket.display()),
+            ErrorKind::ConnectionRefused => {
+                format!("nothing is listening on {}", socket.display())
+            }
+            _ => format!("{}: {}", socket.display(), e),
+        }),
+    };
+
+    Some(UserSession {
+        user: user.name.clone(),
+        uid: user.uid,
+        manager_state,
+        runtime_dir: runtime_dir.display().to_string(),
+        runtime_dir_problem,
+        bus_error,
+    })
+}
+
+pub fn diagnose(users: &[User]) -> SessionDiagnostics {
+    let bus = Bus::system().ok();
+    let mut diag = SessionDiagnostics {
+        sessions: users
+            .iter()
+            .filter_map(|u| check(bus.as_ref(), u))
+            .collect(),
+        ..SessionDiagnostics::default()
+    };
+
+    for s in &diag.sessions {
+        if s.manager_state.as_deref() == Some("failed") {
+            diag.warnings.push(format!(
+                "{}'s service manager {} failed",
+                s.user,
+                s.manager_unit()
+            ));
+        }
+        if let Some(problem) = &s.runtime_dir_problem {
+            diag.warnings
+                .push(format!("{} is {}", s.runtime_dir, problem));
+        }
+        if let Some(e) = &s.bus_error {
+            diag.warnings
+                .push(format!("{}'s session bus is down: {}", s.user, e));
+        }
+        if !s.healthy() {
+            diag.recommendations.push(format!(
+                "Repair it: user-env-ambulance repair session --user {}",
+                s.user
+            ));
+        }
+    }
+    diag
+}
+
+impl SessionDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Sessions ===");
+        if self.sessions.is_empty() {
+            println!("- No user is logged in");
+        }
+        for s in &self.sessions {
+            println!(
+                "{} {}: manager {}, session bus {}",
+                mark(s.healthy()),
+                s.user,
+                s.manager_state.as_deref().unwrap_or("not running"),
+                if s.bus_error.is_none() { "up" } else { "down" }
+            );
+            if verbose {
+                println!(
+                    "    {}: {}",
+                    s.runtime_dir,
+                    s.runtime_dir_problem.as_deref().unwrap_or("ok")
+                );
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! User Environment Ambulance backend
+//!
+//! Checks what breaks one user while the system is fine: home directory
+//! ownership and permissions, applications choking on their own cache or
+//! settings, the per-user service manager and session bus, and quota.
+//! Repairs act on one user with `--user`, or on every user, asking
+//! first. `--json` output follows the network ambulance's report model.
+
+mod diagnostics;
+mod repairs;
+mod report;
+mod scan;
+mod system;
+mod users;
+
+use ambulance_core::error::{exit, Category, Error};
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+fn print_help() {
+    println!("User Environment Ambulance v{}", report::VERSION);
+    println!();
+    println!("Usage: user-env-ambulance <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  diagnose             Run all user environment diagnostics");
+    println!(
+        "  repair <target>      Repair {}",
+        repairs::TARGETS.join("|")
+    );
+    println!("  status               Quick health summary");
+    println!("  version              Show version");
+    println!("  help                 Show this help");
+    println!();
+    println!("Options:");
+    println!("  -v, --verbose        Verbose output");
+    println!("  -j, --json           JSON output format");
+    println!("  -y, --yes            Approve changes that ask for confirmation");
+    println!("  -u, --user <name>    Check or repair only this user");
+}
+
+fn run_diagnose(users: &[users::User], verbose: bool, json: bool) -> ExitCode {
+    let result = diagnostics::run(users);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+        return exit::findings(result.has_warnings());
+    }
+
+    println!("User Environment Ambulance");
+    println!("==========================\n");
+    result.home.print(verbose);
+    result.apps.print(verbose);
+    result.session.print(verbose);
+    result.quota.print(verbose);
+    exit::findings(result.has_warnings())
+}
+
+fn run_status(users: &[users::User]) -> ExitCode {
+    let result = diagnostics::run(users);
+    let sections = [
+        ("Homes", &result.home.warnings),
+        ("Applications", &result.apps.warnings),
+        ("Sessions", &result.session.warnings),
+        ("Quota", &result.quota.warnings),
+    ];
+    for (name, warnings) in sections {
+        println!(
+            "{}: {}",
+            name,
+            if warnings.is_empty() { "OK" } else { "PROBLEM" }
+        );
+    }
+    println!(
+        "Users checked: {}, logged in: {}",
+        users.len(),
+        result.session.sessions.len()
+    );
+    exit::findings(result.has_warnings())
+}
+
+/// Ask on the terminal; without one (or in JSON mode) nothing is approved
+fn prompt(question: &str, json: bool) -> bool {
+    if json || !std::io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn run_repair(target: &str, users: &[users::User], json: bool, yes: bool) -> ExitCode {
+    if let Err(e) = ambulance_privilege::require(&format!("user-env-ambulance repair {}", target)) {
+        return fail(e, json);
+    }
+
+    let mut confirm = |question: &str| yes || prompt(question, json);
+    let result = match repairs::run(target, users, &mut confirm) {
+        Ok(result) => result,
+        Err(e) => return fail(e, json),
+    };
+    let outcomes = [
+        (
+            "ownership",
+            "Ownership and Permissions",
+            &result.ownership_repair,
+        ),
+        ("apps", "Application Data Reset", &result.apps_repair),
+        ("session", "Session Repair", &result.session_repair),
+        ("quota", "Cache and Trash Cleanup", &result.quota_repair),
+    ];
+    let failed = outcomes
+        .iter()
+        .any(|(name, _, o)| (target == *name || target == "all") && !o.success);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        );
+    } else {
+        println!("User Environment Ambulance - Repair Mode");
+        println!("========================================\n");
+        for (name, title, outcome) in outcomes {
+            if target == name || target == "all" {
+                outcome.print(title);
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::from(exit::FAILED)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Report `e` the way `--json` asks, and end with its exit code
+fn fail(e: Error, json: bool) -> ExitCode {
+    e.report(report::TOOL, report::VERSION, json)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v" || a == "--verbose");
+    let json = args.iter().any(|a| a == "-j" || a == "--json");
+    let yes = args.iter().any(|a| a == "-y" || a == "--yes");
+    let user_flag = args.iter().position(|a| a == "-u" || a == "--user");
+    let only = match user_flag {
+        Some(i) => args.get(i + 1).map(String::as_str),
+        None => args.iter().find_map(|a| a.strip_prefix("--user=")),
+    };
+    let positional: Vec<&str> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with('-') && user_flag.map_or(true, |u| *i != u + 1))
+        .map(|(_, a)| a.as_str())
+        .collect();
+    if user_flag.is_some() && only.is_none() {
+        let e = Error::new(
+            "usage.missing-user",
+            Category::Usage,
+            "--user requires a user name",
+        );
+        return fail(e, json);
+    }
+    let users = match users::select(only) {
+        Ok(users) => users,
+        Err(e) => return fail(e, json),
+    };
+
+    match positional.first().copied() {
+        Some("diagnose") => run_diagnose(&users, verbose, json),
+        Some("repair") => match positional.get(1) {
+            Some(target) => run_repair(target, &users, json, yes),
+            None => fail(Error::missing_target(report::TOOL, repairs::TARGETS), json),
+        },
+        Some("status") => run_status(&users),
+        Some("version") => {
+            println!("User Environment Ambulance v{}", report::VERSION);
+            ExitCode::SUCCESS
+        }
+        Some("help") | None => {
+            print_help();
+            ExitCode::SUCCESS
+        }
+        Some(other) => fail(Error::unknown_command(other, report::TOOL), json),
+    }
+}
@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Move suspect application cache and settings aside
+//!
+//! Each suspect path is renamed with a timestamp suffix rather than
+//! deleted; the application builds a fresh one at its next start, and
+//! renaming it back restores the old one. The application should not be
+//! running while this happens, or it writes the old state back.
+
+use crate::diagnostics::apps;
+use crate::report::{RepairOutcome, TOOL};
+use crate::users::User;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn repair(users: &[User], confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let diag = apps::diagnose(users);
+    if let Some(e) = diag.error {
+        let mut result = RepairOutcome::default();
+        result
+            .errors
+            .push(format!("Cannot read the journal: {}", e));
+        return result;
+    }
+    let suspects: Vec<_> = diag
+        .apps
+        .iter()
+        .flat_map(|app| app.suspects.iter().map(move |path| (app, path)))
+        .collect();
+    if suspects.is_empty() {
+        return RepairOutcome::not_needed("No application data is suspected, no repair needed");
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut result = RepairOutcome::default();
+    for (app, path) in suspects {
+        if !confirm(&format!(
+            "Move {} aside ({} of {}; quit it first)?",
+            path, app.process, app.user
+        )) {
+            result.errors.push(format!(
+                "{} not confirmed, left in place (rerun with --yes to confirm)",
+                path
+            ));
+            continue;
+        }
+        let aside = format!("{}.{}-{}", path, TOOL, stamp);
+        match std::fs::rename(path, &aside) {
+            Ok(()) => result.actions.push(format!(
+                "Moved {} to {}; {} starts afresh next time",
+                path, aside, app.process
+            )),
+            Err(e) => result.errors.push(format!("{}: {}", path, e)),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! User environment repairs, one module per target
+//!
+//! Every target acts on the users selected with `--user`, or on all of
+//! them. `all` fixes ownership first, since a session or application
+//! cannot recover from files it is not allowed to write.
+
+pub mod apps;
+pub mod ownership;
+pub mod quota;
+pub mod session;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use crate::users::User;
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["ownership", "apps", "session", "quota", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change, naming the user.
+pub fn run(
+    target: &str,
+    users: &[User],
+    confirm: &mut dyn FnMut(&str) -> bool,
+) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        ownership_repair: if selected("ownership") {
+            ownership::repair(users, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        apps_repair: if selected("apps") {
+            apps::repair(users, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        session_repair: if selected("session") {
+            session::repair(users, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        quota_repair: if selected("quota") {
+            quota::repair(users, confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Give home directory entries back to their user and close open modes
+//!
+//! A foreign-owned entry is handed back with everything below it, as
+//! `chown -R` would, without following symlinks or crossing into another
+//! filesystem mounted inside it. Modes lose only the bits the diagnosis
+//! objected to. The user may swap any of these paths for a symlink while
+//! the repair waits to be confirmed, so both go through
+//! `ambulance_privilege::userfs`, which refuses one.
+
+use crate::diagnostics::home;
+use crate::report::RepairOutcome;
+use crate::users::User;
+use ambulance_privilege::userfs;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+pub fn lchown(path: &Path, uid: u32, gid: u32) -> std::io::Result<()> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    if unsafe { libc::lchown(c_path.as_ptr(), uid, gid) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn repair(users: &[User], confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let mut needed = false;
+
+    for user in users {
+        let findings = home::check(user).findings;
+        if findings.is_empty() {
+            continue;
+        }
+        needed = true;
+        if !confirm(&format!(
+            "Fix owner or mode of {} path(s) in {}'s home?",
+            findings.len(),
+            user.name
+        )) {
+            result.errors.push(format!(
+                "{}'s home not confirmed, left as is (rerun with --yes to confirm)",
+                user.name
+            ));
+            continue;
+        }
+        for f in findings {
+            let Ok(relative) = Path::new(&f.path).strip_prefix(&user.home) else {
+                result
+                    .errors
+                    .push(format!("{}: not in {}'s home", f.path, user.name));
+                continue;
+            };
+            if f.chown {
+                match userfs::chown(&user.home, relative, user.uid, user.gid) {
+                    Ok(count) => result.actions.push(format!(
+                        "Gave {} back to {} ({} entries)",
+                        f.path, user.name, count
+                    )),
+                    Err(e) => result.errors.push(format!("{}: {}", f.path, e)),
+                }
+            }
+            if let Some(mode) = f.mode {
+                match userfs::chmod(&user.home, relative, mode) {
+                    Ok(()) => result
+                        .actions
+                        .push(format!("Set {} to mode {:04o}", f.path, mode)),
+                    Err(e) => result.errors.push(format!("{}: {}", f.path, e)),
+                }
+            }
+        }
+    }
+
+    if !needed {
+        return RepairOutcome::not_needed("Every home is in order, no repair needed");
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Empty the cache and trash of users out of space
+//!
+//! Only what is inside `~/.cache` and the trash goes; the directories
+//! stay. Applications rebuild their caches as they need them.
+
+use crate::diagnostics::quota::{self, DISPOSABLE};
+use crate::report::RepairOutcome;
+use crate::scan::human_bytes;
+use crate::system;
+use crate::users::User;
+use ambulance_privilege::userfs;
+use std::path::Path;
+
+pub fn repair(users: &[User], confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let quota_tool = system::has("quota");
+    let full: Vec<_> = users
+        .iter()
+        .filter(|u| u.home.is_dir())
+        .map(|u| (u, quota::check(u, quota_tool)))
+        .filter(|(_, space)| space.out_of_space())
+        .collect();
+    if full.is_empty() {
+        return RepairOutcome::not_needed("No user is out of space, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    for (user, space) in full {
+        let disposable: u64 = space.disposable_bytes.iter().map(|(_, b)| b).sum();
+        if disposable == 0 {
+            result.errors.push(format!(
+                "{} is out of space with nothing disposable; raise the quota or free space by hand",
+                user.name
+            ));
+            continue;
+        }
+        if !confirm(&format!(
+            "Empty {}'s cache and trash ({})?",
+            user.name,
+            human_bytes(disposable)
+        )) {
+            result.errors.push(format!(
+                "{}'s cache and trash not confirmed, left as is (rerun with --yes to confirm)",
+                user.name
+            ));
+            continue;
+        }
+        for dir in DISPOSABLE {
+            // The user may have linked either elsewhere, say to /etc
+            let path = user.home.join(dir);
+            match userfs::empty(&user.home, Path::new(dir)) {
+                Ok(0) => {}
+                Ok(freed) => result.actions.push(format!(
+                    "Emptied {} ({})",
+                    path.display(),
+                    human_bytes(freed)
+                )),
+                Err(e) => result.errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Repair a user's runtime directory, service manager and session bus
+//!
+//! The runtime directory is given back to its user with mode 0700. A
+//! failed `user@UID.service` has its failed state cleared and is
+//! started. A dead bus is restarted through the user's own manager,
+//! reached on its private socket since the bus it would answer on is the
+//! one that is down; this drops every client's connection to the bus, so
+//! applications that do not reconnect need restarting too.
+
+use crate::diagnostics::session;
+use crate::repairs::ownership::lchown;
+use crate::report::RepairOutcome;
+use crate::users::User;
+use std::os::unix::fs::PermissionsExt;
+use systemd_shim::bus::Bus;
+
+/// Units that make up the session bus, socket first
+const BUS_UNITS: &[&str] = &["dbus.socket", "dbus.service"];
+
+/// Restart `user`'s session bus through their service manager
+fn restart_bus(user: &User) -> Result<(), String> {
+    let socket = user.runtime_dir().join("systemd/private");
+    let manager = Bus::peer(&format!("unix:path={}", socket.display()))
+        .map_err(|e| format!("{}'s service manager: {}", user.name, e))?;
+    for unit in BUS_UNITS {
+        manager
+            .restart_unit(unit, "replace")
+            .map_err(|e| format!("{} of {}: {}", unit, user.name, e))?;
+    }
+    Ok(())
+}
+
+fn runtime_dir(user: &User) -> Result<(), String> {
+    let dir = user.runtime_dir();
+    lchown(&dir, user.uid, user.gid)
+        .and_then(|()| std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)))
+        .map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+pub fn repair(users: &[User], confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let bus = Bus::system().ok();
+    let broken: Vec<_> = users
+        .iter()
+        .filter_map(|u| Some((u, session::check(bus.as_ref(), u)?)))
+        .filter(|(_, s)| !s.healthy())
+        .collect();
+    if broken.is_empty() {
+        return RepairOutcome::not_needed("Every session is healthy, no repair needed");
+    }
+
+    for (user, s) in broken {
+        if !confirm(&format!(
+            "Repair {}'s session (the session bus restarts if it is down)?",
+            user.name
+        )) {
+            result.errors.push(format!(
+                "{}'s session not confirmed, left as is (rerun with --yes to confirm)",
+                user.name
+            ));
+            continue;
+        }
+        if s.runtime_dir_problem.is_some() {
+            match runtime_dir(user) {
+                Ok(()) => result.actions.push(format!(
+                    "Gave {} back to {} with mode 0700",
+                    s.runtime_dir, user.name
+                )),
+                Err(e) => result.errors.push(e),
+            }
+        }
+        let unit = s.manager_unit();
+        if s.manager_state.as_deref() == Some("failed") {
+            let Some(bus) = &bus else {
+                result
+                    .errors
+                    .push(format!("{}: system bus unavailable", unit));
+                continue;
+            };
+            match bus
+                .reset_failed_unit(&unit)
+                .and_then(|()| bus.start_unit(&unit, "replace"))
+            {
+                Ok(_) => result.actions.push(format!("Restarted {}", unit)),
+                Err(e) => {
+                    result.errors.push(format!("{}: {}", unit, e));
+                    continue;
+                }
+            }
+        }
+
+        let after = session::check(bus.as_ref(), user);
+        if after.as_ref().is_some_and(|a| a.bus_error.is_some()) {
+            match restart_bus(user) {
+                Ok(()) => result
+                    .actions
+                    .push(format!("Restarted {}'s session bus", user.name)),
+                Err(e) => result.errors.push(e),
+            }
+        }
+        match session::check(bus.as_ref(), user) {
+            Some(a) if a.healthy() => {}
+            Some(a) => result.errors.push(format!(
+                "{}'s session is still unhealthy{}",
+                user.name,
+                a.bus_error.map(|e| format!(": {}", e)).unwrap_or_default()
+            )),
+            None => result.errors.push(format!(
+                "{} has no running session after the repair",
+                user.name
+            )),
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
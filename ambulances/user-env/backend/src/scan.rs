@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Disk usage of directory trees
+//!
+//! Sizes are allocated blocks, as `du` counts them. Walks never follow
+//! symlinks or leave the filesystem they start on, so measuring `/` does
+//! not wander into `/proc` or a network share.
+
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Space used under `path`; unreadable parts count as empty
+pub fn size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    walk(path, meta.dev())
+}
+
+fn walk(path: &Path, dev: u64) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if meta.dev() != dev {
+        return 0;
+    }
+    let mut total = meta.blocks() * 512;
+    if meta.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            total += entries.flatten().map(|e| walk(&e.path(), dev)).sum::<u64>();
+        }
+    }
+    total
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
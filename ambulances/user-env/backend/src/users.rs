@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The users whose environment is checked
+//!
+//! Human accounts are UID 1000 and up from `/etc/passwd`, less those
+//! whose shell refuses logins. Directory-service users who have never
+//! logged in here have no home to check, and are not listed.
+
+use crate::system;
+use ambulance_core::error::{Category, Error};
+use std::path::PathBuf;
+
+/// Shells that mark an account nobody logs in to
+const NOLOGIN_SHELLS: &[&str] = &["nologin", "false"];
+
+#[derive(Debug, Clone)]
+pub struct User {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: PathBuf,
+}
+
+impl User {
+    /// `XDG_RUNTIME_DIR` as logind creates it
+    pub fn runtime_dir(&self) -> PathBuf {
+        PathBuf::from(format!("/run/user/{}", self.uid))
+    }
+}
+
+fn parse(line: &str) -> Option<User> {
+    let fields: Vec<&str> = line.split(':').collect();
+    let uid: u32 = fields.get(2)?.parse().ok()?;
+    let shell = fields.get(6).copied().unwrap_or_default();
+    if !(1000..65534).contains(&uid) || NOLOGIN_SHELLS.iter().any(|s| shell.ends_with(s)) {
+        return None;
+    }
+    Some(User {
+        name: fields[0].to_string(),
+        uid,
+        gid: fields.get(3)?.parse().ok()?,
+        home: PathBuf::from(fields.get(5)?),
+    })
+}
+
+/// Every human user, or only `only`
+pub fn select(only: Option<&str>) -> Result<Vec<User>, Error> {
+    let users: Vec<User> = system::read("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(parse)
+        .filter(|u| only.map_or(true, |name| u.name == name))
+        .collect();
+    match only {
+        Some(name) if users.is_empty() => Err(Error::new(
+            "usage.unknown-user",
+            Category::Usage,
+            format!("No user {} with a login shell and UID 1000 or above", name),
+        )),
+        _ => Ok(users),
+    }
+}
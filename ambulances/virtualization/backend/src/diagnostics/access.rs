@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Who may use KVM and manage the system's VMs
+//!
+//! `/dev/kvm` belongs to the `kvm` group, which QEMU needs for session
+//! VMs and which libvirt's own QEMU user is in for system VMs. Managing
+//! system VMs without root, as virt-manager does, takes the `libvirt`
+//! group. Group changes only reach processes started after them, so a
+//! member none of whose running processes carries the group has not
+//! logged in again since joining. Users are the members of either group
+//! and whoever ran the command through sudo.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+pub const KVM_GROUP: &str = "kvm";
+pub const LIBVIRT_GROUP: &str = "libvirt";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserAccess {
+    pub user: String,
+    pub in_kvm: bool,
+    pub in_libvirt: bool,
+    /// Groups joined that no running process of the user carries yet
+    pub pending: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccessDiagnostics {
+    pub dev_kvm_group: Option<String>,
+    pub dev_kvm_mode: Option<u32>,
+    pub libvirt_group_exists: bool,
+    pub users: Vec<UserAccess>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+struct Group {
+    name: String,
+    gid: u32,
+    members: Vec<String>,
+}
+
+fn groups() -> Vec<Group> {
+    system::read("/etc/group")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            Some(Group {
+                name: fields.first()?.to_string(),
+                gid: fields.get(2)?.parse().ok()?,
+                members: fields
+                    .get(3)
+                    .map(|m| {
+                        m.split(',')
+                            .filter(|u| !u.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// `(name, uid, primary gid)` of every account
+fn accounts() -> Vec<(String, u32, u32)> {
+    system::read("/etc/passwd")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            Some((
+                fields.first()?.to_string(),
+                fields.get(2)?.parse().ok()?,
+                fields.get(3)?.parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Supplementary and primary groups of each running process of `uid`
+fn process_groups(uid: u32) -> Vec<Vec<u32>> {
+    let Ok(entries) = std::fs::read_dir(host::path("/proc")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+        })
+        .filter_map(|e| {
+            let status = system::read(e.path().join("status"))?;
+            let field = |name: &str| {
+                status.lines().find_map(|l| l.strip_prefix(name)).map(|v| {
+                    v.split_whitespace()
+                        .filter_map(|n| n.parse().ok())
+                        .collect::<Vec<u32>>()
+                })
+            };
+            (field("Uid:")?.first() == Some(&uid)).then(|| {
+                let mut gids = field("Groups:").unwrap_or_default();
+                gids.extend(field("Gid:").unwrap_or_default());
+                gids
+            })
+        })
+        .collect()
+}
+
+pub fn diagnose() -> AccessDiagnostics {
+    let groups = groups();
+    let accounts = accounts();
+    let group = |name: &str| groups.iter().find(|g| g.name == name);
+    let mut diag = AccessDiagnostics {
+        libvirt_group_exists: group(LIBVIRT_GROUP).is_some(),
+        ..AccessDiagnostics::default()
+    };
+    if let Ok(meta) = std::fs::metadata(host::path("/dev/kvm")) {
+        diag.dev_kvm_mode = Some(meta.permissions().mode() & 0o777);
+        diag.dev_kvm_group = groups
+            .iter()
+            .find(|g| g.gid == meta.gid())
+            .map(|g| g.name.clone());
+    }
+
+    let mut names: Vec<String> = [KVM_GROUP, LIBVIRT_GROUP]
+        .iter()
+        .filter_map(|g| group(g))
+        .flat_map(|g| g.members.iter().cloned())
+        .collect();
+    if let Ok(user) = std::env::var("SUDO_USER") {
+        if user != "root" {
+            names.push(user);
+        }
+    }
+    names.sort();
+    names.dedup();
+    for name in names {
+        let Some((_, uid, primary)) = accounts.iter().find(|(n, _, _)| *n == name) else {
+            continue;
+        };
+        let member = |group: Option<&Group>| {
+            group.is_some_and(|g| g.gid == *primary || g.members.contains(&name))
+        };
+        let (kvm, libvirt) = (group(KVM_GROUP), group(LIBVIRT_GROUP));
+        let running = process_groups(*uid);
+        let pending = [kvm, libvirt]
+            .into_iter()
+            .flatten()
+            .filter(|g| member(Some(g)))
+            .filter(|g| !running.is_empty() && !running.iter().any(|gids| gids.contains(&g.gid)))
+            .map(|g| g.name.clone())
+            .collect();
+        diag.users.push(UserAccess {
+            in_kvm: member(kvm),
+            in_libvirt: member(libvirt),
+            user: name,
+            pending,
+        });
+    }
+
+    if let Some(mode) = diag.dev_kvm_mode {
+        let group_ok = diag.dev_kvm_group.as_deref() == Some(KVM_GROUP) && mode & 0o060 == 0o060;
+        if !group_ok && mode & 0o006 != 0o006 {
+            diag.warnings.push(format!(
+                "/dev/kvm is {:o} and owned by group {}, so QEMU cannot use it without root",
+                mode,
+                diag.dev_kvm_group.as_deref().unwrap_or("?")
+            ));
+            diag.recommendations.push(
+                "Reapply the udev rules that set its permissions: udevadm trigger --name-match=kvm"
+                    .to_string(),
+            );
+        }
+    }
+    for u in &diag.users {
+        if !u.pending.is_empty() {
+            diag.warnings.push(format!(
+                "{} joined {} but has not logged in again since",
+                u.user,
+                u.pending.join(" and ")
+            ));
+            diag.recommendations
+                .push(format!("Have {} log out and back in", u.user));
+        }
+        if !u.in_libvirt && diag.libvirt_group_exists {
+            diag.warnings.push(format!(
+                "{} is not in the {} group, so managing system VMs asks for root",
+                u.user, LIBVIRT_GROUP
+            ));
+            diag.recommendations.push(format!(
+                "usermod -aG {} {}, then log in again",
+                LIBVIRT_GROUP, u.user
+            ));
+        }
+        if !u.in_kvm && u.in_libvirt {
+            diag.warnings.push(format!(
+                "{} is not in the {} group, so session VMs (GNOME Boxes, qemu:///session) run without KVM",
+                u.user, KVM_GROUP
+            ));
+            diag.recommendations.push(format!(
+                "usermod -aG {} {}, then log in again",
+                KVM_GROUP, u.user
+            ));
+        }
+    }
+    diag
+}
+
+impl AccessDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Access ===");
+        if let Some(mode) = self.dev_kvm_mode {
+            println!(
+                "- /dev/kvm: mode {:o}, group {}",
+                mode,
+                self.dev_kvm_group.as_deref().unwrap_or("?")
+            );
+        }
+        for u in &self.users {
+            let ok =
+                u.in_kvm && (u.in_libvirt || !self.libvirt_group_exists) && u.pending.is_empty();
+            if !verbose && ok {
+                continue;
+            }
+            let mut joined: Vec<&str> = Vec::new();
+            if u.in_kvm {
+                joined.push(KVM_GROUP);
+            }
+            if u.in_libvirt {
+                joined.push(LIBVIRT_GROUP);
+            }
+            println!(
+                "{} {}: {}",
+                mark(ok),
+                u.user,
+                if joined.is_empty() {
+                    "in neither group".to_string()
+                } else {
+                    format!("in {}", joined.join(" and "))
+                }
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
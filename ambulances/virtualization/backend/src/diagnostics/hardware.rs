@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! CPU virtualization support, the kvm modules and the IOMMU
+//!
+//! Firmware that switches VT-x or AMD-V off leaves the kernel to say so:
+//! recent kernels drop the `vmx` flag and log that the BIOS disabled it,
+//! and kvm_intel or kvm_amd then refuses to load. Those messages are
+//! read from this boot's kernel log. The IOMMU (VT-d on Intel, AMD-Vi on
+//! AMD) only matters for devices passed through to a guest with vfio;
+//! its ACPI table shows whether the firmware has it on when the kernel
+//! does not use it.
+
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use systemd_shim::journal::{self, Journal};
+
+/// Kernel messages, lowercased, that report virtualization off in firmware
+const FIRMWARE_DISABLED: &[&str] = &["disabled by bios", "disabled (by bios)"];
+
+/// Journal entries of this boot read at most
+const MAX_ENTRIES: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Vendor {
+    /// `vendor_id` in /proc/cpuinfo
+    pub id: &'static str,
+    /// CPU flag of the extension
+    pub flag: &'static str,
+    /// What the firmware setup calls it
+    pub extension: &'static str,
+    pub module: &'static str,
+    /// ACPI table describing the IOMMU
+    pub iommu_table: &'static str,
+    /// Kernel parameter that turns the IOMMU on
+    pub iommu_param: &'static str,
+}
+
+pub const VENDORS: &[Vendor] = &[
+    Vendor {
+        id: "GenuineIntel",
+        flag: "vmx",
+        extension: "Intel VT-x",
+        module: "kvm_intel",
+        iommu_table: "DMAR",
+        iommu_param: "intel_iommu=on",
+    },
+    Vendor {
+        id: "AuthenticAMD",
+        flag: "svm",
+        extension: "AMD-V (SVM)",
+        module: "kvm_amd",
+        iommu_table: "IVRS",
+        iommu_param: "amd_iommu=on",
+    },
+    Vendor {
+        id: "HygonGenuine",
+        flag: "svm",
+        extension: "AMD-V (SVM)",
+        module: "kvm_amd",
+        iommu_table: "IVRS",
+        iommu_param: "amd_iommu=on",
+    },
+];
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HardwareDiagnostics {
+    /// `None` off x86, where only `/dev/kvm` is checked
+    pub vendor: Option<Vendor>,
+    /// The CPU flag for the extension is set
+    pub extension: bool,
+    /// Running as a guest itself, so nested virtualization is needed
+    pub in_vm: bool,
+    /// The kernel logged that the firmware turned the extension off
+    pub firmware_disabled: bool,
+    pub kvm_loaded: bool,
+    pub vendor_module_loaded: bool,
+    pub nested: Option<bool>,
+    pub dev_kvm: bool,
+    /// The kernel has an IOMMU in use
+    pub iommu: bool,
+    /// The firmware describes an IOMMU in its ACPI tables
+    pub iommu_in_firmware: bool,
+    /// PCI devices bound to vfio-pci for passthrough
+    pub vfio_devices: Vec<String>,
+    pub log_error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+fn cpuinfo() -> (Option<Vendor>, Vec<String>) {
+    let cpuinfo = system::read(host::path("/proc/cpuinfo")).unwrap_or_default();
+    let field = |name: &str| {
+        cpuinfo.lines().find_map(|l| {
+            let (key, value) = l.split_once(':')?;
+            (key.trim() == name).then(|| value.trim().to_string())
+        })
+    };
+    let vendor = field("vendor_id").and_then(|id| VENDORS.iter().find(|v| v.id == id).copied());
+    let flags = field("flags")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    (vendor, flags)
+}
+
+/// Whether this boot's kernel log says the firmware disabled the extension
+fn firmware_disabled() -> std::io::Result<bool> {
+    let mut journal = Journal::open(journal::LOCAL_ONLY)?;
+    journal.add_match("_TRANSPORT=kernel")?;
+    journal.match_this_boot()?;
+    journal.seek_head()?;
+    for _ in 0..MAX_ENTRIES {
+        if !journal.next_entry()? {
+            break;
+        }
+        let Some(text) = journal.field("MESSAGE") else {
+            continue;
+        };
+        let text = text.to_lowercase();
+        if FIRMWARE_DISABLED.iter().any(|m| text.contains(m)) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn nested(module: &str) -> Option<bool> {
+    let value = system::read(host::path(format!(
+        "/sys/module/{}/parameters/nested",
+        module
+    )))?;
+    Some(matches!(value.as_str(), "Y" | "1"))
+}
+
+fn vfio_devices() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(host::path("/sys/bus/pci/drivers/vfio-pci")) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<String> = entries
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.contains(':'))
+        .collect();
+    devices.sort();
+    devices
+}
+
+pub fn diagnose() -> HardwareDiagnostics {
+    let (vendor, flags) = cpuinfo();
+    let has_flag = |flag: &str| flags.iter().any(|f| f == flag);
+    let mut diag = HardwareDiagnostics {
+        vendor,
+        extension: vendor.is_some_and(|v| has_flag(v.flag)),
+        in_vm: has_flag("hypervisor"),
+        kvm_loaded: host::path("/sys/module/kvm").exists(),
+        vendor_module_loaded: vendor
+            .is_some_and(|v| host::path("/sys/module").join(v.module).exists()),
+        nested: vendor.and_then(|v| nested(v.module)),
+        dev_kvm: host::path("/dev/kvm").exists(),
+        iommu: std::fs::read_dir(host::path("/sys/class/iommu"))
+            .is_ok_and(|mut d| d.next().is_some()),
+        iommu_in_firmware: vendor.is_some_and(|v| {
+            host::path("/sys/firmware/acpi/tables")
+                .join(v.iommu_table)
+                .exists()
+        }),
+        vfio_devices: vfio_devices(),
+        ..HardwareDiagnostics::default()
+    };
+    match firmware_disabled() {
+        Ok(disabled) => diag.firmware_disabled = disabled,
+        Err(e) => diag.log_error = Some(e.to_string()),
+    }
+
+    if !diag.dev_kvm {
+        match vendor {
+            Some(v) if diag.firmware_disabled || (!diag.extension && !diag.in_vm) => {
+                diag.warnings.push(format!(
+                    "{} is {}",
+                    v.extension,
+                    if diag.firmware_disabled {
+                        "disabled in the firmware"
+                    } else {
+                        "not available: the CPU lacks it or the firmware hides it"
+                    }
+                ));
+                diag.recommendations.push(format!(
+                    "Enable {} (often under CPU or Advanced settings) in the firmware setup and boot again",
+                    v.extension
+                ));
+            }
+            Some(v) if !diag.extension => {
+                diag.warnings.push(format!(
+                    "This machine is itself a virtual machine without {} passed through",
+                    v.extension
+                ));
+                diag.recommendations.push(format!(
+                    "Enable nested virtualization on the host (the {} module's nested parameter) and expose the CPU's virtualization to this guest",
+                    v.module
+                ));
+            }
+            Some(v) if !diag.vendor_module_loaded => {
+                diag.warnings.push(format!(
+                    "/dev/kvm is missing: the {} module is not loaded",
+                    v.module
+                ));
+                diag.recommendations.push(
+                    "Load the kvm modules: virtualization-ambulance repair modules".to_string(),
+                );
+            }
+            _ => diag
+                .warnings
+                .push("/dev/kvm is missing: guests run without hardware acceleration".to_string()),
+        }
+    }
+    if !diag.vfio_devices.is_empty() && !diag.iommu {
+        diag.warnings.push(format!(
+            "{} device(s) are bound to vfio-pci but no IOMMU is in use, so they cannot be passed through",
+            diag.vfio_devices.len()
+        ));
+        diag.recommendations.push(match vendor {
+            Some(v) if diag.iommu_in_firmware => format!(
+                "Add {} iommu=pt to the kernel command line and reboot",
+                v.iommu_param
+            ),
+            Some(v) => format!(
+                "Enable {} in the firmware setup",
+                if v.flag == "vmx" {
+                    "VT-d"
+                } else {
+                    "AMD-Vi (IOMMU)"
+                }
+            ),
+            None => "Enable the IOMMU in the firmware setup".to_string(),
+        });
+    }
+    diag
+}
+
+impl HardwareDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Hardware ===");
+        if let Some(v) = self.vendor {
+            println!(
+                "{} {}: {}",
+                mark(self.extension || self.dev_kvm),
+                v.extension,
+                if self.extension {
+                    "available"
+                } else if self.firmware_disabled {
+                    "disabled in firmware"
+                } else {
+                    "not available"
+                }
+            );
+        }
+        println!(
+            "{} /dev/kvm: {}",
+            mark(self.dev_kvm),
+            if self.dev_kvm { "present" } else { "missing" }
+        );
+        if verbose {
+            if let Some(v) = self.vendor {
+                println!(
+                    "    kvm: {}, {}: {}",
+                    if self.kvm_loaded {
+                        "loaded"
+                    } else {
+                        "not loaded"
+                    },
+                    v.module,
+                    if self.vendor_module_loaded {
+                        "loaded"
+                    } else {
+                        "not loaded"
+                    }
+                );
+            }
+            if let Some(nested) = self.nested {
+                println!(
+                    "    Nested virtualization: {}",
+                    if nested { "on" } else { "off" }
+                );
+            }
+            if self.in_vm {
+                println!("    Running as a guest");
+            }
+            if let Some(e) = &self.log_error {
+                println!("    Kernel log unreadable: {}", e);
+            }
+        }
+        if self.iommu || verbose || !self.vfio_devices.is_empty() {
+            println!(
+                "{} IOMMU: {}",
+                mark(self.iommu || self.vfio_devices.is_empty()),
+                match (self.iommu, self.iommu_in_firmware) {
+                    (true, _) => "in use",
+                    (false, true) => "in firmware, not enabled in the kernel",
+                    (false, false) => "not available",
+                }
+            );
+        }
+        if !self.vfio_devices.is_empty() {
+            println!("- Bound to vfio-pci: {}", self.vfio_devices.join(", "));
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! The libvirt daemons and the system connection
+//!
+//! Which daemons are installed decides the layout: `libvirtd` alone, or
+//! the modular `virtqemud` and `virtnetworkd`. With socket activation a
+//! daemon that is not running is normal, so only failed units, and a
+//! connection that does not answer, are problems.
+
+use crate::libvirt::{self, DAEMONS};
+use crate::report::{mark, print_notes};
+use crate::system;
+use serde::Serialize;
+use systemd_shim::bus::{self, Bus};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DaemonStatus {
+    pub service: &'static str,
+    pub socket: &'static str,
+    /// `ActiveState` of the service and socket units, `None` if not installed
+    pub service_state: Option<String>,
+    pub socket_state: Option<String>,
+}
+
+impl DaemonStatus {
+    pub fn failed(&self) -> bool {
+        self.service_state.as_deref() == Some("failed")
+            || self.socket_state.as_deref() == Some("failed")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LibvirtDiagnostics {
+    pub virsh_installed: bool,
+    /// Installed daemons only
+    pub daemons: Vec<DaemonStatus>,
+    /// `Running hypervisor` from `virsh version`
+    pub hypervisor: Option<String>,
+    pub connection_error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+/// `ActiveState` of a unit that is installed
+pub fn unit_state(bus: Option<&Bus>, unit: &str) -> Option<String> {
+    let bus = bus?;
+    let path = bus::unit_path(unit).ok()?;
+    let load = bus
+        .get_property_string(bus::SYSTEMD, &path, bus::UNIT, "LoadState")
+        .ok()?;
+    if load != "loaded" {
+        return None;
+    }
+    bus.get_property_string(bus::SYSTEMD, &path, bus::UNIT, "ActiveState")
+        .ok()
+}
+
+pub fn statuses(bus: Option<&Bus>) -> Vec<DaemonStatus> {
+    DAEMONS
+        .iter()
+        .map(|(service, socket)| DaemonStatus {
+            service,
+            socket,
+            service_state: unit_state(bus, service),
+            socket_state: unit_state(bus, socket),
+        })
+        .filter(|d| d.service_state.is_some() || d.socket_state.is_some())
+        .collect()
+}
+
+pub fn diagnose() -> LibvirtDiagnostics {
+    let bus = Bus::system().ok();
+    let mut diag = LibvirtDiagnostics {
+        virsh_installed: system::has("virsh"),
+        daemons: statuses(bus.as_ref()),
+        ..LibvirtDiagnostics::default()
+    };
+    if !diag.virsh_installed && diag.daemons.is_empty() {
+        diag.warnings.push("libvirt is not installed".to_string());
+        return diag;
+    }
+    if diag.virsh_installed {
+        match libvirt::virsh(&["version"]) {
+            Ok(out) => {
+                diag.hypervisor = out
+                    .lines()
+                    .find_map(|l| l.strip_prefix("Running hypervisor:"))
+                    .map(|v| v.trim().to_string())
+            }
+            Err(e) => diag.connection_error = Some(e),
+        }
+    }
+
+    for d in diag.daemons.iter().filter(|d| d.failed()) {
+        diag.warnings.push(format!("{} failed", d.service));
+        diag.recommendations.push(format!(
+            "Read journalctl -u {} for the cause, then systemctl restart {}",
+            d.service, d.socket
+        ));
+    }
+    if let Some(e) = &diag.connection_error {
+        diag.warnings
+            .push(format!("Cannot connect to {}: {}", libvirt::URI, e));
+        let lower = e.to_lowercase();
+        if lower.contains("permission denied") || lower.contains("authentication") {
+            diag.recommendations.push(
+                "Run as root, or join the libvirt group (see the access section)".to_string(),
+            );
+        } else if diag.daemons.is_empty() {
+            diag.recommendations.push(
+                "Install the libvirt daemon package (libvirt-daemon-system or libvirt)".to_string(),
+            );
+        }
+    }
+    diag
+}
+
+impl LibvirtDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== libvirt ===");
+        if !self.virsh_installed && self.daemons.is_empty() {
+            println!("- libvirt is not installed");
+        }
+        match (&self.hypervisor, &self.connection_error) {
+            (Some(h), _) => println!("✓ {}: {}", libvirt::URI, h),
+            (None, Some(_)) => println!("✗ {}: no connection", libvirt::URI),
+            (None, None) => {}
+        }
+        for d in &self.daemons {
+            if !verbose && !d.failed() {
+                continue;
+            }
+            println!(
+                "{} {}: {}, {}: {}",
+                mark(!d.failed()),
+                d.service,
+                d.service_state.as_deref().unwrap_or("not installed"),
+                d.socket,
+                d.socket_state.as_deref().unwrap_or("not installed")
+            );
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
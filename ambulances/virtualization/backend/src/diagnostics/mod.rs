@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Virtualization diagnostics, one module per report section
+//!
+//! The sections run as ambulance-core jobs at the same time, within the
+//! scan's budget, so a hung libvirtd holds up only the sections that ask
+//! it.
+//! A section the budget cut short is left empty and its timing says why;
+//! one that failed or timed out says so in its warnings.
+
+pub mod access;
+pub mod hardware;
+pub mod libvirt;
+pub mod networks;
+
+use crate::report::{DiagnosticResult, TOOL, VERSION};
+use ambulance_core::runner::{Job, Runner, Status};
+use ambulance_core::scan::Scan;
+use std::time::Duration;
+
+/// How long a section may take; virsh waits on a libvirtd that stopped
+/// answering
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone)]
+enum Section {
+    Hardware(hardware::HardwareDiagnostics),
+    Libvirt(libvirt::LibvirtDiagnostics),
+    Networks(networks::NetworkDiagnostics),
+    Access(access::AccessDiagnostics),
+}
+
+pub fn run() -> DiagnosticResult {
+    let outcomes = Runner::new()
+        .timeout(TIMEOUT)
+        .budget(Scan::from_env().budget)
+        .job(Job::blocking("hardware", |_| {
+            Ok(Section::Hardware(hardware::diagnose()))
+        }))
+        .job(Job::blocking("libvirt", |_| {
+            Ok(Section::Libvirt(libvirt::diagnose()))
+        }))
+        .job(Job::blocking("networks", |_| {
+            Ok(Section::Networks(networks::diagnose()))
+        }))
+        .job(Job::blocking("access", |_| {
+            Ok(Section::Access(access::diagnose()))
+        }))
+        .block_on();
+
+    let mut result = DiagnosticResult {
+        version: VERSION,
+        tool: TOOL,
+        hardware: Default::default(),
+        libvirt: Default::default(),
+        networks: Default::default(),
+        access: Default::default(),
+        timings: outcomes.iter().map(|o| o.timing()).collect(),
+    };
+    for outcome in outcomes {
+        match outcome.output {
+            Some(Section::Hardware(d)) => result.hardware = d,
+            Some(Section::Libvirt(d)) => result.libvirt = d,
+            Some(Section::Networks(d)) => result.networks = d,
+            Some(Section::Access(d)) => result.access = d,
+            None if outcome.status == Status::OverBudget => {}
+            None => {
+                let warnings = match outcome.id {
+                    "hardware" => &mut result.hardware.warnings,
+                    "libvirt" => &mut result.libvirt.warnings,
+                    "networks" => &mut result.networks.warnings,
+                    _ => &mut result.access.warnings,
+                };
+                warnings.push(format!("The {} check {}", outcome.id, outcome.status));
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! libvirt's virtual networks, the default NAT network above all
+//!
+//! A network that should run but does not leaves its guests without an
+//! address. One that runs without its bridge, usually after the host's
+//! network stack was restarted underneath it, leaves them cut off. NAT
+//! also needs IPv4 forwarding, which libvirt turns on when the network
+//! starts and which a later sysctl reload can turn off again.
+
+use crate::libvirt::{self, Network, DEFAULT_NETWORK};
+use crate::report::{mark, print_notes};
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+use std::path::Path;
+
+/// Definition of the default network as libvirt installs it
+pub const DEFAULT_XML: &str = "/usr/share/libvirt/networks/default.xml";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkStatus {
+    pub network: Network,
+    pub problem: Option<String>,
+}
+
+impl NetworkStatus {
+    /// Whether restarting the network would fix it; bridged networks
+    /// need the host's bridge, which libvirt does not create
+    pub fn restartable(&self) -> bool {
+        self.problem.is_some() && self.network.forward.as_deref() != Some("bridge")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkDiagnostics {
+    pub networks: Vec<NetworkStatus>,
+    pub default_defined: bool,
+    pub ip_forward: Option<bool>,
+    pub error: Option<String>,
+    pub warnings: Vec<String>,
+    pub recommendations: Vec<String>,
+}
+
+impl NetworkDiagnostics {
+    pub fn default_network(&self) -> Option<&NetworkStatus> {
+        self.networks
+            .iter()
+            .find(|n| n.network.name == DEFAULT_NETWORK)
+    }
+}
+
+fn problem(n: &Network, ip_forward: Option<bool>) -> Option<String> {
+    if n.wanted() && !n.active {
+        return Some(if n.autostart {
+            "set to start at boot but not running".to_string()
+        } else {
+            "not running".to_string()
+        });
+    }
+    if n.active && !n.bridge_present() {
+        return Some(format!(
+            "running without its bridge {}",
+            n.bridge.as_deref().unwrap_or("(none defined)")
+        ));
+    }
+    if n.active && n.forward.as_deref() == Some("nat") && ip_forward == Some(false) {
+        return Some("NATs without IPv4 forwarding, so guests cannot get out".to_string());
+    }
+    None
+}
+
+pub fn diagnose() -> NetworkDiagnostics {
+    let mut diag = NetworkDiagnostics {
+        ip_forward: system::read(host::path("/proc/sys/net/ipv4/ip_forward")).map(|v| v == "1"),
+        ..NetworkDiagnostics::default()
+    };
+    if !system::has("virsh") {
+        return diag;
+    }
+    match libvirt::networks() {
+        Ok(networks) => {
+            diag.networks = networks
+                .into_iter()
+                .map(|network| NetworkStatus {
+                    problem: problem(&network, diag.ip_forward),
+                    network,
+                })
+                .collect()
+        }
+        Err(e) => {
+            diag.error = Some(e);
+            return diag;
+        }
+    }
+    diag.default_defined = diag.default_network().is_some();
+
+    for s in &diag.networks {
+        if let Some(problem) = &s.problem {
+            diag.warnings
+                .push(format!("Network {} {}", s.network.name, problem));
+        }
+    }
+    if diag.networks.iter().any(NetworkStatus::restartable) {
+        diag.recommendations
+            .push("Restart the networks: virtualization-ambulance repair networks".to_string());
+    }
+    for s in diag
+        .networks
+        .iter()
+        .filter(|s| s.problem.is_some() && !s.restartable())
+    {
+        diag.recommendations.push(format!(
+            "Create the host bridge {} that network {} attaches to",
+            s.network.bridge.as_deref().unwrap_or("?"),
+            s.network.name
+        ));
+    }
+    match diag
+        .default_network()
+        .map(|s| (s.network.autostart, s.problem.is_some()))
+    {
+        None => {
+            diag.warnings
+                .push("The default NAT network is not defined".to_string());
+            diag.recommendations
+                .push(if Path::new(DEFAULT_XML).exists() {
+                    "Define it again: virtualization-ambulance repair networks".to_string()
+                } else {
+                    "Reinstall the libvirt network driver package, which ships it".to_string()
+                });
+        }
+        Some((false, broken)) => {
+            diag.warnings.push(
+                "The default network does not start at boot; new guests have no network until it is started"
+                    .to_string(),
+            );
+            if !broken {
+                diag.recommendations.push(
+                    "Mark it to start at boot: virtualization-ambulance repair networks"
+                        .to_string(),
+                );
+            }
+        }
+        Some(_) => {}
+    }
+    diag
+}
+
+impl NetworkDiagnostics {
+    pub fn print(&self, verbose: bool) {
+        println!("=== Networks ===");
+        if let Some(e) = &self.error {
+            println!("- Cannot list networks: {}", e);
+        }
+        for s in &self.networks {
+            let n = &s.network;
+            println!(
+                "{} {}: {}{}",
+                mark(s.problem.is_none()),
+                n.name,
+                if n.active { "active" } else { "inactive" },
+                if n.autostart { ", autostart" } else { "" }
+            );
+            if verbose {
+                println!(
+                    "    {}, bridge {}{}",
+                    n.forward.as_deref().unwrap_or("isolated"),
+                    n.bridge.as_deref().unwrap_or("none"),
+                    if n.persistent { "" } else { ", transient" }
+                );
+            }
+        }
+        if verbose {
+            if let Some(forward) = self.ip_forward {
+                println!("- IPv4 forwarding: {}", if forward { "on" } else { "off" });
+            }
+        }
+        print_notes(&self.warnings, &self.recommendations);
+    }
+}
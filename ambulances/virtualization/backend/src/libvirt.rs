@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! libvirt's system daemons and virtual networks, through virsh
+//!
+//! libvirt ships either the monolithic `libvirtd` or, since 7.0 and by
+//! default on Fedora and recent Ubuntu, one modular daemon per driver;
+//! VMs are run by `virtqemud` and networks by `virtnetworkd`. Only the
+//! system connection is checked: session VMs get user-mode networking
+//! and need neither the groups nor the NAT network.
+
+use crate::system;
+use ambulance_core::host;
+use serde::Serialize;
+
+/// The system connection; virsh's default depends on who runs it
+pub const URI: &str = "qemu:///system";
+
+/// The network libvirt creates on install, NATed through `virbr0`
+pub const DEFAULT_NETWORK: &str = "default";
+
+/// Units of the monolithic and the modular daemons, as `(service, socket)`
+pub const DAEMONS: &[(&str, &str)] = &[
+    ("libvirtd.service", "libvirtd.socket"),
+    ("virtqemud.service", "virtqemud.socket"),
+    ("virtnetworkd.service", "virtnetworkd.socket"),
+];
+
+/// Run virsh on the system connection
+pub fn virsh(args: &[&str]) -> Result<String, String> {
+    let mut full = vec!["-c", URI];
+    full.extend_from_slice(args);
+    system::run("virsh", &full)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Network {
+    pub name: String,
+    pub active: bool,
+    pub autostart: bool,
+    pub persistent: bool,
+    /// Bridge device, e.g. `virbr0`
+    pub bridge: Option<String>,
+    /// `nat`, `route`, `bridge`, ...; `None` for an isolated network
+    pub forward: Option<String>,
+}
+
+impl Network {
+    /// Whether the network's bridge device exists
+    pub fn bridge_present(&self) -> bool {
+        self.bridge
+            .as_ref()
+            .is_some_and(|b| host::path("/sys/class/net").join(b).exists())
+    }
+
+    /// Whether the network should be running: the default one, or set to autostart
+    pub fn wanted(&self) -> bool {
+        self.autostart || self.name == DEFAULT_NETWORK
+    }
+}
+
+/// Value of `attribute` on the first `<element ...>` in `xml`
+fn attribute<'a>(xml: &'a str, element: &str, attribute: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{}", element))?;
+    let tag = &xml[start..start + xml[start..].find('>')?];
+    let at = tag.find(&format!(" {}=", attribute))? + attribute.len() + 2;
+    let quote = tag[at..].chars().next()?;
+    let value = &tag[at + 1..];
+    Some(&value[..value.find(quote)?])
+}
+
+fn network(name: &str) -> Result<Network, String> {
+    let info = virsh(&["net-info", name])?;
+    let flag = |key: &str| {
+        info.lines()
+            .find_map(|l| l.strip_prefix(key))
+            .is_some_and(|v| v.trim_start_matches(':').trim() == "yes")
+    };
+    let xml = virsh(&["net-dumpxml", name])?;
+    // A <forward> element without a mode means NAT
+    let forward = xml.contains("<forward").then(|| {
+        attribute(&xml, "forward", "mode")
+            .unwrap_or("nat")
+            .to_string()
+    });
+    Ok(Network {
+        name: name.to_string(),
+        active: flag("Active"),
+        autostart: flag("Autostart"),
+        persistent: flag("Persistent"),
+        bridge: attribute(&xml, "bridge", "name").map(str::to_string),
+        forward,
+    })
+}
+
+/// Every network, active or not, defined on the system connection
+pub fn networks() -> Result<Vec<Network>, String> {
+    virsh(&["net-list", "--all", "--name"])?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(network)
+        .collect()
+}
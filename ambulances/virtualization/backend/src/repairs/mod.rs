@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Virtualization repairs, one module per target
+//!
+//! Firmware settings and group membership are left to the administrator:
+//! the first needs a reboot into the setup, the second grants what is
+//! close to root.
+
+pub mod modules;
+pub mod networks;
+
+use crate::report::{RepairOutcome, RepairResult, TOOL, VERSION};
+use ambulance_core::Error;
+
+pub const TARGETS: &[&str] = &["modules", "networks", "all"];
+
+/// Run the repairs for `target`; targets not selected stay at their default
+///
+/// `confirm` is asked before each change.
+pub fn run(target: &str, confirm: &mut dyn FnMut(&str) -> bool) -> Result<RepairResult, Error> {
+    if !TARGETS.contains(&target) {
+        return Err(Error::unknown_target(target, TARGETS));
+    }
+    let selected = |name: &str| target == name || target == "all";
+
+    Ok(RepairResult {
+        version: VERSION,
+        tool: TOOL,
+        modules_repair: if selected("modules") {
+            modules::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+        networks_repair: if selected("networks") {
+            networks::repair(confirm)
+        } else {
+            RepairOutcome::default()
+        },
+    })
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Load the kvm modules when the CPU can run them
+//!
+//! Loading the vendor module pulls in `kvm` and creates `/dev/kvm`.
+//! Nothing is tried when the firmware has the extension off: the module
+//! would only refuse with "Operation not supported", and the fix is in
+//! the firmware setup.
+
+use crate::diagnostics::hardware;
+use crate::report::RepairOutcome;
+use crate::system;
+use std::path::Path;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let diag = hardware::diagnose();
+    if diag.dev_kvm {
+        return RepairOutcome::not_needed("/dev/kvm is present, no repair needed");
+    }
+
+    let mut result = RepairOutcome::default();
+    let Some(vendor) = diag.vendor else {
+        result.errors.push(
+            "/dev/kvm is missing and this is not an x86 CPU; check that the kernel is built with KVM"
+                .to_string(),
+        );
+        return result;
+    };
+    if diag.firmware_disabled || !diag.extension {
+        result.errors.push(format!(
+            "{} is not available to the kernel; enable it in the {} first",
+            vendor.extension,
+            if diag.in_vm && !diag.firmware_disabled {
+                "host's settings for this guest"
+            } else {
+                "firmware setup"
+            }
+        ));
+        return result;
+    }
+    if !confirm(&format!("Load the {} module?", vendor.module)) {
+        result.errors.push(format!(
+            "{} not confirmed, left unloaded (rerun with --yes to confirm)",
+            vendor.module
+        ));
+        return result;
+    }
+    match system::run("modprobe", &[vendor.module]) {
+        Ok(_) if Path::new("/dev/kvm").exists() => result
+            .actions
+            .push(format!("Loaded {}; /dev/kvm is present", vendor.module)),
+        Ok(_) => result.errors.push(format!(
+            "{} loaded but /dev/kvm did not appear; check that udev is running",
+            vendor.module
+        )),
+        Err(e) => result.errors.push(e),
+    }
+    result.success = result.errors.is_empty();
+    result
+}
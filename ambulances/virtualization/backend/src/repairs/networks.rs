@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: PMPL-1.0-or-later
+//! Restart broken libvirt networks and restore the default one
+//!
+//! A network running without its bridge is stopped before it is started
+//! again, which recreates the bridge, its dnsmasq and its NAT rules and
+//! turns IPv4 forwarding back on. Guests attached to it keep a dangling
+//! interface until they are restarted or their interface is replugged.
+//! A missing default network is defined again from the copy libvirt
+//! installs, and is marked to start at boot.
+
+use crate::diagnostics::networks::{self, DEFAULT_XML};
+use crate::libvirt::{virsh, DEFAULT_NETWORK};
+use crate::report::RepairOutcome;
+use std::path::Path;
+
+pub fn repair(confirm: &mut dyn FnMut(&str) -> bool) -> RepairOutcome {
+    let mut result = RepairOutcome::default();
+    let diag = networks::diagnose();
+    if let Some(e) = diag.error {
+        result.errors.push(format!("Cannot list networks: {}", e));
+        return result;
+    }
+    let define = !diag.default_defined && Path::new(DEFAULT_XML).exists();
+    let restart: Vec<_> = diag.networks.iter().filter(|s| s.restartable()).collect();
+    let autostart = diag.default_network().is_some_and(|s| !s.network.autostart);
+    if !define && restart.is_empty() && !autostart {
+        return RepairOutcome::not_needed("Every network runs as it should, no repair needed");
+    }
+
+    let mut start: Vec<&str> = Vec::new();
+    if define {
+        if !confirm("Define the default NAT network again?") {
+            result.errors.push(
+                "Default network not confirmed, left undefined (rerun with --yes to confirm)"
+                    .to_string(),
+            );
+        } else {
+            match virsh(&["net-define", DEFAULT_XML]) {
+                Ok(_) => {
+                    result
+                        .actions
+                        .push(format!("Defined the default network from {}", DEFAULT_XML));
+                    start.push(DEFAULT_NETWORK);
+                }
+                Err(e) => result.errors.push(e),
+            }
+        }
+    }
+
+    for s in restart {
+        let n = &s.network;
+        let question = if n.active {
+            format!(
+                "Restart network {}? Guests on it need restarting to reconnect",
+                n.name
+            )
+        } else {
+            format!("Start network {}?", n.name)
+        };
+        if !confirm(&question) {
+            result.errors.push(format!(
+                "Network {} not confirmed, left as is (rerun with --yes to confirm)",
+                n.name
+            ));
+            continue;
+        }
+        if n.active {
+            if let Err(e) = virsh(&["net-destroy", &n.name]) {
+                result.errors.push(e);
+                continue;
+            }
+        }
+        start.push(&n.name);
+    }
+    for name in &start {
+        match virsh(&["net-start", name]) {
+            Ok(_) => result.actions.push(format!("Started network {}", name)),
+            Err(e) => result.errors.push(e),
+        }
+    }
+
+    if autostart || start.contains(&DEFAULT_NETWORK) {
+        if confirm("Start the default network at boot?") {
+            match virsh(&["net-autostart", DEFAULT_NETWORK]) {
+                Ok(_) => result
+                    .actions
+                    .push("Marked the default network to start at boot".to_string()),
+                Err(e) => result.errors.push(e),
+            }
+        } else {
+            result.errors.push(
+                "Default network autostart not confirmed, left as is (rerun with --yes to confirm)"
+                    .to_string(),
+            );
+        }
+    }
+
+    // virsh can succeed and leave the bridge missing, so check again
+    for s in networks::diagnose().networks {
+        if let Some(problem) = s
+            .problem
+            .filter(|_| start.contains(&s.network.name.as_str()))
+        {
+            result
+                .errors
+                .push(format!("Network {} is still {}", s.network.name, problem));
+        }
+    }
+    result.success = result.errors.is_empty();
+    result
+}
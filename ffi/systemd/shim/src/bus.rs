@@ -0,0 +1,840 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Safe Rust layer over sd-bus for in-process consumers
+//!
+//! Covers what the ambulances ask of the systemd manager, logind and
+//! timedated: listing units and inhibitors, reading typed properties, the
+//! unit lifecycle calls and switching NTP. Failed calls return an `io::Error` carrying the bus
+//! error message when there is one. Services that answer in `a{sv}`
+//! dictionaries, such as fwupd, are read through `call_dicts`.
+
+use crate::raw;
+use libc::{c_char, c_int, c_void};
+use std::collections::BTreeMap;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::ptr;
+
+pub const SYSTEMD: &str = "org.freedesktop.systemd1";
+pub const MANAGER_PATH: &str = "/org/freedesktop/systemd1";
+pub const MANAGER: &str = "org.freedesktop.systemd1.Manager";
+pub const UNIT: &str = "org.freedesktop.systemd1.Unit";
+pub const SERVICE: &str = "org.freedesktop.systemd1.Service";
+pub const SCOPE: &str = "org.freedesktop.systemd1.Scope";
+pub const SLICE: &str = "org.freedesktop.systemd1.Slice";
+
+pub const LOGIN: &str = "org.freedesktop.login1";
+pub const LOGIN_PATH: &str = "/org/freedesktop/login1";
+pub const LOGIN_MANAGER: &str = "org.freedesktop.login1.Manager";
+
+/// timedated's bus name, which is also its interface
+pub const TIMEDATE: &str = "org.freedesktop.timedate1";
+pub const TIMEDATE_PATH: &str = "/org/freedesktop/timedate1";
+
+/// One row of logind's `ListInhibitors`
+#[derive(Debug, Clone)]
+pub struct Inhibitor {
+    /// Colon-separated, e.g. `sleep:idle`
+    pub what: String,
+    pub who: String,
+    pub why: String,
+    /// `block` or `delay`
+    pub mode: String,
+    pub uid: u32,
+    pub pid: u32,
+}
+
+/// One row of the manager's `ListUnits`
+#[derive(Debug, Clone)]
+pub struct UnitInfo {
+    pub name: String,
+    pub description: String,
+    pub load_state: String,
+    pub active_state: String,
+    pub sub_state: String,
+    /// Object path for property reads on this unit
+    pub path: String,
+}
+
+/// A variant read out of an `a{sv}` dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    Bool(bool),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    Strings(Vec<String>),
+    /// A type not read here, given by its signature
+    Other(String),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any unsigned or non-negative integer
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::U32(v) => Some(v as u64),
+            Value::U64(v) => Some(v),
+            Value::I32(v) => u64::try_from(v).ok(),
+            Value::I64(v) => u64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_strings(&self) -> Option<&[String]> {
+        match self {
+            Value::Strings(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// One `a{sv}` dictionary
+pub type Dict = BTreeMap<String, Value>;
+
+fn cstring(s: &str) -> io::Result<CString> {
+    CString::new(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Copy a string borrowed from a message, treating NULL as empty
+unsafe fn borrowed(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }
+}
+
+/// Holds the out-parameter error of one call and frees it when done
+struct CallError(raw::sd_bus_error);
+
+impl CallError {
+    fn new() -> CallError {
+        CallError(raw::sd_bus_error::default())
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut raw::sd_bus_error {
+        &mut self.0
+    }
+
+    /// Turn a negative return into an error, preferring the bus message
+    fn check(&self, ret: c_int) -> io::Result<c_int> {
+        if ret >= 0 {
+            return Ok(ret);
+        }
+        let errno = io::Error::from_raw_os_error(-ret);
+        if self.0.message.is_null() {
+            return Err(errno);
+        }
+        let message = unsafe { borrowed(self.0.message) };
+        Err(io::Error::new(errno.kind(), message))
+    }
+}
+
+impl Drop for CallError {
+    fn drop(&mut self) {
+        unsafe { raw::sd_bus_error_free(&mut self.0) }
+    }
+}
+
+/// A method reply, unreferenced on drop
+struct Message(*mut raw::sd_bus_message);
+
+impl Drop for Message {
+    fn drop(&mut self) {
+        unsafe { raw::sd_bus_message_unref(self.0) };
+    }
+}
+
+fn check(ret: c_int) -> io::Result<c_int> {
+    CallError::new().check(ret)
+}
+
+/// Object path of a loaded unit, without asking the manager
+pub fn unit_path(name: &str) -> io::Result<String> {
+    let prefix = cstring("/org/freedesktop/systemd1/unit")?;
+    let name = cstring(name)?;
+    let mut path: *mut c_char = ptr::null_mut();
+    check(unsafe { raw::sd_bus_path_encode(prefix.as_ptr(), name.as_ptr(), &mut path) })?;
+    let encoded = unsafe { borrowed(path) };
+    unsafe { libc::free(path as *mut c_void) };
+    Ok(encoded)
+}
+
+/// Read an `as` from `m`, freeing the copies sd-bus makes
+fn read_strv(m: *mut raw::sd_bus_message) -> io::Result<Vec<String>> {
+    let mut strv: *mut *mut c_char = ptr::null_mut();
+    check(unsafe { raw::sd_bus_message_read_strv(m, &mut strv) })?;
+    let mut strings = Vec::new();
+    if !strv.is_null() {
+        let mut i = 0;
+        loop {
+            let s = unsafe { *strv.add(i) };
+            if s.is_null() {
+                break;
+            }
+            strings.push(unsafe { borrowed(s) });
+            unsafe { libc::free(s as *mut c_void) };
+            i += 1;
+        }
+        unsafe { libc::free(strv as *mut c_void) };
+    }
+    Ok(strings)
+}
+
+/// Read the variant at the message's cursor
+fn read_variant(m: *mut raw::sd_bus_message) -> io::Result<Value> {
+    let mut kind: c_char = 0;
+    let mut contents: *const c_char = ptr::null();
+    check(unsafe { raw::sd_bus_message_peek_type(m, &mut kind, &mut contents) })?;
+    let signature = unsafe { borrowed(contents) };
+    let inner = cstring(&signature)?;
+    check(unsafe { raw::sd_bus_message_enter_container(m, b'v' as c_char, inner.as_ptr()) })?;
+    let value = match signature.as_str() {
+        "s" | "o" => {
+            let mut v: *const c_char = ptr::null();
+            check(unsafe { raw::sd_bus_message_read(m, inner.as_ptr(), &mut v) })?;
+            Value::Str(unsafe { borrowed(v) })
+        }
+        "b" => {
+            let mut v: c_int = 0;
+            check(unsafe { raw::sd_bus_message_read(m, inner.as_ptr(), &mut v) })?;
+            Value::Bool(v != 0)
+        }
+        "u" => {
+            let mut v: u32 = 0;
+            check(unsafe { raw::sd_bus_message_read(m, inner.as_ptr(), &mut v) })?;
+            Value::U32(v)
+        }
+        "i" => {
+            let mut v: i32 = 0;
+            check(unsafe { raw::sd_bus_message_read(m, inner.as_ptr(), &mut v) })?;
+            Value::I32(v)
+        }
+        "t" => {
+            let mut v: u64 = 0;
+            check(unsafe { raw::sd_bus_message_read(m, inner.as_ptr(), &mut v) })?;
+            Value::U64(v)
+        }
+        "x" => {
+            let mut v: i64 = 0;
+            check(unsafe { raw::sd_bus_message_read(m, inner.as_ptr(), &mut v) })?;
+            Value::I64(v)
+        }
+        "as" => Value::Strings(read_strv(m)?),
+        _ => {
+            check(unsafe { raw::sd_bus_message_skip(m, inner.as_ptr()) })?;
+            Value::Other(signature)
+        }
+    };
+    check(unsafe { raw::sd_bus_message_exit_container(m) })?;
+    Ok(value)
+}
+
+/// Read an `a{sv}` at the message's cursor
+fn read_dict(m: *mut raw::sd_bus_message) -> io::Result<Option<Dict>> {
+    let (entries, entry, key) = (cstring("{sv}")?, cstring("sv")?, cstring("s")?);
+    if check(unsafe { raw::sd_bus_message_enter_container(m, b'a' as c_char, entries.as_ptr()) })?
+        == 0
+    {
+        return Ok(None);
+    }
+    let mut dict = Dict::new();
+    while check(unsafe { raw::sd_bus_message_enter_container(m, b'e' as c_char, entry.as_ptr()) })?
+        > 0
+    {
+        let mut name: *const c_char = ptr::null();
+        check(unsafe { raw::sd_bus_message_read(m, key.as_ptr(), &mut name) })?;
+        let name = unsafe { borrowed(name) };
+        dict.insert(name, read_variant(m)?);
+        check(unsafe { raw::sd_bus_message_exit_container(m) })?;
+    }
+    check(unsafe { raw::sd_bus_message_exit_container(m) })?;
+    Ok(Some(dict))
+}
+
+/// Bus connection handle
+pub struct Bus {
+    bus: *mut raw::sd_bus,
+}
+
+impl Bus {
+    /// Connect to the system bus
+    pub fn system() -> io::Result<Bus> {
+        let mut bus = ptr::null_mut();
+        check(unsafe { raw::sd_bus_open_system(&mut bus) })?;
+        Ok(Bus { bus })
+    }
+
+    /// Connect to the calling user's session bus
+    pub fn user() -> io::Result<Bus> {
+        let mut bus = ptr::null_mut();
+        check(unsafe { raw::sd_bus_open_user(&mut bus) })?;
+        Ok(Bus { bus })
+    }
+
+    /// Connect to the system bus of `host`, `[user@]host[:port]`, over SSH
+    pub fn remote(host: &str) -> io::Result<Bus> {
+        let host = cstring(host)?;
+        let mut bus = ptr::null_mut();
+        check(unsafe { raw::sd_bus_open_system_remote(&mut bus, host.as_ptr()) })?;
+        Ok(Bus { bus })
+    }
+
+    /// Connect to the system bus inside the local container `machine`
+    pub fn machine(machine: &str) -> io::Result<Bus> {
+        let machine = cstring(machine)?;
+        let mut bus = ptr::null_mut();
+        check(unsafe { raw::sd_bus_open_system_machine(&mut bus, machine.as_ptr()) })?;
+        Ok(Bus { bus })
+    }
+
+    /// Connect to the bus at `address`, e.g. `unix:path=/run/dbus/system_bus_socket`
+    pub fn at(address: &str) -> io::Result<Bus> {
+        Bus::connect(address, true)
+    }
+
+    /// Talk to the one peer at `address` with no bus daemon in between,
+    /// such as a service manager's `systemd/private` socket; it still
+    /// answers for [`SYSTEMD`] at [`MANAGER_PATH`]
+    pub fn peer(address: &str) -> io::Result<Bus> {
+        Bus::connect(address, false)
+    }
+
+    fn connect(address: &str, client: bool) -> io::Result<Bus> {
+        let address = cstring(address)?;
+        let mut bus = ptr::null_mut();
+        check(unsafe { raw::sd_bus_new(&mut bus) })?;
+        // Owned from here, so a failed start still frees it
+        let bus = Bus { bus };
+        check(unsafe { raw::sd_bus_set_address(bus.bus, address.as_ptr()) })?;
+        check(unsafe { raw::sd_bus_set_bus_client(bus.bus, c_int::from(client)) })?;
+        check(unsafe { raw::sd_bus_start(bus.bus) })?;
+        Ok(bus)
+    }
+
+    pub fn get_property_string(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<String> {
+        let (destination, path) = (cstring(destination)?, cstring(path)?);
+        let (interface, member) = (cstring(interface)?, cstring(member)?);
+        let mut error = CallError::new();
+        let mut value: *mut c_char = ptr::null_mut();
+        let ret = unsafe {
+            raw::sd_bus_get_property_string(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                &mut value,
+            )
+        };
+        error.check(ret)?;
+        let result = unsafe { borrowed(value) };
+        unsafe { libc::free(value as *mut c_void) };
+        Ok(result)
+    }
+
+    /// Read a fixed-size property; `T` must match the D-Bus `signature`
+    fn get_property_trivial<T: Default>(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        signature: u8,
+    ) -> io::Result<T> {
+        let (destination, path) = (cstring(destination)?, cstring(path)?);
+        let (interface, member) = (cstring(interface)?, cstring(member)?);
+        let mut error = CallError::new();
+        let mut value = T::default();
+        let ret = unsafe {
+            raw::sd_bus_get_property_trivial(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                signature as c_char,
+                &mut value as *mut T as *mut c_void,
+            )
+        };
+        error.check(ret)?;
+        Ok(value)
+    }
+
+    /// `u` property
+    pub fn get_property_u32(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<u32> {
+        self.get_property_trivial(destination, path, interface, member, b'u')
+    }
+
+    /// `i` property
+    pub fn get_property_i32(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<i32> {
+        self.get_property_trivial(destination, path, interface, member, b'i')
+    }
+
+    /// `t` property, e.g. the `*Timestamp` properties in microseconds
+    pub fn get_property_u64(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<u64> {
+        self.get_property_trivial(destination, path, interface, member, b't')
+    }
+
+    /// `d` property
+    pub fn get_property_f64(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<f64> {
+        self.get_property_trivial(destination, path, interface, member, b'd')
+    }
+
+    /// `b` property
+    pub fn get_property_bool(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<bool> {
+        let value: c_int = self.get_property_trivial(destination, path, interface, member, b'b')?;
+        Ok(value != 0)
+    }
+
+    /// Call a method without arguments that returns object paths (`ao`),
+    /// such as UPower's `EnumerateDevices`
+    pub fn call_object_paths(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+    ) -> io::Result<Vec<String>> {
+        let (destination, path) = (cstring(destination)?, cstring(path)?);
+        let (interface, member) = (cstring(interface)?, cstring(member)?);
+        let mut error = CallError::new();
+        let mut reply = Message(ptr::null_mut());
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                &mut reply.0,
+                ptr::null(),
+            )
+        };
+        error.check(ret)?;
+
+        let object = cstring("o")?;
+        check(unsafe {
+            raw::sd_bus_message_enter_container(reply.0, b'a' as c_char, object.as_ptr())
+        })?;
+        let mut paths = Vec::new();
+        loop {
+            let mut value: *const c_char = ptr::null();
+            if check(unsafe { raw::sd_bus_message_read(reply.0, object.as_ptr(), &mut value) })?
+                == 0
+            {
+                break;
+            }
+            paths.push(unsafe { borrowed(value) });
+        }
+        check(unsafe { raw::sd_bus_message_exit_container(reply.0) })?;
+        Ok(paths)
+    }
+
+    /// Call a method taking no argument, or one string, that returns an
+    /// array of dictionaries (`aa{sv}`), such as fwupd's `GetDevices`
+    pub fn call_dicts(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        arg: Option<&str>,
+    ) -> io::Result<Vec<Dict>> {
+        let reply = self.call_with_string(destination, path, interface, member, arg)?;
+        let dict = cstring("a{sv}")?;
+        check(unsafe {
+            raw::sd_bus_message_enter_container(reply.0, b'a' as c_char, dict.as_ptr())
+        })?;
+        let mut dicts = Vec::new();
+        while let Some(d) = read_dict(reply.0)? {
+            dicts.push(d);
+        }
+        check(unsafe { raw::sd_bus_message_exit_container(reply.0) })?;
+        Ok(dicts)
+    }
+
+    /// Call a method taking no argument, or one string, that returns an
+    /// array of strings (`as`), such as fprintd's `ListEnrolledFingers`
+    pub fn call_strings(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        arg: Option<&str>,
+    ) -> io::Result<Vec<String>> {
+        let reply = self.call_with_string(destination, path, interface, member, arg)?;
+        read_strv(reply.0)
+    }
+
+    /// Send a method call with an optional string argument and wait for the reply
+    fn call_with_string(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        arg: Option<&str>,
+    ) -> io::Result<Message> {
+        let (destination, path) = (cstring(destination)?, cstring(path)?);
+        let (interface, member) = (cstring(interface)?, cstring(member)?);
+        let mut call = Message(ptr::null_mut());
+        check(unsafe {
+            raw::sd_bus_message_new_method_call(
+                self.bus,
+                &mut call.0,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+            )
+        })?;
+        if let Some(arg) = arg {
+            let (signature, arg) = (cstring("s")?, cstring(arg)?);
+            check(unsafe { raw::sd_bus_message_append(call.0, signature.as_ptr(), arg.as_ptr()) })?;
+        }
+        let mut error = CallError::new();
+        let mut reply = Message(ptr::null_mut());
+        let ret =
+            unsafe { raw::sd_bus_call(self.bus, call.0, 0, error.as_mut_ptr(), &mut reply.0) };
+        error.check(ret)?;
+        Ok(reply)
+    }
+
+    /// Inhibitor locks held with logind
+    pub fn list_inhibitors(&self) -> io::Result<Vec<Inhibitor>> {
+        let (destination, path) = (cstring(LOGIN)?, cstring(LOGIN_PATH)?);
+        let (interface, member) = (cstring(LOGIN_MANAGER)?, cstring("ListInhibitors")?);
+        let mut error = CallError::new();
+        let mut reply = Message(ptr::null_mut());
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                &mut reply.0,
+                ptr::null(),
+            )
+        };
+        error.check(ret)?;
+
+        let row = cstring("(ssssuu)")?;
+        check(unsafe {
+            raw::sd_bus_message_enter_container(reply.0, b'a' as c_char, row.as_ptr())
+        })?;
+        let mut inhibitors = Vec::new();
+        loop {
+            let mut fields: [*const c_char; 4] = [ptr::null(); 4];
+            let f = fields.as_mut_ptr();
+            let (mut uid, mut pid): (u32, u32) = (0, 0);
+            let more = check(unsafe {
+                raw::sd_bus_message_read(
+                    reply.0,
+                    row.as_ptr(),
+                    f.add(0),
+                    f.add(1),
+                    f.add(2),
+                    f.add(3),
+                    &mut uid,
+                    &mut pid,
+                )
+            })?;
+            if more == 0 {
+                break;
+            }
+            let [what, who, why, mode] = fields.map(|f| unsafe { borrowed(f) });
+            inhibitors.push(Inhibitor {
+                what,
+                who,
+                why,
+                mode,
+                uid,
+                pid,
+            });
+        }
+        check(unsafe { raw::sd_bus_message_exit_container(reply.0) })?;
+        Ok(inhibitors)
+    }
+
+    /// Every unit the manager has loaded
+    pub fn list_units(&self) -> io::Result<Vec<UnitInfo>> {
+        let (destination, path) = (cstring(SYSTEMD)?, cstring(MANAGER_PATH)?);
+        let (interface, member) = (cstring(MANAGER)?, cstring("ListUnits")?);
+        let mut error = CallError::new();
+        let mut reply = Message(ptr::null_mut());
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                &mut reply.0,
+                ptr::null(),
+            )
+        };
+        error.check(ret)?;
+
+        let row = cstring("(ssssssouso)")?;
+        check(unsafe {
+            raw::sd_bus_message_enter_container(reply.0, b'a' as c_char, row.as_ptr())
+        })?;
+        let mut units = Vec::new();
+        loop {
+            let mut fields: [*const c_char; 7] = [ptr::null(); 7];
+            let f = fields.as_mut_ptr();
+            let mut job_id: u32 = 0;
+            let (mut job_type, mut job_path): (*const c_char, *const c_char) =
+                (ptr::null(), ptr::null());
+            let more = check(unsafe {
+                raw::sd_bus_message_read(
+                    reply.0,
+                    row.as_ptr(),
+                    f.add(0),
+                    f.add(1),
+                    f.add(2),
+                    f.add(3),
+                    f.add(4),
+                    f.add(5),
+                    f.add(6),
+                    &mut job_id,
+                    &mut job_type,
+                    &mut job_path,
+                )
+            })?;
+            if more == 0 {
+                break;
+            }
+            let [name, description, load, active, sub, _following, unit_path] =
+                fields.map(|f| unsafe { borrowed(f) });
+            units.push(UnitInfo {
+                name,
+                description,
+                load_state: load,
+                active_state: active,
+                sub_state: sub,
+                path: unit_path,
+            });
+        }
+        check(unsafe { raw::sd_bus_message_exit_container(reply.0) })?;
+        Ok(units)
+    }
+
+    /// Call a manager method taking one unit name and a job mode, e.g.
+    /// `RestartUnit`; returns the queued job's object path
+    fn unit_job(&self, method: &str, name: &str, mode: &str) -> io::Result<String> {
+        let (destination, path) = (cstring(SYSTEMD)?, cstring(MANAGER_PATH)?);
+        let (interface, member) = (cstring(MANAGER)?, cstring(method)?);
+        let (name, mode) = (cstring(name)?, cstring(mode)?);
+        let (signature, object) = (cstring("ss")?, cstring("o")?);
+        let mut error = CallError::new();
+        let mut reply = Message(ptr::null_mut());
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                &mut reply.0,
+                signature.as_ptr(),
+                name.as_ptr(),
+                mode.as_ptr(),
+            )
+        };
+        error.check(ret)?;
+        let mut job: *const c_char = ptr::null();
+        check(unsafe { raw::sd_bus_message_read(reply.0, object.as_ptr(), &mut job) })?;
+        Ok(unsafe { borrowed(job) })
+    }
+
+    /// Queue a restart; `mode` is a job mode such as `replace`
+    pub fn restart_unit(&self, name: &str, mode: &str) -> io::Result<String> {
+        self.unit_job("RestartUnit", name, mode)
+    }
+
+    pub fn start_unit(&self, name: &str, mode: &str) -> io::Result<String> {
+        self.unit_job("StartUnit", name, mode)
+    }
+
+    pub fn stop_unit(&self, name: &str, mode: &str) -> io::Result<String> {
+        self.unit_job("StopUnit", name, mode)
+    }
+
+    pub fn reload_unit(&self, name: &str, mode: &str) -> io::Result<String> {
+        self.unit_job("ReloadUnit", name, mode)
+    }
+
+    /// Clear a unit's failed state and start-rate counter
+    pub fn reset_failed_unit(&self, name: &str) -> io::Result<()> {
+        let (destination, path) = (cstring(SYSTEMD)?, cstring(MANAGER_PATH)?);
+        let (interface, member) = (cstring(MANAGER)?, cstring("ResetFailedUnit")?);
+        let (name, signature) = (cstring(name)?, cstring("s")?);
+        let mut error = CallError::new();
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                ptr::null_mut(),
+                signature.as_ptr(),
+                name.as_ptr(),
+            )
+        };
+        error.check(ret)?;
+        Ok(())
+    }
+
+    /// Disable then enable unit files, recreating their `[Install]` links
+    /// as `systemctl reenable` does
+    pub fn reenable_unit_files(&self, files: &[&str]) -> io::Result<()> {
+        let (destination, path) = (cstring(SYSTEMD)?, cstring(MANAGER_PATH)?);
+        let (interface, member) = (cstring(MANAGER)?, cstring("ReenableUnitFiles")?);
+        let files = files
+            .iter()
+            .map(|f| cstring(f))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut strv: Vec<*mut c_char> = files.iter().map(|f| f.as_ptr() as *mut c_char).collect();
+        strv.push(ptr::null_mut());
+
+        let mut call = Message(ptr::null_mut());
+        check(unsafe {
+            raw::sd_bus_message_new_method_call(
+                self.bus,
+                &mut call.0,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+            )
+        })?;
+        check(unsafe { raw::sd_bus_message_append_strv(call.0, strv.as_mut_ptr()) })?;
+        // runtime = false, force = true (replace conflicting links)
+        let flags = cstring("bb")?;
+        check(unsafe {
+            raw::sd_bus_message_append(call.0, flags.as_ptr(), 0 as c_int, 1 as c_int)
+        })?;
+
+        let mut error = CallError::new();
+        let ret =
+            unsafe { raw::sd_bus_call(self.bus, call.0, 0, error.as_mut_ptr(), ptr::null_mut()) };
+        error.check(ret)?;
+        Ok(())
+    }
+
+    /// Make the manager reread unit files, as `systemctl daemon-reload`
+    pub fn reload(&self) -> io::Result<()> {
+        let (destination, path) = (cstring(SYSTEMD)?, cstring(MANAGER_PATH)?);
+        let (interface, member) = (cstring(MANAGER)?, cstring("Reload")?);
+        let mut error = CallError::new();
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null(),
+            )
+        };
+        error.check(ret)?;
+        Ok(())
+    }
+
+    /// Switch network time sync through timedated, which enables and
+    /// starts (or stops) the NTP service it manages
+    pub fn set_ntp(&self, enable: bool) -> io::Result<()> {
+        let (destination, path) = (cstring(TIMEDATE)?, cstring(TIMEDATE_PATH)?);
+        let (interface, member) = (cstring(TIMEDATE)?, cstring("SetNTP")?);
+        let signature = cstring("bb")?;
+        let mut error = CallError::new();
+        let ret = unsafe {
+            raw::sd_bus_call_method(
+                self.bus,
+                destination.as_ptr(),
+                path.as_ptr(),
+                interface.as_ptr(),
+                member.as_ptr(),
+                error.as_mut_ptr(),
+                ptr::null_mut(),
+                signature.as_ptr(),
+                enable as c_int,
+                // interactive: never wait on a polkit prompt
+                0 as c_int,
+            )
+        };
+        error.check(ret)?;
+        Ok(())
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        unsafe { raw::sd_bus_unref(self.bus) };
+    }
+}
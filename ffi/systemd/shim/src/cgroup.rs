@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Map cgroup paths to the units that own them
+//!
+//! The kernel and systemd-oomd name cgroups, not units, when they kill a
+//! process. systemd lays the hierarchy out as slices containing units, so
+//! the owning unit is the first path component that is not a slice. The
+//! same rule applied below `user@.service` gives the user unit.
+
+/// Unit types that own a cgroup
+const UNIT_SUFFIXES: &[&str] = &[".service", ".scope", ".socket", ".mount", ".swap"];
+
+fn is_unit(component: &str) -> bool {
+    UNIT_SUFFIXES.iter().any(|s| component.ends_with(s))
+}
+
+/// First unit below the slices in `components`
+fn first_unit<'a>(mut components: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    components
+        .find(|c| !c.ends_with(".slice"))
+        .filter(|c| is_unit(c))
+}
+
+/// Non-empty components of a path like `/system.slice/foo.service/child`
+fn components(cgroup: &str) -> impl Iterator<Item = &str> {
+    cgroup.split('/').filter(|c| !c.is_empty())
+}
+
+/// System unit owning `cgroup`, e.g. `foo.service` or `user@1000.service`
+pub fn unit(cgroup: &str) -> Option<String> {
+    first_unit(components(cgroup)).map(str::to_string)
+}
+
+/// User unit owning `cgroup` when it lies inside a user manager
+pub fn user_unit(cgroup: &str) -> Option<String> {
+    let mut components = components(cgroup);
+    components.find(|c| c.starts_with("user@") && c.ends_with(".service"))?;
+    first_unit(components).map(str::to_string)
+}
+
+/// cgroup v2 path of a running process, as in `/proc/<pid>/cgroup`
+pub fn of_pid(pid: u32) -> Option<String> {
+    let content = std::fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    content
+        .lines()
+        .find_map(|l| l.strip_prefix("0::"))
+        .map(str::to_string)
+}
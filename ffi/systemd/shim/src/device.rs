@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Safe Rust layer over sd-device for in-process consumers
+//!
+//! Enumerates devices by subsystem and reads what udev and sysfs know
+//! about each: the bound driver, sysfs attributes and udev properties.
+//! Attributes that are absent read as `None` rather than as errors.
+
+use crate::raw;
+use libc::{c_char, c_int};
+use std::ffi::{CStr, CString};
+use std::io;
+use std::ptr;
+
+fn check(ret: c_int) -> io::Result<c_int> {
+    if ret < 0 {
+        Err(io::Error::from_raw_os_error(-ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+/// A referenced device, released on drop
+pub struct Device {
+    device: *mut raw::sd_device,
+}
+
+impl Device {
+    /// Read a string getter, `None` when the device has no such value
+    fn string(
+        &self,
+        getter: unsafe extern "C" fn(*mut raw::sd_device, *mut *const c_char) -> c_int,
+    ) -> Option<String> {
+        let mut value: *const c_char = ptr::null();
+        if unsafe { getter(self.device, &mut value) } < 0 || value.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(value) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    pub fn syspath(&self) -> Option<String> {
+        self.string(raw::sd_device_get_syspath)
+    }
+
+    /// Last component of the sysfs path, e.g. `0000:00:02.0` or `card0`
+    pub fn sysname(&self) -> Option<String> {
+        self.string(raw::sd_device_get_sysname)
+    }
+
+    pub fn subsystem(&self) -> Option<String> {
+        self.string(raw::sd_device_get_subsystem)
+    }
+
+    /// Kernel driver bound to the device, if any
+    pub fn driver(&self) -> Option<String> {
+        self.string(raw::sd_device_get_driver)
+    }
+
+    /// Device node, e.g. `/dev/dri/card0`
+    pub fn devname(&self) -> Option<String> {
+        self.string(raw::sd_device_get_devname)
+    }
+
+    /// A sysfs attribute, trailing newline removed
+    pub fn sysattr(&self, name: &str) -> Option<String> {
+        let name = CString::new(name).ok()?;
+        let mut value: *const c_char = ptr::null();
+        let ret =
+            unsafe { raw::sd_device_get_sysattr_value(self.device, name.as_ptr(), &mut value) };
+        if ret < 0 || value.is_null() {
+            return None;
+        }
+        let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
+        Some(value.trim_end().to_string())
+    }
+
+    /// A binary sysfs attribute such as a connector's `edid`, read whole;
+    /// `sysattr` stops at the first NUL byte
+    pub fn sysattr_bytes(&self, name: &str) -> Option<Vec<u8>> {
+        let path = std::path::Path::new(&self.syspath()?).join(name);
+        std::fs::read(path).ok()
+    }
+
+    /// A udev property such as `ID_VENDOR_FROM_DATABASE`
+    pub fn property(&self, key: &str) -> Option<String> {
+        let key = CString::new(key).ok()?;
+        let mut value: *const c_char = ptr::null();
+        let ret =
+            unsafe { raw::sd_device_get_property_value(self.device, key.as_ptr(), &mut value) };
+        if ret < 0 || value.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(value) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    /// The parent device, e.g. the PCI function behind a DRM card
+    pub fn parent(&self) -> Option<Device> {
+        let mut parent = ptr::null_mut();
+        if unsafe { raw::sd_device_get_parent(self.device, &mut parent) } < 0 || parent.is_null() {
+            return None;
+        }
+        // The parent is owned by the child; take our own reference
+        Some(Device {
+            device: unsafe { raw::sd_device_ref(parent) },
+        })
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe { raw::sd_device_unref(self.device) };
+    }
+}
+
+/// Every device in `subsystem`, e.g. `pci` or `drm`
+pub fn enumerate(subsystem: &str) -> io::Result<Vec<Device>> {
+    let subsystem =
+        CString::new(subsystem).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut enumerator = ptr::null_mut();
+    check(unsafe { raw::sd_device_enumerator_new(&mut enumerator) })?;
+
+    let mut devices = Vec::new();
+    let result = check(unsafe {
+        raw::sd_device_enumerator_add_match_subsystem(enumerator, subsystem.as_ptr(), 1)
+    });
+    if result.is_ok() {
+        let mut device = unsafe { raw::sd_device_enumerator_get_device_first(enumerator) };
+        while !device.is_null() {
+            // Enumerated devices belong to the enumerator; keep our own reference
+            devices.push(Device {
+                device: unsafe { raw::sd_device_ref(device) },
+            });
+            device = unsafe { raw::sd_device_enumerator_get_device_next(enumerator) };
+        }
+    }
+    unsafe { raw::sd_device_enumerator_unref(enumerator) };
+    result.map(|_| devices)
+}
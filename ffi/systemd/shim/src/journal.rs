@@ -0,0 +1,1272 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Safe Rust layer over sd-journal for in-process consumers
+//!
+//! The C ABI in `lib.rs` serves Zig; Rust callers use these wrappers
+//! instead of juggling raw pointers themselves.
+//!
+//! With `SYSTEM_TOOLS_MOCK_JOURNAL` naming a file of entries as
+//! `journalctl -o json` prints them, [`Journal`] reads that file instead
+//! and [`send`] appends to it, so tests can give a backend exactly the
+//! log lines they need. The running boot is then that of the newest
+//! entry.
+
+use crate::raw;
+use libc::c_int;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{CStr, CString};
+use std::io::{self, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Only open journal files generated on the local machine
+pub const LOCAL_ONLY: c_int = 1;
+
+/// For `Journal::open_directory`: the directory is the root of a system,
+/// say a rescue disk mounted there, to read the journal under
+pub const OS_ROOT: c_int = crate::SYSTEMD_SHIM_JOURNAL_OS_ROOT;
+
+/// Where journald keeps its files: persistent storage, then volatile
+pub const DIRECTORIES: &[&str] = &["/var/log/journal", "/run/log/journal"];
+
+/// Environment variable naming a file of JSON entries to use as the journal
+pub const MOCK_VAR: &str = "SYSTEM_TOOLS_MOCK_JOURNAL";
+
+fn mock_path() -> Option<PathBuf> {
+    std::env::var_os(MOCK_VAR)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+}
+
+/// A string sd-journal allocated, copied out and freed
+unsafe fn owned(s: *mut libc::c_char) -> String {
+    let text = CStr::from_ptr(s).to_string_lossy().into_owned();
+    libc::free(s as *mut libc::c_void);
+    text
+}
+
+/// The message catalog's text for `message_id`, as written, with
+/// `@FIELD@` placeholders for an entry's fields
+pub fn catalog_for_message_id(message_id: &str) -> io::Result<String> {
+    let id = CString::new(message_id).map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+    let mut text = ptr::null_mut();
+    check(unsafe {
+        crate::systemd_shim_journal_get_catalog_for_message_id(id.as_ptr(), &mut text)
+    })?;
+    Ok(unsafe { owned(text) })
+}
+
+/// A field value as `journalctl -o json` gives it: text when it is
+/// printable UTF-8 (newlines and tabs allowed), else an array of bytes
+fn json_value(bytes: &[u8]) -> Value {
+    let control = |c: char| (c < ' ' && c != '\t' && c != '\n') || ('\x7f'..='\u{9f}').contains(&c);
+    match std::str::from_utf8(bytes) {
+        Ok(text) if !text.chars().any(control) => Value::String(text.to_string()),
+        _ => Value::Array(bytes.iter().map(|&b| Value::from(b)).collect()),
+    }
+}
+
+/// What sd-journal keeps for the current entry outside its fields: the
+/// cursor, timestamps and boot ID, named and ordered as journalctl gives them
+unsafe fn entry_header(journal: *mut raw::sd_journal) -> io::Result<[(&'static str, String); 4]> {
+    let mut cursor = ptr::null_mut();
+    check(raw::sd_journal_get_cursor(journal, &mut cursor))?;
+    let cursor = owned(cursor);
+    let mut realtime = 0;
+    check(raw::sd_journal_get_realtime_usec(journal, &mut realtime))?;
+    let (mut monotonic, mut boot_id) = (0, ptr::null_mut());
+    check(crate::systemd_shim_journal_get_monotonic_usec(
+        journal,
+        &mut monotonic,
+        &mut boot_id,
+    ))?;
+    Ok([
+        ("__CURSOR", cursor),
+        ("__REALTIME_TIMESTAMP", realtime.to_string()),
+        ("__MONOTONIC_TIMESTAMP", monotonic.to_string()),
+        ("_BOOT_ID", owned(boot_id)),
+    ])
+}
+
+/// Each field of the current entry as its name and value, but `_BOOT_ID`,
+/// which `entry_header` gives
+unsafe fn each_field(
+    journal: *mut raw::sd_journal,
+    mut f: impl FnMut(&str, &[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    raw::sd_journal_restart_data(journal);
+    loop {
+        let (mut data, mut len) = (ptr::null(), 0);
+        if check(raw::sd_journal_enumerate_data(journal, &mut data, &mut len))? == 0 {
+            return Ok(());
+        }
+        let bytes = std::slice::from_raw_parts(data as *const u8, len);
+        let Some(eq) = bytes.iter().position(|&b| b == b'=') else {
+            continue;
+        };
+        let field = String::from_utf8_lossy(&bytes[..eq]);
+        if field != "_BOOT_ID" {
+            f(&field, &bytes[eq + 1..])?;
+        }
+    }
+}
+
+/// The current entry of `journal` as `journalctl -o json` writes it
+pub(crate) unsafe fn entry_json(journal: *mut raw::sd_journal) -> io::Result<Value> {
+    let mut entry: serde_json::Map<String, Value> = entry_header(journal)?
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), Value::String(v)))
+        .collect();
+    each_field(journal, |field, bytes| {
+        let value = json_value(bytes);
+        match entry.get_mut(field) {
+            Some(Value::Array(values)) if values.first().is_some_and(|v| !v.is_u64()) => {
+                values.push(value)
+            }
+            Some(first) => *first = Value::Array(vec![first.take(), value]),
+            None => {
+                entry.insert(field.to_string(), value);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(Value::Object(entry))
+}
+
+/// One field in the Journal Export Format: `FIELD=value` and a newline
+/// when the value is printable text on one line, else the name, a
+/// newline, the value's length as 64-bit little endian, the value and a
+/// newline
+fn export_field(out: &mut Vec<u8>, field: &str, value: &[u8]) {
+    let text = std::str::from_utf8(value).is_ok_and(|t| {
+        !t.chars()
+            .any(|c| (c < ' ' && c != '\t') || ('\x7f'..='\u{9f}').contains(&c))
+    });
+    out.extend_from_slice(field.as_bytes());
+    if text {
+        out.push(b'=');
+        out.extend_from_slice(value);
+    } else {
+        out.push(b'\n');
+        out.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        out.extend_from_slice(value);
+    }
+    out.push(b'\n');
+}
+
+/// Every entry from `journal`'s read position on, in the Journal Export
+/// Format `journalctl -o export` writes and systemd-journal-remote reads:
+/// fields one after another, an empty line after each entry. Each entry
+/// goes to `out` in one `write_all`. Returns how many were written
+pub(crate) unsafe fn export(journal: *mut raw::sd_journal, out: &mut dyn Write) -> io::Result<u64> {
+    let mut count = 0;
+    while check(raw::sd_journal_next(journal))? > 0 {
+        let mut entry = Vec::new();
+        for (field, value) in entry_header(journal)? {
+            export_field(&mut entry, field, value.as_bytes());
+        }
+        each_field(journal, |field, value| {
+            export_field(&mut entry, field, value);
+            Ok(())
+        })?;
+        entry.push(b'\n');
+        out.write_all(&entry)?;
+        count += 1;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+unsafe fn unique_values(journal: *mut raw::sd_journal, field: &str) -> io::Result<Vec<String>> {
+    let name = CString::new(field).map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+    check(raw::sd_journal_query_unique(journal, name.as_ptr()))?;
+    let mut values = Vec::new();
+    loop {
+        let (mut data, mut len) = (ptr::null(), 0);
+        let more = check(raw::sd_journal_enumerate_unique(
+            journal, &mut data, &mut len,
+        ))?;
+        if more == 0 {
+            return Ok(values);
+        }
+        let bytes = std::slice::from_raw_parts(data as *const u8, len);
+        let value = bytes.get(field.len() + 1..).unwrap_or_default();
+        values.push(String::from_utf8_lossy(value).into_owned());
+    }
+}
+
+type Step = unsafe extern "C" fn(*mut raw::sd_journal) -> c_int;
+
+/// Wall-clock time of the entry `step` finds after `seek`, if any
+unsafe fn edge(journal: *mut raw::sd_journal, seek: Step, step: Step) -> io::Result<Option<u64>> {
+    check(seek(journal))?;
+    if check(step(journal))? == 0 {
+        return Ok(None);
+    }
+    let mut usec = 0;
+    check(raw::sd_journal_get_realtime_usec(journal, &mut usec))?;
+    Ok(Some(usec))
+}
+
+/// Every boot in `journal`, oldest first, found by matching each
+/// `_BOOT_ID` in turn and reading its first and last entries. That takes
+/// the matches; there are none left after
+pub(crate) unsafe fn boots(journal: *mut raw::sd_journal) -> io::Result<Vec<Boot>> {
+    let found = unique_values(journal, "_BOOT_ID").and_then(|ids| {
+        let mut boots = Vec::new();
+        for boot_id in ids {
+            raw::sd_journal_flush_matches(journal);
+            let m = format!("_BOOT_ID={}", boot_id);
+            check(raw::sd_journal_add_match(
+                journal,
+                m.as_ptr() as *const libc::c_void,
+                m.len(),
+            ))?;
+            let first = edge(journal, raw::sd_journal_seek_head, raw::sd_journal_next)?;
+            let last = edge(journal, raw::sd_journal_seek_tail, raw::sd_journal_previous)?;
+            // Gone with a vacuum since the query
+            if let (Some(first), Some(last)) = (first, last) {
+                boots.push(Boot {
+                    boot_id,
+                    first_realtime_usec: first,
+                    last_realtime_usec: last,
+                });
+            }
+        }
+        Ok(boots)
+    });
+    raw::sd_journal_flush_matches(journal);
+    let mut boots = found?;
+    boots.sort_by_key(|b| b.first_realtime_usec);
+    Ok(boots)
+}
+
+fn path_arg(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))
+}
+
+fn check(ret: c_int) -> io::Result<c_int> {
+    if ret < 0 {
+        Err(io::Error::from_raw_os_error(-ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+/// Write one structured entry to the journal
+///
+/// Each item is a `FIELD=value` pair; field names must be upper case
+/// and `MESSAGE` should be present for the entry to be useful.
+pub fn send<K: AsRef<str>, V: AsRef<[u8]>>(fields: &[(K, V)]) -> io::Result<()> {
+    if let Some(path) = mock_path() {
+        return mock::append(&path, fields);
+    }
+    let buffers: Vec<Vec<u8>> = fields
+        .iter()
+        .map(|(k, v)| {
+            let mut buf = Vec::with_capacity(k.as_ref().len() + 1 + v.as_ref().len());
+            buf.extend_from_slice(k.as_ref().as_bytes());
+            buf.push(b'=');
+            buf.extend_from_slice(v.as_ref());
+            buf
+        })
+        .collect();
+    let iov: Vec<libc::iovec> = buffers
+        .iter()
+        .map(|b| libc::iovec {
+            iov_base: b.as_ptr() as *mut libc::c_void,
+            iov_len: b.len(),
+        })
+        .collect();
+
+    check(unsafe { raw::sd_journal_sendv(iov.as_ptr(), iov.len() as c_int) })?;
+    Ok(())
+}
+
+/// What `Journal::wait` woke up to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Timed out with nothing new
+    Nop,
+    /// New entries at the end
+    Append,
+    /// Files added, removed or rotated
+    Invalidate,
+}
+
+/// A boot the journal has entries from, as `journalctl --list-boots`
+/// lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Boot {
+    /// As `_BOOT_ID` holds it, 32 lowercase hex digits
+    pub boot_id: String,
+    /// Wall-clock times of its first and last entries, in microseconds
+    /// since the epoch
+    pub first_realtime_usec: u64,
+    pub last_realtime_usec: u64,
+}
+
+/// Log `message` at `priority`, from `libc::LOG_EMERG` to `LOG_DEBUG`;
+/// it may hold `%` or NUL, which are logged as they are
+pub fn print<M: AsRef<[u8]>>(priority: c_int, message: M) -> io::Result<()> {
+    if !(libc::LOG_EMERG..=libc::LOG_DEBUG).contains(&priority) {
+        return Err(io::Error::from_raw_os_error(libc::EINVAL));
+    }
+    let priority = priority.to_string();
+    send(&[
+        ("MESSAGE", message.as_ref()),
+        ("PRIORITY", priority.as_bytes()),
+    ])
+}
+
+/// Journal reader handle
+pub struct Journal {
+    inner: Inner,
+}
+
+enum Inner {
+    Live(*mut raw::sd_journal),
+    Mock(mock::Reader),
+}
+
+impl Journal {
+    /// Open the journal with `sd_journal_open` flags
+    pub fn open(flags: c_int) -> io::Result<Journal> {
+        if let Some(path) = mock_path() {
+            let inner = Inner::Mock(mock::Reader::open(path)?);
+            return Ok(Journal { inner });
+        }
+        let mut journal = ptr::null_mut();
+        check(unsafe { raw::sd_journal_open(&mut journal, flags) })?;
+        Ok(Journal {
+            inner: Inner::Live(journal),
+        })
+    }
+
+    /// Open the journal files in `dir` rather than this machine's, as
+    /// copied off another machine, or under it with `OS_ROOT`; never the
+    /// mock, as the files are named
+    pub fn open_directory(dir: &Path, flags: c_int) -> io::Result<Journal> {
+        let dir = path_arg(dir)?;
+        let mut journal = ptr::null_mut();
+        check(unsafe { raw::sd_journal_open_directory(&mut journal, dir.as_ptr(), flags) })?;
+        Ok(Journal {
+            inner: Inner::Live(journal),
+        })
+    }
+
+    /// Open just the journal files in `files`
+    pub fn open_files<P: AsRef<Path>>(files: &[P]) -> io::Result<Journal> {
+        let files = files
+            .iter()
+            .map(|f| path_arg(f.as_ref()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut paths: Vec<*const libc::c_char> = files.iter().map(|f| f.as_ptr()).collect();
+        paths.push(ptr::null());
+        let mut journal = ptr::null_mut();
+        check(unsafe { raw::sd_journal_open_files(&mut journal, paths.as_ptr(), 0) })?;
+        Ok(Journal {
+            inner: Inner::Live(journal),
+        })
+    }
+
+    /// Add a `FIELD=value` match
+    pub fn add_match(&mut self, m: &str) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                check(unsafe {
+                    raw::sd_journal_add_match(*journal, m.as_ptr() as *const libc::c_void, m.len())
+                })?;
+            }
+            Inner::Mock(reader) => reader.add_match(m)?,
+        }
+        Ok(())
+    }
+
+    /// Start a new group of matches, OR-ed with those added so far
+    pub fn add_disjunction(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                check(unsafe { raw::sd_journal_add_disjunction(*journal) })?;
+            }
+            Inner::Mock(reader) => reader.add_disjunction(),
+        }
+        Ok(())
+    }
+
+    /// Start a new group of matches, AND-ed with all those added so far
+    pub fn add_conjunction(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                check(unsafe { raw::sd_journal_add_conjunction(*journal) })?;
+            }
+            Inner::Mock(reader) => reader.add_conjunction(),
+        }
+        Ok(())
+    }
+
+    /// Drop every match
+    pub fn flush_matches(&mut self) {
+        match &mut self.inner {
+            Inner::Live(journal) => unsafe { raw::sd_journal_flush_matches(*journal) },
+            Inner::Mock(reader) => reader.flush_matches(),
+        }
+    }
+
+    /// Only match entries from the running boot
+    pub fn match_this_boot(&mut self) -> io::Result<()> {
+        let boot_id = match &self.inner {
+            Inner::Live(journal) => {
+                check(unsafe {
+                    crate::systemd_shim_journal_add_match_boot(*journal, ptr::null())
+                })?;
+                return Ok(());
+            }
+            Inner::Mock(reader) => match reader.boot_id() {
+                Some(boot_id) => boot_id.to_string(),
+                None => return Ok(()),
+            },
+        };
+        self.add_match(&format!("_BOOT_ID={}", boot_id.trim().replace('-', "")))
+    }
+
+    /// Every boot with entries, oldest first, with the times of its first
+    /// and last, as `journalctl --list-boots` gives them. It clears the
+    /// matches and leaves the read position wherever the search ended
+    pub fn boots(&mut self) -> io::Result<Vec<Boot>> {
+        match &mut self.inner {
+            Inner::Live(journal) => unsafe { boots(*journal) },
+            Inner::Mock(reader) => {
+                reader.flush_matches();
+                Ok(reader.boots())
+            }
+        }
+    }
+
+    pub fn seek_head(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                check(unsafe { raw::sd_journal_seek_head(*journal) })?;
+            }
+            Inner::Mock(reader) => reader.seek(mock::Cursor::Head),
+        }
+        Ok(())
+    }
+
+    pub fn seek_tail(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                check(unsafe { raw::sd_journal_seek_tail(*journal) })?;
+            }
+            Inner::Mock(reader) => reader.seek(mock::Cursor::Tail),
+        }
+        Ok(())
+    }
+
+    /// Step back one entry; `false` once the start is reached
+    pub fn previous_entry(&mut self) -> io::Result<bool> {
+        match &mut self.inner {
+            Inner::Live(journal) => Ok(check(unsafe { raw::sd_journal_previous(*journal) })? > 0),
+            Inner::Mock(reader) => Ok(reader.previous()),
+        }
+    }
+
+    /// Step forward one entry; `false` once the end is reached
+    pub fn next_entry(&mut self) -> io::Result<bool> {
+        match &mut self.inner {
+            Inner::Live(journal) => Ok(check(unsafe { raw::sd_journal_next(*journal) })? > 0),
+            Inner::Mock(reader) => Ok(reader.next()),
+        }
+    }
+
+    /// Step back up to `skip` entries at once; how many it moved, fewer
+    /// than `skip` when the start came first
+    pub fn previous_skip(&mut self, skip: u64) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                Ok(check(unsafe { raw::sd_journal_previous_skip(*journal, skip) })? as u64)
+            }
+            Inner::Mock(reader) => reader.skip(skip, mock::Reader::previous),
+        }
+    }
+
+    /// Step forward up to `skip` entries at once; how many it moved
+    pub fn next_skip(&mut self, skip: u64) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                Ok(check(unsafe { raw::sd_journal_next_skip(*journal, skip) })? as u64)
+            }
+            Inner::Mock(reader) => reader.skip(skip, mock::Reader::next),
+        }
+    }
+
+    /// Wall-clock time of the current entry, in microseconds since the epoch
+    pub fn realtime_usec(&mut self) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let mut usec = 0;
+                check(unsafe { raw::sd_journal_get_realtime_usec(*journal, &mut usec) })?;
+                Ok(usec)
+            }
+            Inner::Mock(reader) => reader.realtime_usec(),
+        }
+    }
+
+    /// Block until the journal changes, or `timeout` passes
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Change> {
+        let change = match &mut self.inner {
+            Inner::Live(journal) => {
+                let usec = timeout.map_or(u64::MAX, |t| t.as_micros() as u64);
+                check(unsafe { raw::sd_journal_wait(*journal, usec) })?
+            }
+            Inner::Mock(reader) => reader.wait(timeout)?,
+        };
+        Ok(match change {
+            crate::SYSTEMD_SHIM_JOURNAL_APPEND => Change::Append,
+            crate::SYSTEMD_SHIM_JOURNAL_INVALIDATE => Change::Invalidate,
+            _ => Change::Nop,
+        })
+    }
+
+    /// Time of the current entry since its boot started, in microseconds,
+    /// and that boot's ID as 32 hex digits
+    pub fn monotonic_usec(&mut self) -> io::Result<(u64, String)> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let (mut usec, mut boot_id) = (0, ptr::null_mut());
+                check(unsafe {
+                    crate::systemd_shim_journal_get_monotonic_usec(
+                        *journal,
+                        &mut usec,
+                        &mut boot_id,
+                    )
+                })?;
+                Ok((usec, unsafe { owned(boot_id) }))
+            }
+            Inner::Mock(reader) => reader.monotonic_usec(),
+        }
+    }
+
+    /// Move to the wall-clock time `usec`: `next_entry` then reads the
+    /// first entry at or after it, `previous_entry` the last one before
+    pub fn seek_realtime_usec(&mut self, usec: u64) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                check(unsafe { raw::sd_journal_seek_realtime_usec(*journal, usec) })?;
+            }
+            Inner::Mock(reader) => reader.seek(mock::Cursor::Realtime(usec)),
+        }
+        Ok(())
+    }
+
+    /// Move to `usec` into the boot `boot_id`, or the running boot
+    pub fn seek_monotonic_usec(&mut self, boot_id: Option<&str>, usec: u64) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let boot_id = boot_id
+                    .map(CString::new)
+                    .transpose()
+                    .map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+                check(unsafe {
+                    crate::systemd_shim_journal_seek_monotonic_usec(
+                        *journal,
+                        boot_id.as_ref().map_or(ptr::null(), |b| b.as_ptr()),
+                        usec,
+                    )
+                })?;
+            }
+            Inner::Mock(reader) => {
+                let boot_id = boot_id
+                    .or_else(|| reader.boot_id())
+                    .ok_or_else(|| io::Error::from_raw_os_error(libc::ENODATA))?
+                    .replace('-', "")
+                    .to_ascii_lowercase();
+                reader.seek(mock::Cursor::Monotonic(boot_id, usec));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cursor of the current entry, to come back to it with `seek_cursor`
+    /// after a restart
+    pub fn cursor(&mut self) -> io::Result<String> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let mut cursor: *mut libc::c_char = ptr::null_mut();
+                check(unsafe { raw::sd_journal_get_cursor(*journal, &mut cursor) })?;
+                Ok(unsafe { owned(cursor) })
+            }
+            Inner::Mock(reader) => reader.cursor(),
+        }
+    }
+
+    /// Seek to the entry `cursor` names, or if it is gone to where it
+    /// was; `next_entry` then steps onto it
+    pub fn seek_cursor(&mut self, cursor: &str) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let cursor =
+                    CString::new(cursor).map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+                check(unsafe { raw::sd_journal_seek_cursor(*journal, cursor.as_ptr()) })?;
+            }
+            Inner::Mock(reader) => reader.seek_cursor(cursor)?,
+        }
+        Ok(())
+    }
+
+    /// Whether the current entry is the one `cursor` names
+    pub fn test_cursor(&mut self, cursor: &str) -> io::Result<bool> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let cursor =
+                    CString::new(cursor).map_err(|_| io::Error::from_raw_os_error(libc::EINVAL))?;
+                Ok(check(unsafe { raw::sd_journal_test_cursor(*journal, cursor.as_ptr()) })? > 0)
+            }
+            Inner::Mock(reader) => reader.test_cursor(cursor),
+        }
+    }
+
+    /// The message catalog's explanation of the current entry, by its
+    /// `MESSAGE_ID`, with its fields filled in
+    pub fn catalog(&mut self) -> io::Result<String> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let mut text = ptr::null_mut();
+                check(unsafe { raw::sd_journal_get_catalog(*journal, &mut text) })?;
+                Ok(unsafe { owned(text) })
+            }
+            Inner::Mock(reader) => reader.catalog(),
+        }
+    }
+
+    /// The current entry as `journalctl -o json` writes it, cursor and
+    /// timestamps included
+    pub fn entry_json(&mut self) -> io::Result<Value> {
+        match &mut self.inner {
+            Inner::Live(journal) => unsafe { entry_json(*journal) },
+            Inner::Mock(reader) => reader.entry_json(),
+        }
+    }
+
+    /// Every entry from the read position on, in the Journal Export Format,
+    /// to `out`; how many were written
+    pub fn export(&mut self, out: &mut dyn Write) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Live(journal) => unsafe { export(*journal, out) },
+            Inner::Mock(reader) => {
+                let mut count = 0;
+                while reader.next() {
+                    let entry = reader.entry_json()?;
+                    const HEADER: [&str; 4] = [
+                        "__CURSOR",
+                        "__REALTIME_TIMESTAMP",
+                        "__MONOTONIC_TIMESTAMP",
+                        "_BOOT_ID",
+                    ];
+                    let fields = entry.as_object().into_iter().flatten();
+                    // The header first, in journalctl's order
+                    let mut header: Vec<_> = fields
+                        .clone()
+                        .filter(|(k, _)| HEADER.contains(&k.as_str()))
+                        .collect();
+                    header.sort_by_key(|(k, _)| HEADER.iter().position(|h| h == k));
+                    let rest = fields.filter(|(k, _)| !HEADER.contains(&k.as_str()));
+                    let mut bytes = Vec::new();
+                    for (field, value) in header.into_iter().chain(rest) {
+                        let value = value.as_str().unwrap_or_default();
+                        export_field(&mut bytes, field, value.as_bytes());
+                    }
+                    bytes.push(b'\n');
+                    out.write_all(&bytes)?;
+                    count += 1;
+                }
+                out.flush()?;
+                Ok(count)
+            }
+        }
+    }
+
+    /// Each value `field` takes in the journal, once, regardless of matches
+    pub fn unique_values(&mut self, field: &str) -> io::Result<Vec<String>> {
+        match &mut self.inner {
+            Inner::Live(journal) => unsafe { unique_values(*journal, field) },
+            Inner::Mock(reader) => Ok(reader.unique_values(field)),
+        }
+    }
+
+    /// Disk space used by the journal files this handle has open
+    pub fn usage(&mut self) -> io::Result<u64> {
+        match &mut self.inner {
+            Inner::Live(journal) => {
+                let mut bytes = 0;
+                check(unsafe { raw::sd_journal_get_usage(*journal, &mut bytes) })?;
+                Ok(bytes)
+            }
+            Inner::Mock(reader) => reader.usage(),
+        }
+    }
+
+    /// Value of `field` in the current entry, without the `FIELD=` prefix
+    pub fn field(&mut self, field: &str) -> Option<String> {
+        let journal = match &mut self.inner {
+            Inner::Live(journal) => *journal,
+            Inner::Mock(reader) => return reader.field(field),
+        };
+        let name = CString::new(field).ok()?;
+        let mut data: *const libc::c_void = ptr::null();
+        let mut len: libc::size_t = 0;
+        let ret = unsafe { raw::sd_journal_get_data(journal, name.as_ptr(), &mut data, &mut len) };
+        if ret < 0 || data.is_null() {
+            return None;
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(data as *const u8, len) };
+        let value = bytes.get(field.len() + 1..)?;
+        Some(String::from_utf8_lossy(value).into_owned())
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        if let Inner::Live(journal) = self.inner {
+            unsafe { raw::sd_journal_close(journal) }
+        }
+    }
+}
+
+mod mock {
+    //! The journal as a file of `journalctl -o json` lines
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    pub enum Cursor {
+        Head,
+        Tail,
+        At(usize),
+        /// Just before the entry at this index, which a step either way
+        /// lands on, as after seeking to its cursor
+        Near(usize),
+        /// A wall-clock time, which a step either way lands on entries
+        /// logged at as well as after or before
+        Realtime(u64),
+        /// Like `Realtime`, as time into the boot with this ID
+        Monotonic(String, u64),
+    }
+
+    pub struct Reader {
+        path: PathBuf,
+        /// Oldest first
+        entries: Vec<BTreeMap<String, String>>,
+        /// AND-ed levels of OR-ed groups; within a group, matches on a
+        /// field are OR-ed and different fields AND-ed, as in sd-journal
+        levels: Vec<Vec<Vec<(String, String)>>>,
+        cursor: Cursor,
+        /// Size of the file when last read, to tell when it changes
+        len: u64,
+    }
+
+    /// A field as `journalctl -o json` wrote it: a string, bytes as an
+    /// array of numbers, several values as an array, or null when large
+    fn text(value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Array(items) if items.iter().all(Value::is_u64) => {
+                let bytes: Vec<u8> = items
+                    .iter()
+                    .filter_map(|b| b.as_u64())
+                    .map(|b| b as u8)
+                    .collect();
+                Some(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            Value::Array(items) => items.first().and_then(text),
+            Value::Null => None,
+            other => Some(other.to_string()),
+        }
+    }
+
+    fn invalid(path: &std::path::Path, line: usize, e: impl std::fmt::Display) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}:{}: {}", path.display(), line + 1, e),
+        )
+    }
+
+    impl Reader {
+        pub fn open(path: PathBuf) -> io::Result<Reader> {
+            let mut reader = Reader {
+                path,
+                entries: Vec::new(),
+                levels: Vec::new(),
+                cursor: Cursor::Head,
+                len: 0,
+            };
+            reader.load()?;
+            Ok(reader)
+        }
+
+        fn load(&mut self) -> io::Result<()> {
+            let path = &self.path;
+            let contents = std::fs::read_to_string(path)?;
+            let mut entries = Vec::new();
+            for (i, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let object: BTreeMap<String, Value> =
+                    serde_json::from_str(line).map_err(|e| invalid(path, i, e))?;
+                let entry: BTreeMap<String, String> = object
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), text(v)?)))
+                    .collect();
+                entries.push(entry);
+            }
+            entries.sort_by_key(|e| realtime(e).unwrap_or(0));
+            self.entries = entries;
+            self.len = contents.len() as u64;
+            Ok(())
+        }
+
+        /// Polls the file: it grows as `append` adds to it, anything else
+        /// counts as the journal being rewritten
+        pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<c_int> {
+            let deadline = timeout.map(|t| Instant::now() + t);
+            loop {
+                let len = std::fs::metadata(&self.path)?.len();
+                if len != self.len {
+                    let grew = len > self.len;
+                    self.load()?;
+                    return Ok(if grew {
+                        crate::SYSTEMD_SHIM_JOURNAL_APPEND
+                    } else {
+                        crate::SYSTEMD_SHIM_JOURNAL_INVALIDATE
+                    });
+                }
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    return Ok(crate::SYSTEMD_SHIM_JOURNAL_NOP);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        pub fn add_match(&mut self, m: &str) -> io::Result<()> {
+            let (field, value) = m.split_once('=').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("not FIELD=value: {}", m),
+                )
+            })?;
+            if self.levels.is_empty() {
+                self.levels.push(Vec::new());
+            }
+            let level = self.levels.last_mut().unwrap();
+            if level.is_empty() {
+                level.push(Vec::new());
+            }
+            let group = level.last_mut().unwrap();
+            group.push((field.to_string(), value.to_string()));
+            Ok(())
+        }
+
+        pub fn add_disjunction(&mut self) {
+            if let Some(level) = self.levels.last_mut() {
+                if level.last().is_some_and(|g| !g.is_empty()) {
+                    level.push(Vec::new());
+                }
+            }
+        }
+
+        pub fn add_conjunction(&mut self) {
+            if self
+                .levels
+                .last()
+                .is_some_and(|l| l.iter().any(|g| !g.is_empty()))
+            {
+                self.levels.push(Vec::new());
+            }
+        }
+
+        pub fn flush_matches(&mut self) {
+            self.levels.clear();
+        }
+
+        pub fn boot_id(&self) -> Option<&str> {
+            self.entries.last()?.get("_BOOT_ID").map(String::as_str)
+        }
+
+        fn matches(&self, entry: &BTreeMap<String, String>) -> bool {
+            self.levels.iter().all(|level| {
+                let groups: Vec<_> = level.iter().filter(|g| !g.is_empty()).collect();
+                groups.is_empty()
+                    || groups.iter().any(|group| {
+                        group.iter().all(|(field, _)| {
+                            group
+                                .iter()
+                                .any(|(f, v)| f == field && entry.get(f) == Some(v))
+                        })
+                    })
+            })
+        }
+
+        pub fn seek(&mut self, cursor: Cursor) {
+            self.cursor = cursor;
+        }
+
+        /// Index of the first entry `next` may land on, and one past the
+        /// last `previous` may; either can be past the end once `wait`
+        /// has reloaded a file that shrank
+        fn bounds(&self) -> (usize, usize) {
+            let len = self.entries.len();
+            let at_time = |time: &dyn Fn(&BTreeMap<String, String>) -> Option<u64>, t: u64| {
+                let start = self.entries.iter().position(|e| time(e) >= Some(t));
+                let end = self
+                    .entries
+                    .iter()
+                    .rposition(|e| time(e).is_some_and(|u| u <= t));
+                (start.unwrap_or(len), end.map_or(0, |i| i + 1))
+            };
+            match &self.cursor {
+                Cursor::Head => (0, 0),
+                Cursor::Tail => (len, len),
+                Cursor::At(i) => (i + 1, (*i).min(len)),
+                Cursor::Near(i) => (*i, (i + 1).min(len)),
+                Cursor::Realtime(t) => at_time(&realtime, *t),
+                Cursor::Monotonic(boot_id, t) => at_time(
+                    &|e| monotonic(e).filter(|(_, b)| b == boot_id).map(|(u, _)| u),
+                    *t,
+                ),
+            }
+        }
+
+        pub fn next(&mut self) -> bool {
+            let start = self.bounds().0;
+            let found = (start..self.entries.len()).find(|&i| self.matches(&self.entries[i]));
+            if let Some(i) = found {
+                self.cursor = Cursor::At(i);
+            }
+            found.is_some()
+        }
+
+        pub fn previous(&mut self) -> bool {
+            let end = self.bounds().1;
+            let found = (0..end).rev().find(|&i| self.matches(&self.entries[i]));
+            if let Some(i) = found {
+                self.cursor = Cursor::At(i);
+            }
+            found.is_some()
+        }
+
+        /// `step` up to `skip` times, stopping when it finds no entry;
+        /// sd-journal counts in an int, and refuses more
+        pub fn skip(&mut self, skip: u64, step: fn(&mut Self) -> bool) -> io::Result<u64> {
+            if skip > i32::MAX as u64 {
+                return Err(io::Error::from_raw_os_error(libc::ERANGE));
+            }
+            Ok((0..skip).take_while(|_| step(self)).count() as u64)
+        }
+
+        fn current(&self) -> Option<&BTreeMap<String, String>> {
+            match self.cursor {
+                Cursor::At(i) => self.entries.get(i),
+                _ => None,
+            }
+        }
+
+        /// The entry's own `__CURSOR` if `journalctl -o json` wrote one,
+        /// else one made up like it from the entry's place and time
+        fn cursor_of(&self, i: usize) -> String {
+            let entry = &self.entries[i];
+            match entry.get("__CURSOR") {
+                Some(cursor) => cursor.clone(),
+                None => format!("s=mock;i={:x};t={:x}", i, realtime(entry).unwrap_or(0)),
+            }
+        }
+
+        pub fn cursor(&self) -> io::Result<String> {
+            match self.cursor {
+                Cursor::At(i) if i < self.entries.len() => Ok(self.cursor_of(i)),
+                _ => Err(io::Error::from_raw_os_error(libc::EADDRNOTAVAIL)),
+            }
+        }
+
+        /// An entry that is gone is looked for by the time in its cursor
+        pub fn seek_cursor(&mut self, cursor: &str) -> io::Result<()> {
+            if let Some(i) = (0..self.entries.len()).find(|&i| self.cursor_of(i) == cursor) {
+                self.cursor = Cursor::Near(i);
+                return Ok(());
+            }
+            let time = cursor
+                .split(';')
+                .find_map(|field| field.strip_prefix("t="))
+                .and_then(|t| u64::from_str_radix(t, 16).ok())
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::EINVAL))?;
+            self.cursor = Cursor::Realtime(time);
+            Ok(())
+        }
+
+        pub fn test_cursor(&self, cursor: &str) -> io::Result<bool> {
+            Ok(self.cursor()? == cursor)
+        }
+
+        pub fn realtime_usec(&self) -> io::Result<u64> {
+            self.current()
+                .and_then(realtime)
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::EADDRNOTAVAIL))
+        }
+
+        pub fn monotonic_usec(&self) -> io::Result<(u64, String)> {
+            self.current()
+                .and_then(monotonic)
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::EADDRNOTAVAIL))
+        }
+
+        /// From the system's catalog, filled in as sd-journal does: an
+        /// `@FIELD@` of upper case and `_` becomes the field's value, or
+        /// the bare name when the entry lacks it
+        pub fn catalog(&self) -> io::Result<String> {
+            let entry = self
+                .current()
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::EADDRNOTAVAIL))?;
+            let id = entry
+                .get("MESSAGE_ID")
+                .ok_or_else(|| io::Error::from_raw_os_error(libc::ENOENT))?;
+            let text = catalog_for_message_id(id)?;
+            let mut out = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find('@') {
+                out.push_str(&rest[..start]);
+                let after = &rest[start + 1..];
+                let len = after
+                    .find(|c: char| !(c.is_ascii_uppercase() || c == '_'))
+                    .unwrap_or(after.len());
+                if len > 0 && after[len..].starts_with('@') {
+                    let field = &after[..len];
+                    out.push_str(entry.get(field).map_or(field, String::as_str));
+                    rest = &after[len + 1..];
+                } else {
+                    out.push('@');
+                    rest = after;
+                }
+            }
+            out.push_str(rest);
+            Ok(out)
+        }
+
+        /// Fields as the reader holds them, all as text, with a cursor
+        pub fn entry_json(&self) -> io::Result<Value> {
+            let cursor = self.cursor()?;
+            let mut entry: serde_json::Map<String, Value> = self
+                .current()
+                .into_iter()
+                .flatten()
+                .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                .collect();
+            entry.insert("__CURSOR".to_string(), Value::String(cursor));
+            Ok(Value::Object(entry))
+        }
+
+        pub fn unique_values(&self, field: &str) -> Vec<String> {
+            let values: std::collections::BTreeSet<&String> =
+                self.entries.iter().filter_map(|e| e.get(field)).collect();
+            values.into_iter().cloned().collect()
+        }
+
+        pub fn boots(&self) -> Vec<Boot> {
+            let mut boots: Vec<Boot> = Vec::new();
+            for entry in &self.entries {
+                let (Some(boot_id), Some(usec)) = (entry.get("_BOOT_ID"), realtime(entry)) else {
+                    continue;
+                };
+                let boot_id = boot_id.replace('-', "").to_ascii_lowercase();
+                match boots.iter_mut().find(|b| b.boot_id == boot_id) {
+                    Some(boot) => {
+                        boot.first_realtime_usec = boot.first_realtime_usec.min(usec);
+                        boot.last_realtime_usec = boot.last_realtime_usec.max(usec);
+                    }
+                    None => boots.push(Boot {
+                        boot_id,
+                        first_realtime_usec: usec,
+                        last_realtime_usec: usec,
+                    }),
+                }
+            }
+            boots.sort_by_key(|b| b.first_realtime_usec);
+            boots
+        }
+
+        pub fn usage(&self) -> io::Result<u64> {
+            Ok(std::fs::metadata(&self.path)?.len())
+        }
+
+        pub fn field(&self, field: &str) -> Option<String> {
+            self.current()?.get(field).cloned()
+        }
+    }
+
+    fn realtime(entry: &BTreeMap<String, String>) -> Option<u64> {
+        entry.get("__REALTIME_TIMESTAMP")?.parse().ok()
+    }
+
+    /// Time into the boot, and the boot's ID in the form sd-id128 prints
+    fn monotonic(entry: &BTreeMap<String, String>) -> Option<(u64, String)> {
+        let usec = entry.get("__MONOTONIC_TIMESTAMP")?.parse().ok()?;
+        let boot_id = entry.get("_BOOT_ID")?.replace('-', "").to_ascii_lowercase();
+        Some((usec, boot_id))
+    }
+
+    /// Add an entry to the file, stamped with the current time
+    pub fn append<K: AsRef<str>, V: AsRef<[u8]>>(
+        path: &std::path::Path,
+        fields: &[(K, V)],
+    ) -> io::Result<()> {
+        let mut entry: BTreeMap<String, String> = fields
+            .iter()
+            .map(|(k, v)| {
+                let value = String::from_utf8_lossy(v.as_ref()).into_owned();
+                (k.as_ref().to_string(), value)
+            })
+            .collect();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        entry.insert("__REALTIME_TIMESTAMP".to_string(), now.to_string());
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(file, "{}", line)
+    }
+}
+
+/// How much one source logged over a window
+#[derive(Debug, Clone)]
+pub struct RateStat {
+    /// User unit, system unit, syslog identifier or command name, the
+    /// first one set
+    pub source: String,
+    pub entries: u64,
+    /// Bytes of `MESSAGE` text, not of the entry as stored
+    pub message_bytes: u64,
+}
+
+/// Entries logged since `since_usec` (microseconds since the epoch),
+/// per source, busiest first
+///
+/// Reads backwards from the tail, so a short window stays cheap on a
+/// large journal.
+pub fn rate_stats(since_usec: u64) -> io::Result<Vec<RateStat>> {
+    let mut journal = Journal::open(LOCAL_ONLY)?;
+    journal.seek_tail()?;
+    let mut counts: HashMap<String, (u64, u64)> = HashMap::new();
+    while journal.previous_entry()? {
+        if journal.realtime_usec()? < since_usec {
+            break;
+        }
+        let source = [
+            "_SYSTEMD_USER_UNIT",
+            "_SYSTEMD_UNIT",
+            "SYSLOG_IDENTIFIER",
+            "_COMM",
+        ]
+        .iter()
+        .find_map(|field| journal.field(field))
+        .unwrap_or_else(|| "unknown".to_string());
+        let bytes = journal.field("MESSAGE").map_or(0, |m| m.len() as u64);
+        let count = counts.entry(source).or_default();
+        count.0 += 1;
+        count.1 += bytes;
+    }
+    let mut stats: Vec<RateStat> = counts
+        .into_iter()
+        .map(|(source, (entries, message_bytes))| RateStat {
+            source,
+            entries,
+            message_bytes,
+        })
+        .collect();
+    stats.sort_by_key(|s| Reverse(s.entries));
+    Ok(stats)
+}
+
+/// A journal file journald has rotated away and no longer writes to
+#[derive(Debug, Clone)]
+pub struct ArchivedFile {
+    pub path: PathBuf,
+    /// Space allocated on disk, as `sd_journal_get_usage` counts it
+    pub bytes: u64,
+    pub modified: SystemTime,
+}
+
+/// Rotated (`name@...journal`) and corrupt (`.journal~`) files
+fn is_archived(name: &str) -> bool {
+    (name.contains('@') && name.ends_with(".journal")) || name.ends_with(".journal~")
+}
+
+/// Archived files in every journal directory, oldest first
+pub fn archived_files() -> Vec<ArchivedFile> {
+    let mut files = Vec::new();
+    for dir in DIRECTORIES {
+        // One subdirectory per machine ID (and per namespace)
+        let Ok(machines) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for machine in machines.flatten() {
+            let Ok(entries) = std::fs::read_dir(machine.path()) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if !is_archived(&entry.file_name().to_string_lossy()) {
+                    continue;
+                }
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                files.push(ArchivedFile {
+                    path: entry.path(),
+                    bytes: meta.blocks() * 512,
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+            }
+        }
+    }
+    files.sort_by_key(|f| f.modified);
+    files
+}
+
+/// What [`vacuum`] removed, and what it could not
+#[derive(Debug, Default)]
+pub struct Vacuum {
+    pub removed: Vec<ArchivedFile>,
+    pub failed: Vec<(ArchivedFile, io::Error)>,
+}
+
+/// Delete archived files, oldest first, until the journal uses at most
+/// `max_bytes`
+///
+/// Works like `journalctl --vacuum-size`: active files are never touched,
+/// so usage can stay above `max_bytes`. A file that cannot be deleted is
+/// recorded and the next one tried; only failing to measure the journal
+/// is an error.
+pub fn vacuum(max_bytes: u64) -> io::Result<Vacuum> {
+    let mut usage = Journal::open(0)?.usage()?;
+    let mut vacuum = Vacuum::default();
+    for file in archived_files() {
+        if usage <= max_bytes {
+            break;
+        }
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => {
+                usage = usage.saturating_sub(file.bytes);
+                vacuum.removed.push(file);
+            }
+            Err(e) => vacuum.failed.push((file, e)),
+        }
+    }
+    Ok(vacuum)
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! D-Bus messages as JSON, for callers that want a whole reply at once
+//!
+//! Numbers become numbers, strings, object paths and signatures become
+//! strings, and a variant becomes the value it holds. Structs become
+//! arrays, and so do arrays, except dictionaries (`a{..}`): they become
+//! objects keyed by their keys as text. A file descriptor is given as its
+//! number in the receiving process.
+
+use crate::raw;
+use libc::{c_char, c_int, c_void};
+use serde_json::{Map, Number, Value};
+use std::ffi::{CStr, CString};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ptr;
+
+fn check(ret: c_int) -> io::Result<c_int> {
+    if ret < 0 {
+        Err(io::Error::from_raw_os_error(-ret))
+    } else {
+        Ok(ret)
+    }
+}
+
+unsafe fn basic<T: Default>(m: *mut raw::sd_bus_message, kind: c_char) -> io::Result<T> {
+    let mut v = T::default();
+    check(raw::sd_bus_message_read_basic(
+        m,
+        kind,
+        &mut v as *mut T as *mut c_void,
+    ))?;
+    Ok(v)
+}
+
+unsafe fn text(m: *mut raw::sd_bus_message, kind: c_char) -> io::Result<Value> {
+    let s: *const c_char = basic(m, kind)?;
+    Ok(Value::String(if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }))
+}
+
+/// Enter the container of `kind` holding `contents`, read it with `f`
+/// and leave it
+unsafe fn within(
+    m: *mut raw::sd_bus_message,
+    kind: u8,
+    contents: &CStr,
+    f: impl FnOnce() -> io::Result<Value>,
+) -> io::Result<Value> {
+    check(raw::sd_bus_message_enter_container(
+        m,
+        kind as c_char,
+        contents.as_ptr(),
+    ))?;
+    let value = f()?;
+    check(raw::sd_bus_message_exit_container(m))?;
+    Ok(value)
+}
+
+/// Dictionary keys are strings or numbers; numbers are written out
+fn key(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// The value at the read position, or `None` at the end of the enclosing
+/// container
+pub(crate) unsafe fn read_value(m: *mut raw::sd_bus_message) -> io::Result<Option<Value>> {
+    let mut kind: c_char = 0;
+    let mut contents: *const c_char = ptr::null();
+    if check(raw::sd_bus_message_peek_type(m, &mut kind, &mut contents))? == 0 {
+        return Ok(None);
+    }
+    let contents = if contents.is_null() {
+        CString::default()
+    } else {
+        CStr::from_ptr(contents).to_owned()
+    };
+    let value = match kind as u8 {
+        b's' | b'o' | b'g' => text(m, kind)?,
+        b'b' => Value::Bool(basic::<c_int>(m, kind)? != 0),
+        b'y' => basic::<u8>(m, kind)?.into(),
+        b'n' => basic::<i16>(m, kind)?.into(),
+        b'q' => basic::<u16>(m, kind)?.into(),
+        b'i' | b'h' => basic::<i32>(m, kind)?.into(),
+        b'u' => basic::<u32>(m, kind)?.into(),
+        b'x' => basic::<i64>(m, kind)?.into(),
+        b't' => basic::<u64>(m, kind)?.into(),
+        b'd' => Number::from_f64(basic::<f64>(m, kind)?).map_or(Value::Null, Value::Number),
+        b'v' => within(m, b'v', &contents, || {
+            Ok(read_value(m)?.unwrap_or(Value::Null))
+        })?,
+        b'r' => within(m, b'r', &contents, || Ok(Value::Array(read_all(m)?)))?,
+        b'a' if contents.to_bytes().first() == Some(&b'{') => {
+            let bytes = contents.to_bytes();
+            let entry = CString::new(&bytes[1..bytes.len() - 1])
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            within(m, b'a', &contents, || {
+                let mut object = Map::new();
+                while check(raw::sd_bus_message_enter_container(
+                    m,
+                    b'e' as c_char,
+                    entry.as_ptr(),
+                ))? > 0
+                {
+                    let k = read_value(m)?.map(key).unwrap_or_default();
+                    let v = read_value(m)?.unwrap_or(Value::Null);
+                    object.insert(k, v);
+                    check(raw::sd_bus_message_exit_container(m))?;
+                }
+                Ok(Value::Object(object))
+            })?
+        }
+        b'a' => within(m, b'a', &contents, || Ok(Value::Array(read_all(m)?)))?,
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown D-Bus type {}", other as char),
+            ))
+        }
+    };
+    Ok(Some(value))
+}
+
+/// Every value from the read position to the end of the enclosing
+/// container
+pub(crate) unsafe fn read_all(m: *mut raw::sd_bus_message) -> io::Result<Vec<Value>> {
+    let mut values = Vec::new();
+    while let Some(value) = read_value(m)? {
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// `m`'s body: the value itself when the body is one, else an array of
+/// them
+pub(crate) unsafe fn body(m: *mut raw::sd_bus_message) -> io::Result<Value> {
+    let mut values = read_all(m)?;
+    Ok(if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    })
+}
+
+/// Name the fields of each struct in an array of them, in order; a
+/// reply of records reads better keyed than as bare arrays
+pub(crate) fn records(value: Value, fields: &[&str]) -> Value {
+    let Value::Array(rows) = value else {
+        return value;
+    };
+    let named = |row: Value| match row {
+        Value::Array(row) => Value::Object(fields.iter().map(|f| f.to_string()).zip(row).collect()),
+        other => other,
+    };
+    Value::Array(rows.into_iter().map(named).collect())
+}
+
+/// An `i` address family and `ay` address, as in machined's and
+/// resolved's replies, as an object with the family by name (`inet` or
+/// `inet6`) and the address as text
+pub(crate) fn ip_address(family: &Value, bytes: &Value) -> io::Result<Value> {
+    let malformed = || io::Error::from_raw_os_error(libc::EBADMSG);
+    let family = family.as_i64().ok_or_else(malformed)?;
+    let bytes: Vec<u8> = bytes
+        .as_array()
+        .ok_or_else(malformed)?
+        .iter()
+        .filter_map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect();
+    let (family, address) = match (family as c_int, bytes.len()) {
+        (libc::AF_INET, 4) => {
+            let octets: [u8; 4] = bytes.try_into().map_err(|_| malformed())?;
+            ("inet", Ipv4Addr::from(octets).to_string())
+        }
+        (libc::AF_INET6, 16) => {
+            let octets: [u8; 16] = bytes.try_into().map_err(|_| malformed())?;
+            ("inet6", Ipv6Addr::from(octets).to_string())
+        }
+        _ => return Err(malformed()),
+    };
+    Ok(serde_json::json!({ "family": family, "address": address }))
+}
+
+/// Hand `value` to a C caller in `*ret` as a malloc'd JSON string
+pub(crate) unsafe fn give(value: io::Result<Value>, ret: *mut *mut c_char) -> c_int {
+    match value {
+        Ok(value) => crate::give_string(&value.to_string(), ret),
+        Err(e) => -e.raw_os_error().unwrap_or(libc::EBADMSG),
+    }
+}
+
+/// Hand `m`'s body to a C caller in `*ret` as a malloc'd JSON string
+pub(crate) unsafe fn export(m: *mut raw::sd_bus_message, ret: *mut *mut c_char) -> c_int {
+    give(body(m), ret)
+}
@@ -2,22 +2,123 @@
 //! Rust shim for systemd - exposes sd-bus and sd-journal via C ABI
 //!
 //! This allows Zig to use systemd without @cImport by providing
-//! stable wrapper functions.
+//! stable wrapper functions. Rust consumers link the crate directly and
+//! use the safe modules (`bus`, `cgroup`, `device`, `journal`) instead of
+//! the C ABI. Tests enable the `mock` feature for a scripted system bus.
 
-use libc::{c_char, c_int};
+// Every export is a thin wrapper; its safety contract is that of the
+// libsystemd function it forwards to.
+#![allow(clippy::missing_safety_doc)]
+
+use libc::{c_char, c_int, c_uint, c_void};
+use match_rule::MatchRule;
 use std::ffi::{CStr, CString};
 use std::ptr;
+use transient::Properties;
+use vtable::Vtable;
+
+/// A string literal as a C string, for names handed straight to libsystemd
+macro_rules! c {
+    ($s:literal) => {
+        concat!($s, "\0").as_ptr() as *const libc::c_char
+    };
+}
+
+pub mod bus;
+pub mod cgroup;
+pub mod device;
+pub mod journal;
+mod json;
+pub mod match_rule;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod signature;
+pub mod transient;
+pub mod vtable;
 
 // We use raw libsystemd bindings for low-level access
 // The libsystemd crate provides safe wrappers, but we need raw pointers for FFI
 
+#[allow(non_camel_case_types, dead_code)]
 mod raw {
-    use libc::{c_char, c_int, c_void, size_t};
+    use libc::{c_char, c_int, c_uint, c_void, size_t};
 
     // Opaque types
     pub enum sd_bus {}
     pub enum sd_bus_message {}
+    pub enum sd_bus_slot {}
+    pub enum sd_bus_creds {}
+    pub enum sd_bus_track {}
     pub enum sd_journal {}
+    pub enum sd_device {}
+    pub enum sd_device_enumerator {}
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sd_id128_t {
+        pub qwords: [u64; 2],
+    }
+
+    pub type sd_bus_message_handler_t = unsafe extern "C" fn(
+        m: *mut sd_bus_message,
+        userdata: *mut c_void,
+        ret_error: *mut sd_bus_error,
+    ) -> c_int;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sd_bus_vtable_start {
+        pub element_size: usize,
+        pub features: u64,
+        pub vtable_format_reference: *const c_uint,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sd_bus_vtable_method {
+        pub member: *const c_char,
+        pub signature: *const c_char,
+        pub result: *const c_char,
+        pub handler: Option<sd_bus_message_handler_t>,
+        pub offset: usize,
+        pub names: *const c_char,
+    }
+
+    pub type sd_bus_property_get_t = unsafe extern "C" fn(
+        bus: *mut sd_bus,
+        path: *const c_char,
+        interface: *const c_char,
+        property: *const c_char,
+        reply: *mut sd_bus_message,
+        userdata: *mut c_void,
+        ret_error: *mut sd_bus_error,
+    ) -> c_int;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sd_bus_vtable_property {
+        pub member: *const c_char,
+        pub signature: *const c_char,
+        pub get: Option<sd_bus_property_get_t>,
+        pub set: *const c_void,
+        pub offset: usize,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub union sd_bus_vtable_x {
+        pub start: sd_bus_vtable_start,
+        pub method: sd_bus_vtable_method,
+        pub property: sd_bus_vtable_property,
+    }
+
+    /// The C struct's `type:8` and `flags:56` bitfields share the first
+    /// word, type in the low byte
+    #[repr(C)]
+    pub struct sd_bus_vtable {
+        pub type_and_flags: u64,
+        pub x: sd_bus_vtable_x,
+    }
 
     #[repr(C)]
     pub struct sd_bus_error {
@@ -42,6 +143,13 @@ fn default() -> Self {
     extern "C" {
         pub fn sd_bus_open_system(bus: *mut *mut sd_bus) -> c_int;
         pub fn sd_bus_unref(bus: *mut sd_bus) -> *mut sd_bus;
+        pub fn sd_bus_default(bus: *mut *mut sd_bus) -> c_int;
+        pub fn sd_bus_default_system(bus: *mut *mut sd_bus) -> c_int;
+        pub fn sd_bus_default_user(bus: *mut *mut sd_bus) -> c_int;
+        pub fn sd_bus_flush(bus: *mut sd_bus) -> c_int;
+        pub fn sd_bus_close(bus: *mut sd_bus);
+        pub fn sd_bus_is_open(bus: *mut sd_bus) -> c_int;
+        pub fn sd_bus_close_unref(bus: *mut sd_bus) -> *mut sd_bus;
         pub fn sd_bus_get_property_string(
             bus: *mut sd_bus,
             destination: *const c_char,
@@ -51,25 +159,385 @@ pub fn sd_bus_get_property_string(
             error: *mut sd_bus_error,
             ret: *mut *mut c_char,
         ) -> c_int;
+        pub fn sd_bus_get_property_strv(
+            bus: *mut sd_bus,
+            destination: *const c_char,
+            path: *const c_char,
+            interface: *const c_char,
+            member: *const c_char,
+            error: *mut sd_bus_error,
+            ret: *mut *mut *mut c_char,
+        ) -> c_int;
+        pub fn sd_bus_set_property(
+            bus: *mut sd_bus,
+            destination: *const c_char,
+            path: *const c_char,
+            interface: *const c_char,
+            member: *const c_char,
+            error: *mut sd_bus_error,
+            type_: *const c_char,
+            ...
+        ) -> c_int;
         pub fn sd_bus_error_free(e: *mut sd_bus_error);
+        pub fn sd_bus_error_is_set(e: *const sd_bus_error) -> c_int;
+        pub fn sd_bus_error_has_name(e: *const sd_bus_error, name: *const c_char) -> c_int;
+        pub fn sd_bus_error_get_errno(e: *const sd_bus_error) -> c_int;
+        pub fn sd_bus_open_user(bus: *mut *mut sd_bus) -> c_int;
+        pub fn sd_bus_open_system_remote(bus: *mut *mut sd_bus, host: *const c_char) -> c_int;
+        pub fn sd_bus_open_system_machine(bus: *mut *mut sd_bus, machine: *const c_char) -> c_int;
+        pub fn sd_bus_get_property_trivial(
+            bus: *mut sd_bus,
+            destination: *const c_char,
+            path: *const c_char,
+            interface: *const c_char,
+            member: *const c_char,
+            error: *mut sd_bus_error,
+            type_: c_char,
+            ret: *mut c_void,
+        ) -> c_int;
+        pub fn sd_bus_call_method(
+            bus: *mut sd_bus,
+            destination: *const c_char,
+            path: *const c_char,
+            interface: *const c_char,
+            member: *const c_char,
+            error: *mut sd_bus_error,
+            reply: *mut *mut sd_bus_message,
+            types: *const c_char,
+            ...
+        ) -> c_int;
+        pub fn sd_bus_message_new_method_call(
+            bus: *mut sd_bus,
+            m: *mut *mut sd_bus_message,
+            destination: *const c_char,
+            path: *const c_char,
+            interface: *const c_char,
+            member: *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_message_append(m: *mut sd_bus_message, types: *const c_char, ...) -> c_int;
+        pub fn sd_bus_message_append_array(
+            m: *mut sd_bus_message,
+            type_: c_char,
+            ptr: *const c_void,
+            size: usize,
+        ) -> c_int;
+        pub fn sd_bus_message_append_strv(m: *mut sd_bus_message, l: *mut *mut c_char) -> c_int;
+        pub fn sd_bus_call(
+            bus: *mut sd_bus,
+            m: *mut sd_bus_message,
+            usec: u64,
+            error: *mut sd_bus_error,
+            reply: *mut *mut sd_bus_message,
+        ) -> c_int;
+        pub fn sd_bus_message_enter_container(
+            m: *mut sd_bus_message,
+            type_: c_char,
+            contents: *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_message_exit_container(m: *mut sd_bus_message) -> c_int;
+        pub fn sd_bus_message_read(m: *mut sd_bus_message, types: *const c_char, ...) -> c_int;
+        pub fn sd_bus_message_unref(m: *mut sd_bus_message) -> *mut sd_bus_message;
+        pub fn sd_bus_message_peek_type(
+            m: *mut sd_bus_message,
+            type_: *mut c_char,
+            contents: *mut *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_message_skip(m: *mut sd_bus_message, types: *const c_char) -> c_int;
+        pub fn sd_bus_message_rewind(m: *mut sd_bus_message, complete: c_int) -> c_int;
+        pub fn sd_bus_message_dump(m: *mut sd_bus_message, f: *mut libc::FILE, flags: u64)
+            -> c_int;
+        pub fn sd_bus_message_get_realtime_usec(m: *mut sd_bus_message, usec: *mut u64) -> c_int;
+        pub fn sd_bus_message_get_monotonic_usec(m: *mut sd_bus_message, usec: *mut u64) -> c_int;
+        pub fn sd_bus_message_get_seqnum(m: *mut sd_bus_message, seqnum: *mut u64) -> c_int;
+        pub fn sd_bus_message_read_strv(m: *mut sd_bus_message, l: *mut *mut *mut c_char) -> c_int;
+        pub fn sd_bus_path_encode(
+            prefix: *const c_char,
+            external_id: *const c_char,
+            ret_path: *mut *mut c_char,
+        ) -> c_int;
+        pub fn sd_bus_new(bus: *mut *mut sd_bus) -> c_int;
+        pub fn sd_bus_set_address(bus: *mut sd_bus, address: *const c_char) -> c_int;
+        pub fn sd_bus_set_bus_client(bus: *mut sd_bus, b: c_int) -> c_int;
+        pub fn sd_bus_set_monitor(bus: *mut sd_bus, b: c_int) -> c_int;
+        pub fn sd_bus_negotiate_timestamp(bus: *mut sd_bus, b: c_int) -> c_int;
+        pub fn sd_bus_set_fd(bus: *mut sd_bus, input_fd: c_int, output_fd: c_int) -> c_int;
+        pub fn sd_bus_set_server(bus: *mut sd_bus, b: c_int, bus_id: sd_id128_t) -> c_int;
+        pub fn sd_bus_set_anonymous(bus: *mut sd_bus, b: c_int) -> c_int;
+        pub fn sd_bus_start(bus: *mut sd_bus) -> c_int;
+        pub fn sd_bus_set_method_call_timeout(bus: *mut sd_bus, usec: u64) -> c_int;
+        pub fn sd_bus_get_method_call_timeout(bus: *mut sd_bus, ret: *mut u64) -> c_int;
+        pub fn sd_bus_flush_close_unref(bus: *mut sd_bus) -> *mut sd_bus;
+        pub fn sd_bus_add_fallback(
+            bus: *mut sd_bus,
+            slot: *mut *mut c_void,
+            prefix: *const c_char,
+            callback: sd_bus_message_handler_t,
+            userdata: *mut c_void,
+        ) -> c_int;
+        pub fn sd_bus_add_match(
+            bus: *mut sd_bus,
+            slot: *mut *mut sd_bus_slot,
+            match_: *const c_char,
+            callback: sd_bus_message_handler_t,
+            userdata: *mut c_void,
+        ) -> c_int;
+        pub fn sd_bus_call_async(
+            bus: *mut sd_bus,
+            slot: *mut *mut sd_bus_slot,
+            m: *mut sd_bus_message,
+            callback: sd_bus_message_handler_t,
+            userdata: *mut c_void,
+            usec: u64,
+        ) -> c_int;
+        pub fn sd_bus_message_get_error(m: *mut sd_bus_message) -> *const sd_bus_error;
+        pub fn sd_bus_slot_unref(slot: *mut sd_bus_slot) -> *mut sd_bus_slot;
+        pub fn sd_bus_query_sender_creds(
+            m: *mut sd_bus_message,
+            mask: u64,
+            creds: *mut *mut sd_bus_creds,
+        ) -> c_int;
+        pub fn sd_bus_get_name_creds(
+            bus: *mut sd_bus,
+            name: *const c_char,
+            mask: u64,
+            creds: *mut *mut sd_bus_creds,
+        ) -> c_int;
+        pub fn sd_bus_creds_unref(c: *mut sd_bus_creds) -> *mut sd_bus_creds;
+        pub fn sd_bus_track_new(
+            bus: *mut sd_bus,
+            track: *mut *mut sd_bus_track,
+            handler: Option<crate::TrackHandler>,
+            userdata: *mut c_void,
+        ) -> c_int;
+        pub fn sd_bus_track_unref(t: *mut sd_bus_track) -> *mut sd_bus_track;
+        pub fn sd_bus_track_add_name(t: *mut sd_bus_track, name: *const c_char) -> c_int;
+        pub fn sd_bus_track_add_sender(t: *mut sd_bus_track, m: *mut sd_bus_message) -> c_int;
+        pub fn sd_bus_track_remove_name(t: *mut sd_bus_track, name: *const c_char) -> c_int;
+        pub fn sd_bus_track_count(t: *mut sd_bus_track) -> c_uint;
+        pub fn sd_bus_track_contains(t: *mut sd_bus_track, name: *const c_char) -> *const c_char;
+        pub fn sd_bus_creds_get_pid(c: *mut sd_bus_creds, pid: *mut libc::pid_t) -> c_int;
+        pub fn sd_bus_creds_get_uid(c: *mut sd_bus_creds, uid: *mut libc::uid_t) -> c_int;
+        pub fn sd_bus_creds_get_euid(c: *mut sd_bus_creds, uid: *mut libc::uid_t) -> c_int;
+        pub fn sd_bus_creds_get_gid(c: *mut sd_bus_creds, gid: *mut libc::gid_t) -> c_int;
+        pub fn sd_bus_creds_get_selinux_context(
+            c: *mut sd_bus_creds,
+            context: *mut *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_creds_get_unit(c: *mut sd_bus_creds, unit: *mut *const c_char) -> c_int;
+        pub fn sd_bus_creds_get_cgroup(c: *mut sd_bus_creds, cgroup: *mut *const c_char) -> c_int;
+        pub fn sd_bus_slot_set_destroy_callback(
+            slot: *mut sd_bus_slot,
+            callback: Option<unsafe extern "C" fn(userdata: *mut c_void)>,
+        ) -> c_int;
+        pub fn sd_bus_message_get_sender(m: *mut sd_bus_message) -> *const c_char;
+        pub fn sd_bus_process(bus: *mut sd_bus, r: *mut *mut sd_bus_message) -> c_int;
+        pub fn sd_bus_wait(bus: *mut sd_bus, timeout_usec: u64) -> c_int;
+        pub fn sd_bus_get_fd(bus: *mut sd_bus) -> c_int;
+        pub fn sd_bus_get_events(bus: *mut sd_bus) -> c_int;
+        pub fn sd_bus_get_timeout(bus: *mut sd_bus, timeout_usec: *mut u64) -> c_int;
+        pub fn sd_bus_send(bus: *mut sd_bus, m: *mut sd_bus_message, cookie: *mut u64) -> c_int;
+        pub fn sd_bus_message_get_path(m: *mut sd_bus_message) -> *const c_char;
+        pub fn sd_bus_message_get_interface(m: *mut sd_bus_message) -> *const c_char;
+        pub fn sd_bus_message_get_member(m: *mut sd_bus_message) -> *const c_char;
+        pub fn sd_bus_message_get_signature(
+            m: *mut sd_bus_message,
+            complete: c_int,
+        ) -> *const c_char;
+        pub fn sd_bus_message_new_method_return(
+            call: *mut sd_bus_message,
+            m: *mut *mut sd_bus_message,
+        ) -> c_int;
+        pub fn sd_bus_message_append_basic(
+            m: *mut sd_bus_message,
+            type_: c_char,
+            p: *const c_void,
+        ) -> c_int;
+        pub fn sd_bus_message_open_container(
+            m: *mut sd_bus_message,
+            type_: c_char,
+            contents: *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_message_close_container(m: *mut sd_bus_message) -> c_int;
+        pub fn sd_bus_message_read_basic(
+            m: *mut sd_bus_message,
+            type_: c_char,
+            p: *mut c_void,
+        ) -> c_int;
+        pub fn sd_bus_reply_method_error(
+            call: *mut sd_bus_message,
+            e: *const sd_bus_error,
+        ) -> c_int;
+        pub fn sd_bus_error_set(
+            e: *mut sd_bus_error,
+            name: *const c_char,
+            message: *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_request_name(bus: *mut sd_bus, name: *const c_char, flags: u64) -> c_int;
+        pub fn sd_bus_release_name(bus: *mut sd_bus, name: *const c_char) -> c_int;
+        pub fn sd_bus_add_object_vtable(
+            bus: *mut sd_bus,
+            slot: *mut *mut sd_bus_slot,
+            path: *const c_char,
+            interface: *const c_char,
+            vtable: *const sd_bus_vtable,
+            userdata: *mut c_void,
+        ) -> c_int;
+        pub static sd_bus_object_vtable_format: c_uint;
+        pub fn sd_bus_message_new_signal(
+            bus: *mut sd_bus,
+            m: *mut *mut sd_bus_message,
+            path: *const c_char,
+            interface: *const c_char,
+            member: *const c_char,
+        ) -> c_int;
+        pub fn sd_bus_emit_properties_changed_strv(
+            bus: *mut sd_bus,
+            path: *const c_char,
+            interface: *const c_char,
+            names: *mut *mut c_char,
+        ) -> c_int;
+
+        pub fn sd_device_enumerator_new(ret: *mut *mut sd_device_enumerator) -> c_int;
+        pub fn sd_device_enumerator_unref(
+            e: *mut sd_device_enumerator,
+        ) -> *mut sd_device_enumerator;
+        pub fn sd_device_enumerator_add_match_subsystem(
+            e: *mut sd_device_enumerator,
+            subsystem: *const c_char,
+            match_: c_int,
+        ) -> c_int;
+        pub fn sd_device_enumerator_get_device_first(
+            e: *mut sd_device_enumerator,
+        ) -> *mut sd_device;
+        pub fn sd_device_enumerator_get_device_next(e: *mut sd_device_enumerator)
+            -> *mut sd_device;
+        pub fn sd_device_ref(d: *mut sd_device) -> *mut sd_device;
+        pub fn sd_device_unref(d: *mut sd_device) -> *mut sd_device;
+        pub fn sd_device_get_parent(d: *mut sd_device, ret: *mut *mut sd_device) -> c_int;
+        pub fn sd_device_get_syspath(d: *mut sd_device, ret: *mut *const c_char) -> c_int;
+        pub fn sd_device_get_sysname(d: *mut sd_device, ret: *mut *const c_char) -> c_int;
+        pub fn sd_device_get_subsystem(d: *mut sd_device, ret: *mut *const c_char) -> c_int;
+        pub fn sd_device_get_driver(d: *mut sd_device, ret: *mut *const c_char) -> c_int;
+        pub fn sd_device_get_devname(d: *mut sd_device, ret: *mut *const c_char) -> c_int;
+        pub fn sd_device_get_sysattr_value(
+            d: *mut sd_device,
+            sysattr: *const c_char,
+            ret: *mut *const c_char,
+        ) -> c_int;
+        pub fn sd_device_get_property_value(
+            d: *mut sd_device,
+            key: *const c_char,
+            ret: *mut *const c_char,
+        ) -> c_int;
 
         pub fn sd_journal_open(ret: *mut *mut sd_journal, flags: c_int) -> c_int;
         pub fn sd_journal_close(j: *mut sd_journal);
-        pub fn sd_journal_add_match(
+        pub fn sd_journal_add_match(j: *mut sd_journal, data: *const c_void, size: size_t)
+            -> c_int;
+        pub fn sd_journal_add_disjunction(j: *mut sd_journal) -> c_int;
+        pub fn sd_journal_add_conjunction(j: *mut sd_journal) -> c_int;
+        pub fn sd_journal_query_unique(j: *mut sd_journal, field: *const c_char) -> c_int;
+        pub fn sd_journal_enumerate_unique(
+            j: *mut sd_journal,
+            data: *mut *const c_void,
+            length: *mut size_t,
+        ) -> c_int;
+        pub fn sd_journal_restart_unique(j: *mut sd_journal);
+        pub fn sd_journal_restart_data(j: *mut sd_journal);
+        pub fn sd_journal_enumerate_data(
             j: *mut sd_journal,
-            data: *const c_void,
-            size: size_t,
+            data: *mut *const c_void,
+            length: *mut size_t,
+        ) -> c_int;
+        pub fn sd_journal_get_catalog(j: *mut sd_journal, text: *mut *mut c_char) -> c_int;
+        pub fn sd_journal_get_catalog_for_message_id(
+            id: sd_id128_t,
+            text: *mut *mut c_char,
         ) -> c_int;
+        pub fn sd_journal_open_directory(
+            ret: *mut *mut sd_journal,
+            path: *const c_char,
+            flags: c_int,
+        ) -> c_int;
+        pub fn sd_journal_open_files(
+            ret: *mut *mut sd_journal,
+            paths: *const *const c_char,
+            flags: c_int,
+        ) -> c_int;
+        pub fn sd_journal_flush_matches(j: *mut sd_journal);
+        pub fn sd_journal_get_realtime_usec(j: *mut sd_journal, ret: *mut u64) -> c_int;
+        pub fn sd_journal_get_usage(j: *mut sd_journal, bytes: *mut u64) -> c_int;
+        pub fn sd_journal_seek_head(j: *mut sd_journal) -> c_int;
         pub fn sd_journal_seek_tail(j: *mut sd_journal) -> c_int;
         pub fn sd_journal_previous(j: *mut sd_journal) -> c_int;
         pub fn sd_journal_next(j: *mut sd_journal) -> c_int;
+        pub fn sd_journal_previous_skip(j: *mut sd_journal, skip: u64) -> c_int;
+        pub fn sd_journal_next_skip(j: *mut sd_journal, skip: u64) -> c_int;
         pub fn sd_journal_get_data(
             j: *mut sd_journal,
             field: *const c_char,
             data: *mut *const c_void,
             length: *mut size_t,
         ) -> c_int;
+        pub fn sd_journal_sendv(iov: *const libc::iovec, n: c_int) -> c_int;
+        pub fn sd_journal_get_cursor(j: *mut sd_journal, cursor: *mut *mut c_char) -> c_int;
+        pub fn sd_journal_seek_cursor(j: *mut sd_journal, cursor: *const c_char) -> c_int;
+        pub fn sd_journal_test_cursor(j: *mut sd_journal, cursor: *const c_char) -> c_int;
+        pub fn sd_journal_seek_realtime_usec(j: *mut sd_journal, usec: u64) -> c_int;
+        pub fn sd_journal_seek_monotonic_usec(
+            j: *mut sd_journal,
+            boot_id: sd_id128_t,
+            usec: u64,
+        ) -> c_int;
+        pub fn sd_id128_from_string(s: *const c_char, ret: *mut sd_id128_t) -> c_int;
+        pub fn sd_id128_get_boot(ret: *mut sd_id128_t) -> c_int;
+        pub fn sd_id128_to_string(id: sd_id128_t, s: *mut c_char) -> *mut c_char;
+        pub fn sd_journal_wait(j: *mut sd_journal, timeout_usec: u64) -> c_int;
+        pub fn sd_journal_get_fd(j: *mut sd_journal) -> c_int;
+        pub fn sd_journal_get_events(j: *mut sd_journal) -> c_int;
+        pub fn sd_journal_get_timeout(j: *mut sd_journal, timeout_usec: *mut u64) -> c_int;
+        pub fn sd_journal_process(j: *mut sd_journal) -> c_int;
+        pub fn sd_journal_get_monotonic_usec(
+            j: *mut sd_journal,
+            ret: *mut u64,
+            ret_boot_id: *mut sd_id128_t,
+        ) -> c_int;
+    }
+}
+
+/// A C string argument as UTF-8; `None` when NULL or not UTF-8
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// A NULL-terminated C string array argument as UTF-8
+unsafe fn strv_arg<'a>(mut strv: *const *const c_char) -> Option<Vec<&'a str>> {
+    if strv.is_null() {
+        return None;
+    }
+    let mut v = Vec::new();
+    while !(*strv).is_null() {
+        v.push(str_arg(*strv)?);
+        strv = strv.add(1);
+    }
+    Some(v)
+}
+
+/// Hand `s` to a C caller in `*ret`, malloc'd so that
+/// `systemd_shim_free_string` frees it
+pub(crate) unsafe fn give_string(s: &str, ret: *mut *mut c_char) -> c_int {
+    let Ok(s) = CString::new(s) else {
+        return -libc::EINVAL;
+    };
+    let copy = libc::strdup(s.as_ptr());
+    if copy.is_null() {
+        return -libc::ENOMEM;
     }
+    *ret = copy;
+    0
 }
 
 // =============================================================================
@@ -81,11 +549,102 @@ pub fn sd_journal_get_data(
     raw::sd_bus_open_system(bus)
 }
 
+/// The calling user's session bus, for per-user units
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_open_user(bus: *mut *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_open_user(bus)
+}
+
+/// The system bus of `host` over SSH, given as `[user@]host[:port]`;
+/// needs `ssh` and `systemd-stdio-bridge` on the way
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_open_system_remote(
+    bus: *mut *mut raw::sd_bus,
+    host: *const c_char,
+) -> c_int {
+    raw::sd_bus_open_system_remote(bus, host)
+}
+
+/// The system bus inside the local container `machine`, as machined
+/// knows it (e.g. a systemd-nspawn container)
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_open_system_machine(
+    bus: *mut *mut raw::sd_bus,
+    machine: *const c_char,
+) -> c_int {
+    raw::sd_bus_open_system_machine(bus, machine)
+}
+
+// Threads: a bus handle, and every message, slot and creds handle from
+// it, belongs to one thread at a time; sd-bus takes no locks, so two
+// threads must never call into the same bus at once. Rather than open a
+// connection per call, a thread can take its thread-default bus, one
+// connection per thread and bus type, opened on first use and shared by
+// everything on that thread. Each call hands out a new reference, to
+// release with `systemd_shim_bus_unref` and never with a close function,
+// which would cut the connection for the rest of the thread. Match
+// rules, vtables, transient unit properties and returned strings are
+// plain memory that may move between threads.
+
+/// The calling thread's default bus: the one named by
+/// `$DBUS_STARTER_BUS_TYPE` when the bus daemon started the process, the
+/// user bus for a service of a user's service manager, else the system bus
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_default(bus: *mut *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_default(bus)
+}
+
+/// The calling thread's connection to the system bus
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_default_system(bus: *mut *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_default_system(bus)
+}
+
+/// The calling thread's connection to the user's session bus
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_default_user(bus: *mut *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_default_user(bus)
+}
+
+/// Drop a reference; the connection goes when its last one does
 #[no_mangle]
 pub unsafe extern "C" fn systemd_shim_bus_unref(bus: *mut raw::sd_bus) -> *mut raw::sd_bus {
     raw::sd_bus_unref(bus)
 }
 
+/// Write out every queued outgoing message, blocking until done
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_flush(bus: *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_flush(bus)
+}
+
+/// Disconnect without releasing `bus`; later calls on it fail with
+/// -ENOTCONN
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_close(bus: *mut raw::sd_bus) {
+    raw::sd_bus_close(bus)
+}
+
+/// > 0 while connected, 0 once the connection closed or was lost
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_is_open(bus: *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_is_open(bus)
+}
+
+/// Disconnect and release `bus`, dropping anything still queued
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_close_unref(bus: *mut raw::sd_bus) -> *mut raw::sd_bus {
+    raw::sd_bus_close_unref(bus)
+}
+
+/// Flush, disconnect and release `bus`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_flush_close_unref(
+    bus: *mut raw::sd_bus,
+) -> *mut raw::sd_bus {
+    raw::sd_bus_flush_close_unref(bus)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn systemd_shim_bus_get_property_string(
     bus: *mut raw::sd_bus,
@@ -99,70 +658,4160 @@ pub fn sd_journal_get_data(
     raw::sd_bus_get_property_string(bus, destination, path, interface, member, error, ret)
 }
 
+/// Read a string-array (`as`) property as a NULL-terminated array; free
+/// it with `systemd_shim_free_strv`
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_bus_error_free(e: *mut raw::sd_bus_error) {
-    raw::sd_bus_error_free(e)
+pub unsafe extern "C" fn systemd_shim_bus_get_property_strv(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut *mut c_char,
+) -> c_int {
+    raw::sd_bus_get_property_strv(bus, destination, path, interface, member, error, ret)
 }
 
+/// Every property of `interface` on `path` in one `GetAll` call, as a
+/// JSON object in `*ret`; free it with `systemd_shim_free_string`
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_free_string(s: *mut c_char) {
-    if !s.is_null() {
-        libc::free(s as *mut libc::c_void);
+pub unsafe extern "C" fn systemd_shim_bus_get_all_properties_json(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        destination,
+        path,
+        c!("org.freedesktop.DBus.Properties"),
+        c!("GetAll"),
+        error,
+        &mut reply,
+        c!("s"),
+        interface,
+    );
+    if r < 0 {
+        return r;
     }
+    let r = json::export(reply, ret);
+    raw::sd_bus_message_unref(reply);
+    r
 }
 
-// =============================================================================
-// sd-journal shim functions
-// =============================================================================
+/// Every object an `org.freedesktop.DBus.ObjectManager` at `path` knows
+/// of, from one `GetManagedObjects` call, as JSON in `*ret`:
+/// `{ object path: { interface: { property: value } } }`. Free it with
+/// `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_get_managed_objects_json(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        destination,
+        path,
+        c!("org.freedesktop.DBus.ObjectManager"),
+        c!("GetManagedObjects"),
+        error,
+        &mut reply,
+        ptr::null::<c_char>(),
+    );
+    if r < 0 {
+        return r;
+    }
+    let r = json::export(reply, ret);
+    raw::sd_bus_message_unref(reply);
+    r
+}
+
+// Setters for writable properties, one per type since the value travels
+// as a variadic argument
 
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_open(
-    journal: *mut *mut raw::sd_journal,
-    flags: c_int,
+pub unsafe extern "C" fn systemd_shim_bus_set_property_string(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+    value: *const c_char,
 ) -> c_int {
-    raw::sd_journal_open(journal, flags)
+    raw::sd_bus_set_property(
+        bus,
+        destination,
+        path,
+        interface,
+        member,
+        error,
+        c!("s"),
+        value,
+    )
 }
 
+/// Set a boolean property; any non-zero `value` is true
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_close(journal: *mut raw::sd_journal) {
-    raw::sd_journal_close(journal)
+pub unsafe extern "C" fn systemd_shim_bus_set_property_bool(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+    value: c_int,
+) -> c_int {
+    raw::sd_bus_set_property(
+        bus,
+        destination,
+        path,
+        interface,
+        member,
+        error,
+        c!("b"),
+        c_int::from(value != 0),
+    )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_add_match(
-    journal: *mut raw::sd_journal,
-    data: *const u8,
-    len: usize,
+pub unsafe extern "C" fn systemd_shim_bus_set_property_u32(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+    value: u32,
 ) -> c_int {
-    raw::sd_journal_add_match(journal, data as *const libc::c_void, len)
+    raw::sd_bus_set_property(
+        bus,
+        destination,
+        path,
+        interface,
+        member,
+        error,
+        c!("u"),
+        value,
+    )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_seek_tail(journal: *mut raw::sd_journal) -> c_int {
-    raw::sd_journal_seek_tail(journal)
+pub unsafe extern "C" fn systemd_shim_bus_set_property_u64(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+    value: u64,
+) -> c_int {
+    raw::sd_bus_set_property(
+        bus,
+        destination,
+        path,
+        interface,
+        member,
+        error,
+        c!("t"),
+        value,
+    )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_previous(journal: *mut raw::sd_journal) -> c_int {
-    raw::sd_journal_previous(journal)
+pub unsafe extern "C" fn systemd_shim_bus_error_free(e: *mut raw::sd_bus_error) {
+    raw::sd_bus_error_free(e)
 }
 
+// Errors a call failed with. The accessors take the error a call filled
+// in and are safe on one that was never set; its strings are borrowed
+// and last until `systemd_shim_bus_error_free`.
+
+/// > 0 when a call set `e`
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_next(journal: *mut raw::sd_journal) -> c_int {
-    raw::sd_journal_next(journal)
+pub unsafe extern "C" fn systemd_shim_bus_error_is_set(e: *const raw::sd_bus_error) -> c_int {
+    raw::sd_bus_error_is_set(e)
 }
 
+/// The D-Bus error name, e.g. `org.freedesktop.DBus.Error.AccessDenied`;
+/// NULL when unset
 #[no_mangle]
-pub unsafe extern "C" fn systemd_shim_journal_get_data(
-    journal: *mut raw::sd_journal,
-    field: *const c_char,
-    data: *mut *const u8,
-    len: *mut usize,
+pub unsafe extern "C" fn systemd_shim_bus_error_get_name(
+    e: *const raw::sd_bus_error,
+) -> *const c_char {
+    e.as_ref().map_or(ptr::null(), |e| e.name)
+}
+
+/// The human-readable message, which may be NULL even when set
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_error_get_message(
+    e: *const raw::sd_bus_error,
+) -> *const c_char {
+    e.as_ref().map_or(ptr::null(), |e| e.message)
+}
+
+/// > 0 when `e` is set to `name`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_error_has_name(
+    e: *const raw::sd_bus_error,
+    name: *const c_char,
 ) -> c_int {
-    raw::sd_journal_get_data(
-        journal,
-        field,
-        data as *mut *const libc::c_void,
-        len,
-    )
+    raw::sd_bus_error_has_name(e, name)
+}
+
+/// The positive errno sd-bus maps the error's name to (`EACCES` for
+/// `AccessDenied`, ...), `EIO` for names it does not know, 0 when unset
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_error_get_errno(e: *const raw::sd_bus_error) -> c_int {
+    raw::sd_bus_error_get_errno(e)
+}
+
+pub const SYSTEMD_SHIM_ERROR_NONE: c_int = 0;
+/// Any error without a code of its own
+pub const SYSTEMD_SHIM_ERROR_FAILED: c_int = 1;
+pub const SYSTEMD_SHIM_ERROR_NO_MEMORY: c_int = 2;
+pub const SYSTEMD_SHIM_ERROR_SERVICE_UNKNOWN: c_int = 3;
+pub const SYSTEMD_SHIM_ERROR_NAME_HAS_NO_OWNER: c_int = 4;
+pub const SYSTEMD_SHIM_ERROR_NO_REPLY: c_int = 5;
+pub const SYSTEMD_SHIM_ERROR_TIMEOUT: c_int = 6;
+pub const SYSTEMD_SHIM_ERROR_DISCONNECTED: c_int = 7;
+pub const SYSTEMD_SHIM_ERROR_ACCESS_DENIED: c_int = 8;
+pub const SYSTEMD_SHIM_ERROR_AUTH_FAILED: c_int = 9;
+pub const SYSTEMD_SHIM_ERROR_INTERACTIVE_AUTHORIZATION_REQUIRED: c_int = 10;
+pub const SYSTEMD_SHIM_ERROR_INVALID_ARGS: c_int = 11;
+pub const SYSTEMD_SHIM_ERROR_UNKNOWN_METHOD: c_int = 12;
+pub const SYSTEMD_SHIM_ERROR_UNKNOWN_OBJECT: c_int = 13;
+pub const SYSTEMD_SHIM_ERROR_UNKNOWN_INTERFACE: c_int = 14;
+pub const SYSTEMD_SHIM_ERROR_UNKNOWN_PROPERTY: c_int = 15;
+pub const SYSTEMD_SHIM_ERROR_PROPERTY_READ_ONLY: c_int = 16;
+pub const SYSTEMD_SHIM_ERROR_NOT_SUPPORTED: c_int = 17;
+pub const SYSTEMD_SHIM_ERROR_LIMITS_EXCEEDED: c_int = 18;
+
+/// Error names with a code of their own. The codes are part of the ABI:
+/// new ones are added at the end, never renumbered
+const ERROR_CODES: &[(&str, c_int)] = &[
+    ("NoMemory", SYSTEMD_SHIM_ERROR_NO_MEMORY),
+    ("ServiceUnknown", SYSTEMD_SHIM_ERROR_SERVICE_UNKNOWN),
+    ("NameHasNoOwner", SYSTEMD_SHIM_ERROR_NAME_HAS_NO_OWNER),
+    ("NoReply", SYSTEMD_SHIM_ERROR_NO_REPLY),
+    ("Timeout", SYSTEMD_SHIM_ERROR_TIMEOUT),
+    ("TimedOut", SYSTEMD_SHIM_ERROR_TIMEOUT),
+    ("Disconnected", SYSTEMD_SHIM_ERROR_DISCONNECTED),
+    ("AccessDenied", SYSTEMD_SHIM_ERROR_ACCESS_DENIED),
+    ("AuthFailed", SYSTEMD_SHIM_ERROR_AUTH_FAILED),
+    (
+        "InteractiveAuthorizationRequired",
+        SYSTEMD_SHIM_ERROR_INTERACTIVE_AUTHORIZATION_REQUIRED,
+    ),
+    ("InvalidArgs", SYSTEMD_SHIM_ERROR_INVALID_ARGS),
+    ("UnknownMethod", SYSTEMD_SHIM_ERROR_UNKNOWN_METHOD),
+    ("UnknownObject", SYSTEMD_SHIM_ERROR_UNKNOWN_OBJECT),
+    ("UnknownInterface", SYSTEMD_SHIM_ERROR_UNKNOWN_INTERFACE),
+    ("UnknownProperty", SYSTEMD_SHIM_ERROR_UNKNOWN_PROPERTY),
+    ("PropertyReadOnly", SYSTEMD_SHIM_ERROR_PROPERTY_READ_ONLY),
+    ("NotSupported", SYSTEMD_SHIM_ERROR_NOT_SUPPORTED),
+    ("LimitsExceeded", SYSTEMD_SHIM_ERROR_LIMITS_EXCEEDED),
+];
+
+/// The `SYSTEMD_SHIM_ERROR_*` code for `e`'s `org.freedesktop.DBus.Error.*`
+/// name: `NONE` when unset, `FAILED` for names outside the list
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_error_code(e: *const raw::sd_bus_error) -> c_int {
+    let Some(name) = e.as_ref().and_then(|e| str_arg(e.name)) else {
+        return SYSTEMD_SHIM_ERROR_NONE;
+    };
+    name.strip_prefix("org.freedesktop.DBus.Error.")
+        .and_then(|short| ERROR_CODES.iter().find(|(n, _)| *n == short))
+        .map_or(SYSTEMD_SHIM_ERROR_FAILED, |&(_, code)| code)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        libc::free(s as *mut libc::c_void);
+    }
+}
+
+/// Free a NULL-terminated string array and every string in it
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_free_strv(l: *mut *mut c_char) {
+    if l.is_null() {
+        return;
+    }
+    let mut s = l;
+    while !(*s).is_null() {
+        libc::free(*s as *mut c_void);
+        s = s.add(1);
+    }
+    libc::free(l as *mut c_void);
+}
+
+// =============================================================================
+// sd-bus method calls
+// =============================================================================
+//
+// A call with arguments is built as a message: `new_method_call`, then an
+// append per argument, then `call`. Replies, like the messages built, are
+// the caller's to release with `systemd_shim_bus_message_unref`.
+//
+// A timeout of 0 stands for the connection's method call timeout, 25s
+// unless `systemd_shim_bus_set_method_call_timeout` changed it. Property
+// getters and setters always wait that long.
+
+/// Set how long calls on `bus` wait for a reply by default; 0 restores
+/// sd-bus's own default
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_set_method_call_timeout(
+    bus: *mut raw::sd_bus,
+    usec: u64,
+) -> c_int {
+    raw::sd_bus_set_method_call_timeout(bus, usec)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_get_method_call_timeout(
+    bus: *mut raw::sd_bus,
+    usec: *mut u64,
+) -> c_int {
+    raw::sd_bus_get_method_call_timeout(bus, usec)
+}
+
+/// Call a method that takes no arguments
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_call_method(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+    reply: *mut *mut raw::sd_bus_message,
+) -> c_int {
+    systemd_shim_bus_call_method_timeout(bus, destination, path, interface, member, 0, error, reply)
+}
+
+/// `systemd_shim_bus_call_method`, waiting up to `usec` microseconds
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_call_method_timeout(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    usec: u64,
+    error: *mut raw::sd_bus_error,
+    reply: *mut *mut raw::sd_bus_message,
+) -> c_int {
+    let mut m = ptr::null_mut();
+    let r = raw::sd_bus_message_new_method_call(bus, &mut m, destination, path, interface, member);
+    if r < 0 {
+        return r;
+    }
+    let r = raw::sd_bus_call(bus, m, usec, error, reply);
+    raw::sd_bus_message_unref(m);
+    r
+}
+
+/// The introspection XML of the object at `path` on `destination`, in
+/// `*xml`; free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_introspect(
+    bus: *mut raw::sd_bus,
+    destination: *const c_char,
+    path: *const c_char,
+    error: *mut raw::sd_bus_error,
+    xml: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = systemd_shim_bus_call_method(
+        bus,
+        destination,
+        path,
+        c!("org.freedesktop.DBus.Introspectable"),
+        c!("Introspect"),
+        error,
+        &mut reply,
+    );
+    if r < 0 {
+        return r;
+    }
+    let mut text: *const c_char = ptr::null();
+    let r = match raw::sd_bus_message_read_basic(
+        reply,
+        b's' as c_char,
+        &mut text as *mut *const c_char as *mut c_void,
+    ) {
+        // An empty reply reads nothing and leaves `text` NULL
+        0 => -libc::EBADMSG,
+        r if r < 0 => r,
+        _ => give_string(&CStr::from_ptr(text).to_string_lossy(), xml),
+    };
+    raw::sd_bus_message_unref(reply);
+    r
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_new_method_call(
+    bus: *mut raw::sd_bus,
+    m: *mut *mut raw::sd_bus_message,
+    destination: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+) -> c_int {
+    raw::sd_bus_message_new_method_call(bus, m, destination, path, interface, member)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_string(
+    m: *mut raw::sd_bus_message,
+    s: *const c_char,
+) -> c_int {
+    // Strings are passed as themselves, not by reference
+    raw::sd_bus_message_append_basic(m, b's' as c_char, s as *const c_void)
+}
+
+/// Append one basic value; `p` points at it, except for strings (`s`,
+/// `o`, `g`) where it is the string
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_basic(
+    m: *mut raw::sd_bus_message,
+    type_: c_char,
+    p: *const c_void,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, type_, p)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_object_path(
+    m: *mut raw::sd_bus_message,
+    path: *const c_char,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b'o' as c_char, path as *const c_void)
+}
+
+/// Append a boolean; any non-zero `b` is true
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_bool(
+    m: *mut raw::sd_bus_message,
+    b: c_int,
+) -> c_int {
+    // D-Bus booleans are 32 bits wide and must be 0 or 1
+    let b = c_int::from(b != 0);
+    raw::sd_bus_message_append_basic(m, b'b' as c_char, &b as *const c_int as *const c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_i32(
+    m: *mut raw::sd_bus_message,
+    v: i32,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b'i' as c_char, &v as *const i32 as *const c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_u32(
+    m: *mut raw::sd_bus_message,
+    v: u32,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b'u' as c_char, &v as *const u32 as *const c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_i64(
+    m: *mut raw::sd_bus_message,
+    v: i64,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b'x' as c_char, &v as *const i64 as *const c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_u64(
+    m: *mut raw::sd_bus_message,
+    v: u64,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b't' as c_char, &v as *const u64 as *const c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_double(
+    m: *mut raw::sd_bus_message,
+    v: f64,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b'd' as c_char, &v as *const f64 as *const c_void)
+}
+
+/// Append `fd` as a `h`; the message takes a duplicate, so the caller
+/// still owns and closes `fd`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_append_fd(
+    m: *mut raw::sd_bus_message,
+    fd: c_int,
+) -> c_int {
+    raw::sd_bus_message_append_basic(m, b'h' as c_char, &fd as *const c_int as *const c_void)
+}
+
+/// sd-bus names structs and dict entries `r` and `e`; their opening
+/// brackets are taken as well
+fn container_type(type_: c_char) -> c_char {
+    match type_ as u8 {
+        b'(' => b'r' as c_char,
+        b'{' => b'e' as c_char,
+        _ => type_,
+    }
+}
+
+/// Open an array (`a`), variant (`v`), struct (`r`) or dict entry (`e`)
+/// holding `contents`, e.g. `a` of `{sv}`; close it when its values are
+/// appended
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_open_container(
+    m: *mut raw::sd_bus_message,
+    type_: c_char,
+    contents: *const c_char,
+) -> c_int {
+    raw::sd_bus_message_open_container(m, container_type(type_), contents)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_close_container(
+    m: *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_message_close_container(m)
+}
+
+// Reading walks a reply in order. Each read returns 1 when it read a
+// value and 0 at the end of the enclosing container; strings read point
+// into the message and live as long as it does.
+
+/// Read one basic value of `type_` into `p`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_basic(
+    m: *mut raw::sd_bus_message,
+    type_: c_char,
+    p: *mut c_void,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, type_, p)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_string(
+    m: *mut raw::sd_bus_message,
+    s: *mut *const c_char,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b's' as c_char, s as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_object_path(
+    m: *mut raw::sd_bus_message,
+    path: *mut *const c_char,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b'o' as c_char, path as *mut c_void)
+}
+
+/// Read a boolean as 0 or 1
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_bool(
+    m: *mut raw::sd_bus_message,
+    b: *mut c_int,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b'b' as c_char, b as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_i32(
+    m: *mut raw::sd_bus_message,
+    v: *mut i32,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b'i' as c_char, v as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_u32(
+    m: *mut raw::sd_bus_message,
+    v: *mut u32,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b'u' as c_char, v as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_i64(
+    m: *mut raw::sd_bus_message,
+    v: *mut i64,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b'x' as c_char, v as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_u64(
+    m: *mut raw::sd_bus_message,
+    v: *mut u64,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b't' as c_char, v as *mut c_void)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_double(
+    m: *mut raw::sd_bus_message,
+    v: *mut f64,
+) -> c_int {
+    raw::sd_bus_message_read_basic(m, b'd' as c_char, v as *mut c_void)
+}
+
+/// Read a `h` into `*fd` as a new close-on-exec descriptor the caller
+/// owns and closes. The message closes its own copy when freed, so the
+/// caller's outlives it: a logind inhibitor lock, say, holds for as long
+/// as the caller keeps the descriptor open
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_read_fd(
+    m: *mut raw::sd_bus_message,
+    fd: *mut c_int,
+) -> c_int {
+    let mut borrowed: c_int = -1;
+    let r = raw::sd_bus_message_read_basic(
+        m,
+        b'h' as c_char,
+        &mut borrowed as *mut c_int as *mut c_void,
+    );
+    if r <= 0 {
+        return r;
+    }
+    let owned = libc::fcntl(borrowed, libc::F_DUPFD_CLOEXEC, 3);
+    if owned < 0 {
+        return -*libc::__errno_location();
+    }
+    *fd = owned;
+    r
+}
+
+/// Enter the container of `type_` holding `contents` at the read
+/// position; `contents` may be NULL to take whatever it holds
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_enter_container(
+    m: *mut raw::sd_bus_message,
+    type_: c_char,
+    contents: *const c_char,
+) -> c_int {
+    raw::sd_bus_message_enter_container(m, container_type(type_), contents)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_exit_container(
+    m: *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_message_exit_container(m)
+}
+
+/// The type at the read position, and for a container what it holds;
+/// 0 at the end of the enclosing container
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_peek_type(
+    m: *mut raw::sd_bus_message,
+    type_: *mut c_char,
+    contents: *mut *const c_char,
+) -> c_int {
+    raw::sd_bus_message_peek_type(m, type_, contents)
+}
+
+/// Skip the values of `types`, or the next complete value when NULL
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_skip(
+    m: *mut raw::sd_bus_message,
+    types: *const c_char,
+) -> c_int {
+    raw::sd_bus_message_skip(m, types)
+}
+
+/// The signature of `m`'s body, or with `complete` 0 only of what is
+/// left to read in the current container; owned by the message. NULL if
+/// `m` is NULL
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_get_signature(
+    m: *mut raw::sd_bus_message,
+    complete: c_int,
+) -> *const c_char {
+    raw::sd_bus_message_get_signature(m, complete)
+}
+
+unsafe fn check_signature(
+    signature: *const c_char,
+    check: fn(&str) -> Result<(), String>,
+    error: *mut *mut c_char,
+) -> c_int {
+    let result = match str_arg(signature) {
+        Some(signature) => check(signature),
+        None => Err("no signature, or not UTF-8".to_string()),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(message) => {
+            if !error.is_null() {
+                give_string(&message, error);
+            }
+            -libc::EINVAL
+        }
+    }
+}
+
+/// 0 if `signature` is a valid body signature, any number of complete
+/// types; else -EINVAL and, if `error` is not NULL, what is wrong with
+/// it in `*error`, to free with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_signature_validate(
+    signature: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    check_signature(signature, signature::check, error)
+}
+
+/// As `systemd_shim_signature_validate`, for exactly one complete type,
+/// as a property or variant holds
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_signature_validate_single(
+    signature: *const c_char,
+    error: *mut *mut c_char,
+) -> c_int {
+    check_signature(signature, signature::check_single, error)
+}
+
+/// Start `systemd_shim_bus_message_dump` with the header: type, sender,
+/// destination, path, interface, member, cookie and so on
+pub const SYSTEMD_SHIM_DUMP_WITH_HEADER: u64 = 1 << 0;
+/// Dump only the value at the read position instead of the whole body
+pub const SYSTEMD_SHIM_DUMP_SUBTREE_ONLY: u64 = 1 << 1;
+
+/// Render `m` as text in `*ret`, the way `busctl monitor` prints it, for
+/// logs; free it with `systemd_shim_free_string`. `flags` takes the
+/// `SYSTEMD_SHIM_DUMP_*` bits. A whole-body dump leaves the message
+/// rewound, ready to be read again
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_dump(
+    m: *mut raw::sd_bus_message,
+    flags: u64,
+    ret: *mut *mut c_char,
+) -> c_int {
+    if ret.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut text: *mut c_char = ptr::null_mut();
+    let mut len: usize = 0;
+    let f = libc::open_memstream(&mut text, &mut len);
+    if f.is_null() {
+        return -*libc::__errno_location();
+    }
+    let mut r = raw::sd_bus_message_dump(m, f, flags);
+    // Closing puts the text in place, and can itself fail for want of memory
+    if libc::fclose(f) != 0 && r >= 0 {
+        r = -libc::ENOMEM;
+    }
+    if r < 0 {
+        libc::free(text as *mut c_void);
+        return r;
+    }
+    if flags & SYSTEMD_SHIM_DUMP_SUBTREE_ONLY == 0 {
+        raw::sd_bus_message_rewind(m, 1);
+    }
+    *ret = text;
+    0
+}
+
+/// Ask the transport to stamp the messages `bus` receives with when they
+/// arrived, read back with `systemd_shim_bus_message_get_realtime_usec`
+/// and friends. Only kdbus ever did: over the bus daemon's unix socket
+/// the stamps stay unset, so treat them as a bonus when present
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_negotiate_timestamp(
+    bus: *mut raw::sd_bus,
+    enable: c_int,
+) -> c_int {
+    raw::sd_bus_negotiate_timestamp(bus, enable)
+}
+
+/// When `m` arrived, in CLOCK_REALTIME microseconds; -ENODATA if the
+/// transport did not stamp it, as is always so for messages built here
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_get_realtime_usec(
+    m: *mut raw::sd_bus_message,
+    usec: *mut u64,
+) -> c_int {
+    raw::sd_bus_message_get_realtime_usec(m, usec)
+}
+
+/// When `m` arrived, in CLOCK_MONOTONIC microseconds; -ENODATA if unset
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_get_monotonic_usec(
+    m: *mut raw::sd_bus_message,
+    usec: *mut u64,
+) -> c_int {
+    raw::sd_bus_message_get_monotonic_usec(m, usec)
+}
+
+/// The transport's sequence number for `m`; -ENODATA if unset
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_get_seqnum(
+    m: *mut raw::sd_bus_message,
+    seqnum: *mut u64,
+) -> c_int {
+    raw::sd_bus_message_get_seqnum(m, seqnum)
+}
+
+/// Send `m` and wait up to `usec` microseconds for the reply; 0 waits
+/// the bus's default
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_call(
+    bus: *mut raw::sd_bus,
+    m: *mut raw::sd_bus_message,
+    usec: u64,
+    error: *mut raw::sd_bus_error,
+    reply: *mut *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_call(bus, m, usec, error, reply)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_unref(
+    m: *mut raw::sd_bus_message,
+) -> *mut raw::sd_bus_message {
+    raw::sd_bus_message_unref(m)
+}
+
+// =============================================================================
+// sd-bus signals
+// =============================================================================
+//
+// A match calls its handler from `systemd_shim_bus_process`, on the thread
+// that processes the bus, for as long as its slot is held.
+
+/// Called for each message a match lets through; `m` is only borrowed
+/// for the call. A negative return is logged by sd-bus and otherwise
+/// ignored.
+pub type SignalHandler = unsafe extern "C" fn(
+    sender: *const c_char,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+    m: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+) -> c_int;
+
+struct Subscription {
+    handler: SignalHandler,
+    userdata: *mut c_void,
+}
+
+unsafe extern "C" fn dispatch_signal(
+    m: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+    _error: *mut raw::sd_bus_error,
+) -> c_int {
+    let subscription = &*(userdata as *const Subscription);
+    (subscription.handler)(
+        raw::sd_bus_message_get_sender(m),
+        raw::sd_bus_message_get_path(m),
+        raw::sd_bus_message_get_interface(m),
+        raw::sd_bus_message_get_member(m),
+        m,
+        subscription.userdata,
+    )
+}
+
+unsafe extern "C" fn drop_subscription(userdata: *mut c_void) {
+    drop(Box::from_raw(userdata as *mut Subscription));
+}
+
+/// Install `match_`, a match rule such as
+/// `type='signal',interface='org.freedesktop.systemd1.Manager',member='JobRemoved'`,
+/// and call `handler` with each message it matches. `*slot` holds the
+/// subscription: `systemd_shim_bus_slot_unref` ends it.
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_add_match(
+    bus: *mut raw::sd_bus,
+    slot: *mut *mut raw::sd_bus_slot,
+    match_: *const c_char,
+    handler: SignalHandler,
+    userdata: *mut c_void,
+) -> c_int {
+    // Without a slot the subscription could never be ended or freed
+    if slot.is_null() {
+        return -libc::EINVAL;
+    }
+    let subscription = Box::into_raw(Box::new(Subscription { handler, userdata }));
+    let r = raw::sd_bus_add_match(
+        bus,
+        slot,
+        match_,
+        dispatch_signal,
+        subscription as *mut c_void,
+    );
+    if r < 0 {
+        drop(Box::from_raw(subscription));
+        return r;
+    }
+    raw::sd_bus_slot_set_destroy_callback(*slot, Some(drop_subscription));
+    r
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_slot_unref(
+    slot: *mut raw::sd_bus_slot,
+) -> *mut raw::sd_bus_slot {
+    raw::sd_bus_slot_unref(slot)
+}
+
+// =============================================================================
+// sd-bus async calls
+// =============================================================================
+//
+// The reply handler runs from `systemd_shim_bus_process` once the reply
+// arrives or the call times out. Dropping the slot before then cancels
+// the call, and the handler is never run.
+
+/// Called with the reply to an async call; `error` is NULL on success,
+/// otherwise it holds the error the call failed with, timeouts included.
+/// Both are only borrowed for the call.
+pub type ReplyHandler = unsafe extern "C" fn(
+    reply: *mut raw::sd_bus_message,
+    error: *const raw::sd_bus_error,
+    userdata: *mut c_void,
+) -> c_int;
+
+struct PendingCall {
+    handler: ReplyHandler,
+    userdata: *mut c_void,
+}
+
+unsafe extern "C" fn dispatch_reply(
+    m: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+    _error: *mut raw::sd_bus_error,
+) -> c_int {
+    let call = &*(userdata as *const PendingCall);
+    (call.handler)(m, raw::sd_bus_message_get_error(m), call.userdata)
+}
+
+unsafe extern "C" fn drop_pending_call(userdata: *mut c_void) {
+    drop(Box::from_raw(userdata as *mut PendingCall));
+}
+
+/// Send `m` without waiting and call `handler` with its reply, after at
+/// most `usec` microseconds (0 for the bus default). `*slot` holds the
+/// pending call: `systemd_shim_bus_slot_unref` cancels it.
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_call_async(
+    bus: *mut raw::sd_bus,
+    slot: *mut *mut raw::sd_bus_slot,
+    m: *mut raw::sd_bus_message,
+    handler: ReplyHandler,
+    userdata: *mut c_void,
+    usec: u64,
+) -> c_int {
+    if slot.is_null() {
+        return -libc::EINVAL;
+    }
+    let call = Box::into_raw(Box::new(PendingCall { handler, userdata }));
+    let r = raw::sd_bus_call_async(bus, slot, m, dispatch_reply, call as *mut c_void, usec);
+    if r < 0 {
+        drop(Box::from_raw(call));
+        return r;
+    }
+    raw::sd_bus_slot_set_destroy_callback(*slot, Some(drop_pending_call));
+    r
+}
+
+// =============================================================================
+// sd-bus credentials
+// =============================================================================
+//
+// Who sent a message or owns a name. The bus daemon vouches for what it
+// can; the rest is read from /proc, which a process could have changed
+// by exec'ing since, so the PID and UIDs are the fields to trust. A
+// field that could not be found fails with -ENODATA, one the kernel or
+// daemon does not track with -ENXIO. Strings are borrowed from the
+// handle and last until `systemd_shim_bus_creds_unref`.
+
+const CREDS_PID: u64 = 1 << 0;
+const CREDS_UID: u64 = 1 << 3;
+const CREDS_EUID: u64 = 1 << 4;
+const CREDS_GID: u64 = 1 << 7;
+const CREDS_CGROUP: u64 = 1 << 16;
+const CREDS_UNIT: u64 = 1 << 17;
+const CREDS_SELINUX_CONTEXT: u64 = 1 << 27;
+const CREDS_AUGMENT: u64 = 1 << 63;
+
+/// The fields the getters below read
+const CREDS_MASK: u64 = CREDS_PID
+    | CREDS_UID
+    | CREDS_EUID
+    | CREDS_GID
+    | CREDS_CGROUP
+    | CREDS_UNIT
+    | CREDS_SELINUX_CONTEXT
+    | CREDS_AUGMENT;
+
+/// The credentials of whoever sent `m`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_query_sender_creds(
+    m: *mut raw::sd_bus_message,
+    creds: *mut *mut raw::sd_bus_creds,
+) -> c_int {
+    raw::sd_bus_query_sender_creds(m, CREDS_MASK, creds)
+}
+
+/// The credentials of the current owner of `name`, unique or well-known
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_get_name_creds(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    creds: *mut *mut raw::sd_bus_creds,
+) -> c_int {
+    raw::sd_bus_get_name_creds(bus, name, CREDS_MASK, creds)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_unref(
+    creds: *mut raw::sd_bus_creds,
+) -> *mut raw::sd_bus_creds {
+    raw::sd_bus_creds_unref(creds)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_pid(
+    creds: *mut raw::sd_bus_creds,
+    pid: *mut libc::pid_t,
+) -> c_int {
+    raw::sd_bus_creds_get_pid(creds, pid)
+}
+
+/// The real UID
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_uid(
+    creds: *mut raw::sd_bus_creds,
+    uid: *mut libc::uid_t,
+) -> c_int {
+    raw::sd_bus_creds_get_uid(creds, uid)
+}
+
+/// The effective UID, the one permission checks go by
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_euid(
+    creds: *mut raw::sd_bus_creds,
+    uid: *mut libc::uid_t,
+) -> c_int {
+    raw::sd_bus_creds_get_euid(creds, uid)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_gid(
+    creds: *mut raw::sd_bus_creds,
+    gid: *mut libc::gid_t,
+) -> c_int {
+    raw::sd_bus_creds_get_gid(creds, gid)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_selinux_context(
+    creds: *mut raw::sd_bus_creds,
+    context: *mut *const c_char,
+) -> c_int {
+    raw::sd_bus_creds_get_selinux_context(creds, context)
+}
+
+/// The system unit the process runs in, e.g. `sshd.service`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_unit(
+    creds: *mut raw::sd_bus_creds,
+    unit: *mut *const c_char,
+) -> c_int {
+    raw::sd_bus_creds_get_unit(creds, unit)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_creds_get_cgroup(
+    creds: *mut raw::sd_bus_creds,
+    cgroup: *mut *const c_char,
+) -> c_int {
+    raw::sd_bus_creds_get_cgroup(creds, cgroup)
+}
+
+// =============================================================================
+// sd-bus services
+// =============================================================================
+//
+// Serving an object: take a name with `request_name`, collect the
+// interface's methods on a vtable handle and register it at a path. The
+// handlers run from `systemd_shim_bus_process` and answer each call with
+// one of the reply functions, now or later; a handler that returns a
+// negative errno, or sets `error`, has the error sent as the reply.
+
+/// Handles a call to one method; `call` is borrowed unless referenced
+pub type MethodHandler = unsafe extern "C" fn(
+    call: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+    error: *mut raw::sd_bus_error,
+) -> c_int;
+
+/// Appends a property's current value to `reply`, in the property's
+/// signature and without a variant around it
+pub type PropertyGetter = unsafe extern "C" fn(
+    reply: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+    error: *mut raw::sd_bus_error,
+) -> c_int;
+
+/// Take over the name if its owner allows it
+pub const SYSTEMD_SHIM_NAME_REPLACE_EXISTING: u64 = 1 << 0;
+/// Let another connection take the name over
+pub const SYSTEMD_SHIM_NAME_ALLOW_REPLACEMENT: u64 = 1 << 1;
+/// Wait in line for the name rather than fail with -EEXIST
+pub const SYSTEMD_SHIM_NAME_QUEUE: u64 = 1 << 2;
+
+/// Own the well-known `name` on `bus`; `flags` combines the
+/// `SYSTEMD_SHIM_NAME_*` values
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_request_name(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    flags: u64,
+) -> c_int {
+    raw::sd_bus_request_name(bus, name, flags)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_release_name(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+) -> c_int {
+    raw::sd_bus_release_name(bus, name)
+}
+
+#[no_mangle]
+pub extern "C" fn systemd_shim_vtable_new() -> *mut Vtable {
+    Box::into_raw(Box::new(Vtable::new()))
+}
+
+/// Free a vtable that was never registered
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_vtable_free(vtable: *mut Vtable) {
+    if !vtable.is_null() {
+        drop(Box::from_raw(vtable));
+    }
+}
+
+/// Add method `member` taking `signature` and replying with `result`
+/// (`""` for none), handled by `handler` with `userdata`. On the system
+/// bus a caller needs CAP_SYS_ADMIN or the service's own user unless
+/// `unprivileged` is set. -EINVAL for a bad or repeated member name or a
+/// malformed signature
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_vtable_add_method(
+    vtable: *mut Vtable,
+    member: *const c_char,
+    signature: *const c_char,
+    result: *const c_char,
+    unprivileged: c_int,
+    handler: MethodHandler,
+    userdata: *mut c_void,
+) -> c_int {
+    let (Some(vtable), Some(member), Some(signature), Some(result)) = (
+        vtable.as_mut(),
+        str_arg(member),
+        str_arg(signature),
+        str_arg(result),
+    ) else {
+        return -libc::EINVAL;
+    };
+    match vtable.add_method(
+        member,
+        signature,
+        result,
+        unprivileged != 0,
+        handler,
+        userdata,
+    ) {
+        Ok(()) => 0,
+        Err(_) => -libc::EINVAL,
+    }
+}
+
+/// Add read-only property `member` of type `signature`, read with
+/// `getter` and `userdata` whenever a client asks or it is announced by
+/// `systemd_shim_bus_emit_properties_changed`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_vtable_add_property(
+    vtable: *mut Vtable,
+    member: *const c_char,
+    signature: *const c_char,
+    getter: PropertyGetter,
+    userdata: *mut c_void,
+) -> c_int {
+    let (Some(vtable), Some(member), Some(signature)) =
+        (vtable.as_mut(), str_arg(member), str_arg(signature))
+    else {
+        return -libc::EINVAL;
+    };
+    match vtable.add_property(member, signature, getter, userdata) {
+        Ok(()) => 0,
+        Err(_) => -libc::EINVAL,
+    }
+}
+
+unsafe extern "C" fn drop_vtable(userdata: *mut c_void) {
+    drop(Box::from_raw(userdata as *mut Vtable));
+}
+
+/// Serve `interface` at `path` with the methods on `vtable`, which this
+/// takes over whether or not it succeeds. `*slot` holds the object:
+/// `systemd_shim_bus_slot_unref` removes it.
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_add_object_vtable(
+    bus: *mut raw::sd_bus,
+    slot: *mut *mut raw::sd_bus_slot,
+    path: *const c_char,
+    interface: *const c_char,
+    vtable: *mut Vtable,
+) -> c_int {
+    if vtable.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut vtable = Box::from_raw(vtable);
+    if slot.is_null() {
+        return -libc::EINVAL;
+    }
+    let table = vtable.table();
+    let vtable = Box::into_raw(vtable);
+    let r = raw::sd_bus_add_object_vtable(bus, slot, path, interface, table, vtable as *mut c_void);
+    if r < 0 {
+        drop(Box::from_raw(vtable));
+        return r;
+    }
+    raw::sd_bus_slot_set_destroy_callback(*slot, Some(drop_vtable));
+    r
+}
+
+/// Start the reply to `call`, for a result to be appended to and sent
+/// with `systemd_shim_bus_send`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_new_method_return(
+    call: *mut raw::sd_bus_message,
+    reply: *mut *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_message_new_method_return(call, reply)
+}
+
+/// Send `m` without waiting for any answer; a NULL `bus` sends it on the
+/// connection it was made for
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_send(
+    bus: *mut raw::sd_bus,
+    m: *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_send(bus, m, ptr::null_mut())
+}
+
+/// Reply to `call` with no result
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_reply_method_return(
+    call: *mut raw::sd_bus_message,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_message_new_method_return(call, &mut reply);
+    if r < 0 {
+        return r;
+    }
+    let r = raw::sd_bus_send(ptr::null_mut(), reply, ptr::null_mut());
+    raw::sd_bus_message_unref(reply);
+    r
+}
+
+/// Reply to `call` with the D-Bus error `name`, e.g.
+/// `org.freedesktop.DBus.Error.InvalidArgs`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_reply_method_error(
+    call: *mut raw::sd_bus_message,
+    name: *const c_char,
+    message: *const c_char,
+) -> c_int {
+    let error = raw::sd_bus_error {
+        name,
+        message,
+        need_free: 0,
+    };
+    raw::sd_bus_reply_method_error(call, &error)
+}
+
+/// Start signal `member` of `interface` from the object at `path`, for
+/// its arguments to be appended and sent with `systemd_shim_bus_send`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_message_new_signal(
+    bus: *mut raw::sd_bus,
+    m: *mut *mut raw::sd_bus_message,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+) -> c_int {
+    raw::sd_bus_message_new_signal(bus, m, path, interface, member)
+}
+
+/// Broadcast signal `member`, which carries no arguments
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_emit_signal(
+    bus: *mut raw::sd_bus,
+    path: *const c_char,
+    interface: *const c_char,
+    member: *const c_char,
+) -> c_int {
+    let mut m = ptr::null_mut();
+    let r = raw::sd_bus_message_new_signal(bus, &mut m, path, interface, member);
+    if r < 0 {
+        return r;
+    }
+    let r = raw::sd_bus_send(bus, m, ptr::null_mut());
+    raw::sd_bus_message_unref(m);
+    r
+}
+
+/// Send `PropertiesChanged` for the NULL-terminated `names`, properties
+/// of `interface` registered at `path`, with their current values; NULL
+/// or empty `names` announces them all
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_emit_properties_changed(
+    bus: *mut raw::sd_bus,
+    path: *const c_char,
+    interface: *const c_char,
+    names: *const *const c_char,
+) -> c_int {
+    // sd-bus announces nothing for an empty list, and everything for NULL
+    let names = if !names.is_null() && (*names).is_null() {
+        ptr::null()
+    } else {
+        names
+    };
+    raw::sd_bus_emit_properties_changed_strv(bus, path, interface, names as *mut *mut c_char)
+}
+
+/// Called from `systemd_shim_bus_process` once the last name a tracker
+/// follows has left the bus or been removed. Return 1 when done with it:
+/// while the tracker stays empty, sd-bus calls again after a 0, and logs
+/// a negative errno and gives up
+pub type TrackHandler =
+    unsafe extern "C" fn(track: *mut raw::sd_bus_track, userdata: *mut c_void) -> c_int;
+
+/// A tracker follows the clients of a service, by unique or well-known
+/// name, and calls `handler` with `userdata` when none is left, e.g. so
+/// the service can exit. `handler` may be NULL to only count them.
+/// Release it with `systemd_shim_bus_track_unref`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_new(
+    bus: *mut raw::sd_bus,
+    track: *mut *mut raw::sd_bus_track,
+    handler: Option<TrackHandler>,
+    userdata: *mut c_void,
+) -> c_int {
+    raw::sd_bus_track_new(bus, track, handler, userdata)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_unref(
+    track: *mut raw::sd_bus_track,
+) -> *mut raw::sd_bus_track {
+    raw::sd_bus_track_unref(track)
+}
+
+/// Follow `name` until it leaves the bus; adding a name already followed
+/// changes nothing
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_add_name(
+    track: *mut raw::sd_bus_track,
+    name: *const c_char,
+) -> c_int {
+    raw::sd_bus_track_add_name(track, name)
+}
+
+/// Follow the sender of `m`, typically a method call from a new client
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_add_sender(
+    track: *mut raw::sd_bus_track,
+    m: *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_track_add_sender(track, m)
+}
+
+/// Stop following `name`, as when a client says goodbye; the handler
+/// runs if it was the last
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_remove_name(
+    track: *mut raw::sd_bus_track,
+    name: *const c_char,
+) -> c_int {
+    raw::sd_bus_track_remove_name(track, name)
+}
+
+/// How many names the tracker follows
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_count(track: *mut raw::sd_bus_track) -> c_uint {
+    raw::sd_bus_track_count(track)
+}
+
+/// 1 if the tracker follows `name`, else 0
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_track_contains(
+    track: *mut raw::sd_bus_track,
+    name: *const c_char,
+) -> c_int {
+    c_int::from(!raw::sd_bus_track_contains(track, name).is_null())
+}
+
+/// Fill `error` with copies of `name` and `message`; returns the negative
+/// errno the name maps to, for a method handler to return
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_error_set(
+    error: *mut raw::sd_bus_error,
+    name: *const c_char,
+    message: *const c_char,
+) -> c_int {
+    raw::sd_bus_error_set(error, name, message)
+}
+
+// =============================================================================
+// Match rules
+// =============================================================================
+//
+// Rules are built on a handle, one setter per filter. A setter refuses an
+// invalid value with -EINVAL and leaves the rule as it was, so a rule
+// that reaches the bus is well formed.
+
+#[no_mangle]
+pub extern "C" fn systemd_shim_match_new() -> *mut MatchRule {
+    Box::into_raw(Box::new(MatchRule::new()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_free(rule: *mut MatchRule) {
+    if !rule.is_null() {
+        drop(Box::from_raw(rule));
+    }
+}
+
+unsafe fn set_match(
+    rule: *mut MatchRule,
+    value: *const c_char,
+    check: fn(&str) -> Result<(), String>,
+    set: fn(MatchRule, &str) -> MatchRule,
+) -> c_int {
+    let (Some(rule), Some(value)) = (rule.as_mut(), str_arg(value)) else {
+        return -libc::EINVAL;
+    };
+    if check(value).is_err() {
+        return -libc::EINVAL;
+    }
+    *rule = set(std::mem::take(rule), value);
+    0
+}
+
+/// `signal`, `method_call`, `method_return` or `error`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_type(
+    rule: *mut MatchRule,
+    kind: *const c_char,
+) -> c_int {
+    set_match(rule, kind, match_rule::check_type, MatchRule::kind)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_sender(
+    rule: *mut MatchRule,
+    sender: *const c_char,
+) -> c_int {
+    set_match(rule, sender, match_rule::check_bus_name, MatchRule::sender)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_interface(
+    rule: *mut MatchRule,
+    interface: *const c_char,
+) -> c_int {
+    set_match(
+        rule,
+        interface,
+        match_rule::check_interface,
+        MatchRule::interface,
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_member(
+    rule: *mut MatchRule,
+    member: *const c_char,
+) -> c_int {
+    set_match(rule, member, match_rule::check_member, MatchRule::member)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_path(
+    rule: *mut MatchRule,
+    path: *const c_char,
+) -> c_int {
+    set_match(rule, path, match_rule::check_path, MatchRule::path)
+}
+
+/// Objects at and below `path`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_path_namespace(
+    rule: *mut MatchRule,
+    path: *const c_char,
+) -> c_int {
+    set_match(
+        rule,
+        path,
+        match_rule::check_path,
+        MatchRule::path_namespace,
+    )
+}
+
+/// Messages whose string argument `index` (0 to 63) is `value`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_set_arg(
+    rule: *mut MatchRule,
+    index: c_uint,
+    value: *const c_char,
+) -> c_int {
+    let (Some(rule), Some(value)) = (rule.as_mut(), str_arg(value)) else {
+        return -libc::EINVAL;
+    };
+    let Ok(index) = u8::try_from(index) else {
+        return -libc::EINVAL;
+    };
+    if match_rule::check_arg(index, value).is_err() {
+        return -libc::EINVAL;
+    }
+    *rule = std::mem::take(rule).arg(index, value);
+    0
+}
+
+/// The rule as text in `*ret`; free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_match_to_string(
+    rule: *const MatchRule,
+    ret: *mut *mut c_char,
+) -> c_int {
+    match rule.as_ref().map(MatchRule::rule) {
+        Some(Ok(text)) => give_string(&text, ret),
+        _ => -libc::EINVAL,
+    }
+}
+
+/// `systemd_shim_bus_add_match` for a rule built on a handle, which the
+/// caller still owns
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_add_match_rule(
+    bus: *mut raw::sd_bus,
+    slot: *mut *mut raw::sd_bus_slot,
+    rule: *const MatchRule,
+    handler: SignalHandler,
+    userdata: *mut c_void,
+) -> c_int {
+    let Some(Ok(text)) = rule.as_ref().map(MatchRule::rule) else {
+        return -libc::EINVAL;
+    };
+    let Ok(text) = CString::new(text) else {
+        return -libc::EINVAL;
+    };
+    systemd_shim_bus_add_match(bus, slot, text.as_ptr(), handler, userdata)
+}
+
+/// Dispatch one pending message to its handler; returns > 0 when there
+/// may be more to process, 0 when the bus is idle
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_process(bus: *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_process(bus, ptr::null_mut())
+}
+
+/// Block until the bus has something to process or `timeout_usec` passes;
+/// `u64::MAX` waits indefinitely. Returns > 0 when woken by the bus
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_wait(bus: *mut raw::sd_bus, timeout_usec: u64) -> c_int {
+    raw::sd_bus_wait(bus, timeout_usec)
+}
+
+/// The descriptor to poll for the bus, for callers with their own loop
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_get_fd(bus: *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_get_fd(bus)
+}
+
+/// The poll events (`POLLIN`, `POLLOUT`) to wait for on that descriptor
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_get_events(bus: *mut raw::sd_bus) -> c_int {
+    raw::sd_bus_get_events(bus)
+}
+
+/// The CLOCK_MONOTONIC time in microseconds by which to process the bus
+/// even if the descriptor stays quiet; `u64::MAX` for no deadline
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_bus_get_timeout(
+    bus: *mut raw::sd_bus,
+    timeout_usec: *mut u64,
+) -> c_int {
+    raw::sd_bus_get_timeout(bus, timeout_usec)
+}
+
+// =============================================================================
+// sd-bus monitors
+// =============================================================================
+//
+// A monitor sees every message on the bus, as `busctl monitor` does. It
+// needs a connection of its own, opened in monitor mode, which the bus
+// daemon turns into an eavesdropper with `BecomeMonitor`; from then on
+// it can only receive, and the daemon drops it if it sends anything.
+// Drive it with `systemd_shim_monitor_process`, waiting on
+// `systemd_shim_monitor_bus` in between as for any bus. Seeing traffic
+// that is not yours takes root on the system bus.
+//
+// The monitor asks for message timestamps, and when the transport gives
+// none it notes the time it read each message itself, which
+// `systemd_shim_monitor_received` reports from inside the handler.
+
+/// Called with each message the monitor sees; `m` is only borrowed for
+/// the call. A negative return stops processing and is passed on from
+/// `systemd_shim_monitor_process`.
+pub type MonitorHandler =
+    unsafe extern "C" fn(m: *mut raw::sd_bus_message, userdata: *mut c_void) -> c_int;
+
+pub struct Monitor {
+    bus: *mut raw::sd_bus,
+    handler: MonitorHandler,
+    userdata: *mut c_void,
+    /// CLOCK_REALTIME and CLOCK_MONOTONIC microseconds at which the last
+    /// message handed to the handler arrived, 0 before the first
+    realtime: u64,
+    monotonic: u64,
+}
+
+fn clock_usec(clock: libc::clockid_t) -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    ts.tv_sec as u64 * 1_000_000 + ts.tv_nsec as u64 / 1_000
+}
+
+/// Where the system bus is, as `sd_bus_open_system` finds it
+fn system_bus_address() -> Option<CString> {
+    let address = std::env::var("DBUS_SYSTEM_BUS_ADDRESS")
+        .unwrap_or_else(|_| "unix:path=/run/dbus/system_bus_socket".to_string());
+    CString::new(address).ok()
+}
+
+unsafe fn become_monitor(
+    bus: *mut raw::sd_bus,
+    address: *const c_char,
+    rules: *const *const c_char,
+) -> c_int {
+    let system = system_bus_address();
+    let address = match (address.is_null(), &system) {
+        (false, _) => address,
+        (true, Some(system)) => system.as_ptr(),
+        (true, None) => return -libc::EINVAL,
+    };
+    let mut r = raw::sd_bus_set_address(bus, address);
+    if r >= 0 {
+        r = raw::sd_bus_set_bus_client(bus, 1);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_set_monitor(bus, 1);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_negotiate_timestamp(bus, 1);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_start(bus);
+    }
+    if r < 0 {
+        return r;
+    }
+    let mut m = ptr::null_mut();
+    r = raw::sd_bus_message_new_method_call(
+        bus,
+        &mut m,
+        c!("org.freedesktop.DBus"),
+        c!("/org/freedesktop/DBus"),
+        c!("org.freedesktop.DBus.Monitoring"),
+        c!("BecomeMonitor"),
+    );
+    if r < 0 {
+        return r;
+    }
+    let none = [ptr::null_mut()];
+    let rules = if rules.is_null() {
+        none.as_ptr()
+    } else {
+        rules as *const *mut c_char
+    };
+    r = raw::sd_bus_message_append_strv(m, rules as *mut *mut c_char);
+    if r >= 0 {
+        // flags, of which there are none yet
+        r = raw::sd_bus_message_append(m, c!("u"), 0 as c_uint);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_call(bus, m, 0, ptr::null_mut(), ptr::null_mut());
+    }
+    raw::sd_bus_message_unref(m);
+    r
+}
+
+/// Open a monitor on the bus at `address`, or the system bus if NULL,
+/// that calls `handler` with `userdata` for every message matching one
+/// of the NULL-terminated match `rules`, or every message if `rules` is
+/// NULL. Free it with `systemd_shim_monitor_free`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_monitor_open(
+    address: *const c_char,
+    rules: *const *const c_char,
+    handler: MonitorHandler,
+    userdata: *mut c_void,
+    ret: *mut *mut Monitor,
+) -> c_int {
+    if ret.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut bus = ptr::null_mut();
+    let r = raw::sd_bus_new(&mut bus);
+    if r < 0 {
+        return r;
+    }
+    let r = become_monitor(bus, address, rules);
+    if r < 0 {
+        raw::sd_bus_flush_close_unref(bus);
+        return r;
+    }
+    *ret = Box::into_raw(Box::new(Monitor {
+        bus,
+        handler,
+        userdata,
+        realtime: 0,
+        monotonic: 0,
+    }));
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_monitor_free(monitor: *mut Monitor) {
+    if !monitor.is_null() {
+        let monitor = Box::from_raw(monitor);
+        raw::sd_bus_flush_close_unref(monitor.bus);
+    }
+}
+
+/// The monitor's connection, for `systemd_shim_bus_wait`, `_get_fd` and
+/// the like; it stays the monitor's
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_monitor_bus(monitor: *const Monitor) -> *mut raw::sd_bus {
+    match monitor.as_ref() {
+        Some(monitor) => monitor.bus,
+        None => ptr::null_mut(),
+    }
+}
+
+/// Take in what the connection has pending, handing at most one message
+/// to the handler. Returns > 0 if there may be more to do, so call it
+/// until it returns 0 before waiting
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_monitor_process(monitor: *mut Monitor) -> c_int {
+    let Some(monitor) = monitor.as_mut() else {
+        return -libc::EINVAL;
+    };
+    let mut m = ptr::null_mut();
+    let r = raw::sd_bus_process(monitor.bus, &mut m);
+    if r < 0 || m.is_null() {
+        return r;
+    }
+    if raw::sd_bus_message_get_realtime_usec(m, &mut monitor.realtime) < 0 {
+        monitor.realtime = clock_usec(libc::CLOCK_REALTIME);
+    }
+    if raw::sd_bus_message_get_monotonic_usec(m, &mut monitor.monotonic) < 0 {
+        monitor.monotonic = clock_usec(libc::CLOCK_MONOTONIC);
+    }
+    let handled = (monitor.handler)(m, monitor.userdata);
+    raw::sd_bus_message_unref(m);
+    if handled < 0 {
+        return handled;
+    }
+    r
+}
+
+/// When the message being handled arrived, in CLOCK_REALTIME and
+/// CLOCK_MONOTONIC microseconds, either of which may be NULL: the
+/// transport's stamp if it gave one, or else when the monitor read it.
+/// -ENODATA before any message was handled
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_monitor_received(
+    monitor: *const Monitor,
+    realtime: *mut u64,
+    monotonic: *mut u64,
+) -> c_int {
+    let Some(monitor) = monitor.as_ref() else {
+        return -libc::EINVAL;
+    };
+    if monitor.monotonic == 0 {
+        return -libc::ENODATA;
+    }
+    if let Some(realtime) = realtime.as_mut() {
+        *realtime = monitor.realtime;
+    }
+    if let Some(monotonic) = monotonic.as_mut() {
+        *monotonic = monitor.monotonic;
+    }
+    0
+}
+
+// =============================================================================
+// sd-bus reconnection
+// =============================================================================
+//
+// An sd-bus connection is finished once the bus daemon goes away, as it
+// does when dbus-daemon restarts, and its matches go with it. A
+// reconnecting connection remembers its matches, and when
+// `systemd_shim_reconnect_process` finds the connection lost it opens a
+// new one, installs them again and calls the `reconnected` handler, so
+// that a long-running listener outlives the broker. Calls made while the
+// bus is down fail as on any closed bus. The connection underneath is
+// replaced on each reconnect, so look it up with
+// `systemd_shim_reconnect_bus` for each use instead of keeping it.
+
+/// Called from `systemd_shim_reconnect_process` once a lost connection is
+/// back with its matches installed; `bus` is the new connection, still
+/// the handle's. It is the place to fetch again whatever state was
+/// missed while down. A negative return is passed on from the process
+/// call
+pub type ReconnectHandler =
+    unsafe extern "C" fn(bus: *mut raw::sd_bus, userdata: *mut c_void) -> c_int;
+
+/// How long `systemd_shim_reconnect_wait` sleeps at most while the bus is
+/// down, before the next attempt
+const RECONNECT_RETRY_USEC: u64 = 1_000_000;
+
+struct Resubscription {
+    id: c_int,
+    rule: CString,
+    handler: SignalHandler,
+    userdata: *mut c_void,
+    /// NULL while disconnected
+    slot: *mut raw::sd_bus_slot,
+}
+
+pub struct Reconnecting {
+    /// NULL for the system bus
+    address: Option<CString>,
+    /// NULL while disconnected
+    bus: *mut raw::sd_bus,
+    matches: Vec<Resubscription>,
+    last_id: c_int,
+    reconnected: Option<ReconnectHandler>,
+    userdata: *mut c_void,
+}
+
+impl Reconnecting {
+    unsafe fn connect(&mut self) -> c_int {
+        let mut bus = ptr::null_mut();
+        let r = match &self.address {
+            None => raw::sd_bus_open_system(&mut bus),
+            Some(address) => open_address(address, &mut bus),
+        };
+        if r < 0 {
+            return r;
+        }
+        self.bus = bus;
+        for m in &mut self.matches {
+            let r = systemd_shim_bus_add_match(
+                bus,
+                &mut m.slot,
+                m.rule.as_ptr(),
+                m.handler,
+                m.userdata,
+            );
+            if r < 0 {
+                self.disconnect();
+                return r;
+            }
+        }
+        0
+    }
+
+    unsafe fn disconnect(&mut self) {
+        for m in &mut self.matches {
+            m.slot = raw::sd_bus_slot_unref(m.slot);
+        }
+        if !self.bus.is_null() {
+            raw::sd_bus_flush_close_unref(self.bus);
+            self.bus = ptr::null_mut();
+        }
+    }
+}
+
+unsafe fn open_address(address: &CString, ret: *mut *mut raw::sd_bus) -> c_int {
+    let mut bus = ptr::null_mut();
+    let mut r = raw::sd_bus_new(&mut bus);
+    if r < 0 {
+        return r;
+    }
+    r = raw::sd_bus_set_address(bus, address.as_ptr());
+    if r >= 0 {
+        r = raw::sd_bus_set_bus_client(bus, 1);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_start(bus);
+    }
+    if r < 0 {
+        raw::sd_bus_flush_close_unref(bus);
+        return r;
+    }
+    *ret = bus;
+    0
+}
+
+/// Connect to the bus at `address`, or the system bus if NULL, and keep
+/// reconnecting whenever the connection is lost, calling `reconnected`,
+/// if given, with `userdata` each time it is back. The first connection
+/// has to succeed. Free it with `systemd_shim_reconnect_free`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_open(
+    address: *const c_char,
+    reconnected: Option<ReconnectHandler>,
+    userdata: *mut c_void,
+    ret: *mut *mut Reconnecting,
+) -> c_int {
+    if ret.is_null() {
+        return -libc::EINVAL;
+    }
+    let address = if address.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(address).to_owned())
+    };
+    let mut conn = Box::new(Reconnecting {
+        address,
+        bus: ptr::null_mut(),
+        matches: Vec::new(),
+        last_id: 0,
+        reconnected,
+        userdata,
+    });
+    let r = conn.connect();
+    if r < 0 {
+        return r;
+    }
+    *ret = Box::into_raw(conn);
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_free(conn: *mut Reconnecting) {
+    if !conn.is_null() {
+        Box::from_raw(conn).disconnect();
+    }
+}
+
+/// The current connection, still the handle's, or NULL while the bus is
+/// down
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_bus(conn: *const Reconnecting) -> *mut raw::sd_bus {
+    match conn.as_ref() {
+        Some(conn) => conn.bus,
+        None => ptr::null_mut(),
+    }
+}
+
+/// Install `match_` as `systemd_shim_bus_add_match` would, and again on
+/// every reconnect. Returns an id > 0 for `systemd_shim_reconnect_remove_match`.
+/// While the bus is down the rule is only noted, to install on reconnect
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_add_match(
+    conn: *mut Reconnecting,
+    match_: *const c_char,
+    handler: SignalHandler,
+    userdata: *mut c_void,
+) -> c_int {
+    let Some(conn) = conn.as_mut() else {
+        return -libc::EINVAL;
+    };
+    if match_.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut slot = ptr::null_mut();
+    if !conn.bus.is_null() {
+        let r = systemd_shim_bus_add_match(conn.bus, &mut slot, match_, handler, userdata);
+        if r < 0 {
+            return r;
+        }
+    }
+    conn.last_id += 1;
+    conn.matches.push(Resubscription {
+        id: conn.last_id,
+        rule: CStr::from_ptr(match_).to_owned(),
+        handler,
+        userdata,
+        slot,
+    });
+    conn.last_id
+}
+
+/// Drop the match `id`; -ENOENT if there is none
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_remove_match(
+    conn: *mut Reconnecting,
+    id: c_int,
+) -> c_int {
+    let Some(conn) = conn.as_mut() else {
+        return -libc::EINVAL;
+    };
+    let Some(i) = conn.matches.iter().position(|m| m.id == id) else {
+        return -libc::ENOENT;
+    };
+    let m = conn.matches.remove(i);
+    raw::sd_bus_slot_unref(m.slot);
+    0
+}
+
+/// Dispatch one pending message, as `systemd_shim_bus_process` does, and
+/// reconnect once the connection turns out lost. While the bus is down
+/// each call tries to reconnect and returns 0 if it cannot yet; after a
+/// reconnect it returns the `reconnected` handler's result if negative,
+/// else 1
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_process(conn: *mut Reconnecting) -> c_int {
+    let Some(conn) = conn.as_mut() else {
+        return -libc::EINVAL;
+    };
+    if !conn.bus.is_null() {
+        let r = raw::sd_bus_process(conn.bus, ptr::null_mut());
+        if r != -libc::ECONNRESET && r != -libc::ENOTCONN {
+            return r;
+        }
+        conn.disconnect();
+    }
+    if conn.connect() < 0 {
+        return 0;
+    }
+    if let Some(reconnected) = conn.reconnected {
+        let r = reconnected(conn.bus, conn.userdata);
+        if r < 0 {
+            return r;
+        }
+    }
+    1
+}
+
+/// Block until the bus has something to process or `timeout_usec` passes,
+/// as `systemd_shim_bus_wait` does. While the bus is down, sleep until
+/// the next reconnect attempt is due instead and return 0
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_reconnect_wait(
+    conn: *mut Reconnecting,
+    timeout_usec: u64,
+) -> c_int {
+    let Some(conn) = conn.as_ref() else {
+        return -libc::EINVAL;
+    };
+    if !conn.bus.is_null() {
+        return raw::sd_bus_wait(conn.bus, timeout_usec);
+    }
+    let usec = timeout_usec.min(RECONNECT_RETRY_USEC);
+    std::thread::sleep(std::time::Duration::from_micros(usec));
+    0
+}
+
+// =============================================================================
+// systemd units
+// =============================================================================
+//
+// Manager calls on units by name. Each queues a job and hands back its
+// object path in `*job`, to free with `systemd_shim_free_string`; how the
+// job ended arrives later in the manager's `JobRemoved` signal. `mode` is
+// a job mode: `replace`, `fail`, `isolate`, `ignore-dependencies` or
+// `ignore-requirements`.
+
+unsafe fn unit_job(
+    bus: *mut raw::sd_bus,
+    method: *const c_char,
+    unit: *const c_char,
+    mode: *const c_char,
+    error: *mut raw::sd_bus_error,
+    job: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        method,
+        error,
+        &mut reply,
+        c!("ss"),
+        unit,
+        mode,
+    );
+    if r < 0 {
+        return r;
+    }
+    give_path(reply, job)
+}
+
+/// Hand the object path that `reply` holds, such as a job's, to the
+/// caller, and drop `reply`
+unsafe fn give_path(reply: *mut raw::sd_bus_message, ret: *mut *mut c_char) -> c_int {
+    let mut path: *const c_char = ptr::null();
+    let mut r = raw::sd_bus_message_read_basic(
+        reply,
+        b'o' as c_char,
+        &mut path as *mut *const c_char as *mut c_void,
+    );
+    if r >= 0 {
+        r = give_string(&CStr::from_ptr(path).to_string_lossy(), ret);
+    }
+    raw::sd_bus_message_unref(reply);
+    r
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_start(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    mode: *const c_char,
+    error: *mut raw::sd_bus_error,
+    job: *mut *mut c_char,
+) -> c_int {
+    unit_job(bus, c!("StartUnit"), unit, mode, error, job)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_stop(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    mode: *const c_char,
+    error: *mut raw::sd_bus_error,
+    job: *mut *mut c_char,
+) -> c_int {
+    unit_job(bus, c!("StopUnit"), unit, mode, error, job)
+}
+
+/// Restart the unit, starting it if it is not running
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_restart(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    mode: *const c_char,
+    error: *mut raw::sd_bus_error,
+    job: *mut *mut c_char,
+) -> c_int {
+    unit_job(bus, c!("RestartUnit"), unit, mode, error, job)
+}
+
+/// Have the unit reload its configuration; fails for units that cannot
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_reload(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    mode: *const c_char,
+    error: *mut raw::sd_bus_error,
+    job: *mut *mut c_char,
+) -> c_int {
+    unit_job(bus, c!("ReloadUnit"), unit, mode, error, job)
+}
+
+/// Call manager method `member` on `unit`, which returns nothing
+unsafe fn unit_call(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    unit: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        member,
+        error,
+        ptr::null_mut(),
+        c!("s"),
+        unit,
+    )
+}
+
+/// Pause every process of `unit` in the cgroup freezer, so its state can
+/// be looked at without it moving; it stays active meanwhile. Needs
+/// cgroup v2
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_freeze(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    unit_call(bus, c!("FreezeUnit"), unit, error)
+}
+
+/// Resume a unit paused with `systemd_shim_unit_freeze`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_thaw(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    unit_call(bus, c!("ThawUnit"), unit, error)
+}
+
+/// Which of a unit's processes `systemd_shim_unit_kill` signals: its main
+/// process, its control process (one running ExecReload= or the like),
+/// or every process in its cgroup
+pub const KILL_WHO: &[&str] = &["main", "control", "all"];
+
+/// Send `signal` to the processes of `unit` that `who` names. Gives
+/// -EINVAL for any `who` outside `KILL_WHO` or a signal number out of
+/// range, without calling the manager
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_kill(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    who: *const c_char,
+    signal: c_int,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    let Some(whom) = str_arg(who) else {
+        return -libc::EINVAL;
+    };
+    if !KILL_WHO.contains(&whom) || !(1..=libc::SIGRTMAX()).contains(&signal) {
+        return -libc::EINVAL;
+    }
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        c!("KillUnit"),
+        error,
+        ptr::null_mut(),
+        c!("ssi"),
+        unit,
+        who,
+        signal,
+    )
+}
+
+/// Call manager method `member`, which takes and returns nothing
+unsafe fn manager_call(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        member,
+        error,
+        ptr::null_mut(),
+        ptr::null::<c_char>(),
+    )
+}
+
+/// Reread unit files and apply changed ones, as `systemctl daemon-reload`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_manager_reload(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    manager_call(bus, c!("Reload"), error)
+}
+
+/// Restart the manager in place, as `systemctl daemon-reexec`. The
+/// manager goes away instead of replying, so the call counts as done
+/// when the bus daemon answers NoReply for it or, on a direct
+/// connection, when the connection drops and `bus` has to be reopened
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_manager_reexecute(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    let r = manager_call(bus, c!("Reexecute"), error);
+    let no_reply = !error.is_null()
+        && raw::sd_bus_error_has_name(error, c!("org.freedesktop.DBus.Error.NoReply")) > 0;
+    if no_reply || r == -libc::ECONNRESET || r == -libc::ENOTCONN {
+        if !error.is_null() {
+            raw::sd_bus_error_free(error);
+        }
+        return 0;
+    }
+    r
+}
+
+// Transient units: a service or scope that exists only while it runs,
+// built from properties rather than a unit file. A service runs the
+// command set with `systemd_shim_transient_exec_start`; a scope takes in
+// the processes set with `systemd_shim_transient_pids`. Setters refuse
+// an invalid value with -EINVAL and keep the earlier value.
+
+#[no_mangle]
+pub extern "C" fn systemd_shim_transient_new() -> *mut Properties {
+    Box::into_raw(Box::new(Properties::new()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_free(props: *mut Properties) {
+    if !props.is_null() {
+        drop(Box::from_raw(props));
+    }
+}
+
+fn set_result(r: Result<(), String>) -> c_int {
+    match r {
+        Ok(()) => 0,
+        Err(_) => -libc::EINVAL,
+    }
+}
+
+/// Set string property `name`, e.g. `Description` or `ProtectSystem`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_set_string(
+    props: *mut Properties,
+    name: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    let (Some(props), Some(name), Some(value)) = (props.as_mut(), str_arg(name), str_arg(value))
+    else {
+        return -libc::EINVAL;
+    };
+    set_result(props.set_string(name, value))
+}
+
+/// Set bool property `name`, e.g. `RemainAfterExit` or `PrivateTmp`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_set_bool(
+    props: *mut Properties,
+    name: *const c_char,
+    value: c_int,
+) -> c_int {
+    let (Some(props), Some(name)) = (props.as_mut(), str_arg(name)) else {
+        return -libc::EINVAL;
+    };
+    set_result(props.set_bool(name, value != 0))
+}
+
+/// Set 64-bit property `name`, e.g. `RuntimeMaxUSec`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_set_u64(
+    props: *mut Properties,
+    name: *const c_char,
+    value: u64,
+) -> c_int {
+    let (Some(props), Some(name)) = (props.as_mut(), str_arg(name)) else {
+        return -libc::EINVAL;
+    };
+    set_result(props.set_u64(name, value))
+}
+
+/// Run NULL-terminated `argv`, whose first element is an absolute path
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_exec_start(
+    props: *mut Properties,
+    argv: *const *const c_char,
+    ignore_failure: c_int,
+) -> c_int {
+    let (Some(props), Some(argv)) = (props.as_mut(), strv_arg(argv)) else {
+        return -libc::EINVAL;
+    };
+    set_result(props.exec_start(&argv, ignore_failure != 0))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_pids(
+    props: *mut Properties,
+    pids: *const u32,
+    n: usize,
+) -> c_int {
+    let Some(props) = props.as_mut() else {
+        return -libc::EINVAL;
+    };
+    if pids.is_null() && n > 0 {
+        return -libc::EINVAL;
+    }
+    let pids = if n == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(pids, n)
+    };
+    set_result(props.pids(pids))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_memory_max(
+    props: *mut Properties,
+    bytes: u64,
+) -> c_int {
+    let Some(props) = props.as_mut() else {
+        return -libc::EINVAL;
+    };
+    set_result(props.memory_max(bytes))
+}
+
+/// `percent` of one CPU, e.g. 50 for `CPUQuota=50%`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_cpu_quota(
+    props: *mut Properties,
+    percent: c_uint,
+) -> c_int {
+    let Some(props) = props.as_mut() else {
+        return -libc::EINVAL;
+    };
+    set_result(props.cpu_quota(percent))
+}
+
+/// `inactive` or `inactive-or-failed`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_transient_collect_mode(
+    props: *mut Properties,
+    mode: *const c_char,
+) -> c_int {
+    let (Some(props), Some(mode)) = (props.as_mut(), str_arg(mode)) else {
+        return -libc::EINVAL;
+    };
+    set_result(props.collect_mode(mode))
+}
+
+/// Start transient unit `name` (`*.service` or `*.scope`) with `props`,
+/// which stays the caller's, returning the job path in `*job`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_start_transient(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    mode: *const c_char,
+    props: *const Properties,
+    error: *mut raw::sd_bus_error,
+    job: *mut *mut c_char,
+) -> c_int {
+    let Some(props) = props.as_ref() else {
+        return -libc::EINVAL;
+    };
+    let mut m = ptr::null_mut();
+    let mut r = raw::sd_bus_message_new_method_call(
+        bus,
+        &mut m,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        c!("StartTransientUnit"),
+    );
+    if r < 0 {
+        return r;
+    }
+    r = raw::sd_bus_message_append(m, c!("ss"), name, mode);
+    if r >= 0 {
+        r = props.append(m);
+    }
+    // No auxiliary units
+    if r >= 0 {
+        r = raw::sd_bus_message_append(m, c!("a(sa(sv))"), 0 as c_uint);
+    }
+    let mut reply = ptr::null_mut();
+    if r >= 0 {
+        r = raw::sd_bus_call(bus, m, 0, error, &mut reply);
+    }
+    raw::sd_bus_message_unref(m);
+    if r < 0 {
+        return r;
+    }
+    give_path(reply, job)
+}
+
+/// Fields of a `ListUnits` record, in order
+const UNIT_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "load_state",
+    "active_state",
+    "sub_state",
+    "following",
+    "path",
+    "job_id",
+    "job_type",
+    "job_path",
+];
+
+/// Every unit the manager has loaded, as a JSON array in `*ret` of
+/// objects with the fields of `ListUnits`: `name`, `description`,
+/// `load_state`, `active_state`, `sub_state`, `following`, `path`, and
+/// `job_id`, `job_type` and `job_path` for a queued job (0, `""` and `/`
+/// when there is none). Free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_list_json(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        c!("ListUnits"),
+        error,
+        &mut reply,
+        ptr::null::<c_char>(),
+    );
+    if r < 0 {
+        return r;
+    }
+    let units = json::body(reply).map(|v| json::records(v, UNIT_FIELDS));
+    raw::sd_bus_message_unref(reply);
+    json::give(units, ret)
+}
+
+/// Room for the longest unit state name and its NUL, with plenty to spare
+pub const SYSTEMD_SHIM_STATE_LEN: usize = 32;
+
+/// Where a unit stands. The states are NUL-terminated; `main_pid` and
+/// `exec_main_status` are 0 for units other than services, and
+/// `active_enter_timestamp` is in µs since the epoch, 0 if never active
+#[repr(C)]
+pub struct UnitStatus {
+    pub active_state: [c_char; SYSTEMD_SHIM_STATE_LEN],
+    pub sub_state: [c_char; SYSTEMD_SHIM_STATE_LEN],
+    pub load_state: [c_char; SYSTEMD_SHIM_STATE_LEN],
+    pub main_pid: u32,
+    pub exec_main_status: c_int,
+    pub active_enter_timestamp: u64,
+}
+
+/// Copy `value` into `dst`, cut short to fit
+fn copy_state(dst: &mut [c_char; SYSTEMD_SHIM_STATE_LEN], value: Option<&serde_json::Value>) {
+    let value = value.and_then(|v| v.as_str()).unwrap_or_default();
+    let len = value.len().min(SYSTEMD_SHIM_STATE_LEN - 1);
+    for (d, &b) in dst.iter_mut().zip(&value.as_bytes()[..len]) {
+        *d = b as c_char;
+    }
+    dst[len] = 0;
+}
+
+/// Every property of `unit` from a single `GetAll` on its object. The
+/// empty interface asks for those of every interface at once, so the
+/// service or slice ones come in the same reply and all are read at the
+/// same instant
+unsafe fn unit_properties(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> Result<serde_json::Map<String, serde_json::Value>, c_int> {
+    let Some(unit) = str_arg(unit) else {
+        return Err(-libc::EINVAL);
+    };
+    let Ok(path) = bus::unit_path(unit).and_then(|p| Ok(CString::new(p)?)) else {
+        return Err(-libc::EINVAL);
+    };
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        path.as_ptr(),
+        c!("org.freedesktop.DBus.Properties"),
+        c!("GetAll"),
+        error,
+        &mut reply,
+        c!("s"),
+        c!(""),
+    );
+    if r < 0 {
+        return Err(r);
+    }
+    let properties = json::body(reply);
+    raw::sd_bus_message_unref(reply);
+    match properties {
+        Ok(serde_json::Value::Object(properties)) => Ok(properties),
+        Ok(_) => Err(-libc::EBADMSG),
+        Err(e) => Err(-e.raw_os_error().unwrap_or(libc::EBADMSG)),
+    }
+}
+
+/// Fill `*status` for `unit`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_get_status(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    error: *mut raw::sd_bus_error,
+    status: *mut UnitStatus,
+) -> c_int {
+    let Some(status) = status.as_mut() else {
+        return -libc::EINVAL;
+    };
+    let properties = match unit_properties(bus, unit, error) {
+        Ok(properties) => properties,
+        Err(r) => return r,
+    };
+    let number = |name: &str| properties.get(name).and_then(|v| v.as_i64()).unwrap_or(0);
+    copy_state(&mut status.active_state, properties.get("ActiveState"));
+    copy_state(&mut status.sub_state, properties.get("SubState"));
+    copy_state(&mut status.load_state, properties.get("LoadState"));
+    status.main_pid = number("MainPID") as u32;
+    status.exec_main_status = number("ExecMainStatus") as c_int;
+    status.active_enter_timestamp = properties
+        .get("ActiveEnterTimestamp")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    0
+}
+
+/// What a resource figure reads when accounting for it is off, or the
+/// unit has no cgroup
+pub const SYSTEMD_SHIM_NOT_TRACKED: u64 = u64::MAX;
+
+/// What a unit's cgroup is using, each figure `SYSTEMD_SHIM_NOT_TRACKED`
+/// when unknown. IO counts bytes since the unit started
+#[repr(C)]
+pub struct UnitResources {
+    /// Bytes of memory in use
+    pub memory_current: u64,
+    /// CPU time used, in ns
+    pub cpu_usage_nsec: u64,
+    pub tasks_current: u64,
+    pub io_read_bytes: u64,
+    pub io_write_bytes: u64,
+}
+
+/// Fill `*resources` for `unit` from the manager's cgroup accounting,
+/// all read at once
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_get_resources(
+    bus: *mut raw::sd_bus,
+    unit: *const c_char,
+    error: *mut raw::sd_bus_error,
+    resources: *mut UnitResources,
+) -> c_int {
+    let Some(resources) = resources.as_mut() else {
+        return -libc::EINVAL;
+    };
+    let properties = match unit_properties(bus, unit, error) {
+        Ok(properties) => properties,
+        Err(r) => return r,
+    };
+    // Units without a cgroup, such as targets, have none of these
+    let figure = |name: &str| {
+        properties
+            .get(name)
+            .and_then(|v| v.as_u64())
+            .unwrap_or(SYSTEMD_SHIM_NOT_TRACKED)
+    };
+    *resources = UnitResources {
+        memory_current: figure("MemoryCurrent"),
+        cpu_usage_nsec: figure("CPUUsageNSec"),
+        tasks_current: figure("TasksCurrent"),
+        io_read_bytes: figure("IOReadBytes"),
+        io_write_bytes: figure("IOWriteBytes"),
+    };
+    0
+}
+
+// Unit files: whether each starts on boot and the symlinks that make it
+// so. The enable and disable calls take a NULL-terminated list of unit
+// names or paths and report the symlinks changed as JSON objects with
+// `type` (`symlink` or `unlink`), `file` and `destination`. They act on
+// /etc, or on /run when `runtime` is set so the change lasts until
+// reboot. The manager only sees the new links after a daemon reload.
+
+const UNIT_FILE_FIELDS: &[&str] = &["path", "state"];
+const CHANGE_FIELDS: &[&str] = &["type", "file", "destination"];
+
+/// Every unit file the manager knows of, as a JSON array in `*ret` of
+/// objects with `path` and `state` (`enabled`, `disabled`, `static`,
+/// `masked`, ...). Free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_files_list_json(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        c!("ListUnitFiles"),
+        error,
+        &mut reply,
+        ptr::null::<c_char>(),
+    );
+    if r < 0 {
+        return r;
+    }
+    let files = json::body(reply).map(|v| json::records(v, UNIT_FILE_FIELDS));
+    raw::sd_bus_message_unref(reply);
+    json::give(files, ret)
+}
+
+/// Call manager method `member` with `files` and then `flags` as bools
+unsafe fn unit_files_call(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    files: *const *const c_char,
+    flags: &[c_int],
+    error: *mut raw::sd_bus_error,
+    reply: *mut *mut raw::sd_bus_message,
+) -> c_int {
+    if files.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut m = ptr::null_mut();
+    let mut r = raw::sd_bus_message_new_method_call(
+        bus,
+        &mut m,
+        c!("org.freedesktop.systemd1"),
+        c!("/org/freedesktop/systemd1"),
+        c!("org.freedesktop.systemd1.Manager"),
+        member,
+    );
+    if r < 0 {
+        return r;
+    }
+    r = raw::sd_bus_message_append_strv(m, files as *mut *mut c_char);
+    for &flag in flags {
+        if r >= 0 {
+            r = systemd_shim_bus_message_append_bool(m, flag);
+        }
+    }
+    if r >= 0 {
+        r = raw::sd_bus_call(bus, m, 0, error, reply);
+    }
+    raw::sd_bus_message_unref(m);
+    r
+}
+
+/// Enable `files` to start on boot, as JSON in `*ret`:
+/// `{"carries_install_info": bool, "changes": [...]}`. Without install
+/// info the unit has no [Install] section and enabling it changed
+/// nothing. `force` replaces links that point elsewhere
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_files_enable(
+    bus: *mut raw::sd_bus,
+    files: *const *const c_char,
+    runtime: c_int,
+    force: c_int,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = unit_files_call(
+        bus,
+        c!("EnableUnitFiles"),
+        files,
+        &[runtime, force],
+        error,
+        &mut reply,
+    );
+    if r < 0 {
+        return r;
+    }
+    let result = json::read_all(reply).and_then(|values| match <[_; 2]>::try_from(values) {
+        Ok([carries, changes]) => Ok(serde_json::json!({
+            "carries_install_info": carries,
+            "changes": json::records(changes, CHANGE_FIELDS),
+        })),
+        Err(_) => Err(std::io::Error::from_raw_os_error(libc::EBADMSG)),
+    });
+    raw::sd_bus_message_unref(reply);
+    json::give(result, ret)
+}
+
+/// Stop `files` starting on boot, as JSON in `*ret`: `{"changes": [...]}`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_unit_files_disable(
+    bus: *mut raw::sd_bus,
+    files: *const *const c_char,
+    runtime: c_int,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = unit_files_call(
+        bus,
+        c!("DisableUnitFiles"),
+        files,
+        &[runtime],
+        error,
+        &mut reply,
+    );
+    if r < 0 {
+        return r;
+    }
+    let result = json::body(reply)
+        .map(|changes| serde_json::json!({ "changes": json::records(changes, CHANGE_FIELDS) }));
+    raw::sd_bus_message_unref(reply);
+    json::give(result, ret)
+}
+
+// =============================================================================
+// logind
+// =============================================================================
+//
+// Sessions and inhibitor locks on org.freedesktop.login1. Sessions are
+// named by logind's session ID, e.g. `2` or `c1`.
+
+const SESSION_FIELDS: &[&str] = &["id", "uid", "user", "seat", "path"];
+
+/// What an inhibitor lock can hold off; `Inhibit` takes several joined
+/// with `:`
+pub const INHIBIT_WHAT: &[&str] = &[
+    "shutdown",
+    "sleep",
+    "idle",
+    "handle-power-key",
+    "handle-suspend-key",
+    "handle-hibernate-key",
+    "handle-lid-switch",
+];
+
+/// `block` holds the operation off until the lock goes; `delay` only
+/// for logind's InhibitDelayMaxSec
+pub const INHIBIT_MODES: &[&str] = &["block", "delay"];
+
+unsafe fn login_call(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    session: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.login1"),
+        c!("/org/freedesktop/login1"),
+        c!("org.freedesktop.login1.Manager"),
+        member,
+        error,
+        ptr::null_mut(),
+        c!("s"),
+        session,
+    )
+}
+
+/// Every session, as a JSON array in `*ret` of objects with `id`, `uid`,
+/// `user`, `seat` (empty for sessions without one) and `path`. Free it
+/// with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_login_list_sessions_json(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.login1"),
+        c!("/org/freedesktop/login1"),
+        c!("org.freedesktop.login1.Manager"),
+        c!("ListSessions"),
+        error,
+        &mut reply,
+        ptr::null::<c_char>(),
+    );
+    if r < 0 {
+        return r;
+    }
+    let sessions = json::body(reply).map(|v| json::records(v, SESSION_FIELDS));
+    raw::sd_bus_message_unref(reply);
+    json::give(sessions, ret)
+}
+
+/// Ask the session's screen locker to lock it
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_login_lock_session(
+    bus: *mut raw::sd_bus,
+    session: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    login_call(bus, c!("LockSession"), session, error)
+}
+
+/// End the session, killing its processes
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_login_terminate_session(
+    bus: *mut raw::sd_bus,
+    session: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    login_call(bus, c!("TerminateSession"), session, error)
+}
+
+/// Take an inhibitor lock on `what` (one or more of `INHIBIT_WHAT`, joined
+/// with `:`), shown as held by `who` for `why`. The lock lasts as long as
+/// the file descriptor put in `*fd`, which the caller closes to release
+/// it. Gives -EINVAL for an unknown `what` or `mode` without asking logind
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_login_inhibit(
+    bus: *mut raw::sd_bus,
+    what: *const c_char,
+    who: *const c_char,
+    why: *const c_char,
+    mode: *const c_char,
+    error: *mut raw::sd_bus_error,
+    fd: *mut c_int,
+) -> c_int {
+    let (Some(whats), Some(kind)) = (str_arg(what), str_arg(mode)) else {
+        return -libc::EINVAL;
+    };
+    if fd.is_null()
+        || !whats.split(':').all(|w| INHIBIT_WHAT.contains(&w))
+        || !INHIBIT_MODES.contains(&kind)
+    {
+        return -libc::EINVAL;
+    }
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.login1"),
+        c!("/org/freedesktop/login1"),
+        c!("org.freedesktop.login1.Manager"),
+        c!("Inhibit"),
+        error,
+        &mut reply,
+        c!("ssss"),
+        what,
+        who,
+        why,
+        mode,
+    );
+    if r < 0 {
+        return r;
+    }
+    let r = systemd_shim_bus_message_read_fd(reply, fd);
+    raw::sd_bus_message_unref(reply);
+    match r {
+        0 => -libc::EBADMSG,
+        r if r < 0 => r,
+        _ => 0,
+    }
+}
+
+// =============================================================================
+// hostnamed
+// =============================================================================
+//
+// The host's names on org.freedesktop.hostname1. The kernel's (transient)
+// hostname and the static one in /etc/hostname usually match; when they
+// differ, something set the transient one behind hostnamed's back, or
+// DHCP did. Setters never wait on a polkit prompt.
+
+/// Properties `systemd_shim_hostname_get_json` reports, and their keys
+const HOSTNAME_FIELDS: &[(&str, &str)] = &[
+    ("Hostname", "hostname"),
+    ("StaticHostname", "static_hostname"),
+    ("PrettyHostname", "pretty_hostname"),
+    ("Chassis", "chassis"),
+    ("Deployment", "deployment"),
+];
+
+/// The host's names, chassis (`desktop`, `laptop`, `server`, `vm`, ...)
+/// and deployment (`production`, `development`, ...), as a JSON object
+/// in `*ret` keyed `hostname`, `static_hostname`, `pretty_hostname`,
+/// `chassis` and `deployment`, null where this hostnamed has no such
+/// property. Free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_hostname_get_json(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.hostname1"),
+        c!("/org/freedesktop/hostname1"),
+        c!("org.freedesktop.DBus.Properties"),
+        c!("GetAll"),
+        error,
+        &mut reply,
+        c!("s"),
+        c!("org.freedesktop.hostname1"),
+    );
+    if r < 0 {
+        return r;
+    }
+    let names = json::body(reply).map(|all| {
+        let names = HOSTNAME_FIELDS.iter().map(|(property, key)| {
+            let value = all.get(property).cloned().unwrap_or_default();
+            (key.to_string(), value)
+        });
+        serde_json::Value::Object(names.collect())
+    });
+    raw::sd_bus_message_unref(reply);
+    json::give(names, ret)
+}
+
+unsafe fn hostname_set(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.hostname1"),
+        c!("/org/freedesktop/hostname1"),
+        c!("org.freedesktop.hostname1"),
+        member,
+        error,
+        ptr::null_mut(),
+        c!("sb"),
+        name,
+        // interactive
+        0 as c_int,
+    )
+}
+
+/// Set the transient hostname, the kernel's
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_hostname_set(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    hostname_set(bus, c!("SetHostname"), name, error)
+}
+
+/// Set the hostname in /etc/hostname; an empty one removes it
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_hostname_set_static(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    hostname_set(bus, c!("SetStaticHostname"), name, error)
+}
+
+/// Set the free-form name shown to people, e.g. "Ada's Laptop"
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_hostname_set_pretty(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    hostname_set(bus, c!("SetPrettyHostname"), name, error)
+}
+
+// =============================================================================
+// timedated
+// =============================================================================
+//
+// Time zone and network time sync on org.freedesktop.timedate1. The bool
+// queries return 1 or 0, or a negative errno. Setters never wait on a
+// polkit prompt.
+
+unsafe fn timedate_bool(
+    bus: *mut raw::sd_bus,
+    property: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    let mut value: c_int = 0;
+    let r = raw::sd_bus_get_property_trivial(
+        bus,
+        c!("org.freedesktop.timedate1"),
+        c!("/org/freedesktop/timedate1"),
+        c!("org.freedesktop.timedate1"),
+        property,
+        error,
+        b'b' as c_char,
+        &mut value as *mut c_int as *mut c_void,
+    );
+    if r < 0 {
+        return r;
+    }
+    c_int::from(value != 0)
+}
+
+/// The system time zone, e.g. `Europe/Berlin`, in `*ret`; free it with
+/// `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_timedate_get_timezone(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    raw::sd_bus_get_property_string(
+        bus,
+        c!("org.freedesktop.timedate1"),
+        c!("/org/freedesktop/timedate1"),
+        c!("org.freedesktop.timedate1"),
+        c!("Timezone"),
+        error,
+        ret,
+    )
+}
+
+/// Set the system time zone to one of the tz database's names
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_timedate_set_timezone(
+    bus: *mut raw::sd_bus,
+    timezone: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.timedate1"),
+        c!("/org/freedesktop/timedate1"),
+        c!("org.freedesktop.timedate1"),
+        c!("SetTimezone"),
+        error,
+        ptr::null_mut(),
+        c!("sb"),
+        timezone,
+        // interactive
+        0 as c_int,
+    )
+}
+
+/// Whether network time sync is switched on
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_timedate_get_ntp(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    timedate_bool(bus, c!("NTP"), error)
+}
+
+/// Switch network time sync on or off; timedated enables and starts, or
+/// stops and disables, the NTP service it manages
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_timedate_set_ntp(
+    bus: *mut raw::sd_bus,
+    enable: c_int,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.timedate1"),
+        c!("/org/freedesktop/timedate1"),
+        c!("org.freedesktop.timedate1"),
+        c!("SetNTP"),
+        error,
+        ptr::null_mut(),
+        c!("bb"),
+        c_int::from(enable != 0),
+        // interactive
+        0 as c_int,
+    )
+}
+
+/// Whether the kernel reports the clock as synchronized, by NTP or
+/// otherwise
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_timedate_ntp_synchronized(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    timedate_bool(bus, c!("NTPSynchronized"), error)
+}
+
+// =============================================================================
+// machined
+// =============================================================================
+//
+// Containers and VMs registered with org.freedesktop.machine1, such as
+// systemd-nspawn containers and libvirt guests, by machine name. The
+// host itself is `.host`.
+
+const MACHINE_FIELDS: &[&str] = &["name", "class", "service", "path"];
+
+unsafe fn machine_call(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+    reply: *mut *mut raw::sd_bus_message,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.machine1"),
+        c!("/org/freedesktop/machine1"),
+        c!("org.freedesktop.machine1.Manager"),
+        member,
+        error,
+        reply,
+        c!("s"),
+        name,
+    )
+}
+
+/// Every registered machine, as a JSON array in `*ret` of objects with
+/// `name`, `class` (`container` or `vm`), `service` (what registered it,
+/// e.g. `systemd-nspawn`) and `path`. Free it with
+/// `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_machine_list_json(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.machine1"),
+        c!("/org/freedesktop/machine1"),
+        c!("org.freedesktop.machine1.Manager"),
+        c!("ListMachines"),
+        error,
+        &mut reply,
+        ptr::null::<c_char>(),
+    );
+    if r < 0 {
+        return r;
+    }
+    let machines = json::body(reply).map(|v| json::records(v, MACHINE_FIELDS));
+    raw::sd_bus_message_unref(reply);
+    json::give(machines, ret)
+}
+
+/// The object path of machine `name` in `*path`, to free with
+/// `systemd_shim_free_string`; `org.freedesktop.machine1.NoSuchMachine`
+/// if there is none
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_machine_get(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+    path: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = machine_call(bus, c!("GetMachine"), name, error, &mut reply);
+    if r < 0 {
+        return r;
+    }
+    give_path(reply, path)
+}
+
+/// The IP addresses of machine `name`'s network interfaces, as a JSON
+/// array in `*ret` of objects with `family` (`inet` or `inet6`) and
+/// `address` as text. Free it with `systemd_shim_free_string`. Only
+/// containers with their own network namespace have any to report
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_machine_addresses_json(
+    bus: *mut raw::sd_bus,
+    name: *const c_char,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let mut reply = ptr::null_mut();
+    let r = machine_call(bus, c!("GetMachineAddresses"), name, error, &mut reply);
+    if r < 0 {
+        return r;
+    }
+    let addresses = json::read_all(reply).and_then(|mut values| match values.pop() {
+        Some(serde_json::Value::Array(rows)) if values.is_empty() => rows
+            .into_iter()
+            .map(|row| json::ip_address(&row[0], &row[1]))
+            .collect::<std::io::Result<Vec<_>>>()
+            .map(serde_json::Value::Array),
+        _ => Err(std::io::Error::from_raw_os_error(libc::EBADMSG)),
+    });
+    raw::sd_bus_message_unref(reply);
+    json::give(addresses, ret)
+}
+
+// =============================================================================
+// resolved
+// =============================================================================
+//
+// Lookups through systemd-resolved on org.freedesktop.resolve1, with its
+// cache, DNSSEC checks and per-link DNS servers, as `resolvectl` does
+// them. `ifindex` keeps a lookup to one network interface, 0 for any;
+// `family` is `AF_INET`, `AF_INET6`, or `AF_UNSPEC` for both. `flags`
+// takes resolved's `SD_RESOLVED_*` bits, 0 for its defaults. A lookup
+// that finds nothing fails with a resolve1 error such as
+// `org.freedesktop.resolve1.DnsError.NXDOMAIN`.
+
+const RESOLVED_NAME_FIELDS: &[&str] = &["ifindex", "name"];
+
+unsafe fn resolve_call(
+    bus: *mut raw::sd_bus,
+    member: *const c_char,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.resolve1"),
+        c!("/org/freedesktop/resolve1"),
+        c!("org.freedesktop.resolve1.Manager"),
+        member,
+        error,
+        ptr::null_mut(),
+        ptr::null::<c_char>(),
+    )
+}
+
+/// `a(iiay)` addresses as objects with `ifindex`, `family` and `address`
+fn resolved_addresses(rows: serde_json::Value) -> std::io::Result<serde_json::Value> {
+    let serde_json::Value::Array(rows) = rows else {
+        return Err(std::io::Error::from_raw_os_error(libc::EBADMSG));
+    };
+    rows.into_iter()
+        .map(|row| {
+            let mut address = json::ip_address(&row[1], &row[2])?;
+            address["ifindex"] = row[0].clone();
+            Ok(address)
+        })
+        .collect::<std::io::Result<Vec<_>>>()
+        .map(serde_json::Value::Array)
+}
+
+/// The addresses of host `name`, as JSON in `*ret`: `{"addresses":
+/// [{"ifindex", "family", "address"}, ...], "name", "flags"}`, where
+/// `name` is the canonical name after following CNAMEs and `flags` says
+/// how the answer was come by. Free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_resolve_hostname_json(
+    bus: *mut raw::sd_bus,
+    ifindex: c_int,
+    name: *const c_char,
+    family: c_int,
+    flags: u64,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    if name.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.resolve1"),
+        c!("/org/freedesktop/resolve1"),
+        c!("org.freedesktop.resolve1.Manager"),
+        c!("ResolveHostname"),
+        error,
+        &mut reply,
+        c!("isit"),
+        ifindex,
+        name,
+        family,
+        flags,
+    );
+    if r < 0 {
+        return r;
+    }
+    let result = json::read_all(reply).and_then(|values| match <[_; 3]>::try_from(values) {
+        Ok([addresses, name, flags]) => Ok(serde_json::json!({
+            "addresses": resolved_addresses(addresses)?,
+            "name": name,
+            "flags": flags,
+        })),
+        Err(_) => Err(std::io::Error::from_raw_os_error(libc::EBADMSG)),
+    });
+    raw::sd_bus_message_unref(reply);
+    json::give(result, ret)
+}
+
+/// The names `address` (IPv4 or IPv6, as text) resolves back to, as JSON
+/// in `*ret`: `{"names": [{"ifindex", "name"}, ...], "flags"}`. Free it
+/// with `systemd_shim_free_string`. -EINVAL if `address` is not an
+/// address
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_resolve_address_json(
+    bus: *mut raw::sd_bus,
+    ifindex: c_int,
+    address: *const c_char,
+    flags: u64,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let Some(Ok(address)) = str_arg(address).map(str::parse::<std::net::IpAddr>) else {
+        return -libc::EINVAL;
+    };
+    let (family, bytes) = match address {
+        std::net::IpAddr::V4(a) => (libc::AF_INET, a.octets().to_vec()),
+        std::net::IpAddr::V6(a) => (libc::AF_INET6, a.octets().to_vec()),
+    };
+    let mut m = ptr::null_mut();
+    let mut r = raw::sd_bus_message_new_method_call(
+        bus,
+        &mut m,
+        c!("org.freedesktop.resolve1"),
+        c!("/org/freedesktop/resolve1"),
+        c!("org.freedesktop.resolve1.Manager"),
+        c!("ResolveAddress"),
+    );
+    if r < 0 {
+        return r;
+    }
+    r = raw::sd_bus_message_append(m, c!("ii"), ifindex, family);
+    if r >= 0 {
+        r = raw::sd_bus_message_append_array(
+            m,
+            b'y' as c_char,
+            bytes.as_ptr() as *const c_void,
+            bytes.len(),
+        );
+    }
+    if r >= 0 {
+        r = raw::sd_bus_message_append(m, c!("t"), flags);
+    }
+    let mut reply = ptr::null_mut();
+    if r >= 0 {
+        r = raw::sd_bus_call(bus, m, 0, error, &mut reply);
+    }
+    raw::sd_bus_message_unref(m);
+    if r < 0 {
+        return r;
+    }
+    let result = json::read_all(reply).and_then(|values| match <[_; 2]>::try_from(values) {
+        Ok([names, flags]) => Ok(serde_json::json!({
+            "names": json::records(names, RESOLVED_NAME_FIELDS),
+            "flags": flags,
+        })),
+        Err(_) => Err(std::io::Error::from_raw_os_error(libc::EBADMSG)),
+    });
+    raw::sd_bus_message_unref(reply);
+    json::give(result, ret)
+}
+
+/// A DNS-SD or SRV service lookup, as JSON in `*ret`: `{"services":
+/// [{"priority", "weight", "port", "hostname", "addresses",
+/// "canonical_hostname"}, ...], "txt": [...], "name", "type", "domain",
+/// "flags"}`. Give `type` (e.g. `_http._tcp`) and `domain` for an SRV
+/// lookup, plus `name` for a DNS-SD instance, else `name` NULL or `""`.
+/// Free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_resolve_service_json(
+    bus: *mut raw::sd_bus,
+    ifindex: c_int,
+    name: *const c_char,
+    type_: *const c_char,
+    domain: *const c_char,
+    family: c_int,
+    flags: u64,
+    error: *mut raw::sd_bus_error,
+    ret: *mut *mut c_char,
+) -> c_int {
+    if type_.is_null() || domain.is_null() {
+        return -libc::EINVAL;
+    }
+    let name = if name.is_null() { c!("") } else { name };
+    let mut reply = ptr::null_mut();
+    let r = raw::sd_bus_call_method(
+        bus,
+        c!("org.freedesktop.resolve1"),
+        c!("/org/freedesktop/resolve1"),
+        c!("org.freedesktop.resolve1.Manager"),
+        c!("ResolveService"),
+        error,
+        &mut reply,
+        c!("isssit"),
+        ifindex,
+        name,
+        type_,
+        domain,
+        family,
+        flags,
+    );
+    if r < 0 {
+        return r;
+    }
+    let result = json::read_all(reply).and_then(|values| match <[_; 6]>::try_from(values) {
+        Ok([services, txt, name, kind, domain, flags]) => {
+            let services = match services {
+                serde_json::Value::Array(rows) => rows
+                    .into_iter()
+                    .map(|row| {
+                        Ok(serde_json::json!({
+                            "priority": row[0],
+                            "weight": row[1],
+                            "port": row[2],
+                            "hostname": row[3],
+                            "addresses": resolved_addresses(row[4].clone())?,
+                            "canonical_hostname": row[5],
+                        }))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()?,
+                _ => return Err(std::io::Error::from_raw_os_error(libc::EBADMSG)),
+            };
+            // TXT strings are bytes; they are text in practice
+            let txt: Vec<String> = match txt {
+                serde_json::Value::Array(items) => items
+                    .iter()
+                    .map(|item| {
+                        let bytes: Vec<u8> = item
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                            .collect();
+                        String::from_utf8_lossy(&bytes).into_owned()
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            Ok(serde_json::json!({
+                "services": services,
+                "txt": txt,
+                "name": name,
+                "type": kind,
+                "domain": domain,
+                "flags": flags,
+            }))
+        }
+        Err(_) => Err(std::io::Error::from_raw_os_error(libc::EBADMSG)),
+    });
+    raw::sd_bus_message_unref(reply);
+    json::give(result, ret)
+}
+
+/// Drop everything resolved has cached, as after changing DNS servers
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_resolve_flush_caches(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    resolve_call(bus, c!("FlushCaches"), error)
+}
+
+/// Zero resolved's transaction, cache and DNSSEC counters
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_resolve_reset_statistics(
+    bus: *mut raw::sd_bus,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    resolve_call(bus, c!("ResetStatistics"), error)
+}
+
+// =============================================================================
+// sd-journal shim functions
+// =============================================================================
+
+/// One step in building a journal's matches
+#[derive(Clone)]
+enum Term {
+    Match(Vec<u8>),
+    Disjunction,
+    Conjunction,
+}
+
+/// The matches added through these functions to each open journal, by
+/// handle. sd-journal cannot list a journal's matches, and a call that
+/// needs its own, such as `systemd_shim_journal_list_boots_json`, puts
+/// the caller's back from here
+static MATCHES: std::sync::Mutex<std::collections::BTreeMap<usize, Vec<Term>>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+/// Note `term` against `journal` once sd-journal has taken it
+fn record(journal: *mut raw::sd_journal, r: c_int, term: Term) -> c_int {
+    if r >= 0 {
+        let mut matches = MATCHES.lock().unwrap_or_else(|e| e.into_inner());
+        matches.entry(journal as usize).or_default().push(term);
+    }
+    r
+}
+
+fn forget(journal: *mut raw::sd_journal) {
+    let mut matches = MATCHES.lock().unwrap_or_else(|e| e.into_inner());
+    matches.remove(&(journal as usize));
+}
+
+/// Flush `journal`'s matches and add back the ones recorded for it
+unsafe fn restore_matches(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_flush_matches(journal);
+    let terms = {
+        let matches = MATCHES.lock().unwrap_or_else(|e| e.into_inner());
+        matches
+            .get(&(journal as usize))
+            .cloned()
+            .unwrap_or_default()
+    };
+    for term in terms {
+        let r = match term {
+            Term::Match(m) => {
+                raw::sd_journal_add_match(journal, m.as_ptr() as *const libc::c_void, m.len())
+            }
+            Term::Disjunction => raw::sd_journal_add_disjunction(journal),
+            Term::Conjunction => raw::sd_journal_add_conjunction(journal),
+        };
+        if r < 0 {
+            return r;
+        }
+    }
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_open(
+    journal: *mut *mut raw::sd_journal,
+    flags: c_int,
+) -> c_int {
+    raw::sd_journal_open(journal, flags)
+}
+
+/// Open the journal files in `path` instead of this machine's, say ones
+/// copied off another machine; with `SYSTEMD_SHIM_JOURNAL_OS_ROOT` in
+/// `flags`, `path` is the root of a system, such as a rescue disk mounted
+/// there, whose /var/log/journal and /run/log/journal to read
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_open_directory(
+    journal: *mut *mut raw::sd_journal,
+    path: *const c_char,
+    flags: c_int,
+) -> c_int {
+    raw::sd_journal_open_directory(journal, path, flags)
+}
+
+/// Open just the journal files named in the NULL-terminated `paths`;
+/// `flags` must be 0
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_open_files(
+    journal: *mut *mut raw::sd_journal,
+    paths: *const *const c_char,
+    flags: c_int,
+) -> c_int {
+    raw::sd_journal_open_files(journal, paths, flags)
+}
+
+/// For `systemd_shim_journal_open_directory`: the directory is a system's
+/// root rather than one holding journal files
+pub const SYSTEMD_SHIM_JOURNAL_OS_ROOT: c_int = 1 << 4;
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_close(journal: *mut raw::sd_journal) {
+    forget(journal);
+    raw::sd_journal_close(journal)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_add_match(
+    journal: *mut raw::sd_journal,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    let r = raw::sd_journal_add_match(journal, data as *const libc::c_void, len);
+    if r < 0 {
+        return r;
+    }
+    // A length of 0 means `data` is NUL-terminated
+    let m = match len {
+        0 => CStr::from_ptr(data as *const c_char).to_bytes(),
+        _ => std::slice::from_raw_parts(data, len),
+    };
+    record(journal, r, Term::Match(m.to_vec()))
+}
+
+// Matches combine as sd-journal's do: those on the same field are OR-ed
+// and different fields AND-ed; a disjunction ORs what follows with the
+// terms since the last conjunction, and a conjunction ANDs what follows
+// with everything before. "(unit A OR unit B) AND priority 3" is
+// `_SYSTEMD_UNIT=a`, `_SYSTEMD_UNIT=b`, conjunction, `PRIORITY=3`.
+
+/// Start a new term, OR-ed with the matches added since the last
+/// conjunction
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_add_disjunction(
+    journal: *mut raw::sd_journal,
+) -> c_int {
+    record(
+        journal,
+        raw::sd_journal_add_disjunction(journal),
+        Term::Disjunction,
+    )
+}
+
+/// Start a new term, AND-ed with all the matches added so far
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_add_conjunction(
+    journal: *mut raw::sd_journal,
+) -> c_int {
+    record(
+        journal,
+        raw::sd_journal_add_conjunction(journal),
+        Term::Conjunction,
+    )
+}
+
+/// Drop every match, to read all entries again
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_flush_matches(journal: *mut raw::sd_journal) {
+    forget(journal);
+    raw::sd_journal_flush_matches(journal)
+}
+
+/// Move before the oldest entry, for `systemd_shim_journal_next` to read
+/// forward from the start
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_seek_head(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_seek_head(journal)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_seek_tail(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_seek_tail(journal)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_previous(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_previous(journal)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_next(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_next(journal)
+}
+
+/// Steps back `skip` entries in one call, for paging; returns how many it
+/// moved, fewer when the start came first, and `-ERANGE` past `INT_MAX`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_previous_skip(
+    journal: *mut raw::sd_journal,
+    skip: u64,
+) -> c_int {
+    raw::sd_journal_previous_skip(journal, skip)
+}
+
+/// Steps forward `skip` entries in one call; as
+/// `systemd_shim_journal_previous_skip`, fewer when the end came first
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_next_skip(
+    journal: *mut raw::sd_journal,
+    skip: u64,
+) -> c_int {
+    raw::sd_journal_next_skip(journal, skip)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_data(
+    journal: *mut raw::sd_journal,
+    field: *const c_char,
+    data: *mut *const u8,
+    len: *mut usize,
+) -> c_int {
+    raw::sd_journal_get_data(journal, field, data as *mut *const libc::c_void, len)
+}
+
+// A cursor names a journal entry, so that a reader can note where it got
+// to, say across a restart, and carry on from there: seek to the cursor,
+// step once with `systemd_shim_journal_next` onto the entry it names,
+// and read on from the one after.
+
+/// The cursor of the current entry in `*cursor`, to free with
+/// `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_cursor(
+    journal: *mut raw::sd_journal,
+    cursor: *mut *mut c_char,
+) -> c_int {
+    raw::sd_journal_get_cursor(journal, cursor)
+}
+
+/// Move to the entry `cursor` names or, if it is gone (rotated out, or
+/// filtered by a match), the nearest one to where it was
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_seek_cursor(
+    journal: *mut raw::sd_journal,
+    cursor: *const c_char,
+) -> c_int {
+    raw::sd_journal_seek_cursor(journal, cursor)
+}
+
+/// Positive if the current entry is the one `cursor` names, 0 if not, as
+/// after a seek to a cursor whose entry is gone
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_test_cursor(
+    journal: *mut raw::sd_journal,
+    cursor: *const c_char,
+) -> c_int {
+    raw::sd_journal_test_cursor(journal, cursor)
+}
+
+/// Move to the wall-clock time `usec` (microseconds since the epoch):
+/// `systemd_shim_journal_next` then reads the first entry logged at or
+/// after it, `systemd_shim_journal_previous` the last one at or before
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_seek_realtime_usec(
+    journal: *mut raw::sd_journal,
+    usec: u64,
+) -> c_int {
+    raw::sd_journal_seek_realtime_usec(journal, usec)
+}
+
+/// Move to `usec` after the boot `boot_id` (32 hex digits or a UUID;
+/// NULL for the running boot) started, as `systemd_shim_journal_seek_realtime_usec`
+/// does by wall-clock time; unlike it, unaffected by the clock being set
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_seek_monotonic_usec(
+    journal: *mut raw::sd_journal,
+    boot_id: *const c_char,
+    usec: u64,
+) -> c_int {
+    let mut id = raw::sd_id128_t { qwords: [0; 2] };
+    let r = if boot_id.is_null() {
+        raw::sd_id128_get_boot(&mut id)
+    } else {
+        raw::sd_id128_from_string(boot_id, &mut id)
+    };
+    if r < 0 {
+        return r;
+    }
+    raw::sd_journal_seek_monotonic_usec(journal, id, usec)
+}
+
+/// When the current entry was logged, in microseconds since the epoch
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_realtime_usec(
+    journal: *mut raw::sd_journal,
+    usec: *mut u64,
+) -> c_int {
+    raw::sd_journal_get_realtime_usec(journal, usec)
+}
+
+/// When the current entry was logged, in microseconds since its boot
+/// started; that boot's ID, as 32 hex digits, in `*boot_id` unless NULL,
+/// to free with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_monotonic_usec(
+    journal: *mut raw::sd_journal,
+    usec: *mut u64,
+    boot_id: *mut *mut c_char,
+) -> c_int {
+    let mut id = raw::sd_id128_t { qwords: [0; 2] };
+    let r = raw::sd_journal_get_monotonic_usec(journal, usec, &mut id);
+    if r < 0 || boot_id.is_null() {
+        return r;
+    }
+    let mut text = [0 as c_char; 33];
+    raw::sd_id128_to_string(id, text.as_mut_ptr());
+    give_string(&CStr::from_ptr(text.as_ptr()).to_string_lossy(), boot_id)
+}
+
+/// `systemd_shim_journal_wait` timed out with the journal unchanged
+pub const SYSTEMD_SHIM_JOURNAL_NOP: c_int = 0;
+/// Entries were added at the end; `systemd_shim_journal_next` reads them
+pub const SYSTEMD_SHIM_JOURNAL_APPEND: c_int = 1;
+/// Journal files were added, removed or rotated; entries may be
+/// anywhere, so a tailing reader should seek again from its cursor
+pub const SYSTEMD_SHIM_JOURNAL_INVALIDATE: c_int = 2;
+
+/// Block until the journal changes or `timeout_usec` passes (`u64::MAX`
+/// waits indefinitely), returning one of `SYSTEMD_SHIM_JOURNAL_NOP`,
+/// `_APPEND` or `_INVALIDATE`, so a tail can sleep instead of polling.
+/// The first call on a journal sets up its watches and may come back with
+/// `SYSTEMD_SHIM_JOURNAL_INVALIDATE` straight away
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_wait(
+    journal: *mut raw::sd_journal,
+    timeout_usec: u64,
+) -> c_int {
+    raw::sd_journal_wait(journal, timeout_usec)
+}
+
+// For a caller with its own poll loop, say one already watching bus
+// descriptors, in place of `systemd_shim_journal_wait`: poll the
+// descriptor for its events until its timeout, then call
+// `systemd_shim_journal_process` to learn what changed.
+
+/// The inotify descriptor watching the journal's files
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_fd(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_get_fd(journal)
+}
+
+/// The poll events to wait for on that descriptor
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_events(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_get_events(journal)
+}
+
+/// The CLOCK_MONOTONIC time in microseconds by which to call
+/// `systemd_shim_journal_process` even if the descriptor stays quiet, for
+/// file systems inotify misses changes on; `u64::MAX` for no deadline
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_timeout(
+    journal: *mut raw::sd_journal,
+    timeout_usec: *mut u64,
+) -> c_int {
+    raw::sd_journal_get_timeout(journal, timeout_usec)
+}
+
+/// Take in what woke the descriptor, returning `SYSTEMD_SHIM_JOURNAL_NOP`,
+/// `_APPEND` or `_INVALIDATE` as `systemd_shim_journal_wait` does
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_process(journal: *mut raw::sd_journal) -> c_int {
+    raw::sd_journal_process(journal)
+}
+
+// Every value a field takes anywhere in the journal, each once, say the
+// units or syslog identifiers to offer as filters: query the field, then
+// enumerate until 0. Matches do not narrow these down.
+
+/// Start listing the values of `field`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_query_unique(
+    journal: *mut raw::sd_journal,
+    field: *const c_char,
+) -> c_int {
+    raw::sd_journal_query_unique(journal, field)
+}
+
+/// The next value as `FIELD=value` in `*data` and `*len`, valid until the
+/// next call; > 0 for a value, 0 once all have been listed
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_enumerate_unique(
+    journal: *mut raw::sd_journal,
+    data: *mut *const u8,
+    len: *mut usize,
+) -> c_int {
+    raw::sd_journal_enumerate_unique(journal, data as *mut *const libc::c_void, len)
+}
+
+/// List the queried field's values again from the first
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_restart_unique(journal: *mut raw::sd_journal) {
+    raw::sd_journal_restart_unique(journal)
+}
+
+/// The message catalog's explanation of the current entry, found by its
+/// `MESSAGE_ID` with the entry's fields put in for `@FIELD@`, in `*text`
+/// to free with `systemd_shim_free_string`; -ENOENT when it has none
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_catalog(
+    journal: *mut raw::sd_journal,
+    text: *mut *mut c_char,
+) -> c_int {
+    raw::sd_journal_get_catalog(journal, text)
+}
+
+/// The catalog text for `message_id` (32 hex digits or a UUID) as written,
+/// `@FIELD@` placeholders and all, in `*text` to free with
+/// `systemd_shim_free_string`; -ENOENT when the catalog has none
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_catalog_for_message_id(
+    message_id: *const c_char,
+    text: *mut *mut c_char,
+) -> c_int {
+    if message_id.is_null() {
+        return -libc::EINVAL;
+    }
+    let mut id = raw::sd_id128_t { qwords: [0; 2] };
+    let r = raw::sd_id128_from_string(message_id, &mut id);
+    if r < 0 {
+        return r;
+    }
+    raw::sd_journal_get_catalog_for_message_id(id, text)
+}
+
+/// Log `fields` through `journal::send`, which writes to the mock journal
+/// when `SYSTEM_TOOLS_MOCK_JOURNAL` names one
+fn journal_send(fields: &[(&str, &[u8])]) -> c_int {
+    match journal::send(fields) {
+        Ok(()) => 0,
+        Err(e) => -e.raw_os_error().unwrap_or(libc::EIO),
+    }
+}
+
+/// Log one structured entry from the `n` buffers in `fields`, each
+/// `FIELD=value`, where the value may hold newlines or binary data.
+/// `MESSAGE` is the text shown; `PRIORITY`, `MESSAGE_ID` and the caller's
+/// own upper-case fields go with it
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_sendv(fields: *const libc::iovec, n: c_int) -> c_int {
+    if fields.is_null() || n <= 0 {
+        return -libc::EINVAL;
+    }
+    let mut pairs = Vec::with_capacity(n as usize);
+    for iov in std::slice::from_raw_parts(fields, n as usize) {
+        let buf = std::slice::from_raw_parts(iov.iov_base as *const u8, iov.iov_len);
+        let Some(eq) = buf.iter().position(|&b| b == b'=') else {
+            return -libc::EINVAL;
+        };
+        let Ok(field) = std::str::from_utf8(&buf[..eq]) else {
+            return -libc::EINVAL;
+        };
+        pairs.push((field, &buf[eq + 1..]));
+    }
+    journal_send(&pairs)
+}
+
+/// `systemd_shim_journal_sendv` for text, from a NULL-terminated array of
+/// `FIELD=value` strings
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_send(fields: *const *const c_char) -> c_int {
+    let Some(fields) = strv_arg(fields) else {
+        return -libc::EINVAL;
+    };
+    let mut pairs = Vec::with_capacity(fields.len());
+    for field in fields {
+        let Some((name, value)) = field.split_once('=') else {
+            return -libc::EINVAL;
+        };
+        pairs.push((name, value.as_bytes()));
+    }
+    journal_send(&pairs)
+}
+
+/// Log `msg` at `priority` (`LOG_EMERG` 0 to `LOG_DEBUG` 7), a quick
+/// `systemd_shim_journal_send` of `MESSAGE` and `PRIORITY`. Unlike
+/// `sd_journal_print`, `msg` is not a format: a `%` in it is logged as is
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_print(priority: c_int, msg: *const c_char) -> c_int {
+    if msg.is_null() {
+        return -libc::EINVAL;
+    }
+    match journal::print(priority, CStr::from_ptr(msg).to_bytes()) {
+        Ok(()) => 0,
+        Err(e) => -e.raw_os_error().unwrap_or(libc::EIO),
+    }
+}
+
+/// The current entry in `*ret` as a JSON object, the way `journalctl -o
+/// json` writes one: every field, with `__CURSOR`, `__REALTIME_TIMESTAMP`,
+/// `__MONOTONIC_TIMESTAMP` and `_BOOT_ID`, in one call rather than one
+/// per field. Values are strings, byte arrays when not printable text, and
+/// arrays of those for a field given more than once
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_get_entry_json(
+    journal: *mut raw::sd_journal,
+    ret: *mut *mut c_char,
+) -> c_int {
+    json::give(journal::entry_json(journal), ret)
+}
+
+/// Called with each entry in the Journal Export Format in turn, `len`
+/// bytes at `data` ending in the empty line, only borrowed for the call.
+/// A negative return stops the export and is passed on from
+/// `systemd_shim_journal_export_callback`
+pub type ExportWriter =
+    unsafe extern "C" fn(data: *const u8, len: usize, userdata: *mut c_void) -> c_int;
+
+/// Hands `ExportWriter` each entry `journal::export` writes, keeping the
+/// errno of a refusal
+struct CallbackWriter {
+    writer: ExportWriter,
+    userdata: *mut c_void,
+}
+
+impl std::io::Write for CallbackWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let r = unsafe { (self.writer)(buf.as_ptr(), buf.len(), self.userdata) };
+        if r < 0 {
+            return Err(std::io::Error::from_raw_os_error(-r));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn give_count(r: std::io::Result<u64>, count: *mut u64) -> c_int {
+    match r {
+        Ok(n) => {
+            if !count.is_null() {
+                unsafe { *count = n };
+            }
+            0
+        }
+        Err(e) => -e.raw_os_error().unwrap_or(libc::EIO),
+    }
+}
+
+/// Writes every entry from the read position on to `fd` in the Journal
+/// Export Format, byte for byte what `journalctl -o export` gives, which
+/// systemd-journal-remote and log shippers take. `fd` stays open and the
+/// caller's; the number of entries written goes in `*count` when not NULL.
+/// A pipe or socket that blocks holds the call up until it drains
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_export_fd(
+    journal: *mut raw::sd_journal,
+    fd: c_int,
+    count: *mut u64,
+) -> c_int {
+    use std::os::fd::FromRawFd;
+    if fd < 0 {
+        return -libc::EINVAL;
+    }
+    let mut file = std::mem::ManuallyDrop::new(std::fs::File::from_raw_fd(fd));
+    give_count(journal::export(journal, &mut *file), count)
+}
+
+/// As `systemd_shim_journal_export_fd`, but hands the output to `writer`,
+/// for a caller that frames or compresses it itself
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_export_callback(
+    journal: *mut raw::sd_journal,
+    writer: ExportWriter,
+    userdata: *mut c_void,
+    count: *mut u64,
+) -> c_int {
+    let mut out = CallbackWriter { writer, userdata };
+    give_count(journal::export(journal, &mut out), count)
+}
+
+/// Adds a match for the entries of boot `boot_id` (32 hex digits or a
+/// UUID), or of the running boot when NULL, the usual first filter of
+/// anything reading the logs
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_add_match_boot(
+    journal: *mut raw::sd_journal,
+    boot_id: *const c_char,
+) -> c_int {
+    let mut id = raw::sd_id128_t { qwords: [0; 2] };
+    let r = if boot_id.is_null() {
+        raw::sd_id128_get_boot(&mut id)
+    } else {
+        raw::sd_id128_from_string(boot_id, &mut id)
+    };
+    if r < 0 {
+        return r;
+    }
+    let mut text = [0 as c_char; 33];
+    raw::sd_id128_to_string(id, text.as_mut_ptr());
+    let boot_id = CStr::from_ptr(text.as_ptr()).to_string_lossy();
+    let m = format!("_BOOT_ID={}", boot_id);
+    let r = raw::sd_journal_add_match(journal, m.as_ptr() as *const c_void, m.len());
+    record(journal, r, Term::Match(m.into_bytes()))
+}
+
+/// The boots the journal has entries from, as a JSON array in `*ret`,
+/// oldest first, of objects with `index` (0 for the newest, -1 before it
+/// and so on, as `journalctl --list-boots` numbers them), `boot_id`, and
+/// `first_entry` and `last_entry` in microseconds since the epoch. The
+/// search sets matches of its own and puts the caller's back after, but
+/// not the read position. Free it with `systemd_shim_free_string`
+#[no_mangle]
+pub unsafe extern "C" fn systemd_shim_journal_list_boots_json(
+    journal: *mut raw::sd_journal,
+    ret: *mut *mut c_char,
+) -> c_int {
+    let boots = journal::boots(journal).map(|boots| {
+        let newest = boots.len() as i64 - 1;
+        boots
+            .into_iter()
+            .enumerate()
+            .map(|(i, boot)| {
+                serde_json::json!({
+                    "index": i as i64 - newest,
+                    "boot_id": boot.boot_id,
+                    "first_entry": boot.first_realtime_usec,
+                    "last_entry": boot.last_realtime_usec,
+                })
+            })
+            .collect()
+    });
+    let r = restore_matches(journal);
+    if r < 0 {
+        return r;
+    }
+    json::give(boots, ret)
 }
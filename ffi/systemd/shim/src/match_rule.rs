@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! D-Bus match rules, assembled and checked before they reach the bus
+//!
+//! A rule is a comma-separated list of `key='value'` filters. The bus
+//! daemon refuses a malformed one with a bare `-EINVAL` and no hint of
+//! which filter was wrong, so each value is checked against the D-Bus
+//! naming rules as it is set. sd-bus has no escape for a `'` inside a
+//! quoted value, so an argument holding one is refused too.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Message types a rule can select
+pub const TYPES: &[&str] = &["signal", "method_call", "method_return", "error"];
+
+/// `argN` filters go up to `arg63`
+pub const MAX_ARG: u8 = 63;
+
+/// Longest bus, interface or member name the specification allows
+const MAX_NAME: usize = 255;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatchRule {
+    kind: Option<String>,
+    sender: Option<String>,
+    interface: Option<String>,
+    member: Option<String>,
+    path: Option<String>,
+    path_namespace: Option<String>,
+    args: BTreeMap<u8, String>,
+}
+
+fn element(s: &str, digits_first: bool) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| {
+        c.is_ascii_alphabetic() || c == '_' || (digits_first && c.is_ascii_digit())
+    }) && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn dotted(kind: &str, name: &str, check: impl Fn(&str) -> bool) -> Result<(), String> {
+    let elements: Vec<&str> = name.split('.').collect();
+    if name.len() > MAX_NAME || elements.len() < 2 || !elements.iter().all(|e| check(e)) {
+        return Err(format!("{:?} is not a valid {}", name, kind));
+    }
+    Ok(())
+}
+
+pub fn check_type(kind: &str) -> Result<(), String> {
+    if TYPES.contains(&kind) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} is not a message type, expected one of {}",
+            kind,
+            TYPES.join(", ")
+        ))
+    }
+}
+
+/// A unique name such as `:1.42` or a well-known one such as
+/// `org.freedesktop.systemd1`
+pub fn check_bus_name(name: &str) -> Result<(), String> {
+    match name.strip_prefix(':') {
+        Some(unique) => dotted("unique bus name", unique, |e| {
+            !e.is_empty()
+                && e.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        }),
+        None => dotted("bus name", name, |e| element(&e.replace('-', "_"), false)),
+    }
+}
+
+pub fn check_interface(name: &str) -> Result<(), String> {
+    dotted("interface name", name, |e| element(e, false))
+}
+
+pub fn check_member(name: &str) -> Result<(), String> {
+    if name.len() <= MAX_NAME && element(name, false) {
+        Ok(())
+    } else {
+        Err(format!("{:?} is not a valid member name", name))
+    }
+}
+
+pub fn check_path(path: &str) -> Result<(), String> {
+    let valid = path == "/"
+        || (path.starts_with('/')
+            && path[1..].split('/').all(|e| {
+                !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            }));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("{:?} is not a valid object path", path))
+    }
+}
+
+pub fn check_arg(index: u8, value: &str) -> Result<(), String> {
+    if index > MAX_ARG {
+        return Err(format!("arg{} is past arg{}", index, MAX_ARG));
+    }
+    if value.contains('\'') {
+        return Err(format!("arg{} value {:?} holds a quote", index, value));
+    }
+    Ok(())
+}
+
+impl MatchRule {
+    pub fn new() -> MatchRule {
+        MatchRule::default()
+    }
+
+    /// Signals only, the usual rule
+    pub fn signal() -> MatchRule {
+        MatchRule::new().kind("signal")
+    }
+
+    pub fn kind(mut self, kind: &str) -> MatchRule {
+        self.kind = Some(kind.to_string());
+        self
+    }
+
+    pub fn sender(mut self, sender: &str) -> MatchRule {
+        self.sender = Some(sender.to_string());
+        self
+    }
+
+    pub fn interface(mut self, interface: &str) -> MatchRule {
+        self.interface = Some(interface.to_string());
+        self
+    }
+
+    pub fn member(mut self, member: &str) -> MatchRule {
+        self.member = Some(member.to_string());
+        self
+    }
+
+    pub fn path(mut self, path: &str) -> MatchRule {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// `path` and every object below it
+    pub fn path_namespace(mut self, path: &str) -> MatchRule {
+        self.path_namespace = Some(path.to_string());
+        self
+    }
+
+    /// Only messages whose string argument `index` is `value`
+    pub fn arg(mut self, index: u8, value: &str) -> MatchRule {
+        self.args.insert(index, value.to_string());
+        self
+    }
+
+    /// Check every filter, naming the first that is wrong
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(kind) = &self.kind {
+            check_type(kind)?;
+        }
+        if let Some(sender) = &self.sender {
+            check_bus_name(sender)?;
+        }
+        if let Some(interface) = &self.interface {
+            check_interface(interface)?;
+        }
+        if let Some(member) = &self.member {
+            check_member(member)?;
+        }
+        for path in [&self.path, &self.path_namespace].into_iter().flatten() {
+            check_path(path)?;
+        }
+        if self.path.is_some() && self.path_namespace.is_some() {
+            return Err("path and path_namespace cannot both be set".to_string());
+        }
+        self.args
+            .iter()
+            .try_for_each(|(&i, value)| check_arg(i, value))
+    }
+
+    /// The rule as the bus takes it, once valid
+    pub fn rule(&self) -> Result<String, String> {
+        self.validate()?;
+        Ok(self.to_string())
+    }
+}
+
+impl fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("type", &self.kind),
+            ("sender", &self.sender),
+            ("interface", &self.interface),
+            ("member", &self.member),
+            ("path", &self.path),
+            ("path_namespace", &self.path_namespace),
+        ];
+        let mut parts: Vec<String> = fields
+            .iter()
+            .filter_map(|(key, value)| Some(format!("{}='{}'", key, value.as_deref()?)))
+            .collect();
+        parts.extend(
+            self.args
+                .iter()
+                .map(|(i, value)| format!("arg{}='{}'", i, value)),
+        );
+        f.write_str(&parts.join(","))
+    }
+}
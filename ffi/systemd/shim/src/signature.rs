@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! D-Bus type signatures, checked before they reach sd-bus
+//!
+//! sd-bus refuses a malformed signature, or an append or read that does
+//! not follow it, with a bare `-EINVAL` from wherever it noticed. These
+//! checks follow the specification's grammar and say what is wrong and
+//! where, so a caller can vet a signature once, up front.
+
+/// Longest signature the specification allows
+const MAX_LEN: usize = 255;
+
+/// Arrays and structs may each nest 32 deep
+const MAX_DEPTH: usize = 32;
+
+/// Type codes that stand alone and may key a dict entry
+const BASIC: &[u8] = b"ybnqiuxtdhsog";
+
+struct Parser<'a> {
+    signature: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    arrays: usize,
+    structs: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> String {
+        format!(
+            "signature {:?}: {} at offset {}",
+            self.signature, what, self.pos
+        )
+    }
+
+    /// One complete type from the current position
+    fn complete(&mut self) -> Result<(), String> {
+        let Some(&code) = self.bytes.get(self.pos) else {
+            return Err(self.error("missing a type"));
+        };
+        match code {
+            c if BASIC.contains(&c) || c == b'v' => {
+                self.pos += 1;
+                Ok(())
+            }
+            b'a' => {
+                self.arrays += 1;
+                if self.arrays > MAX_DEPTH {
+                    return Err(self.error("arrays nested too deep"));
+                }
+                self.pos += 1;
+                let r = if self.bytes.get(self.pos) == Some(&b'{') {
+                    self.dict_entry()
+                } else {
+                    self.complete()
+                };
+                self.arrays -= 1;
+                r
+            }
+            b'(' => {
+                self.structs += 1;
+                if self.structs > MAX_DEPTH {
+                    return Err(self.error("structs nested too deep"));
+                }
+                self.pos += 1;
+                if self.bytes.get(self.pos) == Some(&b')') {
+                    return Err(self.error("empty struct"));
+                }
+                while self.bytes.get(self.pos) != Some(&b')') {
+                    if self.pos == self.bytes.len() {
+                        return Err(self.error("struct not closed"));
+                    }
+                    self.complete()?;
+                }
+                self.pos += 1;
+                self.structs -= 1;
+                Ok(())
+            }
+            b'{' => Err(self.error("dict entry outside an array")),
+            b')' | b'}' => Err(self.error(&format!("unexpected '{}'", code as char))),
+            c => Err(self.error(&format!(
+                "unknown type code '{}'",
+                (c as char).escape_default()
+            ))),
+        }
+    }
+
+    /// `{`, a basic key type, one complete value type, `}`
+    fn dict_entry(&mut self) -> Result<(), String> {
+        self.pos += 1;
+        match self.bytes.get(self.pos) {
+            Some(c) if BASIC.contains(c) => self.pos += 1,
+            Some(b'}') | None => return Err(self.error("dict entry without a key type")),
+            Some(_) => return Err(self.error("dict entry key is not a basic type")),
+        }
+        if matches!(self.bytes.get(self.pos), Some(b'}') | None) {
+            return Err(self.error("dict entry without a value type"));
+        }
+        self.complete()?;
+        if self.bytes.get(self.pos) != Some(&b'}') {
+            return Err(self.error("dict entry holds more than a key and a value"));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+}
+
+fn parse(signature: &str) -> Result<Parser<'_>, String> {
+    if signature.len() > MAX_LEN {
+        return Err(format!(
+            "signature {:?} is longer than {} bytes",
+            signature, MAX_LEN
+        ));
+    }
+    Ok(Parser {
+        signature,
+        bytes: signature.as_bytes(),
+        pos: 0,
+        arrays: 0,
+        structs: 0,
+    })
+}
+
+/// Any number of complete types, as a message body or method takes;
+/// `""` is a valid, empty signature
+pub fn check(signature: &str) -> Result<(), String> {
+    let mut parser = parse(signature)?;
+    while parser.pos < parser.bytes.len() {
+        parser.complete()?;
+    }
+    Ok(())
+}
+
+/// Exactly one complete type, as a property or a variant holds
+pub fn check_single(signature: &str) -> Result<(), String> {
+    let mut parser = parse(signature)?;
+    parser.complete()?;
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("more than one complete type"));
+    }
+    Ok(())
+}
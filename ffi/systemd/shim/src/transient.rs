@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Properties of a transient unit, for `StartTransientUnit`
+//!
+//! The manager takes a transient unit's settings as an `a(sv)` array of
+//! unit file properties by their D-Bus names. [`Properties`] keeps them in
+//! the order they were set, replacing a property set twice, and writes
+//! them into the call. The common ones have typed setters; anything else
+//! can be set by name with a string, bool or integer value.
+
+use crate::raw;
+use libc::{c_char, c_int, c_void};
+use std::ffi::CString;
+use std::ptr;
+
+/// `CollectMode=` values
+pub const COLLECT_MODES: &[&str] = &["inactive", "inactive-or-failed"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(CString),
+    Bool(bool),
+    U64(u64),
+    Pids(Vec<u32>),
+    /// Path, argv and whether a failing exit status is ignored
+    Exec(CString, Vec<CString>, bool),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Properties {
+    entries: Vec<(CString, Value)>,
+}
+
+fn cstring(what: &str, s: &str) -> Result<CString, String> {
+    CString::new(s).map_err(|_| format!("{} {:?} holds a NUL", what, s))
+}
+
+impl Properties {
+    pub fn new() -> Properties {
+        Properties::default()
+    }
+
+    fn set(&mut self, name: &str, value: Value) -> Result<(), String> {
+        crate::match_rule::check_member(name)?;
+        let name = cstring("property", name)?;
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((name, value)),
+        }
+        Ok(())
+    }
+
+    pub fn set_string(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.set(name, Value::Str(cstring(name, value)?))
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) -> Result<(), String> {
+        self.set(name, Value::Bool(value))
+    }
+
+    pub fn set_u64(&mut self, name: &str, value: u64) -> Result<(), String> {
+        self.set(name, Value::U64(value))
+    }
+
+    /// The command a service runs, `argv[0]` being an absolute path
+    pub fn exec_start(&mut self, argv: &[&str], ignore_failure: bool) -> Result<(), String> {
+        let Some(path) = argv.first() else {
+            return Err("ExecStart needs a command".to_string());
+        };
+        if !path.starts_with('/') {
+            return Err(format!(
+                "ExecStart command {:?} is not an absolute path",
+                path
+            ));
+        }
+        let argv = argv
+            .iter()
+            .map(|arg| cstring("argument", arg))
+            .collect::<Result<_, _>>()?;
+        self.set(
+            "ExecStart",
+            Value::Exec(cstring("command", path)?, argv, ignore_failure),
+        )
+    }
+
+    /// Processes a scope takes in; a scope runs nothing of its own
+    pub fn pids(&mut self, pids: &[u32]) -> Result<(), String> {
+        self.set("PIDs", Value::Pids(pids.to_vec()))
+    }
+
+    /// Memory limit in bytes, past which the unit is OOM-killed
+    pub fn memory_max(&mut self, bytes: u64) -> Result<(), String> {
+        self.set_u64("MemoryMax", bytes)
+    }
+
+    /// CPU time per second of wall time, in percent of one CPU, so 200
+    /// allows two full CPUs
+    pub fn cpu_quota(&mut self, percent: u32) -> Result<(), String> {
+        if percent == 0 {
+            return Err("CPUQuota must be above 0%".to_string());
+        }
+        self.set_u64("CPUQuotaPerSecUSec", u64::from(percent) * 10_000)
+    }
+
+    /// When the manager forgets the unit once it stops: `inactive`, or
+    /// `inactive-or-failed` so a failed unit does not linger either
+    pub fn collect_mode(&mut self, mode: &str) -> Result<(), String> {
+        if !COLLECT_MODES.contains(&mode) {
+            return Err(format!("{:?} is not a collect mode", mode));
+        }
+        self.set_string("CollectMode", mode)
+    }
+
+    /// Write the properties into `m` as `a(sv)`
+    pub(crate) unsafe fn append(&self, m: *mut raw::sd_bus_message) -> c_int {
+        let mut r = raw::sd_bus_message_open_container(m, b'a' as c_char, c!("(sv)"));
+        for (name, value) in &self.entries {
+            if r < 0 {
+                return r;
+            }
+            r = append_property(m, name, value);
+        }
+        if r < 0 {
+            return r;
+        }
+        raw::sd_bus_message_close_container(m)
+    }
+}
+
+unsafe fn append_property(m: *mut raw::sd_bus_message, name: &CString, value: &Value) -> c_int {
+    let signature = match value {
+        Value::Str(_) => c!("s"),
+        Value::Bool(_) => c!("b"),
+        Value::U64(_) => c!("t"),
+        Value::Pids(_) => c!("au"),
+        Value::Exec(..) => c!("a(sasb)"),
+    };
+    let mut r = raw::sd_bus_message_open_container(m, b'r' as c_char, c!("sv"));
+    if r >= 0 {
+        r = raw::sd_bus_message_append_basic(m, b's' as c_char, name.as_ptr() as *const c_void);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_message_open_container(m, b'v' as c_char, signature);
+    }
+    if r >= 0 {
+        r = append_value(m, value);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_message_close_container(m);
+    }
+    if r >= 0 {
+        r = raw::sd_bus_message_close_container(m);
+    }
+    r
+}
+
+unsafe fn append_value(m: *mut raw::sd_bus_message, value: &Value) -> c_int {
+    match value {
+        Value::Str(s) => {
+            raw::sd_bus_message_append_basic(m, b's' as c_char, s.as_ptr() as *const c_void)
+        }
+        Value::Bool(b) => {
+            let b = c_int::from(*b);
+            raw::sd_bus_message_append_basic(m, b'b' as c_char, &b as *const c_int as *const c_void)
+        }
+        Value::U64(n) => {
+            raw::sd_bus_message_append_basic(m, b't' as c_char, n as *const u64 as *const c_void)
+        }
+        Value::Pids(pids) => {
+            let mut r = raw::sd_bus_message_open_container(m, b'a' as c_char, c!("u"));
+            for pid in pids {
+                if r < 0 {
+                    return r;
+                }
+                r = raw::sd_bus_message_append_basic(
+                    m,
+                    b'u' as c_char,
+                    pid as *const u32 as *const c_void,
+                );
+            }
+            if r < 0 {
+                return r;
+            }
+            raw::sd_bus_message_close_container(m)
+        }
+        Value::Exec(path, argv, ignore_failure) => {
+            let mut strv: Vec<*mut c_char> =
+                argv.iter().map(|a| a.as_ptr() as *mut c_char).collect();
+            strv.push(ptr::null_mut());
+            let ignore = c_int::from(*ignore_failure);
+            let mut r = raw::sd_bus_message_open_container(m, b'a' as c_char, c!("(sasb)"));
+            if r >= 0 {
+                r = raw::sd_bus_message_open_container(m, b'r' as c_char, c!("sasb"));
+            }
+            if r >= 0 {
+                r = raw::sd_bus_message_append_basic(
+                    m,
+                    b's' as c_char,
+                    path.as_ptr() as *const c_void,
+                );
+            }
+            if r >= 0 {
+                r = raw::sd_bus_message_append_strv(m, strv.as_mut_ptr());
+            }
+            if r >= 0 {
+                r = raw::sd_bus_message_append_basic(
+                    m,
+                    b'b' as c_char,
+                    &ignore as *const c_int as *const c_void,
+                );
+            }
+            if r >= 0 {
+                r = raw::sd_bus_message_close_container(m);
+            }
+            if r >= 0 {
+                r = raw::sd_bus_message_close_container(m);
+            }
+            r
+        }
+    }
+}
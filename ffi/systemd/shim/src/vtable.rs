@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! Interfaces served on the bus, for the C ABI
+//!
+//! sd-bus takes an interface as a table of `sd_bus_vtable` entries that
+//! must outlive its registration. A [`Vtable`] collects the methods and
+//! properties, lays the table out once when it is registered, and is then
+//! owned by the object's slot. Every method points at the same
+//! trampoline, which finds the caller's handler by member name, and
+//! every property getter likewise.
+
+use crate::raw;
+use crate::{MethodHandler, PropertyGetter};
+use libc::{c_char, c_int, c_void};
+use std::ffi::{CStr, CString};
+use std::{mem, ptr};
+
+const START: u64 = b'<' as u64;
+const END: u64 = b'>' as u64;
+const METHOD: u64 = b'M' as u64;
+const PROPERTY: u64 = b'P' as u64;
+
+/// Callable by anyone on the bus; without it a caller on the system bus
+/// needs CAP_SYS_ADMIN or the service's own user, which is root's for
+/// most services
+const UNPRIVILEGED: u64 = 1 << 2;
+
+/// A property whose changes are announced with `PropertiesChanged`
+const EMITS_CHANGE: u64 = 1 << 5;
+
+struct Method {
+    member: CString,
+    signature: CString,
+    result: CString,
+    flags: u64,
+    handler: MethodHandler,
+    userdata: *mut c_void,
+}
+
+struct Property {
+    member: CString,
+    signature: CString,
+    getter: PropertyGetter,
+    userdata: *mut c_void,
+}
+
+#[derive(Default)]
+pub struct Vtable {
+    methods: Vec<Method>,
+    properties: Vec<Property>,
+    entries: Vec<raw::sd_bus_vtable>,
+}
+
+fn cstring(what: &str, s: &str) -> Result<CString, String> {
+    CString::new(s).map_err(|_| format!("{} {:?} holds a NUL", what, s))
+}
+
+fn entry(kind: u64, flags: u64, x: raw::sd_bus_vtable_x) -> raw::sd_bus_vtable {
+    raw::sd_bus_vtable {
+        type_and_flags: kind | flags << 8,
+        x,
+    }
+}
+
+impl Vtable {
+    pub fn new() -> Vtable {
+        Vtable::default()
+    }
+
+    /// Add method `member`, taking arguments of `signature` and replying
+    /// with `result` (`""` for none). `handler` is called with `userdata`
+    /// for each call and replies to it.
+    pub fn add_method(
+        &mut self,
+        member: &str,
+        signature: &str,
+        result: &str,
+        unprivileged: bool,
+        handler: MethodHandler,
+        userdata: *mut c_void,
+    ) -> Result<(), String> {
+        self.check_new(member)?;
+        crate::signature::check(signature)?;
+        crate::signature::check(result)?;
+        self.methods.push(Method {
+            member: cstring("member", member)?,
+            signature: cstring("signature", signature)?,
+            result: cstring("result", result)?,
+            flags: if unprivileged { UNPRIVILEGED } else { 0 },
+            handler,
+            userdata,
+        });
+        Ok(())
+    }
+
+    /// Add read-only property `member` of type `signature`, whose value
+    /// `getter` appends when asked for it
+    pub fn add_property(
+        &mut self,
+        member: &str,
+        signature: &str,
+        getter: PropertyGetter,
+        userdata: *mut c_void,
+    ) -> Result<(), String> {
+        self.check_new(member)?;
+        crate::signature::check_single(signature)?;
+        self.properties.push(Property {
+            member: cstring("member", member)?,
+            signature: cstring("signature", signature)?,
+            getter,
+            userdata,
+        });
+        Ok(())
+    }
+
+    /// Methods and properties share one namespace within an interface
+    fn check_new(&self, member: &str) -> Result<(), String> {
+        crate::match_rule::check_member(member)?;
+        let taken = self.methods.iter().map(|m| &m.member);
+        if taken
+            .chain(self.properties.iter().map(|p| &p.member))
+            .any(|m| m.as_bytes() == member.as_bytes())
+        {
+            return Err(format!("{} is already added", member));
+        }
+        Ok(())
+    }
+
+    /// The table for sd-bus, laid out once the members are all added
+    pub(crate) unsafe fn table(&mut self) -> *const raw::sd_bus_vtable {
+        let start = raw::sd_bus_vtable_x {
+            start: raw::sd_bus_vtable_start {
+                element_size: mem::size_of::<raw::sd_bus_vtable>(),
+                features: 0,
+                vtable_format_reference: ptr::addr_of!(raw::sd_bus_object_vtable_format),
+            },
+        };
+        let mut entries = vec![entry(START, 0, start)];
+        for m in &self.methods {
+            let method = raw::sd_bus_vtable_method {
+                member: m.member.as_ptr(),
+                signature: m.signature.as_ptr(),
+                result: m.result.as_ptr(),
+                handler: Some(dispatch_method),
+                offset: 0,
+                names: ptr::null(),
+            };
+            entries.push(entry(METHOD, m.flags, raw::sd_bus_vtable_x { method }));
+        }
+        for p in &self.properties {
+            let property = raw::sd_bus_vtable_property {
+                member: p.member.as_ptr(),
+                signature: p.signature.as_ptr(),
+                get: Some(get_property),
+                set: ptr::null(),
+                offset: 0,
+            };
+            entries.push(entry(
+                PROPERTY,
+                EMITS_CHANGE,
+                raw::sd_bus_vtable_x { property },
+            ));
+        }
+        let end = raw::sd_bus_vtable_method {
+            member: ptr::null(),
+            signature: ptr::null(),
+            result: ptr::null(),
+            handler: None,
+            offset: 0,
+            names: ptr::null(),
+        };
+        entries.push(entry(END, 0, raw::sd_bus_vtable_x { method: end }));
+        self.entries = entries;
+        self.entries.as_ptr()
+    }
+}
+
+/// sd-bus passes the object's userdata, the `Vtable`, to every method
+unsafe extern "C" fn dispatch_method(
+    m: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    let vtable = &*(userdata as *const Vtable);
+    let member: *const c_char = raw::sd_bus_message_get_member(m);
+    if member.is_null() {
+        return -libc::EBADMSG;
+    }
+    let member = CStr::from_ptr(member);
+    match vtable
+        .methods
+        .iter()
+        .find(|method| method.member.as_c_str() == member)
+    {
+        Some(method) => (method.handler)(m, method.userdata, error),
+        None => -libc::EBADMSG,
+    }
+}
+
+unsafe extern "C" fn get_property(
+    _bus: *mut raw::sd_bus,
+    _path: *const c_char,
+    _interface: *const c_char,
+    property: *const c_char,
+    reply: *mut raw::sd_bus_message,
+    userdata: *mut c_void,
+    error: *mut raw::sd_bus_error,
+) -> c_int {
+    let vtable = &*(userdata as *const Vtable);
+    let property = CStr::from_ptr(property);
+    match vtable
+        .properties
+        .iter()
+        .find(|p| p.member.as_c_str() == property)
+    {
+        Some(p) => (p.getter)(reply, p.userdata, error),
+        None => -libc::ENOENT,
+    }
+}
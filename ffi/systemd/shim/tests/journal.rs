@@ -0,0 +1,826 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+//! The journal calls, through the C ABI and `journal::Journal`, against
+//! the local journal
+//!
+//! Each test logs entries tagged with a value unique to the run and reads
+//! back only those. With no journald to log to, or a journal this user
+//! may not read, there is nothing to read back; it says so and passes.
+
+use libc::c_int;
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use systemd_shim::journal;
+use systemd_shim::{
+    systemd_shim_free_string, systemd_shim_journal_add_conjunction,
+    systemd_shim_journal_add_disjunction, systemd_shim_journal_add_match,
+    systemd_shim_journal_add_match_boot, systemd_shim_journal_close,
+    systemd_shim_journal_enumerate_unique, systemd_shim_journal_export_callback,
+    systemd_shim_journal_export_fd, systemd_shim_journal_flush_matches,
+    systemd_shim_journal_get_catalog, systemd_shim_journal_get_catalog_for_message_id,
+    systemd_shim_journal_get_cursor, systemd_shim_journal_get_data,
+    systemd_shim_journal_get_entry_json, systemd_shim_journal_get_events,
+    systemd_shim_journal_get_fd, systemd_shim_journal_get_timeout,
+    systemd_shim_journal_list_boots_json, systemd_shim_journal_next,
+    systemd_shim_journal_next_skip, systemd_shim_journal_open, systemd_shim_journal_previous,
+    systemd_shim_journal_previous_skip, systemd_shim_journal_print, systemd_shim_journal_process,
+    systemd_shim_journal_query_unique, systemd_shim_journal_restart_unique,
+    systemd_shim_journal_seek_cursor, systemd_shim_journal_seek_head,
+    systemd_shim_journal_seek_tail, systemd_shim_journal_send, systemd_shim_journal_sendv,
+    systemd_shim_journal_test_cursor, SYSTEMD_SHIM_JOURNAL_APPEND,
+};
+
+/// The current entry's message
+unsafe fn message<J>(j: *mut J) -> String {
+    let (mut data, mut len) = (ptr::null(), 0);
+    let field = CString::new("MESSAGE").unwrap();
+    assert!(systemd_shim_journal_get_data(j as *mut _, field.as_ptr(), &mut data, &mut len) >= 0);
+    let data = String::from_utf8_lossy(std::slice::from_raw_parts(data, len));
+    data.trim_start_matches("MESSAGE=").to_string()
+}
+
+/// Log `sent` under a tag unique to the run and open the journal on just
+/// those entries once journald has written them all, with the match that
+/// picks them out, or `None` to skip
+unsafe fn logged<J>(sent: &[String]) -> Option<(*mut J, String)> {
+    logged_with(sent, &[])
+}
+
+/// `logged`, with more fields for the first entries
+unsafe fn logged_with<J>(sent: &[String], extra: &[&[(&str, &str)]]) -> Option<(*mut J, String)> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let tag = format!("{}-{}", std::process::id(), nanos);
+    for (i, message) in sent.iter().enumerate() {
+        let mut fields = vec![("MESSAGE", message.as_str()), ("SYSTEMD_SHIM_TEST", &tag)];
+        fields.extend(extra.get(i).copied().unwrap_or_default());
+        if let Err(e) = journal::send(&fields) {
+            eprintln!("skipped: no journald to log to ({})", e);
+            return None;
+        }
+    }
+
+    let mut j: *mut J = ptr::null_mut();
+    let ret = systemd_shim_journal_open(&mut j as *mut *mut J as *mut _, journal::LOCAL_ONLY);
+    assert!(ret >= 0);
+    let rule = format!("SYSTEMD_SHIM_TEST={}", tag);
+    assert!(systemd_shim_journal_add_match(j as *mut _, rule.as_ptr(), rule.len()) >= 0);
+
+    // journald writes what it is sent in its own time
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let forward = messages(
+            j as *mut _,
+            systemd_shim_journal_seek_head,
+            systemd_shim_journal_next,
+        );
+        if forward.len() >= sent.len() {
+            return Some((j, rule));
+        }
+        if Instant::now() > deadline {
+            eprintln!("skipped: the entries logged are not readable here");
+            systemd_shim_journal_close(j as *mut _);
+            return None;
+        }
+        sleep(Duration::from_millis(50));
+    }
+}
+
+/// Every matching message, seeking to one end and stepping to the other
+unsafe fn messages<J>(
+    j: *mut J,
+    seek: unsafe extern "C" fn(*mut J) -> c_int,
+    step: unsafe extern "C" fn(*mut J) -> c_int,
+) -> Vec<String> {
+    assert!(seek(j) >= 0);
+    let mut messages = Vec::new();
+    while step(j) > 0 {
+        messages.push(message(j));
+    }
+    messages
+}
+
+#[test]
+fn head_then_next_reads_what_tail_then_previous_reads_backwards() {
+    let sent: Vec<String> = (1..=3).map(|i| format!("seek test {}", i)).collect();
+    unsafe {
+        let Some((j, _)) = logged(&sent) else { return };
+        let forward = messages(j, systemd_shim_journal_seek_head, systemd_shim_journal_next);
+        assert_eq!(forward, sent);
+
+        let mut backward = messages(
+            j,
+            systemd_shim_journal_seek_tail,
+            systemd_shim_journal_previous,
+        );
+        backward.reverse();
+        assert_eq!(backward, sent);
+
+        // Nothing before the first entry, nothing past the last
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        assert_eq!(systemd_shim_journal_previous(j), 0);
+        assert_eq!(systemd_shim_journal_seek_tail(j), 0);
+        assert_eq!(systemd_shim_journal_previous(j), 1);
+        assert_eq!(systemd_shim_journal_next(j), 0);
+
+        systemd_shim_journal_close(j);
+    }
+}
+
+#[test]
+fn skipping_moves_many_entries_at_once_and_stops_at_either_end() {
+    let sent: Vec<String> = (1..=5).map(|i| format!("skip test {}", i)).collect();
+    let rule = unsafe {
+        let Some((j, rule)) = logged(&sent) else {
+            return;
+        };
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        assert_eq!(systemd_shim_journal_next_skip(j, 3), 3);
+        assert_eq!(message(j), sent[2]);
+        assert_eq!(systemd_shim_journal_next_skip(j, 10), 2);
+        assert_eq!(message(j), sent[4]);
+        assert_eq!(systemd_shim_journal_previous_skip(j, 3), 3);
+        assert_eq!(message(j), sent[1]);
+        assert_eq!(systemd_shim_journal_previous_skip(j, 10), 1);
+        assert_eq!(message(j), sent[0]);
+        assert_eq!(systemd_shim_journal_next_skip(j, u64::MAX), -libc::ERANGE);
+        systemd_shim_journal_close(j);
+        rule
+    };
+
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.add_match(&rule).unwrap();
+    journal.seek_tail().unwrap();
+    assert_eq!(journal.previous_skip(2).unwrap(), 2);
+    assert_eq!(journal.field("MESSAGE").unwrap(), sent[3]);
+    assert_eq!(journal.next_skip(5).unwrap(), 1);
+    assert_eq!(journal.field("MESSAGE").unwrap(), sent[4]);
+}
+
+#[test]
+fn seeking_a_saved_cursor_resumes_after_that_entry() {
+    let sent: Vec<String> = (1..=3).map(|i| format!("cursor test {}", i)).collect();
+    unsafe {
+        let Some((j, _)) = logged(&sent) else { return };
+
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        let mut raw_cursor = ptr::null_mut();
+        assert_eq!(systemd_shim_journal_get_cursor(j, &mut raw_cursor), 0);
+        let cursor = CString::from(std::ffi::CStr::from_ptr(raw_cursor));
+        systemd_shim_free_string(raw_cursor);
+        assert!(systemd_shim_journal_test_cursor(j, cursor.as_ptr()) > 0);
+
+        // As a restarted reader would, from the other end
+        assert_eq!(systemd_shim_journal_seek_tail(j), 0);
+        assert_eq!(systemd_shim_journal_seek_cursor(j, cursor.as_ptr()), 0);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        assert!(systemd_shim_journal_test_cursor(j, cursor.as_ptr()) > 0);
+        assert_eq!(message(j), sent[1]);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        assert_eq!(message(j), sent[2]);
+        assert_eq!(systemd_shim_journal_test_cursor(j, cursor.as_ptr()), 0);
+
+        systemd_shim_journal_close(j);
+    }
+}
+
+#[test]
+fn seeking_a_time_lands_on_the_entries_logged_then() {
+    let sent: Vec<String> = (1..=3).map(|i| format!("time test {}", i)).collect();
+    let mut journal = unsafe {
+        let Some((j, rule)) = logged(&sent) else {
+            return;
+        };
+        systemd_shim_journal_close(j);
+        let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+        journal.add_match(&rule).unwrap();
+        journal
+    };
+    let boot_id = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").unwrap();
+    let (mut times, mut uptimes) = (Vec::new(), Vec::new());
+    journal.seek_head().unwrap();
+    while journal.next_entry().unwrap() {
+        times.push(journal.realtime_usec().unwrap());
+        let (usec, boot) = journal.monotonic_usec().unwrap();
+        assert_eq!(boot, boot_id.trim().replace('-', ""));
+        uptimes.push(usec);
+    }
+    assert_eq!(times.len(), sent.len());
+    assert!(uptimes.windows(2).all(|w| w[0] <= w[1]));
+
+    // Either way, a step lands on an entry logged at the time sought
+    journal.seek_realtime_usec(times[1]).unwrap();
+    assert!(journal.next_entry().unwrap());
+    assert_eq!(journal.realtime_usec().unwrap(), times[1]);
+    journal.seek_realtime_usec(times[1]).unwrap();
+    assert!(journal.previous_entry().unwrap());
+    assert_eq!(journal.realtime_usec().unwrap(), times[1]);
+
+    journal.seek_realtime_usec(times[2] + 1).unwrap();
+    assert!(!journal.next_entry().unwrap());
+    journal.seek_realtime_usec(times[2] + 1).unwrap();
+    assert!(journal.previous_entry().unwrap());
+    assert_eq!(journal.field("MESSAGE").as_deref(), Some(sent[2].as_str()));
+
+    // The whole run was logged this boot, after it started
+    journal.seek_monotonic_usec(None, 0).unwrap();
+    assert!(journal.next_entry().unwrap());
+    assert_eq!(journal.field("MESSAGE").as_deref(), Some(sent[0].as_str()));
+}
+
+#[test]
+fn waiting_at_the_tail_wakes_for_an_entry_logged_meanwhile() {
+    let sent = vec!["tail test before".to_string()];
+    let (mut journal, tag) = unsafe {
+        let Some((j, rule)) = logged(&sent) else {
+            return;
+        };
+        systemd_shim_journal_close(j);
+        let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+        journal.add_match(&rule).unwrap();
+        let tag = rule.split_once('=').unwrap().1.to_string();
+        (journal, tag)
+    };
+    journal.seek_tail().unwrap();
+    assert!(journal.previous_entry().unwrap());
+    assert!(!journal.next_entry().unwrap());
+
+    let logger = std::thread::spawn(move || {
+        sleep(Duration::from_millis(200));
+        let fields = [("MESSAGE", "tail test after"), ("SYSTEMD_SHIM_TEST", &tag)];
+        journal::send(&fields).unwrap();
+    });
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !journal.next_entry().unwrap() {
+        assert!(Instant::now() < deadline, "no entry after waiting");
+        journal.wait(Some(Duration::from_secs(1))).unwrap();
+    }
+    logger.join().unwrap();
+    assert_eq!(journal.field("MESSAGE").as_deref(), Some("tail test after"));
+}
+
+#[test]
+fn polling_the_journal_descriptor_wakes_for_an_entry_logged_meanwhile() {
+    let sent = vec!["poll test before".to_string()];
+    unsafe {
+        let Some((j, rule)) = logged(&sent) else {
+            return;
+        };
+        assert_eq!(systemd_shim_journal_seek_tail(j), 0);
+        assert_eq!(systemd_shim_journal_previous(j), 1);
+        assert_eq!(systemd_shim_journal_next(j), 0);
+
+        let fd = systemd_shim_journal_get_fd(j);
+        assert!(fd >= 0);
+        let tag = rule.split_once('=').unwrap().1.to_string();
+        let logger = std::thread::spawn(move || {
+            sleep(Duration::from_millis(200));
+            let fields = [("MESSAGE", "poll test after"), ("SYSTEMD_SHIM_TEST", &tag)];
+            journal::send(&fields).unwrap();
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut appended = false;
+        while systemd_shim_journal_next(j) <= 0 {
+            assert!(Instant::now() < deadline, "no entry after polling");
+            let events = systemd_shim_journal_get_events(j);
+            assert!(events > 0);
+            let mut timeout_usec = 0;
+            assert!(systemd_shim_journal_get_timeout(j, &mut timeout_usec) >= 0);
+            let mut now = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            };
+            libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now);
+            let now_usec = now.tv_sec as u64 * 1_000_000 + now.tv_nsec as u64 / 1_000;
+            // Capped, to notice the test's own deadline
+            let timeout_ms = (timeout_usec.saturating_sub(now_usec) / 1_000).min(1_000);
+            let mut pfd = libc::pollfd {
+                fd,
+                events: events as i16,
+                revents: 0,
+            };
+            assert!(libc::poll(&mut pfd, 1, timeout_ms as c_int) >= 0);
+            let change = systemd_shim_journal_process(j);
+            assert!(change >= 0);
+            appended |= change == SYSTEMD_SHIM_JOURNAL_APPEND;
+        }
+        logger.join().unwrap();
+        assert!(appended);
+        assert_eq!(message(j), "poll test after");
+
+        systemd_shim_journal_close(j);
+    }
+}
+
+#[test]
+fn disjunctions_and_conjunctions_combine_matches() {
+    let sent: Vec<String> = (1..=4).map(|i| format!("match test {}", i)).collect();
+    let extra: [&[(&str, &str)]; 4] = [
+        &[("SYSTEMD_SHIM_UNIT", "a"), ("PRIORITY", "3")],
+        &[("SYSTEMD_SHIM_UNIT", "b"), ("PRIORITY", "3")],
+        &[("SYSTEMD_SHIM_UNIT", "b"), ("PRIORITY", "6")],
+        &[("SYSTEMD_SHIM_UNIT", "c"), ("PRIORITY", "3")],
+    ];
+    unsafe {
+        let Some((j, rule)) = logged_with(&sent, &extra) else {
+            return;
+        };
+        let add = |m: &str| assert_eq!(systemd_shim_journal_add_match(j, m.as_ptr(), m.len()), 0);
+
+        // (unit a OR unit b) AND priority 3, of this run's entries
+        systemd_shim_journal_flush_matches(j);
+        add("SYSTEMD_SHIM_UNIT=a");
+        assert_eq!(systemd_shim_journal_add_disjunction(j), 0);
+        add("SYSTEMD_SHIM_UNIT=b");
+        assert_eq!(systemd_shim_journal_add_conjunction(j), 0);
+        add("PRIORITY=3");
+        add(&rule);
+        let found = messages(j, systemd_shim_journal_seek_head, systemd_shim_journal_next);
+        assert_eq!(found, &sent[..2]);
+
+        systemd_shim_journal_flush_matches(j);
+        add(&rule);
+        let found = messages(j, systemd_shim_journal_seek_head, systemd_shim_journal_next);
+        assert_eq!(found, sent);
+
+        systemd_shim_journal_close(j);
+    }
+}
+
+#[test]
+fn a_journal_copied_elsewhere_opens_by_directory_file_or_os_root() {
+    let sent: Vec<String> = (1..=2).map(|i| format!("copy test {}", i)).collect();
+    let rule = unsafe {
+        let Some((j, rule)) = logged(&sent) else {
+            return;
+        };
+        systemd_shim_journal_close(j);
+        rule
+    };
+    let machine_id = std::fs::read_to_string("/etc/machine-id").unwrap();
+    let Some(active) = journal::DIRECTORIES
+        .iter()
+        .map(|d| Path::new(d).join(machine_id.trim()).join("system.journal"))
+        .find(|f| f.exists())
+    else {
+        eprintln!("skipped: no system journal file to copy");
+        return;
+    };
+
+    // Laid out as on a rescue disk mounted at `root`
+    let root = std::env::temp_dir().join(format!("systemd-shim-test-{}", std::process::id()));
+    let dir = root.join("var/log/journal").join(machine_id.trim());
+    std::fs::create_dir_all(&dir).unwrap();
+    let copy = dir.join("system.journal");
+    std::fs::copy(&active, &copy).unwrap();
+
+    let read = |mut journal: journal::Journal| {
+        journal.add_match(&rule).unwrap();
+        journal.seek_head().unwrap();
+        let mut found = Vec::new();
+        while journal.next_entry().unwrap() {
+            found.push(journal.field("MESSAGE").unwrap());
+        }
+        found
+    };
+    let by_directory = read(journal::Journal::open_directory(&dir, 0).unwrap());
+    let by_file = read(journal::Journal::open_files(&[&copy]).unwrap());
+    let by_root = read(journal::Journal::open_directory(&root, journal::OS_ROOT).unwrap());
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(by_directory, sent);
+    assert_eq!(by_file, sent);
+    assert_eq!(by_root, sent);
+}
+
+#[test]
+fn unique_values_list_each_value_of_a_field_once() {
+    let sent: Vec<String> = (1..=3).map(|i| format!("unique test {}", i)).collect();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let (a, b) = (format!("{}-a", nanos), format!("{}-b", nanos));
+    let extra: [&[(&str, &str)]; 3] = [
+        &[("SYSTEMD_SHIM_UNIQUE", &a)],
+        &[("SYSTEMD_SHIM_UNIQUE", &b)],
+        &[("SYSTEMD_SHIM_UNIQUE", &a)],
+    ];
+    let ours = |values: Vec<String>| {
+        let mut values: Vec<String> = values
+            .into_iter()
+            .filter(|v| v.starts_with(&nanos.to_string()))
+            .collect();
+        values.sort();
+        values
+    };
+    unsafe {
+        let Some((j, _)) = logged_with(&sent, &extra) else {
+            return;
+        };
+        let field = CString::new("SYSTEMD_SHIM_UNIQUE").unwrap();
+        assert_eq!(systemd_shim_journal_query_unique(j, field.as_ptr()), 0);
+        let enumerate = || {
+            let mut values = Vec::new();
+            let (mut data, mut len) = (ptr::null(), 0);
+            while systemd_shim_journal_enumerate_unique(j, &mut data, &mut len) > 0 {
+                let value = String::from_utf8_lossy(std::slice::from_raw_parts(data, len));
+                values.push(value.trim_start_matches("SYSTEMD_SHIM_UNIQUE=").to_string());
+            }
+            values
+        };
+        assert_eq!(ours(enumerate()), [a.clone(), b.clone()]);
+
+        // Part way through, back to the first value
+        let (mut data, mut len) = (ptr::null(), 0);
+        assert!(systemd_shim_journal_enumerate_unique(j, &mut data, &mut len) > 0);
+        let first = std::slice::from_raw_parts(data, len).to_vec();
+        systemd_shim_journal_restart_unique(j);
+        assert!(systemd_shim_journal_enumerate_unique(j, &mut data, &mut len) > 0);
+        assert_eq!(std::slice::from_raw_parts(data, len), first);
+        systemd_shim_journal_close(j);
+    }
+
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    let values = journal.unique_values("SYSTEMD_SHIM_UNIQUE").unwrap();
+    assert_eq!(ours(values), [a, b]);
+}
+
+#[test]
+fn the_catalog_explains_an_entry_by_its_message_id() {
+    // systemd's "A start job for unit @UNIT@ has finished successfully"
+    let id = "39f53479d3a045ac8e11786248231fbf";
+    let mut text = ptr::null_mut();
+    let c_id = CString::new(id).unwrap();
+    if unsafe { systemd_shim_journal_get_catalog_for_message_id(c_id.as_ptr(), &mut text) } < 0 {
+        eprintln!("skipped: no message catalog here");
+        return;
+    }
+    let template = unsafe { std::ffi::CStr::from_ptr(text) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { systemd_shim_free_string(text) };
+    assert!(template.contains("@UNIT@"), "{}", template);
+    assert_eq!(journal::catalog_for_message_id(id).unwrap(), template);
+
+    let sent = vec!["catalog test".to_string()];
+    let extra: [&[(&str, &str)]; 1] = [&[("MESSAGE_ID", id), ("UNIT", "shim-test.service")]];
+    unsafe {
+        let Some((j, _)) = logged_with(&sent, &extra) else {
+            return;
+        };
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        let mut text = ptr::null_mut();
+        assert_eq!(systemd_shim_journal_get_catalog(j, &mut text), 0);
+        let explained = std::ffi::CStr::from_ptr(text)
+            .to_string_lossy()
+            .into_owned();
+        systemd_shim_free_string(text);
+        assert!(
+            explained.contains("unit shim-test.service has finished"),
+            "{}",
+            explained
+        );
+        assert!(!explained.contains("@UNIT@"));
+        systemd_shim_journal_close(j);
+    }
+}
+
+#[test]
+fn entries_sent_through_the_c_abi_read_back_field_for_field() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let tag = format!("SYSTEMD_SHIM_TEST={}-{}", std::process::id(), nanos);
+    let buffers: [&[u8]; 4] = [
+        b"MESSAGE=send test 1",
+        b"PRIORITY=4",
+        b"SYSTEMD_SHIM_DATA=two\nlines\0and a nul",
+        tag.as_bytes(),
+    ];
+    let iov: Vec<libc::iovec> = buffers
+        .iter()
+        .map(|b| libc::iovec {
+            iov_base: b.as_ptr() as *mut libc::c_void,
+            iov_len: b.len(),
+        })
+        .collect();
+    let r = unsafe { systemd_shim_journal_sendv(iov.as_ptr(), iov.len() as c_int) };
+    if r < 0 {
+        eprintln!("skipped: no journald to log to ({})", r);
+        return;
+    }
+    let strings: Vec<CString> = ["MESSAGE=send test 2", "PRIORITY=6", &tag]
+        .iter()
+        .map(|s| CString::new(*s).unwrap())
+        .collect();
+    let mut strv: Vec<*const libc::c_char> = strings.iter().map(|s| s.as_ptr()).collect();
+    strv.push(ptr::null());
+    assert_eq!(unsafe { systemd_shim_journal_send(strv.as_ptr()) }, 0);
+
+    // No `=` is no field
+    let bad = CString::new("MESSAGE").unwrap();
+    let bad = [bad.as_ptr(), ptr::null()];
+    assert_eq!(
+        unsafe { systemd_shim_journal_send(bad.as_ptr()) },
+        -libc::EINVAL
+    );
+
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.add_match(&tag).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut entries = Vec::new();
+    while entries.len() < 2 && Instant::now() < deadline {
+        sleep(Duration::from_millis(50));
+        entries.clear();
+        journal.seek_head().unwrap();
+        while journal.next_entry().unwrap() {
+            entries.push((
+                journal.field("MESSAGE"),
+                journal.field("PRIORITY"),
+                journal.field("SYSTEMD_SHIM_DATA"),
+            ));
+        }
+    }
+    if entries.is_empty() {
+        eprintln!("skipped: the entries logged are not readable here");
+        return;
+    }
+    let some = |s: &str| Some(s.to_string());
+    assert_eq!(
+        entries,
+        [
+            (
+                some("send test 1"),
+                some("4"),
+                some("two\nlines\0and a nul")
+            ),
+            (some("send test 2"), some("6"), None),
+        ]
+    );
+}
+
+#[test]
+fn printed_messages_keep_percent_signs_and_nuls() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let percent = format!("print test {} at 100%s %n %%", nanos);
+    let nul = format!("print test {}\0after the nul", nanos);
+
+    let c_percent = CString::new(percent.as_str()).unwrap();
+    let r = unsafe { systemd_shim_journal_print(libc::LOG_WARNING, c_percent.as_ptr()) };
+    if r < 0 {
+        eprintln!("skipped: no journald to log to ({})", r);
+        return;
+    }
+    journal::print(libc::LOG_DEBUG, &nul).unwrap();
+    assert_eq!(
+        unsafe { systemd_shim_journal_print(8, c_percent.as_ptr()) },
+        -libc::EINVAL
+    );
+    assert_eq!(
+        unsafe { systemd_shim_journal_print(libc::LOG_INFO, ptr::null()) },
+        -libc::EINVAL
+    );
+
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.add_match(&format!("MESSAGE={}", percent)).unwrap();
+    journal.add_match(&format!("MESSAGE={}", nul)).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut entries = Vec::new();
+    while entries.len() < 2 && Instant::now() < deadline {
+        sleep(Duration::from_millis(50));
+        entries.clear();
+        journal.seek_head().unwrap();
+        while journal.next_entry().unwrap() {
+            entries.push((journal.field("MESSAGE"), journal.field("PRIORITY")));
+        }
+    }
+    if entries.is_empty() {
+        eprintln!("skipped: the entries logged are not readable here");
+        return;
+    }
+    assert_eq!(
+        entries,
+        [
+            (Some(percent), Some("4".to_string())),
+            (Some(nul), Some("7".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn an_entry_comes_out_as_json_like_journalctl_writes_it() {
+    let sent = vec!["json test".to_string()];
+    let extra: [&[(&str, &str)]; 1] = [&[
+        ("SYSTEMD_SHIM_TWICE", "first"),
+        ("SYSTEMD_SHIM_TWICE", "second"),
+        ("SYSTEMD_SHIM_BINARY", "bell\x07"),
+    ]];
+    let (json, rule) = unsafe {
+        let Some((j, rule)) = logged_with(&sent, &extra) else {
+            return;
+        };
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        assert_eq!(systemd_shim_journal_next(j), 1);
+        let mut text = ptr::null_mut();
+        assert_eq!(systemd_shim_journal_get_entry_json(j, &mut text), 0);
+        let json = std::ffi::CStr::from_ptr(text)
+            .to_string_lossy()
+            .into_owned();
+        systemd_shim_free_string(text);
+        systemd_shim_journal_close(j);
+        (json, rule)
+    };
+    let entry: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(entry["MESSAGE"], "json test");
+    assert_eq!(
+        entry["SYSTEMD_SHIM_TWICE"],
+        serde_json::json!(["first", "second"])
+    );
+    assert_eq!(
+        entry["SYSTEMD_SHIM_BINARY"],
+        serde_json::json!([98, 101, 108, 108, 7])
+    );
+    assert!(entry["__CURSOR"].as_str().is_some_and(|c| !c.is_empty()));
+    let realtime: u64 = entry["__REALTIME_TIMESTAMP"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let boot_id = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").unwrap();
+    assert_eq!(entry["_BOOT_ID"], boot_id.trim().replace('-', ""));
+
+    // The Rust handle gives the same, and agrees with its own getters
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.add_match(&rule).unwrap();
+    journal.seek_head().unwrap();
+    assert!(journal.next_entry().unwrap());
+    assert_eq!(journal.entry_json().unwrap(), entry);
+    assert_eq!(journal.realtime_usec().unwrap(), realtime);
+}
+
+unsafe extern "C" fn collect(data: *const u8, len: usize, userdata: *mut libc::c_void) -> c_int {
+    let out = &mut *(userdata as *mut Vec<Vec<u8>>);
+    out.push(std::slice::from_raw_parts(data, len).to_vec());
+    0
+}
+
+unsafe extern "C" fn refuse(_: *const u8, _: usize, calls: *mut libc::c_void) -> c_int {
+    *(calls as *mut u32) += 1;
+    -libc::EPIPE
+}
+
+#[test]
+fn exported_entries_match_journalctl_byte_for_byte() {
+    let sent = vec!["export\ntest".to_string(), "export test".to_string()];
+    let extra: [&[(&str, &str)]; 1] = [&[
+        ("SYSTEMD_SHIM_TWICE", "first"),
+        ("SYSTEMD_SHIM_TWICE", "second"),
+        ("SYSTEMD_SHIM_BINARY", "bell\x07"),
+        ("SYSTEMD_SHIM_TAB", "a\tb"),
+    ]];
+    let (file, entries, rule) = unsafe {
+        let Some((j, rule)) = logged_with(&sent, &extra) else {
+            return;
+        };
+        let path = std::env::temp_dir().join(format!("shim-export-{}", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut count = 0;
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        assert_eq!(
+            systemd_shim_journal_export_fd(j, std::os::fd::AsRawFd::as_raw_fd(&file), &mut count),
+            0
+        );
+        assert_eq!(count, 2);
+        drop(file);
+        let file = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The callback has one call per entry, and a refusal ends it
+        let mut entries: Vec<Vec<u8>> = Vec::new();
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        let userdata = &mut entries as *mut _ as *mut libc::c_void;
+        assert_eq!(
+            systemd_shim_journal_export_callback(j, collect, userdata, ptr::null_mut()),
+            0
+        );
+        let mut calls = 0u32;
+        assert_eq!(systemd_shim_journal_seek_head(j), 0);
+        let userdata = &mut calls as *mut _ as *mut libc::c_void;
+        assert_eq!(
+            systemd_shim_journal_export_callback(j, refuse, userdata, ptr::null_mut()),
+            -libc::EPIPE
+        );
+        assert_eq!(calls, 1);
+        systemd_shim_journal_close(j);
+        (file, entries, rule)
+    };
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries.concat(), file);
+    // Text with a tab stays on its line; a newline or control character
+    // makes the value length-prefixed
+    let has = |field: &[u8]| entries[0].windows(field.len()).any(|w| w == field);
+    let sized = |name: &str, value: &[u8]| {
+        let mut field = format!("\n{}\n", name).into_bytes();
+        field.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        field.extend_from_slice(value);
+        field.push(b'\n');
+        field
+    };
+    assert!(entries[0].ends_with(b"\n\n"));
+    assert!(has(b"\nSYSTEMD_SHIM_TAB=a\tb\n"));
+    assert!(has(&sized("SYSTEMD_SHIM_BINARY", b"bell\x07")));
+    assert!(has(&sized("MESSAGE", b"export\ntest")));
+    assert!(entries[1]
+        .windows(21)
+        .any(|w| w == b"\nMESSAGE=export test\n"));
+
+    // The Rust handle writes the same
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.add_match(&rule).unwrap();
+    journal.seek_head().unwrap();
+    let mut out = Vec::new();
+    assert_eq!(journal.export(&mut out).unwrap(), 2);
+    assert_eq!(out, file);
+
+    match std::process::Command::new("journalctl")
+        .args(["-o", "export", &rule])
+        .output()
+    {
+        Ok(journalctl) if journalctl.status.success() => assert_eq!(journalctl.stdout, file),
+        _ => eprintln!("skipped the comparison: no journalctl to run"),
+    }
+}
+
+#[test]
+fn boots_are_listed_and_matched_like_journalctl_does() {
+    let sent = vec!["boot test".to_string()];
+    let uuid = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").unwrap();
+    let uuid = CString::new(uuid.trim()).unwrap();
+    let (json, rule) = unsafe {
+        let Some((j, rule)) = logged(&sent) else {
+            return;
+        };
+        let this_boot = |boot_id: *const libc::c_char| {
+            systemd_shim_journal_flush_matches(j);
+            assert!(systemd_shim_journal_add_match(j, rule.as_ptr(), rule.len()) >= 0);
+            assert_eq!(systemd_shim_journal_add_match_boot(j, boot_id), 0);
+            messages(j, systemd_shim_journal_seek_head, systemd_shim_journal_next)
+        };
+        // The running boot, by default or by its ID in either form
+        assert_eq!(this_boot(ptr::null()), sent);
+        assert_eq!(this_boot(uuid.as_ptr()), sent);
+        let other = CString::new("0123456789abcdef0123456789abcdef").unwrap();
+        assert!(this_boot(other.as_ptr()).is_empty());
+        let bad = CString::new("not a boot").unwrap();
+        assert!(systemd_shim_journal_add_match_boot(j, bad.as_ptr()) < 0);
+
+        // Listing the boots leaves the caller's matches as they were
+        assert_eq!(this_boot(ptr::null()), sent);
+        let mut text = ptr::null_mut();
+        assert_eq!(systemd_shim_journal_list_boots_json(j, &mut text), 0);
+        let json = std::ffi::CStr::from_ptr(text)
+            .to_string_lossy()
+            .into_owned();
+        systemd_shim_free_string(text);
+        let found = messages(j, systemd_shim_journal_seek_head, systemd_shim_journal_next);
+        assert_eq!(found, sent);
+        systemd_shim_journal_close(j);
+        (json, rule)
+    };
+
+    // The running boot is the newest, index 0, and spans the entry
+    let boots: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let boots = boots.as_array().unwrap();
+    let newest = boots.last().unwrap();
+    let boot_id = uuid.to_str().unwrap().replace('-', "");
+    assert_eq!(newest["boot_id"], boot_id.as_str());
+    assert_eq!(newest["index"], 0);
+    assert_eq!(boots[0]["index"], 1 - boots.len() as i64);
+
+    let mut journal = journal::Journal::open(journal::LOCAL_ONLY).unwrap();
+    journal.add_match(&rule).unwrap();
+    journal.match_this_boot().unwrap();
+    journal.seek_head().unwrap();
+    assert!(journal.next_entry().unwrap());
+    let logged_at = journal.realtime_usec().unwrap();
+    let listed = journal.boots().unwrap();
+    assert_eq!(listed.len(), boots.len());
+    let boot = listed.last().unwrap();
+    assert_eq!(boot.boot_id, boot_id);
+    assert_eq!(newest["first_entry"], boot.first_realtime_usec);
+    assert_eq!(newest["last_entry"], boot.last_realtime_usec);
+    assert!(boot.first_realtime_usec <= logged_at && logged_at <= boot.last_realtime_usec);
+}
@@ -16,6 +16,14 @@ const std = @import("std");
 // Opaque types
 const sd_bus = opaque {};
 const sd_bus_message = opaque {};
+const sd_bus_slot = opaque {};
+const sd_bus_creds = opaque {};
+const sd_bus_track = opaque {};
+const shim_match_rule = opaque {};
+const shim_vtable = opaque {};
+const shim_transient = opaque {};
+const shim_monitor = opaque {};
+const shim_reconnect = opaque {};
 const sd_journal = opaque {};
 
 // sd-bus error struct (simplified)
@@ -28,10 +36,22 @@ const sd_bus_error = extern struct {
 // Constants
 const SD_BUS_ERROR_NULL: sd_bus_error = .{ .name = null, .message = null, .need_free = 0 };
 const SD_JOURNAL_LOCAL_ONLY: c_int = 1;
+const SD_JOURNAL_OS_ROOT: c_int = 1 << 4;
 
 // Rust shim functions (from libsystemd_shim.so)
 extern "C" fn systemd_shim_bus_open_system(bus: *?*sd_bus) c_int;
+extern "C" fn systemd_shim_bus_open_user(bus: *?*sd_bus) c_int;
+extern "C" fn systemd_shim_bus_open_system_remote(bus: *?*sd_bus, host: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_open_system_machine(bus: *?*sd_bus, machine: [*:0]const u8) c_int;
 extern "C" fn systemd_shim_bus_unref(bus: *sd_bus) ?*sd_bus;
+extern "C" fn systemd_shim_bus_default(bus: *?*sd_bus) c_int;
+extern "C" fn systemd_shim_bus_default_system(bus: *?*sd_bus) c_int;
+extern "C" fn systemd_shim_bus_default_user(bus: *?*sd_bus) c_int;
+extern "C" fn systemd_shim_bus_flush(bus: *sd_bus) c_int;
+extern "C" fn systemd_shim_bus_close(bus: *sd_bus) void;
+extern "C" fn systemd_shim_bus_is_open(bus: *sd_bus) c_int;
+extern "C" fn systemd_shim_bus_close_unref(bus: *sd_bus) ?*sd_bus;
+extern "C" fn systemd_shim_bus_flush_close_unref(bus: *sd_bus) ?*sd_bus;
 extern "C" fn systemd_shim_bus_get_property_string(
     bus: *sd_bus,
     destination: [*:0]const u8,
@@ -41,25 +61,489 @@ extern "C" fn systemd_shim_bus_get_property_string(
     err: *sd_bus_error,
     out_value: *?[*:0]u8,
 ) c_int;
+extern "C" fn systemd_shim_bus_get_property_strv(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    err: *sd_bus_error,
+    out_value: *?[*:null]?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_bus_set_property_string(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    err: *sd_bus_error,
+    value: [*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_set_property_bool(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    err: *sd_bus_error,
+    value: c_int,
+) c_int;
+extern "C" fn systemd_shim_bus_set_property_u32(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    err: *sd_bus_error,
+    value: u32,
+) c_int;
+extern "C" fn systemd_shim_bus_set_property_u64(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    err: *sd_bus_error,
+    value: u64,
+) c_int;
+extern "C" fn systemd_shim_bus_get_all_properties_json(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    err: *sd_bus_error,
+    out_json: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_bus_get_managed_objects_json(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    err: *sd_bus_error,
+    out_json: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_bus_call_method(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    err: *sd_bus_error,
+    reply: *?*sd_bus_message,
+) c_int;
+extern "C" fn systemd_shim_bus_call_method_timeout(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+    usec: u64,
+    err: *sd_bus_error,
+    reply: *?*sd_bus_message,
+) c_int;
+extern "C" fn systemd_shim_bus_set_method_call_timeout(bus: *sd_bus, usec: u64) c_int;
+extern "C" fn systemd_shim_bus_get_method_call_timeout(bus: *sd_bus, usec: *u64) c_int;
+extern "C" fn systemd_shim_bus_introspect(
+    bus: *sd_bus,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    err: *sd_bus_error,
+    out_xml: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_bus_message_new_method_call(
+    bus: *sd_bus,
+    m: *?*sd_bus_message,
+    destination: [*:0]const u8,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_message_append_string(m: *sd_bus_message, s: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_message_append_basic(m: *sd_bus_message, type_: u8, p: *const anyopaque) c_int;
+extern "C" fn systemd_shim_bus_message_append_object_path(m: *sd_bus_message, path: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_message_append_bool(m: *sd_bus_message, b: c_int) c_int;
+extern "C" fn systemd_shim_bus_message_append_i32(m: *sd_bus_message, v: i32) c_int;
+extern "C" fn systemd_shim_bus_message_append_u32(m: *sd_bus_message, v: u32) c_int;
+extern "C" fn systemd_shim_bus_message_append_i64(m: *sd_bus_message, v: i64) c_int;
+extern "C" fn systemd_shim_bus_message_append_u64(m: *sd_bus_message, v: u64) c_int;
+extern "C" fn systemd_shim_bus_message_append_double(m: *sd_bus_message, v: f64) c_int;
+extern "C" fn systemd_shim_bus_message_append_fd(m: *sd_bus_message, fd: c_int) c_int;
+extern "C" fn systemd_shim_bus_message_open_container(
+    m: *sd_bus_message,
+    type_: u8,
+    contents: [*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_message_close_container(m: *sd_bus_message) c_int;
+extern "C" fn systemd_shim_bus_message_read_basic(m: *sd_bus_message, type_: u8, p: *anyopaque) c_int;
+extern "C" fn systemd_shim_bus_message_read_string(m: *sd_bus_message, s: *?[*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_message_read_object_path(m: *sd_bus_message, path: *?[*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_message_read_bool(m: *sd_bus_message, b: *c_int) c_int;
+extern "C" fn systemd_shim_bus_message_read_i32(m: *sd_bus_message, v: *i32) c_int;
+extern "C" fn systemd_shim_bus_message_read_u32(m: *sd_bus_message, v: *u32) c_int;
+extern "C" fn systemd_shim_bus_message_read_i64(m: *sd_bus_message, v: *i64) c_int;
+extern "C" fn systemd_shim_bus_message_read_u64(m: *sd_bus_message, v: *u64) c_int;
+extern "C" fn systemd_shim_bus_message_read_double(m: *sd_bus_message, v: *f64) c_int;
+extern "C" fn systemd_shim_bus_message_read_fd(m: *sd_bus_message, fd: *c_int) c_int;
+extern "C" fn systemd_shim_bus_message_enter_container(
+    m: *sd_bus_message,
+    type_: u8,
+    contents: ?[*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_message_exit_container(m: *sd_bus_message) c_int;
+extern "C" fn systemd_shim_bus_message_peek_type(
+    m: *sd_bus_message,
+    type_: *u8,
+    contents: *?[*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_message_skip(m: *sd_bus_message, types: ?[*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_message_dump(m: *sd_bus_message, flags: u64, ret: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_bus_negotiate_timestamp(bus: *sd_bus, enable: c_int) c_int;
+extern "C" fn systemd_shim_bus_message_get_realtime_usec(m: *sd_bus_message, usec: *u64) c_int;
+extern "C" fn systemd_shim_bus_message_get_monotonic_usec(m: *sd_bus_message, usec: *u64) c_int;
+extern "C" fn systemd_shim_bus_message_get_seqnum(m: *sd_bus_message, seqnum: *u64) c_int;
+extern "C" fn systemd_shim_bus_message_get_signature(m: *sd_bus_message, complete: c_int) ?[*:0]const u8;
+extern "C" fn systemd_shim_signature_validate(signature: [*:0]const u8, err: ?*?[*:0]u8) c_int;
+extern "C" fn systemd_shim_signature_validate_single(signature: [*:0]const u8, err: ?*?[*:0]u8) c_int;
+extern "C" fn systemd_shim_bus_call(
+    bus: *sd_bus,
+    m: *sd_bus_message,
+    usec: u64,
+    err: *sd_bus_error,
+    reply: *?*sd_bus_message,
+) c_int;
+extern "C" fn systemd_shim_bus_message_unref(m: *sd_bus_message) ?*sd_bus_message;
+/// Called from `Bus.process` for each message a match lets through; the message is borrowed
+pub const SignalHandler = *const fn (
+    sender: ?[*:0]const u8,
+    path: ?[*:0]const u8,
+    interface: ?[*:0]const u8,
+    member: ?[*:0]const u8,
+    m: *sd_bus_message,
+    userdata: ?*anyopaque,
+) callconv(.C) c_int;
+extern "C" fn systemd_shim_bus_add_match(
+    bus: *sd_bus,
+    slot: *?*sd_bus_slot,
+    match: [*:0]const u8,
+    handler: SignalHandler,
+    userdata: ?*anyopaque,
+) c_int;
+extern "C" fn systemd_shim_bus_query_sender_creds(m: *sd_bus_message, creds: *?*sd_bus_creds) c_int;
+extern "C" fn systemd_shim_bus_get_name_creds(bus: *sd_bus, name: [*:0]const u8, creds: *?*sd_bus_creds) c_int;
+extern "C" fn systemd_shim_bus_creds_unref(creds: *sd_bus_creds) ?*sd_bus_creds;
+extern "C" fn systemd_shim_bus_creds_get_pid(creds: *sd_bus_creds, pid: *i32) c_int;
+extern "C" fn systemd_shim_bus_creds_get_uid(creds: *sd_bus_creds, uid: *u32) c_int;
+extern "C" fn systemd_shim_bus_creds_get_euid(creds: *sd_bus_creds, uid: *u32) c_int;
+extern "C" fn systemd_shim_bus_creds_get_gid(creds: *sd_bus_creds, gid: *u32) c_int;
+extern "C" fn systemd_shim_bus_creds_get_selinux_context(creds: *sd_bus_creds, context: *?[*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_creds_get_unit(creds: *sd_bus_creds, unit: *?[*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_creds_get_cgroup(creds: *sd_bus_creds, cgroup: *?[*:0]const u8) c_int;
+/// Called from `Bus.process` once a tracker's last name is gone; return 1 when
+/// done, as 0 has it called again while the tracker stays empty
+pub const TrackHandler = *const fn (track: *sd_bus_track, userdata: ?*anyopaque) callconv(.C) c_int;
+extern "C" fn systemd_shim_bus_track_new(bus: *sd_bus, track: *?*sd_bus_track, handler: ?TrackHandler, userdata: ?*anyopaque) c_int;
+extern "C" fn systemd_shim_bus_track_unref(track: *sd_bus_track) ?*sd_bus_track;
+extern "C" fn systemd_shim_bus_track_add_name(track: *sd_bus_track, name: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_track_add_sender(track: *sd_bus_track, m: *sd_bus_message) c_int;
+extern "C" fn systemd_shim_bus_track_remove_name(track: *sd_bus_track, name: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_track_count(track: *sd_bus_track) c_uint;
+extern "C" fn systemd_shim_bus_track_contains(track: *sd_bus_track, name: [*:0]const u8) c_int;
+/// Called from `Monitor.process` with each message the monitor sees; the message is borrowed
+pub const MonitorHandler = *const fn (m: *sd_bus_message, userdata: ?*anyopaque) callconv(.C) c_int;
+extern "C" fn systemd_shim_monitor_open(
+    address: ?[*:0]const u8,
+    rules: ?[*:null]const ?[*:0]const u8,
+    handler: MonitorHandler,
+    userdata: ?*anyopaque,
+    monitor: *?*shim_monitor,
+) c_int;
+extern "C" fn systemd_shim_monitor_free(monitor: *shim_monitor) void;
+extern "C" fn systemd_shim_monitor_bus(monitor: *const shim_monitor) *sd_bus;
+extern "C" fn systemd_shim_monitor_process(monitor: *shim_monitor) c_int;
+extern "C" fn systemd_shim_monitor_received(monitor: *const shim_monitor, realtime: ?*u64, monotonic: ?*u64) c_int;
+/// Called from `ReconnectingBus.process` once a lost connection is back with its matches
+pub const ReconnectHandler = *const fn (bus: *sd_bus, userdata: ?*anyopaque) callconv(.C) c_int;
+extern "C" fn systemd_shim_reconnect_open(
+    address: ?[*:0]const u8,
+    reconnected: ?ReconnectHandler,
+    userdata: ?*anyopaque,
+    conn: *?*shim_reconnect,
+) c_int;
+extern "C" fn systemd_shim_reconnect_free(conn: *shim_reconnect) void;
+extern "C" fn systemd_shim_reconnect_bus(conn: *const shim_reconnect) ?*sd_bus;
+extern "C" fn systemd_shim_reconnect_add_match(
+    conn: *shim_reconnect,
+    match: [*:0]const u8,
+    handler: SignalHandler,
+    userdata: ?*anyopaque,
+) c_int;
+extern "C" fn systemd_shim_reconnect_remove_match(conn: *shim_reconnect, id: c_int) c_int;
+extern "C" fn systemd_shim_reconnect_process(conn: *shim_reconnect) c_int;
+extern "C" fn systemd_shim_reconnect_wait(conn: *shim_reconnect, timeout_usec: u64) c_int;
+/// Handles a call to a served method and replies to it, now or later; `call` is borrowed
+pub const MethodHandler = *const fn (
+    call: *sd_bus_message,
+    userdata: ?*anyopaque,
+    err: *sd_bus_error,
+) callconv(.C) c_int;
+/// Appends a served property's current value to `reply`, without a variant around it
+pub const PropertyGetter = *const fn (
+    reply: *sd_bus_message,
+    userdata: ?*anyopaque,
+    err: *sd_bus_error,
+) callconv(.C) c_int;
+extern "C" fn systemd_shim_vtable_add_property(
+    vtable: *shim_vtable,
+    member: [*:0]const u8,
+    signature: [*:0]const u8,
+    getter: PropertyGetter,
+    userdata: ?*anyopaque,
+) c_int;
+extern "C" fn systemd_shim_bus_message_new_signal(
+    bus: *sd_bus,
+    m: *?*sd_bus_message,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_emit_signal(
+    bus: *sd_bus,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    member: [*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_emit_properties_changed(
+    bus: *sd_bus,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    names: ?[*:null]const ?[*:0]const u8,
+) c_int;
+extern "C" fn systemd_shim_bus_request_name(bus: *sd_bus, name: [*:0]const u8, flags: u64) c_int;
+extern "C" fn systemd_shim_bus_release_name(bus: *sd_bus, name: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_vtable_new() ?*shim_vtable;
+extern "C" fn systemd_shim_vtable_free(vtable: *shim_vtable) void;
+extern "C" fn systemd_shim_vtable_add_method(
+    vtable: *shim_vtable,
+    member: [*:0]const u8,
+    signature: [*:0]const u8,
+    result: [*:0]const u8,
+    unprivileged: c_int,
+    handler: MethodHandler,
+    userdata: ?*anyopaque,
+) c_int;
+extern "C" fn systemd_shim_bus_add_object_vtable(
+    bus: *sd_bus,
+    slot: *?*sd_bus_slot,
+    path: [*:0]const u8,
+    interface: [*:0]const u8,
+    vtable: *shim_vtable,
+) c_int;
+extern "C" fn systemd_shim_bus_message_new_method_return(call: *sd_bus_message, reply: *?*sd_bus_message) c_int;
+extern "C" fn systemd_shim_bus_send(bus: ?*sd_bus, m: *sd_bus_message) c_int;
+extern "C" fn systemd_shim_bus_reply_method_return(call: *sd_bus_message) c_int;
+extern "C" fn systemd_shim_bus_reply_method_error(call: *sd_bus_message, name: [*:0]const u8, message: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_error_set(err: *sd_bus_error, name: [*:0]const u8, message: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_new() ?*shim_match_rule;
+extern "C" fn systemd_shim_match_free(rule: *shim_match_rule) void;
+extern "C" fn systemd_shim_match_set_type(rule: *shim_match_rule, kind: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_set_sender(rule: *shim_match_rule, sender: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_set_interface(rule: *shim_match_rule, interface: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_set_member(rule: *shim_match_rule, member: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_set_path(rule: *shim_match_rule, path: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_set_path_namespace(rule: *shim_match_rule, path: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_set_arg(rule: *shim_match_rule, index: c_uint, value: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_match_to_string(rule: *const shim_match_rule, ret: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_bus_add_match_rule(
+    bus: *sd_bus,
+    slot: *?*sd_bus_slot,
+    rule: *const shim_match_rule,
+    handler: SignalHandler,
+    userdata: ?*anyopaque,
+) c_int;
+/// Called from `Bus.process` with the reply to `Bus.callAsync`; `err` is null on success.
+/// Both are borrowed
+pub const ReplyHandler = *const fn (
+    reply: *sd_bus_message,
+    err: ?*const sd_bus_error,
+    userdata: ?*anyopaque,
+) callconv(.C) c_int;
+extern "C" fn systemd_shim_bus_call_async(
+    bus: *sd_bus,
+    slot: *?*sd_bus_slot,
+    m: *sd_bus_message,
+    handler: ReplyHandler,
+    userdata: ?*anyopaque,
+    usec: u64,
+) c_int;
+extern "C" fn systemd_shim_bus_slot_unref(slot: *sd_bus_slot) ?*sd_bus_slot;
+extern "C" fn systemd_shim_bus_process(bus: *sd_bus) c_int;
+extern "C" fn systemd_shim_bus_wait(bus: *sd_bus, timeout_usec: u64) c_int;
+extern "C" fn systemd_shim_bus_get_fd(bus: *sd_bus) c_int;
+extern "C" fn systemd_shim_bus_get_events(bus: *sd_bus) c_int;
+extern "C" fn systemd_shim_bus_get_timeout(bus: *sd_bus, timeout_usec: *u64) c_int;
+extern "C" fn systemd_shim_unit_start(bus: *sd_bus, unit: [*:0]const u8, mode: [*:0]const u8, err: *sd_bus_error, job: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_stop(bus: *sd_bus, unit: [*:0]const u8, mode: [*:0]const u8, err: *sd_bus_error, job: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_restart(bus: *sd_bus, unit: [*:0]const u8, mode: [*:0]const u8, err: *sd_bus_error, job: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_reload(bus: *sd_bus, unit: [*:0]const u8, mode: [*:0]const u8, err: *sd_bus_error, job: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_list_json(bus: *sd_bus, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_get_status(bus: *sd_bus, unit: [*:0]const u8, err: *sd_bus_error, status: *UnitSnapshot) c_int;
+extern "C" fn systemd_shim_unit_get_resources(bus: *sd_bus, unit: [*:0]const u8, err: *sd_bus_error, resources: *UnitResources) c_int;
+extern "C" fn systemd_shim_unit_freeze(bus: *sd_bus, unit: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_unit_thaw(bus: *sd_bus, unit: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_unit_kill(bus: *sd_bus, unit: [*:0]const u8, who: [*:0]const u8, signal: c_int, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_login_list_sessions_json(bus: *sd_bus, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_login_lock_session(bus: *sd_bus, session: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_login_terminate_session(bus: *sd_bus, session: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_login_inhibit(bus: *sd_bus, what: [*:0]const u8, who: [*:0]const u8, why: [*:0]const u8, mode: [*:0]const u8, err: *sd_bus_error, fd: *c_int) c_int;
+extern "C" fn systemd_shim_hostname_get_json(bus: *sd_bus, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_hostname_set(bus: *sd_bus, name: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_hostname_set_static(bus: *sd_bus, name: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_hostname_set_pretty(bus: *sd_bus, name: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_timedate_get_timezone(bus: *sd_bus, err: *sd_bus_error, ret: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_timedate_set_timezone(bus: *sd_bus, timezone: [*:0]const u8, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_timedate_get_ntp(bus: *sd_bus, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_timedate_set_ntp(bus: *sd_bus, enable: c_int, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_timedate_ntp_synchronized(bus: *sd_bus, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_machine_list_json(bus: *sd_bus, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_machine_get(bus: *sd_bus, name: [*:0]const u8, err: *sd_bus_error, path: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_machine_addresses_json(
+    bus: *sd_bus,
+    name: [*:0]const u8,
+    err: *sd_bus_error,
+    out_json: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_resolve_hostname_json(
+    bus: *sd_bus,
+    ifindex: c_int,
+    name: [*:0]const u8,
+    family: c_int,
+    flags: u64,
+    err: *sd_bus_error,
+    out_json: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_resolve_address_json(
+    bus: *sd_bus,
+    ifindex: c_int,
+    address: [*:0]const u8,
+    flags: u64,
+    err: *sd_bus_error,
+    out_json: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_resolve_service_json(
+    bus: *sd_bus,
+    ifindex: c_int,
+    name: ?[*:0]const u8,
+    type_: [*:0]const u8,
+    domain: [*:0]const u8,
+    family: c_int,
+    flags: u64,
+    err: *sd_bus_error,
+    out_json: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_resolve_flush_caches(bus: *sd_bus, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_resolve_reset_statistics(bus: *sd_bus, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_manager_reload(bus: *sd_bus, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_manager_reexecute(bus: *sd_bus, err: *sd_bus_error) c_int;
+extern "C" fn systemd_shim_transient_new() ?*shim_transient;
+extern "C" fn systemd_shim_transient_free(props: *shim_transient) void;
+extern "C" fn systemd_shim_transient_set_string(props: *shim_transient, name: [*:0]const u8, value: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_transient_set_bool(props: *shim_transient, name: [*:0]const u8, value: c_int) c_int;
+extern "C" fn systemd_shim_transient_set_u64(props: *shim_transient, name: [*:0]const u8, value: u64) c_int;
+extern "C" fn systemd_shim_transient_exec_start(props: *shim_transient, argv: [*:null]const ?[*:0]const u8, ignore_failure: c_int) c_int;
+extern "C" fn systemd_shim_transient_pids(props: *shim_transient, pids: [*]const u32, n: usize) c_int;
+extern "C" fn systemd_shim_transient_memory_max(props: *shim_transient, bytes: u64) c_int;
+extern "C" fn systemd_shim_transient_cpu_quota(props: *shim_transient, percent: c_uint) c_int;
+extern "C" fn systemd_shim_transient_collect_mode(props: *shim_transient, mode: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_unit_start_transient(bus: *sd_bus, name: [*:0]const u8, mode: [*:0]const u8, props: *const shim_transient, err: *sd_bus_error, job: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_files_list_json(bus: *sd_bus, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_files_enable(bus: *sd_bus, files: [*:null]const ?[*:0]const u8, runtime: c_int, force: c_int, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_unit_files_disable(bus: *sd_bus, files: [*:null]const ?[*:0]const u8, runtime: c_int, err: *sd_bus_error, out_json: *?[*:0]u8) c_int;
 extern "C" fn systemd_shim_bus_error_free(err: *sd_bus_error) void;
+extern "C" fn systemd_shim_bus_error_is_set(err: *const sd_bus_error) c_int;
+extern "C" fn systemd_shim_bus_error_get_name(err: *const sd_bus_error) ?[*:0]const u8;
+extern "C" fn systemd_shim_bus_error_get_message(err: *const sd_bus_error) ?[*:0]const u8;
+extern "C" fn systemd_shim_bus_error_has_name(err: *const sd_bus_error, name: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_bus_error_get_errno(err: *const sd_bus_error) c_int;
+extern "C" fn systemd_shim_bus_error_code(err: *const sd_bus_error) c_int;
 extern "C" fn systemd_shim_free_string(s: [*:0]u8) void;
+extern "C" fn systemd_shim_free_strv(l: [*:null]?[*:0]u8) void;
 
 extern "C" fn systemd_shim_journal_open(journal: *?*sd_journal, flags: c_int) c_int;
+extern "C" fn systemd_shim_journal_open_directory(
+    journal: *?*sd_journal,
+    path: [*:0]const u8,
+    flags: c_int,
+) c_int;
+extern "C" fn systemd_shim_journal_open_files(
+    journal: *?*sd_journal,
+    paths: [*:null]const ?[*:0]const u8,
+    flags: c_int,
+) c_int;
 extern "C" fn systemd_shim_journal_close(journal: *sd_journal) void;
 extern "C" fn systemd_shim_journal_add_match(
     journal: *sd_journal,
     data: [*]const u8,
     len: usize,
 ) c_int;
+extern "C" fn systemd_shim_journal_add_disjunction(journal: *sd_journal) c_int;
+extern "C" fn systemd_shim_journal_add_conjunction(journal: *sd_journal) c_int;
+extern "C" fn systemd_shim_journal_flush_matches(journal: *sd_journal) void;
+extern "C" fn systemd_shim_journal_seek_head(journal: *sd_journal) c_int;
 extern "C" fn systemd_shim_journal_seek_tail(journal: *sd_journal) c_int;
 extern "C" fn systemd_shim_journal_previous(journal: *sd_journal) c_int;
 extern "C" fn systemd_shim_journal_next(journal: *sd_journal) c_int;
+extern "C" fn systemd_shim_journal_previous_skip(journal: *sd_journal, skip: u64) c_int;
+extern "C" fn systemd_shim_journal_next_skip(journal: *sd_journal, skip: u64) c_int;
 extern "C" fn systemd_shim_journal_get_data(
     journal: *sd_journal,
     field: [*:0]const u8,
     data: *?[*]const u8,
     len: *usize,
 ) c_int;
+extern "C" fn systemd_shim_journal_get_cursor(journal: *sd_journal, cursor: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_journal_seek_cursor(journal: *sd_journal, cursor: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_journal_test_cursor(journal: *sd_journal, cursor: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_journal_get_realtime_usec(journal: *sd_journal, usec: *u64) c_int;
+extern "C" fn systemd_shim_journal_get_monotonic_usec(
+    journal: *sd_journal,
+    usec: *u64,
+    boot_id: ?*?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_journal_query_unique(journal: *sd_journal, field: [*:0]const u8) c_int;
+extern "C" fn systemd_shim_journal_enumerate_unique(
+    journal: *sd_journal,
+    data: *?[*]const u8,
+    len: *usize,
+) c_int;
+extern "C" fn systemd_shim_journal_restart_unique(journal: *sd_journal) void;
+extern "C" fn systemd_shim_journal_get_catalog(journal: *sd_journal, text: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_journal_get_catalog_for_message_id(
+    message_id: [*:0]const u8,
+    text: *?[*:0]u8,
+) c_int;
+extern "C" fn systemd_shim_journal_sendv(fields: [*]const Iovec, n: c_int) c_int;
+extern "C" fn systemd_shim_journal_get_entry_json(journal: *sd_journal, ret: *?[*:0]u8) c_int;
+extern "C" fn systemd_shim_journal_add_match_boot(journal: *sd_journal, boot_id: ?[*:0]const u8) c_int;
+extern "C" fn systemd_shim_journal_list_boots_json(journal: *sd_journal, ret: *?[*:0]u8) c_int;
+/// Called from `Journal.exportTo` with each entry in the Journal Export Format
+pub const ExportWriter = *const fn (data: [*]const u8, len: usize, userdata: ?*anyopaque) callconv(.C) c_int;
+extern "C" fn systemd_shim_journal_export_fd(journal: *sd_journal, fd: c_int, count: ?*u64) c_int;
+extern "C" fn systemd_shim_journal_export_callback(
+    journal: *sd_journal,
+    writer: ExportWriter,
+    userdata: ?*anyopaque,
+    count: ?*u64,
+) c_int;
+extern "C" fn systemd_shim_journal_wait(journal: *sd_journal, timeout_usec: u64) c_int;
+extern "C" fn systemd_shim_journal_get_fd(journal: *sd_journal) c_int;
+extern "C" fn systemd_shim_journal_get_events(journal: *sd_journal) c_int;
+extern "C" fn systemd_shim_journal_get_timeout(journal: *sd_journal, timeout_usec: *u64) c_int;
+extern "C" fn systemd_shim_journal_process(journal: *sd_journal) c_int;
+extern "C" fn systemd_shim_journal_seek_realtime_usec(journal: *sd_journal, usec: u64) c_int;
+extern "C" fn systemd_shim_journal_seek_monotonic_usec(
+    journal: *sd_journal,
+    boot_id: ?[*:0]const u8,
+    usec: u64,
+) c_int;
 
 // =============================================================================
 // Zig API
@@ -71,9 +555,66 @@ pub const Error = error{
     CallFailed,
     JournalOpenFailed,
     JournalSeekFailed,
+    JournalSendFailed,
     AllocationFailed,
+    InvalidMatchRule,
+    InvalidProperty,
+};
+
+/// The shim's stable codes for common org.freedesktop.DBus.Error names
+pub const ErrorCode = enum(c_int) {
+    none = 0,
+    failed = 1,
+    no_memory = 2,
+    service_unknown = 3,
+    name_has_no_owner = 4,
+    no_reply = 5,
+    timeout = 6,
+    disconnected = 7,
+    access_denied = 8,
+    auth_failed = 9,
+    interactive_authorization_required = 10,
+    invalid_args = 11,
+    unknown_method = 12,
+    unknown_object = 13,
+    unknown_interface = 14,
+    unknown_property = 15,
+    property_read_only = 16,
+    not_supported = 17,
+    limits_exceeded = 18,
+    _,
+
+    /// The code for an error a call filled in; `failed` for names without one
+    pub fn of(err: *const sd_bus_error) ErrorCode {
+        return @enumFromInt(systemd_shim_bus_error_code(err));
+    }
 };
 
+/// Name and message of an error a call filled in; null when unset
+fn errorName(err: *const sd_bus_error) ?[]const u8 {
+    if (systemd_shim_bus_error_is_set(err) <= 0) return null;
+    return std.mem.span(systemd_shim_bus_error_get_name(err) orelse return null);
+}
+
+fn errorMessage(err: *const sd_bus_error) ?[]const u8 {
+    return std.mem.span(systemd_shim_bus_error_get_message(err) orelse return null);
+}
+
+/// What is wrong with `signature`, owned by the caller, or null if it is
+/// valid: any number of complete types, or exactly one when `single`
+/// (as for a property or variant)
+pub fn signatureProblem(allocator: std.mem.Allocator, signature: [*:0]const u8, single: bool) Error!?[]u8 {
+    var problem: ?[*:0]u8 = null;
+    const ret = if (single)
+        systemd_shim_signature_validate_single(signature, &problem)
+    else
+        systemd_shim_signature_validate(signature, &problem);
+    if (ret >= 0) return null;
+    const text = problem orelse return Error.AllocationFailed;
+    defer systemd_shim_free_string(text);
+    return allocator.dupe(u8, std.mem.span(text)) catch Error.AllocationFailed;
+}
+
 /// Unit active state
 pub const ActiveState = enum {
     active,
@@ -108,22 +649,1208 @@ pub const UnitStatus = struct {
     pub fn isRunning(self: UnitStatus) bool {
         return self.active_state == .active;
     }
-};
+};
+
+/// Which of a unit's processes `Bus.killUnit` signals
+pub const KillWho = enum {
+    /// The main process only
+    main,
+    /// The process running ExecReload= or another control command
+    control,
+    /// Every process in the unit's cgroup
+    all,
+};
+
+/// A unit's states and main process, all read at once by `Bus.unitSnapshot`.
+/// Laid out as the shim's `UnitStatus`
+pub const UnitSnapshot = extern struct {
+    active_state_buf: [32]u8,
+    sub_state_buf: [32]u8,
+    load_state_buf: [32]u8,
+    /// 0 unless a service with a running main process
+    main_pid: u32,
+    exec_main_status: c_int,
+    /// µs since the epoch, 0 if never active
+    active_enter_timestamp: u64,
+
+    pub fn activeState(self: *const UnitSnapshot) ActiveState {
+        return ActiveState.fromString(std.mem.sliceTo(&self.active_state_buf, 0));
+    }
+
+    pub fn subState(self: *const UnitSnapshot) []const u8 {
+        return std.mem.sliceTo(&self.sub_state_buf, 0);
+    }
+
+    pub fn loadState(self: *const UnitSnapshot) []const u8 {
+        return std.mem.sliceTo(&self.load_state_buf, 0);
+    }
+};
+
+/// A unit's cgroup usage from `Bus.unitResources`, laid out as the shim's
+/// `UnitResources`. Figures without accounting are `not_tracked`
+pub const UnitResources = extern struct {
+    memory_current: u64,
+    cpu_usage_nsec: u64,
+    tasks_current: u64,
+    io_read_bytes: u64,
+    io_write_bytes: u64,
+
+    pub const not_tracked: u64 = std.math.maxInt(u64);
+
+    /// `figure`, or null when it is not tracked
+    pub fn known(figure: u64) ?u64 {
+        return if (figure == not_tracked) null else figure;
+    }
+};
+
+/// A method call being built, or a reply being read
+pub const Message = struct {
+    msg: *sd_bus_message,
+
+    pub fn deinit(self: *Message) void {
+        _ = systemd_shim_bus_message_unref(self.msg);
+    }
+
+    /// Start the reply to this call, to append the result to and `send`
+    pub fn newMethodReturn(self: *Message) Error!Message {
+        var reply: ?*sd_bus_message = null;
+        if (systemd_shim_bus_message_new_method_return(self.msg, &reply) < 0) {
+            return Error.MessageFailed;
+        }
+        return Message{ .msg = reply.? };
+    }
+
+    /// Send this message on the connection it belongs to, without waiting
+    pub fn send(self: *Message) Error!void {
+        if (systemd_shim_bus_send(null, self.msg) < 0) return Error.CallFailed;
+    }
+
+    /// Reply to this call with no result
+    pub fn replyEmpty(self: *Message) Error!void {
+        if (systemd_shim_bus_reply_method_return(self.msg) < 0) return Error.CallFailed;
+    }
+
+    /// Reply to this call with the D-Bus error `name`
+    pub fn replyError(self: *Message, name: [*:0]const u8, message: [*:0]const u8) Error!void {
+        if (systemd_shim_bus_reply_method_error(self.msg, name, message) < 0) return Error.CallFailed;
+    }
+
+    /// Credentials of whoever sent this message
+    pub fn senderCreds(self: *Message) Error!Creds {
+        var creds: ?*sd_bus_creds = null;
+        if (systemd_shim_bus_query_sender_creds(self.msg, &creds) < 0) {
+            return Error.MessageFailed;
+        }
+        return Creds{ .creds = creds.? };
+    }
+
+    pub fn appendString(self: *Message, allocator: std.mem.Allocator, s: []const u8) Error!void {
+        const s_z = allocator.dupeZ(u8, s) catch return Error.AllocationFailed;
+        defer allocator.free(s_z);
+        if (systemd_shim_bus_message_append_string(self.msg, s_z.ptr) < 0) {
+            return Error.MessageFailed;
+        }
+    }
+
+    pub fn appendObjectPath(self: *Message, path: [*:0]const u8) Error!void {
+        return check(systemd_shim_bus_message_append_object_path(self.msg, path));
+    }
+
+    pub fn appendBool(self: *Message, b: bool) Error!void {
+        return check(systemd_shim_bus_message_append_bool(self.msg, @intFromBool(b)));
+    }
+
+    pub fn appendI32(self: *Message, v: i32) Error!void {
+        return check(systemd_shim_bus_message_append_i32(self.msg, v));
+    }
+
+    pub fn appendU32(self: *Message, v: u32) Error!void {
+        return check(systemd_shim_bus_message_append_u32(self.msg, v));
+    }
+
+    pub fn appendI64(self: *Message, v: i64) Error!void {
+        return check(systemd_shim_bus_message_append_i64(self.msg, v));
+    }
+
+    pub fn appendU64(self: *Message, v: u64) Error!void {
+        return check(systemd_shim_bus_message_append_u64(self.msg, v));
+    }
+
+    pub fn appendDouble(self: *Message, v: f64) Error!void {
+        return check(systemd_shim_bus_message_append_double(self.msg, v));
+    }
+
+    /// Pass `fd` along; the message sends a duplicate and `fd` stays the caller's
+    pub fn appendFd(self: *Message, fd: c_int) Error!void {
+        return check(systemd_shim_bus_message_append_fd(self.msg, fd));
+    }
+
+    /// Open an array (`a`), variant (`v`), struct (`r`) or dict entry (`e`) of `contents`
+    pub fn openContainer(self: *Message, type_: u8, contents: [*:0]const u8) Error!void {
+        return check(systemd_shim_bus_message_open_container(self.msg, type_, contents));
+    }
+
+    pub fn closeContainer(self: *Message) Error!void {
+        return check(systemd_shim_bus_message_close_container(self.msg));
+    }
+
+    // Reads return null at the end of the enclosing container. Strings
+    // point into the message and live as long as it does.
+
+    pub fn readString(self: *Message) Error!?[]const u8 {
+        var s: ?[*:0]const u8 = null;
+        if (try read(systemd_shim_bus_message_read_string(self.msg, &s))) {
+            return std.mem.span(s.?);
+        }
+        return null;
+    }
+
+    pub fn readObjectPath(self: *Message) Error!?[]const u8 {
+        var s: ?[*:0]const u8 = null;
+        if (try read(systemd_shim_bus_message_read_object_path(self.msg, &s))) {
+            return std.mem.span(s.?);
+        }
+        return null;
+    }
+
+    pub fn readBool(self: *Message) Error!?bool {
+        var b: c_int = 0;
+        if (try read(systemd_shim_bus_message_read_bool(self.msg, &b))) return b != 0;
+        return null;
+    }
+
+    pub fn readI32(self: *Message) Error!?i32 {
+        var v: i32 = 0;
+        if (try read(systemd_shim_bus_message_read_i32(self.msg, &v))) return v;
+        return null;
+    }
+
+    pub fn readU32(self: *Message) Error!?u32 {
+        var v: u32 = 0;
+        if (try read(systemd_shim_bus_message_read_u32(self.msg, &v))) return v;
+        return null;
+    }
+
+    pub fn readI64(self: *Message) Error!?i64 {
+        var v: i64 = 0;
+        if (try read(systemd_shim_bus_message_read_i64(self.msg, &v))) return v;
+        return null;
+    }
+
+    pub fn readU64(self: *Message) Error!?u64 {
+        var v: u64 = 0;
+        if (try read(systemd_shim_bus_message_read_u64(self.msg, &v))) return v;
+        return null;
+    }
+
+    pub fn readDouble(self: *Message) Error!?f64 {
+        var v: f64 = 0;
+        if (try read(systemd_shim_bus_message_read_double(self.msg, &v))) return v;
+        return null;
+    }
+
+    /// A passed descriptor, duplicated for the caller to keep and close
+    pub fn readFd(self: *Message) Error!?c_int {
+        var fd: c_int = -1;
+        if (try read(systemd_shim_bus_message_read_fd(self.msg, &fd))) return fd;
+        return null;
+    }
+
+    /// Enter the container at the read position; false at the end of the enclosing one
+    pub fn enterContainer(self: *Message, type_: u8, contents: ?[*:0]const u8) Error!bool {
+        return read(systemd_shim_bus_message_enter_container(self.msg, type_, contents));
+    }
+
+    pub fn exitContainer(self: *Message) Error!void {
+        return check(systemd_shim_bus_message_exit_container(self.msg));
+    }
+
+    pub const Peek = struct {
+        type_: u8,
+        /// What a container holds; null for basic types
+        contents: ?[]const u8,
+    };
+
+    /// The type at the read position, or null at the end of the enclosing container
+    pub fn peekType(self: *Message) Error!?Peek {
+        var type_: u8 = 0;
+        var contents: ?[*:0]const u8 = null;
+        if (try read(systemd_shim_bus_message_peek_type(self.msg, &type_, &contents))) {
+            return Peek{
+                .type_ = type_,
+                .contents = if (contents) |c| std.mem.span(c) else null,
+            };
+        }
+        return null;
+    }
+
+    /// Skip the values of `types`, or the next complete value when null
+    pub fn skip(self: *Message, types: ?[*:0]const u8) Error!void {
+        return check(systemd_shim_bus_message_skip(self.msg, types));
+    }
+
+    /// The message as `busctl monitor` prints it, for logs; `flags` combines
+    /// `DumpFlags`. Owned by the caller
+    pub fn dump(self: *Message, allocator: std.mem.Allocator, flags: u64) Error![]u8 {
+        var text: ?[*:0]u8 = null;
+        try check(systemd_shim_bus_message_dump(self.msg, flags, &text));
+        defer systemd_shim_free_string(text.?);
+        return allocator.dupe(u8, std.mem.span(text.?)) catch Error.AllocationFailed;
+    }
+
+    /// When the message arrived, in CLOCK_REALTIME microseconds, if the
+    /// transport stamped it; see `Bus.negotiateTimestamp`
+    pub fn realtimeUsec(self: *Message) ?u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_bus_message_get_realtime_usec(self.msg, &usec) < 0) return null;
+        return usec;
+    }
+
+    /// When the message arrived, in CLOCK_MONOTONIC microseconds, if stamped
+    pub fn monotonicUsec(self: *Message) ?u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_bus_message_get_monotonic_usec(self.msg, &usec) < 0) return null;
+        return usec;
+    }
+
+    /// The transport's sequence number for the message, if it gave one
+    pub fn seqnum(self: *Message) ?u64 {
+        var n: u64 = 0;
+        if (systemd_shim_bus_message_get_seqnum(self.msg, &n) < 0) return null;
+        return n;
+    }
+
+    /// The body's signature, or when not `complete` that of what is left
+    /// to read in the current container; owned by the message
+    pub fn signature(self: *Message, complete: bool) []const u8 {
+        return std.mem.span(systemd_shim_bus_message_get_signature(self.msg, @intFromBool(complete)) orelse return "");
+    }
+
+    fn check(ret: c_int) Error!void {
+        if (ret < 0) return Error.MessageFailed;
+    }
+
+    fn read(ret: c_int) Error!bool {
+        if (ret < 0) return Error.MessageFailed;
+        return ret > 0;
+    }
+};
+
+/// Who sent a message or owns a bus name. Each field is null when it could
+/// not be found; strings live as long as the `Creds`
+pub const Creds = struct {
+    creds: *sd_bus_creds,
+
+    pub fn deinit(self: *Creds) void {
+        _ = systemd_shim_bus_creds_unref(self.creds);
+    }
+
+    fn number(comptime T: type, get: *const fn (*sd_bus_creds, *T) callconv(.C) c_int, creds: *sd_bus_creds) ?T {
+        var v: T = 0;
+        if (get(creds, &v) < 0) return null;
+        return v;
+    }
+
+    fn text(get: *const fn (*sd_bus_creds, *?[*:0]const u8) callconv(.C) c_int, creds: *sd_bus_creds) ?[]const u8 {
+        var s: ?[*:0]const u8 = null;
+        if (get(creds, &s) < 0) return null;
+        return std.mem.span(s orelse return null);
+    }
+
+    pub fn pid(self: *const Creds) ?i32 {
+        return number(i32, systemd_shim_bus_creds_get_pid, self.creds);
+    }
+
+    pub fn uid(self: *const Creds) ?u32 {
+        return number(u32, systemd_shim_bus_creds_get_uid, self.creds);
+    }
+
+    /// The UID permission checks go by
+    pub fn euid(self: *const Creds) ?u32 {
+        return number(u32, systemd_shim_bus_creds_get_euid, self.creds);
+    }
+
+    pub fn gid(self: *const Creds) ?u32 {
+        return number(u32, systemd_shim_bus_creds_get_gid, self.creds);
+    }
+
+    pub fn selinuxContext(self: *const Creds) ?[]const u8 {
+        return text(systemd_shim_bus_creds_get_selinux_context, self.creds);
+    }
+
+    pub fn unit(self: *const Creds) ?[]const u8 {
+        return text(systemd_shim_bus_creds_get_unit, self.creds);
+    }
+
+    pub fn cgroup(self: *const Creds) ?[]const u8 {
+        return text(systemd_shim_bus_creds_get_cgroup, self.creds);
+    }
+};
+
+/// Flags for `Bus.requestName`
+pub const NameFlags = struct {
+    pub const replace_existing: u64 = 1 << 0;
+    pub const allow_replacement: u64 = 1 << 1;
+    pub const queue: u64 = 1 << 2;
+};
+
+/// What `Message.dump` renders
+pub const DumpFlags = struct {
+    /// Start with the type, sender, destination, path, member and so on
+    pub const with_header: u64 = 1 << 0;
+    /// Only the value at the read position, not the whole body
+    pub const subtree_only: u64 = 1 << 1;
+};
+
+/// Every message on a bus, as `busctl monitor` shows them, on a connection
+/// of its own that can no longer send
+pub const Monitor = struct {
+    monitor: *shim_monitor,
+
+    /// Watch the bus at `address`, or the system bus when null, for messages
+    /// matching any of `rules`, or all of them when null
+    pub fn open(
+        address: ?[*:0]const u8,
+        rules: ?[*:null]const ?[*:0]const u8,
+        handler: MonitorHandler,
+        userdata: ?*anyopaque,
+    ) Error!Monitor {
+        var monitor: ?*shim_monitor = null;
+        if (systemd_shim_monitor_open(address, rules, handler, userdata, &monitor) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return Monitor{ .monitor = monitor.? };
+    }
+
+    pub fn close(self: *Monitor) void {
+        systemd_shim_monitor_free(self.monitor);
+    }
+
+    /// Hand the next message, if any, to the handler; true when there may be more
+    pub fn process(self: *Monitor) Error!bool {
+        const ret = systemd_shim_monitor_process(self.monitor);
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret > 0;
+    }
+
+    /// When the message being handled arrived, in CLOCK_REALTIME and
+    /// CLOCK_MONOTONIC microseconds: the transport's stamp, or else when
+    /// the monitor read it. Null before the first message
+    pub fn received(self: *const Monitor) ?Arrival {
+        var arrival = Arrival{ .realtime_usec = 0, .monotonic_usec = 0 };
+        if (systemd_shim_monitor_received(self.monitor, &arrival.realtime_usec, &arrival.monotonic_usec) < 0) return null;
+        return arrival;
+    }
+
+    pub const Arrival = struct {
+        realtime_usec: u64,
+        monotonic_usec: u64,
+    };
+
+    /// Block until there is something to process, or for at most
+    /// `timeout_usec` (null waits indefinitely)
+    pub fn wait(self: *Monitor, timeout_usec: ?u64) Error!bool {
+        const ret = systemd_shim_bus_wait(systemd_shim_monitor_bus(self.monitor), timeout_usec orelse std.math.maxInt(u64));
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret > 0;
+    }
+};
+
+/// A bus connection that comes back by itself after the bus daemon
+/// restarts, with its matches installed again
+pub const ReconnectingBus = struct {
+    conn: *shim_reconnect,
+
+    /// Connect to the bus at `address`, or the system bus when null;
+    /// `reconnected`, if given, is told each time the connection is back
+    pub fn open(address: ?[*:0]const u8, reconnected: ?ReconnectHandler, userdata: ?*anyopaque) Error!ReconnectingBus {
+        var conn: ?*shim_reconnect = null;
+        if (systemd_shim_reconnect_open(address, reconnected, userdata, &conn) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return ReconnectingBus{ .conn = conn.? };
+    }
+
+    pub fn close(self: *ReconnectingBus) void {
+        systemd_shim_reconnect_free(self.conn);
+    }
+
+    /// The current connection, null while the bus is down. It changes on
+    /// every reconnect, so do not keep it, and never close it
+    pub fn bus(self: *const ReconnectingBus) ?Bus {
+        const b = systemd_shim_reconnect_bus(self.conn) orelse return null;
+        return Bus{ .bus = b };
+    }
+
+    /// Like `Bus.addMatch`, for as long as the connection lives across
+    /// reconnects; the id is for `removeMatch`
+    pub fn addMatch(self: *ReconnectingBus, match: [*:0]const u8, handler: SignalHandler, userdata: ?*anyopaque) Error!c_int {
+        const id = systemd_shim_reconnect_add_match(self.conn, match, handler, userdata);
+        if (id < 0) return Error.CallFailed;
+        return id;
+    }
+
+    pub fn removeMatch(self: *ReconnectingBus, id: c_int) void {
+        _ = systemd_shim_reconnect_remove_match(self.conn, id);
+    }
+
+    /// Dispatch one pending message, reconnecting if the connection was
+    /// lost; true when there may be more
+    pub fn process(self: *ReconnectingBus) Error!bool {
+        const ret = systemd_shim_reconnect_process(self.conn);
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret > 0;
+    }
+
+    /// Block until there is something to process, or for at most
+    /// `timeout_usec` (null waits indefinitely); while the bus is down,
+    /// only until the next reconnect attempt
+    pub fn wait(self: *ReconnectingBus, timeout_usec: ?u64) Error!bool {
+        const ret = systemd_shim_reconnect_wait(self.conn, timeout_usec orelse std.math.maxInt(u64));
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret > 0;
+    }
+};
+
+/// The methods of one interface to serve with `Bus.addObject`
+pub const Vtable = struct {
+    vtable: *shim_vtable,
+
+    pub fn init() Error!Vtable {
+        const vtable = systemd_shim_vtable_new() orelse return Error.AllocationFailed;
+        return Vtable{ .vtable = vtable };
+    }
+
+    /// Only for a vtable never passed to `Bus.addObject`
+    pub fn deinit(self: *Vtable) void {
+        systemd_shim_vtable_free(self.vtable);
+    }
+
+    /// Method `member` taking `signature` and replying with `result` ("" for none).
+    /// On the system bus only root may call it unless `unprivileged`
+    pub fn addMethod(
+        self: *Vtable,
+        member: [*:0]const u8,
+        signature: [*:0]const u8,
+        result: [*:0]const u8,
+        unprivileged: bool,
+        handler: MethodHandler,
+        userdata: ?*anyopaque,
+    ) Error!void {
+        if (systemd_shim_vtable_add_method(self.vtable, member, signature, result, @intFromBool(unprivileged), handler, userdata) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Read-only property `member` of type `signature`, read through `getter`
+    pub fn addProperty(
+        self: *Vtable,
+        member: [*:0]const u8,
+        signature: [*:0]const u8,
+        getter: PropertyGetter,
+        userdata: ?*anyopaque,
+    ) Error!void {
+        if (systemd_shim_vtable_add_property(self.vtable, member, signature, getter, userdata) < 0) {
+            return Error.CallFailed;
+        }
+    }
+};
+
+/// A subscription or pending call; ends when released
+pub const Slot = struct {
+    slot: *sd_bus_slot,
+
+    pub fn deinit(self: *Slot) void {
+        _ = systemd_shim_bus_slot_unref(self.slot);
+    }
+};
+
+/// The clients of a served object, followed by bus name until they leave
+pub const Tracker = struct {
+    track: *sd_bus_track,
+
+    pub fn deinit(self: *Tracker) void {
+        _ = systemd_shim_bus_track_unref(self.track);
+    }
+
+    pub fn addName(self: *Tracker, name: [*:0]const u8) Error!void {
+        if (systemd_shim_bus_track_add_name(self.track, name) < 0) return Error.CallFailed;
+    }
+
+    /// Follow whoever sent `m`, e.g. a client's first call
+    pub fn addSender(self: *Tracker, m: *Message) Error!void {
+        if (systemd_shim_bus_track_add_sender(self.track, m.msg) < 0) return Error.CallFailed;
+    }
+
+    pub fn removeName(self: *Tracker, name: [*:0]const u8) Error!void {
+        if (systemd_shim_bus_track_remove_name(self.track, name) < 0) return Error.CallFailed;
+    }
+
+    pub fn count(self: *Tracker) u32 {
+        return systemd_shim_bus_track_count(self.track);
+    }
+
+    pub fn contains(self: *Tracker, name: [*:0]const u8) bool {
+        return systemd_shim_bus_track_contains(self.track, name) > 0;
+    }
+};
+
+/// A match rule checked filter by filter as it is built
+pub const MatchRule = struct {
+    rule: *shim_match_rule,
+
+    pub fn init() Error!MatchRule {
+        const rule = systemd_shim_match_new() orelse return Error.AllocationFailed;
+        return MatchRule{ .rule = rule };
+    }
+
+    pub fn deinit(self: *MatchRule) void {
+        systemd_shim_match_free(self.rule);
+    }
+
+    fn check(ret: c_int) Error!void {
+        if (ret < 0) return Error.InvalidMatchRule;
+    }
+
+    /// `signal`, `method_call`, `method_return` or `error`
+    pub fn setType(self: *MatchRule, kind: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_type(self.rule, kind));
+    }
+
+    pub fn setSender(self: *MatchRule, sender: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_sender(self.rule, sender));
+    }
+
+    pub fn setInterface(self: *MatchRule, interface: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_interface(self.rule, interface));
+    }
+
+    pub fn setMember(self: *MatchRule, member: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_member(self.rule, member));
+    }
+
+    pub fn setPath(self: *MatchRule, path: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_path(self.rule, path));
+    }
+
+    /// `path` and every object below it
+    pub fn setPathNamespace(self: *MatchRule, path: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_path_namespace(self.rule, path));
+    }
+
+    /// Only messages whose string argument `index` (0 to 63) is `value`
+    pub fn setArg(self: *MatchRule, index: u8, value: [*:0]const u8) Error!void {
+        try check(systemd_shim_match_set_arg(self.rule, index, value));
+    }
+
+    /// The rule as text, owned by the caller
+    pub fn toString(self: *const MatchRule, allocator: std.mem.Allocator) Error![]u8 {
+        var text: ?[*:0]u8 = null;
+        try check(systemd_shim_match_to_string(self.rule, &text));
+        defer systemd_shim_free_string(text.?);
+        return allocator.dupe(u8, std.mem.span(text.?)) catch Error.AllocationFailed;
+    }
+};
+
+/// Properties of a transient unit for `Bus.startTransientUnit`: a service
+/// runs `execStart`, a scope takes in `pids`
+pub const TransientUnit = struct {
+    props: *shim_transient,
+
+    pub fn init() Error!TransientUnit {
+        const props = systemd_shim_transient_new() orelse return Error.AllocationFailed;
+        return TransientUnit{ .props = props };
+    }
+
+    pub fn deinit(self: *TransientUnit) void {
+        systemd_shim_transient_free(self.props);
+    }
+
+    fn check(ret: c_int) Error!void {
+        if (ret < 0) return Error.InvalidProperty;
+    }
+
+    /// `argv[0]` must be an absolute path
+    pub fn execStart(self: *TransientUnit, argv: [*:null]const ?[*:0]const u8, ignore_failure: bool) Error!void {
+        try check(systemd_shim_transient_exec_start(self.props, argv, @intFromBool(ignore_failure)));
+    }
+
+    pub fn pids(self: *TransientUnit, list: []const u32) Error!void {
+        try check(systemd_shim_transient_pids(self.props, list.ptr, list.len));
+    }
+
+    pub fn memoryMax(self: *TransientUnit, bytes: u64) Error!void {
+        try check(systemd_shim_transient_memory_max(self.props, bytes));
+    }
+
+    /// Percent of one CPU, e.g. 50 for `CPUQuota=50%`
+    pub fn cpuQuota(self: *TransientUnit, percent: u32) Error!void {
+        try check(systemd_shim_transient_cpu_quota(self.props, percent));
+    }
+
+    /// "inactive" or "inactive-or-failed"
+    pub fn collectMode(self: *TransientUnit, mode: [*:0]const u8) Error!void {
+        try check(systemd_shim_transient_collect_mode(self.props, mode));
+    }
+
+    /// Any other property by its D-Bus name, e.g. `ProtectSystem`
+    pub fn setString(self: *TransientUnit, name: [*:0]const u8, value: [*:0]const u8) Error!void {
+        try check(systemd_shim_transient_set_string(self.props, name, value));
+    }
+
+    pub fn setBool(self: *TransientUnit, name: [*:0]const u8, value: bool) Error!void {
+        try check(systemd_shim_transient_set_bool(self.props, name, @intFromBool(value)));
+    }
+
+    pub fn setU64(self: *TransientUnit, name: [*:0]const u8, value: u64) Error!void {
+        try check(systemd_shim_transient_set_u64(self.props, name, value));
+    }
+};
+
+/// D-Bus connection handle
+pub const Bus = struct {
+    bus: *sd_bus,
+    /// A thread-default bus, which `close` and `abort` only let go of
+    shared: bool = false,
+
+    pub fn connectSystem() Error!Bus {
+        var bus: ?*sd_bus = null;
+        if (systemd_shim_bus_open_system(&bus) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return Bus{ .bus = bus.? };
+    }
+
+    /// The calling user's session bus, where its per-user units live
+    pub fn connectUser() Error!Bus {
+        var bus: ?*sd_bus = null;
+        if (systemd_shim_bus_open_user(&bus) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return Bus{ .bus = bus.? };
+    }
+
+    /// The system bus of `host` (`[user@]host[:port]`), reached over SSH
+    pub fn connectRemote(host: [*:0]const u8) Error!Bus {
+        var bus: ?*sd_bus = null;
+        if (systemd_shim_bus_open_system_remote(&bus, host) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return Bus{ .bus = bus.? };
+    }
+
+    /// The system bus inside the local container `machine`
+    pub fn connectMachine(machine: [*:0]const u8) Error!Bus {
+        var bus: ?*sd_bus = null;
+        if (systemd_shim_bus_open_system_machine(&bus, machine) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return Bus{ .bus = bus.? };
+    }
+
+    fn openThreadDefault(open: *const fn (*?*sd_bus) callconv(.C) c_int) Error!Bus {
+        var bus: ?*sd_bus = null;
+        if (open(&bus) < 0) {
+            return Error.BusConnectionFailed;
+        }
+        return Bus{ .bus = bus.?, .shared = true };
+    }
+
+    /// The calling thread's default bus, shared by everything on the thread
+    /// that asks for it; the system bus unless the process was started for
+    /// another. A bus must never be used from two threads at once, so take
+    /// one of these on each thread rather than connecting per call
+    pub fn threadDefault() Error!Bus {
+        return openThreadDefault(systemd_shim_bus_default);
+    }
+
+    /// The calling thread's connection to the system bus
+    pub fn threadDefaultSystem() Error!Bus {
+        return openThreadDefault(systemd_shim_bus_default_system);
+    }
+
+    /// The calling thread's connection to the user's session bus
+    pub fn threadDefaultUser() Error!Bus {
+        return openThreadDefault(systemd_shim_bus_default_user);
+    }
+
+    /// Send what is still queued, then disconnect and release the bus. A
+    /// thread-default bus stays connected for the rest of the thread
+    pub fn close(self: *Bus) void {
+        if (self.shared) {
+            _ = systemd_shim_bus_unref(self.bus);
+            return;
+        }
+        _ = systemd_shim_bus_flush_close_unref(self.bus);
+    }
+
+    /// Disconnect and release the bus, dropping anything still queued; only
+    /// releases a thread-default bus
+    pub fn abort(self: *Bus) void {
+        if (self.shared) {
+            _ = systemd_shim_bus_unref(self.bus);
+            return;
+        }
+        _ = systemd_shim_bus_close_unref(self.bus);
+    }
+
+    /// Block until every queued message is written
+    pub fn flush(self: *Bus) Error!void {
+        if (systemd_shim_bus_flush(self.bus) < 0) return Error.BusConnectionFailed;
+    }
+
+    /// Disconnect but keep the handle, whose calls then fail; `close` still releases it
+    pub fn disconnect(self: *Bus) void {
+        systemd_shim_bus_close(self.bus);
+    }
+
+    /// False once the connection is closed or lost, e.g. when the bus daemon restarts
+    pub fn isOpen(self: *Bus) bool {
+        return systemd_shim_bus_is_open(self.bus) > 0;
+    }
+
+    const UnitJob = *const fn (*sd_bus, [*:0]const u8, [*:0]const u8, *sd_bus_error, *?[*:0]u8) callconv(.C) c_int;
+
+    fn unitJob(self: *Bus, allocator: std.mem.Allocator, f: UnitJob, unit: [*:0]const u8, mode: [*:0]const u8) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var job: ?[*:0]u8 = null;
+        if (f(self.bus, unit, mode, &err, &job) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(job.?);
+        return allocator.dupe(u8, std.mem.span(job.?)) catch Error.AllocationFailed;
+    }
+
+    /// Queue a start of `unit`; `mode` is a job mode such as "replace".
+    /// Returns the job's object path, owned by the caller
+    pub fn startUnit(self: *Bus, allocator: std.mem.Allocator, unit: [*:0]const u8, mode: [*:0]const u8) Error![]u8 {
+        return self.unitJob(allocator, systemd_shim_unit_start, unit, mode);
+    }
+
+    pub fn stopUnit(self: *Bus, allocator: std.mem.Allocator, unit: [*:0]const u8, mode: [*:0]const u8) Error![]u8 {
+        return self.unitJob(allocator, systemd_shim_unit_stop, unit, mode);
+    }
+
+    pub fn restartUnit(self: *Bus, allocator: std.mem.Allocator, unit: [*:0]const u8, mode: [*:0]const u8) Error![]u8 {
+        return self.unitJob(allocator, systemd_shim_unit_restart, unit, mode);
+    }
+
+    pub fn reloadUnit(self: *Bus, allocator: std.mem.Allocator, unit: [*:0]const u8, mode: [*:0]const u8) Error![]u8 {
+        return self.unitJob(allocator, systemd_shim_unit_reload, unit, mode);
+    }
+
+    /// Reread unit files and apply changed ones, as `systemctl daemon-reload`
+    pub fn reloadDaemon(self: *Bus) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_manager_reload(self.bus, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Restart the manager in place, as `systemctl daemon-reexec`. On a
+    /// direct connection to the manager this one is closed afterwards
+    pub fn reexecuteDaemon(self: *Bus) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_manager_reexecute(self.bus, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Start transient unit `name` (`*.service` or `*.scope`) built from
+    /// `unit`. Returns the job's object path, owned by the caller
+    pub fn startTransientUnit(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        name: [*:0]const u8,
+        mode: [*:0]const u8,
+        unit: *const TransientUnit,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var job: ?[*:0]u8 = null;
+        if (systemd_shim_unit_start_transient(self.bus, name, mode, unit.props, &err, &job) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(job.?);
+        return allocator.dupe(u8, std.mem.span(job.?)) catch Error.AllocationFailed;
+    }
+
+    /// Pause every process of `unit` until `thawUnit`, e.g. to capture its
+    /// state while it cannot change
+    pub fn freezeUnit(self: *Bus, unit: [*:0]const u8) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_unit_freeze(self.bus, unit, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    pub fn thawUnit(self: *Bus, unit: [*:0]const u8) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_unit_thaw(self.bus, unit, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Send `signal`, e.g. `std.posix.SIG.HUP`, to the processes of `unit`
+    /// that `who` names
+    pub fn killUnit(self: *Bus, unit: [*:0]const u8, who: KillWho, signal: c_int) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_unit_kill(self.bus, unit, @tagName(who), signal, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Every loaded unit as a JSON array of objects: name, description,
+    /// load_state, active_state, sub_state, following, path, job_id, job_type
+    /// and job_path. Owned by the caller
+    pub fn listUnitsJson(self: *Bus, allocator: std.mem.Allocator) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_unit_list_json(self.bus, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Every unit file and its enablement state, as JSON `[{"path", "state"}]`
+    pub fn listUnitFilesJson(self: *Bus, allocator: std.mem.Allocator) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_unit_files_list_json(self.bus, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Enable `files` on boot; JSON `{"carries_install_info", "changes"}`.
+    /// Follow with `reloadDaemon` for the manager to see the new links
+    pub fn enableUnitFiles(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        files: [*:null]const ?[*:0]const u8,
+        runtime: bool,
+        force: bool,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_unit_files_enable(self.bus, files, @intFromBool(runtime), @intFromBool(force), &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Stop `files` starting on boot; JSON `{"changes"}`
+    pub fn disableUnitFiles(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        files: [*:null]const ?[*:0]const u8,
+        runtime: bool,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_unit_files_disable(self.bus, files, @intFromBool(runtime), &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// ActiveState, SubState, LoadState, MainPID, ExecMainStatus and
+    /// ActiveEnterTimestamp of `unit` from one round trip
+    pub fn unitSnapshot(self: *Bus, unit: [*:0]const u8) Error!UnitSnapshot {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var snapshot: UnitSnapshot = undefined;
+        if (systemd_shim_unit_get_status(self.bus, unit, &err, &snapshot) < 0) {
+            return Error.CallFailed;
+        }
+        return snapshot;
+    }
+
+    /// MemoryCurrent, CPUUsageNSec, TasksCurrent, IOReadBytes and
+    /// IOWriteBytes of `unit` from one round trip
+    pub fn unitResources(self: *Bus, unit: [*:0]const u8) Error!UnitResources {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var resources: UnitResources = undefined;
+        if (systemd_shim_unit_get_resources(self.bus, unit, &err, &resources) < 0) {
+            return Error.CallFailed;
+        }
+        return resources;
+    }
+
+    /// Every logind session as JSON `[{"id", "uid", "user", "seat", "path"}]`
+    pub fn listSessionsJson(self: *Bus, allocator: std.mem.Allocator) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_login_list_sessions_json(self.bus, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    pub fn lockSession(self: *Bus, session: [*:0]const u8) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_login_lock_session(self.bus, session, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    pub fn terminateSession(self: *Bus, session: [*:0]const u8) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_login_terminate_session(self.bus, session, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Hold off `what`, e.g. "sleep:shutdown", while a repair runs; `mode`
+    /// is "block" or "delay". Close the returned fd to release the lock
+    pub fn inhibit(
+        self: *Bus,
+        what: [*:0]const u8,
+        who: [*:0]const u8,
+        why: [*:0]const u8,
+        mode: [*:0]const u8,
+    ) Error!c_int {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var fd: c_int = -1;
+        if (systemd_shim_login_inhibit(self.bus, what, who, why, mode, &err, &fd) < 0) {
+            return Error.CallFailed;
+        }
+        return fd;
+    }
+
+    /// The host's names, chassis and deployment as JSON `{"hostname",
+    /// "static_hostname", "pretty_hostname", "chassis", "deployment"}`
+    pub fn hostnameJson(self: *Bus, allocator: std.mem.Allocator) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_hostname_get_json(self.bus, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    const HostnameSetter = *const fn (*sd_bus, [*:0]const u8, *sd_bus_error) callconv(.C) c_int;
+
+    fn hostnameSet(self: *Bus, f: HostnameSetter, name: [*:0]const u8) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (f(self.bus, name, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Set the kernel's (transient) hostname
+    pub fn setHostname(self: *Bus, name: [*:0]const u8) Error!void {
+        return self.hostnameSet(systemd_shim_hostname_set, name);
+    }
+
+    /// Set /etc/hostname, e.g. to match the transient hostname again
+    pub fn setStaticHostname(self: *Bus, name: [*:0]const u8) Error!void {
+        return self.hostnameSet(systemd_shim_hostname_set_static, name);
+    }
+
+    pub fn setPrettyHostname(self: *Bus, name: [*:0]const u8) Error!void {
+        return self.hostnameSet(systemd_shim_hostname_set_pretty, name);
+    }
+
+    /// The system time zone, e.g. "Europe/Berlin"; owned by the caller
+    pub fn timezone(self: *Bus, allocator: std.mem.Allocator) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var tz: ?[*:0]u8 = null;
+        if (systemd_shim_timedate_get_timezone(self.bus, &err, &tz) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(tz.?);
+        return allocator.dupe(u8, std.mem.span(tz.?)) catch Error.AllocationFailed;
+    }
+
+    pub fn setTimezone(self: *Bus, timezone_name: [*:0]const u8) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_timedate_set_timezone(self.bus, timezone_name, &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Whether network time sync is switched on
+    pub fn ntpEnabled(self: *Bus) Error!bool {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        const r = systemd_shim_timedate_get_ntp(self.bus, &err);
+        if (r < 0) return Error.CallFailed;
+        return r > 0;
+    }
+
+    pub fn setNtp(self: *Bus, enable: bool) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_timedate_set_ntp(self.bus, @intFromBool(enable), &err) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Whether the kernel reports the clock as synchronized
+    pub fn ntpSynchronized(self: *Bus) Error!bool {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        const r = systemd_shim_timedate_ntp_synchronized(self.bus, &err);
+        if (r < 0) return Error.CallFailed;
+        return r > 0;
+    }
+
+    /// Registered containers and VMs as JSON: an array of objects with
+    /// `name`, `class`, `service` and `path`. Owned by the caller
+    pub fn listMachinesJson(self: *Bus, allocator: std.mem.Allocator) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_machine_list_json(self.bus, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// The object path of machine `name`, owned by the caller
+    pub fn machinePath(self: *Bus, allocator: std.mem.Allocator, name: [*:0]const u8) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var path: ?[*:0]u8 = null;
+        if (systemd_shim_machine_get(self.bus, name, &err, &path) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(path.?);
+        return allocator.dupe(u8, std.mem.span(path.?)) catch Error.AllocationFailed;
+    }
+
+    /// Machine `name`'s IP addresses as JSON: an array of objects with
+    /// `family` (`inet` or `inet6`) and `address`. Owned by the caller
+    pub fn machineAddressesJson(self: *Bus, allocator: std.mem.Allocator, name: [*:0]const u8) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_machine_addresses_json(self.bus, name, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Look up `name` through systemd-resolved as JSON: `addresses` (each
+    /// with `ifindex`, `family`, `address`), the canonical `name` and
+    /// `flags`. `ifindex` 0 asks on every link, `family` 0 for both IPv4
+    /// and IPv6. Owned by the caller
+    pub fn resolveHostnameJson(self: *Bus, allocator: std.mem.Allocator, name: [*:0]const u8, ifindex: c_int, family: c_int) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_resolve_hostname_json(self.bus, ifindex, name, family, 0, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Reverse lookup of `address` (IPv4 or IPv6 text) as JSON: `names`
+    /// (each with `ifindex`, `name`) and `flags`. Owned by the caller
+    pub fn resolveAddressJson(self: *Bus, allocator: std.mem.Allocator, address: [*:0]const u8, ifindex: c_int) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_resolve_address_json(self.bus, ifindex, address, 0, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
 
-/// D-Bus connection handle
-pub const Bus = struct {
-    bus: *sd_bus,
+    /// SRV lookup of `type_` (e.g. `_http._tcp`) in `domain`, or of the
+    /// DNS-SD instance `name` when given, as JSON with `services`, `txt`,
+    /// `name`, `type`, `domain` and `flags`. Owned by the caller
+    pub fn resolveServiceJson(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        name: ?[*:0]const u8,
+        type_: [*:0]const u8,
+        domain: [*:0]const u8,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
 
-    pub fn connectSystem() Error!Bus {
-        var bus: ?*sd_bus = null;
-        if (systemd_shim_bus_open_system(&bus) < 0) {
-            return Error.BusConnectionFailed;
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_resolve_service_json(self.bus, 0, name, type_, domain, 0, 0, &err, &json) < 0) {
+            return Error.CallFailed;
         }
-        return Bus{ .bus = bus.? };
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
     }
 
-    pub fn close(self: *Bus) void {
-        _ = systemd_shim_bus_unref(self.bus);
+    /// Empty systemd-resolved's cache
+    pub fn flushDnsCaches(self: *Bus) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_resolve_flush_caches(self.bus, &err) < 0) return Error.CallFailed;
+    }
+
+    /// Zero systemd-resolved's statistics counters
+    pub fn resetResolverStatistics(self: *Bus) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        if (systemd_shim_resolve_reset_statistics(self.bus, &err) < 0) return Error.CallFailed;
     }
 
     /// Get unit active state
@@ -162,6 +1889,313 @@ pub const Bus = struct {
         return Error.CallFailed;
     }
 
+    /// Read a string-array property such as `Wants`; free the result with `freeStrings`
+    pub fn getPropertyStrings(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+        interface: [*:0]const u8,
+        member: [*:0]const u8,
+    ) Error![][]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var strv: ?[*:null]?[*:0]u8 = null;
+        if (systemd_shim_bus_get_property_strv(self.bus, destination, path, interface, member, &err, &strv) < 0) {
+            return Error.CallFailed;
+        }
+        const l = strv orelse return allocator.alloc([]u8, 0) catch Error.AllocationFailed;
+        defer systemd_shim_free_strv(l);
+
+        const items = std.mem.sliceTo(l, null);
+        const out = allocator.alloc([]u8, items.len) catch return Error.AllocationFailed;
+        for (items, 0..) |item, n| {
+            out[n] = allocator.dupe(u8, std.mem.span(item.?)) catch {
+                for (out[0..n]) |s| allocator.free(s);
+                allocator.free(out);
+                return Error.AllocationFailed;
+            };
+        }
+        return out;
+    }
+
+    pub fn freeStrings(allocator: std.mem.Allocator, strings: [][]u8) void {
+        for (strings) |s| allocator.free(s);
+        allocator.free(strings);
+    }
+
+    /// Every property of `interface` on `path` as a JSON object, in one round trip
+    pub fn getAllPropertiesJson(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+        interface: [*:0]const u8,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_bus_get_all_properties_json(self.bus, destination, path, interface, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Every object under the ObjectManager at `path`, with its interfaces and
+    /// their properties, as a JSON object keyed by object path. Owned by the caller
+    pub fn getManagedObjectsJson(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_bus_get_managed_objects_json(self.bus, destination, path, &err, &json) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Introspection XML for the object at `path`: its interfaces, methods,
+    /// properties, signals and child nodes. Owned by the caller
+    pub fn introspect(
+        self: *Bus,
+        allocator: std.mem.Allocator,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+    ) Error![]u8 {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        var xml: ?[*:0]u8 = null;
+        if (systemd_shim_bus_introspect(self.bus, destination, path, &err, &xml) < 0) {
+            return Error.CallFailed;
+        }
+        defer systemd_shim_free_string(xml.?);
+        return allocator.dupe(u8, std.mem.span(xml.?)) catch Error.AllocationFailed;
+    }
+
+    /// Set a writable property; `value` is a string, bool, u32 or u64
+    pub fn setProperty(
+        self: *Bus,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+        interface: [*:0]const u8,
+        member: [*:0]const u8,
+        value: anytype,
+    ) Error!void {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+
+        const ret = switch (@TypeOf(value)) {
+            [*:0]const u8 => systemd_shim_bus_set_property_string(self.bus, destination, path, interface, member, &err, value),
+            bool => systemd_shim_bus_set_property_bool(self.bus, destination, path, interface, member, &err, @intFromBool(value)),
+            u32 => systemd_shim_bus_set_property_u32(self.bus, destination, path, interface, member, &err, value),
+            u64 => systemd_shim_bus_set_property_u64(self.bus, destination, path, interface, member, &err, value),
+            else => @compileError("setProperty takes a string, bool, u32 or u64"),
+        };
+        if (ret < 0) return Error.CallFailed;
+    }
+
+    /// Start a call to `member`; append its arguments, then `call` it
+    pub fn newMethodCall(
+        self: *Bus,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+        interface: [*:0]const u8,
+        member: [*:0]const u8,
+    ) Error!Message {
+        var m: ?*sd_bus_message = null;
+        if (systemd_shim_bus_message_new_method_call(self.bus, &m, destination, path, interface, member) < 0) {
+            return Error.MessageFailed;
+        }
+        return Message{ .msg = m.? };
+    }
+
+    /// Send `message` and wait for its reply; a `timeout_usec` of 0 waits the bus's default
+    pub fn call(self: *Bus, message: *Message, timeout_usec: u64) Error!Message {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+        var reply: ?*sd_bus_message = null;
+        if (systemd_shim_bus_call(self.bus, message.msg, timeout_usec, &err, &reply) < 0) {
+            return Error.CallFailed;
+        }
+        return Message{ .msg = reply.? };
+    }
+
+    /// How long calls and property access wait for a reply when not told
+    /// otherwise; 0 restores the 25s default
+    pub fn setMethodCallTimeout(self: *Bus, usec: u64) Error!void {
+        if (systemd_shim_bus_set_method_call_timeout(self.bus, usec) < 0) return Error.BusConnectionFailed;
+    }
+
+    /// Ask for received messages to carry arrival timestamps. Only some
+    /// transports provide them; over the bus daemon's socket they stay unset
+    pub fn negotiateTimestamp(self: *Bus, enable: bool) Error!void {
+        if (systemd_shim_bus_negotiate_timestamp(self.bus, @intFromBool(enable)) < 0) return Error.BusConnectionFailed;
+    }
+
+    pub fn methodCallTimeout(self: *Bus) Error!u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_bus_get_method_call_timeout(self.bus, &usec) < 0) return Error.BusConnectionFailed;
+        return usec;
+    }
+
+    /// Send `message` without blocking; `handler` gets the reply from `process`.
+    /// Releasing the returned slot first cancels the call
+    pub fn callAsync(self: *Bus, message: *Message, handler: ReplyHandler, userdata: ?*anyopaque, timeout_usec: u64) Error!Slot {
+        var slot: ?*sd_bus_slot = null;
+        if (systemd_shim_bus_call_async(self.bus, &slot, message.msg, handler, userdata, timeout_usec) < 0) {
+            return Error.CallFailed;
+        }
+        return Slot{ .slot = slot.? };
+    }
+
+    /// Call a method that takes no arguments, waiting up to `timeout_usec`
+    /// (0 for the connection's method call timeout)
+    pub fn callMethod(
+        self: *Bus,
+        destination: [*:0]const u8,
+        path: [*:0]const u8,
+        interface: [*:0]const u8,
+        member: [*:0]const u8,
+        timeout_usec: u64,
+    ) Error!Message {
+        var err: sd_bus_error = SD_BUS_ERROR_NULL;
+        defer systemd_shim_bus_error_free(&err);
+        var reply: ?*sd_bus_message = null;
+        if (systemd_shim_bus_call_method_timeout(self.bus, destination, path, interface, member, timeout_usec, &err, &reply) < 0) {
+            return Error.CallFailed;
+        }
+        return Message{ .msg = reply.? };
+    }
+
+    /// Own the well-known `name`; `flags` combines `NameFlags`
+    pub fn requestName(self: *Bus, name: [*:0]const u8, flags: u64) Error!void {
+        if (systemd_shim_bus_request_name(self.bus, name, flags) < 0) return Error.CallFailed;
+    }
+
+    pub fn releaseName(self: *Bus, name: [*:0]const u8) Error!void {
+        if (systemd_shim_bus_release_name(self.bus, name) < 0) return Error.CallFailed;
+    }
+
+    /// Follow clients and call `handler`, if any, when the last one leaves,
+    /// so a service can exit once nobody uses it
+    pub fn track(self: *Bus, handler: ?TrackHandler, userdata: ?*anyopaque) Error!Tracker {
+        var t: ?*sd_bus_track = null;
+        if (systemd_shim_bus_track_new(self.bus, &t, handler, userdata) < 0) {
+            return Error.CallFailed;
+        }
+        return Tracker{ .track = t.? };
+    }
+
+    /// Serve `interface` at `path`; takes `vtable` over either way. Releasing
+    /// the slot removes the object
+    pub fn addObject(self: *Bus, path: [*:0]const u8, interface: [*:0]const u8, vtable: Vtable) Error!Slot {
+        var slot: ?*sd_bus_slot = null;
+        if (systemd_shim_bus_add_object_vtable(self.bus, &slot, path, interface, vtable.vtable) < 0) {
+            return Error.CallFailed;
+        }
+        return Slot{ .slot = slot.? };
+    }
+
+    /// Start signal `member` from `path`, to append its arguments to and `send`
+    pub fn newSignal(self: *Bus, path: [*:0]const u8, interface: [*:0]const u8, member: [*:0]const u8) Error!Message {
+        var m: ?*sd_bus_message = null;
+        if (systemd_shim_bus_message_new_signal(self.bus, &m, path, interface, member) < 0) {
+            return Error.MessageFailed;
+        }
+        return Message{ .msg = m.? };
+    }
+
+    /// Broadcast a signal that carries no arguments
+    pub fn emitSignal(self: *Bus, path: [*:0]const u8, interface: [*:0]const u8, member: [*:0]const u8) Error!void {
+        if (systemd_shim_bus_emit_signal(self.bus, path, interface, member) < 0) return Error.CallFailed;
+    }
+
+    /// Announce the current values of served properties `names`; null announces them all
+    pub fn emitPropertiesChanged(
+        self: *Bus,
+        path: [*:0]const u8,
+        interface: [*:0]const u8,
+        names: ?[*:null]const ?[*:0]const u8,
+    ) Error!void {
+        if (systemd_shim_bus_emit_properties_changed(self.bus, path, interface, names) < 0) {
+            return Error.CallFailed;
+        }
+    }
+
+    /// Credentials of the process that owns `name`
+    pub fn nameCreds(self: *Bus, name: [*:0]const u8) Error!Creds {
+        var creds: ?*sd_bus_creds = null;
+        if (systemd_shim_bus_get_name_creds(self.bus, name, &creds) < 0) {
+            return Error.CallFailed;
+        }
+        return Creds{ .creds = creds.? };
+    }
+
+    /// Call `handler` from `process` with each message `match` lets through,
+    /// e.g. `type='signal',interface='org.freedesktop.systemd1.Manager',member='JobRemoved'`
+    pub fn addMatch(self: *Bus, match: [*:0]const u8, handler: SignalHandler, userdata: ?*anyopaque) Error!Slot {
+        var slot: ?*sd_bus_slot = null;
+        if (systemd_shim_bus_add_match(self.bus, &slot, match, handler, userdata) < 0) {
+            return Error.CallFailed;
+        }
+        return Slot{ .slot = slot.? };
+    }
+
+    /// `addMatch` for a rule built with `MatchRule`
+    pub fn addMatchRule(self: *Bus, rule: *const MatchRule, handler: SignalHandler, userdata: ?*anyopaque) Error!Slot {
+        var slot: ?*sd_bus_slot = null;
+        const ret = systemd_shim_bus_add_match_rule(self.bus, &slot, rule.rule, handler, userdata);
+        if (ret < 0) return Error.CallFailed;
+        return Slot{ .slot = slot.? };
+    }
+
+    /// Dispatch one pending message; true when there may be more
+    pub fn process(self: *Bus) Error!bool {
+        const ret = systemd_shim_bus_process(self.bus);
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret > 0;
+    }
+
+    /// Block until there is something to process, or for at most
+    /// `timeout_usec` (null waits indefinitely); true when woken by the bus
+    pub fn wait(self: *Bus, timeout_usec: ?u64) Error!bool {
+        const ret = systemd_shim_bus_wait(self.bus, timeout_usec orelse std.math.maxInt(u64));
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret > 0;
+    }
+
+    /// The descriptor to add to a poll loop, with `events` and `timeout`
+    pub fn fd(self: *Bus) Error!c_int {
+        const ret = systemd_shim_bus_get_fd(self.bus);
+        if (ret < 0) return Error.BusConnectionFailed;
+        return ret;
+    }
+
+    /// Poll events to wait for on `fd`
+    pub fn events(self: *Bus) Error!i16 {
+        const ret = systemd_shim_bus_get_events(self.bus);
+        if (ret < 0) return Error.BusConnectionFailed;
+        return @intCast(ret);
+    }
+
+    /// CLOCK_MONOTONIC deadline in microseconds to call `process` by, or
+    /// null when there is none
+    pub fn timeout(self: *Bus) Error!?u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_bus_get_timeout(self.bus, &usec) < 0) return Error.BusConnectionFailed;
+        return if (usec == std.math.maxInt(u64)) null else usec;
+    }
+
     /// Check if unit is active
     pub fn isUnitActive(self: *Bus, allocator: std.mem.Allocator, unit_name: []const u8) Error!bool {
         const state = try self.getUnitActiveState(allocator, unit_name);
@@ -169,6 +2203,16 @@ pub const Bus = struct {
     }
 };
 
+/// What `Journal.wait` woke up to
+pub const JournalChange = enum(c_int) {
+    /// Timed out with nothing new
+    nop = 0,
+    /// New entries at the end, for `next` to read
+    append = 1,
+    /// Journal files added, removed or rotated
+    invalidate = 2,
+};
+
 /// Journal reader handle
 pub const Journal = struct {
     journal: *sd_journal,
@@ -181,6 +2225,35 @@ pub const Journal = struct {
         return Journal{ .journal = j.? };
     }
 
+    /// Open the journal files in `path` rather than this machine's, say
+    /// ones copied off another machine
+    pub fn openDirectory(path: [*:0]const u8) Error!Journal {
+        var j: ?*sd_journal = null;
+        if (systemd_shim_journal_open_directory(&j, path, 0) < 0) {
+            return Error.JournalOpenFailed;
+        }
+        return Journal{ .journal = j.? };
+    }
+
+    /// Open the journal of the system whose root is at `root`, such as a
+    /// rescue disk mounted there
+    pub fn openOsRoot(root: [*:0]const u8) Error!Journal {
+        var j: ?*sd_journal = null;
+        if (systemd_shim_journal_open_directory(&j, root, SD_JOURNAL_OS_ROOT) < 0) {
+            return Error.JournalOpenFailed;
+        }
+        return Journal{ .journal = j.? };
+    }
+
+    /// Open just the journal files in the null-terminated `paths`
+    pub fn openFiles(paths: [*:null]const ?[*:0]const u8) Error!Journal {
+        var j: ?*sd_journal = null;
+        if (systemd_shim_journal_open_files(&j, paths, 0) < 0) {
+            return Error.JournalOpenFailed;
+        }
+        return Journal{ .journal = j.? };
+    }
+
     pub fn close(self: *Journal) void {
         systemd_shim_journal_close(self.journal);
     }
@@ -191,6 +2264,54 @@ pub const Journal = struct {
         _ = systemd_shim_journal_add_match(self.journal, match.ptr, match.len);
     }
 
+    /// OR the matches added next with those since the last `addConjunction`
+    pub fn addDisjunction(self: *Journal) Error!void {
+        if (systemd_shim_journal_add_disjunction(self.journal) < 0) {
+            return Error.JournalOpenFailed;
+        }
+    }
+
+    /// AND the matches added next with all those before, as in
+    /// "(unit A OR unit B) AND priority 3": `addMatch` each unit with an
+    /// `addDisjunction` between, `addConjunction`, then `addMatch` the priority
+    pub fn addConjunction(self: *Journal) Error!void {
+        if (systemd_shim_journal_add_conjunction(self.journal) < 0) {
+            return Error.JournalOpenFailed;
+        }
+    }
+
+    /// Drop every match
+    pub fn flushMatches(self: *Journal) void {
+        systemd_shim_journal_flush_matches(self.journal);
+    }
+
+    /// Match the entries of boot `boot_id`, 32 hex digits or a UUID, or of
+    /// the running boot when null
+    pub fn addMatchBoot(self: *Journal, boot_id: ?[*:0]const u8) Error!void {
+        if (systemd_shim_journal_add_match_boot(self.journal, boot_id) < 0) {
+            return Error.JournalOpenFailed;
+        }
+    }
+
+    /// The boots with entries, oldest first, as a JSON array of objects
+    /// with `index`, `boot_id`, `first_entry` and `last_entry` like
+    /// `journalctl --list-boots`. Keeps the matches. Owned by the caller
+    pub fn listBootsJson(self: *Journal, allocator: std.mem.Allocator) Error![]u8 {
+        var json: ?[*:0]u8 = null;
+        if (systemd_shim_journal_list_boots_json(self.journal, &json) < 0) {
+            return Error.JournalSeekFailed;
+        }
+        defer systemd_shim_free_string(json.?);
+        return allocator.dupe(u8, std.mem.span(json.?)) catch Error.AllocationFailed;
+    }
+
+    /// Seek to start of journal, to read forward with `next`
+    pub fn seekHead(self: *Journal) Error!void {
+        if (systemd_shim_journal_seek_head(self.journal) < 0) {
+            return Error.JournalSeekFailed;
+        }
+    }
+
     /// Seek to end of journal
     pub fn seekTail(self: *Journal) Error!void {
         if (systemd_shim_journal_seek_tail(self.journal) < 0) {
@@ -208,6 +2329,194 @@ pub const Journal = struct {
         return systemd_shim_journal_next(self.journal) > 0;
     }
 
+    /// Move back up to `skip` entries in one call, to page through the
+    /// journal; returns how many it moved, fewer at the start
+    pub fn previousSkip(self: *Journal, skip: u64) Error!u64 {
+        const r = systemd_shim_journal_previous_skip(self.journal, skip);
+        if (r < 0) return Error.JournalSeekFailed;
+        return @intCast(r);
+    }
+
+    /// Move forward up to `skip` entries in one call; fewer at the end
+    pub fn nextSkip(self: *Journal, skip: u64) Error!u64 {
+        const r = systemd_shim_journal_next_skip(self.journal, skip);
+        if (r < 0) return Error.JournalSeekFailed;
+        return @intCast(r);
+    }
+
+    /// Block until the journal changes or `timeout_usec` passes (null
+    /// waits indefinitely); a tail calls `next` until it returns false,
+    /// then waits. The first wait may return `invalidate` at once
+    pub fn wait(self: *Journal, timeout_usec: ?u64) Error!JournalChange {
+        const r = systemd_shim_journal_wait(self.journal, timeout_usec orelse std.math.maxInt(u64));
+        if (r < 0) return Error.JournalSeekFailed;
+        return @enumFromInt(r);
+    }
+
+    /// The descriptor to add to a poll loop in place of `wait`, with
+    /// `events` and `timeout`; call `process` when it wakes
+    pub fn fd(self: *Journal) Error!c_int {
+        const ret = systemd_shim_journal_get_fd(self.journal);
+        if (ret < 0) return Error.JournalOpenFailed;
+        return ret;
+    }
+
+    /// Poll events to wait for on `fd`
+    pub fn events(self: *Journal) Error!i16 {
+        const ret = systemd_shim_journal_get_events(self.journal);
+        if (ret < 0) return Error.JournalOpenFailed;
+        return @intCast(ret);
+    }
+
+    /// CLOCK_MONOTONIC deadline in microseconds to call `process` by, or
+    /// null when there is none
+    pub fn timeout(self: *Journal) Error!?u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_journal_get_timeout(self.journal, &usec) < 0) return Error.JournalOpenFailed;
+        return if (usec == std.math.maxInt(u64)) null else usec;
+    }
+
+    /// What changed since the descriptor woke
+    pub fn process(self: *Journal) Error!JournalChange {
+        const r = systemd_shim_journal_process(self.journal);
+        if (r < 0) return Error.JournalOpenFailed;
+        return @enumFromInt(r);
+    }
+
+    /// When the current entry was logged, in microseconds since the epoch
+    pub fn realtimeUsec(self: *Journal) ?u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_journal_get_realtime_usec(self.journal, &usec) < 0) return null;
+        return usec;
+    }
+
+    /// When the current entry was logged, in microseconds since its boot
+    /// started; `bootId` says which boot that was
+    pub fn monotonicUsec(self: *Journal) ?u64 {
+        var usec: u64 = 0;
+        if (systemd_shim_journal_get_monotonic_usec(self.journal, &usec, null) < 0) return null;
+        return usec;
+    }
+
+    /// ID of the boot the current entry was logged in, as 32 hex digits
+    pub fn bootId(self: *Journal) ?[32]u8 {
+        var usec: u64 = 0;
+        var text: ?[*:0]u8 = null;
+        if (systemd_shim_journal_get_monotonic_usec(self.journal, &usec, &text) < 0) return null;
+        defer systemd_shim_free_string(text.?);
+        var id: [32]u8 = undefined;
+        @memcpy(&id, text.?[0..32]);
+        return id;
+    }
+
+    /// Seek to a wall-clock time in microseconds since the epoch: `next`
+    /// reads on from the first entry logged at or after it, so
+    /// `seekRealtime(now - 10 * std.time.us_per_min)` starts ten minutes back
+    pub fn seekRealtime(self: *Journal, usec: u64) Error!void {
+        if (systemd_shim_journal_seek_realtime_usec(self.journal, usec) < 0) {
+            return Error.JournalSeekFailed;
+        }
+    }
+
+    /// Seek to `usec` into the boot `boot_id`, or the running boot when null
+    pub fn seekMonotonic(self: *Journal, boot_id: ?[*:0]const u8, usec: u64) Error!void {
+        if (systemd_shim_journal_seek_monotonic_usec(self.journal, boot_id, usec) < 0) {
+            return Error.JournalSeekFailed;
+        }
+    }
+
+    /// Cursor of the current entry, to save and resume from with
+    /// `seekCursor`. Owned by the caller
+    pub fn cursor(self: *Journal, allocator: std.mem.Allocator) Error![]u8 {
+        var text: ?[*:0]u8 = null;
+        if (systemd_shim_journal_get_cursor(self.journal, &text) < 0) {
+            return Error.JournalSeekFailed;
+        }
+        defer systemd_shim_free_string(text.?);
+        return allocator.dupe(u8, std.mem.span(text.?)) catch Error.AllocationFailed;
+    }
+
+    /// Seek to the entry a saved cursor names, or where it was if it has
+    /// been rotated away; `next` then lands on it, so skip it with
+    /// `testCursor` to pick up after it
+    pub fn seekCursor(self: *Journal, saved: [*:0]const u8) Error!void {
+        if (systemd_shim_journal_seek_cursor(self.journal, saved) < 0) {
+            return Error.JournalSeekFailed;
+        }
+    }
+
+    /// Whether the current entry is the one `saved` names
+    pub fn testCursor(self: *Journal, saved: [*:0]const u8) bool {
+        return systemd_shim_journal_test_cursor(self.journal, saved) > 0;
+    }
+
+    /// Start listing each value `field` takes in the journal, say every
+    /// unit for a filter menu, with `nextUnique`; matches do not apply
+    pub fn queryUnique(self: *Journal, field: [*:0]const u8) Error!void {
+        if (systemd_shim_journal_query_unique(self.journal, field) < 0) {
+            return Error.JournalSeekFailed;
+        }
+    }
+
+    /// The next value of the queried field, without the `FIELD=` prefix,
+    /// valid until the next call; null once all have been listed
+    pub fn nextUnique(self: *Journal) ?[]const u8 {
+        var data: ?[*]const u8 = null;
+        var len: usize = 0;
+        if (systemd_shim_journal_enumerate_unique(self.journal, &data, &len) <= 0) return null;
+        const pair = data.?[0..len];
+        const eq = std.mem.indexOfScalar(u8, pair, '=') orelse return pair;
+        return pair[eq + 1 ..];
+    }
+
+    /// List the queried field's values again from the first
+    pub fn restartUnique(self: *Journal) void {
+        systemd_shim_journal_restart_unique(self.journal);
+    }
+
+    /// The message catalog's explanation of the current entry, with its
+    /// fields filled in; null when its MESSAGE_ID has none. Owned by the
+    /// caller
+    pub fn catalog(self: *Journal, allocator: std.mem.Allocator) Error!?[]u8 {
+        var text: ?[*:0]u8 = null;
+        if (systemd_shim_journal_get_catalog(self.journal, &text) < 0) return null;
+        defer systemd_shim_free_string(text.?);
+        return allocator.dupe(u8, std.mem.span(text.?)) catch Error.AllocationFailed;
+    }
+
+    /// The current entry as a JSON object, as `journalctl -o json` writes
+    /// it, all fields with cursor and timestamps. Owned by the caller
+    pub fn entryJson(self: *Journal, allocator: std.mem.Allocator) Error![]u8 {
+        var text: ?[*:0]u8 = null;
+        if (systemd_shim_journal_get_entry_json(self.journal, &text) < 0) {
+            return Error.JournalSeekFailed;
+        }
+        defer systemd_shim_free_string(text.?);
+        return allocator.dupe(u8, std.mem.span(text.?)) catch Error.AllocationFailed;
+    }
+
+    /// Write every entry from the read position on to `fd` in the Journal
+    /// Export Format, as `journalctl -o export` does, for
+    /// systemd-journal-remote or a log shipper; `fd` stays open. Returns
+    /// how many entries were written
+    pub fn exportFd(self: *Journal, fd: c_int) Error!u64 {
+        var count: u64 = 0;
+        if (systemd_shim_journal_export_fd(self.journal, fd, &count) < 0) {
+            return Error.JournalSeekFailed;
+        }
+        return count;
+    }
+
+    /// `exportFd`, handing `writer` one entry per call instead; a negative
+    /// return from it stops the export
+    pub fn exportTo(self: *Journal, writer: ExportWriter, userdata: ?*anyopaque) Error!u64 {
+        var count: u64 = 0;
+        if (systemd_shim_journal_export_callback(self.journal, writer, userdata, &count) < 0) {
+            return Error.JournalSeekFailed;
+        }
+        return count;
+    }
+
     /// Get message from current entry
     pub fn getMessage(self: *Journal) ?[]const u8 {
         var data: ?[*]const u8 = null;
@@ -225,6 +2534,45 @@ pub const Journal = struct {
     }
 };
 
+/// C's `struct iovec`, one `FIELD=value` buffer for `sendJournal`
+const Iovec = extern struct {
+    base: [*]const u8,
+    len: usize,
+};
+
+/// Log one structured entry: each of `fields` is `FIELD=value`, with
+/// `MESSAGE` the text shown and `PRIORITY`, `MESSAGE_ID` or the tool's own
+/// upper-case fields alongside; values may hold newlines or binary data
+pub fn sendJournal(allocator: std.mem.Allocator, fields: []const []const u8) Error!void {
+    const iov = allocator.alloc(Iovec, fields.len) catch return Error.AllocationFailed;
+    defer allocator.free(iov);
+    for (fields, iov) |field, *v| {
+        v.* = .{ .base = field.ptr, .len = field.len };
+    }
+    if (systemd_shim_journal_sendv(iov.ptr, @intCast(iov.len)) < 0) {
+        return Error.JournalSendFailed;
+    }
+}
+
+/// Log `message` at `priority`, 0 (emerg) to 7 (debug); it is not a
+/// format, and `%` or NUL in it are logged as they are
+pub fn printJournal(allocator: std.mem.Allocator, priority: u3, message: []const u8) Error!void {
+    const text = std.mem.concat(allocator, u8, &.{ "MESSAGE=", message }) catch return Error.AllocationFailed;
+    defer allocator.free(text);
+    const level = [_]u8{ 'P', 'R', 'I', 'O', 'R', 'I', 'T', 'Y', '=', '0' + @as(u8, priority) };
+    return sendJournal(allocator, &.{ text, &level });
+}
+
+/// The message catalog's text for `message_id`, with `@FIELD@`
+/// placeholders left in; null when the catalog has none. Owned by the
+/// caller
+pub fn catalogForMessageId(allocator: std.mem.Allocator, message_id: [*:0]const u8) Error!?[]u8 {
+    var text: ?[*:0]u8 = null;
+    if (systemd_shim_journal_get_catalog_for_message_id(message_id, &text) < 0) return null;
+    defer systemd_shim_free_string(text.?);
+    return allocator.dupe(u8, std.mem.span(text.?)) catch Error.AllocationFailed;
+}
+
 /// Escape unit name for D-Bus object path
 fn escapeUnitName(name: []const u8) []const u8 {
     // TODO: Proper escaping (replace - with _2d, etc.)
@@ -245,6 +2593,27 @@ export fn systemd_bus_connect() ?*Bus {
     return ptr;
 }
 
+export fn systemd_bus_connect_user() ?*Bus {
+    const bus = Bus.connectUser() catch return null;
+    const ptr = global_allocator.create(Bus) catch return null;
+    ptr.* = bus;
+    return ptr;
+}
+
+export fn systemd_bus_connect_remote(host: [*:0]const u8) ?*Bus {
+    const bus = Bus.connectRemote(host) catch return null;
+    const ptr = global_allocator.create(Bus) catch return null;
+    ptr.* = bus;
+    return ptr;
+}
+
+export fn systemd_bus_connect_machine(machine: [*:0]const u8) ?*Bus {
+    const bus = Bus.connectMachine(machine) catch return null;
+    const ptr = global_allocator.create(Bus) catch return null;
+    ptr.* = bus;
+    return ptr;
+}
+
 export fn systemd_bus_close(bus: *Bus) void {
     bus.close();
     global_allocator.destroy(bus);